@@ -0,0 +1,5 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::compile_protos("proto/ingest.proto")?;
+    tonic_build::compile_protos("proto/sparkplug_b.proto")?;
+    Ok(())
+}
@@ -0,0 +1,211 @@
+// 管理后台：直接调用后端 REST 接口
+const operatorInput = document.getElementById('operator');
+operatorInput.value = localStorage.getItem('operator') || '';
+operatorInput.addEventListener('change', () => localStorage.setItem('operator', operatorInput.value));
+
+function showMessage(text, isError) {
+    const el = document.getElementById('message');
+    el.textContent = text;
+    el.className = isError ? 'message error' : 'message';
+    clearTimeout(showMessage.timer);
+    showMessage.timer = setTimeout(() => el.className = 'message hidden', 5000);
+}
+
+async function api(method, path, body) {
+    const headers = { 'Content-Type': 'application/json' };
+    if (operatorInput.value) {
+        headers['X-Operator'] = operatorInput.value;
+    }
+    const response = await fetch(path, {
+        method,
+        headers,
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    if (response.status === 204) {
+        return null;
+    }
+    const data = await response.json().catch(() => null);
+    if (!response.ok) {
+        throw new Error((data && data.error) || response.statusText);
+    }
+    if (response.status === 202) {
+        showMessage('变更已提交，等待审批后生效');
+    }
+    return data;
+}
+
+function escape(value) {
+    const div = document.createElement('div');
+    div.textContent = value === null || value === undefined ? '' : String(value);
+    return div.innerHTML;
+}
+
+function render(sectionId, rows, columns, actions) {
+    const tbody = document.querySelector(`#${sectionId} tbody`);
+    tbody.innerHTML = '';
+    for (const row of rows) {
+        const tr = document.createElement('tr');
+        tr.innerHTML = columns.map(col => `<td>${escape(col(row))}</td>`).join('') + '<td></td>';
+        const cell = tr.lastElementChild;
+        for (const [label, className, handler] of actions) {
+            const button = document.createElement('button');
+            button.textContent = label;
+            button.className = className;
+            button.addEventListener('click', () => handler(row).catch(e => showMessage(e.message, true)));
+            cell.appendChild(button);
+        }
+        tbody.appendChild(tr);
+    }
+}
+
+function formatLatest(latest) {
+    if (!latest) {
+        return '';
+    }
+    return Object.entries(latest.values)
+        .map(([metric, v]) => `${metric}: ${v.value}${v.unit}`)
+        .join('  ');
+}
+
+async function loadDevices() {
+    const [devices, latest] = await Promise.all([
+        api('GET', '/devices?per_page=100'),
+        api('GET', '/devices/latest'),
+    ]);
+    const latestById = Object.fromEntries(latest.map(l => [l.device_id, l]));
+    render('devices', devices, [
+        d => d.id,
+        d => d.name,
+        d => d.location,
+        d => d.device_type,
+        d => d.provision_status === 'pending' ? '待审批' : d.status,
+        d => formatLatest(latestById[d.id]),
+    ], [
+        ['删除', 'danger', async d => {
+            if (confirm(`删除设备 ${d.name}？`)) {
+                await api('DELETE', `/devices/${d.id}`);
+                await loadDevices();
+            }
+        }],
+    ]);
+}
+
+async function loadAlarmRules() {
+    const rules = await api('GET', '/alarm-rules?per_page=100');
+    render('alarm-rules', rules, [
+        r => r.id,
+        r => r.name,
+        r => r.parameter,
+        r => r.condition,
+        r => r.value,
+    ], [
+        ['删除', 'danger', async r => {
+            if (confirm(`删除报警规则 ${r.name}？`)) {
+                await api('DELETE', `/alarm-rules/${r.id}`);
+                await loadAlarmRules();
+            }
+        }],
+    ]);
+}
+
+async function loadAlarmLogs() {
+    const logs = await api('GET', '/alarm-logs?per_page=100');
+    render('alarm-logs', logs, [
+        l => l.id,
+        l => l.rule_name,
+        l => l.trigger_value,
+        l => new Date(l.trigger_time).toLocaleString(),
+        l => l.is_processed ? '已处理' : '未处理',
+    ], [
+        ['标记已处理', '', async l => {
+            await api('PUT', `/alarm-logs/${l.id}`, { is_processed: true });
+            await loadAlarmLogs();
+        }],
+    ]);
+}
+
+async function loadAutomationRules() {
+    const rules = await api('GET', '/automation-rules?per_page=100');
+    render('automation-rules', rules, [
+        r => r.id,
+        r => r.action,
+        r => r.level,
+        r => r.trigger_time_range,
+        r => r.sync_alarm ? '是' : '否',
+    ], [
+        ['删除', 'danger', async r => {
+            if (confirm(`删除自动化规则 ${r.action}？`)) {
+                await api('DELETE', `/automation-rules/${r.id}`);
+                await loadAutomationRules();
+            }
+        }],
+    ]);
+}
+
+const loaders = {
+    'devices': loadDevices,
+    'alarm-rules': loadAlarmRules,
+    'alarm-logs': loadAlarmLogs,
+    'automation-rules': loadAutomationRules,
+};
+
+function onSubmit(formId, build, path, reload) {
+    const form = document.getElementById(formId);
+    form.addEventListener('submit', async event => {
+        event.preventDefault();
+        try {
+            await api('POST', path, build(new FormData(form)));
+            form.reset();
+            showMessage('已保存');
+            await reload();
+        } catch (e) {
+            showMessage(e.message, true);
+        }
+    });
+}
+
+onSubmit('device-form', data => {
+    const now = new Date().toISOString();
+    return {
+        name: data.get('name'),
+        location: data.get('location'),
+        status: 0,
+        device_type: data.get('device_type'),
+        manufacturer: data.get('manufacturer'),
+        model: data.get('model'),
+        installation_date: now,
+        last_maintenance: now,
+        operational_hours: 0,
+        temperature: 0,
+        pressure: 0,
+        flow_rate: 0,
+        power_consumption: 0,
+        serial_number: data.get('serial_number') || null,
+    };
+}, '/devices', loadDevices);
+
+onSubmit('alarm-rule-form', data => ({
+    name: data.get('name'),
+    parameter: data.get('parameter'),
+    condition: data.get('condition'),
+    value: parseFloat(data.get('value')),
+}), '/alarm-rules', loadAlarmRules);
+
+onSubmit('automation-rule-form', data => ({
+    action: data.get('action'),
+    level: parseInt(data.get('level'), 10),
+    trigger_time_range: data.get('trigger_time_range'),
+    sync_alarm: data.get('sync_alarm') === 'on',
+}), '/automation-rules', loadAutomationRules);
+
+for (const button of document.querySelectorAll('nav button')) {
+    button.addEventListener('click', () => {
+        document.querySelectorAll('nav button, .tab').forEach(el => el.classList.remove('active'));
+        button.classList.add('active');
+        const tab = button.dataset.tab;
+        document.getElementById(tab).classList.add('active');
+        loaders[tab]().catch(e => showMessage(e.message, true));
+    });
+}
+
+loadDevices().catch(e => showMessage(e.message, true));
@@ -0,0 +1,186 @@
+//! ADC 采样
+//!
+//! 按采样间隔读取各通道原始码值并做滑动平均，每个上报周期把平均值按通道的
+//! 线性或多点标定换算为 pH、ORP、浊度等工程量后写入测量表。
+
+use crate::config::adc::{AdcCalibration, AdcChannelConfig, AdcConfig};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::calibration_curve::KIND_PIECEWISE;
+use crate::services::cache::HotCache;
+use crate::services::calibration::Curve;
+use crate::services::measurement::NewMeasurement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::adc::AdcController;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 固定窗口的滑动平均
+struct MovingAverage {
+    window: usize,
+    samples: VecDeque<f64>,
+}
+
+impl MovingAverage {
+    fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window),
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+}
+
+/// 码值换算
+enum Conversion {
+    Linear { offset: f64, slope: f64 },
+    Curve(Curve),
+}
+
+impl Conversion {
+    fn from_config(calibration: &AdcCalibration) -> Result<Self, AppError> {
+        Ok(match calibration {
+            AdcCalibration::Linear { offset, slope } => Conversion::Linear {
+                offset: *offset,
+                slope: *slope,
+            },
+            AdcCalibration::Curve { points } => {
+                let points = serde_json::to_string(points).map_err(|_| AppError::InternalError)?;
+                Conversion::Curve(Curve::parse(KIND_PIECEWISE, &points)?)
+            }
+        })
+    }
+
+    fn apply(&self, counts: f64) -> f64 {
+        match self {
+            Conversion::Linear { offset, slope } => counts * slope + offset,
+            Conversion::Curve(curve) => curve.apply(counts),
+        }
+    }
+}
+
+struct Channel {
+    config: AdcChannelConfig,
+    adc: AdcController,
+    conversion: Conversion,
+    filter: MovingAverage,
+}
+
+async fn store(channel: &Channel, db: &DbManager, cache: &HotCache, read_only: &ReadOnlyMode) {
+    let Some(counts) = channel.filter.average() else {
+        return;
+    };
+    let config = &channel.config;
+    let measurement = NewMeasurement {
+        metric_type: config.metric_type.clone(),
+        timestamp: Utc::now(),
+        value: channel.conversion.apply(counts),
+        device_id: Some(config.device_id),
+        unit: None,
+    };
+    match read_only.ingest(db.get_connection(), cache, measurement).await {
+        Ok(_) => {}
+        Err(AppError::InvalidInput(msg)) => warn!("Rejected ADC value from {}: {}", config.name, msg),
+        Err(e) => error!("Failed to store ADC value from {}: {:?}", config.name, e),
+    }
+}
+
+async fn run(config: AdcConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    let mut channels = Vec::new();
+    for channel in &config.channels {
+        let conversion = match Conversion::from_config(&channel.calibration) {
+            Ok(conversion) => conversion,
+            Err(e) => {
+                error!("Invalid calibration for ADC channel {}: {:?}", channel.name, e);
+                continue;
+            }
+        };
+        match AdcController::open(&channel.source).await {
+            Ok(adc) => channels.push(Channel {
+                config: channel.clone(),
+                adc,
+                conversion,
+                filter: MovingAverage::new(channel.window),
+            }),
+            Err(e) => error!("Failed to open ADC for channel {}: {}", channel.name, e),
+        }
+    }
+    if channels.is_empty() {
+        warn!("ADC sampling enabled but no channels available");
+        return;
+    }
+    info!("Sampling {} ADC channel(s)", channels.len());
+
+    let mut ticker = tokio::time::interval(Duration::from_millis(config.sample_interval_ms.max(10)));
+    let report_interval = Duration::from_secs(config.report_interval_secs.max(1));
+    let mut last_report = Instant::now();
+
+    loop {
+        ticker.tick().await;
+        // 同一芯片的通道共用多路选择器，依次读取
+        for channel in channels.iter_mut() {
+            match channel.adc.read_counts(channel.config.channel).await {
+                Ok(counts) => channel.filter.push(counts),
+                Err(e) => warn!("Failed to sample ADC channel {}: {}", channel.config.name, e),
+            }
+        }
+
+        if last_report.elapsed() >= report_interval {
+            last_report = Instant::now();
+            for channel in &channels {
+                store(channel, &db, &cache, &read_only).await;
+            }
+        }
+    }
+}
+
+/// 启动 ADC 采样
+pub fn start(config: AdcConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    tokio::spawn(run(config, db, cache, read_only));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average() {
+        let mut filter = MovingAverage::new(3);
+        assert_eq!(filter.average(), None);
+        for value in [1.0, 2.0, 3.0, 10.0] {
+            filter.push(value);
+        }
+        assert_eq!(filter.average(), Some(5.0));
+    }
+
+    #[test]
+    fn test_conversion() {
+        let linear = Conversion::from_config(&AdcCalibration::Linear { offset: -2.0, slope: 0.5 }).unwrap();
+        assert_eq!(linear.apply(10.0), 3.0);
+
+        // pH 两点标定：码值 8000 → pH 4，16000 → pH 7
+        let curve = Conversion::from_config(&AdcCalibration::Curve {
+            points: vec![[16000.0, 7.0], [8000.0, 4.0]],
+        })
+        .unwrap();
+        assert_eq!(curve.apply(12000.0), 5.5);
+
+        assert!(Conversion::from_config(&AdcCalibration::Curve { points: vec![[1.0, 1.0]] }).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! CAN 总线采集
+//!
+//! 监听 CAN 接口上配置的帧，把其中的信号解码后写入对应设备的指标，用于接入 CAN 总线流量计等仪表。
+
+use crate::config::can::{CanConfig, CanSignalMapping};
+use crate::database::sea_orm_db::DbManager;
+use crate::services::cache::HotCache;
+use crate::services::measurement::NewMeasurement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::can::CanController;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 扩展帧ID的完整掩码
+const FULL_MASK: u32 = 0x1FFF_FFFF;
+
+/// 帧解码为测量值
+fn decode_frame(mappings: &[CanSignalMapping], data: &[u8]) -> Vec<NewMeasurement> {
+    let timestamp = Utc::now();
+    mappings
+        .iter()
+        .filter_map(|mapping| {
+            let value = mapping.signal.decode(data)?;
+            Some(NewMeasurement {
+                metric_type: mapping.metric_type.clone(),
+                timestamp,
+                value,
+                device_id: Some(mapping.device_id),
+                unit: None,
+            })
+        })
+        .collect()
+}
+
+async fn receive(
+    config: &CanConfig,
+    signals: &HashMap<u32, Vec<CanSignalMapping>>,
+    db: &DbManager,
+    cache: &HotCache,
+    read_only: &ReadOnlyMode,
+) -> std::io::Result<()> {
+    let can = CanController::open(&config.interface)?;
+    let ids: BTreeSet<u32> = signals.keys().copied().collect();
+    let filters: Vec<(u32, u32)> = ids.iter().map(|&id| (id, FULL_MASK)).collect();
+    can.set_filters(&filters)?;
+    info!("Listening for {} CAN ids on {}", ids.len(), can.interface());
+
+    loop {
+        let (id, data) = can.recv().await?;
+        let Some(mappings) = signals.get(&id) else {
+            continue;
+        };
+
+        for measurement in decode_frame(mappings, &data) {
+            let metric_type = measurement.metric_type.clone();
+            match read_only.ingest(db.get_connection(), cache, measurement).await {
+                Ok(_) => {}
+                Err(AppError::InvalidInput(msg)) => {
+                    warn!("Rejected CAN value {} from frame {:#x}: {}", metric_type, id, msg)
+                }
+                Err(e) => error!("Failed to store CAN value from frame {:#x}: {:?}", id, e),
+            }
+        }
+    }
+}
+
+/// 启动 CAN 采集，接口断开后按间隔重试
+pub fn start(config: CanConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    let mut signals: HashMap<u32, Vec<CanSignalMapping>> = HashMap::new();
+    for mapping in &config.signals {
+        signals.entry(mapping.signal.can_id).or_default().push(mapping.clone());
+    }
+    if signals.is_empty() {
+        warn!("CAN acquisition enabled but no signals configured");
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = receive(&config, &signals, &db, &cache, &read_only).await {
+                error!("CAN interface {} error: {}", config.interface, e);
+                if e.kind() == std::io::ErrorKind::Unsupported {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(config.retry_secs.max(1))).await;
+        }
+    });
+}
@@ -0,0 +1,257 @@
+//! 演示模式的模拟传感器
+//!
+//! 启动时写入演示厂站和 pH 计、流量计、浊度仪三台设备（按厂站编号、设备序列号判断，已存在时不重复写入），
+//! 再为每台设备启动一个模拟传感器：数值围绕正常值随机游走，偶尔进入一段超标波动后逐渐回落。
+//! 模拟值与现场采集一样经 [`ReadOnlyMode::ingest`] 写入，压缩、标定、质量检查和只读暂存都照常生效，
+//! 前端开发和售前演示不需要现场硬件，MQTT、RabbitMQ 保持未启用即可。
+
+use crate::config::demo::DemoConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{
+    ActiveModel as DeviceActiveModel, Entity as DeviceEntity, PROVISION_ACTIVE,
+};
+use crate::models::site::{
+    ActiveModel as SiteActiveModel, Column as SiteColumn, Entity as SiteEntity, Model as Site,
+};
+use crate::services::cache::HotCache;
+use crate::services::measurement::NewMeasurement;
+use crate::services::metric_registry::{FLOW, PH, TURBIDITY};
+use crate::services::provisioning;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 开启演示模式的启动参数
+pub const DEMO_FLAG: &str = "--demo";
+
+const SITE_CODE: &str = "DEMO";
+/// 一段超标波动持续的上报次数
+const EXCURSION_SAMPLES: u32 = 12;
+/// 每次上报向目标值回归的比例
+const REVERSION: f64 = 0.2;
+
+/// 模拟传感器的取值特征
+#[derive(Debug, Clone, Copy)]
+struct Profile {
+    metric_type: &'static str,
+    /// 正常值
+    normal: f64,
+    /// 每次上报的最大随机变化量
+    step: f64,
+    /// 超标波动时趋向的值
+    excursion: f64,
+}
+
+struct DemoDevice {
+    serial_number: &'static str,
+    name: &'static str,
+    device_type: &'static str,
+    profile: Profile,
+}
+
+const DEVICES: [DemoDevice; 3] = [
+    DemoDevice {
+        serial_number: "DEMO-PH-01",
+        name: "演示 pH 计",
+        device_type: "pH 计",
+        profile: Profile { metric_type: PH, normal: 7.2, step: 0.05, excursion: 9.6 },
+    },
+    DemoDevice {
+        serial_number: "DEMO-FLOW-01",
+        name: "演示出水流量计",
+        device_type: "流量计",
+        profile: Profile { metric_type: FLOW, normal: 150.0, step: 3.0, excursion: 260.0 },
+    },
+    DemoDevice {
+        serial_number: "DEMO-TURB-01",
+        name: "演示浊度仪",
+        device_type: "浊度仪",
+        profile: Profile { metric_type: TURBIDITY, normal: 5.0, step: 0.3, excursion: 45.0 },
+    },
+];
+
+/// 单台设备的模拟状态
+struct Simulator {
+    profile: Profile,
+    value: f64,
+    /// 超标波动剩余的上报次数
+    excursion_left: u32,
+}
+
+impl Simulator {
+    fn new(profile: Profile) -> Self {
+        Self { profile, value: profile.normal, excursion_left: 0 }
+    }
+
+    /// 下一个模拟值：随机游走并向目标值回归，超标波动结束后回归正常值
+    fn next<R: Rng>(&mut self, rng: &mut R, excursion_percent: f64) -> f64 {
+        let profile = self.profile;
+        if self.excursion_left == 0 && rng.gen_range(0.0..100.0) < excursion_percent {
+            self.excursion_left = EXCURSION_SAMPLES;
+        }
+        let target = if self.excursion_left > 0 {
+            self.excursion_left -= 1;
+            profile.excursion
+        } else {
+            profile.normal
+        };
+
+        let noise = rng.gen_range(-profile.step..=profile.step);
+        self.value = (self.value + (target - self.value) * REVERSION + noise).max(0.0);
+        self.value
+    }
+}
+
+async fn seed_site(conn: &DatabaseConnection) -> Result<Site, AppError> {
+    let existing = SiteEntity::find()
+        .filter(SiteColumn::Code.eq(SITE_CODE))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if let Some(site) = existing {
+        return Ok(site);
+    }
+
+    let now = Utc::now();
+    SiteEntity::insert(SiteActiveModel {
+        name: Set("演示厂站".to_string()),
+        code: Set(SITE_CODE.to_string()),
+        address: Set(String::new()),
+        description: Set("演示模式自动创建".to_string()),
+        timezone: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec_with_returning(conn)
+    .await
+    .map_err(|_| AppError::InternalError)
+}
+
+/// 写入演示厂站和设备，返回各设备 ID 及其取值特征
+async fn seed(conn: &DatabaseConnection) -> Result<Vec<(i32, Profile)>, AppError> {
+    let site = seed_site(conn).await?;
+
+    let mut devices = Vec::with_capacity(DEVICES.len());
+    for demo in &DEVICES {
+        if let Some(device) = provisioning::find_by_serial(conn, demo.serial_number).await? {
+            devices.push((device.id, demo.profile));
+            continue;
+        }
+
+        let now = Utc::now();
+        let device = DeviceEntity::insert(DeviceActiveModel {
+            name: Set(demo.name.to_string()),
+            location: Set(site.name.clone()),
+            status: Set(1),
+            device_type: Set(demo.device_type.to_string()),
+            manufacturer: Set("guolu".to_string()),
+            model: Set("simulator".to_string()),
+            installation_date: Set(now),
+            last_maintenance: Set(now),
+            operational_hours: Set(0.0),
+            temperature: Set(0.0),
+            pressure: Set(0.0),
+            flow_rate: Set(0.0),
+            power_consumption: Set(0.0),
+            serial_number: Set(Some(demo.serial_number.to_string())),
+            provision_status: Set(PROVISION_ACTIVE.to_string()),
+            site_id: Set(Some(site.id)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+        info!("Seeded demo device {} ({})", device.id, demo.serial_number);
+        devices.push((device.id, demo.profile));
+    }
+    Ok(devices)
+}
+
+async fn simulate(
+    config: DemoConfig,
+    device_id: i32,
+    profile: Profile,
+    db: DbManager,
+    cache: HotCache,
+    read_only: ReadOnlyMode,
+) {
+    let mut simulator = Simulator::new(profile);
+    let mut rng = StdRng::from_entropy();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+
+    loop {
+        ticker.tick().await;
+        let measurement = NewMeasurement {
+            metric_type: profile.metric_type.to_string(),
+            timestamp: Utc::now(),
+            value: simulator.next(&mut rng, config.excursion_percent),
+            device_id: Some(device_id),
+            unit: None,
+        };
+        match read_only.ingest(db.get_connection(), &cache, measurement).await {
+            Ok(_) => {}
+            Err(AppError::InvalidInput(msg)) => {
+                warn!("Rejected simulated {} for device {}: {}", profile.metric_type, device_id, msg)
+            }
+            Err(e) => error!("Failed to store simulated value for device {}: {:?}", device_id, e),
+        }
+    }
+}
+
+async fn run(config: DemoConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    let devices = match seed(db.get_connection()).await {
+        Ok(devices) => devices,
+        Err(e) => {
+            error!("Failed to seed demo data: {:?}", e);
+            return;
+        }
+    };
+
+    info!("Demo mode: simulating {} sensors", devices.len());
+    for (device_id, profile) in devices {
+        tokio::spawn(simulate(
+            config.clone(),
+            device_id,
+            profile,
+            db.clone(),
+            cache.clone(),
+            read_only.clone(),
+        ));
+    }
+}
+
+pub fn start(config: DemoConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    tokio::spawn(run(config, db, cache, read_only));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_walk_and_excursion() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let profile = DEVICES[0].profile;
+
+        let mut steady = Simulator::new(profile);
+        for _ in 0..1000 {
+            let value = steady.next(&mut rng, 0.0);
+            assert!((value - profile.normal).abs() < profile.step * 10.0);
+        }
+
+        let mut excursion = Simulator::new(profile);
+        let peak = (0..EXCURSION_SAMPLES).map(|_| excursion.next(&mut rng, 100.0)).last().unwrap();
+        assert!(peak > 9.0);
+        // 波动结束后回落到正常值附近
+        let settled = (0..50).map(|_| excursion.next(&mut rng, 0.0)).last().unwrap();
+        assert!((settled - profile.normal).abs() < profile.step * 10.0);
+    }
+}
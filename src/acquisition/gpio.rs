@@ -0,0 +1,61 @@
+//! 开关量输入
+//!
+//! 监听浮球开关、门磁等 GPIO 输入的边沿事件，触发时写入报警。
+
+use crate::config::gpio::{GpioConfig, GpioInput};
+use crate::database::sea_orm_db::DbManager;
+use crate::services::alarm;
+use crate::utils::gpio::GpioController;
+use futures_util::StreamExt;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 重新打开引脚前的等待时间
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+async fn watch(config: &GpioConfig, input: &GpioInput, db: &DbManager) -> std::io::Result<()> {
+    let gpio = GpioController::open(&config.chip, input.line, config.sysfs_base).await?;
+    let mut events = gpio.events(input.edge).await?;
+    info!("Watching GPIO input {} on line {}", input.name, input.line);
+
+    let debounce = Duration::from_millis(input.debounce_ms);
+    let mut last: Option<Instant> = None;
+    while let Some(event) = events.next().await {
+        let event = event?;
+        let now = Instant::now();
+        if last.is_some_and(|last| now.duration_since(last) < debounce) {
+            continue;
+        }
+        last = Some(now);
+
+        let edge = if event.rising { "上升沿" } else { "下降沿" };
+        let rule_name = format!("开关量动作: {} ({})", input.name, edge);
+        let value = if event.rising { 1.0 } else { 0.0 };
+        let conn = db.get_connection();
+        if let Err(e) = alarm::raise(conn, input.device_id, rule_name, value, &input.severity).await {
+            error!("Failed to raise GPIO alarm for {}: {:?}", input.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 为每个输入启动监听任务
+pub fn start(config: GpioConfig, db: DbManager) {
+    if config.inputs.is_empty() {
+        warn!("GPIO inputs enabled but none configured");
+        return;
+    }
+
+    for input in config.inputs.clone() {
+        let config = config.clone();
+        let db = db.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = watch(&config, &input, &db).await {
+                    error!("GPIO input {} error: {}", input.name, e);
+                }
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,10 @@
+//! 数据采集
+//!
+//! 主动从现场设备读取数据的采集模块，读到的值与 MQTT/HTTP 上报一样写入测量表。
+
+pub mod adc;
+pub mod can;
+pub mod demo;
+pub mod gpio;
+pub mod opcua;
+pub mod snmp;
\ No newline at end of file
@@ -0,0 +1,197 @@
+//! OPC UA 采集
+//!
+//! 连接 PLC 的 OPC UA 服务，订阅配置中的节点，值变化时按映射写入对应设备的指标。
+//! opcua 客户端是阻塞实现，会话运行在独立线程中，收到的数据经通道交给异步任务入库。
+
+use crate::config::opcua::{OpcUaConfig, OpcUaNodeMapping};
+use crate::database::sea_orm_db::DbManager;
+use crate::services::cache::HotCache;
+use crate::services::measurement::NewMeasurement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use opcua::client::prelude::*;
+use opcua::sync::RwLock;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// 采集通道容量，入库跟不上时丢弃新值
+const CHANNEL_CAPACITY: usize = 10000;
+
+/// 数值型 Variant 转为 f64
+fn variant_to_f64(value: &Variant) -> Option<f64> {
+    match value {
+        Variant::Boolean(v) => Some(if *v { 1.0 } else { 0.0 }),
+        Variant::SByte(v) => Some(*v as f64),
+        Variant::Byte(v) => Some(*v as f64),
+        Variant::Int16(v) => Some(*v as f64),
+        Variant::UInt16(v) => Some(*v as f64),
+        Variant::Int32(v) => Some(*v as f64),
+        Variant::UInt32(v) => Some(*v as f64),
+        Variant::Int64(v) => Some(*v as f64),
+        Variant::UInt64(v) => Some(*v as f64),
+        Variant::Float(v) => Some(*v as f64),
+        Variant::Double(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// 解析节点映射，跳过无效的节点ID
+fn parse_nodes(nodes: &[OpcUaNodeMapping]) -> HashMap<NodeId, OpcUaNodeMapping> {
+    nodes
+        .iter()
+        .filter_map(|mapping| match NodeId::from_str(&mapping.node_id) {
+            Ok(node_id) => Some((node_id, mapping.clone())),
+            Err(_) => {
+                warn!("Ignoring invalid OPC UA node id: {}", mapping.node_id);
+                None
+            }
+        })
+        .collect()
+}
+
+fn connect(config: &OpcUaConfig) -> Result<Arc<RwLock<Session>>, StatusCode> {
+    let mut client = ClientBuilder::new()
+        .application_name("guolu")
+        .application_uri("urn:guolu")
+        .trust_server_certs(true)
+        .session_retry_limit(0)
+        .client()
+        .ok_or(StatusCode::BadConfigurationError)?;
+
+    let identity = match (&config.username, &config.password) {
+        (Some(username), Some(password)) => IdentityToken::UserName(username.clone(), password.clone()),
+        _ => IdentityToken::Anonymous,
+    };
+    let endpoint: EndpointDescription = (
+        config.endpoint_url.as_str(),
+        SecurityPolicy::None.to_str(),
+        MessageSecurityMode::None,
+        UserTokenPolicy::anonymous(),
+    )
+        .into();
+
+    client.connect_to_endpoint(endpoint, identity)
+}
+
+/// 建立订阅并阻塞运行会话，会话断开后返回
+fn run_session(
+    config: &OpcUaConfig,
+    nodes: &HashMap<NodeId, OpcUaNodeMapping>,
+    tx: &mpsc::Sender<NewMeasurement>,
+) -> Result<(), StatusCode> {
+    let session = connect(config)?;
+
+    {
+        let session = session.read();
+        let mappings = nodes.clone();
+        let tx = tx.clone();
+        let subscription_id = session.create_subscription(
+            config.publishing_interval_ms as f64,
+            10,
+            30,
+            0,
+            0,
+            true,
+            DataChangeCallback::new(move |items| {
+                for item in items {
+                    let node_id = &item.item_to_monitor().node_id;
+                    let Some(mapping) = mappings.get(node_id) else {
+                        continue;
+                    };
+                    let data_value = item.last_value();
+                    let Some(value) = data_value.value.as_ref().and_then(variant_to_f64) else {
+                        warn!("OPC UA node {} returned a non-numeric value", node_id);
+                        continue;
+                    };
+                    let timestamp = data_value
+                        .source_timestamp
+                        .map(|t| t.as_chrono())
+                        .unwrap_or_else(Utc::now);
+
+                    let measurement = NewMeasurement {
+                        metric_type: mapping.metric_type.clone(),
+                        timestamp,
+                        value: value * mapping.scale,
+                        device_id: Some(mapping.device_id),
+                        unit: None,
+                    };
+                    if tx.try_send(measurement).is_err() {
+                        warn!("OPC UA ingest channel full, dropping value of {}", node_id);
+                    }
+                }
+            }),
+        )?;
+
+        let items: Vec<MonitoredItemCreateRequest> =
+            nodes.keys().map(|node_id| node_id.clone().into()).collect();
+        session.create_monitored_items(subscription_id, TimestampsToReturn::Both, &items)?;
+        info!(
+            "OPC UA subscribed to {} nodes on {}",
+            items.len(),
+            config.endpoint_url
+        );
+    }
+
+    // 阻塞直到会话断开
+    Session::run(session);
+    Ok(())
+}
+
+async fn store(
+    mut rx: mpsc::Receiver<NewMeasurement>,
+    db: DbManager,
+    cache: HotCache,
+    read_only: ReadOnlyMode,
+) {
+    while let Some(measurement) = rx.recv().await {
+        let device_id = measurement.device_id;
+        match read_only.ingest(db.get_connection(), &cache, measurement).await {
+            Ok(_) => {}
+            Err(AppError::InvalidInput(msg)) => {
+                warn!("Rejected OPC UA value for device {:?}: {}", device_id, msg)
+            }
+            Err(e) => error!("Failed to store OPC UA value: {:?}", e),
+        }
+    }
+}
+
+/// 启动 OPC UA 采集
+pub fn start(config: OpcUaConfig, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    let nodes = parse_nodes(&config.nodes);
+    if config.endpoint_url.is_empty() || nodes.is_empty() {
+        warn!("OPC UA acquisition enabled but no endpoint or nodes configured");
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::spawn(store(rx, db, cache, read_only));
+
+    std::thread::spawn(move || loop {
+        match run_session(&config, &nodes, &tx) {
+            Ok(()) => warn!("OPC UA session to {} ended", config.endpoint_url),
+            Err(status) => error!(
+                "OPC UA connection to {} failed: {}",
+                config.endpoint_url, status
+            ),
+        }
+        std::thread::sleep(Duration::from_secs(config.reconnect_interval_secs.max(1)));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variant_to_f64() {
+        assert_eq!(variant_to_f64(&Variant::Double(1.5)), Some(1.5));
+        assert_eq!(variant_to_f64(&Variant::UInt16(42)), Some(42.0));
+        assert_eq!(variant_to_f64(&Variant::Boolean(true)), Some(1.0));
+        assert_eq!(variant_to_f64(&Variant::from("text")), None);
+    }
+}
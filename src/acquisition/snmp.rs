@@ -0,0 +1,198 @@
+//! SNMP 轮询
+//!
+//! 按配置定期 GET 泵站 RTU、UPS 等设备的 OID，按映射写入测量表；
+//! 连续多次无响应时写入不可达报警，恢复后再次报警前需重新达到失败次数。
+
+use crate::config::snmp::{SnmpOidMapping, SnmpTarget, SnmpV3Auth};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::SEVERITY_MAJOR;
+use crate::services::alarm;
+use crate::services::cache::HotCache;
+use crate::services::measurement::NewMeasurement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use snmp2::v3::{Auth, AuthProtocol, Cipher, Security};
+use snmp2::{AsyncSession, Oid, Value};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 解析点分形式的 OID
+fn parse_oid(oid: &str) -> Option<Vec<u64>> {
+    let oid = oid.trim().trim_start_matches('.');
+    if oid.is_empty() {
+        return None;
+    }
+    oid.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// 数值型 SNMP 值转为 f64
+fn value_to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(v) => Some(*v as f64),
+        Value::Counter32(v) | Value::Unsigned32(v) | Value::Timeticks(v) => Some(*v as f64),
+        Value::Counter64(v) => Some(*v as f64),
+        Value::OctetString(bytes) => std::str::from_utf8(bytes).ok()?.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn v3_security(auth: &SnmpV3Auth) -> Result<Security, String> {
+    let protocol = match auth.auth_protocol.to_ascii_lowercase().as_str() {
+        "md5" => AuthProtocol::Md5,
+        "sha1" | "sha" => AuthProtocol::Sha1,
+        "sha256" => AuthProtocol::Sha256,
+        other => return Err(format!("unsupported auth protocol: {}", other)),
+    };
+    let security = Security::new(auth.username.as_bytes(), auth.auth_password.as_bytes())
+        .with_auth_protocol(protocol);
+
+    match (&auth.privacy_protocol, &auth.privacy_password) {
+        (Some(cipher), Some(password)) => {
+            let cipher = match cipher.to_ascii_lowercase().as_str() {
+                "des" => Cipher::Des,
+                "aes" | "aes128" => Cipher::Aes128,
+                other => return Err(format!("unsupported privacy protocol: {}", other)),
+            };
+            Ok(security.with_auth(Auth::AuthPriv {
+                cipher,
+                privacy_password: password.as_bytes().to_vec(),
+            }))
+        }
+        _ => Ok(security.with_auth(Auth::AuthNoPriv)),
+    }
+}
+
+async fn open_session(target: &SnmpTarget) -> Result<AsyncSession, String> {
+    match target.version.as_str() {
+        "v2c" | "2c" => AsyncSession::new_v2c(&target.address, target.community.as_bytes(), 0)
+            .await
+            .map_err(|e| e.to_string()),
+        "v3" | "3" => {
+            let auth = target.v3.as_ref().ok_or("missing v3 credentials")?;
+            let mut session = AsyncSession::new_v3(&target.address, 0, v3_security(auth)?)
+                .await
+                .map_err(|e| e.to_string())?;
+            session.init().await.map_err(|e| format!("{:?}", e))?;
+            Ok(session)
+        }
+        other => Err(format!("unsupported SNMP version: {}", other)),
+    }
+}
+
+/// 读取一个 OID 的数值
+async fn get_value(
+    session: &mut AsyncSession,
+    mapping: &SnmpOidMapping,
+    timeout: Duration,
+) -> Result<f64, String> {
+    let parts = parse_oid(&mapping.oid).ok_or_else(|| format!("invalid OID {}", mapping.oid))?;
+    let oid = Oid::from(&parts).map_err(|_| format!("invalid OID {}", mapping.oid))?;
+
+    let mut pdu = tokio::time::timeout(timeout, session.get(&oid))
+        .await
+        .map_err(|_| "timeout".to_string())?
+        .map_err(|e| format!("{:?}", e))?;
+    let (_, value) = pdu.varbinds.next().ok_or("empty response")?;
+    value_to_f64(&value).ok_or_else(|| format!("non-numeric value for {}", mapping.oid))
+}
+
+/// 轮询单台设备，返回是否有响应
+async fn poll_once(
+    target: &SnmpTarget,
+    db: &DbManager,
+    cache: &HotCache,
+    read_only: &ReadOnlyMode,
+) -> bool {
+    let timeout = Duration::from_millis(target.timeout_ms);
+    let mut session = match tokio::time::timeout(timeout, open_session(target)).await {
+        Ok(Ok(session)) => session,
+        Ok(Err(e)) => {
+            warn!("SNMP session to {} failed: {}", target.name, e);
+            return false;
+        }
+        Err(_) => return false,
+    };
+
+    let mut reachable = false;
+    for mapping in &target.oids {
+        let value = match get_value(&mut session, mapping, timeout).await {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("SNMP get {} on {} failed: {}", mapping.oid, target.name, e);
+                continue;
+            }
+        };
+        reachable = true;
+
+        let measurement = NewMeasurement {
+            metric_type: mapping.metric_type.clone(),
+            timestamp: Utc::now(),
+            value: value * mapping.scale,
+            device_id: Some(target.device_id),
+            unit: None,
+        };
+        match read_only.ingest(db.get_connection(), cache, measurement).await {
+            Ok(_) => {}
+            Err(AppError::InvalidInput(msg)) => {
+                warn!("Rejected SNMP value {} from {}: {}", mapping.oid, target.name, msg)
+            }
+            Err(e) => error!("Failed to store SNMP value from {}: {:?}", target.name, e),
+        }
+    }
+    reachable
+}
+
+async fn poll_target(target: SnmpTarget, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(target.interval_secs.max(1)));
+    let mut failures = 0u32;
+
+    loop {
+        ticker.tick().await;
+
+        if poll_once(&target, &db, &cache, &read_only).await {
+            if failures >= target.unreachable_after {
+                info!("SNMP target {} reachable again", target.name);
+            }
+            failures = 0;
+            continue;
+        }
+
+        failures += 1;
+        if failures == target.unreachable_after {
+            let rule_name = format!("SNMP 设备不可达: {} ({})", target.name, target.address);
+            let (conn, value) = (db.get_connection(), failures as f64);
+            if let Err(e) = alarm::raise(conn, Some(target.device_id), rule_name, value, SEVERITY_MAJOR).await {
+                error!("Failed to raise SNMP reachability alarm: {:?}", e);
+            }
+        }
+    }
+}
+
+/// 为每台设备启动轮询任务
+pub fn start(targets: Vec<SnmpTarget>, db: DbManager, cache: HotCache, read_only: ReadOnlyMode) {
+    for target in targets {
+        tokio::spawn(poll_target(target, db.clone(), cache.clone(), read_only.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_oid() {
+        assert_eq!(parse_oid("1.3.6.1.2.1.1.3.0"), Some(vec![1, 3, 6, 1, 2, 1, 1, 3, 0]));
+        assert_eq!(parse_oid(".1.3.6"), Some(vec![1, 3, 6]));
+        assert_eq!(parse_oid("1.3.x"), None);
+        assert_eq!(parse_oid(""), None);
+    }
+
+    #[test]
+    fn test_value_to_f64() {
+        assert_eq!(value_to_f64(&Value::Integer(-5)), Some(-5.0));
+        assert_eq!(value_to_f64(&Value::Counter64(10)), Some(10.0));
+        assert_eq!(value_to_f64(&Value::OctetString(b" 12.5 ")), Some(12.5));
+        assert_eq!(value_to_f64(&Value::Null), None);
+    }
+}
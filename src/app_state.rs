@@ -1,9 +1,43 @@
 use std::sync::{Arc, RwLock};
+use crate::config::settings::Settings;
+use crate::control::ControlRuntime;
 use crate::models::user::Model as User;
 use crate::database::sea_orm_db::DbManager;
+use crate::message_queue::events::EventBus;
+use crate::message_queue::rabbitmq::RabbitMQManager;
+use crate::mqtt::rumqtt::MqttManager;
+use crate::services::api_usage::UsageRecorder;
+use crate::services::cache::HotCache;
+use crate::services::device_command::CommandTracker;
+use crate::services::gpio_output::GpioOutputs;
+use crate::services::network::NetworkMonitor;
+use crate::services::pump_group::PumpGroupRuntime;
+use crate::services::pwm::PwmManager;
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::remote_access::RemoteAccessManager;
+use crate::services::safe_state::SafeStateMonitor;
+use crate::services::serial_console::SerialConsoleManager;
+use crate::services::timeseries::TimeSeriesStore;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub users: Arc<RwLock<Vec<User>>>,
     pub db: DbManager,
+    pub cache: HotCache,
+    pub timeseries: Arc<dyn TimeSeriesStore>,
+    pub mqtt: Option<MqttManager>,
+    pub rabbitmq: Option<RabbitMQManager>,
+    pub events: EventBus,
+    pub commands: CommandTracker,
+    pub remote_access: RemoteAccessManager,
+    pub serial_console: SerialConsoleManager,
+    pub pwm: PwmManager,
+    pub gpio_outputs: GpioOutputs,
+    pub safe_state: SafeStateMonitor,
+    pub control: ControlRuntime,
+    pub pump_groups: PumpGroupRuntime,
+    pub network: NetworkMonitor,
+    pub read_only: ReadOnlyMode,
+    pub api_usage: UsageRecorder,
+    pub settings: Arc<Settings>,
 }
\ No newline at end of file
@@ -0,0 +1,208 @@
+//! 管理命令
+//!
+//! `guolu <命令> [参数]` 直接操作数据库后退出，不启动服务，首次部署和运维时不必再手工修改数据库：
+//!
+//! - `create-admin <名称>`：签发拥有全部权限的平台管理 API Key，明文只打印一次
+//! - `migrate`：按启动流程执行迁移预检和建表迁移
+//! - `import-devices <文件> [--dry-run]`：导入设备，格式同 `/devices/import`，`.csv` 文件按 CSV 解析，
+//!   其余按 JSON 数组解析
+//! - `rotate-key <ID>`：轮换 API Key，旧明文立即失效
+//! - `replay-backlog`：补写只读期间暂存的上报数据（MQTT、采集任务等），暂存文件被服务占用，须先停止服务
+//!
+//! 每个命令执行前都先完成迁移，迁移含破坏性变更时须加 `--allow-destructive`。
+
+use crate::config::settings::Settings;
+use crate::database::sea_orm_db::DbManager;
+use crate::database::{migration, preflight};
+use crate::handlers::device::{self as device_handler, TransferFormat};
+use crate::models::api_key::Entity as ApiKeyEntity;
+use crate::services::api_key::{self as api_key_service, ALL_SCOPES};
+use crate::services::cache::HotCache;
+use crate::services::compression::Compressor;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use anyhow::{anyhow, bail, Context};
+use sea_orm::EntityTrait;
+use std::path::Path;
+
+/// 命令及用法
+const COMMANDS: [(&str, &str); 5] = [
+    ("create-admin", "create-admin <名称>"),
+    ("migrate", "migrate"),
+    ("import-devices", "import-devices <文件> [--dry-run]"),
+    ("rotate-key", "rotate-key <ID>"),
+    ("replay-backlog", "replay-backlog"),
+];
+
+/// 记入配置变更历史的操作人
+const OPERATOR: &str = "cli";
+const DRY_RUN_FLAG: &str = "--dry-run";
+
+pub fn is_command(name: &str) -> bool {
+    COMMANDS.iter().any(|(command, _)| *command == name)
+}
+
+/// 第 `index` 个非 `--` 开头的参数
+fn positional<'a>(command: &str, args: &'a [String], index: usize) -> anyhow::Result<&'a str> {
+    args.iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .nth(index)
+        .map(String::as_str)
+        .ok_or_else(|| {
+            let usage = COMMANDS.iter().find(|(name, _)| *name == command).map_or("", |c| c.1);
+            anyhow!("用法: guolu {}", usage)
+        })
+}
+
+fn describe(e: AppError) -> anyhow::Error {
+    match e {
+        AppError::InvalidInput(msg)
+        | AppError::ServiceUnavailable(msg)
+        | AppError::Unprocessable(msg) => anyhow!(msg.into_owned()),
+        AppError::NotFound => anyhow!("未找到"),
+        other => anyhow!("{:?}", other),
+    }
+}
+
+/// 连接数据库并完成迁移，与服务启动时一致
+async fn connect(settings: &Settings, args: &[String]) -> anyhow::Result<DbManager> {
+    let db = DbManager::from_config(&settings.database).await?;
+    if settings.migration.preflight {
+        let allow_destructive = settings.migration.allow_destructive
+            || args.iter().any(|a| a == preflight::ALLOW_DESTRUCTIVE_FLAG);
+        let (database, config) = (&settings.database, &settings.migration);
+        preflight::run(db.get_connection(), database, config, allow_destructive).await?;
+    }
+    migration::run_migrations(db.get_connection()).await?;
+    Ok(db)
+}
+
+/// 执行管理命令，`args` 为命令之后的参数
+pub async fn run(settings: &Settings, command: &str, args: &[String]) -> anyhow::Result<()> {
+    match command {
+        "create-admin" => {
+            let name = positional(command, args, 0)?;
+            let db = connect(settings, args).await?;
+            create_admin(&db, name).await
+        }
+        "migrate" => {
+            connect(settings, args).await?;
+            println!("数据库迁移完成");
+            Ok(())
+        }
+        "import-devices" => {
+            let path = positional(command, args, 0)?;
+            let db = connect(settings, args).await?;
+            import_devices(&db, Path::new(path), args.iter().any(|a| a == DRY_RUN_FLAG)).await
+        }
+        "rotate-key" => {
+            let id: i32 = positional(command, args, 0)?
+                .parse()
+                .context("API Key ID 须为整数")?;
+            let db = connect(settings, args).await?;
+            rotate_key(&db, id).await
+        }
+        "replay-backlog" => {
+            let db = connect(settings, args).await?;
+            replay_backlog(settings, &db).await
+        }
+        other => bail!("未知的命令: {}", other),
+    }
+}
+
+async fn create_admin(db: &DbManager, name: &str) -> anyhow::Result<()> {
+    let scopes: Vec<String> = ALL_SCOPES.iter().map(|s| s.to_string()).collect();
+    let conn = db.get_connection();
+    let (api_key, key) = api_key_service::issue(conn, name.to_string(), &scopes, None)
+        .await
+        .map_err(describe)?;
+
+    println!("已签发平台管理 API Key #{}（{}），权限: {}", api_key.id, api_key.name, api_key.scopes);
+    println!("{}", key);
+    println!("明文只显示这一次，请妥善保存");
+    Ok(())
+}
+
+async fn import_devices(db: &DbManager, path: &Path, dry_run: bool) -> anyhow::Result<()> {
+    let body = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("读取 {} 失败", path.display()))?;
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => TransferFormat::Csv,
+        _ => TransferFormat::Json,
+    };
+
+    let operator = Some(OPERATOR.to_string());
+    let report = device_handler::import_rows(
+        db.get_connection(),
+        Tenant::All,
+        format,
+        &body,
+        dry_run,
+        operator,
+    )
+    .await
+    .map_err(describe)?;
+
+    if !report.errors.is_empty() {
+        for error in &report.errors {
+            eprintln!("第 {} 行: {}", error.row, error.message);
+        }
+        bail!("{} 行中有 {} 行错误，未导入", report.total, report.errors.len());
+    }
+    if report.dry_run {
+        println!("校验通过，共 {} 行，未写入", report.total);
+    } else {
+        println!("已导入 {} 台设备", report.imported);
+    }
+    Ok(())
+}
+
+async fn rotate_key(db: &DbManager, id: i32) -> anyhow::Result<()> {
+    let conn = db.get_connection();
+    let existing = ApiKeyEntity::find_by_id(id)
+        .one(conn)
+        .await?
+        .ok_or_else(|| anyhow!("API Key #{} 不存在", id))?;
+
+    let (api_key, key) = api_key_service::rotate(conn, existing).await.map_err(describe)?;
+    println!("已轮换 API Key #{}（{}），旧 Key 已失效", api_key.id, api_key.name);
+    println!("{}", key);
+    Ok(())
+}
+
+async fn replay_backlog(settings: &Settings, db: &DbManager) -> anyhow::Result<()> {
+    let mode = ReadOnlyMode::new(
+        settings.read_only.clone(),
+        Compressor::new(settings.compression.clone()),
+        settings.quality.clone(),
+    );
+    if !mode.buffer_available() {
+        bail!("无法打开暂存文件 {}，请先停止服务", settings.read_only.buffer_path);
+    }
+    let pending = mode.buffered_count();
+    if pending == 0 {
+        println!("没有暂存的上报数据");
+        return Ok(());
+    }
+
+    let replayed = mode.replay(db, &HotCache::disabled()).await.map_err(describe)?;
+    println!("已补写 {} 条暂存数据，{} 条无效已丢弃", replayed, pending.saturating_sub(replayed));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positional() {
+        let args = vec!["--dry-run".to_string(), "devices.csv".to_string()];
+        assert_eq!(positional("import-devices", &args, 0).unwrap(), "devices.csv");
+        let error = positional("import-devices", &args, 1).unwrap_err();
+        assert_eq!(error.to_string(), "用法: guolu import-devices <文件> [--dry-run]");
+        assert!(is_command("migrate"));
+        assert!(!is_command("tui"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::utils::spi::SpiOptions;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 采样间隔
+    #[serde(default = "default_sample_interval_ms")]
+    pub sample_interval_ms: u64,
+    /// 写入测量值的间隔，期间的采样做滑动平均
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: u64,
+    #[serde(default)]
+    pub channels: Vec<AdcChannelConfig>,
+}
+
+impl Default for AdcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_interval_ms: default_sample_interval_ms(),
+            report_interval_secs: default_report_interval_secs(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// ADC 通道到设备指标的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdcChannelConfig {
+    pub name: String,
+    pub device_id: i32,
+    pub metric_type: String,
+    #[serde(flatten)]
+    pub source: AdcSource,
+    pub channel: u8,
+    /// 滑动平均的采样数
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// 原始码值到工程量的换算
+    #[serde(default)]
+    pub calibration: AdcCalibration,
+}
+
+/// ADC 芯片及其总线参数
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "chip", rename_all = "lowercase")]
+pub enum AdcSource {
+    Ads1115 {
+        #[serde(default = "default_i2c_bus")]
+        bus: String,
+        #[serde(default = "default_ads1115_address")]
+        address: u16,
+        /// 满量程电压 (V)
+        #[serde(default = "default_full_scale")]
+        full_scale: f64,
+    },
+    Mcp3208 {
+        spi: SpiOptions,
+    },
+}
+
+/// 码值换算方式
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AdcCalibration {
+    /// 工程量 = 码值 × slope + offset
+    Linear {
+        #[serde(default)]
+        offset: f64,
+        #[serde(default = "default_slope")]
+        slope: f64,
+    },
+    /// 多点标定 [[码值, 工程量], ...]，点之间线性插值
+    Curve { points: Vec<[f64; 2]> },
+}
+
+impl Default for AdcCalibration {
+    fn default() -> Self {
+        AdcCalibration::Linear {
+            offset: 0.0,
+            slope: default_slope(),
+        }
+    }
+}
+
+fn default_sample_interval_ms() -> u64 {
+    1000
+}
+
+fn default_report_interval_secs() -> u64 {
+    10
+}
+
+fn default_window() -> usize {
+    10
+}
+
+fn default_i2c_bus() -> String {
+    "/dev/i2c-1".to_string()
+}
+
+fn default_ads1115_address() -> u16 {
+    0x48
+}
+
+fn default_full_scale() -> f64 {
+    4.096
+}
+
+fn default_slope() -> f64 {
+    1.0
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlarmConfig {
+    /// 单次搁置的最长时长，到期自动解除
+    #[serde(default = "default_max_shelve_minutes")]
+    pub max_shelve_minutes: u64,
+    /// 同一报警点在该时间窗口内报警达到 `chattering_count` 次视为振荡报警
+    #[serde(default = "default_chattering_window_secs")]
+    pub chattering_window_secs: u64,
+    #[serde(default = "default_chattering_count")]
+    pub chattering_count: usize,
+    /// 报警超过该时长仍未处理视为常驻报警
+    #[serde(default = "default_standing_hours")]
+    pub standing_hours: u64,
+    /// 10 分钟内报警超过该条数视为报警泛滥
+    #[serde(default = "default_flood_threshold")]
+    pub flood_threshold: usize,
+    #[serde(default)]
+    pub snapshot: AlarmSnapshotConfig,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            max_shelve_minutes: default_max_shelve_minutes(),
+            chattering_window_secs: default_chattering_window_secs(),
+            chattering_count: default_chattering_count(),
+            standing_hours: default_standing_hours(),
+            flood_threshold: default_flood_threshold(),
+            snapshot: AlarmSnapshotConfig::default(),
+        }
+    }
+}
+
+/// 报警前后的过程数据快照
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlarmSnapshotConfig {
+    #[serde(default = "default_snapshot_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_window_minutes")]
+    pub before_minutes: u64,
+    /// 报警后经过该时长才采集快照
+    #[serde(default = "default_window_minutes")]
+    pub after_minutes: u64,
+    /// 包含报警设备自身的全部指标
+    #[serde(default = "default_include_device_metrics")]
+    pub include_device_metrics: bool,
+    /// 每个序列最多保留的点数，超出时均匀抽取
+    #[serde(default = "default_max_points")]
+    pub max_points: usize,
+    /// 按规则名称前缀追加的相关参数
+    #[serde(default)]
+    pub related: Vec<SnapshotRule>,
+}
+
+impl Default for AlarmSnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_snapshot_enabled(),
+            before_minutes: default_window_minutes(),
+            after_minutes: default_window_minutes(),
+            include_device_metrics: default_include_device_metrics(),
+            max_points: default_max_points(),
+            related: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnapshotRule {
+    /// 规则名称前缀，为空时匹配全部报警
+    #[serde(default)]
+    pub rule_prefix: String,
+    pub parameters: Vec<SnapshotParameter>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotParameter {
+    pub metric_type: String,
+    #[serde(default)]
+    pub device_id: Option<i32>,
+}
+
+fn default_max_shelve_minutes() -> u64 {
+    480
+}
+
+fn default_chattering_window_secs() -> u64 {
+    60
+}
+
+fn default_chattering_count() -> usize {
+    3
+}
+
+fn default_standing_hours() -> u64 {
+    24
+}
+
+fn default_flood_threshold() -> usize {
+    10
+}
+
+fn default_snapshot_enabled() -> bool {
+    true
+}
+
+fn default_window_minutes() -> u64 {
+    15
+}
+
+fn default_include_device_metrics() -> bool {
+    true
+}
+
+fn default_max_points() -> usize {
+    1000
+}
@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+/// 报警转发：以 SNMP Trap 和 syslog 发送到网管中心
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlarmForwardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 检查新报警的间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub snmp_traps: Vec<TrapTarget>,
+    #[serde(default)]
+    pub syslog: Vec<SyslogTarget>,
+}
+
+impl Default for AlarmForwardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval_secs(),
+            snmp_traps: Vec::new(),
+            syslog: Vec::new(),
+        }
+    }
+}
+
+/// SNMPv2c Trap 接收端
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrapTarget {
+    /// `ip:port`，端口通常为 162
+    pub address: String,
+    #[serde(default = "default_community")]
+    pub community: String,
+    /// 自定义 MIB 的根 OID，Trap OID 为 `{根}.0.1`，各字段为 `{根}.1.n`
+    #[serde(default = "default_enterprise_oid")]
+    pub enterprise_oid: String,
+    /// 转发的严重程度
+    #[serde(default = "default_severities")]
+    pub severities: Vec<String>,
+}
+
+/// RFC 5424 syslog 接收端
+#[derive(Deserialize, Debug, Clone)]
+pub struct SyslogTarget {
+    /// `ip:port`，端口通常为 514
+    pub address: String,
+    /// udp / tcp，TCP 按 RFC 6587 加长度前缀
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    /// 设施号，默认 16（local0）
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    /// 报文中的主机名，为空时填 `-`
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    #[serde(default = "default_severities")]
+    pub severities: Vec<String>,
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_community() -> String {
+    "public".to_string()
+}
+
+fn default_enterprise_oid() -> String {
+    "1.3.6.1.4.1.8072.9999.9999".to_string()
+}
+
+fn default_severities() -> Vec<String> {
+    vec!["critical".to_string()]
+}
+
+fn default_protocol() -> String {
+    "udp".to_string()
+}
+
+fn default_facility() -> u8 {
+    16
+}
+
+fn default_app_name() -> String {
+    "guolu".to_string()
+}
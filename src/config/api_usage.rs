@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+/// 按客户端统计接口调用
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiUsageConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 内存中的统计写入数据库的间隔
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// 统计记录保留天数
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for ApiUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            flush_interval_secs: default_flush_interval_secs(),
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_flush_interval_secs() -> u64 {
+    60
+}
+
+fn default_retention_days() -> u32 {
+    90
+}
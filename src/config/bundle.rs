@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+/// 配置包导出/导入
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BundleConfig {
+    /// HMAC 签名密钥，测试环境与生产环境须一致；为空时禁用导出/导入
+    #[serde(default)]
+    pub signing_key: String,
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+/// 热点数据缓存（redb）
+#[derive(Deserialize, Debug, Clone)]
+pub struct CacheConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 缓存文件路径，启动时清空
+    #[serde(default = "default_path")]
+    pub path: String,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            path: default_path(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_path() -> String {
+    "cache.redb".to_string()
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// 传感器现场校准到期提醒
+#[derive(Deserialize, Debug, Clone)]
+pub struct CalibrationConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 记录校准时未给出校准周期时使用的天数
+    #[serde(default = "default_interval_days")]
+    pub default_interval_days: u32,
+    /// 到期前几天开始提醒
+    #[serde(default = "default_remind_days")]
+    pub remind_days: u32,
+    /// 到期提醒的严重程度
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            default_interval_days: default_interval_days(),
+            remind_days: default_remind_days(),
+            severity: default_severity(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+fn default_interval_days() -> u32 {
+    90
+}
+
+fn default_remind_days() -> u32 {
+    7
+}
+
+fn default_severity() -> String {
+    "warning".to_string()
+}
@@ -0,0 +1,43 @@
+use crate::utils::can::CanSignal;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CanConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interface")]
+    pub interface: String,
+    /// 打开接口失败或读取出错后的重试间隔
+    #[serde(default = "default_retry_secs")]
+    pub retry_secs: u64,
+    #[serde(default)]
+    pub signals: Vec<CanSignalMapping>,
+}
+
+impl Default for CanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: default_interface(),
+            retry_secs: default_retry_secs(),
+            signals: Vec::new(),
+        }
+    }
+}
+
+/// 信号到设备指标的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct CanSignalMapping {
+    #[serde(flatten)]
+    pub signal: CanSignal,
+    pub device_id: i32,
+    pub metric_type: String,
+}
+
+fn default_interface() -> String {
+    "can0".to_string()
+}
+
+fn default_retry_secs() -> u64 {
+    10
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+/// 变更管理（MOC）
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ChangeControlConfig {
+    /// 开启后报警规则、自动化规则的修改需另一名工程师审批后才生效
+    #[serde(default)]
+    pub require_review: bool,
+}
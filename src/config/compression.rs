@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CompressionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 按指标（可细化到设备）配置的压缩规则，没有匹配规则的数据全部入库
+    #[serde(default)]
+    pub rules: Vec<CompressionRule>,
+}
+
+impl CompressionConfig {
+    /// 查找生效的规则，设备规则优先于指标规则
+    pub fn rule_for(&self, device_id: Option<i32>, metric_type: &str) -> Option<&CompressionRule> {
+        if !self.enabled {
+            return None;
+        }
+        self.rules
+            .iter()
+            .filter(|r| r.metric_type == metric_type)
+            .filter(|r| r.device_id.is_none() || r.device_id == device_id)
+            .max_by_key(|r| r.device_id.is_some())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionMethod {
+    /// 死区：与上次入库值相差超过容差才入库
+    #[default]
+    Deadband,
+    /// 旋转门：入库点之间线性插值的偏差不超过容差，适合长期归档
+    SwingingDoor,
+}
+
+/// 压缩规则，距上次入库超过 `max_interval_secs` 时总会入库
+#[derive(Deserialize, Debug, Clone)]
+pub struct CompressionRule {
+    pub metric_type: String,
+    /// 为空时对该指标的所有设备生效；同时存在时设备规则优先
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    #[serde(default)]
+    pub method: CompressionMethod,
+    /// 容差，与上报的原始值比较
+    #[serde(alias = "deadband")]
+    pub tolerance: f64,
+    #[serde(default = "default_max_interval_secs")]
+    pub max_interval_secs: u64,
+}
+
+fn default_max_interval_secs() -> u64 {
+    300
+}
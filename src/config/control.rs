@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+/// PID 控制回路
+#[derive(Deserialize, Debug, Clone)]
+pub struct ControlConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 控制周期
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// 测量值超过该时长未更新时保持输出不变，不再积分
+    #[serde(default = "default_input_max_age_secs")]
+    pub input_max_age_secs: u64,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_ms: default_interval_ms(),
+            input_max_age_secs: default_input_max_age_secs(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+fn default_input_max_age_secs() -> u64 {
+    60
+}
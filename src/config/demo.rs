@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+/// 演示模式
+///
+/// 开启后写入演示厂站和设备，由模拟传感器持续上报 pH、流量、浊度，不需要现场硬件。
+/// 也可以用启动参数 `--demo` 临时开启。
+#[derive(Deserialize, Debug, Clone)]
+pub struct DemoConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每台模拟设备的上报间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次上报时开始一段超标波动的概率（百分比）
+    #[serde(default = "default_excursion_percent")]
+    pub excursion_percent: f64,
+}
+
+impl Default for DemoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval_secs(),
+            excursion_percent: default_excursion_percent(),
+        }
+    }
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_excursion_percent() -> f64 {
+    2.0
+}
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+/// 加药管理
+#[derive(Deserialize, Debug, Clone)]
+pub struct DosingConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 储罐液位递减和 pH 闭环的周期
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// pH 读数超过该时长未更新时，自动模式的加药泵停机
+    #[serde(default = "default_ph_max_age_secs")]
+    pub ph_max_age_secs: u64,
+    /// 偏离设定值不超过该值时不投加
+    #[serde(default = "default_ph_deadband")]
+    pub ph_deadband: f64,
+    /// 低液位报警的严重程度
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+impl Default for DosingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            ph_max_age_secs: default_ph_max_age_secs(),
+            ph_deadband: default_ph_deadband(),
+            severity: default_severity(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    10
+}
+
+fn default_ph_max_age_secs() -> u64 {
+    120
+}
+
+fn default_ph_deadband() -> f64 {
+    0.05
+}
+
+fn default_severity() -> String {
+    "major".to_string()
+}
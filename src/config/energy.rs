@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+/// 能耗计量
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnergyConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次重算最近几天（含今天）的能耗，补录数据在此范围内会被计入
+    #[serde(default = "default_backfill_days")]
+    pub backfill_days: u32,
+    /// 计算单位水量能耗时作为处理水量的流量计，通常是出水总流量计；为空时不计算 kWh/m³
+    #[serde(default)]
+    pub treated_flow_devices: Vec<i32>,
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            backfill_days: default_backfill_days(),
+            treated_flow_devices: Vec::new(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+fn default_backfill_days() -> u32 {
+    3
+}
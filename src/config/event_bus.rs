@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+/// 事件总线
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventBusConfig {
+    /// auto / rabbitmq / in_process，auto 在 RabbitMQ 可用时使用 RabbitMQ，否则使用进程内总线
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    /// 进程内总线的缓冲事件数
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// 报警和设备上下线事件的发布间隔
+    #[serde(default = "default_relay_interval_secs")]
+    pub relay_interval_secs: u64,
+    /// 同时发布新入库的测量值，数据量大，一般只在抄送 Kafka 时开启
+    #[serde(default)]
+    pub relay_measurements: bool,
+}
+
+impl Default for EventBusConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_backend(),
+            capacity: default_capacity(),
+            relay_interval_secs: default_relay_interval_secs(),
+            relay_measurements: false,
+        }
+    }
+}
+
+fn default_backend() -> String {
+    "auto".to_string()
+}
+
+fn default_capacity() -> usize {
+    1024
+}
+
+fn default_relay_interval_secs() -> u64 {
+    2
+}
@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+/// 累计流量（排放水量）
+#[derive(Deserialize, Debug, Clone)]
+pub struct FlowTotalConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次重算最近几天（含今天）的累计量，补录数据在此范围内会被计入
+    #[serde(default = "default_backfill_days")]
+    pub backfill_days: u32,
+    /// 瞬时流量相邻两个样本间隔超过该秒数时视为中断，中间不积分；应不小于压缩的最长入库间隔
+    #[serde(default = "default_max_gap_secs")]
+    pub max_gap_secs: i64,
+}
+
+impl Default for FlowTotalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            backfill_days: default_backfill_days(),
+            max_gap_secs: default_max_gap_secs(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    900
+}
+
+fn default_backfill_days() -> u32 {
+    3
+}
+
+fn default_max_gap_secs() -> i64 {
+    3600
+}
@@ -0,0 +1,76 @@
+use crate::utils::gpio::Edge;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GpioConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_chip")]
+    pub chip: String,
+    /// 退回 sysfs 时 gpiochip 的起始编号
+    #[serde(default)]
+    pub sysfs_base: u32,
+    #[serde(default)]
+    pub inputs: Vec<GpioInput>,
+    #[serde(default)]
+    pub outputs: Vec<GpioOutput>,
+}
+
+impl Default for GpioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chip: default_chip(),
+            sysfs_base: 0,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+/// 浮球开关、门磁等开关量输入，在指定边沿触发报警
+#[derive(Deserialize, Debug, Clone)]
+pub struct GpioInput {
+    pub name: String,
+    pub line: u32,
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    #[serde(default)]
+    pub edge: Edge,
+    /// 消抖时间，期间的重复跳变忽略
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    /// 报警严重程度：critical / major / minor / warning
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+/// 继电器等开关量输出
+#[derive(Deserialize, Debug, Clone)]
+pub struct GpioOutput {
+    pub name: String,
+    pub line: u32,
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    /// 运行反馈输入，启动核对时优先读取；未配置时读取输出引脚本身的电平
+    #[serde(default)]
+    pub feedback_line: Option<u32>,
+}
+
+impl GpioConfig {
+    pub fn output(&self, name: &str) -> Option<&GpioOutput> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+}
+
+fn default_chip() -> String {
+    "/dev/gpiochip0".to_string()
+}
+
+fn default_debounce_ms() -> u64 {
+    50
+}
+
+fn default_severity() -> String {
+    "major".to_string()
+}
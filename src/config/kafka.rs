@@ -0,0 +1,72 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// 事件抄送到 Kafka（总部数据汇聚），需要开启 `kafka` 特性编译
+#[derive(Deserialize, Debug, Clone)]
+pub struct KafkaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// bootstrap.servers，逗号分隔
+    #[serde(default = "default_brokers")]
+    pub brokers: String,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    /// 事件路由键到 Kafka 主题的映射，如 `alarm.triggered` -> `plant01.alarms`
+    #[serde(default)]
+    pub topics: HashMap<String, String>,
+    /// 未在 `topics` 中列出的事件发往该主题，为空时不发送
+    #[serde(default)]
+    pub default_topic: Option<String>,
+    /// 攒批等待时间（linger.ms）
+    #[serde(default = "default_linger_ms")]
+    pub linger_ms: u64,
+    /// 单批最多消息数（batch.num.messages）
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// none / gzip / snappy / lz4 / zstd
+    #[serde(default = "default_compression")]
+    pub compression: String,
+    /// 消息在本地重试投递的最长时间
+    #[serde(default = "default_message_timeout_ms")]
+    pub message_timeout_ms: u64,
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brokers: default_brokers(),
+            client_id: default_client_id(),
+            topics: HashMap::new(),
+            default_topic: None,
+            linger_ms: default_linger_ms(),
+            batch_size: default_batch_size(),
+            compression: default_compression(),
+            message_timeout_ms: default_message_timeout_ms(),
+        }
+    }
+}
+
+fn default_brokers() -> String {
+    "localhost:9092".to_string()
+}
+
+fn default_client_id() -> String {
+    "guolu".to_string()
+}
+
+fn default_linger_ms() -> u64 {
+    100
+}
+
+fn default_batch_size() -> usize {
+    1000
+}
+
+fn default_compression() -> String {
+    "lz4".to_string()
+}
+
+fn default_message_timeout_ms() -> u64 {
+    300_000
+}
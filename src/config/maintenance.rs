@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+/// 维护计划与工单
+#[derive(Deserialize, Debug, Clone)]
+pub struct MaintenanceConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 检查计划到期和工单超期的间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 超期报警的严重程度
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            severity: default_severity(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    600
+}
+
+fn default_severity() -> String {
+    "minor".to_string()
+}
@@ -0,0 +1,49 @@
+use serde::Deserialize;
+
+/// 启动迁移前的预检
+#[derive(Deserialize, Debug, Clone)]
+pub struct MigrationConfig {
+    /// 迁移前检查磁盘空间、自动备份并估算耗时
+    #[serde(default = "default_preflight")]
+    pub preflight: bool,
+    /// 迁移所需的最少剩余磁盘空间（MB），另需预留一份数据库大小用于备份
+    #[serde(default = "default_min_free_disk_mb")]
+    pub min_free_disk_mb: u64,
+    /// 自动备份目录，仅 SQLite 可自动备份
+    #[serde(default = "default_backup_dir")]
+    pub backup_dir: String,
+    /// 允许破坏性变更（搬迁并清空旧表等），也可用启动参数 `--allow-destructive` 临时开启
+    #[serde(default)]
+    pub allow_destructive: bool,
+    /// 估算耗时用的处理速度（行/秒）
+    #[serde(default = "default_rows_per_second")]
+    pub rows_per_second: u64,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            preflight: default_preflight(),
+            min_free_disk_mb: default_min_free_disk_mb(),
+            backup_dir: default_backup_dir(),
+            allow_destructive: false,
+            rows_per_second: default_rows_per_second(),
+        }
+    }
+}
+
+fn default_preflight() -> bool {
+    true
+}
+
+fn default_min_free_disk_mb() -> u64 {
+    512
+}
+
+fn default_backup_dir() -> String {
+    "backups".to_string()
+}
+
+fn default_rows_per_second() -> u64 {
+    20_000
+}
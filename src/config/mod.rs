@@ -1 +1,54 @@
-pub mod server;
\ No newline at end of file
+pub mod adc;
+pub mod alarm;
+pub mod alarm_forward;
+pub mod api_usage;
+pub mod bundle;
+pub mod cache;
+pub mod calibration;
+pub mod can;
+pub mod change_control;
+pub mod compression;
+pub mod control;
+pub mod database;
+pub mod demo;
+pub mod dosing;
+pub mod energy;
+pub mod event_bus;
+pub mod flow_total;
+pub mod gpio;
+pub mod grpc;
+pub mod kafka;
+pub mod maintenance;
+pub mod migration;
+pub mod modbus;
+pub mod mqtt;
+pub mod network;
+pub mod opcua;
+pub mod permit;
+pub mod pi;
+pub mod pump;
+pub mod pump_group;
+pub mod pwm;
+pub mod quality;
+pub mod query_guard;
+pub mod rabbitmq;
+pub mod rate_limit;
+pub mod read_only;
+pub mod reconcile;
+pub mod remote_access;
+pub mod report;
+pub mod retention;
+pub mod run_hours;
+pub mod runtime;
+pub mod safe_state;
+pub mod security;
+pub mod serial_console;
+pub mod server;
+pub mod settings;
+pub mod snmp;
+pub mod summary;
+pub mod system;
+pub mod tenancy;
+pub mod timeseries;
+pub mod timezone;
+pub mod trend;
\ No newline at end of file
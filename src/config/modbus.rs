@@ -0,0 +1,249 @@
+use crate::config::serial_console::SerialPortConfig;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModbusConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单次请求（含连接）的超时时间
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub devices: Vec<ModbusDeviceConfig>,
+    /// 本机作为 Modbus TCP 从站，供上位机轮询
+    #[serde(default)]
+    pub server: ModbusServerConfig,
+}
+
+impl Default for ModbusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_ms: default_timeout_ms(),
+            devices: Vec::new(),
+            server: ModbusServerConfig::default(),
+        }
+    }
+}
+
+impl ModbusConfig {
+    pub fn device(&self, device_id: i32) -> Option<&ModbusDeviceConfig> {
+        self.devices.iter().find(|d| d.device_id == device_id)
+    }
+}
+
+/// 通信方式
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "transport", rename_all = "snake_case")]
+pub enum ModbusTarget {
+    Tcp {
+        host: String,
+        #[serde(default = "default_port")]
+        port: u16,
+    },
+    Rtu { serial: SerialPortConfig },
+    /// 内存中的模拟 RTU 总线，见 [`crate::utils::modbus_loopback`]
+    Loopback { bus: String },
+}
+
+/// 一台 Modbus 从站设备
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModbusDeviceConfig {
+    pub device_id: i32,
+    #[serde(flatten)]
+    pub target: ModbusTarget,
+    #[serde(default = "default_unit_id")]
+    pub unit_id: u8,
+    /// 允许写入的寄存器，未列出的一律拒绝
+    #[serde(default)]
+    pub writable: Vec<WritableRegister>,
+}
+
+impl ModbusDeviceConfig {
+    pub fn writable(&self, kind: RegisterKind, address: u16) -> Option<&WritableRegister> {
+        self.writable.iter().find(|r| r.kind == kind && r.address == address)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterKind {
+    #[default]
+    Holding,
+    Coil,
+}
+
+impl RegisterKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RegisterKind::Holding => "holding",
+            RegisterKind::Coil => "coil",
+        }
+    }
+}
+
+/// 可写寄存器及其安全限制
+#[derive(Deserialize, Debug, Clone)]
+pub struct WritableRegister {
+    #[serde(default)]
+    pub kind: RegisterKind,
+    pub address: u16,
+    /// 例如 `1#泵启停`
+    #[serde(default)]
+    pub label: String,
+    /// 工程值范围，超出时截断到边界
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 寄存器原始值 = 工程值 / scale
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub interlocks: Vec<Interlock>,
+}
+
+/// 联锁条件：写入前检查其他设备的最新值，不满足时拒绝写入
+#[derive(Deserialize, Debug, Clone)]
+pub struct Interlock {
+    pub device_id: i32,
+    pub metric_type: String,
+    /// 最新值须在 [min, max] 内
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 最新值超过该时长视为不可信，联锁不满足
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+    /// 只在写入该值时检查，例如线圈写 1（启泵）时才要求液位足够
+    #[serde(default)]
+    pub when_value: Option<f64>,
+    /// 例如 `集水井液位过低禁止启泵`
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Modbus 从站：输入寄存器映射最新测量值，离散输入映射设备状态，
+/// 保持寄存器接收设定值并转为设备命令
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModbusServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// 寄存器刷新间隔
+    #[serde(default = "default_refresh_secs")]
+    pub refresh_secs: u64,
+    #[serde(default)]
+    pub input_registers: Vec<ServerPoint>,
+    #[serde(default)]
+    pub setpoints: Vec<ServerSetpoint>,
+    #[serde(default)]
+    pub discrete_inputs: Vec<ServerStatus>,
+}
+
+impl Default for ModbusServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_listen(),
+            refresh_secs: default_refresh_secs(),
+            input_registers: Vec::new(),
+            setpoints: Vec::new(),
+            discrete_inputs: Vec::new(),
+        }
+    }
+}
+
+/// 输入寄存器：设备指标的最新值
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerPoint {
+    pub address: u16,
+    pub device_id: i32,
+    pub metric_type: String,
+    /// u16 / i16 / u32 / i32 / f32 / u64 / i64 / f64
+    #[serde(default = "default_data_type")]
+    pub data_type: String,
+    /// ABCD / CDAB / BADC / DCBA
+    #[serde(default = "default_byte_order")]
+    pub byte_order: String,
+    /// 寄存器值 = 工程值 * scale
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// 最新值超过该时长视为失效，寄存器保持最后的有效值；为空不检查
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+    /// 质量位所在的离散输入地址，值有效时为 1
+    #[serde(default)]
+    pub quality_address: Option<u16>,
+}
+
+/// 离散输入：设备处于某状态时为 1
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerStatus {
+    pub address: u16,
+    pub device_id: i32,
+    /// running / stopped / fault / online / offline
+    pub state: String,
+}
+
+/// 保持寄存器：上位机写入的设定值，转为设备命令下发
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerSetpoint {
+    pub address: u16,
+    pub device_id: i32,
+    /// 下发的命令名，命令参数为 `{"value": 工程值}`
+    pub command: String,
+    #[serde(default = "default_data_type")]
+    pub data_type: String,
+    #[serde(default = "default_byte_order")]
+    pub byte_order: String,
+    /// 工程值 = 寄存器值 / scale
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// 工程值范围，超出时拒绝
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 回读的指标，配置后寄存器随该指标最新值刷新，否则保持最后写入的值
+    #[serde(default)]
+    pub feedback_metric: Option<String>,
+}
+
+fn default_listen() -> String {
+    "0.0.0.0:5020".to_string()
+}
+
+fn default_refresh_secs() -> u64 {
+    5
+}
+
+fn default_data_type() -> String {
+    "f32".to_string()
+}
+
+fn default_byte_order() -> String {
+    "ABCD".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_port() -> u16 {
+    502
+}
+
+fn default_unit_id() -> u8 {
+    1
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_max_age_secs() -> u64 {
+    300
+}
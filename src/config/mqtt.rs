@@ -0,0 +1,131 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+    /// 离线队列文件，broker 断线或进程重启期间未发出的消息保存在这里
+    #[serde(default = "default_outbox_path")]
+    pub outbox_path: String,
+    /// 离线队列最多保留的消息数，超出时丢弃最早的消息
+    #[serde(default = "default_outbox_max_messages")]
+    pub outbox_max_messages: u64,
+    /// 设备命令等待确认的时长
+    #[serde(default = "default_command_timeout_secs")]
+    pub command_timeout_secs: u64,
+    #[serde(default)]
+    pub sparkplug: SparkplugConfig,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: default_client_id(),
+            host: default_host(),
+            port: default_port(),
+            keep_alive_secs: default_keep_alive_secs(),
+            outbox_path: default_outbox_path(),
+            outbox_max_messages: default_outbox_max_messages(),
+            command_timeout_secs: default_command_timeout_secs(),
+            sparkplug: SparkplugConfig::default(),
+        }
+    }
+}
+
+/// Sparkplug B 边缘网关接入
+#[derive(Deserialize, Debug, Clone)]
+pub struct SparkplugConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 订阅的组，`+` 表示全部
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+    /// 收到未知别名的数据时请求边缘节点重发 BIRTH
+    #[serde(default = "default_request_rebirth")]
+    pub request_rebirth: bool,
+    #[serde(default)]
+    pub devices: Vec<SparkplugDevice>,
+}
+
+impl Default for SparkplugConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            group_id: default_group_id(),
+            request_rebirth: default_request_rebirth(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// Sparkplug 节点或设备到本系统设备的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct SparkplugDevice {
+    pub edge_node: String,
+    /// Sparkplug 设备名，为空表示边缘节点自身的指标
+    #[serde(default)]
+    pub device: Option<String>,
+    pub device_id: i32,
+    #[serde(default)]
+    pub metrics: Vec<SparkplugMetric>,
+}
+
+/// Sparkplug 指标到测量值的映射，未列出的指标忽略
+#[derive(Deserialize, Debug, Clone)]
+pub struct SparkplugMetric {
+    /// Sparkplug 指标名，例如 `Inlet/Flow`
+    pub name: String,
+    pub metric_type: String,
+    /// 写入前乘以的系数
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_client_id() -> String {
+    "guolu-backend".to_string()
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_keep_alive_secs() -> u64 {
+    30
+}
+
+fn default_outbox_path() -> String {
+    "mqtt_outbox.redb".to_string()
+}
+
+fn default_outbox_max_messages() -> u64 {
+    100_000
+}
+
+fn default_command_timeout_secs() -> u64 {
+    30
+}
+
+fn default_group_id() -> String {
+    "+".to_string()
+}
+
+fn default_request_rebirth() -> bool {
+    true
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
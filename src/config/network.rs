@@ -0,0 +1,46 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NetworkMonitorConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_sample_interval_secs")]
+    pub sample_interval_secs: u64,
+    /// 测试时可指向模拟的 sysfs 目录
+    #[serde(default)]
+    pub sysfs_base: Option<String>,
+    #[serde(default)]
+    pub interfaces: Vec<NetworkInterfaceConfig>,
+}
+
+impl Default for NetworkMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_interval_secs: default_sample_interval_secs(),
+            sysfs_base: None,
+            interfaces: Vec::new(),
+        }
+    }
+}
+
+/// 被监测的网络接口
+#[derive(Deserialize, Debug, Clone)]
+pub struct NetworkInterfaceConfig {
+    /// 接口名，例如 `eth0`
+    pub name: String,
+    /// 报警关联的设备（例如泵站网关），可为空
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    /// 收发字节数持续不变超过该时长视为无流量，0 表示不检测
+    #[serde(default = "default_zero_throughput_secs")]
+    pub zero_throughput_secs: u64,
+}
+
+fn default_sample_interval_secs() -> u64 {
+    10
+}
+
+fn default_zero_throughput_secs() -> u64 {
+    300
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpcUaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PLC 的 OPC UA 服务地址，例如 `opc.tcp://10.20.1.10:4840`
+    #[serde(default)]
+    pub endpoint_url: String,
+    /// 为空时匿名登录
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 订阅的发布间隔
+    #[serde(default = "default_publishing_interval_ms")]
+    pub publishing_interval_ms: u64,
+    /// 连接断开后的重连间隔
+    #[serde(default = "default_reconnect_interval_secs")]
+    pub reconnect_interval_secs: u64,
+    #[serde(default)]
+    pub nodes: Vec<OpcUaNodeMapping>,
+}
+
+impl Default for OpcUaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint_url: String::new(),
+            username: None,
+            password: None,
+            publishing_interval_ms: default_publishing_interval_ms(),
+            reconnect_interval_secs: default_reconnect_interval_secs(),
+            nodes: Vec::new(),
+        }
+    }
+}
+
+/// 节点到设备指标的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpcUaNodeMapping {
+    /// 节点ID，例如 `ns=2;s=Pump1.Flow`
+    pub node_id: String,
+    pub device_id: i32,
+    pub metric_type: String,
+    /// 写入前乘以的系数
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_publishing_interval_ms() -> u64 {
+    1000
+}
+
+fn default_reconnect_interval_secs() -> u64 {
+    10
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
@@ -0,0 +1,44 @@
+use serde::Deserialize;
+
+/// 排污许可限值评估
+#[derive(Deserialize, Debug, Clone)]
+pub struct PermitConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 评估间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次评估回看的小时数，补录数据造成的超标在此范围内会被补记
+    #[serde(default = "default_lookback_hours")]
+    pub lookback_hours: u32,
+    /// 只评估在该小时数之前结束的窗口，等待小时汇总完成
+    #[serde(default = "default_settle_hours")]
+    pub settle_hours: u32,
+}
+
+impl Default for PermitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            lookback_hours: default_lookback_hours(),
+            settle_hours: default_settle_hours(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+fn default_lookback_hours() -> u32 {
+    48
+}
+
+fn default_settle_hours() -> u32 {
+    1
+}
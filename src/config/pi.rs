@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+/// 转发到 PI 历史库（PI Web API）
+#[derive(Deserialize, Debug, Clone)]
+pub struct PiExportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PI Web API 地址，例如 `https://pi.example.local/piwebapi`
+    #[serde(default)]
+    pub base_url: String,
+    /// Basic 认证，为空时不认证
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 接受自签名证书
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 无新数据时的轮询间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次发送的最多测量值条数
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+    /// 发送失败后的最长重试间隔，间隔从 `interval_secs` 开始逐次加倍
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// 已发送位置的保存文件，重启后从该位置继续
+    #[serde(default = "default_state_path")]
+    pub state_path: String,
+    #[serde(default)]
+    pub tags: Vec<PiTagMapping>,
+}
+
+impl Default for PiExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: String::new(),
+            username: None,
+            password: None,
+            accept_invalid_certs: false,
+            timeout_secs: default_timeout_secs(),
+            interval_secs: default_interval_secs(),
+            batch_size: default_batch_size(),
+            max_backoff_secs: default_max_backoff_secs(),
+            state_path: default_state_path(),
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// 设备指标到 PI 点的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct PiTagMapping {
+    pub device_id: i32,
+    pub metric_type: String,
+    /// PI 点的 WebId
+    pub web_id: String,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_interval_secs() -> u64 {
+    10
+}
+
+fn default_batch_size() -> u64 {
+    1000
+}
+
+fn default_max_backoff_secs() -> u64 {
+    600
+}
+
+fn default_state_path() -> String {
+    "pi_export.redb".to_string()
+}
@@ -0,0 +1,42 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PumpMonitorConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 流量、压力、功率的最新值超过该时长未更新时不评估
+    #[serde(default = "default_max_sample_age_secs")]
+    pub max_sample_age_secs: i64,
+    /// 介质密度 (kg/m³)
+    #[serde(default = "default_fluid_density")]
+    pub fluid_density: f64,
+}
+
+impl Default for PumpMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            max_sample_age_secs: default_max_sample_age_secs(),
+            fluid_density: default_fluid_density(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+fn default_max_sample_age_secs() -> i64 {
+    600
+}
+
+fn default_fluid_density() -> f64 {
+    1000.0
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+/// 主备泵组轮换
+#[derive(Deserialize, Debug, Clone)]
+pub struct PumpGroupConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 故障切换和可用泵不足报警的严重程度
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+impl Default for PumpGroupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            severity: default_severity(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_severity() -> String {
+    "major".to_string()
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PwmConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub channels: Vec<PwmChannelConfig>,
+}
+
+/// 设备到 PWM 通道的映射及安全限制
+#[derive(Deserialize, Debug, Clone)]
+pub struct PwmChannelConfig {
+    pub device_id: i32,
+    #[serde(default)]
+    pub chip: u32,
+    pub channel: u32,
+    /// 允许的占空比范围 (%)
+    #[serde(default)]
+    pub min_duty: f64,
+    #[serde(default = "default_max_duty")]
+    pub max_duty: f64,
+    /// 允许的频率范围 (Hz)
+    #[serde(default = "default_min_frequency_hz")]
+    pub min_frequency_hz: f64,
+    #[serde(default = "default_max_frequency_hz")]
+    pub max_frequency_hz: f64,
+    /// 未指定频率时使用
+    #[serde(default = "default_frequency_hz")]
+    pub default_frequency_hz: f64,
+    /// 占空比变化速率 (%/s)，0 表示不限速直接跳变
+    #[serde(default = "default_ramp_rate")]
+    pub ramp_rate_pct_per_sec: f64,
+}
+
+fn default_max_duty() -> f64 {
+    100.0
+}
+
+fn default_min_frequency_hz() -> f64 {
+    1.0
+}
+
+fn default_max_frequency_hz() -> f64 {
+    20000.0
+}
+
+fn default_frequency_hz() -> f64 {
+    1000.0
+}
+
+fn default_ramp_rate() -> f64 {
+    10.0
+}
@@ -0,0 +1,55 @@
+use serde::Deserialize;
+
+/// 测量值数据质量检查
+#[derive(Deserialize, Debug, Clone)]
+pub struct QualityConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 按指标（可细化到设备）配置的检查规则，没有匹配规则的数据标记为 good
+    #[serde(default)]
+    pub rules: Vec<QualityRule>,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self { enabled: default_enabled(), rules: Vec::new() }
+    }
+}
+
+impl QualityConfig {
+    /// 查找生效的规则，设备规则优先于指标规则
+    pub fn rule_for(&self, device_id: Option<i32>, metric_type: &str) -> Option<&QualityRule> {
+        if !self.enabled {
+            return None;
+        }
+        self.rules
+            .iter()
+            .filter(|r| r.metric_type == metric_type)
+            .filter(|r| r.device_id.is_none() || r.device_id == device_id)
+            .max_by_key(|r| r.device_id.is_some())
+    }
+}
+
+/// 检查规则，未配置的项不检查
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct QualityRule {
+    pub metric_type: String,
+    /// 为空时对该指标的所有设备生效；同时存在时设备规则优先
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    /// 仪表量程或工艺上的合理范围（默认单位），超出时标记为 out_of_range
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 与上一条数据相比每分钟变化超过该值时标记为 suspect
+    #[serde(default)]
+    pub max_rate_per_min: Option<f64>,
+    /// 数值连续该分钟数不变时标记为 frozen
+    #[serde(default)]
+    pub frozen_minutes: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
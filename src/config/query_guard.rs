@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueryGuardConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 原始数据查询允许的估算行数上限
+    #[serde(default = "default_max_raw_rows")]
+    pub max_raw_rows: u64,
+    /// 估算行数时假定的采样间隔
+    #[serde(default = "default_sample_interval_secs")]
+    pub sample_interval_secs: u64,
+    /// 分页偏移上限，再往后翻页须缩小时间范围
+    #[serde(default = "default_max_offset")]
+    pub max_offset: u64,
+    /// 聚合查询允许的最多时间桶数
+    #[serde(default = "default_max_buckets")]
+    pub max_buckets: u64,
+}
+
+impl Default for QueryGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_raw_rows: default_max_raw_rows(),
+            sample_interval_secs: default_sample_interval_secs(),
+            max_offset: default_max_offset(),
+            max_buckets: default_max_buckets(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_raw_rows() -> u64 {
+    100_000
+}
+
+fn default_sample_interval_secs() -> u64 {
+    10
+}
+
+fn default_max_offset() -> u64 {
+    10_000
+}
+
+fn default_max_buckets() -> u64 {
+    2_000
+}
@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 令牌桶容量（允许的突发请求数）
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// 每秒补充的令牌数
+    #[serde(default = "default_per_second")]
+    pub per_second: f64,
+    /// 识别客户端的 API Key 请求头，缺失或无效时按客户端 IP 限流
+    #[serde(default = "default_key_header")]
+    pub key_header: String,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            burst: default_burst(),
+            per_second: default_per_second(),
+            key_header: default_key_header(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_burst() -> u32 {
+    60
+}
+
+fn default_per_second() -> f64 {
+    10.0
+}
+
+fn default_key_header() -> String {
+    "x-api-key".to_string()
+}
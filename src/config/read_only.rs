@@ -0,0 +1,60 @@
+use serde::Deserialize;
+
+/// 只读模式
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReadOnlyConfig {
+    /// 磁盘空间不足时自动进入只读模式
+    #[serde(default = "default_auto_on_low_disk")]
+    pub auto_on_low_disk: bool,
+    /// 检查可用空间的路径，一般为数据库所在目录
+    #[serde(default = "default_disk_path")]
+    pub disk_path: String,
+    /// 可用空间低于该值（MB）时进入只读模式，恢复到 1.1 倍以上时退出
+    #[serde(default = "default_min_free_disk_mb")]
+    pub min_free_disk_mb: u64,
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 只读期间上报数据的暂存文件
+    #[serde(default = "default_buffer_path")]
+    pub buffer_path: String,
+    /// 最多暂存的上报条数，超出后拒绝上报
+    #[serde(default = "default_max_buffered")]
+    pub max_buffered: u64,
+}
+
+impl Default for ReadOnlyConfig {
+    fn default() -> Self {
+        Self {
+            auto_on_low_disk: default_auto_on_low_disk(),
+            disk_path: default_disk_path(),
+            min_free_disk_mb: default_min_free_disk_mb(),
+            check_interval_secs: default_check_interval_secs(),
+            buffer_path: default_buffer_path(),
+            max_buffered: default_max_buffered(),
+        }
+    }
+}
+
+fn default_auto_on_low_disk() -> bool {
+    true
+}
+
+fn default_disk_path() -> String {
+    ".".to_string()
+}
+
+fn default_min_free_disk_mb() -> u64 {
+    256
+}
+
+fn default_check_interval_secs() -> u64 {
+    30
+}
+
+fn default_buffer_path() -> String {
+    "ingest_buffer.redb".to_string()
+}
+
+fn default_max_buffered() -> u64 {
+    100_000
+}
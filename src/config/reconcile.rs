@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+/// 启动时核对执行器状态
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReconcileConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 实际状态与最后一次下发不一致时写入报警
+    #[serde(default = "default_alarm_on_mismatch")]
+    pub alarm_on_mismatch: bool,
+    /// 报警严重程度：critical / major / minor / warning
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            alarm_on_mismatch: default_alarm_on_mismatch(),
+            severity: default_severity(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_alarm_on_mismatch() -> bool {
+    true
+}
+
+fn default_severity() -> String {
+    "major".to_string()
+}
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RemoteAccessConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 代理监听地址，端口由系统分配；默认只监听本机，需要厂商接入时配置为 VPN 网卡地址
+    #[serde(default = "default_bind_host")]
+    pub bind_host: String,
+    /// 允许代理的目标网段
+    #[serde(default)]
+    pub allowed_targets: Vec<String>,
+    #[serde(default = "default_max_duration_minutes")]
+    pub max_duration_minutes: u32,
+    #[serde(default = "default_recording_dir")]
+    pub recording_dir: String,
+}
+
+impl Default for RemoteAccessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_host: default_bind_host(),
+            allowed_targets: Vec::new(),
+            max_duration_minutes: default_max_duration_minutes(),
+            recording_dir: default_recording_dir(),
+        }
+    }
+}
+
+fn default_bind_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_max_duration_minutes() -> u32 {
+    120
+}
+
+fn default_recording_dir() -> String {
+    "recordings".to_string()
+}
@@ -0,0 +1,159 @@
+use serde::Deserialize;
+
+/// 定期合规报表
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 生成日报
+    #[serde(default = "default_true")]
+    pub daily: bool,
+    /// 每周一生成上一周的周报
+    #[serde(default = "default_true")]
+    pub weekly: bool,
+    /// 输出格式：pdf / xlsx
+    #[serde(default = "default_formats")]
+    pub formats: Vec<String>,
+    /// 每天该时刻（`timezone.default` 时区的本地小时）之后生成前一天的报表，留出补录数据的时间
+    #[serde(default = "default_generate_hour")]
+    pub generate_hour: u32,
+    /// 计算排放量的流量指标（m³/h）
+    #[serde(default = "default_flow_metric")]
+    pub flow_metric: String,
+    #[serde(default = "default_ph_metric")]
+    pub ph_metric: String,
+    /// pH 允许范围，超出即为超标
+    #[serde(default = "default_ph_min")]
+    pub ph_min: f64,
+    #[serde(default = "default_ph_max")]
+    pub ph_max: f64,
+    /// PDF 使用的 TrueType 字体，需包含中文字形；未配置或加载失败时使用内置字体
+    #[serde(default)]
+    pub pdf_font: Option<String>,
+    #[serde(default)]
+    pub storage: ReportStorageConfig,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            daily: true,
+            weekly: true,
+            formats: default_formats(),
+            generate_hour: default_generate_hour(),
+            flow_metric: default_flow_metric(),
+            ph_metric: default_ph_metric(),
+            ph_min: default_ph_min(),
+            ph_max: default_ph_max(),
+            pdf_font: None,
+            storage: ReportStorageConfig::default(),
+        }
+    }
+}
+
+/// 报表文件存储
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReportStorageConfig {
+    /// local / s3
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    /// 本地存储目录
+    #[serde(default = "default_directory")]
+    pub directory: String,
+    #[serde(default)]
+    pub s3: S3Config,
+}
+
+impl Default for ReportStorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_backend(),
+            directory: default_directory(),
+            s3: S3Config::default(),
+        }
+    }
+}
+
+/// S3 兼容对象存储（AWS S3、MinIO 等），按路径风格访问
+#[derive(Deserialize, Debug, Clone)]
+pub struct S3Config {
+    /// 例如 `https://s3.cn-north-1.amazonaws.com.cn` 或 `http://minio:9000`
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub access_key: String,
+    #[serde(default)]
+    pub secret_key: String,
+    /// 对象键前缀
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: default_region(),
+            bucket: String::new(),
+            access_key: String::new(),
+            secret_key: String::new(),
+            prefix: default_prefix(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_formats() -> Vec<String> {
+    vec!["pdf".to_string(), "xlsx".to_string()]
+}
+
+fn default_generate_hour() -> u32 {
+    1
+}
+
+fn default_flow_metric() -> String {
+    "flow".to_string()
+}
+
+fn default_ph_metric() -> String {
+    "ph".to_string()
+}
+
+fn default_ph_min() -> f64 {
+    6.0
+}
+
+fn default_ph_max() -> f64 {
+    9.0
+}
+
+fn default_backend() -> String {
+    "local".to_string()
+}
+
+fn default_directory() -> String {
+    "reports".to_string()
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_prefix() -> String {
+    "reports/".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
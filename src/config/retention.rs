@@ -0,0 +1,42 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 测量值保留天数
+    #[serde(default = "default_measurement_days")]
+    pub measurement_days: u32,
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 测量值按月分区（仅 PostgreSQL），启动时把已有的表转为分区表
+    #[serde(default)]
+    pub partition_by_month: bool,
+    /// 提前创建的月分区数（含当月）
+    #[serde(default = "default_partitions_ahead")]
+    pub partitions_ahead: u32,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            measurement_days: default_measurement_days(),
+            check_interval_secs: default_check_interval_secs(),
+            partition_by_month: false,
+            partitions_ahead: default_partitions_ahead(),
+        }
+    }
+}
+
+fn default_measurement_days() -> u32 {
+    365
+}
+
+fn default_check_interval_secs() -> u64 {
+    3600
+}
+
+fn default_partitions_ahead() -> u32 {
+    3
+}
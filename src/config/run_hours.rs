@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+/// 设备运行小时累计
+#[derive(Deserialize, Debug, Clone)]
+pub struct RunHoursConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 持续运行的设备多久结算一次运行小时数
+    #[serde(default = "default_checkpoint_secs")]
+    pub checkpoint_secs: u64,
+}
+
+impl Default for RunHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            checkpoint_secs: default_checkpoint_secs(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_checkpoint_secs() -> u64 {
+    300
+}
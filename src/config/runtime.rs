@@ -0,0 +1,49 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RuntimeConfig {
+    /// 异步工作线程数，缺省为 CPU 核数
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    /// 阻塞线程池上限，硬件读写和文件 IO 在这里执行
+    #[serde(default = "default_max_blocking_threads")]
+    pub max_blocking_threads: usize,
+    /// 阻塞线程空闲多久后回收
+    #[serde(default = "default_blocking_keep_alive_secs")]
+    pub blocking_keep_alive_secs: u64,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: None,
+            max_blocking_threads: default_max_blocking_threads(),
+            blocking_keep_alive_secs: default_blocking_keep_alive_secs(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// 按配置构建多线程运行时
+    pub fn build(&self) -> std::io::Result<tokio::runtime::Runtime> {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads.max(1));
+        }
+        builder
+            .max_blocking_threads(self.max_blocking_threads.max(1))
+            .thread_keep_alive(Duration::from_secs(self.blocking_keep_alive_secs))
+            .thread_name("guolu-worker")
+            .enable_all()
+            .build()
+    }
+}
+
+fn default_max_blocking_threads() -> usize {
+    512
+}
+
+fn default_blocking_keep_alive_secs() -> u64 {
+    10
+}
@@ -0,0 +1,102 @@
+use crate::config::modbus::RegisterKind;
+use serde::Deserialize;
+
+/// 通信中断时的安全状态
+#[derive(Deserialize, Debug, Clone)]
+pub struct SafeStateConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 检查遥测是否中断的间隔
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 报警严重程度：critical / major / minor / warning
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    #[serde(default)]
+    pub actuators: Vec<SafeStateActuator>,
+}
+
+impl Default for SafeStateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            check_interval_secs: default_check_interval_secs(),
+            severity: default_severity(),
+            actuators: Vec::new(),
+        }
+    }
+}
+
+/// 单个执行器的安全状态策略
+#[derive(Deserialize, Debug, Clone)]
+pub struct SafeStateActuator {
+    pub name: String,
+    pub target: ActuatorTarget,
+    /// 控制该执行器所依赖的传感器，任一个超时未上报即视为遥测中断
+    pub sensors: Vec<SensorRef>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    pub policy: SafePolicy,
+    /// 下发的值，默认 open 为 1（PWM 为 100%），close / stop 为 0
+    #[serde(default)]
+    pub value: Option<f64>,
+}
+
+/// 执行器
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActuatorTarget {
+    /// `gpio.outputs` 中的输出名称
+    Gpio { output: String },
+    /// `modbus.devices[].writable` 中的寄存器
+    Modbus {
+        device_id: i32,
+        #[serde(default)]
+        register: RegisterKind,
+        address: u16,
+    },
+    Pwm { device_id: i32 },
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SensorRef {
+    pub device_id: i32,
+    pub metric_type: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SafePolicy {
+    /// 保持当前输出，只记录和报警
+    Hold,
+    Open,
+    Close,
+    Stop,
+}
+
+impl SafePolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SafePolicy::Hold => "hold",
+            SafePolicy::Open => "open",
+            SafePolicy::Close => "close",
+            SafePolicy::Stop => "stop",
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_check_interval_secs() -> u64 {
+    10
+}
+
+fn default_severity() -> String {
+    "critical".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CorsConfig {
+    #[serde(default = "default_cors_enabled")]
+    pub enabled: bool,
+    /// 允许的来源，`*` 表示任意来源
+    #[serde(default = "default_allowed_origins")]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default = "default_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+    /// 是否允许携带凭据，来源为 `*` 时无效
+    #[serde(default)]
+    pub allow_credentials: bool,
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cors_enabled(),
+            allowed_origins: default_allowed_origins(),
+            allowed_methods: default_allowed_methods(),
+            allowed_headers: default_allowed_headers(),
+            allow_credentials: false,
+            max_age_secs: default_max_age_secs(),
+        }
+    }
+}
+
+fn default_cors_enabled() -> bool {
+    true
+}
+
+fn default_allowed_origins() -> Vec<String> {
+    vec!["http://localhost:5173".to_string()]
+}
+
+fn default_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_allowed_headers() -> Vec<String> {
+    ["content-type", "authorization", "x-api-key", "x-operator", "x-request-id"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()
+}
+
+fn default_max_age_secs() -> u64 {
+    3600
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SecurityHeadersConfig {
+    #[serde(default = "default_headers_enabled")]
+    pub enabled: bool,
+    /// 仅在 HTTPS 反向代理之后部署时开启
+    #[serde(default)]
+    pub hsts: bool,
+    #[serde(default = "default_frame_options")]
+    pub frame_options: String,
+    #[serde(default = "default_referrer_policy")]
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_headers_enabled(),
+            hsts: false,
+            frame_options: default_frame_options(),
+            referrer_policy: default_referrer_policy(),
+        }
+    }
+}
+
+fn default_headers_enabled() -> bool {
+    true
+}
+
+fn default_frame_options() -> String {
+    "DENY".to_string()
+}
+
+fn default_referrer_policy() -> String {
+    "no-referrer".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKeyConfig {
+    /// 数据上报接口是否要求 `X-Api-Key`
+    #[serde(default = "default_require_for_ingest")]
+    pub require_for_ingest: bool,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            require_for_ingest: default_require_for_ingest(),
+        }
+    }
+}
+
+fn default_require_for_ingest() -> bool {
+    true
+}
+
+/// 网络区域访问策略
+#[derive(Deserialize, Serialize, Debug, Clone, Default, ToSchema)]
+pub struct NetworkPolicyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 区域名称 -> CIDR 列表，例如 "ot" -> ["10.20.0.0/16"]
+    #[serde(default)]
+    pub zones: BTreeMap<String, Vec<String>>,
+    /// 按顺序匹配，第一条命中的规则生效；未命中任何规则的请求放行
+    #[serde(default)]
+    pub rules: Vec<NetworkRuleConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct NetworkRuleConfig {
+    pub path_prefix: String,
+    /// 为空表示所有方法
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// 允许访问的区域
+    pub zones: Vec<String>,
+}
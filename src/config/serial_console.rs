@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SerialConsoleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许远程访问的串口，只有列在这里的串口可以打开
+    #[serde(default)]
+    pub ports: Vec<SerialPortConfig>,
+    /// 无数据收发超过该时长自动断开
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    #[serde(default = "default_recording_dir")]
+    pub recording_dir: String,
+}
+
+impl Default for SerialConsoleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ports: Vec::new(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            recording_dir: default_recording_dir(),
+        }
+    }
+}
+
+/// 单个串口的参数
+#[derive(Deserialize, Debug, Clone)]
+pub struct SerialPortConfig {
+    /// 接口中使用的名称，例如 `analyzer-1`
+    pub name: String,
+    /// 设备路径，例如 `/dev/ttyUSB0`
+    pub path: String,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: u32,
+    #[serde(default = "default_data_bits")]
+    pub data_bits: u8,
+    /// none / odd / even
+    #[serde(default = "default_parity")]
+    pub parity: String,
+    #[serde(default = "default_stop_bits")]
+    pub stop_bits: u8,
+    /// none / software / hardware
+    #[serde(default = "default_flow_control")]
+    pub flow_control: String,
+    /// 读取一帧的超时时间
+    #[serde(default = "default_read_timeout_ms")]
+    pub read_timeout_ms: u64,
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    900
+}
+
+fn default_recording_dir() -> String {
+    "recordings".to_string()
+}
+
+fn default_baud_rate() -> u32 {
+    9600
+}
+
+fn default_data_bits() -> u8 {
+    8
+}
+
+fn default_parity() -> String {
+    "none".to_string()
+}
+
+fn default_stop_bits() -> u8 {
+    1
+}
+
+fn default_flow_control() -> String {
+    "none".to_string()
+}
+
+fn default_read_timeout_ms() -> u64 {
+    1000
+}
@@ -6,6 +6,9 @@ pub struct ServerConfig {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// 在 `/admin/` 提供内置管理后台
+    #[serde(default = "default_admin_ui")]
+    pub admin_ui: bool,
 }
 
 impl Default for ServerConfig {
@@ -13,6 +16,7 @@ impl Default for ServerConfig {
         Self {
             host: default_host(),
             port: default_port(),
+            admin_ui: default_admin_ui(),
         }
     }
 }
@@ -25,6 +29,10 @@ fn default_port() -> u16 {
     3000
 }
 
+fn default_admin_ui() -> bool {
+    true
+}
+
 impl ServerConfig {
     pub fn address(&self) -> String {
         format!("{}:{}", self.host, self.port)
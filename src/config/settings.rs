@@ -0,0 +1,207 @@
+use crate::config::adc::AdcConfig;
+use crate::config::alarm::AlarmConfig;
+use crate::config::alarm_forward::AlarmForwardConfig;
+use crate::config::api_usage::ApiUsageConfig;
+use crate::config::bundle::BundleConfig;
+use crate::config::cache::CacheConfig;
+use crate::config::calibration::CalibrationConfig;
+use crate::config::can::CanConfig;
+use crate::config::change_control::ChangeControlConfig;
+use crate::config::compression::CompressionConfig;
+use crate::config::control::ControlConfig;
+use crate::config::database::DatabaseConfig;
+use crate::config::demo::DemoConfig;
+use crate::config::dosing::DosingConfig;
+use crate::config::energy::EnergyConfig;
+use crate::config::event_bus::EventBusConfig;
+use crate::config::flow_total::FlowTotalConfig;
+use crate::config::gpio::GpioConfig;
+use crate::config::kafka::KafkaConfig;
+use crate::config::maintenance::MaintenanceConfig;
+use crate::config::migration::MigrationConfig;
+use crate::config::modbus::ModbusConfig;
+use crate::config::grpc::GrpcConfig;
+use crate::config::mqtt::MqttConfig;
+use crate::config::network::NetworkMonitorConfig;
+use crate::config::opcua::OpcUaConfig;
+use crate::config::permit::PermitConfig;
+use crate::config::pi::PiExportConfig;
+use crate::config::pump::PumpMonitorConfig;
+use crate::config::pump_group::PumpGroupConfig;
+use crate::config::pwm::PwmConfig;
+use crate::config::quality::QualityConfig;
+use crate::config::query_guard::QueryGuardConfig;
+use crate::config::rabbitmq::RabbitMqConfig;
+use crate::config::rate_limit::RateLimitConfig;
+use crate::config::read_only::ReadOnlyConfig;
+use crate::config::reconcile::ReconcileConfig;
+use crate::config::remote_access::RemoteAccessConfig;
+use crate::config::report::ReportConfig;
+use crate::config::retention::RetentionConfig;
+use crate::config::run_hours::RunHoursConfig;
+use crate::config::safe_state::SafeStateConfig;
+use crate::config::runtime::RuntimeConfig;
+use crate::config::security::{ApiKeyConfig, CorsConfig, NetworkPolicyConfig, SecurityHeadersConfig};
+use crate::config::serial_console::SerialConsoleConfig;
+use crate::config::server::ServerConfig;
+use crate::config::snmp::SnmpConfig;
+use crate::config::summary::{DailySummaryConfig, HourlyRollupConfig};
+use crate::config::system::SystemMonitorConfig;
+use crate::config::tenancy::TenancyConfig;
+use crate::config::timeseries::TimeSeriesConfig;
+use crate::config::timezone::TimezoneConfig;
+use crate::config::trend::TrendConfig;
+use serde::Deserialize;
+use std::path::Path;
+
+/// 配置文件路径的环境变量
+const CONFIG_PATH_ENV: &str = "GUOLU_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = "config.json";
+
+/// 应用全局配置
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Settings {
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub demo: DemoConfig,
+    #[serde(default)]
+    pub migration: MigrationConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    #[serde(default)]
+    pub rabbitmq: RabbitMqConfig,
+    #[serde(default)]
+    pub event_bus: EventBusConfig,
+    #[serde(default)]
+    pub kafka: KafkaConfig,
+    #[serde(default)]
+    pub opcua: OpcUaConfig,
+    #[serde(default)]
+    pub snmp: SnmpConfig,
+    #[serde(default)]
+    pub can: CanConfig,
+    #[serde(default)]
+    pub adc: AdcConfig,
+    #[serde(default)]
+    pub gpio: GpioConfig,
+    #[serde(default)]
+    pub pwm: PwmConfig,
+    #[serde(default)]
+    pub modbus: ModbusConfig,
+    #[serde(default)]
+    pub reconcile: ReconcileConfig,
+    #[serde(default)]
+    pub safe_state: SafeStateConfig,
+    #[serde(default)]
+    pub network_monitor: NetworkMonitorConfig,
+    #[serde(default)]
+    pub system_monitor: SystemMonitorConfig,
+    #[serde(default)]
+    pub pump_monitor: PumpMonitorConfig,
+    #[serde(default)]
+    pub daily_summary: DailySummaryConfig,
+    #[serde(default)]
+    pub hourly_rollup: HourlyRollupConfig,
+    #[serde(default)]
+    pub report: ReportConfig,
+    #[serde(default)]
+    pub permit: PermitConfig,
+    #[serde(default)]
+    pub energy: EnergyConfig,
+    #[serde(default)]
+    pub flow_total: FlowTotalConfig,
+    #[serde(default)]
+    pub dosing: DosingConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    #[serde(default)]
+    pub run_hours: RunHoursConfig,
+    #[serde(default)]
+    pub pump_group: PumpGroupConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub timeseries: TimeSeriesConfig,
+    #[serde(default)]
+    pub pi_export: PiExportConfig,
+    #[serde(default)]
+    pub alarm: AlarmConfig,
+    #[serde(default)]
+    pub alarm_forward: AlarmForwardConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub quality: QualityConfig,
+    #[serde(default)]
+    pub query_guard: QueryGuardConfig,
+    #[serde(default)]
+    pub trend: TrendConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub api_usage: ApiUsageConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub security_headers: SecurityHeadersConfig,
+    #[serde(default)]
+    pub api_keys: ApiKeyConfig,
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+    #[serde(default)]
+    pub timezone: TimezoneConfig,
+    #[serde(default)]
+    pub network_policy: NetworkPolicyConfig,
+    #[serde(default)]
+    pub remote_access: RemoteAccessConfig,
+    #[serde(default)]
+    pub serial_console: SerialConsoleConfig,
+    #[serde(default)]
+    pub change_control: ChangeControlConfig,
+    #[serde(default)]
+    pub config_bundle: BundleConfig,
+    #[serde(default)]
+    pub read_only: ReadOnlyConfig,
+}
+
+impl Settings {
+    /// 加载配置
+    ///
+    /// 优先读取 `GUOLU_CONFIG` 指定的文件，其次是当前目录下的 `config.json`；
+    /// 文件不存在时使用默认配置。`DATABASE_URL` 环境变量会覆盖文件中的数据库地址。
+    pub fn load() -> anyhow::Result<Self> {
+        let path = std::env::var(CONFIG_PATH_ENV).unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        let mut settings = Self::from_file(&path)?;
+
+        if let Ok(url) = std::env::var("DATABASE_URL") {
+            settings.database.url = url;
+        }
+
+        Ok(settings)
+    }
+
+    /// 从 JSON 文件读取配置
+    pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let settings = serde_json::from_str(&content)?;
+        Ok(settings)
+    }
+}
@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SnmpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub targets: Vec<SnmpTarget>,
+}
+
+/// 一台被轮询的设备（RTU、UPS 等）
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnmpTarget {
+    pub name: String,
+    /// `ip:port`，端口通常为 161
+    pub address: String,
+    /// 对应的设备，不可达报警与测量值都记在该设备下
+    pub device_id: i32,
+    /// v2c / v3
+    #[serde(default = "default_version")]
+    pub version: String,
+    /// v2c 团体名
+    #[serde(default = "default_community")]
+    pub community: String,
+    /// v3 用户名及认证/加密参数
+    #[serde(default)]
+    pub v3: Option<SnmpV3Auth>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 连续失败多少次后报警
+    #[serde(default = "default_unreachable_after")]
+    pub unreachable_after: u32,
+    #[serde(default)]
+    pub oids: Vec<SnmpOidMapping>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnmpV3Auth {
+    pub username: String,
+    /// md5 / sha1 / sha256
+    #[serde(default = "default_auth_protocol")]
+    pub auth_protocol: String,
+    pub auth_password: String,
+    /// des / aes128，为空时只认证不加密
+    #[serde(default)]
+    pub privacy_protocol: Option<String>,
+    #[serde(default)]
+    pub privacy_password: Option<String>,
+}
+
+/// OID 到指标的映射
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnmpOidMapping {
+    /// 例如 `1.3.6.1.2.1.33.1.2.4.0`
+    pub oid: String,
+    pub metric_type: String,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_version() -> String {
+    "v2c".to_string()
+}
+
+fn default_community() -> String {
+    "public".to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_unreachable_after() -> u32 {
+    3
+}
+
+fn default_auth_protocol() -> String {
+    "sha1".to_string()
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
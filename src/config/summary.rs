@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DailySummaryConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 检查是否有未汇总日期的间隔
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 启动及每次检查时回看的天数，缺失的日期会补算
+    #[serde(default = "default_backfill_days")]
+    pub backfill_days: u32,
+}
+
+impl Default for DailySummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            check_interval_secs: default_check_interval_secs(),
+            backfill_days: default_backfill_days(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_check_interval_secs() -> u64 {
+    3600
+}
+
+fn default_backfill_days() -> u32 {
+    7
+}
+
+/// 小时汇总
+#[derive(Deserialize, Debug, Clone)]
+pub struct HourlyRollupConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 检查是否有新的整小时需要汇总的间隔
+    #[serde(default = "default_rollup_interval_secs")]
+    pub interval_secs: u64,
+    /// 首次启动时回看的小时数
+    #[serde(default = "default_backfill_hours")]
+    pub backfill_hours: u32,
+}
+
+impl Default for HourlyRollupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_rollup_interval_secs(),
+            backfill_hours: default_backfill_hours(),
+        }
+    }
+}
+
+fn default_rollup_interval_secs() -> u64 {
+    300
+}
+
+fn default_backfill_hours() -> u32 {
+    168
+}
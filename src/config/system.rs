@@ -0,0 +1,64 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SystemMonitorConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 1 分钟平均负载除以 CPU 核数的上限
+    #[serde(default = "default_max_load_per_cpu")]
+    pub max_load_per_cpu: f64,
+    #[serde(default = "default_max_memory_pct")]
+    pub max_memory_pct: f64,
+    /// 数据库所在磁盘的使用率上限
+    #[serde(default = "default_max_disk_pct")]
+    pub max_disk_pct: f64,
+    #[serde(default = "default_max_temperature_c")]
+    pub max_temperature_c: f64,
+    /// SoC 温度文件，内容为千分之一摄氏度
+    #[serde(default = "default_thermal_path")]
+    pub thermal_path: String,
+}
+
+impl Default for SystemMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            max_load_per_cpu: default_max_load_per_cpu(),
+            max_memory_pct: default_max_memory_pct(),
+            max_disk_pct: default_max_disk_pct(),
+            max_temperature_c: default_max_temperature_c(),
+            thermal_path: default_thermal_path(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_max_load_per_cpu() -> f64 {
+    2.0
+}
+
+fn default_max_memory_pct() -> f64 {
+    90.0
+}
+
+fn default_max_disk_pct() -> f64 {
+    90.0
+}
+
+fn default_max_temperature_c() -> f64 {
+    80.0
+}
+
+fn default_thermal_path() -> String {
+    "/sys/class/thermal/thermal_zone0/temp".to_string()
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+/// 多租户
+///
+/// 开启后所有接口都须携带 `X-Api-Key`，按 Key 所属组织隔离数据；未绑定组织的 Key 为平台管理 Key。
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TenancyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
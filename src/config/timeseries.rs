@@ -0,0 +1,128 @@
+use serde::Deserialize;
+
+/// 时序数据存储
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimeSeriesConfig {
+    /// sql / influxdb / timescaledb，sql 直接在测量值表上聚合
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    #[serde(default)]
+    pub influxdb: InfluxDbConfig,
+    #[serde(default)]
+    pub timescaledb: TimescaleDbConfig,
+}
+
+impl Default for TimeSeriesConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_backend(),
+            influxdb: InfluxDbConfig::default(),
+            timescaledb: TimescaleDbConfig::default(),
+        }
+    }
+}
+
+/// InfluxDB 2.x
+#[derive(Deserialize, Debug, Clone)]
+pub struct InfluxDbConfig {
+    /// 服务地址，例如 `http://localhost:8086`
+    #[serde(default = "default_influx_url")]
+    pub url: String,
+    #[serde(default)]
+    pub org: String,
+    #[serde(default = "default_bucket")]
+    pub bucket: String,
+    /// API Token，为空时不认证
+    #[serde(default)]
+    pub token: Option<String>,
+    /// 写入的 measurement 名称
+    #[serde(default = "default_measurement")]
+    pub measurement: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 无新数据时的轮询间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 每次写入的最多测量值条数
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+    /// 写入失败后的最长重试间隔，间隔从 `interval_secs` 开始逐次加倍
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// 已写入位置的保存文件，重启后从该位置继续
+    #[serde(default = "default_state_path")]
+    pub state_path: String,
+}
+
+impl Default for InfluxDbConfig {
+    fn default() -> Self {
+        Self {
+            url: default_influx_url(),
+            org: String::new(),
+            bucket: default_bucket(),
+            token: None,
+            measurement: default_measurement(),
+            timeout_secs: default_timeout_secs(),
+            interval_secs: default_interval_secs(),
+            batch_size: default_batch_size(),
+            max_backoff_secs: default_max_backoff_secs(),
+            state_path: default_state_path(),
+        }
+    }
+}
+
+/// TimescaleDB，须使用 PostgreSQL 数据库并已安装扩展
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimescaleDbConfig {
+    /// 每个 chunk 覆盖的天数
+    #[serde(default = "default_chunk_interval_days")]
+    pub chunk_interval_days: u32,
+}
+
+impl Default for TimescaleDbConfig {
+    fn default() -> Self {
+        Self {
+            chunk_interval_days: default_chunk_interval_days(),
+        }
+    }
+}
+
+fn default_backend() -> String {
+    "sql".to_string()
+}
+
+fn default_influx_url() -> String {
+    "http://localhost:8086".to_string()
+}
+
+fn default_bucket() -> String {
+    "guolu".to_string()
+}
+
+fn default_measurement() -> String {
+    "measurements".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_batch_size() -> u64 {
+    5000
+}
+
+fn default_max_backoff_secs() -> u64 {
+    300
+}
+
+fn default_state_path() -> String {
+    "influx_export.redb".to_string()
+}
+
+fn default_chunk_interval_days() -> u32 {
+    7
+}
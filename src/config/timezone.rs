@@ -0,0 +1,21 @@
+use serde::Deserialize;
+
+/// 时区
+///
+/// 厂站未设置时区时使用 `default`；报表、数据保留等不区分厂站的任务也按该时区划分日界。
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimezoneConfig {
+    /// IANA 时区名，例如 `Asia/Shanghai`
+    #[serde(default = "default_zone")]
+    pub default: String,
+}
+
+impl Default for TimezoneConfig {
+    fn default() -> Self {
+        Self { default: default_zone() }
+    }
+}
+
+fn default_zone() -> String {
+    "UTC".to_string()
+}
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+/// `/trend` 的分辨率切换阈值
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrendConfig {
+    /// 时间范围不超过该小时数时返回原始数据
+    #[serde(default = "default_raw_max_hours")]
+    pub raw_max_hours: u64,
+    /// 原始数据点数上限，超出时改用小时聚合
+    #[serde(default = "default_max_raw_points")]
+    pub max_raw_points: u64,
+    /// 时间范围不超过该天数时返回小时聚合，更长时返回日聚合
+    #[serde(default = "default_hourly_max_days")]
+    pub hourly_max_days: u64,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            raw_max_hours: default_raw_max_hours(),
+            max_raw_points: default_max_raw_points(),
+            hourly_max_days: default_hourly_max_days(),
+        }
+    }
+}
+
+fn default_raw_max_hours() -> u64 {
+    24
+}
+
+fn default_max_raw_points() -> u64 {
+    10_000
+}
+
+fn default_hourly_max_days() -> u64 {
+    60
+}
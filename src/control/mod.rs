@@ -0,0 +1,309 @@
+//! PID 控制回路
+//!
+//! 每个回路读取一台设备的一个测量值，经 PID 计算后通过 PWM 通道或 Modbus 保持寄存器输出，
+//! 例如按 pH 调节投酸泵转速。回路按固定周期执行；手动模式下输出保持操作员给定的值，
+//! 切换到自动时从当前输出无扰开始。测量值缺失或过期时保持输出不变并停止积分。
+//! 回路的输出对象不能同时由自动模式的加药泵控制。
+
+pub mod pid;
+
+use crate::app_state::AppState;
+use crate::config::control::ControlConfig;
+use crate::config::modbus::RegisterKind;
+use crate::models::control_loop::{
+    Column as ControlLoopColumn, Entity as ControlLoopEntity, Model as ControlLoop, ACTION_DIRECT,
+    ACTION_REVERSE, MODE_AUTO, MODE_MANUAL, OUTPUTS, OUTPUT_MODBUS, OUTPUT_PWM,
+};
+use crate::models::dosing_pump::{self, Column as DosingPumpColumn, Entity as DosingPumpEntity};
+use crate::services::latest;
+use crate::services::modbus_write::{self, WriteRequest, WriteSource};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use pid::{Pid, PidParams};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration as StdDuration, Instant};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// 输出变化小于量程的该比例时不重新下发
+const OUTPUT_TOLERANCE: f64 = 0.001;
+
+pub fn validate(control_loop: &ControlLoop) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("控制回路参数无效: {}", msg).into()));
+
+    if control_loop.name.trim().is_empty() || control_loop.input_metric.trim().is_empty() {
+        return invalid("名称和测量指标不能为空");
+    }
+    if !OUTPUTS.contains(&control_loop.output_kind.as_str()) {
+        return invalid("输出方式须为 pwm / modbus");
+    }
+    if control_loop.output_kind == OUTPUT_MODBUS
+        && !control_loop.output_address.is_some_and(|a| (0..=u16::MAX as i32).contains(&a))
+    {
+        return invalid("Modbus 输出须指定有效的寄存器地址");
+    }
+    if !matches!(control_loop.action.as_str(), ACTION_DIRECT | ACTION_REVERSE) {
+        return invalid("作用方向须为 direct / reverse");
+    }
+    if !matches!(control_loop.mode.as_str(), MODE_MANUAL | MODE_AUTO) {
+        return invalid("运行模式须为 manual / auto");
+    }
+    let gains = [control_loop.setpoint, control_loop.kp, control_loop.ki, control_loop.kd];
+    if !gains.iter().all(|v| v.is_finite()) || gains[1..].iter().any(|v| *v < 0.0) {
+        return invalid("设定值须为有效数值，增益不能为负");
+    }
+    if !(control_loop.output_min.is_finite()
+        && control_loop.output_max.is_finite()
+        && control_loop.output_min < control_loop.output_max)
+    {
+        return invalid("输出下限须小于上限");
+    }
+    if !(control_loop.output_min..=control_loop.output_max).contains(&control_loop.output) {
+        return invalid("输出须在上下限之间");
+    }
+    Ok(())
+}
+
+/// 输出对象已由自动模式的加药泵控制时拒绝，避免两个控制器争抢同一执行器
+pub async fn ensure_output_free(
+    conn: &DatabaseConnection,
+    control_loop: &ControlLoop,
+) -> Result<(), AppError> {
+    let mut query = DosingPumpEntity::find()
+        .filter(DosingPumpColumn::Mode.eq(dosing_pump::MODE_AUTO))
+        .filter(DosingPumpColumn::Control.eq(control_loop.output_kind.as_str()))
+        .filter(DosingPumpColumn::ControlDeviceId.eq(control_loop.output_device_id));
+    if control_loop.output_kind == OUTPUT_MODBUS {
+        query = query.filter(DosingPumpColumn::ControlAddress.eq(control_loop.output_address));
+    }
+    let pump = query.one(conn).await.map_err(|_| AppError::InternalError)?;
+    match pump {
+        Some(pump) => Err(AppError::InvalidInput(
+            format!("输出对象已由自动模式的加药泵 {} 控制", pump.name).into(),
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn params(control_loop: &ControlLoop) -> PidParams {
+    PidParams {
+        setpoint: control_loop.setpoint,
+        kp: control_loop.kp,
+        ki: control_loop.ki,
+        kd: control_loop.kd,
+        output_min: control_loop.output_min,
+        output_max: control_loop.output_max,
+        direct: control_loop.action == ACTION_DIRECT,
+    }
+}
+
+/// 下发输出并记录，返回更新后的回路
+pub async fn command(
+    state: &AppState,
+    control_loop: &ControlLoop,
+    output: f64,
+    source: WriteSource,
+    operator: Option<String>,
+) -> Result<ControlLoop, AppError> {
+    if !(control_loop.output_min..=control_loop.output_max).contains(&output) {
+        return Err(AppError::InvalidInput(
+            format!("输出须在 {} 到 {} 之间", control_loop.output_min, control_loop.output_max).into(),
+        ));
+    }
+    let conn = state.db.get_connection();
+    let output = match control_loop.output_kind.as_str() {
+        OUTPUT_PWM => {
+            state.pwm.set(control_loop.output_device_id, output, None, false).await?.target_duty_cycle
+        }
+        _ => {
+            let request = WriteRequest {
+                device_id: control_loop.output_device_id,
+                kind: RegisterKind::Holding,
+                address: control_loop.output_address.unwrap_or_default() as u16,
+                value: output,
+                source,
+                operator,
+                override_interlocks: false,
+                reason: Some(format!("控制回路 {}", control_loop.name)),
+            };
+            let record =
+                modbus_write::write(conn, &state.cache, &state.settings.modbus, request).await?;
+            record.written_value.unwrap_or(output)
+        }
+    };
+
+    let mut active_model = control_loop.clone().into_active_model();
+    active_model.output = Set(output);
+    active_model.updated_at = Set(Utc::now());
+    active_model.update(conn).await.map_err(|_| AppError::InternalError)
+}
+
+/// 回路运行状态
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct LoopStatus {
+    pub loop_id: i32,
+    pub mode: String,
+    /// 最近一次使用的测量值
+    pub process_value: Option<f64>,
+    pub error: Option<f64>,
+    /// 积分项（输出单位）
+    pub integral: f64,
+    pub output: f64,
+    /// 测量值缺失或过期，输出保持不变
+    pub input_stale: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct LoopState {
+    /// 手动模式下为空，切换到自动时从当前输出初始化
+    pid: Option<Pid>,
+    status: LoopStatus,
+}
+
+/// 各回路的 PID 状态
+#[derive(Debug, Clone, Default)]
+pub struct ControlRuntime {
+    loops: Arc<Mutex<HashMap<i32, LoopState>>>,
+}
+
+impl ControlRuntime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> Vec<LoopStatus> {
+        let loops = self.loops.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statuses: Vec<LoopStatus> = loops.values().map(|l| l.status.clone()).collect();
+        statuses.sort_by_key(|s| s.loop_id);
+        statuses
+    }
+
+    pub fn get(&self, loop_id: i32) -> Option<LoopStatus> {
+        let loops = self.loops.lock().unwrap_or_else(|e| e.into_inner());
+        loops.get(&loop_id).map(|l| l.status.clone())
+    }
+
+    /// 计算一个回路的新输出，`None` 表示保持当前输出
+    fn step(
+        &self,
+        control_loop: &ControlLoop,
+        process_value: Option<f64>,
+        dt: f64,
+        now: DateTime<Utc>,
+    ) -> Option<f64> {
+        let mut loops = self.loops.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = loops.entry(control_loop.id).or_insert_with(|| LoopState {
+            pid: None,
+            status: LoopStatus { loop_id: control_loop.id, ..Default::default() },
+        });
+        let status = &mut entry.status;
+        status.mode = control_loop.mode.clone();
+        status.output = control_loop.output;
+        status.input_stale = process_value.is_none();
+        status.updated_at = Some(now);
+
+        if control_loop.mode != MODE_AUTO {
+            entry.pid = None;
+            return None;
+        }
+        let process_value = process_value?;
+        let params = params(control_loop);
+        // 切换到自动后的首个周期只设定积分项，输出保持当前值
+        let dt = if entry.pid.is_some() { dt } else { 0.0 };
+        let pid = entry
+            .pid
+            .get_or_insert_with(|| Pid::bumpless(&params, process_value, control_loop.output));
+        let output = pid.update(&params, process_value, dt);
+        status.process_value = Some(process_value);
+        status.error = Some(params.error(process_value));
+        status.integral = pid.integral();
+        status.output = output;
+        Some(output)
+    }
+
+    /// 删除已不存在或停用的回路
+    fn retain(&self, ids: &[i32]) {
+        let mut loops = self.loops.lock().unwrap_or_else(|e| e.into_inner());
+        loops.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// 回路的当前测量值，缺失或过期时为 `None`
+async fn process_value(
+    state: &AppState,
+    config: &ControlConfig,
+    control_loop: &ControlLoop,
+    now: DateTime<Utc>,
+) -> Result<Option<f64>, AppError> {
+    let conn = state.db.get_connection();
+    let value = match latest::for_device(conn, &state.cache, control_loop.device_id).await {
+        Ok(latest) => latest.values.get(&control_loop.input_metric).cloned(),
+        Err(AppError::NotFound) => None,
+        Err(e) => return Err(e),
+    };
+    let max_age = Duration::seconds(config.input_max_age_secs as i64);
+    Ok(value.filter(|v| now - v.timestamp <= max_age).map(|v| v.value))
+}
+
+async fn tick(
+    state: &AppState,
+    runtime: &ControlRuntime,
+    config: &ControlConfig,
+    dt: f64,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let loops = ControlLoopEntity::find()
+        .filter(ControlLoopColumn::Enabled.eq(true))
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    runtime.retain(&loops.iter().map(|l| l.id).collect::<Vec<_>>());
+
+    for control_loop in &loops {
+        let value = if control_loop.mode == MODE_AUTO {
+            process_value(state, config, control_loop, now).await?
+        } else {
+            None
+        };
+        let newly_stale = runtime.get(control_loop.id).is_none_or(|s| !s.input_stale);
+        if control_loop.mode == MODE_AUTO && value.is_none() && newly_stale {
+            warn!("Input for control loop {} is missing or stale, holding output", control_loop.name);
+        }
+        let Some(output) = runtime.step(control_loop, value, dt, now) else {
+            continue;
+        };
+        let span = control_loop.output_max - control_loop.output_min;
+        if (output - control_loop.output).abs() < span * OUTPUT_TOLERANCE {
+            continue;
+        }
+        if let Err(e) = command(state, control_loop, output, WriteSource::Automation, None).await {
+            error!("Failed to command control loop {}: {:?}", control_loop.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 后台任务：按固定周期执行全部自动模式的回路
+pub async fn run(state: Arc<AppState>) {
+    let config = state.settings.control.clone();
+    let runtime = state.control.clone();
+    let mut ticker = tokio::time::interval(StdDuration::from_millis(config.interval_ms.max(100)));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    info!("Control loops running every {} ms", config.interval_ms.max(100));
+    let mut last = Instant::now();
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+        if let Err(e) = tick(&state, &runtime, &config, (now - last).as_secs_f64()).await {
+            error!("Control cycle failed: {:?}", e);
+        }
+        last = now;
+    }
+}
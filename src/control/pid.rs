@@ -0,0 +1,149 @@
+//! PID 算法
+//!
+//! 积分项按输出单位累积（`ki * e * dt`），在线修改积分增益时输出不跳变；微分项取测量值
+//! 的变化率，修改设定值时不产生微分冲击。输出饱和且偏差继续推向饱和方向时停止积分，
+//! 积分项本身也限制在输出范围向两侧各扩展一个量程之内，避免积分饱和，同时留出无扰切换时
+//! 抵消比例项的余量。
+
+/// 回路参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PidParams {
+    pub setpoint: f64,
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    pub output_min: f64,
+    pub output_max: f64,
+    /// 正作用：测量值高于设定值时增大输出
+    pub direct: bool,
+}
+
+impl PidParams {
+    /// 偏差，正值表示需要增大输出
+    pub fn error(&self, process_value: f64) -> f64 {
+        if self.direct {
+            process_value - self.setpoint
+        } else {
+            self.setpoint - process_value
+        }
+    }
+}
+
+/// 单个回路的运行状态
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pid {
+    integral: f64,
+    last_value: Option<f64>,
+}
+
+impl Pid {
+    /// 从当前输出开始的无扰切换：积分项抵消当前测量值下的比例项，切换后首个周期输出不变
+    pub fn bumpless(params: &PidParams, process_value: f64, output: f64) -> Self {
+        let (min, max) = integral_limits(params);
+        Self {
+            integral: (output - params.kp * params.error(process_value)).clamp(min, max),
+            last_value: Some(process_value),
+        }
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// 计算一个周期的输出，`dt` 为距上一周期的秒数
+    pub fn update(&mut self, params: &PidParams, process_value: f64, dt: f64) -> f64 {
+        let (min, max) = (params.output_min, params.output_max);
+        let error = params.error(process_value);
+
+        let rate = match self.last_value {
+            Some(last) if dt > 0.0 => (process_value - last) / dt,
+            _ => 0.0,
+        };
+        self.last_value = Some(process_value);
+        let derivative = if params.direct { rate } else { -rate };
+
+        let proportional = params.kp * error;
+        let differential = params.kd * derivative;
+        let integral = self.integral + params.ki * error * dt;
+        let unclamped = proportional + integral + differential;
+
+        let saturating = (unclamped > max && error > 0.0) || (unclamped < min && error < 0.0);
+        if !saturating {
+            self.integral = integral;
+        }
+        let (integral_min, integral_max) = integral_limits(params);
+        self.integral = self.integral.clamp(integral_min, integral_max);
+
+        (proportional + self.integral + differential).clamp(min, max)
+    }
+}
+
+/// 积分项的范围：输出范围向两侧各扩展一个量程
+fn integral_limits(params: &PidParams) -> (f64, f64) {
+    let span = params.output_max - params.output_min;
+    (params.output_min - span, params.output_max + span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acid_dosing() -> PidParams {
+        PidParams {
+            setpoint: 7.0,
+            kp: 20.0,
+            ki: 1.0,
+            kd: 0.0,
+            output_min: 0.0,
+            output_max: 100.0,
+            direct: true,
+        }
+    }
+
+    #[test]
+    fn test_proportional_and_integral() {
+        let params = acid_dosing();
+        let mut pid = Pid::default();
+        // pH 8.0：比例 20 + 积分 1
+        assert_eq!(pid.update(&params, 8.0, 1.0), 21.0);
+        assert_eq!(pid.update(&params, 8.0, 1.0), 22.0);
+        // 低于设定值且输出已在下限时不再积分
+        assert_eq!(pid.update(&params, 6.5, 2.0), 0.0);
+        assert_eq!(pid.integral(), 2.0);
+    }
+
+    #[test]
+    fn test_anti_windup() {
+        let params = acid_dosing();
+        let mut pid = Pid::default();
+        for _ in 0..100 {
+            assert_eq!(pid.update(&params, 12.0, 1.0), 100.0);
+        }
+        // 输出饱和期间没有积分，偏差反向后立即退出饱和
+        assert_eq!(pid.integral(), 0.0);
+        assert_eq!(pid.update(&params, 7.5, 1.0), 10.5);
+    }
+
+    #[test]
+    fn test_bumpless_and_reverse() {
+        let mut params = acid_dosing();
+        let mut pid = Pid::bumpless(&params, 7.0, 35.0);
+        assert_eq!(pid.update(&params, 7.0, 1.0), 35.0);
+
+        // 有偏差时切换后首个周期输出仍为手动时的值，之后从该值开始调节
+        let mut pid = Pid::bumpless(&params, 8.5, 35.0);
+        assert_eq!(pid.integral(), 5.0);
+        assert_eq!(pid.update(&params, 8.5, 0.0), 35.0);
+        assert_eq!(pid.update(&params, 8.5, 1.0), 36.5);
+        let mut pid = Pid::bumpless(&params, 6.0, 35.0);
+        assert_eq!(pid.update(&params, 6.0, 0.0), 35.0);
+
+        params.direct = false;
+        params.kd = 10.0;
+        let mut pid = Pid::default();
+        pid.update(&params, 6.0, 1.0);
+        // 测量值上升时反作用回路的微分项减小输出
+        let output = pid.update(&params, 6.5, 1.0);
+        assert_eq!(output, 20.0 * 0.5 + 1.5 - 5.0);
+    }
+}
@@ -0,0 +1,367 @@
+//! 数据表迁移
+//!
+//! 表结构全部由实体定义生成（`Schema::create_table_from_entity`），
+//! 由 sea-query 按当前后端（SQLite / PostgreSQL / MySQL）输出对应方言的 DDL，
+//! 因此不需要为每个数据库维护手写 SQL。
+
+use crate::database::sea_orm_db::Result;
+use crate::models::{
+    alarm_log, alarm_rule, alarm_shelf, alarm_snapshot, api_key, api_usage, area, automation_rule,
+    calendar_day, calendar_shift, calibration, calibration_curve, chemical_tank, config_revision,
+    control_loop, daily_device_summary, daily_energy, daily_summary, data_correction, device,
+    device_command, device_credential, device_state_event, discharge_permit, dosing_pump,
+    flow_total, flow_value, gpio_write, hourly_summary, kpi_definition, lab_result,
+    maintenance_plan, measurement, modbus_mapping, modbus_write, organization, permit_exceedance,
+    ph_value, pump_curve, pump_group, remote_session, report, safe_state_event, serial_session,
+    site, summary_dirty_day, tank_geometry, tds_value, turbidity_value, vibration_limit,
+    vibration_record, work_order,
+};
+use crate::services::metric_registry;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Alias, Asterisk, Expr, Query, Table};
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityName, EntityTrait, IdenStatic,
+    Schema, Set, Statement, TransactionTrait,
+};
+use tracing::info;
+
+/// 批量插入时每批的行数，避免超出 SQLite 的绑定参数上限
+const INSERT_CHUNK_SIZE: usize = 500;
+
+/// 待执行的变更，供迁移前预检使用
+#[derive(Debug, Clone)]
+pub struct PendingChange {
+    pub description: String,
+    /// 受影响的行数，用于估算耗时
+    pub rows: u64,
+    /// 是否会删除或改写已有数据
+    pub destructive: bool,
+}
+
+/// 查询表中已有的列名，表不存在时为空
+async fn table_columns(db: &DatabaseConnection, table: &str) -> Result<Vec<String>> {
+    let backend = db.get_database_backend();
+    let sql = match backend {
+        DatabaseBackend::Sqlite => "SELECT name FROM pragma_table_info(?)",
+        DatabaseBackend::Postgres => {
+            "SELECT column_name AS name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1"
+        }
+        DatabaseBackend::MySql => {
+            "SELECT column_name AS name FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = ?"
+        }
+    };
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(backend, sql, [table.into()]))
+        .await?;
+    let mut columns = Vec::with_capacity(rows.len());
+    for row in rows {
+        columns.push(row.try_get::<String>("", "name")?);
+    }
+    Ok(columns)
+}
+
+/// 表中的行数
+async fn count_rows<E>(db: &DatabaseConnection, entity: E) -> Result<u64>
+where
+    E: EntityTrait,
+{
+    let backend = db.get_database_backend();
+    let statement = Query::select()
+        .expr_as(Expr::col(Asterisk).count(), Alias::new("n"))
+        .from(entity)
+        .to_owned();
+    let row = db.query_one(backend.build(&statement)).await?;
+    let count = match row {
+        Some(row) => row.try_get::<i64>("", "n")?,
+        None => 0,
+    };
+    Ok(count.max(0) as u64)
+}
+
+fn legacy_row(
+    metric_type: &str,
+    timestamp: DateTime<Utc>,
+    value: f64,
+    device_id: Option<i32>,
+    unit: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> measurement::ActiveModel {
+    measurement::ActiveModel {
+        metric_type: Set(metric_type.to_string()),
+        timestamp: Set(timestamp),
+        value: Set(value),
+        device_id: Set(device_id),
+        unit: Set(unit),
+        created_at: Set(created_at),
+        updated_at: Set(updated_at),
+        ..Default::default()
+    }
+}
+
+/// 把旧表的数据搬到 measurements 后清空旧表
+async fn move_legacy_rows<E>(
+    db: &DatabaseConnection,
+    entity: E,
+    rows: Vec<measurement::ActiveModel>,
+) -> Result<()>
+where
+    E: EntityTrait,
+{
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let count = rows.len();
+    let txn = db.begin().await?;
+    let mut rows = rows.into_iter().peekable();
+    while rows.peek().is_some() {
+        let chunk: Vec<_> = rows.by_ref().take(INSERT_CHUNK_SIZE).collect();
+        measurement::Entity::insert_many(chunk).exec(&txn).await?;
+    }
+    E::delete_many().exec(&txn).await?;
+    txn.commit().await?;
+
+    info!("Moved {} rows from {} into measurements", count, entity.table_name());
+    Ok(())
+}
+
+struct Migrator<'a> {
+    db: &'a DatabaseConnection,
+    schema: Schema,
+    /// 为 Some 时只记录待执行的变更，不修改数据库
+    plan: Option<Vec<PendingChange>>,
+}
+
+impl Migrator<'_> {
+    /// 创建单张表，已存在时跳过
+    async fn create_table<E>(&mut self, entity: E) -> Result<()>
+    where
+        E: EntityTrait,
+    {
+        if let Some(plan) = self.plan.as_mut() {
+            if table_columns(self.db, entity.table_name()).await?.is_empty() {
+                plan.push(PendingChange {
+                    description: format!("create table {}", entity.table_name()),
+                    rows: 0,
+                    destructive: false,
+                });
+            }
+            return Ok(());
+        }
+
+        let backend = self.db.get_database_backend();
+        let mut statement = self.schema.create_table_from_entity(entity);
+        statement.if_not_exists();
+        self.db.execute(backend.build(&statement)).await?;
+        info!("Ensured table: {}", entity.table_name());
+        Ok(())
+    }
+
+    /// 为已存在的表补充新增的列
+    ///
+    /// 新增列必须可为空或带默认值，否则旧数据无法满足约束。
+    async fn add_column_if_missing<E>(&mut self, entity: E, column: E::Column) -> Result<()>
+    where
+        E: EntityTrait,
+    {
+        let columns = table_columns(self.db, entity.table_name()).await?;
+        if columns.iter().any(|c| c == column.as_str()) {
+            return Ok(());
+        }
+
+        if let Some(plan) = self.plan.as_mut() {
+            // 表尚未创建时会随建表一起带上该列
+            if !columns.is_empty() {
+                plan.push(PendingChange {
+                    description: format!("add column {}.{}", entity.table_name(), column.as_str()),
+                    rows: count_rows(self.db, entity).await?,
+                    destructive: false,
+                });
+            }
+            return Ok(());
+        }
+
+        let backend = self.db.get_database_backend();
+        let statement = Table::alter()
+            .table(entity)
+            .add_column(self.schema.get_column_def::<E>(column))
+            .to_owned();
+        self.db.execute(backend.build(&statement)).await?;
+        info!("Added column {}.{}", entity.table_name(), column.as_str());
+        Ok(())
+    }
+
+    /// 旧表是否需要搬迁；预检时只记录待搬迁的行数
+    async fn legacy_pending<E>(&mut self, entity: E) -> Result<bool>
+    where
+        E: EntityTrait,
+    {
+        let Some(plan) = self.plan.as_mut() else {
+            return Ok(true);
+        };
+
+        if !table_columns(self.db, entity.table_name()).await?.is_empty() {
+            let rows = count_rows(self.db, entity).await?;
+            if rows > 0 {
+                plan.push(PendingChange {
+                    description: format!(
+                        "move {} rows from {} into measurements and clear {}",
+                        rows,
+                        entity.table_name(),
+                        entity.table_name()
+                    ),
+                    rows,
+                    destructive: true,
+                });
+            }
+        }
+        Ok(false)
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        self.create_table(device::Entity).await?;
+        self.add_column_if_missing(device::Entity, device::Column::SerialNumber).await?;
+        self.add_column_if_missing(device::Entity, device::Column::ProvisionStatus).await?;
+        self.add_column_if_missing(device::Entity, device::Column::SiteId).await?;
+        self.add_column_if_missing(device::Entity, device::Column::AreaId).await?;
+        self.add_column_if_missing(device::Entity, device::Column::RunHoursAccruedAt).await?;
+        self.add_column_if_missing(device::Entity, device::Column::OrganizationId).await?;
+        self.create_table(ph_value::Entity).await?;
+        self.create_table(tds_value::Entity).await?;
+        self.create_table(turbidity_value::Entity).await?;
+        self.create_table(flow_value::Entity).await?;
+        self.create_table(alarm_rule::Entity).await?;
+        self.add_column_if_missing(alarm_rule::Entity, alarm_rule::Column::OrganizationId).await?;
+        self.create_table(alarm_log::Entity).await?;
+        self.add_column_if_missing(alarm_log::Entity, alarm_log::Column::DeviceId).await?;
+        self.add_column_if_missing(alarm_log::Entity, alarm_log::Column::Severity).await?;
+        self.add_column_if_missing(alarm_log::Entity, alarm_log::Column::Shelved).await?;
+        self.create_table(automation_rule::Entity).await?;
+        self.add_column_if_missing(automation_rule::Entity, automation_rule::Column::OrganizationId).await?;
+        self.add_column_if_missing(automation_rule::Entity, automation_rule::Column::SiteId).await?;
+        self.create_table(measurement::Entity).await?;
+        self.add_column_if_missing(measurement::Entity, measurement::Column::SuppressedCount).await?;
+        self.add_column_if_missing(measurement::Entity, measurement::Column::Quality).await?;
+        self.create_table(api_key::Entity).await?;
+        self.add_column_if_missing(api_key::Entity, api_key::Column::OrganizationId).await?;
+        self.create_table(device_credential::Entity).await?;
+        self.create_table(remote_session::Entity).await?;
+        self.add_column_if_missing(remote_session::Entity, remote_session::Column::ClientIp).await?;
+        self.create_table(config_revision::Entity).await?;
+        self.add_column_if_missing(config_revision::Entity, config_revision::Column::Status).await?;
+        self.add_column_if_missing(config_revision::Entity, config_revision::Column::ReviewedBy).await?;
+        self.add_column_if_missing(config_revision::Entity, config_revision::Column::ReviewedAt).await?;
+        self.create_table(site::Entity).await?;
+        self.add_column_if_missing(site::Entity, site::Column::Timezone).await?;
+        self.create_table(area::Entity).await?;
+        self.create_table(serial_session::Entity).await?;
+        self.create_table(calibration_curve::Entity).await?;
+        self.create_table(tank_geometry::Entity).await?;
+        self.create_table(pump_curve::Entity).await?;
+        self.create_table(vibration_record::Entity).await?;
+        self.create_table(vibration_limit::Entity).await?;
+        self.create_table(device_state_event::Entity).await?;
+        self.create_table(daily_summary::Entity).await?;
+        self.create_table(daily_device_summary::Entity).await?;
+        self.create_table(modbus_write::Entity).await?;
+        self.create_table(modbus_mapping::Entity).await?;
+        self.create_table(summary_dirty_day::Entity).await?;
+        self.create_table(calendar_shift::Entity).await?;
+        self.create_table(calendar_day::Entity).await?;
+        self.create_table(device_command::Entity).await?;
+        self.create_table(alarm_shelf::Entity).await?;
+        self.create_table(alarm_snapshot::Entity).await?;
+        self.create_table(api_usage::Entity).await?;
+        self.create_table(hourly_summary::Entity).await?;
+        self.create_table(kpi_definition::Entity).await?;
+        self.create_table(gpio_write::Entity).await?;
+        self.create_table(report::Entity).await?;
+        self.create_table(discharge_permit::Entity).await?;
+        self.create_table(permit_exceedance::Entity).await?;
+        self.create_table(safe_state_event::Entity).await?;
+        self.create_table(daily_energy::Entity).await?;
+        self.create_table(chemical_tank::Entity).await?;
+        self.create_table(dosing_pump::Entity).await?;
+        self.create_table(control_loop::Entity).await?;
+        self.create_table(maintenance_plan::Entity).await?;
+        self.create_table(work_order::Entity).await?;
+        self.create_table(pump_group::Entity).await?;
+        self.create_table(flow_total::Entity).await?;
+        self.create_table(calibration::Entity).await?;
+        self.create_table(lab_result::Entity).await?;
+        self.create_table(data_correction::Entity).await?;
+        self.create_table(organization::Entity).await?;
+
+        self.migrate_legacy_values().await?;
+
+        Ok(())
+    }
+
+    /// 将 ph/tds/浊度/流量旧表中的数据迁移到通用 measurements 表
+    async fn migrate_legacy_values(&mut self) -> Result<()> {
+        if self.legacy_pending(ph_value::Entity).await? {
+            let rows = ph_value::Entity::find()
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(|r| legacy_row(metric_registry::PH, r.timestamp, r.value, r.device_id, r.unit, r.created_at, r.updated_at))
+                .collect();
+            move_legacy_rows(self.db, ph_value::Entity, rows).await?;
+        }
+
+        if self.legacy_pending(tds_value::Entity).await? {
+            let rows = tds_value::Entity::find()
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(|r| legacy_row(metric_registry::TDS, r.timestamp, r.value, r.device_id, r.unit, r.created_at, r.updated_at))
+                .collect();
+            move_legacy_rows(self.db, tds_value::Entity, rows).await?;
+        }
+
+        if self.legacy_pending(turbidity_value::Entity).await? {
+            let rows = turbidity_value::Entity::find()
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(|r| legacy_row(metric_registry::TURBIDITY, r.timestamp, r.value, r.device_id, r.unit, r.created_at, r.updated_at))
+                .collect();
+            move_legacy_rows(self.db, turbidity_value::Entity, rows).await?;
+        }
+
+        if self.legacy_pending(flow_value::Entity).await? {
+            let rows = flow_value::Entity::find()
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(|r| legacy_row(metric_registry::FLOW, r.timestamp, r.value, r.device_id, r.unit, r.created_at, r.updated_at))
+                .collect();
+            move_legacy_rows(self.db, flow_value::Entity, rows).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 执行全部迁移
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<()> {
+    let mut migrator = Migrator {
+        db,
+        schema: Schema::new(db.get_database_backend()),
+        plan: None,
+    };
+    migrator.run().await
+}
+
+/// 列出待执行的变更，不修改数据库
+pub async fn plan_migrations(db: &DatabaseConnection) -> Result<Vec<PendingChange>> {
+    let mut migrator = Migrator {
+        db,
+        schema: Schema::new(db.get_database_backend()),
+        plan: Some(Vec::new()),
+    };
+    migrator.run().await?;
+    Ok(migrator.plan.unwrap_or_default())
+}
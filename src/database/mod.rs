@@ -1,3 +1,8 @@
+pub mod migration;
+pub mod partition;
+pub mod preflight;
+pub mod query_metrics;
 pub mod redb;
 pub mod sea_orm_db;
-pub mod sea_orm_example;
\ No newline at end of file
+pub mod sea_orm_example;
+pub mod timescale;
\ No newline at end of file
@@ -0,0 +1,230 @@
+//! 测量值按月分区（仅 PostgreSQL）
+//!
+//! `measurements` 转为按 `timestamp` 范围分区的表，每月一个分区 `measurements_yYYYYmMM`，
+//! 另有接收超出范围数据的默认分区。转换前已有的数据整体作为 `measurements_legacy` 分区挂载。
+//! 保留期清理直接删除整月分区，不再逐行删除、改写整张表。SQLite / MySQL 不分区。
+
+use crate::database::sea_orm_db::Result;
+use crate::models::measurement;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityName, Statement, TransactionTrait,
+};
+use tracing::{info, warn};
+
+const LEGACY_PARTITION: &str = "measurements_legacy";
+const DEFAULT_PARTITION: &str = "measurements_default";
+
+fn table() -> &'static str {
+    measurement::Entity.table_name()
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn next_month(month: NaiveDate) -> NaiveDate {
+    let (year, month) = if month.month() == 12 {
+        (month.year() + 1, 1)
+    } else {
+        (month.year(), month.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(NaiveDate::MAX)
+}
+
+fn bound(month: NaiveDate) -> String {
+    format!("'{} 00:00:00+00'", month.format("%Y-%m-%d"))
+}
+
+/// 月分区的表名
+pub fn partition_name(month: NaiveDate) -> String {
+    format!("{}_y{:04}m{:02}", table(), month.year(), month.month())
+}
+
+/// 从月分区表名解析月份，其他分区返回 None
+fn partition_month(name: &str) -> Option<NaiveDate> {
+    let rest = name.strip_prefix(table())?.strip_prefix("_y")?;
+    let (year, month) = rest.split_once('m')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+}
+
+async fn relkind(db: &DatabaseConnection) -> Result<Option<String>> {
+    let sql = "SELECT c.relkind::text AS kind FROM pg_class c \
+               JOIN pg_namespace n ON n.oid = c.relnamespace \
+               WHERE n.nspname = current_schema() AND c.relname = $1";
+    let row = db
+        .query_one(Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, [table().into()]))
+        .await?;
+    Ok(match row {
+        Some(row) => Some(row.try_get::<String>("", "kind")?),
+        None => None,
+    })
+}
+
+/// 当前挂载的分区表名
+async fn partitions(db: &DatabaseConnection) -> Result<Vec<String>> {
+    let sql = "SELECT c.relname AS name FROM pg_inherits i \
+               JOIN pg_class c ON c.oid = i.inhrelid \
+               JOIN pg_class p ON p.oid = i.inhparent \
+               JOIN pg_namespace n ON n.oid = p.relnamespace \
+               WHERE n.nspname = current_schema() AND p.relname = $1";
+    let rows = db
+        .query_all(Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, [table().into()]))
+        .await?;
+    let mut names = Vec::with_capacity(rows.len());
+    for row in rows {
+        names.push(row.try_get::<String>("", "name")?);
+    }
+    Ok(names)
+}
+
+/// 把普通表转为分区表，已是分区表或非 PostgreSQL 时跳过
+///
+/// 返回是否已分区。原有数据保留在 `measurements_legacy` 分区中，随保留期清理逐步删除。
+pub async fn ensure_partitioned(db: &DatabaseConnection) -> Result<bool> {
+    if db.get_database_backend() != DatabaseBackend::Postgres {
+        warn!("Monthly partitioning is only supported on PostgreSQL, skipped");
+        return Ok(false);
+    }
+    match relkind(db).await?.as_deref() {
+        Some("p") => return Ok(true),
+        Some("r") => {}
+        _ => {
+            warn!("Table {} not found, partitioning skipped", table());
+            return Ok(false);
+        }
+    }
+
+    let txn = db.begin().await?;
+    let row = txn
+        .query_one(Statement::from_string(
+            DatabaseBackend::Postgres,
+            format!("SELECT max(\"timestamp\") AS latest FROM {}", table()),
+        ))
+        .await?;
+    let latest: Option<DateTime<Utc>> = match row {
+        Some(row) => row.try_get("", "latest")?,
+        None => None,
+    };
+    // 旧数据的分区覆盖到最新一条数据所在月份的末尾，之后由月分区接管
+    let current = month_start(Utc::now().date_naive());
+    let upper = latest
+        .map(|t| next_month(month_start(t.date_naive())))
+        .map_or(current, |m| m.max(current));
+
+    let table = table();
+    for sql in [
+        format!("ALTER TABLE {table} RENAME TO {LEGACY_PARTITION}"),
+        format!(
+            "ALTER TABLE {LEGACY_PARTITION} RENAME CONSTRAINT {table}_pkey \
+             TO {LEGACY_PARTITION}_pkey"
+        ),
+        format!(
+            "CREATE TABLE {table} (LIKE {LEGACY_PARTITION} INCLUDING DEFAULTS) \
+             PARTITION BY RANGE (\"timestamp\")"
+        ),
+        format!("ALTER TABLE {table} ADD PRIMARY KEY (id, \"timestamp\")"),
+        format!(
+            "ALTER TABLE {table} ATTACH PARTITION {LEGACY_PARTITION} \
+             FOR VALUES FROM (MINVALUE) TO ({})",
+            bound(upper)
+        ),
+        format!("CREATE TABLE {DEFAULT_PARTITION} PARTITION OF {table} DEFAULT"),
+    ] {
+        txn.execute_unprepared(&sql).await?;
+    }
+    txn.commit().await?;
+
+    info!("Converted {} to a partitioned table, legacy rows before {}", table, upper);
+    Ok(true)
+}
+
+/// 创建一个月分区，默认分区中落在该月的数据一并移入
+async fn create_month(db: &DatabaseConnection, name: &str, month: NaiveDate) -> Result<()> {
+    let table = table();
+    let (from, to) = (bound(month), bound(next_month(month)));
+    let range = format!("\"timestamp\" >= {from} AND \"timestamp\" < {to}");
+    let txn = db.begin().await?;
+    for sql in [
+        format!("CREATE TABLE {name} (LIKE {table} INCLUDING DEFAULTS)"),
+        format!("INSERT INTO {name} SELECT * FROM {DEFAULT_PARTITION} WHERE {range}"),
+        format!("DELETE FROM {DEFAULT_PARTITION} WHERE {range}"),
+        format!("ALTER TABLE {table} ATTACH PARTITION {name} FOR VALUES FROM ({from}) TO ({to})"),
+    ] {
+        txn.execute_unprepared(&sql).await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 创建从 `from` 所在月起共 `count` 个月的分区，已存在的跳过
+///
+/// 与旧数据分区重叠的月份会创建失败，只记录日志，等旧数据分区的范围过去后自然不再重叠。
+pub async fn ensure_months(
+    db: &DatabaseConnection,
+    from: NaiveDate,
+    count: u32,
+) -> Result<Vec<String>> {
+    let existing = partitions(db).await?;
+    let mut created = Vec::new();
+    let mut month = month_start(from);
+    for _ in 0..count {
+        let name = partition_name(month);
+        if !existing.contains(&name) {
+            match create_month(db, &name, month).await {
+                Ok(()) => {
+                    info!("Created partition {}", name);
+                    created.push(name);
+                }
+                Err(e) => warn!("Failed to create partition {}: {:?}", name, e),
+            }
+        }
+        month = next_month(month);
+    }
+    Ok(created)
+}
+
+/// 删除整月都早于 `cutoff` 的月分区，返回删除的表名
+pub async fn drop_expired(db: &DatabaseConnection, cutoff: DateTime<Utc>) -> Result<Vec<String>> {
+    let mut dropped = Vec::new();
+    for name in partitions(db).await? {
+        let Some(month) = partition_month(&name) else {
+            continue;
+        };
+        if next_month(month) > cutoff.date_naive() {
+            continue;
+        }
+        db.execute_unprepared(&format!("DROP TABLE {}", name)).await?;
+        info!("Dropped expired partition {}", name);
+        dropped.push(name);
+    }
+    Ok(dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_partition_names() {
+        assert_eq!(partition_name(date(2026, 3, 1)), "measurements_y2026m03");
+        assert_eq!(partition_month("measurements_y2026m03"), Some(date(2026, 3, 1)));
+        assert_eq!(partition_month(LEGACY_PARTITION), None);
+        assert_eq!(partition_month(DEFAULT_PARTITION), None);
+        assert_eq!(partition_month("measurements_y2026m13"), None);
+    }
+
+    #[test]
+    fn test_months() {
+        assert_eq!(month_start(date(2026, 10, 16)), date(2026, 10, 1));
+        assert_eq!(next_month(date(2026, 10, 1)), date(2026, 11, 1));
+        assert_eq!(next_month(date(2026, 12, 1)), date(2027, 1, 1));
+    }
+}
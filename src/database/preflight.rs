@@ -0,0 +1,230 @@
+//! 迁移前预检
+//!
+//! 现场网关升级时，迁移前先确认磁盘空间足够、估算大表的迁移耗时，并自动备份数据库（仅 SQLite，
+//! 其他后端需用数据库自带工具备份）。存在破坏性变更（如搬迁后清空旧表）时，
+//! 除非配置 `migration.allow_destructive` 或带 `--allow-destructive` 启动，否则拒绝迁移。
+//! 带 `--preflight` 启动只输出预检结果，不做任何修改。
+
+use crate::config::database::DatabaseConfig;
+use crate::config::migration::MigrationConfig;
+use crate::database::migration::{self, PendingChange};
+use crate::database::sea_orm_db::{DbError, Result};
+use crate::utils::disk;
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, Statement};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 只做预检、不执行迁移的启动参数
+pub const PREFLIGHT_FLAG: &str = "--preflight";
+/// 允许破坏性变更的启动参数
+pub const ALLOW_DESTRUCTIVE_FLAG: &str = "--allow-destructive";
+
+const MB: u64 = 1024 * 1024;
+
+/// 预检结果
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    pub changes: Vec<PendingChange>,
+    /// SQLite 数据库文件大小，其他后端为 None
+    pub database_bytes: Option<u64>,
+    /// 数据库所在磁盘的可用空间，无法获取时为 None
+    pub free_disk_bytes: Option<u64>,
+    /// 迁移所需的可用空间（含一份备份）
+    pub required_disk_bytes: u64,
+    pub estimated_duration: Duration,
+}
+
+impl PreflightReport {
+    pub fn destructive(&self) -> Vec<&PendingChange> {
+        self.changes.iter().filter(|c| c.destructive).collect()
+    }
+
+    pub fn log(&self) {
+        if self.changes.is_empty() {
+            info!("Migration preflight: database schema is up to date");
+            return;
+        }
+
+        for change in &self.changes {
+            info!(
+                "Pending migration: {}{}",
+                change.description,
+                if change.destructive { " [destructive]" } else { "" }
+            );
+        }
+        if let Some(free) = self.free_disk_bytes {
+            info!(
+                "Free disk space: {} MB, required: {} MB",
+                free / MB,
+                self.required_disk_bytes / MB
+            );
+        }
+        info!("Estimated migration duration: {}s", self.estimated_duration.as_secs());
+    }
+}
+
+/// SQLite 数据库文件路径；内存库或其他后端为 None
+fn sqlite_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("sqlite://").or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+fn estimate(changes: &[PendingChange], rows_per_second: u64) -> Duration {
+    let rows: u64 = changes.iter().map(|c| c.rows).sum();
+    Duration::from_secs(rows.div_ceil(rows_per_second.max(1)))
+}
+
+/// 列出待执行的变更并检查磁盘，不修改数据库
+pub async fn inspect(
+    db: &DatabaseConnection,
+    database: &DatabaseConfig,
+    config: &MigrationConfig,
+) -> Result<PreflightReport> {
+    let changes = migration::plan_migrations(db).await?;
+
+    let (database_bytes, free_disk) = match sqlite_path(&database.url) {
+        Some(path) => {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            (Some(size), disk::free_bytes(&dir))
+        }
+        None => (None, None),
+    };
+
+    Ok(PreflightReport {
+        estimated_duration: estimate(&changes, config.rows_per_second),
+        changes,
+        database_bytes,
+        free_disk_bytes: free_disk,
+        required_disk_bytes: config.min_free_disk_mb * MB + database_bytes.unwrap_or(0),
+    })
+}
+
+/// 检查磁盘空间与破坏性变更
+pub fn check(report: &PreflightReport, allow_destructive: bool) -> Result<()> {
+    if report.changes.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(free) = report.free_disk_bytes {
+        if free < report.required_disk_bytes {
+            return Err(DbError::Preflight(format!(
+                "insufficient disk space: {} MB free, {} MB required",
+                free / MB,
+                report.required_disk_bytes / MB
+            )));
+        }
+    }
+
+    let destructive = report.destructive();
+    if !destructive.is_empty() && !allow_destructive {
+        let descriptions: Vec<&str> = destructive.iter().map(|c| c.description.as_str()).collect();
+        return Err(DbError::Preflight(format!(
+            "refusing destructive migration ({}); start with {} or set migration.allow_destructive",
+            descriptions.join("; "),
+            ALLOW_DESTRUCTIVE_FLAG
+        )));
+    }
+
+    Ok(())
+}
+
+/// 备份 SQLite 数据库，返回备份文件路径
+pub async fn backup(
+    db: &DatabaseConnection,
+    database: &DatabaseConfig,
+    config: &MigrationConfig,
+) -> Result<Option<PathBuf>> {
+    if db.get_database_backend() != DatabaseBackend::Sqlite {
+        warn!("Automatic backup is only supported for SQLite; back up the database before upgrading");
+        return Ok(None);
+    }
+    let Some(path) = sqlite_path(&database.url) else {
+        return Ok(None);
+    };
+
+    std::fs::create_dir_all(&config.backup_dir)
+        .map_err(|e| DbError::Preflight(format!("failed to create backup directory: {}", e)))?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("database");
+    let target = Path::new(&config.backup_dir).join(format!(
+        "{}-{}.db",
+        stem,
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    // VACUUM INTO 生成一致的快照，迁移过程中无需停写
+    db.execute(Statement::from_sql_and_values(
+        DatabaseBackend::Sqlite,
+        "VACUUM INTO ?",
+        [target.to_string_lossy().into_owned().into()],
+    ))
+    .await?;
+
+    info!("Database backed up to {}", target.display());
+    Ok(Some(target))
+}
+
+/// 迁移前预检：检查通过后自动备份
+pub async fn run(
+    db: &DatabaseConnection,
+    database: &DatabaseConfig,
+    config: &MigrationConfig,
+    allow_destructive: bool,
+) -> Result<PreflightReport> {
+    let report = inspect(db, database, config).await?;
+    report.log();
+    check(&report, allow_destructive)?;
+
+    if !report.changes.is_empty() {
+        backup(db, database, config).await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_path() {
+        assert_eq!(sqlite_path("sqlite://guolu.db?mode=rwc"), Some(PathBuf::from("guolu.db")));
+        assert_eq!(sqlite_path("sqlite:/data/guolu.db"), Some(PathBuf::from("/data/guolu.db")));
+        assert_eq!(sqlite_path("sqlite::memory:"), None);
+        assert_eq!(sqlite_path("postgres://user@host/db"), None);
+    }
+
+    #[test]
+    fn test_check_destructive() {
+        let change = |destructive| PendingChange {
+            description: "move rows".to_string(),
+            rows: 100_000,
+            destructive,
+        };
+        let report = PreflightReport {
+            changes: vec![change(false), change(true)],
+            database_bytes: Some(0),
+            free_disk_bytes: Some(1024 * MB),
+            required_disk_bytes: 512 * MB,
+            estimated_duration: estimate(&[change(true)], 20_000),
+        };
+
+        assert_eq!(report.estimated_duration, Duration::from_secs(5));
+        assert!(check(&report, false).is_err());
+        assert!(check(&report, true).is_ok());
+
+        let full = PreflightReport {
+            free_disk_bytes: Some(100 * MB),
+            ..report
+        };
+        assert!(check(&full, true).is_err());
+    }
+}
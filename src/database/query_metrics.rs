@@ -0,0 +1,242 @@
+//! 查询耗时统计
+//!
+//! 通过 SeaORM 的 metric 回调记录每条语句的耗时直方图，超过阈值的写入慢查询日志。
+//! 语句按去掉字面量后的 SQL 归类，日志和统计中不出现绑定参数或内联的字面量。
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// 直方图桶上界（毫秒），最后一个桶为 +Inf
+const BUCKETS_MS: [f64; 11] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+/// 最多分别统计的语句数，超出后归入 `OTHER`
+const MAX_STATEMENTS: usize = 500;
+const OTHER: &str = "<other>";
+
+/// 把字符串和数字字面量替换为 `?`，并压缩空白
+fn normalize(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut prev = ' ';
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // 跳过字符串字面量，'' 为转义的单引号
+            while let Some(c) = chars.next() {
+                if c == '\'' {
+                    if chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            out.push('?');
+            prev = '?';
+        } else if c.is_ascii_digit() && !(prev.is_alphanumeric() || prev == '_' || prev == '$') {
+            while chars.peek().is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                chars.next();
+            }
+            out.push('?');
+            prev = '?';
+        } else if c.is_whitespace() {
+            if prev != ' ' {
+                out.push(' ');
+                prev = ' ';
+            }
+        } else {
+            out.push(c);
+            prev = c;
+        }
+    }
+    out.trim_end().to_string()
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// 与 BUCKETS_MS 对应，多出的最后一个为 +Inf
+    buckets: [u64; BUCKETS_MS.len() + 1],
+    count: u64,
+    failed: u64,
+    total_ms: f64,
+    max_ms: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, ms: f64, failed: bool) {
+        let index = BUCKETS_MS.iter().position(|le| ms <= *le).unwrap_or(BUCKETS_MS.len());
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        if failed {
+            self.failed += 1;
+        }
+    }
+}
+
+/// 直方图的一个桶
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HistogramBucket {
+    /// 桶上界（毫秒），空表示 +Inf
+    pub le_ms: Option<f64>,
+    /// 耗时不超过上界的累计次数
+    pub count: u64,
+}
+
+/// 单条语句的统计
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueryStats {
+    /// 去掉字面量后的 SQL
+    pub statement: String,
+    pub count: u64,
+    pub failed: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueryMetricsSnapshot {
+    pub since: DateTime<Utc>,
+    pub slow_query_ms: u64,
+    pub slow_queries: u64,
+    /// 按总耗时降序
+    pub statements: Vec<QueryStats>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    since: DateTime<Utc>,
+    statements: HashMap<String, Histogram>,
+    slow_queries: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryMetrics {
+    slow_query: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl QueryMetrics {
+    pub fn new(slow_query: Duration) -> Self {
+        Self {
+            slow_query,
+            inner: Arc::new(Mutex::new(Inner {
+                since: Utc::now(),
+                statements: HashMap::new(),
+                slow_queries: 0,
+            })),
+        }
+    }
+
+    /// 记录一次执行
+    pub fn observe(&self, sql: &str, params: usize, elapsed: Duration, failed: bool) {
+        let statement = normalize(sql);
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let slow = !self.slow_query.is_zero() && elapsed >= self.slow_query;
+        if slow {
+            warn!("Slow query ({:.1} ms, {} params redacted): {}", ms, params, statement);
+        }
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if slow {
+            inner.slow_queries += 1;
+        }
+        let known = inner.statements.contains_key(&statement);
+        let key = if known || inner.statements.len() < MAX_STATEMENTS {
+            statement
+        } else {
+            OTHER.to_string()
+        };
+        inner.statements.entry(key).or_default().observe(ms, failed);
+    }
+
+    pub fn snapshot(&self) -> QueryMetricsSnapshot {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statements: Vec<QueryStats> = inner
+            .statements
+            .iter()
+            .map(|(statement, h)| {
+                let mut cumulative = 0;
+                let buckets = h
+                    .buckets
+                    .iter()
+                    .enumerate()
+                    .map(|(i, count)| {
+                        cumulative += count;
+                        HistogramBucket {
+                            le_ms: BUCKETS_MS.get(i).copied(),
+                            count: cumulative,
+                        }
+                    })
+                    .collect();
+                QueryStats {
+                    statement: statement.clone(),
+                    count: h.count,
+                    failed: h.failed,
+                    total_ms: h.total_ms,
+                    mean_ms: if h.count > 0 { h.total_ms / h.count as f64 } else { 0.0 },
+                    max_ms: h.max_ms,
+                    buckets,
+                }
+            })
+            .collect();
+        statements.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+        QueryMetricsSnapshot {
+            since: inner.since,
+            slow_query_ms: self.slow_query.as_millis() as u64,
+            slow_queries: inner.slow_queries,
+            statements,
+        }
+    }
+
+    /// 清空统计
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.since = Utc::now();
+        inner.statements.clear();
+        inner.slow_queries = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_redacts_literals() {
+        assert_eq!(
+            normalize("SELECT * FROM t\n  WHERE device_id = 42 AND unit = 'it''s' LIMIT $1"),
+            "SELECT * FROM t WHERE device_id = ? AND unit = ? LIMIT $1"
+        );
+        let table = "DROP TABLE measurements_y2026m03";
+        assert_eq!(normalize(table), table);
+        assert_eq!(normalize("SELECT 1.5 + x1"), "SELECT ? + x1");
+    }
+
+    #[test]
+    fn test_histogram() {
+        let metrics = QueryMetrics::new(Duration::from_millis(100));
+        metrics.observe("SELECT 1", 0, Duration::from_millis(3), false);
+        metrics.observe("SELECT 2", 0, Duration::from_millis(300), true);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.slow_queries, 1);
+        assert_eq!(snapshot.statements.len(), 1);
+
+        let stats = &snapshot.statements[0];
+        assert_eq!(stats.statement, "SELECT ?");
+        assert_eq!((stats.count, stats.failed), (2, 1));
+        // 5ms 桶包含 3ms，500ms 桶包含两次
+        assert_eq!(stats.buckets[1].count, 1);
+        assert_eq!(stats.buckets[7].count, 2);
+        assert_eq!(stats.buckets.last().map(|b| (b.le_ms, b.count)), Some((None, 2)));
+    }
+}
@@ -1,9 +1,10 @@
     use bincode::{config, Decode, Encode};
 use redb::{
-    CommitError, Database, DatabaseError, ReadableDatabase, ReadableTable, StorageError,
-    TableDefinition, TableError, TableStats, TransactionError,
+    CommitError, Database, DatabaseError, ReadableDatabase, ReadableTable, ReadableTableMetadata,
+    StorageError, TableDefinition, TableError, TableStats, TransactionError,
 };
 use std::fmt::Debug;
+use std::ops::Bound;
 use std::path::Path;
 use std::result::Result as StdResult;
 
@@ -85,6 +86,55 @@ impl DbManager {
         }
     }
 
+    /// 按键顺序读取 `after` 之后的最多 `limit` 条记录
+    pub fn range_after<V>(&self, table_name: &str, after: Option<&str>, limit: usize) -> Result<Vec<(String, V)>>
+    where
+        V: Decode<()> + Debug,
+    {
+        let table = TableDefinition::<String, Vec<u8>>::new(table_name);
+        let read_txn = self.db.begin_read()?;
+        let t = match read_txn.open_table(table) {
+            Ok(t) => t,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let start = match after {
+            Some(key) => Bound::Excluded(key.to_string()),
+            None => Bound::Unbounded,
+        };
+        let mut entries = Vec::new();
+        for entry in t.range::<String>((start, Bound::Unbounded))?.take(limit) {
+            let (key, value) = entry?;
+            let decoded = bincode::decode_from_slice(value.value().as_slice(), config::standard())
+                .map_err(|e| DbError::Serialization(format!("Deserialization error: {}", e)))?
+                .0;
+            entries.push((key.value(), decoded));
+        }
+        Ok(entries)
+    }
+
+    /// 最大的键
+    pub fn last_key(&self, table_name: &str) -> Result<Option<String>> {
+        let table = TableDefinition::<String, Vec<u8>>::new(table_name);
+        let read_txn = self.db.begin_read()?;
+        let t = match read_txn.open_table(table) {
+            Ok(t) => t,
+            Err(_) => return Ok(None),
+        };
+        Ok(t.last()?.map(|(key, _)| key.value()))
+    }
+
+    /// 表中的记录数
+    pub fn len(&self, table_name: &str) -> Result<u64> {
+        let table = TableDefinition::<String, Vec<u8>>::new(table_name);
+        let read_txn = self.db.begin_read()?;
+        let t = match read_txn.open_table(table) {
+            Ok(t) => t,
+            Err(_) => return Ok(0),
+        };
+        Ok(t.len()?)
+    }
+
     /// 删除指定键的值
     pub fn delete(&self, table_name: &str, key: &str) -> Result<bool> {
         let table = TableDefinition::<String, Vec<u8>>::new(table_name);
@@ -1,9 +1,12 @@
+use crate::config::database::DatabaseConfig;
+use crate::database::query_metrics::QueryMetrics;
 use sea_orm::{
-    Database, DatabaseConnection, DbErr
+    ConnectOptions, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr
 };
 use std::fmt::Debug;
 use std::result::Result as StdResult;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// 数据库错误类型
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +17,10 @@ pub enum DbError {
     Serialization(String),
     #[error("Key not found: {0}")]
     KeyNotFound(String),
+    #[error("Unsupported database backend: {0}")]
+    UnsupportedBackend(String),
+    #[error("Migration preflight failed: {0}")]
+    Preflight(String),
 }
 
 pub type Result<T> = StdResult<T, DbError>;
@@ -22,17 +29,59 @@ pub type Result<T> = StdResult<T, DbError>;
 #[derive(Debug, Clone)]
 pub struct DbManager {
     db: Arc<DatabaseConnection>,
+    metrics: QueryMetrics,
 }
 
 impl DbManager {
     /// 创建或打开数据库连接
     pub async fn new(uri: &str) -> Result<Self> {
         let db = Database::connect(uri).await?;
-        Ok(DbManager { db: Arc::new(db) })
+        Ok(DbManager {
+            db: Arc::new(db),
+            metrics: QueryMetrics::new(Duration::ZERO),
+        })
+    }
+
+    /// 根据配置创建连接池，支持 sqlite / postgres / mysql
+    pub async fn from_config(config: &DatabaseConfig) -> Result<Self> {
+        if config.kind().is_none() {
+            return Err(DbError::UnsupportedBackend(config.url.clone()));
+        }
+
+        let mut options = ConnectOptions::new(config.url.clone());
+        options
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .connect_timeout(config.connect_timeout())
+            .acquire_timeout(config.acquire_timeout())
+            .idle_timeout(config.idle_timeout())
+            .sqlx_logging(config.sqlx_logging);
+
+        let mut db = Database::connect(options).await?;
+        let metrics = QueryMetrics::new(config.slow_query());
+        let recorder = metrics.clone();
+        db.set_metric_callback(move |info| {
+            let params = info.statement.values.as_ref().map_or(0, |v| v.0.len());
+            recorder.observe(&info.statement.sql, params, info.elapsed, info.failed);
+        });
+        Ok(DbManager {
+            db: Arc::new(db),
+            metrics,
+        })
     }
 
     /// 获取数据库连接
     pub fn get_connection(&self) -> &DatabaseConnection {
         &self.db
     }
-}
\ No newline at end of file
+
+    /// 各语句的耗时统计
+    pub fn query_metrics(&self) -> &QueryMetrics {
+        &self.metrics
+    }
+
+    /// 当前连接使用的数据库后端
+    pub fn backend(&self) -> DatabaseBackend {
+        self.db.get_database_backend()
+    }
+}
@@ -0,0 +1,78 @@
+//! TimescaleDB hypertable（仅 PostgreSQL）
+//!
+//! `measurements` 转为按 `timestamp` 分 chunk 的 hypertable，原有数据随转换迁入 chunk。
+//! hypertable 的唯一约束必须包含分区列，主键改为 `(id, timestamp)`；`id` 仍自增且唯一。
+//! 与按月分区互斥，已按月分区的表不再转换。
+
+use crate::config::timeseries::TimescaleDbConfig;
+use crate::database::sea_orm_db::Result;
+use crate::models::measurement;
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityName, Statement, TransactionTrait,
+};
+use tracing::{info, warn};
+
+fn table() -> &'static str {
+    measurement::Entity.table_name()
+}
+
+async fn is_hypertable(db: &DatabaseConnection) -> Result<bool> {
+    let sql = "SELECT count(*) AS n FROM timescaledb_information.hypertables \
+               WHERE hypertable_schema = current_schema() AND hypertable_name = $1";
+    let row = db
+        .query_one(Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, [table().into()]))
+        .await?;
+    Ok(match row {
+        Some(row) => row.try_get::<i64>("", "n")? > 0,
+        None => false,
+    })
+}
+
+async fn is_partitioned(db: &DatabaseConnection) -> Result<bool> {
+    let sql = "SELECT count(*) AS n FROM pg_class c \
+               JOIN pg_namespace n ON n.oid = c.relnamespace \
+               WHERE n.nspname = current_schema() AND c.relname = $1 AND c.relkind = 'p'";
+    let row = db
+        .query_one(Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, [table().into()]))
+        .await?;
+    Ok(match row {
+        Some(row) => row.try_get::<i64>("", "n")? > 0,
+        None => false,
+    })
+}
+
+/// 把测量值表转为 hypertable，已转换时跳过
+///
+/// 返回是否为 hypertable。非 PostgreSQL 或表已按月分区时返回 false。
+pub async fn ensure_hypertable(db: &DatabaseConnection, config: &TimescaleDbConfig) -> Result<bool> {
+    if db.get_database_backend() != DatabaseBackend::Postgres {
+        warn!("TimescaleDB requires PostgreSQL, hypertable skipped");
+        return Ok(false);
+    }
+    db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS timescaledb").await?;
+    if is_hypertable(db).await? {
+        return Ok(true);
+    }
+    if is_partitioned(db).await? {
+        warn!("Table {} is partitioned by month, hypertable skipped", table());
+        return Ok(false);
+    }
+
+    let table = table();
+    let txn = db.begin().await?;
+    for sql in [
+        format!("ALTER TABLE {table} DROP CONSTRAINT IF EXISTS {table}_pkey"),
+        format!("ALTER TABLE {table} ADD PRIMARY KEY (id, \"timestamp\")"),
+        format!(
+            "SELECT create_hypertable('{table}', 'timestamp', \
+             chunk_time_interval => INTERVAL '{} days', migrate_data => TRUE)",
+            config.chunk_interval_days.max(1)
+        ),
+    ] {
+        txn.execute_unprepared(&sql).await?;
+    }
+    txn.commit().await?;
+
+    info!("Converted {} to a TimescaleDB hypertable", table);
+    Ok(true)
+}
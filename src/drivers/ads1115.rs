@@ -0,0 +1,98 @@
+//! ADS1115 16 位 ADC（I2C）
+//!
+//! 单次转换模式读取单端输入 AIN0~AIN3，返回电压。
+
+use crate::utils::i2c::I2cController;
+use std::io;
+use std::time::Duration;
+
+const REG_CONVERSION: u8 = 0x00;
+const REG_CONFIG: u8 = 0x01;
+
+/// 启动单次转换
+const OS_SINGLE: u16 = 1 << 15;
+/// 单次转换模式
+const MODE_SINGLE: u16 = 1 << 8;
+/// 128 SPS
+const DATA_RATE_128: u16 = 0b100 << 5;
+/// 关闭比较器
+const COMP_DISABLE: u16 = 0b11;
+/// 128 SPS 下一次转换约 7.8ms，留出余量
+const CONVERSION_TIME: Duration = Duration::from_millis(10);
+
+/// 可编程增益对应的满量程电压
+const GAINS: [(f64, u16); 6] = [
+    (6.144, 0b000),
+    (4.096, 0b001),
+    (2.048, 0b010),
+    (1.024, 0b011),
+    (0.512, 0b100),
+    (0.256, 0b101),
+];
+
+pub struct Ads1115 {
+    i2c: I2cController,
+}
+
+/// 单端输入通道的配置字
+fn config_word(channel: u8, full_scale: f64) -> Option<u16> {
+    if channel > 3 {
+        return None;
+    }
+    let (_, pga) = GAINS.iter().find(|(fsr, _)| (fsr - full_scale).abs() < 1e-9)?;
+    let mux = (0b100 | channel as u16) << 12;
+    Some(OS_SINGLE | mux | (pga << 9) | MODE_SINGLE | DATA_RATE_128 | COMP_DISABLE)
+}
+
+/// 转换结果换算为电压
+pub fn to_volts(raw: i16, full_scale: f64) -> f64 {
+    raw as f64 * full_scale / 32768.0
+}
+
+impl Ads1115 {
+    pub fn new(i2c: I2cController) -> Self {
+        Self { i2c }
+    }
+
+    /// 读取单端通道电压，`full_scale` 为满量程 (V)：6.144 / 4.096 / 2.048 / 1.024 / 0.512 / 0.256
+    pub async fn read_voltage(&self, channel: u8, full_scale: f64) -> io::Result<f64> {
+        let raw = self.read_raw(channel, full_scale).await?;
+        Ok(to_volts(raw, full_scale))
+    }
+
+    /// 读取单端通道的原始码值
+    pub async fn read_raw(&self, channel: u8, full_scale: f64) -> io::Result<i16> {
+        let config = config_word(channel, full_scale).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid ADS1115 channel {} or full scale {}", channel, full_scale),
+            )
+        })?;
+
+        self.i2c.write_register_u16(REG_CONFIG, config).await?;
+        tokio::time::sleep(CONVERSION_TIME).await;
+        let raw = self.i2c.read_register_u16(REG_CONVERSION).await?;
+
+        Ok(raw as i16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_word() {
+        // AIN0、±4.096V、单次、128SPS、关闭比较器
+        assert_eq!(config_word(0, 4.096), Some(0xC383));
+        assert_eq!(config_word(3, 2.048), Some(0xF583));
+        assert_eq!(config_word(4, 4.096), None);
+        assert_eq!(config_word(0, 5.0), None);
+    }
+
+    #[test]
+    fn test_to_volts() {
+        assert_eq!(to_volts(0x4000, 4.096), 2.048);
+        assert_eq!(to_volts(-0x4000, 4.096), -2.048);
+    }
+}
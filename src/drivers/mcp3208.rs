@@ -0,0 +1,56 @@
+//! MCP3208 12 位 ADC（SPI）
+//!
+//! 单端输入 CH0~CH7，返回原始码值 0~4095。
+
+use crate::utils::spi::SpiController;
+use std::io;
+
+pub const MAX_COUNT: u16 = 4095;
+
+pub struct Mcp3208 {
+    spi: SpiController,
+}
+
+/// 单端读取命令：起始位、单端位、3 位通道号
+fn command(channel: u8) -> Option<[u8; 3]> {
+    if channel > 7 {
+        return None;
+    }
+    Some([0b0000_0110 | (channel >> 2), (channel & 0b11) << 6, 0])
+}
+
+/// 从回读数据中取出 12 位结果
+fn parse_reply(reply: &[u8]) -> u16 {
+    (((reply[1] & 0x0F) as u16) << 8) | reply[2] as u16
+}
+
+impl Mcp3208 {
+    pub fn new(spi: SpiController) -> Self {
+        Self { spi }
+    }
+
+    pub async fn read_raw(&self, channel: u8) -> io::Result<u16> {
+        let command = command(channel).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("invalid MCP3208 channel {}", channel))
+        })?;
+        let reply = self.spi.transfer(command.to_vec()).await?;
+        Ok(parse_reply(&reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(command(0), Some([0x06, 0x00, 0x00]));
+        assert_eq!(command(5), Some([0x07, 0x40, 0x00]));
+        assert_eq!(command(8), None);
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply(&[0xFF, 0xEA, 0xBC]), 0xABC);
+    }
+}
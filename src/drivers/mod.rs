@@ -0,0 +1,4 @@
+//! 传感器/板卡驱动
+
+pub mod ads1115;
+pub mod mcp3208;
@@ -0,0 +1,129 @@
+use crate::app_state::AppState;
+use crate::middleware::api_key::ControlKey;
+use crate::models::gpio_write::Model as GpioWrite;
+use crate::models::safe_state_event::Model as SafeStateEvent;
+use crate::services::reconcile::{self, Reconciliation};
+use crate::services::safe_state::{self, ActuatorSafeState};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetGpioOutputRequest {
+    /// true 为高电平
+    pub value: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SafeStateEventQuery {
+    /// 执行器名称
+    pub actuator: Option<String>,
+    /// 默认 100 条，最多 1000 条
+    pub limit: Option<u64>,
+}
+
+/// 设置 GPIO 输出，需使用具备 control 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/gpio/outputs/{name}",
+    params(
+        ("name" = String, Path, description = "输出名称")
+    ),
+    request_body = SetGpioOutputRequest,
+    responses(
+        (status = 200, description = "设置 GPIO 输出成功", body = GpioWrite),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限，或不是平台管理 Key"),
+        (status = 404, description = "输出未配置"),
+        (status = 503, description = "GPIO 输出失败")
+    ),
+    tag = "Actuators"
+)]
+pub async fn set_gpio_output(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<SetGpioOutputRequest>,
+) -> Result<Json<GpioWrite>, AppError> {
+    // 网关本机的输出不属于任何组织
+    tenant.require_platform()?;
+    let record = state
+        .gpio_outputs
+        .set(state.db.get_connection(), &name, payload.value, Some(caller.operator_label()))
+        .await?;
+
+    Ok(Json(record))
+}
+
+/// 核对执行器实际状态与最后一次下发（只读，不会写入输出）
+#[utoipa::path(
+    get,
+    path = "/actuators/reconciliation",
+    responses(
+        (status = 200, description = "核对完成", body = Reconciliation),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Actuators"
+)]
+pub async fn get_reconciliation(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Reconciliation>, AppError> {
+    tenant.require_platform()?;
+    let result = reconcile::check(
+        state.db.get_connection(),
+        &state.gpio_outputs,
+        &state.settings.modbus,
+    )
+    .await?;
+
+    Ok(Json(result))
+}
+
+/// 获取各执行器的通信中断安全状态
+#[utoipa::path(
+    get,
+    path = "/actuators/safe-state",
+    responses(
+        (status = 200, description = "获取安全状态成功", body = [ActuatorSafeState]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Actuators"
+)]
+pub async fn get_safe_state(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ActuatorSafeState>>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(state.safe_state.status()))
+}
+
+/// 获取安全状态事件
+#[utoipa::path(
+    get,
+    path = "/actuators/safe-state/events",
+    params(SafeStateEventQuery),
+    responses(
+        (status = 200, description = "获取安全状态事件成功", body = [SafeStateEvent]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Actuators"
+)]
+pub async fn get_safe_state_events(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SafeStateEventQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<SafeStateEvent>>, AppError> {
+    tenant.require_platform()?;
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let events =
+        safe_state::events(state.db.get_connection(), query.actuator.as_deref(), limit).await?;
+    Ok(Json(events))
+}
@@ -0,0 +1,45 @@
+use crate::app_state::AppState;
+use crate::services::alarm_kpi::{self, AlarmKpis};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlarmKpiQuery {
+    /// 默认为结束时间前 30 天
+    pub start: Option<DateTime<Utc>>,
+    /// 默认为当前时间
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// 报警管理 KPI
+#[utoipa::path(
+    get,
+    path = "/alarms/kpis",
+    params(AlarmKpiQuery),
+    responses(
+        (status = 200, description = "获取报警 KPI 成功", body = AlarmKpis),
+        (status = 400, description = "时间段无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Alarm Logs"
+)]
+pub async fn get_alarm_kpis(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AlarmKpiQuery>,
+    tenant: Tenant,
+) -> Result<Json<AlarmKpis>, AppError> {
+    tenant.require_platform()?;
+    let end = query.end.unwrap_or_else(Utc::now);
+    let start = query.start.unwrap_or(end - Duration::days(30));
+    let kpis =
+        alarm_kpi::kpis(state.db.get_connection(), start, end, &state.settings.alarm).await?;
+    Ok(Json(kpis))
+}
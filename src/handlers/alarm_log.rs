@@ -1,12 +1,14 @@
 use crate::app_state::AppState;
-use crate::models::alarm_log::{Entity as AlarmLogEntity, Model as AlarmLog, ActiveModel as AlarmLogActiveModel};
+use crate::models::alarm_log::{Column as AlarmLogColumn, Entity as AlarmLogEntity, Model as AlarmLog, ActiveModel as AlarmLogActiveModel, SEVERITIES, SEVERITY_WARNING};
+use crate::services::alarm_snapshot::{self, AlarmSnapshotView};
 use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
     response::Json,
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
+use sea_orm::{DatabaseConnection, EntityTrait, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -17,6 +19,11 @@ pub struct CreateAlarmLogRequest {
     pub rule_name: String,
     pub trigger_value: f64,
     pub is_processed: bool,
+    #[serde(default)]
+    pub device_id: Option<i32>,
+    /// critical / major / minor / warning，默认 warning
+    #[serde(default)]
+    pub severity: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -24,6 +31,30 @@ pub struct UpdateAlarmLogRequest {
     pub rule_name: Option<String>,
     pub trigger_value: Option<f64>,
     pub is_processed: Option<bool>,
+    pub severity: Option<String>,
+}
+
+fn check_severity(severity: &str) -> Result<(), AppError> {
+    if SEVERITIES.contains(&severity) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!("未知的严重程度: {}", severity).into()))
+    }
+}
+
+/// 报警日志按关联设备判断是否属于当前租户
+async fn find_alarm_log(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<AlarmLog, AppError> {
+    let alarm_log = AlarmLogEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, alarm_log.device_id).await?;
+    Ok(alarm_log)
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -45,13 +76,15 @@ pub struct Pagination {
 pub async fn get_alarm_logs(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<AlarmLog>>, AppError> {
     let conn = state.db.get_connection();
     
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
     
-    let alarm_logs = AlarmLogEntity::find()
+    let alarm_logs = tenant
+        .scope_devices(AlarmLogEntity::find(), AlarmLogColumn::DeviceId)
         .all(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
@@ -84,14 +117,11 @@ pub async fn get_alarm_logs(
 pub async fn get_alarm_log(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<AlarmLog>, AppError> {
     let conn = state.db.get_connection();
     
-    let alarm_log = AlarmLogEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
+    let alarm_log = find_alarm_log(conn, tenant, id).await?;
 
     Ok(Json(alarm_log))
 }
@@ -103,21 +133,31 @@ pub async fn get_alarm_log(
     request_body = CreateAlarmLogRequest,
     responses(
         (status = 201, description = "创建报警日志成功", body = AlarmLog),
-        (status = 400, description = "请求参数错误")
+        (status = 400, description = "请求参数错误"),
+        (status = 404, description = "设备未找到")
     ),
     tag = "Alarm Logs"
 )]
 pub async fn create_alarm_log(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
     Json(payload): Json<CreateAlarmLogRequest>,
 ) -> Result<(StatusCode, Json<AlarmLog>), AppError> {
     let conn = state.db.get_connection();
-    
+    tenant.check_device(conn, payload.device_id).await?;
+    let severity = payload.severity.unwrap_or_else(|| SEVERITY_WARNING.to_string());
+    check_severity(&severity)?;
+
+    let now = chrono::Utc::now();
     let new_alarm_log = AlarmLogActiveModel {
         rule_name: sea_orm::Set(payload.rule_name),
-        trigger_time: sea_orm::Set(chrono::Utc::now()),
+        trigger_time: sea_orm::Set(now),
         trigger_value: sea_orm::Set(payload.trigger_value),
         is_processed: sea_orm::Set(payload.is_processed),
+        device_id: sea_orm::Set(payload.device_id),
+        severity: sea_orm::Set(severity),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
         ..Default::default()
     };
 
@@ -146,15 +186,12 @@ pub async fn create_alarm_log(
 pub async fn update_alarm_log(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
     Json(payload): Json<UpdateAlarmLogRequest>,
 ) -> Result<Json<AlarmLog>, AppError> {
     let conn = state.db.get_connection();
     
-    let existing_alarm_log = AlarmLogEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
+    let existing_alarm_log = find_alarm_log(conn, tenant, id).await?;
         
     let mut alarm_log_active_model = existing_alarm_log.into_active_model();
     
@@ -169,6 +206,11 @@ pub async fn update_alarm_log(
     if let Some(is_processed) = payload.is_processed {
         alarm_log_active_model.is_processed = sea_orm::Set(is_processed);
     }
+
+    if let Some(severity) = payload.severity {
+        check_severity(&severity)?;
+        alarm_log_active_model.severity = sea_orm::Set(severity);
+    }
     
     // 更新 updated_at 字段
     alarm_log_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
@@ -197,14 +239,11 @@ pub async fn update_alarm_log(
 pub async fn delete_alarm_log(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let conn = state.db.get_connection();
     
-    let alarm_log = AlarmLogEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
+    let alarm_log = find_alarm_log(conn, tenant, id).await?;
 
     let _ = AlarmLogEntity::delete_by_id(alarm_log.id)
         .exec(conn)
@@ -212,4 +251,28 @@ pub async fn delete_alarm_log(
         .map_err(|_| AppError::InternalError)?;
 
     Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取报警前后的过程数据快照，报警窗口结束后才会生成
+#[utoipa::path(
+    get,
+    path = "/alarm-logs/{id}/snapshot",
+    params(
+        ("id" = i32, Path, description = "报警日志ID")
+    ),
+    responses(
+        (status = 200, description = "获取快照成功", body = AlarmSnapshotView),
+        (status = 404, description = "尚未生成或没有相关数据")
+    ),
+    tag = "Alarm Logs"
+)]
+pub async fn get_alarm_snapshot(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<AlarmSnapshotView>, AppError> {
+    let conn = state.db.get_connection();
+    find_alarm_log(conn, tenant, id).await?;
+    let snapshot = alarm_snapshot::get(conn, id).await?;
+    Ok(Json(snapshot))
 }
\ No newline at end of file
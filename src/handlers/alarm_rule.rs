@@ -1,12 +1,16 @@
 use crate::app_state::AppState;
-use crate::models::alarm_rule::{Entity as AlarmRuleEntity, Model as AlarmRule, ActiveModel as AlarmRuleActiveModel};
+use crate::models::alarm_rule::{Entity as AlarmRuleEntity, Model as AlarmRule, ActiveModel as AlarmRuleActiveModel, Column as AlarmRuleColumn};
+use crate::services::alarm_template::{self, AlarmRuleTemplate};
+use crate::services::config_revision;
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
+use sea_orm::{EntityTrait, IntoActiveModel, TryIntoModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -18,6 +22,9 @@ pub struct CreateAlarmRuleRequest {
     pub condition: String,
     pub parameter: String,
     pub value: f64,
+    /// 所属组织，租户 Key 只能建在本组织
+    #[serde(default)]
+    pub organization_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -28,6 +35,15 @@ pub struct UpdateAlarmRuleRequest {
     pub value: Option<f64>,
 }
 
+/// 由模板创建报警规则，未给出的项取模板的默认名称和限值
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct CreateFromTemplateRequest {
+    pub name: Option<String>,
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub organization_id: Option<i32>,
+}
+
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct Pagination {
     pub page: Option<u64>,
@@ -47,13 +63,15 @@ pub struct Pagination {
 pub async fn get_alarm_rules(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<AlarmRule>>, AppError> {
     let conn = state.db.get_connection();
     
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
     
-    let alarm_rules = AlarmRuleEntity::find()
+    let alarm_rules = tenant
+        .scope(AlarmRuleEntity::find(), AlarmRuleColumn::OrganizationId)
         .all(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
@@ -86,6 +104,7 @@ pub async fn get_alarm_rules(
 pub async fn get_alarm_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<AlarmRule>, AppError> {
     let conn = state.db.get_connection();
     
@@ -94,6 +113,7 @@ pub async fn get_alarm_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(alarm_rule.organization_id)?;
 
     Ok(Json(alarm_rule))
 }
@@ -105,21 +125,53 @@ pub async fn get_alarm_rule(
     request_body = CreateAlarmRuleRequest,
     responses(
         (status = 201, description = "创建报警规则成功", body = AlarmRule),
-        (status = 400, description = "请求参数错误")
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不能在其他组织下创建报警规则")
     ),
     tag = "Alarm Rules"
 )]
 pub async fn create_alarm_rule(
     State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<CreateAlarmRuleRequest>,
-) -> Result<(StatusCode, Json<AlarmRule>), AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
+    let organization_id = tenant.assign(payload.organization_id)?;
+    let now = chrono::Utc::now();
+
+    // 审批模式下只提交待审批版本
+    if config_revision::requires_review(&state.settings.change_control, config_revision::ALARM_RULE) {
+        let proposed = AlarmRule {
+            id: 0,
+            name: payload.name,
+            condition: payload.condition,
+            parameter: payload.parameter,
+            value: payload.value,
+            organization_id,
+            created_at: now,
+            updated_at: now,
+        };
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_CREATE,
+            None,
+            Some(&proposed),
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
     
     let new_alarm_rule = AlarmRuleActiveModel {
         name: sea_orm::Set(payload.name),
         condition: sea_orm::Set(payload.condition),
         parameter: sea_orm::Set(payload.parameter),
         value: sea_orm::Set(payload.value),
+        organization_id: sea_orm::Set(organization_id),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
         ..Default::default()
     };
 
@@ -128,7 +180,9 @@ pub async fn create_alarm_rule(
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok((StatusCode::CREATED, Json(alarm_rule)))
+    config_revision::record_create(conn, &alarm_rule, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(alarm_rule)).into_response())
 }
 
 /// 更新报警规则
@@ -141,6 +195,7 @@ pub async fn create_alarm_rule(
     request_body = UpdateAlarmRuleRequest,
     responses(
         (status = 200, description = "更新报警规则成功", body = AlarmRule),
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
         (status = 404, description = "报警规则未找到")
     ),
     tag = "Alarm Rules"
@@ -148,8 +203,10 @@ pub async fn create_alarm_rule(
 pub async fn update_alarm_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdateAlarmRuleRequest>,
-) -> Result<Json<AlarmRule>, AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
     
     let existing_alarm_rule = AlarmRuleEntity::find_by_id(id)
@@ -157,8 +214,9 @@ pub async fn update_alarm_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(existing_alarm_rule.organization_id)?;
         
-    let mut alarm_rule_active_model = existing_alarm_rule.into_active_model();
+    let mut alarm_rule_active_model = existing_alarm_rule.clone().into_active_model();
     
     if let Some(name) = payload.name {
         alarm_rule_active_model.name = sea_orm::Set(name);
@@ -178,13 +236,28 @@ pub async fn update_alarm_rule(
     
     // 更新 updated_at 字段
     alarm_rule_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    if config_revision::requires_review(&state.settings.change_control, config_revision::ALARM_RULE) {
+        let proposed = alarm_rule_active_model.try_into_model().map_err(|_| AppError::InternalError)?;
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_UPDATE,
+            Some(&existing_alarm_rule),
+            Some(&proposed),
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
     
     let updated_alarm_rule = AlarmRuleEntity::update(alarm_rule_active_model)
         .exec(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok(Json(updated_alarm_rule))
+    config_revision::record_update(conn, &existing_alarm_rule, &updated_alarm_rule, operator).await?;
+
+    Ok(Json(updated_alarm_rule).into_response())
 }
 
 /// 删除报警规则
@@ -196,6 +269,7 @@ pub async fn update_alarm_rule(
     ),
     responses(
         (status = 204, description = "删除报警规则成功"),
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
         (status = 404, description = "报警规则未找到")
     ),
     tag = "Alarm Rules"
@@ -203,7 +277,9 @@ pub async fn update_alarm_rule(
 pub async fn delete_alarm_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
-) -> Result<StatusCode, AppError> {
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
     
     let alarm_rule = AlarmRuleEntity::find_by_id(id)
@@ -211,11 +287,72 @@ pub async fn delete_alarm_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(alarm_rule.organization_id)?;
+
+    if config_revision::requires_review(&state.settings.change_control, config_revision::ALARM_RULE) {
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_DELETE,
+            Some(&alarm_rule),
+            None,
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
 
     let _ = AlarmRuleEntity::delete_by_id(alarm_rule.id)
         .exec(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+    config_revision::record_delete(conn, &alarm_rule, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// 获取报警规则模板（高/低液位、高/低压力）
+#[utoipa::path(
+    get,
+    path = "/alarm-rules/templates",
+    responses(
+        (status = 200, description = "获取报警规则模板成功", body = [AlarmRuleTemplate])
+    ),
+    tag = "Alarm Rules"
+)]
+pub async fn get_alarm_rule_templates() -> Json<Vec<AlarmRuleTemplate>> {
+    Json(alarm_template::TEMPLATES.to_vec())
+}
+
+/// 由模板创建报警规则，与直接创建一样受变更审批控制
+#[utoipa::path(
+    post,
+    path = "/alarm-rules/templates/{key}",
+    params(
+        ("key" = String, Path, description = "模板标识")
+    ),
+    request_body = CreateFromTemplateRequest,
+    responses(
+        (status = 201, description = "创建报警规则成功", body = AlarmRule),
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
+        (status = 404, description = "模板不存在")
+    ),
+    tag = "Alarm Rules"
+)]
+pub async fn create_alarm_rule_from_template(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    operator: Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateFromTemplateRequest>,
+) -> Result<Response, AppError> {
+    let template = alarm_template::lookup(&key).ok_or(AppError::NotFound)?;
+    let request = CreateAlarmRuleRequest {
+        name: payload.name.unwrap_or_else(|| template.name.to_string()),
+        condition: template.condition.to_string(),
+        parameter: template.parameter.to_string(),
+        value: payload.value.unwrap_or(template.default_value),
+        organization_id: payload.organization_id,
+    };
+    create_alarm_rule(State(state), operator, tenant, Json(request)).await
+}
@@ -0,0 +1,144 @@
+use crate::app_state::AppState;
+use crate::models::alarm_shelf::Model as AlarmShelf;
+use crate::services::alarm_shelving::{self, ShelvingStats};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ShelveAlarmRequest {
+    /// 搁置时长（分钟），不超过配置的上限
+    pub duration_minutes: u64,
+    /// 搁置原因
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlarmShelfQuery {
+    /// 只返回生效中的搁置
+    #[serde(default)]
+    pub active: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ShelvingStatsQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// 搁置报警记录所在的报警点，需通过 `X-Operator` 提供操作人
+#[utoipa::path(
+    post,
+    path = "/alarm-logs/{id}/shelve",
+    params(("id" = i32, Path, description = "报警记录ID")),
+    request_body = ShelveAlarmRequest,
+    responses(
+        (status = 201, description = "搁置成功", body = AlarmShelf),
+        (status = 400, description = "未填写原因、时长超限或报警点已在搁置中"),
+        (status = 403, description = "未提供操作人"),
+        (status = 404, description = "报警记录不存在")
+    ),
+    tag = "Alarm Shelving"
+)]
+pub async fn shelve_alarm(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<ShelveAlarmRequest>,
+) -> Result<(StatusCode, Json<AlarmShelf>), AppError> {
+    let operator = operator.ok_or(AppError::Forbidden)?;
+    let conn = state.db.get_connection();
+    tenant.check_alarm_log(conn, id).await?;
+    let shelf = alarm_shelving::shelve(
+        conn,
+        id,
+        payload.duration_minutes,
+        &payload.reason,
+        &operator,
+        state.settings.alarm.max_shelve_minutes,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(shelf)))
+}
+
+/// 获取搁置记录
+#[utoipa::path(
+    get,
+    path = "/alarm-shelves",
+    params(AlarmShelfQuery),
+    responses(
+        (status = 200, description = "获取搁置记录成功", body = [AlarmShelf])
+    ),
+    tag = "Alarm Shelving"
+)]
+pub async fn get_alarm_shelves(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AlarmShelfQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<AlarmShelf>>, AppError> {
+    let shelves =
+        alarm_shelving::list(state.db.get_connection(), query.active, tenant.organization_id())
+            .await?;
+    Ok(Json(shelves))
+}
+
+/// 提前解除搁置，需通过 `X-Operator` 提供操作人
+#[utoipa::path(
+    delete,
+    path = "/alarm-shelves/{id}",
+    params(("id" = i32, Path, description = "搁置记录ID")),
+    responses(
+        (status = 200, description = "解除成功", body = AlarmShelf),
+        (status = 400, description = "搁置已结束"),
+        (status = 403, description = "未提供操作人"),
+        (status = 404, description = "搁置记录不存在")
+    ),
+    tag = "Alarm Shelving"
+)]
+pub async fn unshelve_alarm(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Json<AlarmShelf>, AppError> {
+    let operator = operator.ok_or(AppError::Forbidden)?;
+    let conn = state.db.get_connection();
+    if tenant != Tenant::All {
+        let shelf = alarm_shelving::get(conn, id).await?;
+        tenant.check_device(conn, shelf.device_id).await?;
+    }
+    let shelf = alarm_shelving::unshelve(conn, id, &operator).await?;
+    Ok(Json(shelf))
+}
+
+/// 搁置统计
+#[utoipa::path(
+    get,
+    path = "/alarm-shelves/stats",
+    params(ShelvingStatsQuery),
+    responses(
+        (status = 200, description = "获取搁置统计成功", body = ShelvingStats),
+        (status = 400, description = "时间段无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Alarm Shelving"
+)]
+pub async fn get_shelving_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ShelvingStatsQuery>,
+    tenant: Tenant,
+) -> Result<Json<ShelvingStats>, AppError> {
+    tenant.require_platform()?;
+    let stats = alarm_shelving::stats(state.db.get_connection(), query.start, query.end).await?;
+    Ok(Json(stats))
+}
@@ -0,0 +1,177 @@
+use crate::app_state::AppState;
+use crate::middleware::api_key::AdminKey;
+use crate::models::api_key::{Entity as ApiKeyEntity, Model as ApiKey, Column as ApiKeyColumn};
+use crate::services::api_key as api_key_service;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use sea_orm::{EntityTrait, IntoActiveModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// 权限范围：ingest、read、admin、control、override，不能超出调用方 Key 的权限
+    pub scopes: Vec<String>,
+    /// 所属组织，不填为平台管理 Key；租户 Key 只能签发本组织的 Key
+    #[serde(default)]
+    pub organization_id: Option<i32>,
+}
+
+/// 签发结果，`key` 仅在此时返回一次
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssuedApiKeyResponse {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+/// 获取API Key列表
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    responses(
+        (status = 200, description = "获取API Key列表成功", body = [ApiKey])
+    ),
+    tag = "API Keys"
+)]
+pub async fn get_api_keys(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let api_keys = tenant
+        .scope(ApiKeyEntity::find(), ApiKeyColumn::OrganizationId)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(api_keys))
+}
+
+/// 调用方只能签发或轮换权限不超过自身的 Key
+fn check_scopes<'a>(
+    caller: &ApiKey,
+    scopes: impl IntoIterator<Item = &'a str>,
+) -> Result<(), AppError> {
+    if scopes.into_iter().all(|scope| caller.has_scope(scope.trim())) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
+
+/// 签发API Key，需使用具备 admin 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "签发API Key成功", body = IssuedApiKeyResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key、权限超出调用方或不能为其他组织签发")
+    ),
+    tag = "API Keys"
+)]
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    AdminKey(caller): AdminKey,
+    tenant: Tenant,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<IssuedApiKeyResponse>), AppError> {
+    let conn = state.db.get_connection();
+
+    api_key_service::normalize_scopes(&payload.scopes)?;
+    check_scopes(&caller, payload.scopes.iter().map(String::as_str))?;
+    let organization_id = tenant.assign(payload.organization_id)?;
+    let (api_key, key) =
+        api_key_service::issue(conn, payload.name, &payload.scopes, organization_id).await?;
+
+    Ok((StatusCode::CREATED, Json(IssuedApiKeyResponse { api_key, key })))
+}
+
+/// 轮换API Key，旧Key立即失效，需使用具备 admin 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/api-keys/{id}/rotate",
+    params(
+        ("id" = i32, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 200, description = "轮换API Key成功", body = IssuedApiKeyResponse),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key 或权限超出调用方"),
+        (status = 404, description = "API Key未找到")
+    ),
+    tag = "API Keys"
+)]
+pub async fn rotate_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    AdminKey(caller): AdminKey,
+    tenant: Tenant,
+) -> Result<Json<IssuedApiKeyResponse>, AppError> {
+    let conn = state.db.get_connection();
+
+    let existing_api_key = ApiKeyEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(existing_api_key.organization_id)?;
+    // 轮换会返回新明文，不能借此取得权限更高的 Key
+    check_scopes(&caller, existing_api_key.scopes.split(','))?;
+
+    let (api_key, key) = api_key_service::rotate(conn, existing_api_key).await?;
+
+    Ok(Json(IssuedApiKeyResponse { api_key, key }))
+}
+
+/// 吊销API Key，需使用具备 admin 权限的 Key 调用
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    params(
+        ("id" = i32, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 204, description = "吊销API Key成功"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key"),
+        (status = 404, description = "API Key未找到")
+    ),
+    tag = "API Keys"
+)]
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    AdminKey(_): AdminKey,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+
+    let existing_api_key = ApiKeyEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(existing_api_key.organization_id)?;
+
+    let mut api_key_active_model = existing_api_key.into_active_model();
+    api_key_active_model.revoked = sea_orm::Set(true);
+    api_key_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    ApiKeyEntity::update(api_key_active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,50 @@
+use crate::app_state::AppState;
+use crate::services::api_usage::{self, EndpointUsage};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ApiUsageQuery {
+    /// 默认为结束时间前 24 小时
+    pub start: Option<DateTime<Utc>>,
+    /// 默认为当前时间
+    pub end: Option<DateTime<Utc>>,
+    /// 客户端，例如 `key:gk_ab12cd34` 或 `ip:10.0.0.5`，为空时返回全部客户端
+    pub client: Option<String>,
+}
+
+/// 按客户端和接口的调用统计
+///
+/// 统计按整小时汇总，最近一个写入间隔内的调用尚未计入。`raw_data` 标记原始数据接口，
+/// 调用量大的客户端应改用 `/measurements/aggregate` 或 `/trend`。
+#[utoipa::path(
+    get,
+    path = "/api-usage",
+    params(ApiUsageQuery),
+    responses(
+        (status = 200, description = "获取调用统计成功", body = [EndpointUsage]),
+        (status = 400, description = "时间段无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "API Usage"
+)]
+pub async fn get_api_usage(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ApiUsageQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<EndpointUsage>>, AppError> {
+    tenant.require_platform()?;
+    let end = query.end.unwrap_or_else(Utc::now);
+    let start = query.start.unwrap_or(end - Duration::hours(24));
+    let usage =
+        api_usage::report(state.db.get_connection(), start, end, query.client.as_deref()).await?;
+    Ok(Json(usage))
+}
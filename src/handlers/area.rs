@@ -0,0 +1,279 @@
+use crate::app_state::AppState;
+use crate::models::area::{Column as AreaColumn, Entity as AreaEntity, Model as Area, ActiveModel as AreaActiveModel};
+use crate::models::device::Model as Device;
+use crate::models::measurement::Model as Measurement;
+use crate::services::{measurement as measurement_service, site as site_service};
+use crate::utils::error::AppError;
+use crate::utils::serde_ext::double_option;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State, Query},
+    http::StatusCode,
+    response::Json,
+};
+use sea_orm::{ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateAreaRequest {
+    pub site_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateAreaRequest {
+    /// 传 null 表示移到厂站顶级
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent_id: Option<Option<i32>>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AreaQuery {
+    /// 按厂站过滤
+    pub site_id: Option<i32>,
+}
+
+/// 获取区域列表
+#[utoipa::path(
+    get,
+    path = "/areas",
+    params(AreaQuery),
+    responses(
+        (status = 200, description = "获取区域列表成功", body = [Area])
+    ),
+    tag = "Sites"
+)]
+pub async fn get_areas(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AreaQuery>,
+) -> Result<Json<Vec<Area>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mut select = AreaEntity::find();
+    if let Some(site_id) = query.site_id {
+        select = select.filter(AreaColumn::SiteId.eq(site_id));
+    }
+    let areas = select.all(conn).await.map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(areas))
+}
+
+/// 获取指定区域
+#[utoipa::path(
+    get,
+    path = "/areas/{id}",
+    params(
+        ("id" = i32, Path, description = "区域ID")
+    ),
+    responses(
+        (status = 200, description = "获取区域成功", body = Area),
+        (status = 404, description = "区域未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_area(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Area>, AppError> {
+    let conn = state.db.get_connection();
+
+    let area = site_service::get_area(conn, id).await?;
+
+    Ok(Json(area))
+}
+
+/// 创建区域
+#[utoipa::path(
+    post,
+    path = "/areas",
+    request_body = CreateAreaRequest,
+    responses(
+        (status = 201, description = "创建区域成功", body = Area),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Sites"
+)]
+pub async fn create_area(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateAreaRequest>,
+) -> Result<(StatusCode, Json<Area>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    site_service::get_site(conn, payload.site_id)
+        .await
+        .map_err(|_| AppError::InvalidInput(format!("厂站不存在: {}", payload.site_id).into()))?;
+    site_service::check_parent(conn, payload.site_id, None, payload.parent_id).await?;
+
+    let now = chrono::Utc::now();
+    let new_area = AreaActiveModel {
+        site_id: sea_orm::Set(payload.site_id),
+        parent_id: sea_orm::Set(payload.parent_id),
+        name: sea_orm::Set(payload.name),
+        description: sea_orm::Set(payload.description),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
+        ..Default::default()
+    };
+
+    let area = AreaEntity::insert(new_area)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(area)))
+}
+
+/// 更新区域
+#[utoipa::path(
+    put,
+    path = "/areas/{id}",
+    params(
+        ("id" = i32, Path, description = "区域ID")
+    ),
+    request_body = UpdateAreaRequest,
+    responses(
+        (status = 200, description = "更新区域成功", body = Area),
+        (status = 400, description = "上级区域无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "区域未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn update_area(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdateAreaRequest>,
+) -> Result<Json<Area>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let existing_area = site_service::get_area(conn, id).await?;
+    if let Some(parent_id) = payload.parent_id {
+        site_service::check_parent(conn, existing_area.site_id, Some(existing_area.id), parent_id).await?;
+    }
+
+    let mut area_active_model = existing_area.into_active_model();
+
+    if let Some(parent_id) = payload.parent_id {
+        area_active_model.parent_id = sea_orm::Set(parent_id);
+    }
+    if let Some(name) = payload.name {
+        area_active_model.name = sea_orm::Set(name);
+    }
+    if let Some(description) = payload.description {
+        area_active_model.description = sea_orm::Set(description);
+    }
+    area_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    let updated_area = AreaEntity::update(area_active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(updated_area))
+}
+
+/// 删除区域，区域下须没有下级区域和设备
+#[utoipa::path(
+    delete,
+    path = "/areas/{id}",
+    params(
+        ("id" = i32, Path, description = "区域ID")
+    ),
+    responses(
+        (status = 204, description = "删除区域成功"),
+        (status = 400, description = "区域下仍有下级区域或设备"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "区域未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn delete_area(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let area = site_service::get_area(conn, id).await?;
+    site_service::ensure_area_empty(conn, area.id).await?;
+
+    AreaEntity::delete_by_id(area.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取区域（含下级区域）下的全部设备
+#[utoipa::path(
+    get,
+    path = "/areas/{id}/devices",
+    params(
+        ("id" = i32, Path, description = "区域ID")
+    ),
+    responses(
+        (status = 200, description = "获取设备成功", body = [Device]),
+        (status = 404, description = "区域未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_area_devices(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Device>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let area = site_service::get_area(conn, id).await?;
+    let mut devices = site_service::devices_in_area(conn, &area).await?;
+    devices.retain(|d| tenant.owns(d.organization_id));
+
+    Ok(Json(devices))
+}
+
+/// 获取区域（含下级区域）下各设备每个指标的最新测量值
+#[utoipa::path(
+    get,
+    path = "/areas/{id}/latest",
+    params(
+        ("id" = i32, Path, description = "区域ID")
+    ),
+    responses(
+        (status = 200, description = "获取最新测量值成功", body = [Measurement]),
+        (status = 404, description = "区域未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_area_latest(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Measurement>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let area = site_service::get_area(conn, id).await?;
+    let device_ids: Vec<i32> = site_service::devices_in_area(conn, &area)
+        .await?
+        .into_iter()
+        .filter(|d| tenant.owns(d.organization_id))
+        .map(|d| d.id)
+        .collect();
+    let measurements = measurement_service::latest_for_devices(conn, &device_ids).await?;
+
+    Ok(Json(measurements))
+}
@@ -1,12 +1,17 @@
 use crate::app_state::AppState;
-use crate::models::automation_rule::{Entity as AutomationRuleEntity, Model as AutomationRule, ActiveModel as AutomationRuleActiveModel};
+use crate::models::automation_rule::{Entity as AutomationRuleEntity, Model as AutomationRule, ActiveModel as AutomationRuleActiveModel, Column as AutomationRuleColumn};
+use crate::services::{automation, config_revision, site as site_service};
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::serde_ext::double_option;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
+use chrono::{DateTime, Utc};
+use sea_orm::{EntityTrait, IntoActiveModel, TryIntoModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -16,8 +21,15 @@ use utoipa::IntoParams;
 pub struct CreateAutomationRuleRequest {
     pub action: String,
     pub level: i32,
+    /// 本地时段 `HH:MM-HH:MM`，结束不晚于开始表示跨零点，为空表示全天
     pub trigger_time_range: String,
     pub sync_alarm: bool,
+    /// 所属厂站，触发时间段按厂站时区解释；为空时用默认时区
+    #[serde(default)]
+    pub site_id: Option<i32>,
+    /// 所属组织，租户 Key 只能建在本组织
+    #[serde(default)]
+    pub organization_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -26,6 +38,15 @@ pub struct UpdateAutomationRuleRequest {
     pub level: Option<i32>,
     pub trigger_time_range: Option<String>,
     pub sync_alarm: Option<bool>,
+    /// 传 null 表示不再关联厂站
+    #[serde(default, deserialize_with = "double_option")]
+    pub site_id: Option<Option<i32>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ActiveRuleQuery {
+    /// 查询时刻，默认当前
+    pub at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -47,13 +68,15 @@ pub struct Pagination {
 pub async fn get_automation_rules(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<AutomationRule>>, AppError> {
     let conn = state.db.get_connection();
     
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
     
-    let automation_rules = AutomationRuleEntity::find()
+    let automation_rules = tenant
+        .scope(AutomationRuleEntity::find(), AutomationRuleColumn::OrganizationId)
         .all(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
@@ -70,6 +93,35 @@ pub async fn get_automation_rules(
     Ok(Json(paginated_automation_rules))
 }
 
+/// 获取某一时刻处于触发时间段内的自动化规则，各规则按所属厂站的本地时间判断
+#[utoipa::path(
+    get,
+    path = "/automation-rules/active",
+    params(ActiveRuleQuery),
+    responses(
+        (status = 200, description = "获取生效的自动化规则成功", body = [AutomationRule])
+    ),
+    tag = "Automation Rules"
+)]
+pub async fn get_active_automation_rules(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ActiveRuleQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<AutomationRule>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let automation_rules = tenant
+        .scope(AutomationRuleEntity::find(), AutomationRuleColumn::OrganizationId)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let at = query.at.unwrap_or_else(Utc::now);
+    let active =
+        automation::active_rules(conn, &state.settings.timezone, automation_rules, at).await?;
+
+    Ok(Json(active))
+}
+
 /// 获取指定自动化规则
 #[utoipa::path(
     get,
@@ -86,6 +138,7 @@ pub async fn get_automation_rules(
 pub async fn get_automation_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<AutomationRule>, AppError> {
     let conn = state.db.get_connection();
     
@@ -94,6 +147,7 @@ pub async fn get_automation_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(automation_rule.organization_id)?;
 
     Ok(Json(automation_rule))
 }
@@ -105,21 +159,57 @@ pub async fn get_automation_rule(
     request_body = CreateAutomationRuleRequest,
     responses(
         (status = 201, description = "创建自动化规则成功", body = AutomationRule),
-        (status = 400, description = "请求参数错误")
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不能在其他组织下创建自动化规则")
     ),
     tag = "Automation Rules"
 )]
 pub async fn create_automation_rule(
     State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<CreateAutomationRuleRequest>,
-) -> Result<(StatusCode, Json<AutomationRule>), AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
+    let organization_id = tenant.assign(payload.organization_id)?;
+    automation::parse_window(&payload.trigger_time_range)?;
+    let (site_id, _) = site_service::resolve_placement(conn, payload.site_id, None).await?;
+    let now = chrono::Utc::now();
+
+    // 审批模式下只提交待审批版本
+    if config_revision::requires_review(&state.settings.change_control, config_revision::AUTOMATION_RULE) {
+        let proposed = AutomationRule {
+            id: 0,
+            action: payload.action,
+            level: payload.level,
+            trigger_time_range: payload.trigger_time_range,
+            site_id,
+            sync_alarm: payload.sync_alarm,
+            organization_id,
+            created_at: now,
+            updated_at: now,
+        };
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_CREATE,
+            None,
+            Some(&proposed),
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
     
     let new_automation_rule = AutomationRuleActiveModel {
         action: sea_orm::Set(payload.action),
         level: sea_orm::Set(payload.level),
         trigger_time_range: sea_orm::Set(payload.trigger_time_range),
+        site_id: sea_orm::Set(site_id),
         sync_alarm: sea_orm::Set(payload.sync_alarm),
+        organization_id: sea_orm::Set(organization_id),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
         ..Default::default()
     };
 
@@ -128,7 +218,9 @@ pub async fn create_automation_rule(
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok((StatusCode::CREATED, Json(automation_rule)))
+    config_revision::record_create(conn, &automation_rule, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(automation_rule)).into_response())
 }
 
 /// 更新自动化规则
@@ -141,6 +233,8 @@ pub async fn create_automation_rule(
     request_body = UpdateAutomationRuleRequest,
     responses(
         (status = 200, description = "更新自动化规则成功", body = AutomationRule),
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
+        (status = 400, description = "触发时间段或厂站无效"),
         (status = 404, description = "自动化规则未找到")
     ),
     tag = "Automation Rules"
@@ -148,8 +242,10 @@ pub async fn create_automation_rule(
 pub async fn update_automation_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdateAutomationRuleRequest>,
-) -> Result<Json<AutomationRule>, AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
     
     let existing_automation_rule = AutomationRuleEntity::find_by_id(id)
@@ -157,8 +253,9 @@ pub async fn update_automation_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(existing_automation_rule.organization_id)?;
         
-    let mut automation_rule_active_model = existing_automation_rule.into_active_model();
+    let mut automation_rule_active_model = existing_automation_rule.clone().into_active_model();
     
     if let Some(action) = payload.action {
         automation_rule_active_model.action = sea_orm::Set(action);
@@ -169,8 +266,14 @@ pub async fn update_automation_rule(
     }
     
     if let Some(trigger_time_range) = payload.trigger_time_range {
+        automation::parse_window(&trigger_time_range)?;
         automation_rule_active_model.trigger_time_range = sea_orm::Set(trigger_time_range);
     }
+
+    if let Some(site_id) = payload.site_id {
+        let (site_id, _) = site_service::resolve_placement(conn, site_id, None).await?;
+        automation_rule_active_model.site_id = sea_orm::Set(site_id);
+    }
     
     if let Some(sync_alarm) = payload.sync_alarm {
         automation_rule_active_model.sync_alarm = sea_orm::Set(sync_alarm);
@@ -178,13 +281,28 @@ pub async fn update_automation_rule(
     
     // 更新 updated_at 字段
     automation_rule_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    if config_revision::requires_review(&state.settings.change_control, config_revision::AUTOMATION_RULE) {
+        let proposed = automation_rule_active_model.try_into_model().map_err(|_| AppError::InternalError)?;
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_UPDATE,
+            Some(&existing_automation_rule),
+            Some(&proposed),
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
     
     let updated_automation_rule = AutomationRuleEntity::update(automation_rule_active_model)
         .exec(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok(Json(updated_automation_rule))
+    config_revision::record_update(conn, &existing_automation_rule, &updated_automation_rule, operator).await?;
+
+    Ok(Json(updated_automation_rule).into_response())
 }
 
 /// 删除自动化规则
@@ -196,6 +314,7 @@ pub async fn update_automation_rule(
     ),
     responses(
         (status = 204, description = "删除自动化规则成功"),
+        (status = 202, description = "已提交审批", body = crate::models::config_revision::Model),
         (status = 404, description = "自动化规则未找到")
     ),
     tag = "Automation Rules"
@@ -203,7 +322,9 @@ pub async fn update_automation_rule(
 pub async fn delete_automation_rule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
-) -> Result<StatusCode, AppError> {
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
     
     let automation_rule = AutomationRuleEntity::find_by_id(id)
@@ -211,11 +332,26 @@ pub async fn delete_automation_rule(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(automation_rule.organization_id)?;
+
+    if config_revision::requires_review(&state.settings.change_control, config_revision::AUTOMATION_RULE) {
+        let revision = config_revision::propose(
+            conn,
+            config_revision::ACTION_DELETE,
+            Some(&automation_rule),
+            None,
+            operator,
+        )
+        .await?;
+        return Ok((StatusCode::ACCEPTED, Json(revision)).into_response());
+    }
 
     let _ = AutomationRuleEntity::delete_by_id(automation_rule.id)
         .exec(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
-    Ok(StatusCode::NO_CONTENT)
+    config_revision::record_delete(conn, &automation_rule, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
\ No newline at end of file
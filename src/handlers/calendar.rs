@@ -0,0 +1,515 @@
+use crate::app_state::AppState;
+use crate::models::calendar_day::{
+    Column as DayColumn, Entity as DayEntity, Model as CalendarDay,
+};
+use crate::models::calendar_shift::{
+    Column as ShiftColumn, Entity as ShiftEntity, Model as CalendarShift,
+};
+use crate::models::site::Entity as SiteEntity;
+use crate::services::calendar::{self, Calendar, CalendarStatus, ShiftPeriod};
+use crate::services::config_revision;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use crate::utils::timezone;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set,
+    TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCalendarShiftRequest {
+    /// 空表示所有厂站
+    pub site_id: Option<i32>,
+    pub name: String,
+    /// 逗号分隔，1 为周一，例如 `1,2,3,4,5`
+    pub weekdays: String,
+    /// `HH:MM`
+    pub start_time: String,
+    /// `HH:MM`，不晚于开始时间表示跨零点
+    pub end_time: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateCalendarShiftRequest {
+    pub name: Option<String>,
+    pub weekdays: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCalendarDayRequest {
+    /// 空表示全局（法定节假日）
+    pub site_id: Option<i32>,
+    pub date: NaiveDate,
+    /// holiday / workday
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateCalendarDayRequest {
+    pub kind: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ShiftQuery {
+    pub site_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DayQuery {
+    pub site_id: Option<i32>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StatusQuery {
+    /// 厂站，空表示只按全局日历
+    pub site_id: Option<i32>,
+    /// 查询时刻，默认当前
+    pub at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PeriodQuery {
+    pub site_id: Option<i32>,
+    /// 本地日期，默认今天
+    pub date: Option<NaiveDate>,
+}
+
+async fn ensure_site(conn: &DatabaseConnection, site_id: Option<i32>) -> Result<(), AppError> {
+    let Some(site_id) = site_id else {
+        return Ok(());
+    };
+    SiteEntity::find_by_id(site_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::InvalidInput(format!("厂站不存在: {}", site_id).into()))?;
+    Ok(())
+}
+
+/// 获取班次列表
+#[utoipa::path(
+    get,
+    path = "/calendar/shifts",
+    params(ShiftQuery),
+    responses(
+        (status = 200, description = "获取班次列表成功", body = [CalendarShift])
+    ),
+    tag = "Calendar"
+)]
+pub async fn get_shifts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ShiftQuery>,
+) -> Result<Json<Vec<CalendarShift>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mut select = ShiftEntity::find();
+    if let Some(site_id) = query.site_id {
+        select = select.filter(ShiftColumn::SiteId.eq(site_id));
+    }
+    let shifts = select
+        .order_by_asc(ShiftColumn::StartTime)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(shifts))
+}
+
+/// 创建班次
+#[utoipa::path(
+    post,
+    path = "/calendar/shifts",
+    request_body = CreateCalendarShiftRequest,
+    responses(
+        (status = 201, description = "创建班次成功", body = CalendarShift),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Calendar"
+)]
+pub async fn create_shift(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateCalendarShiftRequest>,
+) -> Result<(StatusCode, Json<CalendarShift>), AppError> {
+    // 生产日历全厂共用，租户只能查看
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    ensure_site(conn, payload.site_id).await?;
+
+    let now = Utc::now();
+    let shift = CalendarShift {
+        id: 0,
+        site_id: payload.site_id,
+        name: payload.name,
+        weekdays: payload.weekdays,
+        start_time: payload.start_time,
+        end_time: payload.end_time,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    calendar::validate_shift(&shift)?;
+
+    let mut active_model = shift.into_active_model();
+    active_model.id = Default::default();
+    let shift = ShiftEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &shift, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(shift)))
+}
+
+/// 更新班次
+#[utoipa::path(
+    put,
+    path = "/calendar/shifts/{id}",
+    params(
+        ("id" = i32, Path, description = "班次ID")
+    ),
+    request_body = UpdateCalendarShiftRequest,
+    responses(
+        (status = 200, description = "更新班次成功", body = CalendarShift),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "班次未找到")
+    ),
+    tag = "Calendar"
+)]
+pub async fn update_shift(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateCalendarShiftRequest>,
+) -> Result<Json<CalendarShift>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let existing = ShiftEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(weekdays) = payload.weekdays {
+        active_model.weekdays = Set(weekdays);
+    }
+    if let Some(start_time) = payload.start_time {
+        active_model.start_time = Set(start_time);
+    }
+    if let Some(end_time) = payload.end_time {
+        active_model.end_time = Set(end_time);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    calendar::validate_shift(&proposed)?;
+
+    let updated = ShiftEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除班次
+#[utoipa::path(
+    delete,
+    path = "/calendar/shifts/{id}",
+    params(
+        ("id" = i32, Path, description = "班次ID")
+    ),
+    responses(
+        (status = 204, description = "删除班次成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "班次未找到")
+    ),
+    tag = "Calendar"
+)]
+pub async fn delete_shift(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let shift = ShiftEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    ShiftEntity::delete_by_id(shift.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &shift, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取特殊日期列表（节假日、调休、厂站停产日）
+#[utoipa::path(
+    get,
+    path = "/calendar/days",
+    params(DayQuery),
+    responses(
+        (status = 200, description = "获取特殊日期列表成功", body = [CalendarDay])
+    ),
+    tag = "Calendar"
+)]
+pub async fn get_days(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DayQuery>,
+) -> Result<Json<Vec<CalendarDay>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mut select = DayEntity::find();
+    if let Some(site_id) = query.site_id {
+        select = select.filter(DayColumn::SiteId.eq(site_id));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(DayColumn::Date.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(DayColumn::Date.lte(to));
+    }
+    let days = select
+        .order_by_asc(DayColumn::Date)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(days))
+}
+
+/// 创建特殊日期
+#[utoipa::path(
+    post,
+    path = "/calendar/days",
+    request_body = CreateCalendarDayRequest,
+    responses(
+        (status = 201, description = "创建特殊日期成功", body = CalendarDay),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Calendar"
+)]
+pub async fn create_day(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateCalendarDayRequest>,
+) -> Result<(StatusCode, Json<CalendarDay>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    ensure_site(conn, payload.site_id).await?;
+
+    let mut duplicate = DayEntity::find().filter(DayColumn::Date.eq(payload.date));
+    duplicate = match payload.site_id {
+        Some(site_id) => duplicate.filter(DayColumn::SiteId.eq(site_id)),
+        None => duplicate.filter(DayColumn::SiteId.is_null()),
+    };
+    if duplicate.one(conn).await.map_err(|_| AppError::InternalError)?.is_some() {
+        return Err(AppError::InvalidInput(format!("日期 {} 已存在", payload.date).into()));
+    }
+
+    let now = Utc::now();
+    let day = CalendarDay {
+        id: 0,
+        site_id: payload.site_id,
+        date: payload.date,
+        kind: payload.kind,
+        name: payload.name,
+        created_at: now,
+        updated_at: now,
+    };
+    calendar::validate_day(&day)?;
+
+    let mut active_model = day.into_active_model();
+    active_model.id = Default::default();
+    let day = DayEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &day, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(day)))
+}
+
+/// 更新特殊日期
+#[utoipa::path(
+    put,
+    path = "/calendar/days/{id}",
+    params(
+        ("id" = i32, Path, description = "特殊日期ID")
+    ),
+    request_body = UpdateCalendarDayRequest,
+    responses(
+        (status = 200, description = "更新特殊日期成功", body = CalendarDay),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "特殊日期未找到")
+    ),
+    tag = "Calendar"
+)]
+pub async fn update_day(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateCalendarDayRequest>,
+) -> Result<Json<CalendarDay>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let existing = DayEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(kind) = payload.kind {
+        active_model.kind = Set(kind);
+    }
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    calendar::validate_day(&proposed)?;
+
+    let updated = DayEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除特殊日期
+#[utoipa::path(
+    delete,
+    path = "/calendar/days/{id}",
+    params(
+        ("id" = i32, Path, description = "特殊日期ID")
+    ),
+    responses(
+        (status = 204, description = "删除特殊日期成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "特殊日期未找到")
+    ),
+    tag = "Calendar"
+)]
+pub async fn delete_day(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let day = DayEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    DayEntity::delete_by_id(day.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &day, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 查询某一时刻是否工作日、所在班次
+#[utoipa::path(
+    get,
+    path = "/calendar/status",
+    params(StatusQuery),
+    responses(
+        (status = 200, description = "查询成功", body = CalendarStatus)
+    ),
+    tag = "Calendar"
+)]
+pub async fn get_status(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatusQuery>,
+) -> Result<Json<CalendarStatus>, AppError> {
+    let conn = state.db.get_connection();
+    let calendar = Calendar::load(conn, &state.settings.timezone, query.site_id).await?;
+    Ok(Json(calendar.status(query.at.unwrap_or_else(Utc::now))))
+}
+
+/// 查询某天各班次的起止时间，用于按班次出报表
+#[utoipa::path(
+    get,
+    path = "/calendar/periods",
+    params(PeriodQuery),
+    responses(
+        (status = 200, description = "查询成功", body = [ShiftPeriod])
+    ),
+    tag = "Calendar"
+)]
+pub async fn get_periods(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PeriodQuery>,
+) -> Result<Json<Vec<ShiftPeriod>>, AppError> {
+    let conn = state.db.get_connection();
+    let calendar = Calendar::load(conn, &state.settings.timezone, query.site_id).await?;
+    let date = query.date.unwrap_or_else(|| timezone::local_date(calendar.timezone(), Utc::now()));
+    Ok(Json(calendar.periods(date)))
+}
@@ -0,0 +1,247 @@
+use crate::app_state::AppState;
+use crate::models::calibration::{
+    Column as CalibrationColumn, Entity as CalibrationEntity, Model as Calibration,
+};
+use crate::models::device::Entity as DeviceEntity;
+use crate::services::calibration::{self, NewCalibration};
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+
+/// 校准点
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationPoint {
+    /// 标准液、标准表的值
+    pub reference: f64,
+    /// 校准时系统显示的读数
+    pub measured: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCalibrationRequest {
+    pub device_id: i32,
+    pub metric_type: String,
+    /// 一个点只修正零点偏移，两个点同时修正斜率
+    pub points: Vec<CalibrationPoint>,
+    /// 校准时间，默认当前时间
+    pub calibrated_at: Option<DateTime<Utc>>,
+    pub technician: String,
+    pub notes: Option<String>,
+    /// 校准周期天数，默认取配置 `calibration.default_interval_days`
+    pub interval_days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CalibrationQuery {
+    pub device_id: Option<i32>,
+    pub metric_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DueQuery {
+    /// 未来多少天内到期，默认取配置 `calibration.remind_days`
+    pub within_days: Option<u32>,
+}
+
+async fn find_calibration(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<Calibration, AppError> {
+    let calibration = CalibrationEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, Some(calibration.device_id)).await?;
+    Ok(calibration)
+}
+
+/// 获取校准记录，按校准时间倒序
+#[utoipa::path(
+    get,
+    path = "/calibrations",
+    params(CalibrationQuery),
+    responses(
+        (status = 200, description = "获取校准记录成功", body = [Calibration])
+    ),
+    tag = "Calibration"
+)]
+pub async fn get_calibrations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CalibrationQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Calibration>>, AppError> {
+    let mut select = tenant.scope_devices(CalibrationEntity::find(), CalibrationColumn::DeviceId);
+    if let Some(device_id) = query.device_id {
+        select = select.filter(CalibrationColumn::DeviceId.eq(device_id));
+    }
+    if let Some(metric_type) = &query.metric_type {
+        select = select.filter(CalibrationColumn::MetricType.eq(metric_type.as_str()));
+    }
+    let calibrations = select
+        .order_by_desc(CalibrationColumn::CalibratedAt)
+        .order_by_desc(CalibrationColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(calibrations))
+}
+
+/// 获取指定校准记录
+#[utoipa::path(
+    get,
+    path = "/calibrations/{id}",
+    params(
+        ("id" = i32, Path, description = "校准记录 ID")
+    ),
+    responses(
+        (status = 200, description = "获取校准记录成功", body = Calibration),
+        (status = 404, description = "校准记录未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn get_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Calibration>, AppError> {
+    Ok(Json(find_calibration(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 记录一次现场校准，之后入库的数据按校准结果修正
+#[utoipa::path(
+    post,
+    path = "/calibrations",
+    request_body = CreateCalibrationRequest,
+    responses(
+        (status = 201, description = "记录校准成功", body = Calibration),
+        (status = 400, description = "校准参数无效")
+    ),
+    tag = "Calibration"
+)]
+pub async fn create_calibration(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateCalibrationRequest>,
+) -> Result<(StatusCode, Json<Calibration>), AppError> {
+    let conn = state.db.get_connection();
+
+    DeviceEntity::find_by_id(payload.device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::InvalidInput(format!("设备不存在: {}", payload.device_id).into()))?;
+    if metric_registry::lookup(&payload.metric_type).is_none() {
+        return Err(AppError::InvalidInput(
+            format!("未知的指标类型: {}", payload.metric_type).into(),
+        ));
+    }
+
+    let new = NewCalibration {
+        device_id: payload.device_id,
+        metric_type: payload.metric_type,
+        points: payload.points.iter().map(|p| (p.reference, p.measured)).collect(),
+        calibrated_at: payload.calibrated_at.unwrap_or_else(Utc::now),
+        technician: payload.technician,
+        notes: payload.notes,
+        interval_days: payload.interval_days,
+    };
+    let calibration = calibration::record(conn, &state.settings.calibration, new).await?;
+    info!(
+        "Device {} {} calibrated by {} (recorded by {:?}): gain {} offset {}",
+        calibration.device_id,
+        calibration.metric_type,
+        calibration.technician,
+        operator,
+        calibration.gain,
+        calibration.offset
+    );
+
+    Ok((StatusCode::CREATED, Json(calibration)))
+}
+
+/// 获取即将到期或已到期的校准，每个设备、指标只看最近一次校准
+#[utoipa::path(
+    get,
+    path = "/calibrations/due",
+    params(DueQuery),
+    responses(
+        (status = 200, description = "获取到期校准成功", body = [Calibration])
+    ),
+    tag = "Calibration"
+)]
+pub async fn get_due_calibrations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DueQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Calibration>>, AppError> {
+    let within_days = query.within_days.unwrap_or(state.settings.calibration.remind_days);
+    let horizon = Utc::now() + Duration::days(within_days as i64);
+    let conn = state.db.get_connection();
+    let owned = tenant.device_ids(conn).await?;
+    let due = calibration::latest(conn)
+        .await?
+        .into_iter()
+        .filter(|c| c.due_at <= horizon)
+        .filter(|c| owned.as_ref().is_none_or(|owned| owned.contains(&c.device_id)))
+        .collect();
+
+    Ok(Json(due))
+}
+
+/// 撤销校准记录，只能撤销设备该指标最近的一次，用于更正录入错误
+#[utoipa::path(
+    delete,
+    path = "/calibrations/{id}",
+    params(
+        ("id" = i32, Path, description = "校准记录 ID")
+    ),
+    responses(
+        (status = 204, description = "撤销校准成功"),
+        (status = 400, description = "不是最近一次校准"),
+        (status = 404, description = "校准记录未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn delete_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    let target = find_calibration(conn, tenant, id).await?;
+
+    // 之后的校准系数叠加在这一次之上，只能从最近一次开始撤销
+    let latest = calibration::last(conn, target.device_id, &target.metric_type).await?;
+    if latest.is_some_and(|latest| latest.id != target.id) {
+        return Err(AppError::InvalidInput("只能撤销该设备此指标最近一次的校准".into()));
+    }
+
+    CalibrationEntity::delete_by_id(target.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    info!(
+        "Calibration {} of device {} {} deleted by {:?}",
+        target.id, target.device_id, target.metric_type, operator
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
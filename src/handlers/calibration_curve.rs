@@ -0,0 +1,306 @@
+use crate::app_state::AppState;
+use crate::models::calibration_curve::{
+    ActiveModel as CurveActiveModel, Column as CurveColumn, Entity as CurveEntity,
+    Model as CalibrationCurve,
+};
+use crate::models::device::Entity as DeviceEntity;
+use crate::services::calibration;
+use crate::services::config_revision;
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, Set, TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateCalibrationCurveRequest {
+    pub device_id: i32,
+    pub metric_type: String,
+    /// piecewise / polynomial
+    pub kind: String,
+    /// piecewise 为 [[原始值, 实际值], ...]，polynomial 为 [c0, c1, ...]
+    pub points: serde_json::Value,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateCalibrationCurveRequest {
+    pub kind: Option<String>,
+    pub points: Option<serde_json::Value>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CalibrationCurveQuery {
+    pub device_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PreviewQuery {
+    /// 原始值
+    pub raw: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreviewResponse {
+    pub raw: f64,
+    pub value: f64,
+}
+
+/// 同一设备、指标只能有一条启用的曲线
+async fn ensure_single_enabled(conn: &DatabaseConnection, curve: &CalibrationCurve) -> Result<(), AppError> {
+    if !curve.enabled {
+        return Ok(());
+    }
+    let existing = calibration::find_enabled(conn, curve.device_id, &curve.metric_type).await?;
+    if existing.is_some_and(|existing| existing.id != curve.id) {
+        return Err(AppError::InvalidInput("该设备的此指标已有启用的标定曲线".into()));
+    }
+    Ok(())
+}
+
+/// 按 ID 查找标定曲线，其他组织设备的曲线按未找到处理
+async fn find_curve(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<CalibrationCurve, AppError> {
+    let curve = CurveEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, Some(curve.device_id)).await?;
+    Ok(curve)
+}
+
+/// 获取标定曲线列表
+#[utoipa::path(
+    get,
+    path = "/calibration-curves",
+    params(CalibrationCurveQuery),
+    responses(
+        (status = 200, description = "获取标定曲线列表成功", body = [CalibrationCurve])
+    ),
+    tag = "Calibration"
+)]
+pub async fn get_calibration_curves(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CalibrationCurveQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<CalibrationCurve>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mut select = tenant.scope_devices(CurveEntity::find(), CurveColumn::DeviceId);
+    if let Some(device_id) = query.device_id {
+        select = select.filter(CurveColumn::DeviceId.eq(device_id));
+    }
+    let curves = select.all(conn).await.map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(curves))
+}
+
+/// 获取指定标定曲线
+#[utoipa::path(
+    get,
+    path = "/calibration-curves/{id}",
+    params(
+        ("id" = i32, Path, description = "标定曲线ID")
+    ),
+    responses(
+        (status = 200, description = "获取标定曲线成功", body = CalibrationCurve),
+        (status = 404, description = "标定曲线未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn get_calibration_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<CalibrationCurve>, AppError> {
+    Ok(Json(find_curve(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 创建标定曲线
+#[utoipa::path(
+    post,
+    path = "/calibration-curves",
+    request_body = CreateCalibrationCurveRequest,
+    responses(
+        (status = 201, description = "创建标定曲线成功", body = CalibrationCurve),
+        (status = 400, description = "请求参数错误")
+    ),
+    tag = "Calibration"
+)]
+pub async fn create_calibration_curve(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateCalibrationCurveRequest>,
+) -> Result<(StatusCode, Json<CalibrationCurve>), AppError> {
+    let conn = state.db.get_connection();
+
+    DeviceEntity::find_by_id(payload.device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::InvalidInput(format!("设备不存在: {}", payload.device_id).into()))?;
+    if metric_registry::lookup(&payload.metric_type).is_none() {
+        return Err(AppError::InvalidInput(
+            format!("未知的指标类型: {}", payload.metric_type).into(),
+        ));
+    }
+
+    let now = Utc::now();
+    let curve = CalibrationCurve {
+        id: 0,
+        device_id: payload.device_id,
+        metric_type: payload.metric_type,
+        kind: payload.kind,
+        points: payload.points.to_string(),
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    calibration::validate(&curve)?;
+    ensure_single_enabled(conn, &curve).await?;
+
+    let mut active_model = curve.into_active_model();
+    active_model.id = Default::default();
+    let curve = CurveEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &curve, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(curve)))
+}
+
+/// 更新标定曲线
+#[utoipa::path(
+    put,
+    path = "/calibration-curves/{id}",
+    params(
+        ("id" = i32, Path, description = "标定曲线ID")
+    ),
+    request_body = UpdateCalibrationCurveRequest,
+    responses(
+        (status = 200, description = "更新标定曲线成功", body = CalibrationCurve),
+        (status = 400, description = "请求参数错误"),
+        (status = 404, description = "标定曲线未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn update_calibration_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateCalibrationCurveRequest>,
+) -> Result<Json<CalibrationCurve>, AppError> {
+    let conn = state.db.get_connection();
+
+    let existing = find_curve(conn, tenant, id).await?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(kind) = payload.kind {
+        active_model.kind = Set(kind);
+    }
+    if let Some(points) = payload.points {
+        active_model.points = Set(points.to_string());
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    calibration::validate(&proposed)?;
+    ensure_single_enabled(conn, &proposed).await?;
+
+    let updated = CurveEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除标定曲线
+#[utoipa::path(
+    delete,
+    path = "/calibration-curves/{id}",
+    params(
+        ("id" = i32, Path, description = "标定曲线ID")
+    ),
+    responses(
+        (status = 204, description = "删除标定曲线成功"),
+        (status = 404, description = "标定曲线未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn delete_calibration_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+
+    let curve = find_curve(conn, tenant, id).await?;
+
+    CurveEntity::delete_by_id(curve.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &curve, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 用标定曲线换算一个原始值，用于现场核对
+#[utoipa::path(
+    get,
+    path = "/calibration-curves/{id}/preview",
+    params(
+        ("id" = i32, Path, description = "标定曲线ID"),
+        PreviewQuery
+    ),
+    responses(
+        (status = 200, description = "换算成功", body = PreviewResponse),
+        (status = 404, description = "标定曲线未找到")
+    ),
+    tag = "Calibration"
+)]
+pub async fn preview_calibration_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<PreviewQuery>,
+    tenant: Tenant,
+) -> Result<Json<PreviewResponse>, AppError> {
+    let curve = find_curve(state.db.get_connection(), tenant, id).await?;
+
+    let value = calibration::validate(&curve)?.apply(query.raw);
+    Ok(Json(PreviewResponse { raw: query.raw, value }))
+}
@@ -0,0 +1,85 @@
+use crate::app_state::AppState;
+use crate::services::config_bundle::{self as bundle_service, BundleImportSummary, ConfigBundle};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{State, Query},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BundleImportParams {
+    /// 仅校验并统计变更，不写入
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 导出签名配置包（厂站、区域、设备、规则、寄存器映射、标定曲线、罐体和水泵参数，不含测量数据）
+#[utoipa::path(
+    get,
+    path = "/config-bundle/export",
+    responses(
+        (status = 200, description = "导出配置包成功", body = ConfigBundle),
+        (status = 400, description = "未配置签名密钥"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Config Bundle"
+)]
+pub async fn export_config_bundle(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<ConfigBundle>, AppError> {
+    // 配置包包含全部组织的设备和规则
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let bundle = bundle_service::export(conn, &state.settings.config_bundle).await?;
+
+    Ok(Json(bundle))
+}
+
+/// 导入签名配置包
+///
+/// 按ID覆盖已有对象，包中没有的对象保持不变。签名校验失败时拒绝导入。
+/// 开启审批模式时，有变化的报警规则和自动化规则只生成待审批版本。
+#[utoipa::path(
+    post,
+    path = "/config-bundle/import",
+    params(BundleImportParams),
+    request_body = ConfigBundle,
+    responses(
+        (status = 200, description = "导入配置包成功", body = BundleImportSummary),
+        (status = 400, description = "签名校验失败或版本不支持"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Config Bundle"
+)]
+pub async fn import_config_bundle(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BundleImportParams>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(bundle): Json<ConfigBundle>,
+) -> Result<Json<BundleImportSummary>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let summary = bundle_service::import(
+        conn,
+        &state.settings.config_bundle,
+        &state.settings.change_control,
+        bundle,
+        params.dry_run,
+        operator,
+    )
+    .await?;
+    if !summary.dry_run {
+        state.cache.clear().await;
+    }
+
+    Ok(Json(summary))
+}
@@ -0,0 +1,185 @@
+use crate::app_state::AppState;
+use crate::models::config_revision::Model as ConfigRevision;
+use crate::services::config_revision::{self as revision_service, FieldChange, RevisionFilter};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State, Query},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RevisionQuery {
+    /// 配置类型：alarm_rule、automation_rule、device
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    /// 状态：applied、pending、rejected
+    pub status: Option<String>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 版本详情，包含与上一状态的字段差异
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigRevisionDetail {
+    pub revision: ConfigRevision,
+    pub changes: Vec<FieldChange>,
+}
+
+/// 获取配置版本列表
+#[utoipa::path(
+    get,
+    path = "/config-revisions",
+    params(RevisionQuery),
+    responses(
+        (status = 200, description = "获取配置版本列表成功", body = [ConfigRevision]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Config Revisions"
+)]
+pub async fn get_config_revisions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RevisionQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ConfigRevision>>, AppError> {
+    // 变更记录覆盖各组织的配置，暂不按组织拆分
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let filter = RevisionFilter {
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        status: query.status,
+    };
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+
+    let revisions = revision_service::list(conn, &filter, page, per_page).await?;
+
+    Ok(Json(revisions))
+}
+
+/// 获取指定配置版本及差异
+#[utoipa::path(
+    get,
+    path = "/config-revisions/{id}",
+    params(
+        ("id" = i32, Path, description = "版本ID")
+    ),
+    responses(
+        (status = 200, description = "获取配置版本成功", body = ConfigRevisionDetail),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "配置版本未找到")
+    ),
+    tag = "Config Revisions"
+)]
+pub async fn get_config_revision(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<ConfigRevisionDetail>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let revision = revision_service::get(conn, id).await?;
+    let changes = revision_service::diff(revision.previous.as_deref(), revision.snapshot.as_deref());
+
+    Ok(Json(ConfigRevisionDetail { revision, changes }))
+}
+
+/// 回滚到指定版本
+#[utoipa::path(
+    post,
+    path = "/config-revisions/{id}/rollback",
+    params(
+        ("id" = i32, Path, description = "要恢复的版本ID")
+    ),
+    responses(
+        (status = 200, description = "回滚成功，返回新生成的版本", body = ConfigRevision),
+        (status = 400, description = "审批模式下缺少提交人"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "配置版本未找到")
+    ),
+    tag = "Config Revisions"
+)]
+pub async fn rollback_config_revision(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Json<ConfigRevision>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let revision = revision_service::rollback(conn, &state.settings.change_control, id, operator).await?;
+    if revision.entity_type == revision_service::DEVICE {
+        state.cache.invalidate_device(revision.entity_id).await;
+    }
+
+    Ok(Json(revision))
+}
+
+/// 审批通过待审批的变更
+///
+/// 审批人通过 `X-Operator` 提供，且不能与提交人相同。
+#[utoipa::path(
+    post,
+    path = "/config-revisions/{id}/approve",
+    params(
+        ("id" = i32, Path, description = "待审批的版本ID")
+    ),
+    responses(
+        (status = 200, description = "审批通过，变更已生效", body = ConfigRevision),
+        (status = 400, description = "版本不处于待审批状态或配置已被修改"),
+        (status = 403, description = "提交人不能审批自己的变更"),
+        (status = 404, description = "配置版本未找到")
+    ),
+    tag = "Config Revisions"
+)]
+pub async fn approve_config_revision(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Json<ConfigRevision>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let revision = revision_service::approve(conn, id, operator).await?;
+
+    Ok(Json(revision))
+}
+
+/// 驳回待审批的变更
+#[utoipa::path(
+    post,
+    path = "/config-revisions/{id}/reject",
+    params(
+        ("id" = i32, Path, description = "待审批的版本ID")
+    ),
+    responses(
+        (status = 200, description = "已驳回", body = ConfigRevision),
+        (status = 400, description = "版本不处于待审批状态"),
+        (status = 403, description = "提交人不能审批自己的变更"),
+        (status = 404, description = "配置版本未找到")
+    ),
+    tag = "Config Revisions"
+)]
+pub async fn reject_config_revision(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Json<ConfigRevision>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let revision = revision_service::reject(conn, id, operator).await?;
+
+    Ok(Json(revision))
+}
@@ -0,0 +1,454 @@
+use crate::app_state::AppState;
+use crate::control::{self, LoopStatus};
+use crate::middleware::api_key::ControlKey;
+use crate::models::control_loop::{
+    Column as ControlLoopColumn, Entity as ControlLoopEntity, Model as ControlLoop,
+    ACTION_DIRECT, MODE_AUTO, MODE_MANUAL,
+};
+use crate::services::modbus_write::WriteSource;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, QuerySelect,
+    Set, TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateControlLoopRequest {
+    pub name: String,
+    /// 提供测量值的设备
+    pub device_id: i32,
+    /// 测量值指标类型，例如 ph
+    pub input_metric: String,
+    /// pwm / modbus
+    pub output_kind: String,
+    pub output_device_id: i32,
+    /// Modbus 保持寄存器地址
+    pub output_address: Option<i32>,
+    /// direct（默认，测量值高于设定值时增大输出）/ reverse
+    #[serde(default = "default_action")]
+    pub action: String,
+    pub setpoint: f64,
+    pub kp: f64,
+    #[serde(default)]
+    pub ki: f64,
+    #[serde(default)]
+    pub kd: f64,
+    #[serde(default)]
+    pub output_min: f64,
+    #[serde(default = "default_output_max")]
+    pub output_max: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_action() -> String {
+    ACTION_DIRECT.to_string()
+}
+
+fn default_output_max() -> f64 {
+    100.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateControlLoopRequest {
+    pub name: Option<String>,
+    pub device_id: Option<i32>,
+    pub input_metric: Option<String>,
+    pub output_kind: Option<String>,
+    pub output_device_id: Option<i32>,
+    pub output_address: Option<i32>,
+    pub action: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// 在线整定，未给出的项保持不变
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TuneControlLoopRequest {
+    pub setpoint: Option<f64>,
+    pub kp: Option<f64>,
+    pub ki: Option<f64>,
+    pub kd: Option<f64>,
+    pub output_min: Option<f64>,
+    pub output_max: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetControlModeRequest {
+    /// manual / auto
+    pub mode: String,
+    /// 切换到手动时下发的输出，不给出时保持当前输出
+    pub output: Option<f64>,
+}
+
+/// 回路的测量设备和输出设备都须属于当前租户
+async fn check_devices(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    control_loop: &ControlLoop,
+) -> Result<(), AppError> {
+    tenant.check_device(conn, Some(control_loop.device_id)).await?;
+    tenant.check_device(conn, Some(control_loop.output_device_id)).await
+}
+
+async fn find_loop(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<ControlLoop, AppError> {
+    let control_loop = ControlLoopEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    check_devices(conn, tenant, &control_loop).await?;
+    Ok(control_loop)
+}
+
+/// 获取控制回路列表
+#[utoipa::path(
+    get,
+    path = "/control-loops",
+    responses(
+        (status = 200, description = "获取控制回路列表成功", body = [ControlLoop])
+    ),
+    tag = "Control"
+)]
+pub async fn get_control_loops(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ControlLoop>>, AppError> {
+    let loops = tenant.scope_devices(ControlLoopEntity::find(), ControlLoopColumn::DeviceId);
+    let loops = tenant
+        .scope_devices(loops, ControlLoopColumn::OutputDeviceId)
+        .order_by_asc(ControlLoopColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(loops))
+}
+
+/// 获取指定控制回路
+#[utoipa::path(
+    get,
+    path = "/control-loops/{id}",
+    params(
+        ("id" = i32, Path, description = "控制回路 ID")
+    ),
+    responses(
+        (status = 200, description = "获取控制回路成功", body = ControlLoop),
+        (status = 404, description = "控制回路未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn get_control_loop(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<ControlLoop>, AppError> {
+    Ok(Json(find_loop(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 创建控制回路，初始为手动模式、输出为下限
+#[utoipa::path(
+    post,
+    path = "/control-loops",
+    request_body = CreateControlLoopRequest,
+    responses(
+        (status = 201, description = "创建控制回路成功", body = ControlLoop),
+        (status = 400, description = "控制回路参数无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn create_control_loop(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateControlLoopRequest>,
+) -> Result<(StatusCode, Json<ControlLoop>), AppError> {
+    let conn = state.db.get_connection();
+    let now = Utc::now();
+    let control_loop = ControlLoop {
+        id: 0,
+        name: payload.name,
+        device_id: payload.device_id,
+        input_metric: payload.input_metric,
+        output_kind: payload.output_kind,
+        output_device_id: payload.output_device_id,
+        output_address: payload.output_address,
+        action: payload.action,
+        setpoint: payload.setpoint,
+        kp: payload.kp,
+        ki: payload.ki,
+        kd: payload.kd,
+        output_min: payload.output_min,
+        output_max: payload.output_max,
+        mode: MODE_MANUAL.to_string(),
+        output: payload.output_min,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    control::validate(&control_loop)?;
+    check_devices(conn, tenant, &control_loop).await?;
+    control::ensure_output_free(conn, &control_loop).await?;
+
+    let mut active_model = control_loop.into_active_model();
+    active_model.id = Default::default();
+    let control_loop = ControlLoopEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(control_loop)))
+}
+
+/// 更新控制回路的测量和输出对象
+#[utoipa::path(
+    put,
+    path = "/control-loops/{id}",
+    params(
+        ("id" = i32, Path, description = "控制回路 ID")
+    ),
+    request_body = UpdateControlLoopRequest,
+    responses(
+        (status = 200, description = "更新控制回路成功", body = ControlLoop),
+        (status = 400, description = "控制回路参数无效"),
+        (status = 404, description = "控制回路未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn update_control_loop(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdateControlLoopRequest>,
+) -> Result<Json<ControlLoop>, AppError> {
+    let conn = state.db.get_connection();
+    let mut active_model = find_loop(conn, tenant, id).await?.into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(device_id) = payload.device_id {
+        active_model.device_id = Set(device_id);
+    }
+    if let Some(input_metric) = payload.input_metric {
+        active_model.input_metric = Set(input_metric);
+    }
+    if let Some(output_kind) = payload.output_kind {
+        active_model.output_kind = Set(output_kind);
+    }
+    if let Some(output_device_id) = payload.output_device_id {
+        active_model.output_device_id = Set(output_device_id);
+    }
+    if let Some(output_address) = payload.output_address {
+        active_model.output_address = Set(Some(output_address));
+    }
+    if let Some(action) = payload.action {
+        active_model.action = Set(action);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    control::validate(&proposed)?;
+    check_devices(conn, tenant, &proposed).await?;
+    control::ensure_output_free(conn, &proposed).await?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    Ok(Json(updated))
+}
+
+/// 删除控制回路，输出保持不变
+#[utoipa::path(
+    delete,
+    path = "/control-loops/{id}",
+    params(
+        ("id" = i32, Path, description = "控制回路 ID")
+    ),
+    responses(
+        (status = 204, description = "删除控制回路成功"),
+        (status = 404, description = "控制回路未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn delete_control_loop(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    let control_loop = find_loop(conn, tenant, id).await?;
+
+    ControlLoopEntity::delete_by_id(control_loop.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 调整设定值、增益和输出限幅，自动模式下从下一周期起生效，需使用具备 control 权限的 Key 调用
+#[utoipa::path(
+    put,
+    path = "/control-loops/{id}/tuning",
+    params(
+        ("id" = i32, Path, description = "控制回路 ID")
+    ),
+    request_body = TuneControlLoopRequest,
+    responses(
+        (status = 200, description = "整定成功", body = ControlLoop),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限"),
+        (status = 404, description = "控制回路未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn tune_control_loop(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<TuneControlLoopRequest>,
+) -> Result<Json<ControlLoop>, AppError> {
+    let conn = state.db.get_connection();
+    let existing = find_loop(conn, tenant, id).await?;
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(setpoint) = payload.setpoint {
+        active_model.setpoint = Set(setpoint);
+    }
+    if let Some(kp) = payload.kp {
+        active_model.kp = Set(kp);
+    }
+    if let Some(ki) = payload.ki {
+        active_model.ki = Set(ki);
+    }
+    if let Some(kd) = payload.kd {
+        active_model.kd = Set(kd);
+    }
+    if let Some(output_min) = payload.output_min {
+        active_model.output_min = Set(output_min);
+    }
+    if let Some(output_max) = payload.output_max {
+        active_model.output_max = Set(output_max);
+    }
+    // 收窄限幅后当前输出按新的范围截断，下一周期由回路下发
+    let output_min = payload.output_min.unwrap_or(existing.output_min);
+    let output_max = payload.output_max.unwrap_or(existing.output_max);
+    if output_min < output_max {
+        active_model.output = Set(existing.output.clamp(output_min, output_max));
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    control::validate(&proposed)?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    info!(
+        "Control loop {} tuned by {}: setpoint {} kp {} ki {} kd {}",
+        updated.name,
+        caller.operator_label(),
+        updated.setpoint,
+        updated.kp,
+        updated.ki,
+        updated.kd
+    );
+    Ok(Json(updated))
+}
+
+/// 切换手动/自动
+///
+/// 切换到手动时可同时给出输出并立即下发；切换到自动时从当前输出无扰开始调节。
+/// 需使用具备 control 权限的 Key 调用。
+#[utoipa::path(
+    put,
+    path = "/control-loops/{id}/mode",
+    params(
+        ("id" = i32, Path, description = "控制回路 ID")
+    ),
+    request_body = SetControlModeRequest,
+    responses(
+        (status = 200, description = "切换成功", body = ControlLoop),
+        (status = 400, description = "模式或输出无效"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限"),
+        (status = 404, description = "控制回路未找到")
+    ),
+    tag = "Control"
+)]
+pub async fn set_control_mode(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<SetControlModeRequest>,
+) -> Result<Json<ControlLoop>, AppError> {
+    if !matches!(payload.mode.as_str(), MODE_MANUAL | MODE_AUTO) {
+        return Err(AppError::InvalidInput("运行模式须为 manual / auto".into()));
+    }
+    if payload.mode == MODE_AUTO && payload.output.is_some() {
+        return Err(AppError::InvalidInput("自动模式的输出由回路计算".into()));
+    }
+    let conn = state.db.get_connection();
+    let mut control_loop = find_loop(conn, tenant, id).await?;
+
+    let mut active_model = control_loop.clone().into_active_model();
+    active_model.mode = Set(payload.mode);
+    active_model.updated_at = Set(Utc::now());
+    control_loop = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    let operator = caller.operator_label();
+    info!("Control loop {} switched to {} by {}", control_loop.name, control_loop.mode, operator);
+
+    if let Some(output) = payload.output {
+        control_loop =
+            control::command(&state, &control_loop, output, WriteSource::Api, Some(operator))
+                .await?;
+    }
+    Ok(Json(control_loop))
+}
+
+/// 获取全部控制回路的运行状态
+#[utoipa::path(
+    get,
+    path = "/control-loops/status",
+    responses(
+        (status = 200, description = "获取运行状态成功", body = [LoopStatus])
+    ),
+    tag = "Control"
+)]
+pub async fn get_control_status(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<LoopStatus>>, AppError> {
+    let mut status = state.control.status();
+    if tenant != Tenant::All {
+        let loops = ControlLoopEntity::find().select_only().column(ControlLoopColumn::Id);
+        let loops = tenant.scope_devices(loops, ControlLoopColumn::DeviceId);
+        let owned: Vec<i32> = tenant
+            .scope_devices(loops, ControlLoopColumn::OutputDeviceId)
+            .into_tuple()
+            .all(state.db.get_connection())
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        status.retain(|s| owned.contains(&s.loop_id));
+    }
+    Ok(Json(status))
+}
@@ -0,0 +1,90 @@
+use crate::app_state::AppState;
+use crate::services::daily_summary::{self, DeviceDay};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// 一次最多查询的天数
+const MAX_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DailySummaryQuery {
+    /// 起始日期（含），缺省为 30 天前
+    pub from: Option<NaiveDate>,
+    /// 结束日期（含），缺省为昨天
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RebuildSummaryRequest {
+    pub day: NaiveDate,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RebuildSummaryResponse {
+    pub day: NaiveDate,
+    /// 写入汇总的设备数
+    pub devices: usize,
+}
+
+/// 获取设备每日汇总
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/daily-summaries",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        DailySummaryQuery
+    ),
+    responses(
+        (status = 200, description = "获取每日汇总成功", body = [DeviceDay]),
+        (status = 400, description = "日期区间无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Summaries"
+)]
+pub async fn get_device_daily_summaries(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<DailySummaryQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceDay>>, AppError> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive() - Duration::days(1));
+    let from = query.from.unwrap_or(to - Duration::days(29));
+    if from > to || (to - from).num_days() >= MAX_DAYS {
+        return Err(AppError::InvalidInput(format!("日期区间须在 1 到 {} 天之间", MAX_DAYS).into()));
+    }
+
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+    let days = daily_summary::device_days(conn, id, from, to).await?;
+    Ok(Json(days))
+}
+
+/// 重算某天的汇总，用于补录数据之后
+#[utoipa::path(
+    post,
+    path = "/daily-summaries/rebuild",
+    request_body = RebuildSummaryRequest,
+    responses(
+        (status = 200, description = "重算成功", body = RebuildSummaryResponse),
+        (status = 400, description = "日期尚未开始"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Summaries"
+)]
+pub async fn rebuild_daily_summary(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<RebuildSummaryRequest>,
+) -> Result<Json<RebuildSummaryResponse>, AppError> {
+    tenant.require_platform()?;
+    let devices = daily_summary::materialize(state.db.get_connection(), payload.day).await?;
+    Ok(Json(RebuildSummaryResponse { day: payload.day, devices }))
+}
@@ -0,0 +1,59 @@
+use crate::app_state::AppState;
+use crate::models::data_correction::Model as DataCorrection;
+use crate::services::data_correction::{self, CorrectionFilter};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DataCorrectionQuery {
+    /// measurement / lab_result
+    pub record_type: Option<String>,
+    pub record_id: Option<i32>,
+    pub corrected_by: Option<String>,
+    /// 修正时间范围
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 获取数据修正记录，供审计核查，按时间倒序
+#[utoipa::path(
+    get,
+    path = "/data-corrections",
+    params(DataCorrectionQuery),
+    responses(
+        (status = 200, description = "获取修正记录成功", body = [DataCorrection]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Data Corrections"
+)]
+pub async fn get_data_corrections(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataCorrectionQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DataCorrection>>, AppError> {
+    // 修正记录涉及各类数据，租户通过具体数据的修正历史接口查看
+    tenant.require_platform()?;
+    let filter = CorrectionFilter {
+        record_type: query.record_type,
+        record_id: query.record_id,
+        corrected_by: query.corrected_by,
+        start: query.start,
+        end: query.end,
+    };
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+    let corrections =
+        data_correction::list(state.db.get_connection(), &filter, page, per_page).await?;
+
+    Ok(Json(corrections))
+}
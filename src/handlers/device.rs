@@ -1,14 +1,37 @@
 use crate::app_state::AppState;
-use crate::models::device::{Entity as DeviceEntity, Model as Device, ActiveModel as DeviceActiveModel};
+use crate::message_queue::events::Event;
+use crate::middleware::api_key::AdminKey;
+use crate::models::device::{Entity as DeviceEntity, Model as Device, ActiveModel as DeviceActiveModel, Column as DeviceColumn};
+use crate::models::device_command::Model as DeviceCommand;
+use crate::models::device_state_event::SOURCE_API;
+use crate::services::cache;
+use crate::services::config_revision;
+use crate::services::device_command;
+use crate::services::device_state;
+use crate::services::latest::{self, DeviceLatest};
+use crate::services::provisioning::{self, IssuedCredentials};
+use crate::services::site as site_service;
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::serde_ext::double_option;
+use crate::utils::tenant::Tenant;
 use axum::{
+    body::Bytes,
     extract::{Path, State, Query},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+};
+use futures_util::stream::{self, Stream};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, TransactionTrait,
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use utoipa::ToSchema;
 use utoipa::IntoParams;
 
@@ -27,6 +50,69 @@ pub struct CreateDeviceRequest {
     pub pressure: f64,
     pub flow_rate: f64,
     pub power_consumption: f64,
+    #[serde(default)]
+    pub serial_number: Option<String>,
+    /// 所属厂站，只填区域时自动取区域所属厂站
+    #[serde(default)]
+    pub site_id: Option<i32>,
+    #[serde(default)]
+    pub area_id: Option<i32>,
+    /// 所属组织，租户 Key 只能建在本组织
+    #[serde(default)]
+    pub organization_id: Option<i32>,
+}
+
+impl CreateDeviceRequest {
+    fn into_new_device(self) -> DeviceActiveModel {
+        let now = chrono::Utc::now();
+        DeviceActiveModel {
+            name: sea_orm::Set(self.name),
+            location: sea_orm::Set(self.location),
+            status: sea_orm::Set(self.status),
+            device_type: sea_orm::Set(self.device_type),
+            manufacturer: sea_orm::Set(self.manufacturer),
+            model: sea_orm::Set(self.model),
+            installation_date: sea_orm::Set(self.installation_date),
+            last_maintenance: sea_orm::Set(self.last_maintenance),
+            operational_hours: sea_orm::Set(self.operational_hours),
+            temperature: sea_orm::Set(self.temperature),
+            pressure: sea_orm::Set(self.pressure),
+            flow_rate: sea_orm::Set(self.flow_rate),
+            power_consumption: sea_orm::Set(self.power_consumption),
+            serial_number: sea_orm::Set(self.serial_number),
+            site_id: sea_orm::Set(self.site_id),
+            area_id: sea_orm::Set(self.area_id),
+            organization_id: sea_orm::Set(self.organization_id),
+            provision_status: sea_orm::Set(crate::models::device::PROVISION_ACTIVE.to_string()),
+            created_at: sea_orm::Set(now),
+            updated_at: sea_orm::Set(now),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Device> for CreateDeviceRequest {
+    fn from(device: Device) -> Self {
+        Self {
+            name: device.name,
+            location: device.location,
+            status: device.status,
+            device_type: device.device_type,
+            manufacturer: device.manufacturer,
+            model: device.model,
+            installation_date: device.installation_date,
+            last_maintenance: device.last_maintenance,
+            operational_hours: device.operational_hours,
+            temperature: device.temperature,
+            pressure: device.pressure,
+            flow_rate: device.flow_rate,
+            power_consumption: device.power_consumption,
+            serial_number: device.serial_number,
+            site_id: device.site_id,
+            area_id: device.area_id,
+            organization_id: device.organization_id,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -44,6 +130,73 @@ pub struct UpdateDeviceRequest {
     pub pressure: Option<f64>,
     pub flow_rate: Option<f64>,
     pub power_consumption: Option<f64>,
+    pub serial_number: Option<Option<String>>,
+    /// 传 null 表示移出厂站/区域
+    #[serde(default, deserialize_with = "double_option")]
+    pub site_id: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub area_id: Option<Option<i32>>,
+}
+
+/// 设备审批结果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApproveDeviceResponse {
+    pub device: Device,
+    /// 密码明文只在此返回一次
+    pub credentials: IssuedCredentials,
+}
+
+/// broker 认证钩子的请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MqttAuthRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// broker 认证钩子的结果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MqttAuthResponse {
+    /// allow / deny / ignore，ignore 表示不是设备账号
+    pub result: String,
+}
+
+/// 导入/导出的文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportParams {
+    /// 仅校验不写入
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: TransferFormat,
+}
+
+/// 导入失败的行
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRowError {
+    /// 数据行号，从 1 开始（不含 CSV 表头）
+    pub row: usize,
+    pub message: String,
+}
+
+/// 导入结果报告
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub total: usize,
+    pub imported: usize,
+    pub dry_run: bool,
+    pub errors: Vec<ImportRowError>,
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -65,13 +218,15 @@ pub struct Pagination {
 pub async fn get_devices(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<Device>>, AppError> {
     let conn = state.db.get_connection();
     
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
     
-    let devices = DeviceEntity::find()
+    let devices = tenant
+        .scope(DeviceEntity::find(), DeviceColumn::OrganizationId)
         .all(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
@@ -104,14 +259,23 @@ pub async fn get_devices(
 pub async fn get_device(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<Device>, AppError> {
     let conn = state.db.get_connection();
-    
+
+    let generation = state.cache.generation();
+    if let Some(device) = state.cache.get::<Device>(cache::DEVICE_STATUS, id).await {
+        tenant.check(device.organization_id)?;
+        return Ok(Json(device));
+    }
+
     let device = DeviceEntity::find_by_id(id)
         .one(conn)
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    state.cache.put(cache::DEVICE_STATUS, id, &device, generation).await;
+    tenant.check(device.organization_id)?;
 
     Ok(Json(device))
 }
@@ -123,38 +287,35 @@ pub async fn get_device(
     request_body = CreateDeviceRequest,
     responses(
         (status = 201, description = "创建设备成功", body = Device),
-        (status = 400, description = "请求参数错误")
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不能在其他组织下创建设备")
     ),
     tag = "Devices"
 )]
 pub async fn create_device(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<CreateDeviceRequest>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(mut payload): Json<CreateDeviceRequest>,
 ) -> Result<(StatusCode, Json<Device>), AppError> {
     let conn = state.db.get_connection();
+
+    payload.organization_id = tenant.assign(payload.organization_id)?;
+    (payload.site_id, payload.area_id) =
+        site_service::resolve_placement(conn, payload.site_id, payload.area_id).await?;
     
-    let new_device = DeviceActiveModel {
-        name: sea_orm::Set(payload.name),
-        location: sea_orm::Set(payload.location),
-        status: sea_orm::Set(payload.status),
-        device_type: sea_orm::Set(payload.device_type),
-        manufacturer: sea_orm::Set(payload.manufacturer),
-        model: sea_orm::Set(payload.model),
-        installation_date: sea_orm::Set(payload.installation_date),
-        last_maintenance: sea_orm::Set(payload.last_maintenance),
-        operational_hours: sea_orm::Set(payload.operational_hours),
-        temperature: sea_orm::Set(payload.temperature),
-        pressure: sea_orm::Set(payload.pressure),
-        flow_rate: sea_orm::Set(payload.flow_rate),
-        power_consumption: sea_orm::Set(payload.power_consumption),
-        ..Default::default()
-    };
+    let new_device = payload.into_new_device();
 
     let device = DeviceEntity::insert(new_device)
         .exec_with_returning(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
+    config_revision::record_create(conn, &device, operator).await?;
+    if let Some(initial) = device_state::state_for_status(device.status) {
+        device_state::record(conn, &state.cache, device.id, initial, SOURCE_API, device.created_at).await?;
+    }
+
     Ok((StatusCode::CREATED, Json(device)))
 }
 
@@ -175,6 +336,8 @@ pub async fn create_device(
 pub async fn update_device(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdateDeviceRequest>,
 ) -> Result<Json<Device>, AppError> {
     let conn = state.db.get_connection();
@@ -184,8 +347,9 @@ pub async fn update_device(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(existing_device.organization_id)?;
         
-    let mut device_active_model = existing_device.into_active_model();
+    let mut device_active_model = existing_device.clone().into_active_model();
     
     if let Some(name) = payload.name {
         device_active_model.name = sea_orm::Set(name);
@@ -220,7 +384,9 @@ pub async fn update_device(
     }
     
     if let Some(operational_hours) = payload.operational_hours {
+        // 手动设定的运行小时数作为当前时刻的基准，此前的运行时长不再累计
         device_active_model.operational_hours = sea_orm::Set(operational_hours);
+        device_active_model.run_hours_accrued_at = sea_orm::Set(Some(chrono::Utc::now()));
     }
     
     if let Some(temperature) = payload.temperature {
@@ -239,6 +405,28 @@ pub async fn update_device(
         device_active_model.power_consumption = sea_orm::Set(power_consumption);
     }
     
+    if let Some(serial_number) = payload.serial_number {
+        device_active_model.serial_number = sea_orm::Set(serial_number);
+    }
+
+    if payload.site_id.is_some() || payload.area_id.is_some() {
+        // 换了厂站但没指定区域时，原区域不再有效
+        let site_changed = payload.site_id.is_some_and(|site_id| site_id != existing_device.site_id);
+        let area_id = match payload.area_id {
+            Some(area_id) => area_id,
+            None if site_changed => None,
+            None => existing_device.area_id,
+        };
+        let site_id = match (payload.site_id, area_id) {
+            (Some(site_id), _) => site_id,
+            (None, Some(_)) => None,
+            (None, None) => existing_device.site_id,
+        };
+        let (site_id, area_id) = site_service::resolve_placement(conn, site_id, area_id).await?;
+        device_active_model.site_id = sea_orm::Set(site_id);
+        device_active_model.area_id = sea_orm::Set(area_id);
+    }
+    
     // 更新 updated_at 字段
     device_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
     
@@ -247,6 +435,15 @@ pub async fn update_device(
         .await
         .map_err(|_| AppError::InternalError)?;
 
+    config_revision::record_update(conn, &existing_device, &updated_device, operator).await?;
+    state.cache.invalidate_device(updated_device.id).await;
+
+    if updated_device.status != existing_device.status {
+        if let Some(new_state) = device_state::state_for_status(updated_device.status) {
+            device_state::record(conn, &state.cache, id, new_state, SOURCE_API, updated_device.updated_at).await?;
+        }
+    }
+
     Ok(Json(updated_device))
 }
 
@@ -266,6 +463,8 @@ pub async fn update_device(
 pub async fn delete_device(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let conn = state.db.get_connection();
     
@@ -274,11 +473,498 @@ pub async fn delete_device(
         .await
         .map_err(|_| AppError::InternalError)?
         .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(device.organization_id)?;
 
     let _ = DeviceEntity::delete_by_id(device.id)
         .exec(conn)
         .await
         .map_err(|_| AppError::InternalError)?;
 
+    config_revision::record_delete(conn, &device, operator).await?;
+    state.cache.invalidate_device(device.id).await;
+
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
+
+/// 审批自注册设备并签发MQTT凭据
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/approve",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "审批设备成功", body = ApproveDeviceResponse),
+        (status = 400, description = "设备不处于待审批状态"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn approve_device(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    AdminKey(_): AdminKey,
+    tenant: Tenant,
+) -> Result<Json<ApproveDeviceResponse>, AppError> {
+    let conn = state.db.get_connection();
+
+    tenant.check_device(conn, Some(id)).await?;
+
+    let (device, credentials) = provisioning::approve(conn, state.mqtt.as_ref(), id).await?;
+    state.cache.invalidate_device(device.id).await;
+
+    Ok(Json(ApproveDeviceResponse { device, credentials }))
+}
+
+/// MQTT broker 认证钩子，按凭据摘要校验设备账号
+#[utoipa::path(
+    post,
+    path = "/mqtt/auth",
+    request_body = MqttAuthRequest,
+    responses(
+        (status = 200, description = "校验完成", body = MqttAuthResponse)
+    ),
+    tag = "Devices"
+)]
+pub async fn authenticate_mqtt_client(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MqttAuthRequest>,
+) -> Result<Json<MqttAuthResponse>, AppError> {
+    let conn = state.db.get_connection();
+
+    let verified =
+        provisioning::verify_credentials(conn, &payload.username, &payload.password).await?;
+    let result = match verified {
+        Some(true) => "allow",
+        Some(false) => "deny",
+        None => "ignore",
+    };
+
+    Ok(Json(MqttAuthResponse { result: result.to_string() }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeviceCommandRequest {
+    pub command: String,
+    /// 命令参数
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceCommandResponse {
+    /// 命令发布到的 MQTT 主题
+    pub topic: String,
+    /// 命令记录，初始状态为 pending
+    pub command: DeviceCommand,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeviceCommandQuery {
+    /// pending / acked / failed / timed_out
+    pub status: Option<String>,
+    /// 默认 100，最多 500
+    pub limit: Option<u64>,
+}
+
+/// 向设备下发命令，需使用具备 admin 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/commands",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = DeviceCommandRequest,
+    responses(
+        (status = 202, description = "命令已进入下发队列，等待设备确认", body = DeviceCommandResponse),
+        (status = 400, description = "命令为空或设备未审批"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key"),
+        (status = 404, description = "设备未找到"),
+        (status = 503, description = "MQTT 未启用")
+    ),
+    tag = "Devices"
+)]
+pub async fn send_device_command(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    AdminKey(_): AdminKey,
+    tenant: Tenant,
+    Json(payload): Json<DeviceCommandRequest>,
+) -> Result<(StatusCode, Json<DeviceCommandResponse>), AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+    let command = state
+        .commands
+        .send(
+            state.db.get_connection(),
+            state.mqtt.as_ref(),
+            id,
+            &payload.command,
+            payload.payload,
+        )
+        .await?;
+    state.events.publish(Event::from(&command));
+    let topic = device_command::command_topic(command.device_id);
+
+    Ok((StatusCode::ACCEPTED, Json(DeviceCommandResponse { topic, command })))
+}
+
+/// 设备的命令记录
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/commands",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        DeviceCommandQuery
+    ),
+    responses(
+        (status = 200, description = "查询成功，按下发时间倒序", body = [DeviceCommand])
+    ),
+    tag = "Devices"
+)]
+pub async fn get_device_commands(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<DeviceCommandQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceCommand>>, AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+    let commands = device_command::list(
+        state.db.get_connection(),
+        id,
+        query.status.as_deref(),
+        query.limit,
+    )
+    .await?;
+    Ok(Json(commands))
+}
+
+/// 查询命令状态
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/commands/{command_id}",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        ("command_id" = i32, Path, description = "命令ID")
+    ),
+    responses(
+        (status = 200, description = "查询成功", body = DeviceCommand),
+        (status = 404, description = "命令未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_device_command(
+    State(state): State<Arc<AppState>>,
+    Path((id, command_id)): Path<(i32, i32)>,
+    tenant: Tenant,
+) -> Result<Json<DeviceCommand>, AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+    let command = device_command::get(state.db.get_connection(), id, command_id).await?;
+    Ok(Json(command))
+}
+
+/// 以 SSE 推送命令状态，先推送当前状态，确认、失败或超时后结束
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/commands/{command_id}/events",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        ("command_id" = i32, Path, description = "命令ID")
+    ),
+    responses(
+        (status = 200, description = "text/event-stream，每个 status 事件为一条命令记录"),
+        (status = 404, description = "命令未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn stream_device_command(
+    State(state): State<Arc<AppState>>,
+    Path((id, command_id)): Path<(i32, i32)>,
+    tenant: Tenant,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+    // 先订阅再查询，避免错过两者之间的状态变化
+    let updates = state.commands.subscribe();
+    let current = device_command::get(state.db.get_connection(), id, command_id).await?;
+
+    let events = stream::unfold(Some((Some(current), updates)), move |next| {
+        let state = state.clone();
+        async move {
+            let (current, mut updates) = next?;
+            let command = match current {
+                Some(command) => command,
+                None => loop {
+                    match updates.recv().await {
+                        Ok(command) if command.id == command_id => break command,
+                        Ok(_) => continue,
+                        // 积压时可能漏掉本命令的变化，改为查库
+                        Err(RecvError::Lagged(_)) => {
+                            let conn = state.db.get_connection();
+                            match device_command::get(conn, id, command_id).await {
+                                Ok(command) if !command.is_pending() => break command,
+                                Ok(_) => continue,
+                                Err(_) => return None,
+                            }
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                },
+            };
+            let event = Event::default().event("status").json_data(&command);
+            let next = command.is_pending().then_some((None, updates));
+            Some((event, next))
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// 解析导入文件，返回每行的解析结果
+fn parse_import_rows(
+    format: TransferFormat,
+    body: &[u8],
+) -> Result<Vec<Result<CreateDeviceRequest, String>>, AppError> {
+    match format {
+        TransferFormat::Json => {
+            let rows: Vec<serde_json::Value> = serde_json::from_slice(body)
+                .map_err(|e| AppError::InvalidInput(format!("JSON格式错误: {}", e).into()))?;
+            Ok(rows
+                .into_iter()
+                .map(|row| serde_json::from_value(row).map_err(|e| e.to_string()))
+                .collect())
+        }
+        TransferFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(body);
+            Ok(reader
+                .deserialize::<CreateDeviceRequest>()
+                .map(|row| row.map_err(|e| e.to_string()))
+                .collect())
+        }
+    }
+}
+
+/// 批量导入设备
+///
+/// 请求体为 JSON 数组或 CSV 文件（`Content-Type: text/csv`），列与创建设备的字段一致。
+/// 任意一行校验失败时整批不写入，并返回逐行错误报告。
+#[utoipa::path(
+    post,
+    path = "/devices/import",
+    params(ImportParams),
+    request_body(content = Vec<CreateDeviceRequest>, description = "JSON数组或CSV文件"),
+    responses(
+        (status = 201, description = "导入设备成功", body = ImportReport),
+        (status = 200, description = "校验通过（dry_run）", body = ImportReport),
+        (status = 400, description = "存在错误行，未导入", body = ImportReport)
+    ),
+    tag = "Devices"
+)]
+pub async fn import_devices(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ImportParams>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportReport>), AppError> {
+    let format = match headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+        Some(content_type) if content_type.starts_with("text/csv") => TransferFormat::Csv,
+        _ => TransferFormat::Json,
+    };
+    let conn = state.db.get_connection();
+    let report = import_rows(conn, tenant, format, &body, params.dry_run, operator).await?;
+
+    let status = if !report.errors.is_empty() {
+        StatusCode::BAD_REQUEST
+    } else if report.dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(report)))
+}
+
+/// 校验并导入设备，任意一行有错误或 `dry_run` 时整批不写入
+pub async fn import_rows(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    format: TransferFormat,
+    body: &[u8],
+    dry_run: bool,
+    operator: Option<String>,
+) -> Result<ImportReport, AppError> {
+    let rows = parse_import_rows(format, body)?;
+
+    // 已有序列号，用于检查重复
+    let mut serial_numbers: HashSet<String> = DeviceEntity::find()
+        .filter(DeviceColumn::SerialNumber.is_not_null())
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .filter_map(|d| d.serial_number)
+        .collect();
+
+    let total = rows.len();
+    let mut errors = Vec::new();
+    let mut devices = Vec::with_capacity(total);
+    for (index, row) in rows.into_iter().enumerate() {
+        let row_no = index + 1;
+        let mut device = match row {
+            Ok(device) => device,
+            Err(message) => {
+                errors.push(ImportRowError { row: row_no, message });
+                continue;
+            }
+        };
+
+        if device.name.trim().is_empty() {
+            errors.push(ImportRowError { row: row_no, message: "设备名称不能为空".to_string() });
+            continue;
+        }
+        if let Some(serial_number) = &device.serial_number {
+            if !serial_numbers.insert(serial_number.clone()) {
+                errors.push(ImportRowError {
+                    row: row_no,
+                    message: format!("序列号重复: {}", serial_number),
+                });
+                continue;
+            }
+        }
+        match site_service::resolve_placement(conn, device.site_id, device.area_id).await {
+            Ok((site_id, area_id)) => {
+                device.site_id = site_id;
+                device.area_id = area_id;
+            }
+            Err(AppError::InvalidInput(message)) => {
+                errors.push(ImportRowError { row: row_no, message: message.into_owned() });
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        match tenant.assign(device.organization_id) {
+            Ok(organization_id) => device.organization_id = organization_id,
+            Err(_) => {
+                let message = "不能导入到其他组织".to_string();
+                errors.push(ImportRowError { row: row_no, message });
+                continue;
+            }
+        }
+        devices.push(device);
+    }
+
+    if !errors.is_empty() || dry_run {
+        return Ok(ImportReport { total, imported: 0, dry_run, errors });
+    }
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    let imported = devices.len();
+    for device in devices {
+        let device = DeviceEntity::insert(device.into_new_device())
+            .exec_with_returning(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        config_revision::record_create(&txn, &device, operator.clone()).await?;
+    }
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    Ok(ImportReport { total, imported, dry_run: false, errors })
+}
+
+/// 导出全部设备，格式与导入一致；租户只导出本组织设备
+#[utoipa::path(
+    get,
+    path = "/devices/export",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "导出设备成功", body = [CreateDeviceRequest])
+    ),
+    tag = "Devices"
+)]
+pub async fn export_devices(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExportParams>,
+    tenant: Tenant,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+
+    let devices: Vec<CreateDeviceRequest> = tenant
+        .scope(DeviceEntity::find(), DeviceColumn::OrganizationId)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(CreateDeviceRequest::from)
+        .collect();
+
+    match params.format {
+        TransferFormat::Json => Ok(Json(devices).into_response()),
+        TransferFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for device in &devices {
+                writer.serialize(device).map_err(|_| AppError::InternalError)?;
+            }
+            let body = writer.into_inner().map_err(|_| AppError::InternalError)?;
+
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"devices.csv\""),
+                ],
+                body,
+            )
+                .into_response())
+        }
+    }
+}
+
+/// 获取全部设备各指标的最新值
+#[utoipa::path(
+    get,
+    path = "/devices/latest",
+    responses(
+        (status = 200, description = "获取最新值成功", body = [DeviceLatest])
+    ),
+    tag = "Devices"
+)]
+pub async fn get_devices_latest(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceLatest>>, AppError> {
+    let snapshot = latest::for_all_devices(
+        state.db.get_connection(),
+        &state.cache,
+        tenant.organization_id(),
+    )
+    .await?;
+
+    Ok(Json(snapshot))
+}
+
+/// 获取指定设备各指标的最新值
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/latest",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "获取最新值成功", body = DeviceLatest),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_device_latest(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<DeviceLatest>, AppError> {
+    let conn = state.db.get_connection();
+
+    tenant.check_device(conn, Some(id)).await?;
+
+    let snapshot = latest::for_device(conn, &state.cache, id).await?;
+
+    Ok(Json(snapshot))
+}
@@ -0,0 +1,257 @@
+use crate::app_state::AppState;
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity};
+use crate::models::device_state_event::{Model as DeviceStateEvent, CATEGORY_OPERATION, SOURCE_API};
+use crate::services::device_state::{self, StateDuration};
+use crate::services::run_hours::{self, DeviceRunHours};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateStateEventRequest {
+    /// online / offline / running / stopped / fault
+    pub state: String,
+    /// 缺省为接收时间
+    pub timestamp: Option<DateTime<Utc>>,
+    /// 缺省为 api
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StateEventQuery {
+    /// connectivity / operation
+    pub category: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StateDurationQuery {
+    /// connectivity / operation，默认 operation
+    pub category: Option<String>,
+    /// 缺省为 7 天前
+    pub start: Option<DateTime<Utc>>,
+    /// 缺省为当前时间
+    pub end: Option<DateTime<Utc>>,
+    /// day：按天分组；total：整段合计（默认）
+    pub group_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RunHoursQuery {
+    /// 最近几天（含今天），默认 7，最多 366
+    pub days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FleetRunHoursQuery {
+    /// 设备类型，例如 pump / blower
+    pub device_type: Option<String>,
+    pub site_id: Option<i32>,
+    /// 最近几天（含今天），默认 7，最多 366
+    pub days: Option<u32>,
+}
+
+/// 运行历史的最多天数
+const MAX_RUN_DAYS: u32 = 366;
+
+/// 记录设备状态变化
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/state-events",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = CreateStateEventRequest,
+    responses(
+        (status = 201, description = "记录成功", body = DeviceStateEvent),
+        (status = 204, description = "状态未变化，未记录"),
+        (status = 400, description = "未知的状态或时间早于最后一条事件"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn create_state_event(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<CreateStateEventRequest>,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+    device_state::ensure_device(conn, id).await?;
+    tenant.check_device(conn, Some(id)).await?;
+
+    let event = device_state::record(
+        conn,
+        &state.cache,
+        id,
+        &payload.state,
+        payload.source.as_deref().unwrap_or(SOURCE_API),
+        payload.timestamp.unwrap_or_else(Utc::now),
+    )
+    .await?;
+
+    Ok(match event {
+        Some(event) => (StatusCode::CREATED, Json(event)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+/// 获取设备状态事件
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/state-events",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        StateEventQuery
+    ),
+    responses(
+        (status = 200, description = "获取状态事件成功", body = [DeviceStateEvent]),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_state_events(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<StateEventQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceStateEvent>>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(20).min(100);
+
+    let events = device_state::list(
+        conn,
+        id,
+        query.category.as_deref(),
+        query.start,
+        query.end,
+        page,
+        per_page,
+    )
+    .await?;
+
+    Ok(Json(events))
+}
+
+/// 统计各状态持续时间，例如水泵每天的运行时长
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/state-durations",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        StateDurationQuery
+    ),
+    responses(
+        (status = 200, description = "统计成功", body = [StateDuration]),
+        (status = 400, description = "参数错误"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_state_durations(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<StateDurationQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<StateDuration>>, AppError> {
+    let conn = state.db.get_connection();
+    device_state::ensure_device(conn, id).await?;
+    tenant.check_device(conn, Some(id)).await?;
+
+    let by_day = match query.group_by.as_deref() {
+        None | Some("total") => false,
+        Some("day") => true,
+        Some(other) => return Err(AppError::InvalidInput(format!("不支持的分组方式: {}", other).into())),
+    };
+    let end = query.end.unwrap_or_else(Utc::now);
+    let start = query.start.unwrap_or(end - Duration::days(7));
+    let category = query.category.as_deref().unwrap_or(CATEGORY_OPERATION);
+
+    let durations = device_state::durations(conn, id, category, start, end, by_day).await?;
+    Ok(Json(durations))
+}
+
+/// 获取设备的累计运行小时数和每天的运行历史
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/run-hours",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        RunHoursQuery
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = DeviceRunHours),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_run_hours(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<RunHoursQuery>,
+    tenant: Tenant,
+) -> Result<Json<DeviceRunHours>, AppError> {
+    let conn = state.db.get_connection();
+    let device = DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(device.organization_id)?;
+
+    let days = query.days.unwrap_or(7).clamp(1, MAX_RUN_DAYS);
+    Ok(Json(run_hours::history(conn, &device, days, Utc::now()).await?))
+}
+
+/// 按累计运行小时数从少到多列出设备，供主备泵、风机轮换参考
+#[utoipa::path(
+    get,
+    path = "/run-hours",
+    params(FleetRunHoursQuery),
+    responses(
+        (status = 200, description = "获取成功", body = [DeviceRunHours])
+    ),
+    tag = "Devices"
+)]
+pub async fn get_fleet_run_hours(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FleetRunHoursQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceRunHours>>, AppError> {
+    let conn = state.db.get_connection();
+    let mut select = tenant.scope(DeviceEntity::find(), DeviceColumn::OrganizationId);
+    if let Some(device_type) = &query.device_type {
+        select = select.filter(DeviceColumn::DeviceType.eq(device_type.as_str()));
+    }
+    if let Some(site_id) = query.site_id {
+        select = select.filter(DeviceColumn::SiteId.eq(site_id));
+    }
+    let devices = select
+        .order_by_asc(DeviceColumn::Id)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let days = query.days.unwrap_or(7).clamp(1, MAX_RUN_DAYS);
+    let now = Utc::now();
+    let mut result = Vec::with_capacity(devices.len());
+    for device in &devices {
+        result.push(run_hours::history(conn, device, days, now).await?);
+    }
+    result.sort_by(|a, b| a.operational_hours.total_cmp(&b.operational_hours));
+    Ok(Json(result))
+}
@@ -0,0 +1,599 @@
+use crate::app_state::AppState;
+use crate::middleware::api_key::ControlKey;
+use crate::models::chemical_tank::{
+    Column as ChemicalTankColumn, Entity as ChemicalTankEntity, Model as ChemicalTank,
+};
+use crate::models::dosing_pump::{
+    Column as DosingPumpColumn, Entity as DosingPumpEntity, Model as DosingPump, MODE_AUTO,
+    MODE_MANUAL,
+};
+use crate::services::dosing;
+use crate::services::modbus_write::WriteSource;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateTankRequest {
+    pub name: String,
+    /// 药剂，例如“硫酸”
+    pub chemical: String,
+    /// 有效容积 (L)
+    pub capacity_l: f64,
+    /// 当前存量 (L)
+    pub level_l: f64,
+    /// 低液位报警阈值 (L)
+    pub low_level_l: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateTankRequest {
+    pub name: Option<String>,
+    pub chemical: Option<String>,
+    pub capacity_l: Option<f64>,
+    /// 直接校正存量，例如人工量液位后
+    pub level_l: Option<f64>,
+    pub low_level_l: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefillRequest {
+    /// 补入的药量 (L)
+    pub volume_l: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatePumpRequest {
+    pub name: String,
+    pub tank_id: i32,
+    /// 转速 100% 时的投加量 (L/h)
+    pub max_rate_lph: f64,
+    /// pwm / modbus / manual
+    pub control: String,
+    pub control_device_id: Option<i32>,
+    /// Modbus 保持寄存器地址，写入转速百分比
+    pub control_address: Option<i32>,
+    /// manual（默认）/ auto
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub ph_device_id: Option<i32>,
+    pub ph_setpoint: Option<f64>,
+    /// lower（投酸）/ raise（投碱）
+    pub direction: Option<String>,
+    /// 每偏离 1 个 pH 对应的转速 (%)
+    #[serde(default = "default_gain")]
+    pub gain: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_mode() -> String {
+    MODE_MANUAL.to_string()
+}
+
+fn default_gain() -> f64 {
+    50.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePumpRequest {
+    pub name: Option<String>,
+    pub tank_id: Option<i32>,
+    pub max_rate_lph: Option<f64>,
+    pub control: Option<String>,
+    pub control_device_id: Option<i32>,
+    pub control_address: Option<i32>,
+    pub mode: Option<String>,
+    pub ph_device_id: Option<i32>,
+    pub ph_setpoint: Option<f64>,
+    pub direction: Option<String>,
+    pub gain: Option<f64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PumpSpeedRequest {
+    /// 转速 (%)
+    pub speed_percent: f64,
+}
+
+async fn find_tank(conn: &DatabaseConnection, id: i32) -> Result<ChemicalTank, AppError> {
+    ChemicalTankEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+async fn find_pump(conn: &DatabaseConnection, id: i32) -> Result<DosingPump, AppError> {
+    DosingPumpEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+async fn ensure_tank(conn: &DatabaseConnection, tank_id: i32) -> Result<(), AppError> {
+    match find_tank(conn, tank_id).await {
+        Err(AppError::NotFound) => Err(AppError::InvalidInput("储罐不存在".into())),
+        other => other.map(|_| ()),
+    }
+}
+
+/// 获取药剂储罐列表
+#[utoipa::path(
+    get,
+    path = "/chemical-tanks",
+    responses(
+        (status = 200, description = "获取储罐列表成功", body = [ChemicalTank]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Dosing"
+)]
+pub async fn get_tanks(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ChemicalTank>>, AppError> {
+    // 加药系统是全厂共用设备
+    tenant.require_platform()?;
+    let tanks = ChemicalTankEntity::find()
+        .order_by_asc(ChemicalTankColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(tanks))
+}
+
+/// 获取指定药剂储罐
+#[utoipa::path(
+    get,
+    path = "/chemical-tanks/{id}",
+    params(
+        ("id" = i32, Path, description = "储罐 ID")
+    ),
+    responses(
+        (status = 200, description = "获取储罐成功", body = ChemicalTank),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "储罐未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn get_tank(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<ChemicalTank>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(find_tank(state.db.get_connection(), id).await?))
+}
+
+/// 创建药剂储罐
+#[utoipa::path(
+    post,
+    path = "/chemical-tanks",
+    request_body = CreateTankRequest,
+    responses(
+        (status = 201, description = "创建储罐成功", body = ChemicalTank),
+        (status = 400, description = "储罐参数无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Dosing"
+)]
+pub async fn create_tank(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateTankRequest>,
+) -> Result<(StatusCode, Json<ChemicalTank>), AppError> {
+    tenant.require_platform()?;
+    let now = Utc::now();
+    let tank = ChemicalTank {
+        id: 0,
+        name: payload.name,
+        chemical: payload.chemical,
+        capacity_l: payload.capacity_l,
+        level_l: payload.level_l,
+        low_level_l: payload.low_level_l,
+        low_alarm_active: false,
+        created_at: now,
+        updated_at: now,
+    };
+    dosing::validate_tank(&tank)?;
+
+    let mut active_model = tank.into_active_model();
+    active_model.id = Default::default();
+    let tank = ChemicalTankEntity::insert(active_model)
+        .exec_with_returning(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(tank)))
+}
+
+/// 更新药剂储罐
+#[utoipa::path(
+    put,
+    path = "/chemical-tanks/{id}",
+    params(
+        ("id" = i32, Path, description = "储罐 ID")
+    ),
+    request_body = UpdateTankRequest,
+    responses(
+        (status = 200, description = "更新储罐成功", body = ChemicalTank),
+        (status = 400, description = "储罐参数无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "储罐未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn update_tank(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdateTankRequest>,
+) -> Result<Json<ChemicalTank>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let mut active_model = find_tank(conn, id).await?.into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(chemical) = payload.chemical {
+        active_model.chemical = Set(chemical);
+    }
+    if let Some(capacity_l) = payload.capacity_l {
+        active_model.capacity_l = Set(capacity_l);
+    }
+    if let Some(level_l) = payload.level_l {
+        active_model.level_l = Set(level_l);
+    }
+    if let Some(low_level_l) = payload.low_level_l {
+        active_model.low_level_l = Set(low_level_l);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    dosing::validate_tank(&proposed)?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    Ok(Json(updated))
+}
+
+/// 删除药剂储罐，仍有加药泵使用时拒绝
+#[utoipa::path(
+    delete,
+    path = "/chemical-tanks/{id}",
+    params(
+        ("id" = i32, Path, description = "储罐 ID")
+    ),
+    responses(
+        (status = 204, description = "删除储罐成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "储罐未找到"),
+        (status = 422, description = "仍有加药泵使用该储罐")
+    ),
+    tag = "Dosing"
+)]
+pub async fn delete_tank(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let tank = find_tank(conn, id).await?;
+
+    let pumps = DosingPumpEntity::find()
+        .filter(DosingPumpColumn::TankId.eq(tank.id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if pumps > 0 {
+        return Err(AppError::Unprocessable("仍有加药泵使用该储罐".into()));
+    }
+
+    ChemicalTankEntity::delete_by_id(tank.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 补药，存量增加到不超过容积，低液位报警随之复位
+#[utoipa::path(
+    post,
+    path = "/chemical-tanks/{id}/refill",
+    params(
+        ("id" = i32, Path, description = "储罐 ID")
+    ),
+    request_body = RefillRequest,
+    responses(
+        (status = 200, description = "补药成功", body = ChemicalTank),
+        (status = 400, description = "补药量无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "储罐未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn refill_tank(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<RefillRequest>,
+) -> Result<Json<ChemicalTank>, AppError> {
+    tenant.require_platform()?;
+    let tank = dosing::refill(state.db.get_connection(), id, payload.volume_l).await?;
+    Ok(Json(tank))
+}
+
+/// 获取加药泵列表
+#[utoipa::path(
+    get,
+    path = "/dosing-pumps",
+    responses(
+        (status = 200, description = "获取加药泵列表成功", body = [DosingPump]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Dosing"
+)]
+pub async fn get_pumps(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DosingPump>>, AppError> {
+    tenant.require_platform()?;
+    let pumps = DosingPumpEntity::find()
+        .order_by_asc(DosingPumpColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(pumps))
+}
+
+/// 获取指定加药泵
+#[utoipa::path(
+    get,
+    path = "/dosing-pumps/{id}",
+    params(
+        ("id" = i32, Path, description = "加药泵 ID")
+    ),
+    responses(
+        (status = 200, description = "获取加药泵成功", body = DosingPump),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "加药泵未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn get_pump(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<DosingPump>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(find_pump(state.db.get_connection(), id).await?))
+}
+
+/// 创建加药泵，初始转速为 0
+#[utoipa::path(
+    post,
+    path = "/dosing-pumps",
+    request_body = CreatePumpRequest,
+    responses(
+        (status = 201, description = "创建加药泵成功", body = DosingPump),
+        (status = 400, description = "加药泵参数无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Dosing"
+)]
+pub async fn create_pump(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreatePumpRequest>,
+) -> Result<(StatusCode, Json<DosingPump>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let now = Utc::now();
+    let pump = DosingPump {
+        id: 0,
+        name: payload.name,
+        tank_id: payload.tank_id,
+        max_rate_lph: payload.max_rate_lph,
+        control: payload.control,
+        control_device_id: payload.control_device_id,
+        control_address: payload.control_address,
+        speed_percent: 0.0,
+        mode: payload.mode,
+        ph_device_id: payload.ph_device_id,
+        ph_setpoint: payload.ph_setpoint,
+        direction: payload.direction,
+        gain: payload.gain,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    dosing::validate_pump(&pump)?;
+    ensure_tank(conn, pump.tank_id).await?;
+    dosing::ensure_output_free(conn, &pump).await?;
+
+    let mut active_model = pump.into_active_model();
+    active_model.id = Default::default();
+    let pump = DosingPumpEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(pump)))
+}
+
+/// 更新加药泵，停用时先停泵
+#[utoipa::path(
+    put,
+    path = "/dosing-pumps/{id}",
+    params(
+        ("id" = i32, Path, description = "加药泵 ID")
+    ),
+    request_body = UpdatePumpRequest,
+    responses(
+        (status = 200, description = "更新加药泵成功", body = DosingPump),
+        (status = 400, description = "加药泵参数无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "加药泵未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn update_pump(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdatePumpRequest>,
+) -> Result<Json<DosingPump>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let mut existing = find_pump(conn, id).await?;
+
+    if payload.enabled == Some(false) && existing.speed_percent > 0.0 {
+        existing = dosing::command(&state, &existing, 0.0, WriteSource::Api, operator).await?;
+    }
+
+    let mut active_model = existing.into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(tank_id) = payload.tank_id {
+        ensure_tank(conn, tank_id).await?;
+        active_model.tank_id = Set(tank_id);
+    }
+    if let Some(max_rate_lph) = payload.max_rate_lph {
+        active_model.max_rate_lph = Set(max_rate_lph);
+    }
+    if let Some(control) = payload.control {
+        active_model.control = Set(control);
+    }
+    if let Some(control_device_id) = payload.control_device_id {
+        active_model.control_device_id = Set(Some(control_device_id));
+    }
+    if let Some(control_address) = payload.control_address {
+        active_model.control_address = Set(Some(control_address));
+    }
+    if let Some(mode) = payload.mode {
+        active_model.mode = Set(mode);
+    }
+    if let Some(ph_device_id) = payload.ph_device_id {
+        active_model.ph_device_id = Set(Some(ph_device_id));
+    }
+    if let Some(ph_setpoint) = payload.ph_setpoint {
+        active_model.ph_setpoint = Set(Some(ph_setpoint));
+    }
+    if let Some(direction) = payload.direction {
+        active_model.direction = Set(Some(direction));
+    }
+    if let Some(gain) = payload.gain {
+        active_model.gain = Set(gain);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    dosing::validate_pump(&proposed)?;
+    dosing::ensure_output_free(conn, &proposed).await?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    Ok(Json(updated))
+}
+
+/// 删除加药泵，运行中的先停泵
+#[utoipa::path(
+    delete,
+    path = "/dosing-pumps/{id}",
+    params(
+        ("id" = i32, Path, description = "加药泵 ID")
+    ),
+    responses(
+        (status = 204, description = "删除加药泵成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "加药泵未找到")
+    ),
+    tag = "Dosing"
+)]
+pub async fn delete_pump(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let pump = find_pump(conn, id).await?;
+    if pump.speed_percent > 0.0 {
+        dosing::command(&state, &pump, 0.0, WriteSource::Api, operator).await?;
+    }
+
+    DosingPumpEntity::delete_by_id(pump.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 手动调速，仅手动模式下允许，需使用具备 control 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/dosing-pumps/{id}/speed",
+    params(
+        ("id" = i32, Path, description = "加药泵 ID")
+    ),
+    request_body = PumpSpeedRequest,
+    responses(
+        (status = 200, description = "调速成功", body = DosingPump),
+        (status = 400, description = "转速超出范围"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限，或不是平台管理 Key"),
+        (status = 404, description = "加药泵未找到"),
+        (status = 422, description = "自动模式、已停用或被联锁阻止"),
+        (status = 503, description = "下发失败")
+    ),
+    tag = "Dosing"
+)]
+pub async fn set_pump_speed(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<PumpSpeedRequest>,
+) -> Result<Json<DosingPump>, AppError> {
+    tenant.require_platform()?;
+    let pump = find_pump(state.db.get_connection(), id).await?;
+    if !pump.enabled {
+        return Err(AppError::Unprocessable("加药泵已停用".into()));
+    }
+    if pump.mode == MODE_AUTO {
+        return Err(AppError::Unprocessable("自动模式下不能手动调速".into()));
+    }
+    let operator = Some(caller.operator_label());
+    let pump =
+        dosing::command(&state, &pump, payload.speed_percent, WriteSource::Api, operator).await?;
+    Ok(Json(pump))
+}
@@ -0,0 +1,119 @@
+use crate::app_state::AppState;
+use crate::services::energy::{self, AreaEnergy, DeviceEnergy, EnergyKpis};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EnergyQuery {
+    /// 开始日期（含），默认为 30 天前
+    pub start: Option<NaiveDate>,
+    /// 结束日期（含），默认为今天
+    pub end: Option<NaiveDate>,
+    pub site_id: Option<i32>,
+}
+
+impl EnergyQuery {
+    fn range(&self) -> Result<(NaiveDate, NaiveDate), AppError> {
+        let end = self.end.unwrap_or_else(|| Utc::now().date_naive());
+        let start = self.start.unwrap_or(end - Duration::days(29));
+        if start > end {
+            return Err(AppError::InvalidInput("开始日期不能晚于结束日期".into()));
+        }
+        Ok((start, end))
+    }
+}
+
+/// 获取设备用电量
+#[utoipa::path(
+    get,
+    path = "/energy/devices",
+    params(EnergyQuery),
+    responses(
+        (status = 200, description = "获取设备用电量成功", body = [DeviceEnergy]),
+        (status = 400, description = "日期范围无效")
+    ),
+    tag = "Energy"
+)]
+pub async fn get_device_energy(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EnergyQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeviceEnergy>>, AppError> {
+    let (start, end) = query.range()?;
+    let conn = state.db.get_connection();
+    let mut devices = energy::device_totals(conn, start, end, query.site_id).await?;
+    if let Some(owned) = tenant.device_ids(conn).await? {
+        devices.retain(|d| owned.contains(&d.device_id));
+    }
+    Ok(Json(devices))
+}
+
+/// 获取区域用电量
+#[utoipa::path(
+    get,
+    path = "/energy/areas",
+    params(EnergyQuery),
+    responses(
+        (status = 200, description = "获取区域用电量成功", body = [AreaEnergy]),
+        (status = 400, description = "日期范围无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Energy"
+)]
+pub async fn get_area_energy(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EnergyQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<AreaEnergy>>, AppError> {
+    // 区域和全厂指标包含其他组织的设备
+    tenant.require_platform()?;
+    let (start, end) = query.range()?;
+    let kpis = energy::kpis(
+        state.db.get_connection(),
+        &state.settings.energy,
+        start,
+        end,
+        query.site_id,
+    )
+    .await?;
+    Ok(Json(kpis.areas))
+}
+
+/// 获取能耗指标（总用电量、单位水量能耗及逐日明细）
+#[utoipa::path(
+    get,
+    path = "/energy/kpis",
+    params(EnergyQuery),
+    responses(
+        (status = 200, description = "获取能耗指标成功", body = EnergyKpis),
+        (status = 400, description = "日期范围无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Energy"
+)]
+pub async fn get_energy_kpis(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EnergyQuery>,
+    tenant: Tenant,
+) -> Result<Json<EnergyKpis>, AppError> {
+    // 区域和全厂指标包含其他组织的设备
+    tenant.require_platform()?;
+    let (start, end) = query.range()?;
+    let kpis = energy::kpis(
+        state.db.get_connection(),
+        &state.settings.energy,
+        start,
+        end,
+        query.site_id,
+    )
+    .await?;
+    Ok(Json(kpis))
+}
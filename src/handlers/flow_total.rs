@@ -0,0 +1,82 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::flow_total::{PERIOD_DAY, PERIOD_MONTH};
+use crate::services::flow_total::{self, DeviceFlowTotal};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use crate::utils::timezone;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{Duration, Months, NaiveDate, Utc};
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FlowTotalQuery {
+    /// day：逐日（默认）；month：逐月
+    pub period: Option<String>,
+    /// 开始日期（含），默认逐日为 30 天前、逐月为 11 个月前的月初
+    pub start: Option<NaiveDate>,
+    /// 结束日期（含），默认为 `timezone.default` 时区的今天
+    pub end: Option<NaiveDate>,
+}
+
+impl FlowTotalQuery {
+    fn range(&self, today: NaiveDate) -> Result<(&'static str, NaiveDate, NaiveDate), AppError> {
+        let end = self.end.unwrap_or(today);
+        let (period, start) = match self.period.as_deref() {
+            None | Some("day") => (PERIOD_DAY, self.start.unwrap_or(end - Duration::days(29))),
+            Some("month") => {
+                let start = self
+                    .start
+                    .unwrap_or_else(|| end.checked_sub_months(Months::new(11)).unwrap_or(end));
+                (PERIOD_MONTH, flow_total::month_start(start))
+            }
+            Some(other) => {
+                return Err(AppError::InvalidInput(format!("不支持的统计周期: {}", other).into()))
+            }
+        };
+        if start > end {
+            return Err(AppError::InvalidInput("开始日期不能晚于结束日期".into()));
+        }
+        Ok((period, start, end))
+    }
+}
+
+/// 获取设备的累计流量（排放水量），按日或按月
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/flow-total",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        FlowTotalQuery
+    ),
+    responses(
+        (status = 200, description = "获取累计流量成功", body = DeviceFlowTotal),
+        (status = 400, description = "统计周期或日期范围无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_flow_total(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<FlowTotalQuery>,
+    tenant: Tenant,
+) -> Result<Json<DeviceFlowTotal>, AppError> {
+    let conn = state.db.get_connection();
+    let tz = timezone::default_zone(&state.settings.timezone);
+    let (period, start, end) = query.range(timezone::local_date(tz, Utc::now()))?;
+    let device = DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check(device.organization_id)?;
+
+    Ok(Json(flow_total::device_totals(conn, &device, period, start, end).await?))
+}
@@ -1,12 +1,19 @@
+//! 流量值接口，基于通用测量值接口（metric_type = "flow"）的兼容封装
+
 use crate::app_state::AppState;
-use crate::models::flow_value::{Entity as FlowValueEntity, Model as FlowValue, ActiveModel as FlowValueActiveModel};
+use crate::handlers::measurement::ingest_response;
+use crate::models::flow_value::Model as FlowValue;
+use crate::services::data_correction::CorrectionNote;
+use crate::services::measurement::{self as measurement_service, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::FLOW as METRIC;
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
-    response::Json,
+    response::{Json, Response},
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -47,27 +54,21 @@ pub struct Pagination {
 pub async fn get_flow_values(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<FlowValue>>, AppError> {
     let conn = state.db.get_connection();
-    
+
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
-    
-    let flow_values = FlowValueEntity::find()
-        .all(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    // 简化的分页实现
-    let start = ((page - 1) * per_page) as usize;
-    let end = (start + per_page as usize).min(flow_values.len());
-    let paginated_flow_values = if start < flow_values.len() {
-        flow_values[start..end].to_vec()
-    } else {
-        vec![]
+
+    let filter = MeasurementFilter {
+        metric_type: Some(METRIC.to_string()),
+        organization_id: tenant.organization_id(),
+        ..Default::default()
     };
+    let flow_values = measurement_service::list(conn, &filter, page, per_page).await?;
 
-    Ok(Json(paginated_flow_values))
+    Ok(Json(flow_values.into_iter().map(FlowValue::from).collect()))
 }
 
 /// 获取指定流量值
@@ -86,16 +87,14 @@ pub async fn get_flow_values(
 pub async fn get_flow_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<FlowValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let flow_value = FlowValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    Ok(Json(flow_value))
+
+    let flow_value = measurement_service::get(conn, id, Some(METRIC)).await?;
+    tenant.check_device(conn, flow_value.device_id).await?;
+
+    Ok(Json(flow_value.into()))
 }
 
 /// 创建流量值
@@ -105,30 +104,35 @@ pub async fn get_flow_value(
     request_body = CreateFlowValueRequest,
     responses(
         (status = 201, description = "创建流量值成功", body = FlowValue),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
         (status = 400, description = "请求参数错误")
     ),
     tag = "Flow Values"
 )]
 pub async fn create_flow_value(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
     Json(payload): Json<CreateFlowValueRequest>,
-) -> Result<(StatusCode, Json<FlowValue>), AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
-    
-    let new_flow_value = FlowValueActiveModel {
-        timestamp: sea_orm::Set(payload.timestamp),
-        value: sea_orm::Set(payload.value),
-        device_id: sea_orm::Set(payload.device_id),
-        unit: sea_orm::Set(payload.unit),
-        ..Default::default()
-    };
-
-    let flow_value = FlowValueEntity::insert(new_flow_value)
-        .exec_with_returning(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok((StatusCode::CREATED, Json(flow_value)))
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: METRIC.to_string(),
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: Some(payload.unit),
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<FlowValue>(ingested))
 }
 
 /// 更新流量值
@@ -148,43 +152,34 @@ pub async fn create_flow_value(
 pub async fn update_flow_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdateFlowValueRequest>,
 ) -> Result<Json<FlowValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let existing_flow_value = FlowValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-        
-    let mut flow_value_active_model = existing_flow_value.into_active_model();
-    
-    if let Some(timestamp) = payload.timestamp {
-        flow_value_active_model.timestamp = sea_orm::Set(timestamp);
-    }
-    
-    if let Some(value) = payload.value {
-        flow_value_active_model.value = sea_orm::Set(value);
-    }
-    
+    tenant.check_measurement(conn, id).await?;
     if let Some(device_id) = payload.device_id {
-        flow_value_active_model.device_id = sea_orm::Set(device_id);
+        tenant.check_device(conn, device_id).await?;
     }
-    
-    if let Some(unit) = payload.unit {
-        flow_value_active_model.unit = sea_orm::Set(unit);
-    }
-    
-    // 更新 updated_at 字段
-    flow_value_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
-    
-    let updated_flow_value = FlowValueEntity::update(flow_value_active_model)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok(Json(updated_flow_value))
+
+    let updated_flow_value = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        Some(METRIC),
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+            ..Default::default()
+        },
+        CorrectionNote { corrected_by: operator, ..Default::default() },
+    )
+    .await?;
+
+    Ok(Json(updated_flow_value.into()))
 }
 
 /// 删除流量值
@@ -203,19 +198,14 @@ pub async fn update_flow_value(
 pub async fn delete_flow_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let conn = state.db.get_connection();
-    
-    let flow_value = FlowValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    let _ = FlowValueEntity::delete_by_id(flow_value.id)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
+    tenant.check_measurement(conn, id).await?;
+
+    let note = CorrectionNote { corrected_by: operator, ..Default::default() };
+    measurement_service::delete(conn, &state.cache, id, Some(METRIC), note).await?;
 
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
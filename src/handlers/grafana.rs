@@ -0,0 +1,168 @@
+use crate::app_state::AppState;
+use crate::services::grafana::{self, Annotation, SearchResult, TimeSeries};
+use crate::services::query_guard;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{extract::State, http::StatusCode, response::Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TimeRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct SearchRequest {
+    /// 过滤条件，匹配序列标识或名称
+    #[serde(default)]
+    pub target: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct QueryTarget {
+    /// `设备ID:指标`，例如 `3:flow`
+    pub target: String,
+    #[serde(default, rename = "refId")]
+    pub ref_id: Option<String>,
+    /// 只支持 timeserie
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    pub range: TimeRange,
+    #[serde(default)]
+    pub interval_ms: Option<i64>,
+    #[serde(default)]
+    pub max_data_points: Option<i64>,
+    pub targets: Vec<QueryTarget>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct AnnotationQuery {
+    /// 设备ID，为空时返回全部设备的报警
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AnnotationRequest {
+    pub range: TimeRange,
+    #[serde(default)]
+    pub annotation: AnnotationQuery,
+}
+
+/// 数据源连通性检查
+#[utoipa::path(
+    get,
+    path = "/grafana",
+    responses(
+        (status = 200, description = "数据源可用")
+    ),
+    tag = "Grafana"
+)]
+pub async fn health() -> StatusCode {
+    StatusCode::OK
+}
+
+/// 列出可选序列
+#[utoipa::path(
+    post,
+    path = "/grafana/search",
+    request_body = SearchRequest,
+    responses(
+        (status = 200, description = "查询成功", body = [SearchResult])
+    ),
+    tag = "Grafana"
+)]
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    payload: Option<Json<SearchRequest>>,
+) -> Result<Json<Vec<SearchResult>>, AppError> {
+    let Json(payload) = payload.unwrap_or_default();
+    let results = grafana::search(
+        state.db.get_connection(),
+        &state.cache,
+        &payload.target,
+        tenant.organization_id(),
+    )
+    .await?;
+    Ok(Json(results))
+}
+
+/// 查询时间序列
+#[utoipa::path(
+    post,
+    path = "/grafana/query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "查询成功", body = [TimeSeries]),
+        (status = 400, description = "序列标识无效"),
+        (status = 404, description = "设备未找到"),
+        (status = 422, description = "时间桶过多")
+    ),
+    tag = "Grafana"
+)]
+pub async fn query(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<QueryRequest>,
+) -> Result<Json<Vec<TimeSeries>>, AppError> {
+    let TimeRange { from, to } = payload.range;
+    let bucket_secs = grafana::bucket_secs(from, to, payload.interval_ms, payload.max_data_points);
+    query_guard::check_buckets(&state.settings.query_guard, from, to, bucket_secs)?;
+
+    let conn = state.db.get_connection();
+    let store = state.timeseries.as_ref();
+    let mut series = Vec::with_capacity(payload.targets.len());
+    for target in payload.targets.iter().filter(|t| !t.target.trim().is_empty()) {
+        if let Some((device_id, _)) = grafana::parse_target(&target.target) {
+            tenant.check_device(conn, Some(device_id)).await?;
+        }
+        series.push(grafana::query(conn, store, &target.target, from, to, bucket_secs).await?);
+    }
+    Ok(Json(series))
+}
+
+/// 报警记录作为注释
+#[utoipa::path(
+    post,
+    path = "/grafana/annotations",
+    request_body = AnnotationRequest,
+    responses(
+        (status = 200, description = "查询成功", body = [Annotation]),
+        (status = 400, description = "设备ID无效")
+    ),
+    tag = "Grafana"
+)]
+pub async fn annotations(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<AnnotationRequest>,
+) -> Result<Json<Vec<Annotation>>, AppError> {
+    let device_id = match payload.annotation.query.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(query) => Some(
+            query
+                .parse()
+                .map_err(|_| AppError::InvalidInput(format!("无效的设备ID: {}", query).into()))?,
+        ),
+    };
+    let TimeRange { from, to } = payload.range;
+    let annotations = grafana::annotations(
+        state.db.get_connection(),
+        from,
+        to,
+        device_id,
+        tenant.organization_id(),
+    )
+    .await?;
+    Ok(Json(annotations))
+}
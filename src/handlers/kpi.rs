@@ -0,0 +1,392 @@
+use crate::app_state::AppState;
+use crate::models::kpi_definition::{
+    Column as KpiColumn, Entity as KpiEntity, Model as KpiDefinition,
+};
+use crate::services::config_revision;
+use crate::services::kpi::{self, Binding, KpiValue};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set,
+    TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateKpiRequest {
+    /// 字母、数字和下划线，例如 `cod_removal`
+    pub name: String,
+    pub description: Option<String>,
+    /// 公式，例如 `(inlet_cod_avg - outlet_cod_avg) / inlet_cod_avg * 100`
+    pub expression: String,
+    /// 变量别名到设备指标的绑定，公式中用 `<别名>_avg|min|max|count` 引用
+    pub variables: BTreeMap<String, Binding>,
+    pub unit: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateKpiRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub expression: Option<String>,
+    pub variables: Option<BTreeMap<String, Binding>>,
+    pub unit: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// 公式校验结果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KpiValidation {
+    pub valid: bool,
+    pub error: Option<String>,
+    /// 公式引用的变量
+    pub variables: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct KpiValueQuery {
+    /// 默认为结束时间前 24 小时
+    pub start: Option<DateTime<Utc>>,
+    /// 默认为当前时间
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl KpiValueQuery {
+    fn range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let end = self.end.unwrap_or_else(Utc::now);
+        (self.start.unwrap_or(end - Duration::hours(24)), end)
+    }
+}
+
+fn variables_json(variables: &BTreeMap<String, Binding>) -> Result<String, AppError> {
+    serde_json::to_string(variables).map_err(|_| AppError::InternalError)
+}
+
+async fn find(conn: &DatabaseConnection, id: i32) -> Result<KpiDefinition, AppError> {
+    KpiEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+async fn ensure_unique_name(
+    conn: &DatabaseConnection,
+    name: &str,
+    id: Option<i32>,
+) -> Result<(), AppError> {
+    let mut select = KpiEntity::find().filter(KpiColumn::Name.eq(name));
+    if let Some(id) = id {
+        select = select.filter(KpiColumn::Id.ne(id));
+    }
+    let existing = select.one(conn).await.map_err(|_| AppError::InternalError)?;
+    if existing.is_some() {
+        return Err(AppError::InvalidInput(format!("KPI 名称已存在: {}", name).into()));
+    }
+    Ok(())
+}
+
+/// 获取 KPI 定义列表
+#[utoipa::path(
+    get,
+    path = "/kpi-definitions",
+    responses(
+        (status = 200, description = "获取 KPI 定义列表成功", body = [KpiDefinition]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "KPI"
+)]
+pub async fn get_kpi_definitions(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<KpiDefinition>>, AppError> {
+    // 指标按全厂数据计算
+    tenant.require_platform()?;
+    let definitions = KpiEntity::find()
+        .order_by_asc(KpiColumn::Name)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(definitions))
+}
+
+/// 获取指定 KPI 定义
+#[utoipa::path(
+    get,
+    path = "/kpi-definitions/{id}",
+    params(
+        ("id" = i32, Path, description = "KPI ID")
+    ),
+    responses(
+        (status = 200, description = "获取 KPI 定义成功", body = KpiDefinition),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "KPI 未找到")
+    ),
+    tag = "KPI"
+)]
+pub async fn get_kpi_definition(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<KpiDefinition>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(find(state.db.get_connection(), id).await?))
+}
+
+/// 创建 KPI 定义
+#[utoipa::path(
+    post,
+    path = "/kpi-definitions",
+    request_body = CreateKpiRequest,
+    responses(
+        (status = 201, description = "创建 KPI 定义成功", body = KpiDefinition),
+        (status = 400, description = "公式或变量绑定无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "KPI"
+)]
+pub async fn create_kpi_definition(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateKpiRequest>,
+) -> Result<(StatusCode, Json<KpiDefinition>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let now = Utc::now();
+    let definition = KpiDefinition {
+        id: 0,
+        name: payload.name,
+        description: payload.description,
+        expression: payload.expression,
+        variables: variables_json(&payload.variables)?,
+        unit: payload.unit,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    kpi::validate(&definition)?;
+    ensure_unique_name(conn, &definition.name, None).await?;
+
+    let mut active_model = definition.into_active_model();
+    active_model.id = Default::default();
+    let definition = KpiEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &definition, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(definition)))
+}
+
+/// 更新 KPI 定义
+#[utoipa::path(
+    put,
+    path = "/kpi-definitions/{id}",
+    params(
+        ("id" = i32, Path, description = "KPI ID")
+    ),
+    request_body = UpdateKpiRequest,
+    responses(
+        (status = 200, description = "更新 KPI 定义成功", body = KpiDefinition),
+        (status = 400, description = "公式或变量绑定无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "KPI 未找到")
+    ),
+    tag = "KPI"
+)]
+pub async fn update_kpi_definition(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateKpiRequest>,
+) -> Result<Json<KpiDefinition>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let existing = find(conn, id).await?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(name) = payload.name {
+        ensure_unique_name(conn, &name, Some(id)).await?;
+        active_model.name = Set(name);
+    }
+    if let Some(description) = payload.description {
+        active_model.description = Set(Some(description));
+    }
+    if let Some(expression) = payload.expression {
+        active_model.expression = Set(expression);
+    }
+    if let Some(variables) = payload.variables {
+        active_model.variables = Set(variables_json(&variables)?);
+    }
+    if let Some(unit) = payload.unit {
+        active_model.unit = Set(Some(unit));
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    kpi::validate(&proposed)?;
+
+    let updated = KpiEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除 KPI 定义
+#[utoipa::path(
+    delete,
+    path = "/kpi-definitions/{id}",
+    params(
+        ("id" = i32, Path, description = "KPI ID")
+    ),
+    responses(
+        (status = 204, description = "删除 KPI 定义成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "KPI 未找到")
+    ),
+    tag = "KPI"
+)]
+pub async fn delete_kpi_definition(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let definition = find(conn, id).await?;
+
+    KpiEntity::delete_by_id(definition.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &definition, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 校验 KPI 公式（不保存）
+#[utoipa::path(
+    post,
+    path = "/kpi-definitions/validate",
+    request_body = CreateKpiRequest,
+    responses(
+        (status = 200, description = "校验完成", body = KpiValidation)
+    ),
+    tag = "KPI"
+)]
+pub async fn validate_kpi_definition(
+    Json(payload): Json<CreateKpiRequest>,
+) -> Result<Json<KpiValidation>, AppError> {
+    let now = Utc::now();
+    let definition = KpiDefinition {
+        id: 0,
+        name: payload.name,
+        description: payload.description,
+        expression: payload.expression,
+        variables: variables_json(&payload.variables)?,
+        unit: payload.unit,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let validation = match kpi::validate(&definition) {
+        Ok(expr) => KpiValidation {
+            valid: true,
+            error: None,
+            variables: expr.variables().into_iter().map(String::from).collect(),
+        },
+        Err(AppError::InvalidInput(msg)) => KpiValidation {
+            valid: false,
+            error: Some(msg.into_owned()),
+            variables: Vec::new(),
+        },
+        Err(e) => return Err(e),
+    };
+    Ok(Json(validation))
+}
+
+/// 计算指定 KPI
+#[utoipa::path(
+    get,
+    path = "/kpi-definitions/{id}/value",
+    params(
+        ("id" = i32, Path, description = "KPI ID"),
+        KpiValueQuery
+    ),
+    responses(
+        (status = 200, description = "计算 KPI 成功", body = KpiValue),
+        (status = 400, description = "时间范围无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "KPI 未找到")
+    ),
+    tag = "KPI"
+)]
+pub async fn get_kpi_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<KpiValueQuery>,
+    tenant: Tenant,
+) -> Result<Json<KpiValue>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let definition = find(conn, id).await?;
+    let (start, end) = query.range();
+
+    let value = kpi::evaluate(conn, state.timeseries.as_ref(), &definition, start, end).await?;
+    Ok(Json(value))
+}
+
+/// 计算全部启用的 KPI
+#[utoipa::path(
+    get,
+    path = "/kpis",
+    params(KpiValueQuery),
+    responses(
+        (status = 200, description = "计算 KPI 成功", body = [KpiValue]),
+        (status = 400, description = "时间范围无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "KPI"
+)]
+pub async fn get_kpi_values(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<KpiValueQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<KpiValue>>, AppError> {
+    tenant.require_platform()?;
+    let (start, end) = query.range();
+    let values =
+        kpi::evaluate_all(state.db.get_connection(), state.timeseries.as_ref(), start, end).await?;
+    Ok(Json(values))
+}
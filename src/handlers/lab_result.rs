@@ -0,0 +1,381 @@
+use crate::app_state::AppState;
+use crate::models::data_correction::{
+    Model as DataCorrection, ACTION_DELETE, ACTION_UPDATE, RECORD_LAB_RESULT,
+};
+use crate::models::lab_result::{
+    Column as LabResultColumn, Entity as LabResultEntity, Model as LabResult,
+};
+use crate::services::data_correction::{self, CorrectionNote};
+use crate::services::lab_result::{self, Analyte};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait, TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateLabResultRequest {
+    pub site_id: Option<i32>,
+    pub sample_point: String,
+    pub device_id: Option<i32>,
+    /// 化验项目，见 `/lab-results/analytes`
+    pub analyte: String,
+    pub value: f64,
+    /// 缺省为化验项目的单位
+    pub unit: Option<String>,
+    pub sampled_at: DateTime<Utc>,
+    pub analyzed_at: Option<DateTime<Utc>>,
+    pub method: Option<String>,
+    pub analyst: String,
+    pub notes: Option<String>,
+}
+
+/// 未给出的项保持不变；修正原因必填
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateLabResultRequest {
+    pub sample_point: Option<String>,
+    pub value: Option<f64>,
+    pub sampled_at: Option<DateTime<Utc>>,
+    pub analyzed_at: Option<DateTime<Utc>>,
+    pub method: Option<String>,
+    pub analyst: Option<String>,
+    pub notes: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LabResultQuery {
+    pub site_id: Option<i32>,
+    pub sample_point: Option<String>,
+    pub analyte: Option<String>,
+    /// 采样时间范围
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteLabResultQuery {
+    /// 删除原因，必填
+    pub reason: String,
+}
+
+fn require_reason(reason: &str) -> Result<(), AppError> {
+    if reason.trim().is_empty() {
+        return Err(AppError::InvalidInput("修正化验结果须说明原因".into()));
+    }
+    Ok(())
+}
+
+async fn find_result(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<LabResult, AppError> {
+    let result = LabResultEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, result.device_id).await?;
+    Ok(result)
+}
+
+/// 获取化验项目
+#[utoipa::path(
+    get,
+    path = "/lab-results/analytes",
+    responses(
+        (status = 200, description = "获取化验项目成功", body = [Analyte])
+    ),
+    tag = "Lab Results"
+)]
+pub async fn get_analytes() -> Json<Vec<Analyte>> {
+    Json(lab_result::ANALYTES.to_vec())
+}
+
+/// 获取化验结果列表，按采样时间倒序
+#[utoipa::path(
+    get,
+    path = "/lab-results",
+    params(LabResultQuery),
+    responses(
+        (status = 200, description = "获取化验结果成功", body = [LabResult])
+    ),
+    tag = "Lab Results"
+)]
+pub async fn get_lab_results(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LabResultQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<LabResult>>, AppError> {
+    let mut select = tenant.scope_devices(LabResultEntity::find(), LabResultColumn::DeviceId);
+    if let Some(site_id) = query.site_id {
+        select = select.filter(LabResultColumn::SiteId.eq(site_id));
+    }
+    if let Some(sample_point) = &query.sample_point {
+        select = select.filter(LabResultColumn::SamplePoint.eq(sample_point.as_str()));
+    }
+    if let Some(analyte) = &query.analyte {
+        select = select.filter(LabResultColumn::Analyte.eq(analyte.as_str()));
+    }
+    if let Some(start) = query.start {
+        select = select.filter(LabResultColumn::SampledAt.gte(start));
+    }
+    if let Some(end) = query.end {
+        select = select.filter(LabResultColumn::SampledAt.lte(end));
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+    let results = select
+        .order_by_desc(LabResultColumn::SampledAt)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(results))
+}
+
+/// 获取指定化验结果
+#[utoipa::path(
+    get,
+    path = "/lab-results/{id}",
+    params(
+        ("id" = i32, Path, description = "化验结果 ID")
+    ),
+    responses(
+        (status = 200, description = "获取化验结果成功", body = LabResult),
+        (status = 404, description = "化验结果未找到")
+    ),
+    tag = "Lab Results"
+)]
+pub async fn get_lab_result(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<LabResult>, AppError> {
+    Ok(Json(find_result(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 录入化验结果
+#[utoipa::path(
+    post,
+    path = "/lab-results",
+    request_body = CreateLabResultRequest,
+    responses(
+        (status = 201, description = "录入化验结果成功", body = LabResult),
+        (status = 400, description = "化验结果无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Lab Results"
+)]
+pub async fn create_lab_result(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateLabResultRequest>,
+) -> Result<(StatusCode, Json<LabResult>), AppError> {
+    let conn = state.db.get_connection();
+    // 未关联设备的化验结果只有平台管理 Key 可见
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let unit = payload.unit.unwrap_or_else(|| {
+        lab_result::lookup(&payload.analyte).map(|a| a.unit.to_string()).unwrap_or_default()
+    });
+    let now = Utc::now();
+    let result = LabResult {
+        id: 0,
+        site_id: payload.site_id,
+        sample_point: payload.sample_point,
+        device_id: payload.device_id,
+        analyte: payload.analyte,
+        value: payload.value,
+        unit,
+        sampled_at: payload.sampled_at,
+        analyzed_at: payload.analyzed_at,
+        method: payload.method,
+        analyst: payload.analyst,
+        notes: payload.notes,
+        created_at: now,
+        updated_at: now,
+    };
+    lab_result::validate(&result)?;
+
+    let mut active_model = result.into_active_model();
+    active_model.id = Default::default();
+    let result = LabResultEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// 修正化验结果，修改前的版本保存在修正记录中
+#[utoipa::path(
+    put,
+    path = "/lab-results/{id}",
+    params(
+        ("id" = i32, Path, description = "化验结果 ID")
+    ),
+    request_body = UpdateLabResultRequest,
+    responses(
+        (status = 200, description = "修正化验结果成功", body = LabResult),
+        (status = 400, description = "化验结果无效或未说明原因"),
+        (status = 404, description = "化验结果未找到")
+    ),
+    tag = "Lab Results"
+)]
+pub async fn update_lab_result(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateLabResultRequest>,
+) -> Result<Json<LabResult>, AppError> {
+    require_reason(&payload.reason)?;
+    let conn = state.db.get_connection();
+    let existing = find_result(conn, tenant, id).await?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(sample_point) = payload.sample_point {
+        active_model.sample_point = Set(sample_point);
+    }
+    if let Some(value) = payload.value {
+        active_model.value = Set(value);
+    }
+    if let Some(sampled_at) = payload.sampled_at {
+        active_model.sampled_at = Set(sampled_at);
+    }
+    if let Some(analyzed_at) = payload.analyzed_at {
+        active_model.analyzed_at = Set(Some(analyzed_at));
+    }
+    if let Some(method) = payload.method {
+        active_model.method = Set(Some(method));
+    }
+    if let Some(analyst) = payload.analyst {
+        active_model.analyst = Set(analyst);
+    }
+    if let Some(notes) = payload.notes {
+        active_model.notes = Set(Some(notes));
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    lab_result::validate(&proposed)?;
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    let updated = LabResultEntity::update(active_model)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let note = CorrectionNote { reason: Some(payload.reason), corrected_by: operator };
+    let correction = data_correction::record(
+        &txn,
+        RECORD_LAB_RESULT,
+        id,
+        ACTION_UPDATE,
+        &existing,
+        Some(&updated),
+        note,
+    )
+    .await?;
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+    info!(
+        "Lab result {} {} corrected by {:?}: {} -> {}",
+        id, updated.analyte, correction.corrected_by, existing.value, updated.value
+    );
+
+    Ok(Json(updated))
+}
+
+/// 删除化验结果，删除前的版本保存在修正记录中
+#[utoipa::path(
+    delete,
+    path = "/lab-results/{id}",
+    params(
+        ("id" = i32, Path, description = "化验结果 ID"),
+        DeleteLabResultQuery
+    ),
+    responses(
+        (status = 204, description = "删除化验结果成功"),
+        (status = 400, description = "未说明原因"),
+        (status = 404, description = "化验结果未找到")
+    ),
+    tag = "Lab Results"
+)]
+pub async fn delete_lab_result(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    Query(query): Query<DeleteLabResultQuery>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    require_reason(&query.reason)?;
+    let conn = state.db.get_connection();
+    let existing = find_result(conn, tenant, id).await?;
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    LabResultEntity::delete_by_id(existing.id)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let note = CorrectionNote { reason: Some(query.reason), corrected_by: operator };
+    data_correction::record(&txn, RECORD_LAB_RESULT, id, ACTION_DELETE, &existing, None, note)
+        .await?;
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取化验结果的修正历史，含已删除的化验结果
+#[utoipa::path(
+    get,
+    path = "/lab-results/{id}/corrections",
+    params(
+        ("id" = i32, Path, description = "化验结果 ID")
+    ),
+    responses(
+        (status = 200, description = "获取修正历史成功，按版本顺序", body = [DataCorrection]),
+        (status = 404, description = "化验结果未找到")
+    ),
+    tag = "Lab Results"
+)]
+pub async fn get_lab_result_corrections(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DataCorrection>>, AppError> {
+    let conn = state.db.get_connection();
+    let corrections = data_correction::history(conn, RECORD_LAB_RESULT, id).await?;
+
+    if tenant != Tenant::All {
+        // 化验结果可能已删除，按最后一个版本关联的设备判断
+        let device_id = match corrections.last() {
+            Some(last) => {
+                let json = last.snapshot.as_deref().unwrap_or(&last.previous);
+                serde_json::from_str::<LabResult>(json)
+                    .map_err(|_| AppError::InternalError)?
+                    .device_id
+            }
+            None => find_result(conn, Tenant::All, id).await?.device_id,
+        };
+        tenant.check_device(conn, device_id).await?;
+    }
+    Ok(Json(corrections))
+}
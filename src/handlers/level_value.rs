@@ -0,0 +1,219 @@
+//! 液位值接口（湿井、水池、储罐），基于通用测量值接口（metric_type = "level"）
+
+use crate::app_state::AppState;
+use crate::handlers::measurement::ingest_response;
+use crate::models::measurement::Model as Measurement;
+use crate::services::data_correction::CorrectionNote;
+use crate::services::measurement::{self as measurement_service, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::LEVEL as METRIC;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State, Query},
+    http::StatusCode,
+    response::{Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateLevelValueRequest {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    /// m（默认）/ cm / mm，入库前换算为 m
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateLevelValueRequest {
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub value: Option<f64>,
+    pub device_id: Option<Option<i32>>,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LevelValueQuery {
+    pub device_id: Option<i32>,
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 获取液位值列表
+#[utoipa::path(
+    get,
+    path = "/level-values",
+    params(LevelValueQuery),
+    responses(
+        (status = 200, description = "获取液位值列表成功", body = [Measurement])
+    ),
+    tag = "Level Values"
+)]
+pub async fn get_level_values(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LevelValueQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Measurement>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+
+    let filter = MeasurementFilter {
+        metric_type: Some(METRIC.to_string()),
+        device_id: query.device_id,
+        start: query.start,
+        end: query.end,
+        organization_id: tenant.organization_id(),
+        ..Default::default()
+    };
+    let level_values = measurement_service::list(conn, &filter, page, per_page).await?;
+
+    Ok(Json(level_values))
+}
+
+/// 获取指定液位值
+#[utoipa::path(
+    get,
+    path = "/level-values/{id}",
+    params(
+        ("id" = i32, Path, description = "液位值ID")
+    ),
+    responses(
+        (status = 200, description = "获取液位值成功", body = Measurement),
+        (status = 404, description = "液位值未找到")
+    ),
+    tag = "Level Values"
+)]
+pub async fn get_level_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+
+    let level_value = measurement_service::get(conn, id, Some(METRIC)).await?;
+    tenant.check_device(conn, level_value.device_id).await?;
+
+    Ok(Json(level_value))
+}
+
+/// 创建液位值
+#[utoipa::path(
+    post,
+    path = "/level-values",
+    request_body = CreateLevelValueRequest,
+    responses(
+        (status = 201, description = "创建液位值成功", body = Measurement),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
+        (status = 400, description = "请求参数错误")
+    ),
+    tag = "Level Values"
+)]
+pub async fn create_level_value(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateLevelValueRequest>,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: METRIC.to_string(),
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: payload.unit,
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<Measurement>(ingested))
+}
+
+/// 更新液位值
+#[utoipa::path(
+    put,
+    path = "/level-values/{id}",
+    params(
+        ("id" = i32, Path, description = "液位值ID")
+    ),
+    request_body = UpdateLevelValueRequest,
+    responses(
+        (status = 200, description = "更新液位值成功", body = Measurement),
+        (status = 404, description = "液位值未找到")
+    ),
+    tag = "Level Values"
+)]
+pub async fn update_level_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateLevelValueRequest>,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+    if let Some(device_id) = payload.device_id {
+        tenant.check_device(conn, device_id).await?;
+    }
+
+    let updated_level_value = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        Some(METRIC),
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+
+            ..Default::default()
+        },
+        CorrectionNote { corrected_by: operator, ..Default::default() },
+    )
+    .await?;
+
+    Ok(Json(updated_level_value))
+}
+
+/// 删除液位值
+#[utoipa::path(
+    delete,
+    path = "/level-values/{id}",
+    params(
+        ("id" = i32, Path, description = "液位值ID")
+    ),
+    responses(
+        (status = 204, description = "删除液位值成功"),
+        (status = 404, description = "液位值未找到")
+    ),
+    tag = "Level Values"
+)]
+pub async fn delete_level_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+
+    let note = CorrectionNote { corrected_by: operator, ..Default::default() };
+    measurement_service::delete(conn, &state.cache, id, Some(METRIC), note).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,440 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::maintenance_plan::{
+    Column as PlanColumn, Entity as PlanEntity, Model as MaintenancePlan,
+};
+use crate::models::work_order::{
+    Column as WorkOrderColumn, Entity as WorkOrderEntity, Model as WorkOrder, STATUS_OPEN,
+};
+use crate::services::maintenance;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatePlanRequest {
+    pub device_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    /// calendar / operating_hours
+    pub interval_kind: String,
+    pub interval_days: Option<i32>,
+    pub interval_hours: Option<f64>,
+    /// 工单打开超过该天数未关闭即报警
+    #[serde(default = "default_overdue_days")]
+    pub overdue_days: i32,
+    /// 上次完成时间，默认取设备的上次维护时间
+    pub last_done_at: Option<DateTime<Utc>>,
+    /// 上次完成时的运行小时数，默认取设备当前值
+    pub last_done_hours: Option<f64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_overdue_days() -> i32 {
+    7
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePlanRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub interval_kind: Option<String>,
+    pub interval_days: Option<i32>,
+    pub interval_hours: Option<f64>,
+    pub overdue_days: Option<i32>,
+    pub last_done_at: Option<DateTime<Utc>>,
+    pub last_done_hours: Option<f64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateWorkOrderRequest {
+    pub device_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    /// 应完成时间，默认 7 天后
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CloseWorkOrderRequest {
+    /// 处理记录
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WorkOrderQuery {
+    /// open / closed
+    pub status: Option<String>,
+    pub device_id: Option<i32>,
+}
+
+async fn find_plan(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<MaintenancePlan, AppError> {
+    let plan = PlanEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, Some(plan.device_id)).await?;
+    Ok(plan)
+}
+
+async fn find_work_order(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<WorkOrder, AppError> {
+    let order = WorkOrderEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, Some(order.device_id)).await?;
+    Ok(order)
+}
+
+async fn find_device(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    device_id: i32,
+) -> Result<crate::models::device::Model, AppError> {
+    DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::InvalidInput("设备不存在".into()))
+}
+
+/// 获取维护计划列表
+#[utoipa::path(
+    get,
+    path = "/maintenance-plans",
+    responses(
+        (status = 200, description = "获取维护计划列表成功", body = [MaintenancePlan])
+    ),
+    tag = "Maintenance"
+)]
+pub async fn get_plans(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<MaintenancePlan>>, AppError> {
+    let plans = tenant
+        .scope_devices(PlanEntity::find(), PlanColumn::DeviceId)
+        .order_by_asc(PlanColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(plans))
+}
+
+/// 获取指定维护计划
+#[utoipa::path(
+    get,
+    path = "/maintenance-plans/{id}",
+    params(
+        ("id" = i32, Path, description = "维护计划 ID")
+    ),
+    responses(
+        (status = 200, description = "获取维护计划成功", body = MaintenancePlan),
+        (status = 404, description = "维护计划未找到")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn get_plan(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<MaintenancePlan>, AppError> {
+    Ok(Json(find_plan(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 创建维护计划
+#[utoipa::path(
+    post,
+    path = "/maintenance-plans",
+    request_body = CreatePlanRequest,
+    responses(
+        (status = 201, description = "创建维护计划成功", body = MaintenancePlan),
+        (status = 400, description = "维护计划参数无效")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn create_plan(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreatePlanRequest>,
+) -> Result<(StatusCode, Json<MaintenancePlan>), AppError> {
+    let conn = state.db.get_connection();
+    let device = find_device(conn, tenant, payload.device_id).await?;
+
+    let now = Utc::now();
+    let plan = MaintenancePlan {
+        id: 0,
+        device_id: payload.device_id,
+        name: payload.name,
+        description: payload.description,
+        interval_kind: payload.interval_kind,
+        interval_days: payload.interval_days,
+        interval_hours: payload.interval_hours,
+        overdue_days: payload.overdue_days,
+        last_done_at: payload.last_done_at.unwrap_or(device.last_maintenance),
+        last_done_hours: payload.last_done_hours.unwrap_or(device.operational_hours),
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    maintenance::validate_plan(&plan)?;
+
+    let mut active_model = plan.into_active_model();
+    active_model.id = Default::default();
+    let plan = PlanEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(plan)))
+}
+
+/// 更新维护计划
+#[utoipa::path(
+    put,
+    path = "/maintenance-plans/{id}",
+    params(
+        ("id" = i32, Path, description = "维护计划 ID")
+    ),
+    request_body = UpdatePlanRequest,
+    responses(
+        (status = 200, description = "更新维护计划成功", body = MaintenancePlan),
+        (status = 400, description = "维护计划参数无效"),
+        (status = 404, description = "维护计划未找到")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn update_plan(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdatePlanRequest>,
+) -> Result<Json<MaintenancePlan>, AppError> {
+    let conn = state.db.get_connection();
+    let mut active_model = find_plan(conn, tenant, id).await?.into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(description) = payload.description {
+        active_model.description = Set(Some(description));
+    }
+    if let Some(interval_kind) = payload.interval_kind {
+        active_model.interval_kind = Set(interval_kind);
+    }
+    if let Some(interval_days) = payload.interval_days {
+        active_model.interval_days = Set(Some(interval_days));
+    }
+    if let Some(interval_hours) = payload.interval_hours {
+        active_model.interval_hours = Set(Some(interval_hours));
+    }
+    if let Some(overdue_days) = payload.overdue_days {
+        active_model.overdue_days = Set(overdue_days);
+    }
+    if let Some(last_done_at) = payload.last_done_at {
+        active_model.last_done_at = Set(last_done_at);
+    }
+    if let Some(last_done_hours) = payload.last_done_hours {
+        active_model.last_done_hours = Set(last_done_hours);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    maintenance::validate_plan(&proposed)?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    Ok(Json(updated))
+}
+
+/// 删除维护计划，已生成的工单保留
+#[utoipa::path(
+    delete,
+    path = "/maintenance-plans/{id}",
+    params(
+        ("id" = i32, Path, description = "维护计划 ID")
+    ),
+    responses(
+        (status = 204, description = "删除维护计划成功"),
+        (status = 404, description = "维护计划未找到")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn delete_plan(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    let plan = find_plan(conn, tenant, id).await?;
+
+    PlanEntity::delete_by_id(plan.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取工单列表，按创建时间倒序
+#[utoipa::path(
+    get,
+    path = "/work-orders",
+    params(WorkOrderQuery),
+    responses(
+        (status = 200, description = "获取工单列表成功", body = [WorkOrder])
+    ),
+    tag = "Maintenance"
+)]
+pub async fn get_work_orders(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WorkOrderQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<WorkOrder>>, AppError> {
+    let mut select = tenant.scope_devices(WorkOrderEntity::find(), WorkOrderColumn::DeviceId);
+    if let Some(status) = query.status {
+        select = select.filter(WorkOrderColumn::Status.eq(status));
+    }
+    if let Some(device_id) = query.device_id {
+        select = select.filter(WorkOrderColumn::DeviceId.eq(device_id));
+    }
+    let orders = select
+        .order_by_desc(WorkOrderColumn::CreatedAt)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(orders))
+}
+
+/// 获取指定工单
+#[utoipa::path(
+    get,
+    path = "/work-orders/{id}",
+    params(
+        ("id" = i32, Path, description = "工单 ID")
+    ),
+    responses(
+        (status = 200, description = "获取工单成功", body = WorkOrder),
+        (status = 404, description = "工单未找到")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn get_work_order(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<WorkOrder>, AppError> {
+    Ok(Json(find_work_order(state.db.get_connection(), tenant, id).await?))
+}
+
+/// 人工创建工单，例如巡检发现的故障
+#[utoipa::path(
+    post,
+    path = "/work-orders",
+    request_body = CreateWorkOrderRequest,
+    responses(
+        (status = 201, description = "创建工单成功", body = WorkOrder),
+        (status = 400, description = "工单参数无效")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn create_work_order(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateWorkOrderRequest>,
+) -> Result<(StatusCode, Json<WorkOrder>), AppError> {
+    let conn = state.db.get_connection();
+    if payload.title.trim().is_empty() {
+        return Err(AppError::InvalidInput("工单标题不能为空".into()));
+    }
+    find_device(conn, tenant, payload.device_id).await?;
+
+    let now = Utc::now();
+    let order = WorkOrder {
+        id: 0,
+        plan_id: None,
+        device_id: payload.device_id,
+        title: payload.title,
+        description: payload.description,
+        status: STATUS_OPEN.to_string(),
+        due_at: payload.due_at.unwrap_or(now + Duration::days(default_overdue_days() as i64)),
+        overdue_alarmed: false,
+        closed_at: None,
+        closed_by: None,
+        notes: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let mut active_model = order.into_active_model();
+    active_model.id = Default::default();
+    let order = WorkOrderEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(order)))
+}
+
+/// 关闭工单并填写处理记录
+///
+/// 来源维护计划从关闭时刻重新计时，设备的上次维护时间同步更新。
+#[utoipa::path(
+    post,
+    path = "/work-orders/{id}/close",
+    params(
+        ("id" = i32, Path, description = "工单 ID")
+    ),
+    request_body = CloseWorkOrderRequest,
+    responses(
+        (status = 200, description = "关闭工单成功", body = WorkOrder),
+        (status = 404, description = "工单未找到"),
+        (status = 422, description = "工单已关闭")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn close_work_order(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CloseWorkOrderRequest>,
+) -> Result<Json<WorkOrder>, AppError> {
+    let conn = state.db.get_connection();
+    find_work_order(conn, tenant, id).await?;
+
+    let order = maintenance::close(conn, id, payload.notes, operator).await?;
+    Ok(Json(order))
+}
@@ -0,0 +1,531 @@
+use crate::app_state::AppState;
+use crate::models::data_correction::{Model as DataCorrection, RECORD_MEASUREMENT};
+use crate::models::measurement::Model as Measurement;
+use crate::models::device::Entity as DeviceEntity;
+use crate::services::binary_ingest;
+use crate::services::compression::{self, InterpolatedPoint};
+use crate::services::data_correction::{self, CorrectionNote};
+use crate::services::measurement::{self as measurement_service, AggregatePoint, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::{self, MetricTypeResponse};
+use crate::services::query_guard::{self, RawQuery};
+use crate::services::read_only::Ingested;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::{self, Tenant};
+use axum::{
+    body::Bytes,
+    extract::{Path, State, Query},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use sea_orm::{EntityTrait, PaginatorTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+/// 二进制上报最多返回的错误条数
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// 单条上报的响应：入库时返回 201 和记录，被压缩合并或暂存时返回 202
+pub(crate) fn ingest_response<T: Serialize + From<Measurement>>(ingested: Ingested) -> Response {
+    let message = match ingested {
+        Ingested::Stored(measurement) => {
+            return (StatusCode::CREATED, Json(T::from(measurement))).into_response();
+        }
+        Ingested::Buffered => "系统处于只读模式，数据已暂存，恢复后自动入库",
+        Ingested::Suppressed => "数据变化在压缩容差内，已合并到相邻记录",
+    };
+    let buffered = matches!(ingested, Ingested::Buffered);
+    (StatusCode::ACCEPTED, Json(json!({ "buffered": buffered, "message": message })))
+        .into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateMeasurementRequest {
+    pub metric_type: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    /// 缺省为默认单位；可使用 `/metric-types` 中列出的其他单位，入库前换算为默认单位
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateMeasurementRequest {
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub value: Option<f64>,
+    pub device_id: Option<Option<i32>>,
+    pub unit: Option<String>,
+    /// 人工设定数据质量：good / suspect / bad / out_of_range / frozen；
+    /// 未给出而修改了值时按质量规则重新评估
+    pub quality: Option<String>,
+    /// 修正原因，与修改前的版本一并留档
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteMeasurementQuery {
+    /// 删除原因，与删除前的版本一并留档
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MeasurementQuery {
+    /// 指标类型，例如 ph、tds、turbidity、flow、do、orp
+    pub metric: Option<String>,
+    pub device_id: Option<i32>,
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    /// 只查询该质量的数据，例如 suspect、frozen
+    pub quality: Option<String>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateQuery {
+    /// 指标类型
+    pub metric: String,
+    pub device_id: Option<i32>,
+    pub start: chrono::DateTime<chrono::Utc>,
+    /// 缺省为当前时间
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    /// 时间桶长度（秒）
+    pub bucket_secs: i64,
+    /// 包含 bad / out_of_range 的数据，默认排除
+    #[serde(default)]
+    pub include_bad: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct InterpolateQuery {
+    /// 指标类型
+    pub metric: String,
+    /// 为空时查询不关联设备的数据
+    pub device_id: Option<i32>,
+    pub start: chrono::DateTime<chrono::Utc>,
+    /// 缺省为当前时间
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    /// 输出间隔（秒）
+    pub interval_secs: i64,
+    /// 前后入库点相距超过该值时视为缺数，缺省为压缩规则最长入库间隔的两倍
+    pub max_gap_secs: Option<i64>,
+}
+
+/// 二进制上报的处理结果
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BinaryIngestSummary {
+    pub accepted: u64,
+    /// 只读模式下暂存、稍后入库的条数
+    pub buffered: u64,
+    pub rejected: u64,
+    pub errors: Vec<BinaryIngestError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BinaryIngestError {
+    /// 记录在请求体中的序号（跨帧连续编号），从 0 开始
+    pub index: u64,
+    pub message: String,
+}
+
+/// 获取支持的指标类型
+#[utoipa::path(
+    get,
+    path = "/metric-types",
+    responses(
+        (status = 200, description = "获取指标类型成功", body = [MetricTypeResponse])
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_metric_types() -> Json<Vec<MetricTypeResponse>> {
+    Json(metric_registry::METRICS.iter().map(MetricTypeResponse::from).collect())
+}
+
+/// 获取测量值列表
+#[utoipa::path(
+    get,
+    path = "/measurements",
+    params(MeasurementQuery),
+    responses(
+        (status = 200, description = "获取测量值列表成功", body = [Measurement]),
+        (status = 400, description = "未知的指标类型"),
+        (status = 422, description = "预计扫描行数过多，应改用聚合查询")
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_measurements(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MeasurementQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Measurement>>, AppError> {
+    let conn = state.db.get_connection();
+
+    if let Some(metric) = &query.metric {
+        if metric_registry::lookup(metric).is_none() {
+            return Err(AppError::InvalidInput(format!("未知的指标类型: {}", metric).into()));
+        }
+    }
+
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+
+    let raw = RawQuery {
+        start: query.start,
+        end: query.end,
+        single_device: query.device_id.is_some(),
+        single_metric: query.metric.is_some(),
+        offset: page.saturating_sub(1) * per_page,
+    };
+    let device_count = if raw.single_device || raw.start.is_none() {
+        1
+    } else {
+        DeviceEntity::find().count(conn).await.map_err(|_| AppError::InternalError)?
+    };
+    query_guard::check_raw(&state.settings.query_guard, &raw, device_count)?;
+
+    let filter = MeasurementFilter {
+        metric_type: query.metric,
+        device_id: query.device_id,
+        start: query.start,
+        end: query.end,
+        quality: query.quality,
+        organization_id: tenant.organization_id(),
+        ..Default::default()
+    };
+    let measurements = measurement_service::list(conn, &filter, page, per_page).await?;
+
+    Ok(Json(measurements))
+}
+
+/// 按时间桶聚合测量值
+#[utoipa::path(
+    get,
+    path = "/measurements/aggregate",
+    params(AggregateQuery),
+    responses(
+        (status = 200, description = "聚合成功", body = [AggregatePoint]),
+        (status = 400, description = "参数错误"),
+        (status = 422, description = "时间桶过多，应增大 bucket_secs")
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_measurement_aggregate(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AggregateQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<AggregatePoint>>, AppError> {
+    if metric_registry::lookup(&query.metric).is_none() {
+        return Err(AppError::InvalidInput(format!("未知的指标类型: {}", query.metric).into()));
+    }
+    let end = query.end.unwrap_or_else(chrono::Utc::now);
+    query_guard::check_buckets(&state.settings.query_guard, query.start, end, query.bucket_secs)?;
+
+    let filter = MeasurementFilter {
+        metric_type: Some(query.metric),
+        device_id: query.device_id,
+        start: Some(query.start),
+        end: Some(end),
+        include_bad: query.include_bad,
+        organization_id: tenant.organization_id(),
+        ..Default::default()
+    };
+    let conn = state.db.get_connection();
+    let points = state.timeseries.aggregate(conn, &filter, query.bucket_secs).await?;
+
+    Ok(Json(points))
+}
+
+/// 按固定间隔插值还原测量值
+///
+/// 适用于经过旋转门或死区压缩的数据，在入库点之间线性插值。
+#[utoipa::path(
+    get,
+    path = "/measurements/interpolated",
+    params(InterpolateQuery),
+    responses(
+        (status = 200, description = "插值成功", body = [InterpolatedPoint]),
+        (status = 400, description = "参数错误"),
+        (status = 422, description = "输出点数过多，应增大 interval_secs")
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_measurement_interpolated(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InterpolateQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<InterpolatedPoint>>, AppError> {
+    if metric_registry::lookup(&query.metric).is_none() {
+        return Err(AppError::InvalidInput(format!("未知的指标类型: {}", query.metric).into()));
+    }
+    let end = query.end.unwrap_or_else(chrono::Utc::now);
+    query_guard::check_buckets(&state.settings.query_guard, query.start, end, query.interval_secs)?;
+
+    let max_gap_secs = query.max_gap_secs.or_else(|| {
+        state
+            .settings
+            .compression
+            .rule_for(query.device_id, &query.metric)
+            .map(|rule| rule.max_interval_secs as i64 * 2)
+    });
+    let conn = state.db.get_connection();
+    // 租户只能插值本组织设备的数据，须指定设备
+    tenant.check_device(conn, query.device_id).await?;
+    let points =
+        measurement_service::series(conn, &query.metric, query.device_id, query.start, end).await?;
+    let result = compression::interpolate(&points, query.start, end, query.interval_secs, max_gap_secs);
+
+    Ok(Json(result))
+}
+
+/// 获取指定测量值
+#[utoipa::path(
+    get,
+    path = "/measurements/{id}",
+    params(
+        ("id" = i32, Path, description = "测量值ID")
+    ),
+    responses(
+        (status = 200, description = "获取测量值成功", body = Measurement),
+        (status = 404, description = "测量值未找到")
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_measurement(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+    let measurement = measurement_service::get(conn, id, None).await?;
+    tenant.check_device(conn, measurement.device_id).await?;
+    Ok(Json(measurement))
+}
+
+/// 创建测量值
+#[utoipa::path(
+    post,
+    path = "/measurements",
+    request_body = CreateMeasurementRequest,
+    responses(
+        (status = 201, description = "创建测量值成功", body = Measurement),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
+        (status = 400, description = "请求参数错误")
+    ),
+    tag = "Measurements"
+)]
+pub async fn create_measurement(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateMeasurementRequest>,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: payload.metric_type,
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: payload.unit,
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<Measurement>(ingested))
+}
+
+/// 二进制批量上报测量值
+///
+/// 请求体为一个或多个小端序二进制帧（`Content-Type: application/octet-stream`），
+/// 帧格式见 `services::binary_ingest`。
+#[utoipa::path(
+    post,
+    path = "/measurements/binary",
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "上报处理完成", body = BinaryIngestSummary),
+        (status = 400, description = "帧结构错误，整批未写入")
+    ),
+    tag = "Measurements"
+)]
+pub async fn create_measurements_binary(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    body: Bytes,
+) -> Result<Json<BinaryIngestSummary>, AppError> {
+    let frames = binary_ingest::decode_frames(&body)
+        .map_err(|e| AppError::InvalidInput(format!("请求体格式错误: {}", e).into()))?;
+
+    let conn = state.db.get_connection();
+    // 租户只能上报本组织设备的数据
+    let devices: Option<HashSet<i32>> = match tenant.organization_id() {
+        Some(organization_id) => {
+            let ids = tenant::organization_device_ids(conn, organization_id).await?;
+            Some(ids.into_iter().collect())
+        }
+        None => None,
+    };
+    let allowed = |device_id: Option<i32>| match &devices {
+        Some(devices) => device_id.is_some_and(|id| devices.contains(&id)),
+        None => true,
+    };
+    let mut summary = BinaryIngestSummary::default();
+    let mut index = 0u64;
+    for record in frames.iter().flat_map(|frame| frame.records()) {
+        let result = match record {
+            Ok(record) if !allowed(record.device_id) => {
+                Err(AppError::InvalidInput("设备不属于本组织".into()))
+            }
+            Ok(record) => {
+                let new = NewMeasurement {
+                    metric_type: record.metric_type.to_string(),
+                    timestamp: record.timestamp,
+                    value: record.value,
+                    device_id: record.device_id,
+                    unit: None,
+                };
+                state.read_only.ingest(conn, &state.cache, new).await
+            }
+            Err(message) => Err(AppError::InvalidInput(message.into())),
+        };
+        match result {
+            Ok(false) => summary.accepted += 1,
+            Ok(true) => summary.buffered += 1,
+            // 数据库或暂存不可用时中止，由网关整批重发
+            Err(e @ (AppError::InternalError | AppError::ServiceUnavailable(_))) => return Err(e),
+            Err(e) => {
+                summary.rejected += 1;
+                if summary.errors.len() < MAX_REPORTED_ERRORS {
+                    let message = match e {
+                        AppError::InvalidInput(msg) => msg.into_owned(),
+                        other => format!("{:?}", other),
+                    };
+                    summary.errors.push(BinaryIngestError { index, message });
+                }
+            }
+        }
+        index += 1;
+    }
+
+    Ok(Json(summary))
+}
+
+/// 更新测量值，修改前的版本保存在修正记录中
+#[utoipa::path(
+    put,
+    path = "/measurements/{id}",
+    params(
+        ("id" = i32, Path, description = "测量值ID")
+    ),
+    request_body = UpdateMeasurementRequest,
+    responses(
+        (status = 200, description = "更新测量值成功", body = Measurement),
+        (status = 404, description = "测量值未找到")
+    ),
+    tag = "Measurements"
+)]
+pub async fn update_measurement(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateMeasurementRequest>,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+    if let Some(device_id) = payload.device_id {
+        tenant.check_device(conn, device_id).await?;
+    }
+
+    let measurement = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        None,
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+            quality: payload.quality,
+        },
+        CorrectionNote { reason: payload.reason, corrected_by: operator },
+    )
+    .await?;
+
+    Ok(Json(measurement))
+}
+
+/// 删除测量值，删除前的版本保存在修正记录中
+#[utoipa::path(
+    delete,
+    path = "/measurements/{id}",
+    params(
+        ("id" = i32, Path, description = "测量值ID"),
+        DeleteMeasurementQuery
+    ),
+    responses(
+        (status = 204, description = "删除测量值成功"),
+        (status = 404, description = "测量值未找到")
+    ),
+    tag = "Measurements"
+)]
+pub async fn delete_measurement(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Query(query): Query<DeleteMeasurementQuery>,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+    let note = CorrectionNote { reason: query.reason, corrected_by: operator };
+    measurement_service::delete(conn, &state.cache, id, None, note).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取测量值的修正历史，含已删除的测量值
+#[utoipa::path(
+    get,
+    path = "/measurements/{id}/corrections",
+    params(
+        ("id" = i32, Path, description = "测量值ID")
+    ),
+    responses(
+        (status = 200, description = "获取修正历史成功，按版本顺序", body = [DataCorrection])
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_measurement_corrections(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DataCorrection>>, AppError> {
+    let conn = state.db.get_connection();
+    let corrections = data_correction::history(conn, RECORD_MEASUREMENT, id).await?;
+
+    if tenant != Tenant::All {
+        // 测量值可能已删除，按最后一个版本所属的设备判断
+        let device_id = match corrections.last() {
+            Some(last) => {
+                let json = last.snapshot.as_deref().unwrap_or(&last.previous);
+                serde_json::from_str::<Measurement>(json)
+                    .map_err(|_| AppError::InternalError)?
+                    .device_id
+            }
+            None => measurement_service::get(conn, id, None).await?.device_id,
+        };
+        tenant.check_device(conn, device_id).await?;
+    }
+    Ok(Json(corrections))
+}
@@ -0,0 +1,124 @@
+use crate::app_state::AppState;
+use crate::message_queue::rabbitmq::{DeadLetter, RabbitMQManager};
+use crate::message_queue::schema::{self, SchemaChange};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+/// 单次查看或重放的最多条数
+const MAX_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeadLetterQuery {
+    /// 最多返回的条数，默认 50
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReplayDeadLettersRequest {
+    /// 最多重放的条数，默认 100
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayDeadLettersResponse {
+    pub replayed: usize,
+}
+
+fn manager(state: &AppState) -> Result<&RabbitMQManager, AppError> {
+    state
+        .rabbitmq
+        .as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("RabbitMQ 未启用或未连接".into()))
+}
+
+/// 查看死信队列中的消息，查看后消息仍保留在死信队列
+#[utoipa::path(
+    get,
+    path = "/message-queue/dead-letters",
+    params(DeadLetterQuery),
+    responses(
+        (status = 200, description = "获取死信消息成功", body = [DeadLetter]),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 503, description = "RabbitMQ 未启用或不可用")
+    ),
+    tag = "Message Queue"
+)]
+pub async fn get_dead_letters(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DeadLetterQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DeadLetter>>, AppError> {
+    tenant.require_platform()?;
+    let limit = query.limit.unwrap_or(50).min(MAX_LIMIT);
+    let letters = manager(&state)?
+        .peek_dead_letters(&state.settings.rabbitmq.queue, limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to read dead letters: {}", e);
+            AppError::ServiceUnavailable("读取死信队列失败".into())
+        })?;
+    Ok(Json(letters))
+}
+
+/// 把死信队列中的消息重新投递到原队列
+#[utoipa::path(
+    post,
+    path = "/message-queue/dead-letters/replay",
+    request_body = ReplayDeadLettersRequest,
+    responses(
+        (status = 200, description = "重放成功", body = ReplayDeadLettersResponse),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 503, description = "RabbitMQ 未启用或不可用")
+    ),
+    tag = "Message Queue"
+)]
+pub async fn replay_dead_letters(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<ReplayDeadLettersRequest>,
+) -> Result<Json<ReplayDeadLettersResponse>, AppError> {
+    tenant.require_platform()?;
+    let limit = payload.limit.unwrap_or(100).min(MAX_LIMIT);
+    let replayed = manager(&state)?
+        .replay_dead_letters(&state.settings.rabbitmq.queue, limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to replay dead letters: {}", e);
+            AppError::ServiceUnavailable("重放死信消息失败".into())
+        })?;
+    Ok(Json(ReplayDeadLettersResponse { replayed }))
+}
+
+/// 获取全部对外事件的 JSON Schema，`version` 与事件信封中的 `version` 对应
+#[utoipa::path(
+    get,
+    path = "/schemas/events",
+    responses(
+        (status = 200, description = "JSON Schema（draft 2020-12），含路由键与输出通道", body = Object)
+    ),
+    tag = "Message Queue"
+)]
+pub async fn get_event_schemas() -> Json<serde_json::Value> {
+    Json(schema::document())
+}
+
+/// 获取事件格式的版本变更记录
+#[utoipa::path(
+    get,
+    path = "/schemas/events/changelog",
+    responses(
+        (status = 200, description = "按版本升序排列", body = [SchemaChange])
+    ),
+    tag = "Message Queue"
+)]
+pub async fn get_event_schema_changelog() -> Json<Vec<SchemaChange>> {
+    Json(schema::changelog())
+}
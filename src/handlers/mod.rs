@@ -6,4 +6,46 @@ pub mod turbidity_value;
 pub mod flow_value;
 pub mod alarm_rule;
 pub mod alarm_log;
-pub mod automation_rule;
\ No newline at end of file
+pub mod automation_rule;
+pub mod measurement;
+pub mod api_key;
+pub mod system;
+pub mod remote_session;
+pub mod config_revision;
+pub mod site;
+pub mod area;
+pub mod config_bundle;
+pub mod serial_console;
+pub mod calibration_curve;
+pub mod tank_geometry;
+pub mod pump_curve;
+pub mod vibration;
+pub mod device_state;
+pub mod daily_summary;
+pub mod pwm;
+pub mod modbus;
+pub mod modbus_mapping;
+pub mod calendar;
+pub mod grafana;
+pub mod alarm_shelving;
+pub mod message_queue;
+pub mod alarm_kpi;
+pub mod trend;
+pub mod api_usage;
+pub mod kpi;
+pub mod actuator;
+pub mod report;
+pub mod permit;
+pub mod energy;
+pub mod dosing;
+pub mod control_loop;
+pub mod maintenance;
+pub mod pump_group;
+pub mod level_value;
+pub mod pressure_value;
+pub mod flow_total;
+pub mod calibration;
+pub mod lab_result;
+pub mod data_correction;
+pub mod organization;
+pub mod simulation;
\ No newline at end of file
@@ -0,0 +1,117 @@
+use crate::app_state::AppState;
+use crate::config::modbus::RegisterKind;
+use crate::middleware::api_key::ControlKey;
+use crate::models::modbus_write::Model as ModbusWrite;
+use crate::services::api_key::SCOPE_OVERRIDE;
+use crate::services::modbus_write::{self, WriteRequest, WriteSource};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WriteRegisterRequest {
+    /// holding / coil，默认 holding
+    pub kind: Option<String>,
+    pub address: u16,
+    /// 工程值，线圈写 1 / 0
+    pub value: f64,
+    /// 越过联锁检查，必须同时填写原因，调用方 Key 须具备 override 权限
+    #[serde(default)]
+    pub override_interlocks: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ModbusWriteQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 人工写入 Modbus 寄存器，需使用具备 control 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/modbus/writes",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = WriteRegisterRequest,
+    responses(
+        (status = 200, description = "写入成功，超出范围的值已截断", body = ModbusWrite),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限，或越过联锁时没有 override 权限"),
+        (status = 404, description = "设备未找到"),
+        (status = 422, description = "寄存器不允许写入或联锁不满足"),
+        (status = 503, description = "Modbus 未启用或通信失败")
+    ),
+    tag = "Devices"
+)]
+pub async fn write_register(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<WriteRegisterRequest>,
+) -> Result<Json<ModbusWrite>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+    if payload.override_interlocks && !caller.has_scope(SCOPE_OVERRIDE) {
+        return Err(AppError::Forbidden);
+    }
+
+    let kind = match payload.kind.as_deref().unwrap_or("holding") {
+        "holding" => RegisterKind::Holding,
+        "coil" => RegisterKind::Coil,
+        other => return Err(AppError::InvalidInput(format!("未知的寄存器类型: {}", other).into())),
+    };
+
+    let request = WriteRequest {
+        device_id: id,
+        kind,
+        address: payload.address,
+        value: payload.value,
+        source: WriteSource::Api,
+        operator: Some(caller.operator_label()),
+        override_interlocks: payload.override_interlocks,
+        reason: payload.reason,
+    };
+    let record = modbus_write::write(conn, &state.cache, &state.settings.modbus, request).await?;
+
+    Ok(Json(record))
+}
+
+/// 获取设备的 Modbus 写入记录
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/modbus/writes",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        ModbusWriteQuery
+    ),
+    responses(
+        (status = 200, description = "获取写入记录成功", body = [ModbusWrite]),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_writes(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<ModbusWriteQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ModbusWrite>>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(20).min(100);
+
+    let writes = modbus_write::list(conn, id, page, per_page).await?;
+    Ok(Json(writes))
+}
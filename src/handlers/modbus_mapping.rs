@@ -0,0 +1,303 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::modbus_mapping::{
+    Column as MappingColumn, Entity as MappingEntity, Model as ModbusMapping,
+};
+use crate::services::config_revision;
+use crate::services::modbus_mapping;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set,
+    TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateModbusMappingRequest {
+    pub device_id: i32,
+    pub name: String,
+    /// 1 读线圈 / 2 读离散输入 / 3 读保持寄存器 / 4 读输入寄存器
+    pub function_code: i32,
+    pub address: i32,
+    /// bool / u16 / i16 / u32 / i32 / f32 / u64 / i64 / f64
+    pub data_type: String,
+    /// ABCD / CDAB / BADC / DCBA，默认 ABCD
+    #[serde(default = "default_byte_order")]
+    pub byte_order: String,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+    pub metric_type: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_byte_order() -> String {
+    "ABCD".to_string()
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateModbusMappingRequest {
+    pub name: Option<String>,
+    pub function_code: Option<i32>,
+    pub address: Option<i32>,
+    pub data_type: Option<String>,
+    pub byte_order: Option<String>,
+    pub scale: Option<f64>,
+    pub offset: Option<f64>,
+    pub metric_type: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ModbusMappingQuery {
+    pub device_id: Option<i32>,
+}
+
+/// 寄存器映射按所属设备判断是否属于当前租户
+async fn find_mapping(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<ModbusMapping, AppError> {
+    let mapping = MappingEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    tenant.check_device(conn, Some(mapping.device_id)).await?;
+    Ok(mapping)
+}
+
+/// 获取寄存器映射列表
+#[utoipa::path(
+    get,
+    path = "/modbus-mappings",
+    params(ModbusMappingQuery),
+    responses(
+        (status = 200, description = "获取寄存器映射列表成功", body = [ModbusMapping])
+    ),
+    tag = "Modbus"
+)]
+pub async fn get_modbus_mappings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ModbusMappingQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ModbusMapping>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mut select = tenant.scope_devices(MappingEntity::find(), MappingColumn::DeviceId);
+    if let Some(device_id) = query.device_id {
+        select = select.filter(MappingColumn::DeviceId.eq(device_id));
+    }
+    let mappings = select
+        .order_by_asc(MappingColumn::DeviceId)
+        .order_by_asc(MappingColumn::Address)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(mappings))
+}
+
+/// 获取指定寄存器映射
+#[utoipa::path(
+    get,
+    path = "/modbus-mappings/{id}",
+    params(
+        ("id" = i32, Path, description = "寄存器映射ID")
+    ),
+    responses(
+        (status = 200, description = "获取寄存器映射成功", body = ModbusMapping),
+        (status = 404, description = "寄存器映射未找到")
+    ),
+    tag = "Modbus"
+)]
+pub async fn get_modbus_mapping(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<ModbusMapping>, AppError> {
+    let conn = state.db.get_connection();
+
+    let mapping = find_mapping(conn, tenant, id).await?;
+
+    Ok(Json(mapping))
+}
+
+/// 创建寄存器映射
+#[utoipa::path(
+    post,
+    path = "/modbus-mappings",
+    request_body = CreateModbusMappingRequest,
+    responses(
+        (status = 201, description = "创建寄存器映射成功", body = ModbusMapping),
+        (status = 400, description = "请求参数错误")
+    ),
+    tag = "Modbus"
+)]
+pub async fn create_modbus_mapping(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreateModbusMappingRequest>,
+) -> Result<(StatusCode, Json<ModbusMapping>), AppError> {
+    let conn = state.db.get_connection();
+
+    DeviceEntity::find_by_id(payload.device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::InvalidInput(format!("设备不存在: {}", payload.device_id).into()))?;
+
+    let now = Utc::now();
+    let mapping = ModbusMapping {
+        id: 0,
+        device_id: payload.device_id,
+        name: payload.name,
+        function_code: payload.function_code,
+        address: payload.address,
+        data_type: payload.data_type,
+        byte_order: payload.byte_order,
+        scale: payload.scale,
+        offset: payload.offset,
+        metric_type: payload.metric_type,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    modbus_mapping::validate(&mapping)?;
+
+    let mut active_model = mapping.into_active_model();
+    active_model.id = Default::default();
+    let mapping = MappingEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &mapping, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(mapping)))
+}
+
+/// 更新寄存器映射
+#[utoipa::path(
+    put,
+    path = "/modbus-mappings/{id}",
+    params(
+        ("id" = i32, Path, description = "寄存器映射ID")
+    ),
+    request_body = UpdateModbusMappingRequest,
+    responses(
+        (status = 200, description = "更新寄存器映射成功", body = ModbusMapping),
+        (status = 400, description = "请求参数错误"),
+        (status = 404, description = "寄存器映射未找到")
+    ),
+    tag = "Modbus"
+)]
+pub async fn update_modbus_mapping(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdateModbusMappingRequest>,
+) -> Result<Json<ModbusMapping>, AppError> {
+    let conn = state.db.get_connection();
+
+    let existing = find_mapping(conn, tenant, id).await?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(function_code) = payload.function_code {
+        active_model.function_code = Set(function_code);
+    }
+    if let Some(address) = payload.address {
+        active_model.address = Set(address);
+    }
+    if let Some(data_type) = payload.data_type {
+        active_model.data_type = Set(data_type);
+    }
+    if let Some(byte_order) = payload.byte_order {
+        active_model.byte_order = Set(byte_order);
+    }
+    if let Some(scale) = payload.scale {
+        active_model.scale = Set(scale);
+    }
+    if let Some(offset) = payload.offset {
+        active_model.offset = Set(offset);
+    }
+    if let Some(metric_type) = payload.metric_type {
+        active_model.metric_type = Set(metric_type);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    modbus_mapping::validate(&proposed)?;
+
+    let updated = MappingEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除寄存器映射
+#[utoipa::path(
+    delete,
+    path = "/modbus-mappings/{id}",
+    params(
+        ("id" = i32, Path, description = "寄存器映射ID")
+    ),
+    responses(
+        (status = 204, description = "删除寄存器映射成功"),
+        (status = 404, description = "寄存器映射未找到")
+    ),
+    tag = "Modbus"
+)]
+pub async fn delete_modbus_mapping(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+
+    let mapping = find_mapping(conn, tenant, id).await?;
+
+    MappingEntity::delete_by_id(mapping.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &mapping, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,213 @@
+use crate::app_state::AppState;
+use crate::models::api_key::{Column as ApiKeyColumn, Entity as ApiKeyEntity};
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity};
+use crate::models::organization::{
+    ActiveModel as OrganizationActiveModel, Column as OrganizationColumn,
+    Entity as OrganizationEntity, Model as Organization,
+};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateOrganizationRequest {
+    pub name: Option<String>,
+    pub code: Option<String>,
+}
+
+async fn find_organization(conn: &DatabaseConnection, id: i32) -> Result<Organization, AppError> {
+    OrganizationEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+/// 获取组织列表，租户只能看到本组织
+#[utoipa::path(
+    get,
+    path = "/organizations",
+    responses(
+        (status = 200, description = "获取组织列表成功", body = [Organization])
+    ),
+    tag = "Organizations"
+)]
+pub async fn get_organizations(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Organization>>, AppError> {
+    let organizations = tenant
+        .scope(OrganizationEntity::find(), OrganizationColumn::Id)
+        .order_by_asc(OrganizationColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(organizations))
+}
+
+/// 获取指定组织
+#[utoipa::path(
+    get,
+    path = "/organizations/{id}",
+    params(
+        ("id" = i32, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取组织成功", body = Organization),
+        (status = 404, description = "组织未找到")
+    ),
+    tag = "Organizations"
+)]
+pub async fn get_organization(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Organization>, AppError> {
+    tenant.check(Some(id))?;
+    Ok(Json(find_organization(state.db.get_connection(), id).await?))
+}
+
+/// 创建组织，仅平台管理 Key
+#[utoipa::path(
+    post,
+    path = "/organizations",
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "创建组织成功", body = Organization),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Organizations"
+)]
+pub async fn create_organization(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<Organization>), AppError> {
+    tenant.require_platform()?;
+    if payload.name.trim().is_empty() || payload.code.trim().is_empty() {
+        return Err(AppError::InvalidInput("组织名称和编号不能为空".into()));
+    }
+
+    let now = chrono::Utc::now();
+    let new_organization = OrganizationActiveModel {
+        name: sea_orm::Set(payload.name),
+        code: sea_orm::Set(payload.code),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
+        ..Default::default()
+    };
+    let organization = OrganizationEntity::insert(new_organization)
+        .exec_with_returning(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+/// 更新组织，仅平台管理 Key
+#[utoipa::path(
+    put,
+    path = "/organizations/{id}",
+    params(
+        ("id" = i32, Path, description = "组织ID")
+    ),
+    request_body = UpdateOrganizationRequest,
+    responses(
+        (status = 200, description = "更新组织成功", body = Organization),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "组织未找到")
+    ),
+    tag = "Organizations"
+)]
+pub async fn update_organization(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdateOrganizationRequest>,
+) -> Result<Json<Organization>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let mut active_model = find_organization(conn, id).await?.into_active_model();
+
+    if let Some(name) = payload.name {
+        active_model.name = sea_orm::Set(name);
+    }
+    if let Some(code) = payload.code {
+        active_model.code = sea_orm::Set(code);
+    }
+    active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    let organization = OrganizationEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(organization))
+}
+
+/// 删除组织，仅平台管理 Key；组织下须没有设备和 API Key
+#[utoipa::path(
+    delete,
+    path = "/organizations/{id}",
+    params(
+        ("id" = i32, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 204, description = "删除组织成功"),
+        (status = 400, description = "组织下仍有设备或 API Key"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "组织未找到")
+    ),
+    tag = "Organizations"
+)]
+pub async fn delete_organization(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let organization = find_organization(conn, id).await?;
+
+    let devices = DeviceEntity::find()
+        .filter(DeviceColumn::OrganizationId.eq(organization.id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let api_keys = ApiKeyEntity::find()
+        .filter(ApiKeyColumn::OrganizationId.eq(organization.id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if devices > 0 || api_keys > 0 {
+        return Err(AppError::InvalidInput(
+            format!("组织下仍有 {} 台设备、{} 个 API Key", devices, api_keys).into(),
+        ));
+    }
+
+    OrganizationEntity::delete_by_id(organization.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,331 @@
+use crate::app_state::AppState;
+use crate::models::discharge_permit::{
+    Column as PermitColumn, Entity as PermitEntity, Model as DischargePermit, LIMIT_MAX,
+};
+use crate::models::permit_exceedance::Model as PermitExceedance;
+use crate::services::config_revision;
+use crate::services::permit::{self, ComplianceSummary};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sea_orm::{DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, Set, TryIntoModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatePermitRequest {
+    pub name: String,
+    /// 排污许可证编号
+    pub permit_number: Option<String>,
+    /// 排口监测设备
+    pub device_id: i32,
+    /// 受限参数，例如 `cod`
+    pub metric_type: String,
+    pub limit_value: f64,
+    /// max（默认）/ min
+    #[serde(default = "default_limit_type")]
+    pub limit_type: String,
+    /// hourly / daily / weekly / monthly
+    pub averaging_period: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_limit_type() -> String {
+    LIMIT_MAX.to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePermitRequest {
+    pub name: Option<String>,
+    pub permit_number: Option<String>,
+    pub device_id: Option<i32>,
+    pub metric_type: Option<String>,
+    pub limit_value: Option<f64>,
+    pub limit_type: Option<String>,
+    pub averaging_period: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExceedanceQuery {
+    pub permit_id: Option<i32>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ComplianceQuery {
+    /// YYYY-MM，默认为本月
+    pub month: Option<String>,
+}
+
+async fn find(conn: &DatabaseConnection, id: i32) -> Result<DischargePermit, AppError> {
+    PermitEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+/// 获取排污许可限值列表
+#[utoipa::path(
+    get,
+    path = "/discharge-permits",
+    responses(
+        (status = 200, description = "获取许可限值列表成功", body = [DischargePermit]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Compliance"
+)]
+pub async fn get_permits(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DischargePermit>>, AppError> {
+    // 排放许可和达标情况按全厂统计
+    tenant.require_platform()?;
+    let permits = PermitEntity::find()
+        .order_by_asc(PermitColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(permits))
+}
+
+/// 获取指定排污许可限值
+#[utoipa::path(
+    get,
+    path = "/discharge-permits/{id}",
+    params(
+        ("id" = i32, Path, description = "许可限值 ID")
+    ),
+    responses(
+        (status = 200, description = "获取许可限值成功", body = DischargePermit),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "许可限值未找到")
+    ),
+    tag = "Compliance"
+)]
+pub async fn get_permit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<DischargePermit>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(find(state.db.get_connection(), id).await?))
+}
+
+/// 创建排污许可限值
+#[utoipa::path(
+    post,
+    path = "/discharge-permits",
+    request_body = CreatePermitRequest,
+    responses(
+        (status = 201, description = "创建许可限值成功", body = DischargePermit),
+        (status = 400, description = "限值类型或平均周期无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Compliance"
+)]
+pub async fn create_permit(
+    State(state): State<Arc<AppState>>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<CreatePermitRequest>,
+) -> Result<(StatusCode, Json<DischargePermit>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let now = Utc::now();
+    let permit = DischargePermit {
+        id: 0,
+        name: payload.name,
+        permit_number: payload.permit_number,
+        device_id: payload.device_id,
+        metric_type: payload.metric_type,
+        limit_value: payload.limit_value,
+        limit_type: payload.limit_type,
+        averaging_period: payload.averaging_period,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    permit::validate(&permit)?;
+
+    let mut active_model = permit.into_active_model();
+    active_model.id = Default::default();
+    let permit = PermitEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_create(conn, &permit, operator).await?;
+
+    Ok((StatusCode::CREATED, Json(permit)))
+}
+
+/// 更新排污许可限值
+///
+/// 已记录的超标事件保留当时的限值，不随修改变化。
+#[utoipa::path(
+    put,
+    path = "/discharge-permits/{id}",
+    params(
+        ("id" = i32, Path, description = "许可限值 ID")
+    ),
+    request_body = UpdatePermitRequest,
+    responses(
+        (status = 200, description = "更新许可限值成功", body = DischargePermit),
+        (status = 400, description = "限值类型或平均周期无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "许可限值未找到")
+    ),
+    tag = "Compliance"
+)]
+pub async fn update_permit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdatePermitRequest>,
+) -> Result<Json<DischargePermit>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let existing = find(conn, id).await?;
+
+    let mut active_model = existing.clone().into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(permit_number) = payload.permit_number {
+        active_model.permit_number = Set(Some(permit_number));
+    }
+    if let Some(device_id) = payload.device_id {
+        active_model.device_id = Set(device_id);
+    }
+    if let Some(metric_type) = payload.metric_type {
+        active_model.metric_type = Set(metric_type);
+    }
+    if let Some(limit_value) = payload.limit_value {
+        active_model.limit_value = Set(limit_value);
+    }
+    if let Some(limit_type) = payload.limit_type {
+        active_model.limit_type = Set(limit_type);
+    }
+    if let Some(averaging_period) = payload.averaging_period {
+        active_model.averaging_period = Set(averaging_period);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    permit::validate(&proposed)?;
+
+    let updated = PermitEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_update(conn, &existing, &updated, operator).await?;
+
+    Ok(Json(updated))
+}
+
+/// 删除排污许可限值，已记录的超标事件保留
+#[utoipa::path(
+    delete,
+    path = "/discharge-permits/{id}",
+    params(
+        ("id" = i32, Path, description = "许可限值 ID")
+    ),
+    responses(
+        (status = 204, description = "删除许可限值成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "许可限值未找到")
+    ),
+    tag = "Compliance"
+)]
+pub async fn delete_permit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let permit = find(conn, id).await?;
+
+    PermitEntity::delete_by_id(permit.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &permit, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取超标事件
+#[utoipa::path(
+    get,
+    path = "/permit-exceedances",
+    params(ExceedanceQuery),
+    responses(
+        (status = 200, description = "获取超标事件成功", body = [PermitExceedance]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Compliance"
+)]
+pub async fn get_exceedances(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExceedanceQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<PermitExceedance>>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let exceedances = permit::exceedances(conn, query.permit_id, query.start, query.end).await?;
+    Ok(Json(exceedances))
+}
+
+/// 获取月度合规汇总
+#[utoipa::path(
+    get,
+    path = "/compliance/summary",
+    params(ComplianceQuery),
+    responses(
+        (status = 200, description = "获取合规汇总成功", body = ComplianceSummary),
+        (status = 400, description = "月份格式无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Compliance"
+)]
+pub async fn get_compliance_summary(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ComplianceQuery>,
+    tenant: Tenant,
+) -> Result<Json<ComplianceSummary>, AppError> {
+    tenant.require_platform()?;
+    let month = match query.month {
+        Some(month) => permit::parse_month(&month)?,
+        None => {
+            let today = Utc::now().date_naive();
+            NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap_or(today)
+        }
+    };
+    let summary = permit::monthly_summary(state.db.get_connection(), month).await?;
+    Ok(Json(summary))
+}
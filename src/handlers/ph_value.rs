@@ -1,12 +1,19 @@
+//! PH值接口，基于通用测量值接口（metric_type = "ph"）的兼容封装
+
 use crate::app_state::AppState;
-use crate::models::ph_value::{Entity as PhValueEntity, Model as PhValue, ActiveModel as PhValueActiveModel};
+use crate::handlers::measurement::ingest_response;
+use crate::models::ph_value::Model as PhValue;
+use crate::services::data_correction::CorrectionNote;
+use crate::services::measurement::{self as measurement_service, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::PH as METRIC;
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
-    response::Json,
+    response::{Json, Response},
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -47,27 +54,21 @@ pub struct Pagination {
 pub async fn get_ph_values(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<PhValue>>, AppError> {
     let conn = state.db.get_connection();
-    
+
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
-    
-    let ph_values = PhValueEntity::find()
-        .all(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    // 简化的分页实现
-    let start = ((page - 1) * per_page) as usize;
-    let end = (start + per_page as usize).min(ph_values.len());
-    let paginated_ph_values = if start < ph_values.len() {
-        ph_values[start..end].to_vec()
-    } else {
-        vec![]
+
+    let filter = MeasurementFilter {
+        metric_type: Some(METRIC.to_string()),
+        organization_id: tenant.organization_id(),
+        ..Default::default()
     };
+    let ph_values = measurement_service::list(conn, &filter, page, per_page).await?;
 
-    Ok(Json(paginated_ph_values))
+    Ok(Json(ph_values.into_iter().map(PhValue::from).collect()))
 }
 
 /// 获取指定PH值
@@ -86,16 +87,14 @@ pub async fn get_ph_values(
 pub async fn get_ph_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<PhValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let ph_value = PhValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    Ok(Json(ph_value))
+
+    let ph_value = measurement_service::get(conn, id, Some(METRIC)).await?;
+    tenant.check_device(conn, ph_value.device_id).await?;
+
+    Ok(Json(ph_value.into()))
 }
 
 /// 创建PH值
@@ -105,30 +104,35 @@ pub async fn get_ph_value(
     request_body = CreatePhValueRequest,
     responses(
         (status = 201, description = "创建PH值成功", body = PhValue),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
         (status = 400, description = "请求参数错误")
     ),
     tag = "PH Values"
 )]
 pub async fn create_ph_value(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
     Json(payload): Json<CreatePhValueRequest>,
-) -> Result<(StatusCode, Json<PhValue>), AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
-    
-    let new_ph_value = PhValueActiveModel {
-        timestamp: sea_orm::Set(payload.timestamp),
-        value: sea_orm::Set(payload.value),
-        device_id: sea_orm::Set(payload.device_id),
-        unit: sea_orm::Set(payload.unit),
-        ..Default::default()
-    };
-
-    let ph_value = PhValueEntity::insert(new_ph_value)
-        .exec_with_returning(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok((StatusCode::CREATED, Json(ph_value)))
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: METRIC.to_string(),
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: Some(payload.unit),
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<PhValue>(ingested))
 }
 
 /// 更新PH值
@@ -148,43 +152,34 @@ pub async fn create_ph_value(
 pub async fn update_ph_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdatePhValueRequest>,
 ) -> Result<Json<PhValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let existing_ph_value = PhValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-        
-    let mut ph_value_active_model = existing_ph_value.into_active_model();
-    
-    if let Some(timestamp) = payload.timestamp {
-        ph_value_active_model.timestamp = sea_orm::Set(timestamp);
-    }
-    
-    if let Some(value) = payload.value {
-        ph_value_active_model.value = sea_orm::Set(value);
-    }
-    
+    tenant.check_measurement(conn, id).await?;
     if let Some(device_id) = payload.device_id {
-        ph_value_active_model.device_id = sea_orm::Set(device_id);
+        tenant.check_device(conn, device_id).await?;
     }
-    
-    if let Some(unit) = payload.unit {
-        ph_value_active_model.unit = sea_orm::Set(unit);
-    }
-    
-    // 更新 updated_at 字段
-    ph_value_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
-    
-    let updated_ph_value = PhValueEntity::update(ph_value_active_model)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok(Json(updated_ph_value))
+
+    let updated_ph_value = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        Some(METRIC),
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+            ..Default::default()
+        },
+        CorrectionNote { corrected_by: operator, ..Default::default() },
+    )
+    .await?;
+
+    Ok(Json(updated_ph_value.into()))
 }
 
 /// 删除PH值
@@ -203,19 +198,14 @@ pub async fn update_ph_value(
 pub async fn delete_ph_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let conn = state.db.get_connection();
-    
-    let ph_value = PhValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    let _ = PhValueEntity::delete_by_id(ph_value.id)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
+    tenant.check_measurement(conn, id).await?;
+
+    let note = CorrectionNote { corrected_by: operator, ..Default::default() };
+    measurement_service::delete(conn, &state.cache, id, Some(METRIC), note).await?;
 
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
@@ -0,0 +1,219 @@
+//! 压力值接口（泵出口、管网），基于通用测量值接口（metric_type = "pressure"）
+
+use crate::app_state::AppState;
+use crate::handlers::measurement::ingest_response;
+use crate::models::measurement::Model as Measurement;
+use crate::services::data_correction::CorrectionNote;
+use crate::services::measurement::{self as measurement_service, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::PRESSURE as METRIC;
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State, Query},
+    http::StatusCode,
+    response::{Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatePressureValueRequest {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    /// MPa（默认）/ kPa / bar / psi，入库前换算为 MPa
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePressureValueRequest {
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub value: Option<f64>,
+    pub device_id: Option<Option<i32>>,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PressureValueQuery {
+    pub device_id: Option<i32>,
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 获取压力值列表
+#[utoipa::path(
+    get,
+    path = "/pressure-values",
+    params(PressureValueQuery),
+    responses(
+        (status = 200, description = "获取压力值列表成功", body = [Measurement])
+    ),
+    tag = "Pressure Values"
+)]
+pub async fn get_pressure_values(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PressureValueQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Measurement>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+
+    let filter = MeasurementFilter {
+        metric_type: Some(METRIC.to_string()),
+        device_id: query.device_id,
+        start: query.start,
+        end: query.end,
+        organization_id: tenant.organization_id(),
+        ..Default::default()
+    };
+    let pressure_values = measurement_service::list(conn, &filter, page, per_page).await?;
+
+    Ok(Json(pressure_values))
+}
+
+/// 获取指定压力值
+#[utoipa::path(
+    get,
+    path = "/pressure-values/{id}",
+    params(
+        ("id" = i32, Path, description = "压力值ID")
+    ),
+    responses(
+        (status = 200, description = "获取压力值成功", body = Measurement),
+        (status = 404, description = "压力值未找到")
+    ),
+    tag = "Pressure Values"
+)]
+pub async fn get_pressure_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+
+    let pressure_value = measurement_service::get(conn, id, Some(METRIC)).await?;
+    tenant.check_device(conn, pressure_value.device_id).await?;
+
+    Ok(Json(pressure_value))
+}
+
+/// 创建压力值
+#[utoipa::path(
+    post,
+    path = "/pressure-values",
+    request_body = CreatePressureValueRequest,
+    responses(
+        (status = 201, description = "创建压力值成功", body = Measurement),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
+        (status = 400, description = "请求参数错误")
+    ),
+    tag = "Pressure Values"
+)]
+pub async fn create_pressure_value(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreatePressureValueRequest>,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: METRIC.to_string(),
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: payload.unit,
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<Measurement>(ingested))
+}
+
+/// 更新压力值
+#[utoipa::path(
+    put,
+    path = "/pressure-values/{id}",
+    params(
+        ("id" = i32, Path, description = "压力值ID")
+    ),
+    request_body = UpdatePressureValueRequest,
+    responses(
+        (status = 200, description = "更新压力值成功", body = Measurement),
+        (status = 404, description = "压力值未找到")
+    ),
+    tag = "Pressure Values"
+)]
+pub async fn update_pressure_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdatePressureValueRequest>,
+) -> Result<Json<Measurement>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+    if let Some(device_id) = payload.device_id {
+        tenant.check_device(conn, device_id).await?;
+    }
+
+    let updated_pressure_value = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        Some(METRIC),
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+
+            ..Default::default()
+        },
+        CorrectionNote { corrected_by: operator, ..Default::default() },
+    )
+    .await?;
+
+    Ok(Json(updated_pressure_value))
+}
+
+/// 删除压力值
+#[utoipa::path(
+    delete,
+    path = "/pressure-values/{id}",
+    params(
+        ("id" = i32, Path, description = "压力值ID")
+    ),
+    responses(
+        (status = 204, description = "删除压力值成功"),
+        (status = 404, description = "压力值未找到")
+    ),
+    tag = "Pressure Values"
+)]
+pub async fn delete_pressure_value(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_measurement(conn, id).await?;
+
+    let note = CorrectionNote { corrected_by: operator, ..Default::default() };
+    measurement_service::delete(conn, &state.cache, id, Some(METRIC), note).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
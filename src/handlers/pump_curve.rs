@@ -0,0 +1,198 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::pump_curve::{Entity as PumpCurveEntity, Model as PumpCurve};
+use crate::services::config_revision;
+use crate::services::pump::{self, PumpEfficiency};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PumpCurveRequest {
+    /// 效率曲线 [[流量 m³/h, 效率 %], ...]
+    pub efficiency_curve: serde_json::Value,
+    /// 扬程曲线 [[流量 m³/h, 扬程 m], ...]
+    pub head_curve: Option<serde_json::Value>,
+    /// 效率低于曲线值该百分比时报警
+    #[serde(default = "default_degradation_pct")]
+    pub degradation_pct: f64,
+}
+
+fn default_degradation_pct() -> f64 {
+    10.0
+}
+
+/// 获取水泵曲线
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/pump-curve",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "获取水泵曲线成功", body = PumpCurve),
+        (status = 404, description = "未配置水泵曲线")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_pump_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<PumpCurve>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let curve = pump::find_curve(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    Ok(Json(curve))
+}
+
+/// 设置水泵曲线
+#[utoipa::path(
+    put,
+    path = "/devices/{id}/pump-curve",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = PumpCurveRequest,
+    responses(
+        (status = 200, description = "设置水泵曲线成功", body = PumpCurve),
+        (status = 400, description = "曲线无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn put_pump_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<PumpCurveRequest>,
+) -> Result<Json<PumpCurve>, AppError> {
+    let conn = state.db.get_connection();
+
+    DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let existing = pump::find_curve(conn, id).await?;
+    let now = Utc::now();
+    let curve = PumpCurve {
+        id: existing.as_ref().map(|c| c.id).unwrap_or_default(),
+        device_id: id,
+        efficiency_curve: payload.efficiency_curve.to_string(),
+        head_curve: payload.head_curve.map(|c| c.to_string()),
+        degradation_pct: payload.degradation_pct,
+        created_at: existing.as_ref().map(|c| c.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    pump::validate(&curve)?;
+
+    let curve = match existing {
+        Some(existing) => {
+            let updated = PumpCurveEntity::update(curve.into_active_model().reset_all())
+                .exec(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_update(conn, &existing, &updated, operator).await?;
+            updated
+        }
+        None => {
+            let mut active_model = curve.into_active_model();
+            active_model.id = Default::default();
+            let created = PumpCurveEntity::insert(active_model)
+                .exec_with_returning(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_create(conn, &created, operator).await?;
+            created
+        }
+    };
+
+    Ok(Json(curve))
+}
+
+/// 删除水泵曲线
+#[utoipa::path(
+    delete,
+    path = "/devices/{id}/pump-curve",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 204, description = "删除水泵曲线成功"),
+        (status = 404, description = "未配置水泵曲线")
+    ),
+    tag = "Devices"
+)]
+pub async fn delete_pump_curve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let curve = pump::find_curve(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    PumpCurveEntity::delete_by_id(curve.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &curve, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 按最新的流量、压力、功率评估水泵当前效率
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/pump-efficiency",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "评估成功", body = PumpEfficiency),
+        (status = 204, description = "数据不全或已过期，无法评估"),
+        (status = 404, description = "未配置水泵曲线")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_pump_efficiency(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Response, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let curve = pump::find_curve(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let result = pump::current(conn, &state.cache, &state.settings.pump_monitor, &curve).await?;
+    Ok(match result {
+        Some(result) => Json(result).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
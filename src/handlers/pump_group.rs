@@ -0,0 +1,335 @@
+use crate::app_state::AppState;
+use crate::models::pump_group::{
+    Column as PumpGroupColumn, Entity as PumpGroupEntity, Model as PumpGroup,
+};
+use crate::services::pump_group::{self, DutyStatus};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, Set,
+    TryIntoModel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatePumpGroupRequest {
+    pub name: String,
+    /// 组内泵的设备 ID，按优先顺序，初始分配取前 `duty_count` 台
+    pub members: Vec<i32>,
+    /// 同时运行的台数，默认 1
+    #[serde(default = "default_duty_count")]
+    pub duty_count: i32,
+    /// 运行小时数差超过该值时轮换，0 不轮换，默认 24
+    #[serde(default = "default_rotation_hours")]
+    pub rotation_hours: f64,
+    /// 运行分配后等待运行反馈的秒数，默认 30
+    #[serde(default = "default_feedback_timeout_secs")]
+    pub feedback_timeout_secs: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_duty_count() -> i32 {
+    1
+}
+
+fn default_rotation_hours() -> f64 {
+    24.0
+}
+
+fn default_feedback_timeout_secs() -> i32 {
+    30
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 未给出的项保持不变；修改组成员时当前分配中已不在组内的泵由下一周期重新分配
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePumpGroupRequest {
+    pub name: Option<String>,
+    pub members: Option<Vec<i32>>,
+    pub duty_count: Option<i32>,
+    pub rotation_hours: Option<f64>,
+    pub feedback_timeout_secs: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResetPumpGroupResponse {
+    /// 清除了没有运行反馈故障的泵
+    pub cleared: Vec<i32>,
+}
+
+async fn find_group(conn: &DatabaseConnection, id: i32) -> Result<PumpGroup, AppError> {
+    PumpGroupEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+fn to_json(ids: &[i32]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 获取泵组列表
+#[utoipa::path(
+    get,
+    path = "/pump-groups",
+    responses(
+        (status = 200, description = "获取泵组列表成功", body = [PumpGroup]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn get_pump_groups(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<PumpGroup>>, AppError> {
+    // 泵组是全厂共用设备，组员可能属于不同组织
+    tenant.require_platform()?;
+    let groups = PumpGroupEntity::find()
+        .order_by_asc(PumpGroupColumn::Id)
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(groups))
+}
+
+/// 获取指定泵组
+#[utoipa::path(
+    get,
+    path = "/pump-groups/{id}",
+    params(
+        ("id" = i32, Path, description = "泵组 ID")
+    ),
+    responses(
+        (status = 200, description = "获取泵组成功", body = PumpGroup),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "泵组未找到")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn get_pump_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<PumpGroup>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(find_group(state.db.get_connection(), id).await?))
+}
+
+/// 创建泵组
+#[utoipa::path(
+    post,
+    path = "/pump-groups",
+    request_body = CreatePumpGroupRequest,
+    responses(
+        (status = 201, description = "创建泵组成功", body = PumpGroup),
+        (status = 400, description = "泵组参数无效"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn create_pump_group(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreatePumpGroupRequest>,
+) -> Result<(StatusCode, Json<PumpGroup>), AppError> {
+    tenant.require_platform()?;
+    let now = Utc::now();
+    let duty: Vec<i32> =
+        payload.members.iter().copied().take(payload.duty_count.max(0) as usize).collect();
+    let group = PumpGroup {
+        id: 0,
+        name: payload.name,
+        members: to_json(&payload.members),
+        duty_count: payload.duty_count,
+        rotation_hours: payload.rotation_hours,
+        feedback_timeout_secs: payload.feedback_timeout_secs,
+        duty: to_json(&duty),
+        last_rotated_at: None,
+        enabled: payload.enabled,
+        created_at: now,
+        updated_at: now,
+    };
+    pump_group::validate(&group)?;
+
+    let mut active_model = group.into_active_model();
+    active_model.id = Default::default();
+    let group = PumpGroupEntity::insert(active_model)
+        .exec_with_returning(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(group)))
+}
+
+/// 更新泵组，例如由液位控制调整运行台数
+#[utoipa::path(
+    put,
+    path = "/pump-groups/{id}",
+    params(
+        ("id" = i32, Path, description = "泵组 ID")
+    ),
+    request_body = UpdatePumpGroupRequest,
+    responses(
+        (status = 200, description = "更新泵组成功", body = PumpGroup),
+        (status = 400, description = "泵组参数无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "泵组未找到")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn update_pump_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<UpdatePumpGroupRequest>,
+) -> Result<Json<PumpGroup>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let mut active_model = find_group(conn, id).await?.into_active_model();
+    if let Some(name) = payload.name {
+        active_model.name = Set(name);
+    }
+    if let Some(members) = payload.members {
+        active_model.members = Set(to_json(&members));
+    }
+    if let Some(duty_count) = payload.duty_count {
+        active_model.duty_count = Set(duty_count);
+    }
+    if let Some(rotation_hours) = payload.rotation_hours {
+        active_model.rotation_hours = Set(rotation_hours);
+    }
+    if let Some(feedback_timeout_secs) = payload.feedback_timeout_secs {
+        active_model.feedback_timeout_secs = Set(feedback_timeout_secs);
+    }
+    if let Some(enabled) = payload.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let proposed = active_model.clone().try_into_model().map_err(|_| AppError::InternalError)?;
+    pump_group::validate(&proposed)?;
+
+    let updated = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    info!(
+        "Pump group {} updated by {:?}: members {} duty count {}",
+        updated.name, operator, updated.members, updated.duty_count
+    );
+    Ok(Json(updated))
+}
+
+/// 删除泵组，各泵保持当前启停状态
+#[utoipa::path(
+    delete,
+    path = "/pump-groups/{id}",
+    params(
+        ("id" = i32, Path, description = "泵组 ID")
+    ),
+    responses(
+        (status = 204, description = "删除泵组成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "泵组未找到")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn delete_pump_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+    let group = find_group(conn, id).await?;
+
+    PumpGroupEntity::delete_by_id(group.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取全部泵组当前的主泵、辅泵、备用泵和故障泵
+#[utoipa::path(
+    get,
+    path = "/pump-groups/duty",
+    responses(
+        (status = 200, description = "获取运行分配成功", body = [DutyStatus]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn get_duty(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<DutyStatus>>, AppError> {
+    tenant.require_platform()?;
+    Ok(Json(state.pump_groups.status()))
+}
+
+/// 获取指定泵组当前的运行分配
+#[utoipa::path(
+    get,
+    path = "/pump-groups/{id}/duty",
+    params(
+        ("id" = i32, Path, description = "泵组 ID")
+    ),
+    responses(
+        (status = 200, description = "获取运行分配成功", body = DutyStatus),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "泵组未找到或尚未运行")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn get_group_duty(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<DutyStatus>, AppError> {
+    tenant.require_platform()?;
+    state.pump_groups.get(id).map(Json).ok_or(AppError::NotFound)
+}
+
+/// 复位没有运行反馈的故障，排除故障后由操作员确认
+#[utoipa::path(
+    post,
+    path = "/pump-groups/{id}/reset",
+    params(
+        ("id" = i32, Path, description = "泵组 ID")
+    ),
+    responses(
+        (status = 200, description = "复位成功", body = ResetPumpGroupResponse),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "泵组未找到")
+    ),
+    tag = "Pump Groups"
+)]
+pub async fn reset_pump_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<Json<ResetPumpGroupResponse>, AppError> {
+    tenant.require_platform()?;
+    let group = find_group(state.db.get_connection(), id).await?;
+    let cleared = state.pump_groups.reset(group.id);
+    info!("Pump group {} reset by {:?}, cleared {:?}", group.name, operator, cleared);
+    Ok(Json(ResetPumpGroupResponse { cleared }))
+}
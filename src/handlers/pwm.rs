@@ -0,0 +1,90 @@
+use crate::app_state::AppState;
+use crate::middleware::api_key::ControlKey;
+use crate::services::pwm::PwmStatus;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetPwmRequest {
+    /// 占空比 (%)
+    pub duty_cycle: f64,
+    /// 频率 (Hz)，缺省保持当前频率
+    pub frequency_hz: Option<f64>,
+    /// 是否按通道配置的速率逐步调整，默认 true
+    #[serde(default = "default_ramp")]
+    pub ramp: bool,
+}
+
+fn default_ramp() -> bool {
+    true
+}
+
+/// 获取设备 PWM 状态
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/pwm",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "获取 PWM 状态成功", body = PwmStatus),
+        (status = 404, description = "设备未配置 PWM 通道")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_pwm(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<PwmStatus>, AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+    Ok(Json(state.pwm.status(id)?))
+}
+
+/// 设置设备 PWM 占空比和频率，需使用具备 control 权限的 Key 调用
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/pwm",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = SetPwmRequest,
+    responses(
+        (status = 200, description = "已设置，逐步调整时返回调整开始时的状态", body = PwmStatus),
+        (status = 400, description = "超出安全范围"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "Key 没有 control 权限"),
+        (status = 404, description = "设备未配置 PWM 通道"),
+        (status = 503, description = "PWM 通道不可用")
+    ),
+    tag = "Devices"
+)]
+pub async fn set_pwm(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ControlKey(caller): ControlKey,
+    tenant: Tenant,
+    Json(payload): Json<SetPwmRequest>,
+) -> Result<Json<PwmStatus>, AppError> {
+    tenant.check_device(state.db.get_connection(), Some(id)).await?;
+
+    let status = state
+        .pwm
+        .set(id, payload.duty_cycle, payload.frequency_hz, payload.ramp)
+        .await?;
+    info!(
+        "PWM for device {} set to {:.1}% by {}",
+        id,
+        payload.duty_cycle,
+        caller.operator_label()
+    );
+    Ok(Json(status))
+}
@@ -0,0 +1,144 @@
+use crate::app_state::AppState;
+use crate::models::remote_session::{Entity as RemoteSessionEntity, Model as RemoteSession};
+use crate::services::remote_access::OpenSession;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateRemoteSessionRequest {
+    pub vendor: String,
+    pub reason: String,
+    /// 目标设备地址，例如 10.20.1.15:502
+    pub target: String,
+    /// 厂商接入的来源 IP，其他地址的连接一律断开
+    pub client_ip: String,
+    pub duration_minutes: u32,
+}
+
+/// 获取远程访问会话列表
+#[utoipa::path(
+    get,
+    path = "/remote-sessions",
+    responses(
+        (status = 200, description = "获取远程访问会话列表成功", body = [RemoteSession]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Remote Access"
+)]
+pub async fn get_remote_sessions(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<RemoteSession>>, AppError> {
+    // 远程维护会话针对整个网关
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let sessions = RemoteSessionEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(sessions))
+}
+
+/// 获取指定远程访问会话
+#[utoipa::path(
+    get,
+    path = "/remote-sessions/{id}",
+    params(
+        ("id" = i32, Path, description = "会话ID")
+    ),
+    responses(
+        (status = 200, description = "获取远程访问会话成功", body = RemoteSession),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "会话未找到")
+    ),
+    tag = "Remote Access"
+)]
+pub async fn get_remote_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<RemoteSession>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let session = RemoteSessionEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    Ok(Json(session))
+}
+
+/// 开启远程访问会话
+#[utoipa::path(
+    post,
+    path = "/remote-sessions",
+    request_body = CreateRemoteSessionRequest,
+    responses(
+        (status = 201, description = "开启远程访问会话成功", body = RemoteSession),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "目标地址不在允许范围内")
+    ),
+    tag = "Remote Access"
+)]
+pub async fn create_remote_session(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateRemoteSessionRequest>,
+) -> Result<(StatusCode, Json<RemoteSession>), AppError> {
+    tenant.require_platform()?;
+    let session = state
+        .remote_access
+        .open(
+            &state.db,
+            OpenSession {
+                vendor: payload.vendor,
+                reason: payload.reason,
+                target: payload.target,
+                client_ip: payload.client_ip,
+                duration_minutes: payload.duration_minutes,
+            },
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+/// 提前关闭远程访问会话
+#[utoipa::path(
+    delete,
+    path = "/remote-sessions/{id}",
+    params(
+        ("id" = i32, Path, description = "会话ID")
+    ),
+    responses(
+        (status = 204, description = "关闭会话成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "会话不存在或已结束")
+    ),
+    tag = "Remote Access"
+)]
+pub async fn close_remote_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    if state.remote_access.close(id).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound)
+    }
+}
@@ -0,0 +1,148 @@
+use crate::app_state::AppState;
+use crate::models::report::{Entity as ReportEntity, Model as Report};
+use crate::services::report;
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use crate::utils::timezone;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{Duration, NaiveDate, Utc};
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReportListQuery {
+    /// daily / weekly
+    pub kind: Option<String>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 报表记录
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportEntry {
+    #[serde(flatten)]
+    pub report: Report,
+    pub download_url: String,
+}
+
+impl From<Report> for ReportEntry {
+    fn from(report: Report) -> Self {
+        Self {
+            download_url: format!("/reports/{}/download", report.id),
+            report,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GenerateReportRequest {
+    /// daily / weekly
+    pub kind: String,
+    /// 周期内任意一天，默认为昨天；周报取该日所在的周一至周日
+    pub date: Option<NaiveDate>,
+}
+
+/// 获取报表列表
+#[utoipa::path(
+    get,
+    path = "/reports",
+    params(ReportListQuery),
+    responses(
+        (status = 200, description = "获取报表列表成功", body = [ReportEntry]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Reports"
+)]
+pub async fn get_reports(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ReportListQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<ReportEntry>>, AppError> {
+    // 报表按全厂汇总，包含各组织的数据
+    tenant.require_platform()?;
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+    let reports =
+        report::list(state.db.get_connection(), query.kind.as_deref(), page, per_page).await?;
+
+    Ok(Json(reports.into_iter().map(ReportEntry::from).collect()))
+}
+
+/// 下载报表文件
+#[utoipa::path(
+    get,
+    path = "/reports/{id}/download",
+    params(
+        ("id" = i32, Path, description = "报表 ID")
+    ),
+    responses(
+        (status = 200, description = "下载报表成功"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "报表未找到"),
+        (status = 503, description = "报表文件读取失败")
+    ),
+    tag = "Reports"
+)]
+pub async fn download_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Response, AppError> {
+    tenant.require_platform()?;
+    let report = ReportEntity::find_by_id(id)
+        .one(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)?;
+    let body = report::load(&state.settings.report, &report).await?;
+
+    let name = report::file_name(&report.kind, report.period_start, &report.format);
+    Ok((
+        [
+            (header::CONTENT_TYPE, report::content_type(&report.format).to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// 立即生成报表，已有同周期的报表时覆盖
+#[utoipa::path(
+    post,
+    path = "/reports",
+    request_body = GenerateReportRequest,
+    responses(
+        (status = 201, description = "生成报表成功", body = [ReportEntry]),
+        (status = 400, description = "报表类型无效"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 503, description = "报表文件保存失败")
+    ),
+    tag = "Reports"
+)]
+pub async fn generate_report(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<GenerateReportRequest>,
+) -> Result<(StatusCode, Json<Vec<ReportEntry>>), AppError> {
+    tenant.require_platform()?;
+    let tz = timezone::default_zone(&state.settings.timezone);
+    let date =
+        payload.date.unwrap_or_else(|| timezone::local_date(tz, Utc::now()) - Duration::days(1));
+    let reports = report::generate(
+        state.db.get_connection(),
+        &state.settings.report,
+        tz,
+        &payload.kind,
+        date,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(reports.into_iter().map(ReportEntry::from).collect())))
+}
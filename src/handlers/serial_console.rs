@@ -0,0 +1,101 @@
+use crate::app_state::AppState;
+use crate::models::serial_session::{Entity as SerialSessionEntity, Model as SerialSession};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{ws::WebSocketUpgrade, ConnectInfo, Path, Query, State},
+    response::{Json, Response},
+};
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SerialConsoleQuery {
+    /// 访问原因
+    pub reason: String,
+}
+
+/// 获取可访问的串口
+#[utoipa::path(
+    get,
+    path = "/serial-console/ports",
+    responses(
+        (status = 200, description = "获取串口列表成功", body = [String]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Serial Console"
+)]
+pub async fn get_serial_ports(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<String>>, AppError> {
+    // 网关本机的串口不属于任何组织
+    tenant.require_platform()?;
+    Ok(Json(state.serial_console.port_names()))
+}
+
+/// 连接串口控制台（WebSocket），需通过 `X-Operator` 提供操作人
+#[utoipa::path(
+    get,
+    path = "/serial-console/{port}",
+    params(
+        ("port" = String, Path, description = "串口名称"),
+        SerialConsoleQuery
+    ),
+    responses(
+        (status = 101, description = "切换为 WebSocket，二进制帧双向转发串口数据"),
+        (status = 400, description = "未启用、未填写原因或串口正被使用"),
+        (status = 403, description = "未提供操作人或不是平台管理 Key"),
+        (status = 404, description = "串口不存在"),
+        (status = 503, description = "串口无法打开")
+    ),
+    tag = "Serial Console"
+)]
+pub async fn connect_serial_console(
+    State(state): State<Arc<AppState>>,
+    Path(port): Path<String>,
+    Query(query): Query<SerialConsoleQuery>,
+    Operator(operator): Operator,
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+    tenant: Tenant,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    tenant.require_platform()?;
+    let operator = operator.ok_or(AppError::Forbidden)?;
+
+    let session = state
+        .serial_console
+        .open(&state.db, &port, operator, query.reason, remote_addr)
+        .await?;
+
+    Ok(ws.on_upgrade(move |socket| session.run(socket)))
+}
+
+/// 获取串口控制台会话记录
+#[utoipa::path(
+    get,
+    path = "/serial-sessions",
+    responses(
+        (status = 200, description = "获取串口会话记录成功", body = [SerialSession]),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Serial Console"
+)]
+pub async fn get_serial_sessions(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Result<Json<Vec<SerialSession>>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let sessions = SerialSessionEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(sessions))
+}
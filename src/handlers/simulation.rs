@@ -0,0 +1,99 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::services::simulation::{self, InjectionReport, ScenarioStep};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InjectRequest {
+    pub metric_type: String,
+    /// 第一个样本的时间戳，默认当前时间
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    /// 样本间隔
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u32,
+    /// 按顺序执行的脚本段
+    pub steps: Vec<ScenarioStep>,
+    /// 是否把触发的报警规则写入报警记录
+    #[serde(default)]
+    pub raise_alarms: bool,
+}
+
+fn default_interval_secs() -> u32 {
+    60
+}
+
+/// 向设备注入模拟测量序列（仅调试构建）
+///
+/// 按脚本生成斜坡、阶跃和传感器故障序列，经正常的上报流程写入，返回每个样本的写入结果、
+/// 触发的报警规则和生效的自动化规则。供端到端测试使用，不要在生产网关上使用。
+#[utoipa::path(
+    post,
+    path = "/simulation/devices/{id}/inject",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = InjectRequest,
+    responses(
+        (status = 200, description = "注入完成", body = InjectionReport),
+        (status = 400, description = "指标类型或脚本无效"),
+        (status = 404, description = "设备未找到，或发布构建不提供模拟注入")
+    ),
+    tag = "Simulation"
+)]
+pub async fn inject(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<InjectRequest>,
+) -> Result<Json<InjectionReport>, AppError> {
+    if !simulation::AVAILABLE {
+        return Err(AppError::NotFound);
+    }
+    let conn = state.db.get_connection();
+
+    let device = DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)?;
+    tenant.check(device.organization_id)?;
+
+    let metric = simulation::lookup_metric(&payload.metric_type)?;
+    let start = payload.start.unwrap_or_else(Utc::now);
+    let samples = simulation::plan(metric, &payload.steps, start, payload.interval_secs)?;
+    warn!(
+        "Injecting {} simulated {} samples into device {} by {:?}",
+        samples.len(),
+        metric.key,
+        id,
+        operator
+    );
+
+    let report = simulation::inject(
+        conn,
+        &state.cache,
+        &state.read_only,
+        &state.settings.timezone,
+        &device,
+        metric.key,
+        samples,
+        payload.raise_alarms,
+    )
+    .await?;
+
+    Ok(Json(report))
+}
@@ -0,0 +1,324 @@
+use crate::app_state::AppState;
+use crate::models::device::Model as Device;
+use crate::models::measurement::Model as Measurement;
+use crate::models::site::{
+    ActiveModel as SiteActiveModel, Column as SiteColumn, Entity as SiteEntity, Model as Site,
+};
+use crate::services::{measurement as measurement_service, site as site_service};
+use crate::utils::error::AppError;
+use crate::utils::serde_ext::double_option;
+use crate::utils::tenant::Tenant;
+use crate::utils::timezone;
+use axum::{
+    extract::{Path, State, Query},
+    http::StatusCode,
+    response::Json,
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa::IntoParams;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateSiteRequest {
+    pub name: String,
+    pub code: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub description: String,
+    /// IANA 时区名，例如 `Asia/Shanghai`；为空时用默认时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSiteRequest {
+    pub name: Option<String>,
+    pub code: Option<String>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    /// 传 null 表示改用默认时区
+    #[serde(default, deserialize_with = "double_option")]
+    pub timezone: Option<Option<String>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct Pagination {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// 校验时区名，空字符串按未设置处理
+fn normalize_timezone(name: Option<String>) -> Result<Option<String>, AppError> {
+    match name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => Ok(Some(timezone::parse(name)?.name().to_string())),
+        None => Ok(None),
+    }
+}
+
+/// 厂站编号唯一，重复时返回 400 而不是等数据库报唯一约束错误
+async fn ensure_unique_code(
+    conn: &DatabaseConnection,
+    code: &str,
+    id: Option<i32>,
+) -> Result<(), AppError> {
+    let mut select = SiteEntity::find().filter(SiteColumn::Code.eq(code));
+    if let Some(id) = id {
+        select = select.filter(SiteColumn::Id.ne(id));
+    }
+    let existing = select.one(conn).await.map_err(|_| AppError::InternalError)?;
+    if existing.is_some() {
+        return Err(AppError::InvalidInput(format!("厂站编号已存在: {}", code).into()));
+    }
+    Ok(())
+}
+
+/// 获取厂站列表
+#[utoipa::path(
+    get,
+    path = "/sites",
+    params(Pagination),
+    responses(
+        (status = 200, description = "获取厂站列表成功", body = [Site])
+    ),
+    tag = "Sites"
+)]
+pub async fn get_sites(
+    State(state): State<Arc<AppState>>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<Site>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let page = pagination.page.unwrap_or(1).max(1);
+    let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
+
+    let sites = SiteEntity::find()
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(sites))
+}
+
+/// 获取指定厂站
+#[utoipa::path(
+    get,
+    path = "/sites/{id}",
+    params(
+        ("id" = i32, Path, description = "厂站ID")
+    ),
+    responses(
+        (status = 200, description = "获取厂站成功", body = Site),
+        (status = 404, description = "厂站未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_site(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Site>, AppError> {
+    let conn = state.db.get_connection();
+
+    let site = site_service::get_site(conn, id).await?;
+
+    Ok(Json(site))
+}
+
+/// 创建厂站
+#[utoipa::path(
+    post,
+    path = "/sites",
+    request_body = CreateSiteRequest,
+    responses(
+        (status = 201, description = "创建厂站成功", body = Site),
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不是平台管理 Key")
+    ),
+    tag = "Sites"
+)]
+pub async fn create_site(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(payload): Json<CreateSiteRequest>,
+) -> Result<(StatusCode, Json<Site>), AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    if payload.name.trim().is_empty() || payload.code.trim().is_empty() {
+        return Err(AppError::InvalidInput("厂站名称和编号不能为空".into()));
+    }
+    ensure_unique_code(conn, &payload.code, None).await?;
+    let site_timezone = normalize_timezone(payload.timezone)?;
+
+    let now = chrono::Utc::now();
+    let new_site = SiteActiveModel {
+        name: sea_orm::Set(payload.name),
+        code: sea_orm::Set(payload.code),
+        address: sea_orm::Set(payload.address),
+        description: sea_orm::Set(payload.description),
+        timezone: sea_orm::Set(site_timezone),
+        created_at: sea_orm::Set(now),
+        updated_at: sea_orm::Set(now),
+        ..Default::default()
+    };
+
+    let site = SiteEntity::insert(new_site)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok((StatusCode::CREATED, Json(site)))
+}
+
+/// 更新厂站
+#[utoipa::path(
+    put,
+    path = "/sites/{id}",
+    params(
+        ("id" = i32, Path, description = "厂站ID")
+    ),
+    request_body = UpdateSiteRequest,
+    responses(
+        (status = 200, description = "更新厂站成功", body = Site),
+        (status = 400, description = "时区无效或编号已存在"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "厂站未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn update_site(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<UpdateSiteRequest>,
+) -> Result<Json<Site>, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let existing_site = site_service::get_site(conn, id).await?;
+    let mut site_active_model = existing_site.into_active_model();
+
+    if let Some(name) = payload.name {
+        site_active_model.name = sea_orm::Set(name);
+    }
+    if let Some(code) = payload.code {
+        ensure_unique_code(conn, &code, Some(id)).await?;
+        site_active_model.code = sea_orm::Set(code);
+    }
+    if let Some(address) = payload.address {
+        site_active_model.address = sea_orm::Set(address);
+    }
+    if let Some(description) = payload.description {
+        site_active_model.description = sea_orm::Set(description);
+    }
+    if let Some(site_timezone) = payload.timezone {
+        site_active_model.timezone = sea_orm::Set(normalize_timezone(site_timezone)?);
+    }
+    site_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
+
+    let updated_site = SiteEntity::update(site_active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(Json(updated_site))
+}
+
+/// 删除厂站，厂站下须没有区域和设备
+#[utoipa::path(
+    delete,
+    path = "/sites/{id}",
+    params(
+        ("id" = i32, Path, description = "厂站ID")
+    ),
+    responses(
+        (status = 204, description = "删除厂站成功"),
+        (status = 400, description = "厂站下仍有区域或设备"),
+        (status = 403, description = "不是平台管理 Key"),
+        (status = 404, description = "厂站未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn delete_site(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    let conn = state.db.get_connection();
+
+    let site = site_service::get_site(conn, id).await?;
+    site_service::ensure_site_empty(conn, site.id).await?;
+
+    SiteEntity::delete_by_id(site.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取厂站下的全部设备
+#[utoipa::path(
+    get,
+    path = "/sites/{id}/devices",
+    params(
+        ("id" = i32, Path, description = "厂站ID")
+    ),
+    responses(
+        (status = 200, description = "获取设备成功", body = [Device]),
+        (status = 404, description = "厂站未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_site_devices(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Device>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let site = site_service::get_site(conn, id).await?;
+    let mut devices = site_service::devices_in_site(conn, site.id).await?;
+    devices.retain(|d| tenant.owns(d.organization_id));
+
+    Ok(Json(devices))
+}
+
+/// 获取厂站下各设备每个指标的最新测量值
+#[utoipa::path(
+    get,
+    path = "/sites/{id}/latest",
+    params(
+        ("id" = i32, Path, description = "厂站ID")
+    ),
+    responses(
+        (status = 200, description = "获取最新测量值成功", body = [Measurement]),
+        (status = 404, description = "厂站未找到")
+    ),
+    tag = "Sites"
+)]
+pub async fn get_site_latest(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<Vec<Measurement>>, AppError> {
+    let conn = state.db.get_connection();
+
+    let site = site_service::get_site(conn, id).await?;
+    let device_ids: Vec<i32> = site_service::devices_in_site(conn, site.id)
+        .await?
+        .into_iter()
+        .filter(|d| tenant.owns(d.organization_id))
+        .map(|d| d.id)
+        .collect();
+    let measurements = measurement_service::latest_for_devices(conn, &device_ids).await?;
+
+    Ok(Json(measurements))
+}
@@ -0,0 +1,255 @@
+use crate::app_state::AppState;
+use crate::config::security::NetworkPolicyConfig;
+use crate::database::query_metrics::QueryMetricsSnapshot;
+use crate::middleware::api_key::AdminKey;
+use crate::services::fault_injection::{self, FaultInjectionStatus};
+use crate::services::network::InterfaceStatus;
+use crate::services::read_only::ReadOnlyStatus;
+use crate::services::system::{SystemProbe, SystemStats};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// 各队列积压情况
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QueueDepths {
+    /// MQTT 离线队列中尚未确认的消息数，未启用 MQTT 时为空
+    pub mqtt_outbox: Option<u64>,
+    /// 只读期间暂存、等待补写的上报条数
+    pub ingest_buffer: u64,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetReadOnlyRequest {
+    pub enabled: bool,
+    /// 开启原因，会出现在被拒绝请求的提示中
+    pub reason: Option<String>,
+}
+
+/// 调整注入的故障，未给出的项保持不变
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetFaultInjectionRequest {
+    /// 先清除全部故障再应用本次设置
+    #[serde(default)]
+    pub clear: bool,
+    /// 丢弃收发 MQTT 消息的比例（0-100）
+    pub mqtt_drop_percent: Option<u8>,
+    /// 每次写入测量值前的延迟
+    pub db_commit_delay_ms: Option<u64>,
+    /// 立即关闭 RabbitMQ 消费者 channel
+    #[serde(default)]
+    pub kill_rabbitmq_channel: bool,
+}
+
+/// 获取当前生效的网络区域策略
+#[utoipa::path(
+    get,
+    path = "/system/network-policy",
+    responses(
+        (status = 200, description = "获取网络策略成功", body = NetworkPolicyConfig)
+    ),
+    tag = "System"
+)]
+pub async fn get_network_policy(
+    State(state): State<Arc<AppState>>,
+) -> Json<NetworkPolicyConfig> {
+    Json(state.settings.network_policy.clone())
+}
+
+/// 获取网络接口状态
+#[utoipa::path(
+    get,
+    path = "/system/network",
+    responses(
+        (status = 200, description = "获取网络接口状态成功", body = [InterfaceStatus])
+    ),
+    tag = "System"
+)]
+pub async fn get_network(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<InterfaceStatus>> {
+    Json(state.network.statuses())
+}
+
+/// 获取主机资源状况
+#[utoipa::path(
+    get,
+    path = "/system/stats",
+    responses(
+        (status = 200, description = "获取主机资源状况成功", body = SystemStats)
+    ),
+    tag = "System"
+)]
+pub async fn get_stats(
+    State(state): State<Arc<AppState>>,
+) -> Json<SystemStats> {
+    Json(SystemProbe::new(&state.settings).collect().await)
+}
+
+/// 获取数据库语句耗时统计，用于定位拖慢网关的查询
+#[utoipa::path(
+    get,
+    path = "/system/queries",
+    responses(
+        (status = 200, description = "获取语句耗时统计成功", body = QueryMetricsSnapshot)
+    ),
+    tag = "System"
+)]
+pub async fn get_query_metrics(
+    State(state): State<Arc<AppState>>,
+) -> Json<QueryMetricsSnapshot> {
+    Json(state.db.query_metrics().snapshot())
+}
+
+/// 清空数据库语句耗时统计，需使用具备 admin 权限的 Key 调用
+#[utoipa::path(
+    delete,
+    path = "/system/queries",
+    responses(
+        (status = 204, description = "已清空"),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key，或不是平台管理 Key")
+    ),
+    tag = "System"
+)]
+pub async fn reset_query_metrics(
+    State(state): State<Arc<AppState>>,
+    AdminKey(_): AdminKey,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    tenant.require_platform()?;
+    state.db.query_metrics().reset();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 获取只读模式状态
+#[utoipa::path(
+    get,
+    path = "/system/read-only",
+    responses(
+        (status = 200, description = "获取只读模式状态成功", body = ReadOnlyStatus)
+    ),
+    tag = "System"
+)]
+pub async fn get_read_only(
+    State(state): State<Arc<AppState>>,
+) -> Json<ReadOnlyStatus> {
+    Json(state.read_only.status())
+}
+
+/// 手动开启或关闭只读模式，需使用具备 admin 权限的 Key 调用
+///
+/// 只读期间修改类接口返回 503，数据上报写入暂存，关闭后自动补写入库。
+/// 磁盘空间不足触发的只读模式不能手动关闭。
+#[utoipa::path(
+    put,
+    path = "/system/read-only",
+    request_body = SetReadOnlyRequest,
+    responses(
+        (status = 200, description = "设置只读模式成功", body = ReadOnlyStatus),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key，或不是平台管理 Key")
+    ),
+    tag = "System"
+)]
+pub async fn set_read_only(
+    State(state): State<Arc<AppState>>,
+    AdminKey(caller): AdminKey,
+    tenant: Tenant,
+    Json(payload): Json<SetReadOnlyRequest>,
+) -> Result<Json<ReadOnlyStatus>, AppError> {
+    tenant.require_platform()?;
+    let operator = Some(caller.operator_label());
+    state.read_only.set_manual(payload.enabled, payload.reason, operator);
+    Ok(Json(state.read_only.status()))
+}
+
+/// 获取队列积压情况
+#[utoipa::path(
+    get,
+    path = "/system/queues",
+    responses(
+        (status = 200, description = "获取队列积压成功", body = QueueDepths)
+    ),
+    tag = "System"
+)]
+pub async fn get_queues(
+    State(state): State<Arc<AppState>>,
+) -> Json<QueueDepths> {
+    Json(QueueDepths {
+        mqtt_outbox: state.mqtt.as_ref().map(|mqtt| mqtt.pending_messages()),
+        ingest_buffer: state.read_only.buffered_count(),
+        read_only: state.read_only.is_active(),
+    })
+}
+
+/// 获取当前注入的故障（仅调试构建）
+#[utoipa::path(
+    get,
+    path = "/system/fault-injection",
+    responses(
+        (status = 200, description = "获取故障注入状态成功", body = FaultInjectionStatus),
+        (status = 404, description = "发布构建不提供故障注入")
+    ),
+    tag = "System"
+)]
+pub async fn get_fault_injection() -> Result<Json<FaultInjectionStatus>, AppError> {
+    if !fault_injection::AVAILABLE {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(fault_injection::status()))
+}
+
+/// 注入故障（仅调试构建），需使用具备 admin 权限的 Key 调用
+///
+/// 用于验证离线队列、重试和消费者监管在故障下的表现，不要在生产网关上使用。
+#[utoipa::path(
+    put,
+    path = "/system/fault-injection",
+    request_body = SetFaultInjectionRequest,
+    responses(
+        (status = 200, description = "设置故障注入成功", body = FaultInjectionStatus),
+        (status = 401, description = "未提供有效的 API Key"),
+        (status = 403, description = "不是 admin Key，或不是平台管理 Key"),
+        (status = 404, description = "发布构建不提供故障注入")
+    ),
+    tag = "System"
+)]
+pub async fn set_fault_injection(
+    State(state): State<Arc<AppState>>,
+    AdminKey(caller): AdminKey,
+    tenant: Tenant,
+    Json(payload): Json<SetFaultInjectionRequest>,
+) -> Result<Json<FaultInjectionStatus>, AppError> {
+    tenant.require_platform()?;
+    if !fault_injection::AVAILABLE {
+        return Err(AppError::NotFound);
+    }
+    warn!("Fault injection changed by {}: {:?}", caller.operator_label(), payload);
+    if payload.clear {
+        fault_injection::clear();
+    }
+    if let Some(percent) = payload.mqtt_drop_percent {
+        fault_injection::set_mqtt_drop_percent(percent);
+    }
+    if let Some(delay_ms) = payload.db_commit_delay_ms {
+        fault_injection::set_db_commit_delay(Duration::from_millis(delay_ms));
+    }
+    if payload.kill_rabbitmq_channel {
+        if let Some(rabbitmq) = &state.rabbitmq {
+            let killed = rabbitmq.kill_consumer_channels().await;
+            fault_injection::record_channels_killed(killed as u64);
+        }
+    }
+    Ok(Json(fault_injection::status()))
+}
@@ -0,0 +1,167 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::tank_geometry::{Entity as TankEntity, Model as TankGeometry};
+use crate::services::config_revision;
+use crate::services::tank::{self, Tank};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TankGeometryRequest {
+    /// cylinder / cone / strapping
+    pub shape: String,
+    /// 探头到罐底的距离 (m)
+    pub sensor_offset: f64,
+    /// 最高液位 (m)
+    pub height: f64,
+    pub diameter: Option<f64>,
+    pub cone_height: Option<f64>,
+    /// 容积表 [[液位, 容积], ...]
+    pub strapping_table: Option<serde_json::Value>,
+}
+
+/// 获取液位计的罐体参数
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/tank-geometry",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "获取罐体参数成功", body = TankGeometry),
+        (status = 404, description = "未配置罐体参数")
+    ),
+    tag = "Devices"
+)]
+pub async fn get_tank_geometry(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<TankGeometry>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let geometry = tank::find_geometry(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    Ok(Json(geometry))
+}
+
+/// 设置液位计的罐体参数，之后上报的距离会换算为液位、容积和充满度
+#[utoipa::path(
+    put,
+    path = "/devices/{id}/tank-geometry",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = TankGeometryRequest,
+    responses(
+        (status = 200, description = "设置罐体参数成功", body = TankGeometry),
+        (status = 400, description = "罐体参数无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Devices"
+)]
+pub async fn put_tank_geometry(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<TankGeometryRequest>,
+) -> Result<Json<TankGeometry>, AppError> {
+    let conn = state.db.get_connection();
+
+    DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let existing = tank::find_geometry(conn, id).await?;
+    let now = Utc::now();
+    let geometry = TankGeometry {
+        id: existing.as_ref().map(|g| g.id).unwrap_or_default(),
+        device_id: id,
+        shape: payload.shape,
+        sensor_offset: payload.sensor_offset,
+        height: payload.height,
+        diameter: payload.diameter,
+        cone_height: payload.cone_height,
+        strapping_table: payload.strapping_table.map(|t| t.to_string()),
+        created_at: existing.as_ref().map(|g| g.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    Tank::from_geometry(&geometry)?;
+
+    let geometry = match existing {
+        Some(existing) => {
+            let updated = TankEntity::update(geometry.into_active_model().reset_all())
+                .exec(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_update(conn, &existing, &updated, operator).await?;
+            updated
+        }
+        None => {
+            let mut active_model = geometry.into_active_model();
+            active_model.id = Default::default();
+            let created = TankEntity::insert(active_model)
+                .exec_with_returning(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_create(conn, &created, operator).await?;
+            created
+        }
+    };
+
+    Ok(Json(geometry))
+}
+
+/// 删除液位计的罐体参数
+#[utoipa::path(
+    delete,
+    path = "/devices/{id}/tank-geometry",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 204, description = "删除罐体参数成功"),
+        (status = 404, description = "未配置罐体参数")
+    ),
+    tag = "Devices"
+)]
+pub async fn delete_tank_geometry(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let geometry = tank::find_geometry(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    TankEntity::delete_by_id(geometry.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &geometry, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,12 +1,19 @@
+//! TDS值接口，基于通用测量值接口（metric_type = "tds"）的兼容封装
+
 use crate::app_state::AppState;
-use crate::models::tds_value::{Entity as TdsValueEntity, Model as TdsValue, ActiveModel as TdsValueActiveModel};
+use crate::handlers::measurement::ingest_response;
+use crate::models::tds_value::Model as TdsValue;
+use crate::services::data_correction::CorrectionNote;
+use crate::services::measurement::{self as measurement_service, MeasurementChanges, MeasurementFilter, NewMeasurement};
+use crate::services::metric_registry::TDS as METRIC;
 use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State, Query},
     http::StatusCode,
-    response::Json,
+    response::{Json, Response},
 };
-use sea_orm::{EntityTrait, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -47,27 +54,21 @@ pub struct Pagination {
 pub async fn get_tds_values(
     State(state): State<Arc<AppState>>,
     Query(pagination): Query<Pagination>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<TdsValue>>, AppError> {
     let conn = state.db.get_connection();
-    
+
     let page = pagination.page.unwrap_or(1);
     let per_page = pagination.per_page.unwrap_or(10).min(100); // 限制每页最多100条
-    
-    let tds_values = TdsValueEntity::find()
-        .all(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    // 简化的分页实现
-    let start = ((page - 1) * per_page) as usize;
-    let end = (start + per_page as usize).min(tds_values.len());
-    let paginated_tds_values = if start < tds_values.len() {
-        tds_values[start..end].to_vec()
-    } else {
-        vec![]
+
+    let filter = MeasurementFilter {
+        metric_type: Some(METRIC.to_string()),
+        organization_id: tenant.organization_id(),
+        ..Default::default()
     };
+    let tds_values = measurement_service::list(conn, &filter, page, per_page).await?;
 
-    Ok(Json(paginated_tds_values))
+    Ok(Json(tds_values.into_iter().map(TdsValue::from).collect()))
 }
 
 /// 获取指定TDS值
@@ -86,16 +87,14 @@ pub async fn get_tds_values(
 pub async fn get_tds_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    tenant: Tenant,
 ) -> Result<Json<TdsValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let tds_value = TdsValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    Ok(Json(tds_value))
+
+    let tds_value = measurement_service::get(conn, id, Some(METRIC)).await?;
+    tenant.check_device(conn, tds_value.device_id).await?;
+
+    Ok(Json(tds_value.into()))
 }
 
 /// 创建TDS值
@@ -105,30 +104,35 @@ pub async fn get_tds_value(
     request_body = CreateTdsValueRequest,
     responses(
         (status = 201, description = "创建TDS值成功", body = TdsValue),
+        (status = 202, description = "被压缩规则合并或已暂存，未单独入库"),
         (status = 400, description = "请求参数错误")
     ),
     tag = "TDS Values"
 )]
 pub async fn create_tds_value(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
     Json(payload): Json<CreateTdsValueRequest>,
-) -> Result<(StatusCode, Json<TdsValue>), AppError> {
+) -> Result<Response, AppError> {
     let conn = state.db.get_connection();
-    
-    let new_tds_value = TdsValueActiveModel {
-        timestamp: sea_orm::Set(payload.timestamp),
-        value: sea_orm::Set(payload.value),
-        device_id: sea_orm::Set(payload.device_id),
-        unit: sea_orm::Set(payload.unit),
-        ..Default::default()
-    };
-
-    let tds_value = TdsValueEntity::insert(new_tds_value)
-        .exec_with_returning(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok((StatusCode::CREATED, Json(tds_value)))
+    tenant.check_device(conn, payload.device_id).await?;
+
+    let ingested = state
+        .read_only
+        .ingest_one(
+            conn,
+            &state.cache,
+            NewMeasurement {
+                metric_type: METRIC.to_string(),
+                timestamp: payload.timestamp,
+                value: payload.value,
+                device_id: payload.device_id,
+                unit: Some(payload.unit),
+            },
+        )
+        .await?;
+
+    Ok(ingest_response::<TdsValue>(ingested))
 }
 
 /// 更新TDS值
@@ -148,43 +152,34 @@ pub async fn create_tds_value(
 pub async fn update_tds_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
     Json(payload): Json<UpdateTdsValueRequest>,
 ) -> Result<Json<TdsValue>, AppError> {
     let conn = state.db.get_connection();
-    
-    let existing_tds_value = TdsValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-        
-    let mut tds_value_active_model = existing_tds_value.into_active_model();
-    
-    if let Some(timestamp) = payload.timestamp {
-        tds_value_active_model.timestamp = sea_orm::Set(timestamp);
-    }
-    
-    if let Some(value) = payload.value {
-        tds_value_active_model.value = sea_orm::Set(value);
-    }
-    
+    tenant.check_measurement(conn, id).await?;
     if let Some(device_id) = payload.device_id {
-        tds_value_active_model.device_id = sea_orm::Set(device_id);
+        tenant.check_device(conn, device_id).await?;
     }
-    
-    if let Some(unit) = payload.unit {
-        tds_value_active_model.unit = sea_orm::Set(unit);
-    }
-    
-    // 更新 updated_at 字段
-    tds_value_active_model.updated_at = sea_orm::Set(chrono::Utc::now());
-    
-    let updated_tds_value = TdsValueEntity::update(tds_value_active_model)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
-
-    Ok(Json(updated_tds_value))
+
+    let updated_tds_value = measurement_service::update(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        id,
+        Some(METRIC),
+        MeasurementChanges {
+            timestamp: payload.timestamp,
+            value: payload.value,
+            device_id: payload.device_id,
+            unit: payload.unit,
+            ..Default::default()
+        },
+        CorrectionNote { corrected_by: operator, ..Default::default() },
+    )
+    .await?;
+
+    Ok(Json(updated_tds_value.into()))
 }
 
 /// 删除TDS值
@@ -203,19 +198,14 @@ pub async fn update_tds_value(
 pub async fn delete_tds_value(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let conn = state.db.get_connection();
-    
-    let tds_value = TdsValueEntity::find_by_id(id)
-        .one(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?
-        .ok_or_else(|| AppError::NotFound)?;
-
-    let _ = TdsValueEntity::delete_by_id(tds_value.id)
-        .exec(conn)
-        .await
-        .map_err(|_| AppError::InternalError)?;
+    tenant.check_measurement(conn, id).await?;
+
+    let note = CorrectionNote { corrected_by: operator, ..Default::default() };
+    measurement_service::delete(conn, &state.cache, id, Some(METRIC), note).await?;
 
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
@@ -0,0 +1,61 @@
+use crate::app_state::AppState;
+use crate::services::metric_registry;
+use crate::services::trend::{self, Trend};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TrendQuery {
+    /// 指标类型
+    pub metric: String,
+    pub device_id: Option<i32>,
+    pub start: DateTime<Utc>,
+    /// 缺省为当前时间
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// 长期趋势
+///
+/// 按时间范围自动选择原始数据、小时聚合或日聚合，`resolution` 为实际使用的分辨率。
+#[utoipa::path(
+    get,
+    path = "/trend",
+    params(TrendQuery),
+    responses(
+        (status = 200, description = "获取趋势成功", body = Trend),
+        (status = 400, description = "参数错误")
+    ),
+    tag = "Measurements"
+)]
+pub async fn get_trend(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TrendQuery>,
+    tenant: Tenant,
+) -> Result<Json<Trend>, AppError> {
+    if metric_registry::lookup(&query.metric).is_none() {
+        return Err(AppError::InvalidInput(format!("未知的指标类型: {}", query.metric).into()));
+    }
+    let conn = state.db.get_connection();
+    // 租户只能查询本组织设备的趋势
+    tenant.check_device(conn, query.device_id).await?;
+    let end = query.end.unwrap_or_else(Utc::now);
+    let trend = trend::trend(
+        conn,
+        state.timeseries.as_ref(),
+        query.metric,
+        query.device_id,
+        query.start,
+        end,
+        &state.settings.trend,
+    )
+    .await?;
+    Ok(Json(trend))
+}
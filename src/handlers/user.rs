@@ -1,6 +1,7 @@
 use crate::app_state::AppState;
 use crate::models::user::Model as User;
 use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -16,6 +17,9 @@ pub struct CreateUserRequest {
     pub email: String,
     pub password: String,
     pub permission: String,
+    /// 所属组织，租户 Key 只能建在本组织
+    #[serde(default)]
+    pub organization_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -37,8 +41,16 @@ pub struct UpdateUserRequest {
 )]
 pub async fn get_users(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
 ) -> Result<Json<Vec<User>>, AppError> {
-    let users = state.users.read().unwrap().clone();
+    let users = state
+        .users
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|u| tenant.owns(u.organization_id))
+        .cloned()
+        .collect();
     Ok(Json(users))
 }
 
@@ -58,9 +70,10 @@ pub async fn get_users(
 pub async fn get_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
+    tenant: Tenant,
 ) -> Result<Json<User>, AppError> {
     let users = state.users.read().unwrap();
-    let user = users.iter().find(|u| u.id == id).cloned();
+    let user = users.iter().find(|u| u.id == id && tenant.owns(u.organization_id)).cloned();
 
     match user {
         Some(u) => Ok(Json(u)),
@@ -75,14 +88,17 @@ pub async fn get_user(
     request_body = CreateUserRequest,
     responses(
         (status = 201, description = "创建用户成功", body = User),
-        (status = 400, description = "请求参数错误")
+        (status = 400, description = "请求参数错误"),
+        (status = 403, description = "不能在其他组织下创建用户")
     ),
     tag = "Users"
 )]
 pub async fn create_user(
     State(state): State<Arc<AppState>>,
+    tenant: Tenant,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<User>), AppError> {
+    let organization_id = tenant.assign(payload.organization_id)?;
     let mut users = state.users.write().unwrap();
     
     // 确定新用户的ID
@@ -94,6 +110,7 @@ pub async fn create_user(
         email: payload.email,
         password: payload.password,
         permission: payload.permission,
+        organization_id,
     };
 
     users.push(user.clone());
@@ -118,10 +135,11 @@ pub async fn create_user(
 pub async fn update_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
+    tenant: Tenant,
     Json(payload): Json<UpdateUserRequest>,
 ) -> Result<Json<User>, AppError> {
     let mut users = state.users.write().unwrap();
-    let user = users.iter_mut().find(|u| u.id == id);
+    let user = users.iter_mut().find(|u| u.id == id && tenant.owns(u.organization_id));
 
     match user {
         Some(u) => {
@@ -161,10 +179,11 @@ pub async fn update_user(
 pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Path(id): Path<u32>,
+    tenant: Tenant,
 ) -> Result<StatusCode, AppError> {
     let mut users = state.users.write().unwrap();
     let len_before = users.len();
-    users.retain(|u| u.id != id);
+    users.retain(|u| !(u.id == id && tenant.owns(u.organization_id)));
     
     if users.len() < len_before {
         Ok(StatusCode::NO_CONTENT)
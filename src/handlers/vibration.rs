@@ -0,0 +1,302 @@
+use crate::app_state::AppState;
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::vibration_limit::{Entity as VibrationLimitEntity, Model as VibrationLimit};
+use crate::models::vibration_record::Model as VibrationRecord;
+use crate::services::config_revision;
+use crate::services::vibration::{self, BandLimit, NewVibration, TrendPoint};
+use crate::utils::error::AppError;
+use crate::utils::operator::Operator;
+use crate::utils::tenant::Tenant;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VibrationRequest {
+    /// 缺省为接收时间
+    pub timestamp: Option<DateTime<Utc>>,
+    /// bands：频带幅值；burst：原始波形
+    pub kind: String,
+    /// 采样率 (Hz)，burst 使用
+    pub sample_rate: Option<f64>,
+    /// 每个频带的宽度 (Hz)，bands 使用
+    pub band_width: Option<f64>,
+    pub values: Vec<f64>,
+    /// 轴承温度 (°C)
+    pub temperature: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VibrationLimitRequest {
+    #[serde(default)]
+    pub band_limits: Vec<BandLimit>,
+    pub rms_limit: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct VibrationQuery {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// 最多返回条数，默认 20，最多 100
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct VibrationTrendQuery {
+    /// 频带序号，不指定时为总振动有效值
+    pub band: Option<usize>,
+    /// 缺省为 7 天前
+    pub start: Option<DateTime<Utc>>,
+    /// 缺省为当前时间
+    pub end: Option<DateTime<Utc>>,
+    /// 时间桶长度（秒），默认 3600
+    pub bucket_secs: Option<i64>,
+}
+
+async fn ensure_device(
+    conn: &DatabaseConnection,
+    tenant: Tenant,
+    id: i32,
+) -> Result<(), AppError> {
+    DeviceEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .filter(|device| tenant.owns(device.organization_id))
+        .ok_or_else(|| AppError::NotFound)?;
+    Ok(())
+}
+
+/// 上报振动数据
+#[utoipa::path(
+    post,
+    path = "/devices/{id}/vibration",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = VibrationRequest,
+    responses(
+        (status = 201, description = "保存成功", body = VibrationRecord),
+        (status = 400, description = "数据无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Vibration"
+)]
+pub async fn create_vibration(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+    Json(payload): Json<VibrationRequest>,
+) -> Result<(StatusCode, Json<VibrationRecord>), AppError> {
+    let conn = state.db.get_connection();
+    ensure_device(conn, tenant, id).await?;
+
+    let record = vibration::ingest(
+        conn,
+        &state.cache,
+        &state.settings.quality,
+        NewVibration {
+            device_id: id,
+            timestamp: payload.timestamp.unwrap_or_else(Utc::now),
+            kind: payload.kind,
+            sample_rate: payload.sample_rate,
+            band_width: payload.band_width,
+            values: payload.values,
+            temperature: payload.temperature,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(record)))
+}
+
+/// 获取振动记录
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/vibration",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        VibrationQuery
+    ),
+    responses(
+        (status = 200, description = "获取振动记录成功", body = [VibrationRecord]),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Vibration"
+)]
+pub async fn get_vibration(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<VibrationQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<VibrationRecord>>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let limit = query.limit.unwrap_or(20).min(100);
+    let records = vibration::list(conn, id, query.start, query.end, limit).await?;
+    Ok(Json(records))
+}
+
+/// 获取频带或总值趋势
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/vibration/trend",
+    params(
+        ("id" = i32, Path, description = "设备ID"),
+        VibrationTrendQuery
+    ),
+    responses(
+        (status = 200, description = "获取趋势成功", body = [TrendPoint]),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Vibration"
+)]
+pub async fn get_vibration_trend(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Query(query): Query<VibrationTrendQuery>,
+    tenant: Tenant,
+) -> Result<Json<Vec<TrendPoint>>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let end = query.end.unwrap_or_else(Utc::now);
+    let start = query.start.unwrap_or(end - Duration::days(7));
+    let bucket_secs = query.bucket_secs.unwrap_or(3600);
+
+    let points = vibration::trend(conn, id, query.band, start, end, bucket_secs).await?;
+    Ok(Json(points))
+}
+
+/// 获取振动阈值
+#[utoipa::path(
+    get,
+    path = "/devices/{id}/vibration-limits",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 200, description = "获取振动阈值成功", body = VibrationLimit),
+        (status = 404, description = "未配置振动阈值")
+    ),
+    tag = "Vibration"
+)]
+pub async fn get_vibration_limit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    tenant: Tenant,
+) -> Result<Json<VibrationLimit>, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let limit = vibration::find_limit(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    Ok(Json(limit))
+}
+
+/// 设置振动阈值
+#[utoipa::path(
+    put,
+    path = "/devices/{id}/vibration-limits",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    request_body = VibrationLimitRequest,
+    responses(
+        (status = 200, description = "设置振动阈值成功", body = VibrationLimit),
+        (status = 400, description = "阈值无效"),
+        (status = 404, description = "设备未找到")
+    ),
+    tag = "Vibration"
+)]
+pub async fn put_vibration_limit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+    Json(payload): Json<VibrationLimitRequest>,
+) -> Result<Json<VibrationLimit>, AppError> {
+    let conn = state.db.get_connection();
+    ensure_device(conn, tenant, id).await?;
+
+    let existing = vibration::find_limit(conn, id).await?;
+    let now = Utc::now();
+    let limit = VibrationLimit {
+        id: existing.as_ref().map(|l| l.id).unwrap_or_default(),
+        device_id: id,
+        band_limits: serde_json::to_string(&payload.band_limits).map_err(|_| AppError::InternalError)?,
+        rms_limit: payload.rms_limit,
+        created_at: existing.as_ref().map(|l| l.created_at).unwrap_or(now),
+        updated_at: now,
+    };
+    vibration::validate_limit(&limit)?;
+
+    let limit = match existing {
+        Some(existing) => {
+            let updated = VibrationLimitEntity::update(limit.into_active_model().reset_all())
+                .exec(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_update(conn, &existing, &updated, operator).await?;
+            updated
+        }
+        None => {
+            let mut active_model = limit.into_active_model();
+            active_model.id = Default::default();
+            let created = VibrationLimitEntity::insert(active_model)
+                .exec_with_returning(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            config_revision::record_create(conn, &created, operator).await?;
+            created
+        }
+    };
+
+    Ok(Json(limit))
+}
+
+/// 删除振动阈值
+#[utoipa::path(
+    delete,
+    path = "/devices/{id}/vibration-limits",
+    params(
+        ("id" = i32, Path, description = "设备ID")
+    ),
+    responses(
+        (status = 204, description = "删除振动阈值成功"),
+        (status = 404, description = "未配置振动阈值")
+    ),
+    tag = "Vibration"
+)]
+pub async fn delete_vibration_limit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Operator(operator): Operator,
+    tenant: Tenant,
+) -> Result<StatusCode, AppError> {
+    let conn = state.db.get_connection();
+    tenant.check_device(conn, Some(id)).await?;
+
+    let limit = vibration::find_limit(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    VibrationLimitEntity::delete_by_id(limit.id)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    config_revision::record_delete(conn, &limit, operator).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,21 @@
+//! guolu 水处理网关
+//!
+//! 服务进程（`src/main.rs`）和集成测试（`tests/`）共用的各模块。
+
+pub mod acquisition;
+pub mod app_state;
+pub mod cli;
+pub mod config;
+pub mod control;
+pub mod database;
+pub mod drivers;
+pub mod grpc;
+pub mod handlers;
+pub mod middleware;
+pub mod models;
+pub mod mqtt;
+pub mod message_queue;
+pub mod routes;
+pub mod services;
+pub mod tui;
+pub mod utils;
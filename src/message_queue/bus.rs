@@ -0,0 +1,142 @@
+//! 事件总线后端
+//!
+//! 事件通过 [`MessageBus`] 发出：有 RabbitMQ 时发布到交换机，单机部署没有 broker 时
+//! 使用进程内的 broadcast 通道，本进程的消费者照常收到事件。后端由 `event_bus.backend`
+//! 选择，`auto` 在 RabbitMQ 连接成功时使用 RabbitMQ，否则退回进程内。
+
+use crate::config::event_bus::EventBusConfig;
+use crate::message_queue::events::{EventBus, EventEnvelope};
+use crate::message_queue::rabbitmq::RabbitMQManager;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+pub const BACKEND_AUTO: &str = "auto";
+pub const BACKEND_RABBITMQ: &str = "rabbitmq";
+pub const BACKEND_IN_PROCESS: &str = "in_process";
+
+#[tonic::async_trait]
+pub trait MessageBus: Send + Sync + std::fmt::Debug {
+    /// 后端名称
+    fn backend(&self) -> &'static str;
+
+    async fn publish(&self, envelope: &EventEnvelope) -> Result<()>;
+}
+
+/// 发布到 RabbitMQ 交换机
+#[derive(Debug, Clone)]
+pub struct RabbitMqBus {
+    manager: RabbitMQManager,
+    exchange: String,
+}
+
+impl RabbitMqBus {
+    pub fn new(manager: RabbitMQManager, exchange: &str) -> Self {
+        Self {
+            manager,
+            exchange: exchange.to_string(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl MessageBus for RabbitMqBus {
+    fn backend(&self) -> &'static str {
+        BACKEND_RABBITMQ
+    }
+
+    async fn publish(&self, envelope: &EventEnvelope) -> Result<()> {
+        self.manager.publish_event(&self.exchange, envelope).await?;
+        Ok(())
+    }
+}
+
+/// 进程内广播，消费者跟不上时丢弃最旧的事件
+#[derive(Debug, Clone)]
+pub struct InProcessBus {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl InProcessBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+}
+
+#[tonic::async_trait]
+impl MessageBus for InProcessBus {
+    fn backend(&self) -> &'static str {
+        BACKEND_IN_PROCESS
+    }
+
+    async fn publish(&self, envelope: &EventEnvelope) -> Result<()> {
+        // 没有订阅者时事件直接丢弃，不算失败
+        let _ = self.sender.send(envelope.clone());
+        Ok(())
+    }
+}
+
+/// 按配置选择后端；使用进程内后端时同时返回它以便启动本地消费者
+pub fn select(
+    config: &EventBusConfig,
+    rabbitmq: &RabbitMQManager,
+    connected: bool,
+    exchange: &str,
+) -> (EventBus, Option<InProcessBus>) {
+    let use_rabbitmq = match config.backend.as_str() {
+        BACKEND_RABBITMQ => {
+            // 明确要求 RabbitMQ 时不退回进程内，未连接时发布失败会记日志
+            if !connected {
+                warn!("Event bus backend is rabbitmq but RabbitMQ is not connected");
+            }
+            true
+        }
+        BACKEND_IN_PROCESS => false,
+        other => {
+            if other != BACKEND_AUTO {
+                warn!("Unknown event bus backend '{}', using auto", other);
+            }
+            connected
+        }
+    };
+    if use_rabbitmq {
+        let bus = RabbitMqBus::new(rabbitmq.clone(), exchange);
+        (EventBus::new(Arc::new(bus)), None)
+    } else {
+        let bus = InProcessBus::new(config.capacity);
+        (EventBus::new(Arc::new(bus.clone())), Some(bus))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_queue::events::Event;
+
+    #[tokio::test]
+    async fn test_in_process_fallback() {
+        let manager = RabbitMQManager::new("amqp://localhost");
+        let config = EventBusConfig::default();
+        let (events, in_process) = select(&config, &manager, false, "boiler_exchange");
+        assert_eq!(events.backend(), BACKEND_IN_PROCESS);
+
+        let mut receiver = in_process.unwrap().subscribe();
+        let event = Event::DeviceOnline {
+            device_id: 1,
+            source: "mqtt".to_string(),
+            timestamp: chrono::Utc::now(),
+        };
+        events.send(event.clone()).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().event, event);
+
+        let (events, in_process) = select(&config, &manager, true, "boiler_exchange");
+        assert_eq!(events.backend(), BACKEND_RABBITMQ);
+        assert!(in_process.is_none());
+    }
+}
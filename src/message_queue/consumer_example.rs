@@ -1,23 +1,41 @@
 //! RabbitMQ 消息消费者示例
 //!
-//! 展示如何订阅消息队列并处理接收到的消息
+//! 展示如何订阅消息队列并按事件类型处理接收到的事件
+//!
+//! 无法解析的消息直接拒绝并转入死信队列；处理失败的消息重新投递，
+//! 超过最大次数后同样转入死信队列，避免毒消息无限循环。
 
-use crate::message_queue::rabbitmq::{Message, RabbitMQManager};
+use crate::message_queue::bus::InProcessBus;
+use crate::message_queue::events::{Event, EventEnvelope};
+use crate::message_queue::rabbitmq::{self, RabbitMQManager};
 use anyhow::Result;
 use futures_util::StreamExt;
-use tracing::{error, info};
+use lapin::message::Delivery;
+use lapin::options::BasicNackOptions;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+/// 拒绝消息且不重新入队，配置了死信交换机时转入死信队列
+async fn dead_letter(delivery: &Delivery) {
+    let options = BasicNackOptions { requeue: false, ..Default::default() };
+    if let Err(e) = delivery.nack(options).await {
+        error!("拒绝消息失败: {}", e);
+    }
+}
 
 /// 启动消息消费者任务
 ///
 /// # 参数
 /// * `rabbitmq_manager` - RabbitMQ 管理器实例
 /// * `queue_name` - 要订阅的队列名称
+/// * `max_redeliveries` - 处理失败后最多重新投递的次数
 ///
 /// # 返回
 /// 返回一个任务句柄，可用于等待任务完成
 pub async fn start_consumer_task(
     rabbitmq_manager: RabbitMQManager,
     queue_name: &str,
+    max_redeliveries: u32,
 ) -> Result<tokio::task::JoinHandle<()>> {
     // 克隆管理器以在异步任务中使用
     let manager = rabbitmq_manager.clone();
@@ -35,29 +53,34 @@ pub async fn start_consumer_task(
                     match delivery {
                         Ok(delivery) => {
                             // 解析消息内容
-                            match serde_json::from_slice::<Message>(&delivery.data) {
-                                Ok(message) => {
-                                    // 处理消息
-                                    handle_message(&message).await;
-
-                                    // 确认消息已被处理
-                                    if let Err(e) = delivery.ack(Default::default()).await {
-                                        error!("确认消息失败: {}", e);
-                                    }
-                                }
+                            let envelope = match EventEnvelope::decode(&delivery.data) {
+                                Ok(envelope) => envelope,
                                 Err(e) => {
-                                    error!("解析消息失败: {}", e);
-                                    // 拒绝消息并重新入队
-                                    if let Err(e) = delivery
-                                        .nack(lapin::options::BasicNackOptions {
-                                            requeue: true,
-                                            ..Default::default()
-                                        })
-                                        .await
-                                    {
-                                        error!("拒绝消息失败: {}", e);
-                                    }
+                                    // 重试也无法解析，直接转入死信队列
+                                    error!("解析消息失败，转入死信队列: {}", e);
+                                    dead_letter(&delivery).await;
+                                    continue;
                                 }
+                            };
+
+                            // 处理事件
+                            if let Err(e) = handle_event(&envelope).await {
+                                let retries = rabbitmq::retry_count(&delivery.properties);
+                                if retries >= max_redeliveries {
+                                    error!("消息已重试 {} 次仍失败，转入死信队列: {}", retries, e);
+                                    dead_letter(&delivery).await;
+                                } else if let Err(e) = manager.redeliver(&queue, &delivery).await {
+                                    // 重新投递失败时保持未确认，连接断开后由 broker 重新入队
+                                    error!("重新投递消息失败: {}", e);
+                                } else {
+                                    warn!("处理消息失败，已重新投递（第 {} 次）: {}", retries + 1, e);
+                                }
+                                continue;
+                            }
+
+                            // 确认消息已被处理
+                            if let Err(e) = delivery.ack(Default::default()).await {
+                                error!("确认消息失败: {}", e);
                             }
                         }
                         Err(e) => {
@@ -75,52 +98,76 @@ pub async fn start_consumer_task(
     Ok(handle)
 }
 
-/// 处理接收到的消息
+/// 启动进程内总线的消费者任务，没有 RabbitMQ 时事件在本进程内处理
+///
+/// 进程内事件处理失败不重试，只记日志
+pub fn start_in_process_consumer(bus: &InProcessBus) -> tokio::task::JoinHandle<()> {
+    let mut receiver = bus.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(envelope) => {
+                    if let Err(e) = handle_event(&envelope).await {
+                        error!("处理事件 {} 失败: {}", envelope.id, e);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("事件处理跟不上，丢弃了 {} 条事件", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// 按事件类型分发
 ///
 /// 这是一个示例处理函数，您可以根据实际需求修改此函数
 ///
 /// # 参数
-/// * `message` - 接收到的消息
-async fn handle_message(message: &Message) {
-    info!("接收到消息:");
-    info!("  主题: {}", message.topic);
-    info!("  内容: {}", message.payload);
-    info!("  时间戳: {}", message.timestamp);
-
-    // 根据消息主题执行不同操作
-    match message.topic.as_str() {
-        "device.status" => {
-            handle_device_status_update(&message.payload).await;
+/// * `envelope` - 接收到的事件
+///
+/// 返回错误时消息会重新投递
+async fn handle_event(envelope: &EventEnvelope) -> Result<()> {
+    info!("接收到事件 {} ({})", envelope.event.routing_key(), envelope.id);
+
+    match &envelope.event {
+        Event::MeasurementRecorded { device_id, metric, value, .. } => {
+            handle_measurement(*device_id, metric, *value).await;
+        }
+        Event::AlarmTriggered { alarm_id, rule_name, severity, .. } => {
+            handle_alarm_trigger(*alarm_id, rule_name, severity).await;
         }
-        "sensor.data" => {
-            handle_sensor_data(&message.payload).await;
+        Event::DeviceOnline { device_id, .. } => {
+            handle_device_status_update(*device_id, true).await;
         }
-        "alarm.trigger" => {
-            handle_alarm_trigger(&message.payload).await;
+        Event::DeviceOffline { device_id, .. } => {
+            handle_device_status_update(*device_id, false).await;
         }
-        _ => {
-            info!("未知消息主题: {}", message.topic);
+        Event::CommandIssued { device_id, command, .. } => {
+            info!("设备 {} 已下发命令: {}", device_id, command);
         }
     }
+    Ok(())
 }
 
-/// 处理设备状态更新消息
-async fn handle_device_status_update(payload: &str) {
-    info!("处理设备状态更新: {}", payload);
+/// 处理设备上下线事件
+async fn handle_device_status_update(device_id: i32, online: bool) {
+    info!("处理设备状态更新: 设备 {} {}", device_id, if online { "上线" } else { "离线" });
     // 在这里添加具体的设备状态更新逻辑
     // 例如：更新数据库中的设备状态
 }
 
-/// 处理传感器数据消息
-async fn handle_sensor_data(payload: &str) {
-    info!("处理传感器数据: {}", payload);
+/// 处理测量值事件
+async fn handle_measurement(device_id: i32, metric: &str, value: f64) {
+    info!("处理传感器数据: 设备 {} {} = {}", device_id, metric, value);
     // 在这里添加具体的传感器数据处理逻辑
     // 例如：将数据存储到数据库或触发某些操作
 }
 
-/// 处理报警触发消息
-async fn handle_alarm_trigger(payload: &str) {
-    info!("处理报警触发: {}", payload);
+/// 处理报警触发事件
+async fn handle_alarm_trigger(alarm_id: i32, rule_name: &str, severity: &str) {
+    info!("处理报警触发: #{} {} [{}]", alarm_id, rule_name, severity);
     // 在这里添加具体的报警处理逻辑
     // 例如：发送通知、记录日志等
 }
@@ -128,16 +175,16 @@ async fn handle_alarm_trigger(payload: &str) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio;
 
     #[tokio::test]
-    async fn test_message_handling() {
-        let message = Message {
-            topic: "test.message".to_string(),
-            payload: "Hello, RabbitMQ!".to_string(),
+    async fn test_event_handling() {
+        let envelope = EventEnvelope::new(Event::MeasurementRecorded {
+            device_id: 1,
+            metric: "ph".to_string(),
+            value: 7.2,
             timestamp: chrono::Utc::now(),
-        };
+        });
 
-        handle_message(&message).await;
+        assert!(handle_event(&envelope).await.is_ok());
     }
 }
@@ -0,0 +1,218 @@
+//! 类型化事件
+//!
+//! 消息队列上传递的事件统一包在 [`EventEnvelope`] 中，以 JSON 编码：
+//! `{"version":1,"id":"…","occurred_at":"…","type":"alarm_triggered","data":{…}}`。
+//! 路由键由事件类型决定，消费者按类型分发，不再自行解析字符串内容。
+//! 新增字段须有默认值以保持兼容；不兼容的改动递增 [`SCHEMA_VERSION`]，
+//! 高于当前版本的事件拒绝解析。版本变更记录在 [`schema::CHANGELOG`](super::schema::CHANGELOG)。
+
+use crate::message_queue::bus::MessageBus;
+use crate::models::alarm_log::Model as AlarmLog;
+use crate::models::device_command::Model as DeviceCommand;
+use crate::utils::crypto;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// 当前事件格式版本
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    MeasurementRecorded {
+        device_id: i32,
+        metric: String,
+        value: f64,
+        timestamp: DateTime<Utc>,
+    },
+    AlarmTriggered {
+        alarm_id: i32,
+        rule_name: String,
+        device_id: Option<i32>,
+        severity: String,
+        value: f64,
+        #[serde(default)]
+        shelved: bool,
+        trigger_time: DateTime<Utc>,
+    },
+    DeviceOnline {
+        device_id: i32,
+        source: String,
+        timestamp: DateTime<Utc>,
+    },
+    DeviceOffline {
+        device_id: i32,
+        source: String,
+        timestamp: DateTime<Utc>,
+    },
+    CommandIssued {
+        command_id: i32,
+        correlation_id: String,
+        device_id: i32,
+        command: String,
+        issued_at: DateTime<Utc>,
+    },
+}
+
+impl Event {
+    /// 事件关联的设备
+    pub fn device_id(&self) -> Option<i32> {
+        match self {
+            Event::MeasurementRecorded { device_id, .. }
+            | Event::DeviceOnline { device_id, .. }
+            | Event::DeviceOffline { device_id, .. }
+            | Event::CommandIssued { device_id, .. } => Some(*device_id),
+            Event::AlarmTriggered { device_id, .. } => *device_id,
+        }
+    }
+
+    /// 发布用的路由键
+    pub fn routing_key(&self) -> &'static str {
+        match self {
+            Event::MeasurementRecorded { .. } => "measurement.recorded",
+            Event::AlarmTriggered { .. } => "alarm.triggered",
+            Event::DeviceOnline { .. } => "device.online",
+            Event::DeviceOffline { .. } => "device.offline",
+            Event::CommandIssued { .. } => "command.issued",
+        }
+    }
+}
+
+impl From<&AlarmLog> for Event {
+    fn from(alarm: &AlarmLog) -> Self {
+        Event::AlarmTriggered {
+            alarm_id: alarm.id,
+            rule_name: alarm.rule_name.clone(),
+            device_id: alarm.device_id,
+            severity: alarm.severity.clone(),
+            value: alarm.trigger_value,
+            shelved: alarm.shelved,
+            trigger_time: alarm.trigger_time,
+        }
+    }
+}
+
+impl From<&DeviceCommand> for Event {
+    fn from(command: &DeviceCommand) -> Self {
+        Event::CommandIssued {
+            command_id: command.id,
+            correlation_id: command.correlation_id.clone(),
+            device_id: command.device_id,
+            command: command.command.clone(),
+            issued_at: command.issued_at,
+        }
+    }
+}
+
+/// 事件信封
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct EventEnvelope {
+    pub version: u32,
+    /// 事件唯一 ID，供消费者去重
+    pub id: String,
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl EventEnvelope {
+    pub fn new(event: Event) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            id: crypto::random_token(16),
+            occurred_at: Utc::now(),
+            event,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("事件序列化不会失败")
+    }
+
+    pub fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        let envelope: Self = serde_json::from_slice(data)?;
+        if envelope.version > SCHEMA_VERSION {
+            anyhow::bail!("不支持的事件版本: {}", envelope.version);
+        }
+        Ok(envelope)
+    }
+}
+
+/// 事件发布器，后端见 [`crate::message_queue::bus`]
+///
+/// 事件发到主后端，同时抄送到附加的输出（如 Kafka）。
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    bus: Arc<dyn MessageBus>,
+    sinks: Vec<Arc<dyn MessageBus>>,
+}
+
+impl EventBus {
+    pub fn new(bus: Arc<dyn MessageBus>) -> Self {
+        Self { bus, sinks: Vec::new() }
+    }
+
+    /// 附加一个输出
+    pub fn with_sink(mut self, sink: Arc<dyn MessageBus>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.bus.backend()
+    }
+
+    /// 发布事件并等待各后端接收，全部尝试后返回第一个错误
+    pub async fn send(&self, event: Event) -> anyhow::Result<()> {
+        let envelope = EventEnvelope::new(event);
+        let mut result = Ok(());
+        for bus in std::iter::once(&self.bus).chain(&self.sinks) {
+            if let Err(e) = bus.publish(&envelope).await {
+                if result.is_ok() {
+                    result = Err(e.context(format!("{} 发布失败", bus.backend())));
+                }
+            }
+        }
+        result
+    }
+
+    /// 后台发布事件，不阻塞调用方；发布失败只记日志
+    pub fn publish(&self, event: Event) {
+        let bus = self.clone();
+        tokio::spawn(async move {
+            let routing_key = event.routing_key();
+            if let Err(e) = bus.send(event).await {
+                warn!("Failed to publish {} event: {:#}", routing_key, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_roundtrip() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let envelope = EventEnvelope::new(Event::DeviceOffline {
+            device_id: 3,
+            source: "mqtt".to_string(),
+            timestamp,
+        });
+        assert_eq!(envelope.event.routing_key(), "device.offline");
+        let json: serde_json::Value = serde_json::from_slice(&envelope.encode()).unwrap();
+        assert_eq!(json["type"], "device_offline");
+        assert_eq!(json["data"]["device_id"], 3);
+        assert_eq!(EventEnvelope::decode(&envelope.encode()).unwrap(), envelope);
+
+        let mut newer = json.clone();
+        newer["version"] = serde_json::json!(SCHEMA_VERSION + 1);
+        assert!(EventEnvelope::decode(newer.to_string().as_bytes()).is_err());
+        assert!(EventEnvelope::decode(br#"{"topic":"x","payload":"y"}"#).is_err());
+    }
+}
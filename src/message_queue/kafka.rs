@@ -0,0 +1,115 @@
+//! Kafka 事件输出
+//!
+//! 事件按路由键映射到主题，以设备 ID 为消息键保证同一设备的事件有序。
+//! 消息先进入 librdkafka 的本地队列，按 `linger_ms` 和 `batch_size` 攒批发送并在
+//! `message_timeout_ms` 内自动重试；本地队列满时发布返回错误，由调用方稍后重试。
+
+use crate::config::kafka::KafkaConfig;
+use crate::message_queue::bus::MessageBus;
+use crate::message_queue::events::EventEnvelope;
+use anyhow::Result;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::collections::HashMap;
+use tracing::warn;
+
+pub const BACKEND_KAFKA: &str = "kafka";
+
+pub struct KafkaBus {
+    producer: FutureProducer,
+    topics: HashMap<String, String>,
+    default_topic: Option<String>,
+}
+
+impl std::fmt::Debug for KafkaBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaBus")
+            .field("topics", &self.topics)
+            .field("default_topic", &self.default_topic)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 事件对应的主题
+fn topic_for<'a>(
+    topics: &'a HashMap<String, String>,
+    default_topic: Option<&'a str>,
+    routing_key: &str,
+) -> Option<&'a str> {
+    topics
+        .get(routing_key)
+        .map(String::as_str)
+        .or(default_topic)
+        .filter(|topic| !topic.is_empty())
+}
+
+impl KafkaBus {
+    pub fn new(config: &KafkaConfig) -> Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", &config.client_id)
+            .set("linger.ms", config.linger_ms.to_string())
+            .set("batch.num.messages", config.batch_size.max(1).to_string())
+            .set("compression.type", &config.compression)
+            .set("message.timeout.ms", config.message_timeout_ms.to_string())
+            .create()?;
+        Ok(Self {
+            producer,
+            topics: config.topics.clone(),
+            default_topic: config.default_topic.clone(),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl MessageBus for KafkaBus {
+    fn backend(&self) -> &'static str {
+        BACKEND_KAFKA
+    }
+
+    async fn publish(&self, envelope: &EventEnvelope) -> Result<()> {
+        let routing_key = envelope.event.routing_key();
+        let Some(topic) = topic_for(&self.topics, self.default_topic.as_deref(), routing_key)
+        else {
+            return Ok(());
+        };
+        let key = match envelope.event.device_id() {
+            Some(device_id) => device_id.to_string(),
+            None => envelope.id.clone(),
+        };
+        let payload = envelope.encode();
+        let record = FutureRecord::to(topic).key(&key).payload(&payload);
+        // 只等待进入本地队列，投递结果在后台记录
+        let delivery = self.producer.send_result(record).map_err(|(e, _)| e)?;
+        let topic = topic.to_string();
+        tokio::spawn(async move {
+            match delivery.await {
+                Ok(Ok(_)) => {}
+                Ok(Err((e, _))) => warn!("Failed to deliver {} event to Kafka: {}", topic, e),
+                Err(_) => warn!("Kafka delivery of {} event was cancelled", topic),
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_for() {
+        let topics = HashMap::from([
+            ("alarm.triggered".to_string(), "plant01.alarms".to_string()),
+            ("command.issued".to_string(), String::new()),
+        ]);
+        assert_eq!(topic_for(&topics, None, "alarm.triggered"), Some("plant01.alarms"));
+        assert_eq!(topic_for(&topics, None, "device.online"), None);
+        assert_eq!(
+            topic_for(&topics, Some("plant01.events"), "device.online"),
+            Some("plant01.events")
+        );
+        // 映射为空字符串表示不发送
+        assert_eq!(topic_for(&topics, Some("plant01.events"), "command.issued"), None);
+    }
+}
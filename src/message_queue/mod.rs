@@ -1,6 +1,11 @@
 //! 消息队列模块
 //!
-//! 提供各种消息队列的集成支持，包括 RabbitMQ、Apache Kafka 等
+//! 提供各种消息队列的集成支持，包括 RabbitMQ、Apache Kafka 等。Kafka 需要开启 `kafka` 特性编译。
 
+pub mod bus;
 pub mod consumer_example;
+pub mod events;
+#[cfg(feature = "kafka")]
+pub mod kafka;
 pub mod rabbitmq;
+pub mod schema;
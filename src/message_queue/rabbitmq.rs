@@ -1,39 +1,177 @@
+use crate::message_queue::events::EventEnvelope;
 use anyhow::Result;
 use lapin::{
+    message::Delivery,
     options::{
-        BasicConsumeOptions, BasicPublishOptions, ExchangeDeclareOptions, QueueBindOptions,
-        QueueDeclareOptions,
+        BasicAckOptions, BasicConsumeOptions, BasicGetOptions, BasicNackOptions,
+        BasicPublishOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
     },
     publisher_confirm::Confirmation,
-    types::FieldTable,
-    BasicProperties, Channel, Connection, ConnectionProperties, Consumer,
+    types::{AMQPValue, FieldTable},
+    BasicProperties, Channel, Connection, ConnectionProperties, Consumer, ExchangeKind,
 };
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{debug, error, info};
+use utoipa::ToSchema;
 
-/// 消息内容结构
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Message {
-    pub topic: String,
+/// 重新投递次数的消息头
+const RETRY_HEADER: &str = "x-retry-count";
+
+/// 队列对应的死信队列名称
+pub fn dead_letter_queue(queue: &str) -> String {
+    format!("{}.dlq", queue)
+}
+
+/// 消息已重新投递的次数
+pub fn retry_count(properties: &BasicProperties) -> u32 {
+    let value = properties
+        .headers()
+        .as_ref()
+        .and_then(|headers| headers.inner().get(RETRY_HEADER));
+    match value {
+        Some(AMQPValue::LongLongInt(n)) => (*n).max(0) as u32,
+        Some(AMQPValue::LongInt(n)) => (*n).max(0) as u32,
+        Some(AMQPValue::LongUInt(n)) => *n,
+        _ => 0,
+    }
+}
+
+/// 复制消息属性并设置重新投递次数
+fn with_retry_count(properties: &BasicProperties, retries: u32) -> BasicProperties {
+    let mut headers = properties.headers().clone().unwrap_or_default();
+    headers.insert(RETRY_HEADER.into(), AMQPValue::LongLongInt(retries as i64));
+    properties.clone().with_headers(headers)
+}
+
+/// 死信原因（`x-death` 中最近一条的 reason，如 rejected、expired）
+fn death_reason(properties: &BasicProperties) -> Option<String> {
+    let headers = properties.headers().as_ref()?;
+    let Some(AMQPValue::FieldArray(deaths)) = headers.inner().get("x-death") else {
+        return None;
+    };
+    match deaths.as_slice().first()? {
+        AMQPValue::FieldTable(death) => match death.inner().get("reason")? {
+            AMQPValue::LongString(reason) => {
+                Some(String::from_utf8_lossy(reason.as_bytes()).into_owned())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 死信队列中的消息
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct DeadLetter {
+    /// 消息内容，非 UTF-8 字节按替换字符显示
     pub payload: String,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub routing_key: String,
+    pub retries: u32,
+    pub reason: Option<String>,
+}
+
+impl DeadLetter {
+    fn from_delivery(delivery: &Delivery) -> Self {
+        Self {
+            payload: String::from_utf8_lossy(&delivery.data).into_owned(),
+            routing_key: delivery.routing_key.to_string(),
+            retries: retry_count(&delivery.properties),
+            reason: death_reason(&delivery.properties),
+        }
+    }
 }
 
 /// RabbitMQ 管理器
 #[derive(Clone)]
 pub struct RabbitMQManager {
     connection: Arc<Mutex<Option<Connection>>>,
+    /// 消费者使用的 channel，供故障注入强制关闭
+    consumer_channels: Arc<Mutex<Vec<Channel>>>,
     uri: String,
+    /// 死信交换机，设置后声明的队列都把被拒绝的消息转入 `{queue}.dlq`
+    dead_letter_exchange: Option<String>,
+}
+
+impl std::fmt::Debug for RabbitMQManager {
+    // uri 含账号密码，不输出
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RabbitMQManager")
+            .field("dead_letter_exchange", &self.dead_letter_exchange)
+            .finish_non_exhaustive()
+    }
 }
 
 impl RabbitMQManager {
     pub fn new(uri: &str) -> Self {
         Self {
             connection: Arc::new(Mutex::new(None)),
+            consumer_channels: Arc::new(Mutex::new(Vec::new())),
             uri: uri.to_string(),
+            dead_letter_exchange: None,
+        }
+    }
+
+    /// 启用死信交换机
+    pub fn with_dead_letter_exchange(mut self, exchange: &str) -> Self {
+        self.dead_letter_exchange = Some(exchange.to_string());
+        self
+    }
+
+    /// 队列参数：启用死信时指定死信交换机，路由键为队列名
+    fn queue_arguments(&self, queue_name: &str) -> FieldTable {
+        let mut arguments = FieldTable::default();
+        if let Some(exchange) = &self.dead_letter_exchange {
+            arguments.insert(
+                "x-dead-letter-exchange".into(),
+                AMQPValue::LongString(exchange.as_str().into()),
+            );
+            arguments.insert(
+                "x-dead-letter-routing-key".into(),
+                AMQPValue::LongString(queue_name.into()),
+            );
+        }
+        arguments
+    }
+
+    /// 声明队列；启用死信时先声明死信交换机和 `{queue}.dlq` 并绑定
+    async fn declare_queue(&self, channel: &Channel, queue_name: &str) -> Result<()> {
+        if let Some(exchange) = &self.dead_letter_exchange {
+            let dlq = dead_letter_queue(queue_name);
+            channel
+                .exchange_declare(
+                    exchange.as_str().into(),
+                    ExchangeKind::Direct,
+                    ExchangeDeclareOptions { durable: true, ..Default::default() },
+                    FieldTable::default(),
+                )
+                .await?;
+            channel
+                .queue_declare(
+                    dlq.as_str().into(),
+                    QueueDeclareOptions { durable: true, ..Default::default() },
+                    FieldTable::default(),
+                )
+                .await?;
+            channel
+                .queue_bind(
+                    dlq.as_str().into(),
+                    exchange.as_str().into(),
+                    queue_name.into(),
+                    QueueBindOptions::default(),
+                    FieldTable::default(),
+                )
+                .await?;
         }
+        channel
+            .queue_declare(
+                queue_name.into(), // 转换为 ShortString
+                QueueDeclareOptions::default(),
+                self.queue_arguments(queue_name),
+            )
+            .await?;
+        Ok(())
     }
 
     /// 建立连接
@@ -66,13 +204,13 @@ impl RabbitMQManager {
         }
     }
 
-    /// 发布消息
-    pub async fn publish_message(
+    /// 发布事件，路由键由事件类型决定
+    pub async fn publish_event(
         &self,
         exchange: &str,
-        routing_key: &str,
-        message: &Message,
+        envelope: &EventEnvelope,
     ) -> Result<Confirmation> {
+        let routing_key = envelope.event.routing_key();
         let channel = self.get_channel().await?;
 
         // 先声明 exchange（如果需要）
@@ -85,20 +223,24 @@ impl RabbitMQManager {
             )
             .await?;
 
-        let payload = serde_json::to_vec(message)?;
+        let payload = envelope.encode();
+        let properties = BasicProperties::default()
+            .with_content_type("application/json".into())
+            .with_message_id(envelope.id.as_str().into())
+            .with_kind(routing_key.into());
         let confirm = channel
             .basic_publish(
                 exchange.into(), // 转换为 ShortString
                 routing_key.into(), // 转换为 ShortString
                 BasicPublishOptions::default(),
                 &payload,
-                BasicProperties::default(),
+                properties,
             )
             .await?
             .await?; // 这里需 await 两次：publish + confirmation
 
-        info!(
-            "Published message to exchange '{}', routing_key '{}'",
+        debug!(
+            "Published event to exchange '{}', routing_key '{}'",
             exchange, routing_key
         );
         Ok(confirm)
@@ -121,13 +263,7 @@ impl RabbitMQManager {
                 FieldTable::default(),
             )
             .await?;
-        channel
-            .queue_declare(
-                queue_name.into(), // 转换为 ShortString
-                QueueDeclareOptions::default(),
-                FieldTable::default(),
-            )
-            .await?;
+        self.declare_queue(&channel, queue_name).await?;
         channel
             .queue_bind(
                 queue_name.into(), // 转换为 ShortString
@@ -150,14 +286,7 @@ impl RabbitMQManager {
     /// 返回一个 Consumer。调用者应该 spawn tokio 任务负责 .next() + ack/nack
     pub async fn subscribe(&self, queue_name: &str) -> Result<Consumer> {
         let channel = self.get_channel().await?;
-
-        channel
-            .queue_declare(
-                queue_name.into(), // 转换为 ShortString
-                QueueDeclareOptions::default(),
-                FieldTable::default(),
-            )
-            .await?;
+        self.declare_queue(&channel, queue_name).await?;
 
         let consumer = channel
             .basic_consume(
@@ -169,6 +298,118 @@ impl RabbitMQManager {
             .await?;
 
         info!("Subscribed to queue '{}'", queue_name);
+        let mut channels = self.consumer_channels.lock().await;
+        channels.retain(|ch| ch.status().connected());
+        channels.push(channel);
         Ok(consumer)
     }
+
+    /// 强制关闭全部消费者 channel，模拟 broker 端断开，返回关闭的数量
+    pub async fn kill_consumer_channels(&self) -> usize {
+        let channels: Vec<Channel> = self.consumer_channels.lock().await.drain(..).collect();
+        let mut killed = 0;
+        for channel in channels {
+            match channel.close(320, "fault injection".into()).await {
+                Ok(()) => killed += 1,
+                Err(e) => error!("Failed to close RabbitMQ channel {}: {}", channel.id(), e),
+            }
+        }
+        killed
+    }
+
+    /// 把处理失败的消息以递增的重新投递次数放回队列末尾，再确认原消息
+    pub async fn redeliver(&self, queue_name: &str, delivery: &Delivery) -> Result<u32> {
+        let retries = retry_count(&delivery.properties) + 1;
+        let channel = self.get_channel().await?;
+        channel
+            .basic_publish(
+                "".into(), // 默认交换机按队列名路由
+                queue_name.into(),
+                BasicPublishOptions::default(),
+                &delivery.data,
+                with_retry_count(&delivery.properties, retries),
+            )
+            .await?
+            .await?;
+        delivery.ack(BasicAckOptions::default()).await?;
+        Ok(retries)
+    }
+
+    /// 查看死信队列中最早的若干条消息，查看后消息仍留在死信队列
+    pub async fn peek_dead_letters(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> Result<Vec<DeadLetter>> {
+        let dlq = dead_letter_queue(queue_name);
+        let channel = self.get_channel().await?;
+        let mut fetched = Vec::new();
+        // 先全部取出再放回，避免放回的消息被再次取到
+        while fetched.len() < limit {
+            match channel.basic_get(dlq.as_str().into(), BasicGetOptions::default()).await? {
+                Some(message) => fetched.push(message.delivery),
+                None => break,
+            }
+        }
+        let letters = fetched.iter().map(DeadLetter::from_delivery).collect();
+        for delivery in &fetched {
+            delivery.nack(BasicNackOptions { requeue: true, ..Default::default() }).await?;
+        }
+        Ok(letters)
+    }
+
+    /// 把死信队列中的消息重新投递到原队列，重新投递次数清零，返回投递的条数
+    pub async fn replay_dead_letters(&self, queue_name: &str, limit: usize) -> Result<usize> {
+        let dlq = dead_letter_queue(queue_name);
+        let channel = self.get_channel().await?;
+        let mut replayed = 0;
+        while replayed < limit {
+            let Some(message) =
+                channel.basic_get(dlq.as_str().into(), BasicGetOptions::default()).await?
+            else {
+                break;
+            };
+            let delivery = message.delivery;
+            let properties = with_retry_count(&delivery.properties, 0);
+            // 去掉死信记录，再次失败时重新计数
+            let mut headers = FieldTable::default();
+            for (key, value) in properties.headers().iter().flat_map(|h| h.inner()) {
+                if key.as_str() != "x-death" {
+                    headers.insert(key.clone(), value.clone());
+                }
+            }
+            let properties = properties.with_headers(headers);
+            channel
+                .basic_publish(
+                    "".into(),
+                    queue_name.into(),
+                    BasicPublishOptions::default(),
+                    &delivery.data,
+                    properties,
+                )
+                .await?
+                .await?;
+            delivery.ack(BasicAckOptions::default()).await?;
+            replayed += 1;
+        }
+        if replayed > 0 {
+            info!("Replayed {} dead-lettered messages to queue '{}'", replayed, queue_name);
+        }
+        Ok(replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_count() {
+        let properties = BasicProperties::default();
+        assert_eq!(retry_count(&properties), 0);
+        let properties = with_retry_count(&properties, 2);
+        assert_eq!(retry_count(&properties), 2);
+        assert_eq!(retry_count(&with_retry_count(&properties, 3)), 3);
+        assert_eq!(dead_letter_queue("boiler_queue"), "boiler_queue.dlq");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! 对外事件的 JSON Schema
+//!
+//! 由 [`EventEnvelope`] 等类型的 OpenAPI 定义生成（OpenAPI 3.1 的 schema 即 JSON Schema
+//! 2020-12），与接口文档同源，不会与实际编码的事件脱节。`fingerprint` 是 schema 内容的
+//! 摘要，下游可据此发现格式变化；[`CHANGELOG`] 记录每个版本的改动及是否兼容。
+
+use crate::message_queue::events::{Event, EventEnvelope, SCHEMA_VERSION};
+use crate::models::device_command::Model as DeviceCommand;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use utoipa::{PartialSchema, ToSchema};
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// 事件格式的一次版本变更
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SchemaChange {
+    pub version: u32,
+    /// 发布日期 YYYY-MM-DD
+    pub date: &'static str,
+    /// 是否不兼容旧的消费者
+    pub breaking: bool,
+    pub changes: Vec<&'static str>,
+}
+
+/// 事件格式变更记录，新版本追加在末尾
+pub const CHANGELOG: &[(u32, &str, bool, &[&str])] = &[(
+    1,
+    "2026-09-01",
+    false,
+    &[
+        "事件统一包在 version / id / occurred_at / type / data 信封中",
+        "事件类型：measurement_recorded、alarm_triggered、device_online、device_offline、command_issued",
+    ],
+)];
+
+pub fn changelog() -> Vec<SchemaChange> {
+    CHANGELOG
+        .iter()
+        .map(|(version, date, breaking, changes)| SchemaChange {
+            version: *version,
+            date: *date,
+            breaking: *breaking,
+            changes: changes.to_vec(),
+        })
+        .collect()
+}
+
+/// 事件的输出通道
+#[derive(Debug, Clone, Serialize)]
+pub struct EventChannel {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 消息体对应的定义，位于 `$defs` 中
+    pub schema: &'static str,
+}
+
+const CHANNELS: [EventChannel; 3] = [
+    EventChannel {
+        name: "rabbitmq",
+        description: "发布到事件交换机，路由键见 routing_keys",
+        schema: "EventEnvelope",
+    },
+    EventChannel {
+        name: "kafka",
+        description: "启用 Kafka 时抄送的同一份事件，消息键为设备 ID",
+        schema: "EventEnvelope",
+    },
+    EventChannel {
+        name: "sse:/devices/{id}/commands/{command_id}/events",
+        description: "设备命令状态推送，每个 status 事件为一条命令记录",
+        schema: "DeviceCommand",
+    },
+];
+
+/// 各事件类型的路由键
+fn routing_keys() -> Map<String, Value> {
+    let now = chrono::Utc::now();
+    let samples = [
+        Event::MeasurementRecorded { device_id: 0, metric: String::new(), value: 0.0, timestamp: now },
+        Event::AlarmTriggered {
+            alarm_id: 0,
+            rule_name: String::new(),
+            device_id: None,
+            severity: String::new(),
+            value: 0.0,
+            shelved: false,
+            trigger_time: now,
+        },
+        Event::DeviceOnline { device_id: 0, source: String::new(), timestamp: now },
+        Event::DeviceOffline { device_id: 0, source: String::new(), timestamp: now },
+        Event::CommandIssued {
+            command_id: 0,
+            correlation_id: String::new(),
+            device_id: 0,
+            command: String::new(),
+            issued_at: now,
+        },
+    ];
+    samples
+        .iter()
+        .map(|event| {
+            let kind = serde_json::to_value(event).ok().and_then(|v| v["type"].as_str().map(str::to_string));
+            (kind.unwrap_or_default(), Value::from(event.routing_key()))
+        })
+        .collect()
+}
+
+/// 组件引用改为 `$defs` 引用
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get_mut("$ref") {
+                if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+                    *reference = format!("#/$defs/{}", name);
+                }
+            }
+            map.values_mut().for_each(rewrite_refs);
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+fn definitions() -> Map<String, Value> {
+    let mut defs = Map::new();
+    defs.insert("EventEnvelope".to_string(), json!(EventEnvelope::schema()));
+    defs.insert(Event::name().into_owned(), json!(Event::schema()));
+    defs.insert("DeviceCommand".to_string(), json!(DeviceCommand::schema()));
+    let mut defs = Value::Object(defs);
+    rewrite_refs(&mut defs);
+    match defs {
+        Value::Object(defs) => defs,
+        _ => unreachable!(),
+    }
+}
+
+/// 全部对外事件的 JSON Schema 文档
+pub fn document() -> Value {
+    let defs = definitions();
+    let digest = Sha256::digest(Value::Object(defs.clone()).to_string().as_bytes());
+    let fingerprint: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "$id": format!("urn:guolu:events:v{}", SCHEMA_VERSION),
+        "title": "Outbound events",
+        "version": SCHEMA_VERSION,
+        "api_version": env!("CARGO_PKG_VERSION"),
+        "fingerprint": fingerprint,
+        "$ref": "#/$defs/EventEnvelope",
+        "channels": CHANNELS,
+        "routing_keys": routing_keys(),
+        "$defs": defs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let doc = document();
+        assert_eq!(doc["version"], SCHEMA_VERSION);
+        assert!(doc["$defs"]["EventEnvelope"].is_object());
+        assert!(doc["$defs"]["Event"].is_object());
+        assert_eq!(doc["routing_keys"]["alarm_triggered"], "alarm.triggered");
+        assert_eq!(doc["routing_keys"].as_object().unwrap().len(), 5);
+        // 不残留 OpenAPI 组件引用
+        assert!(!doc.to_string().contains("#/components/schemas/"));
+        // 摘要只取决于 schema 内容
+        assert_eq!(doc["fingerprint"], document()["fingerprint"]);
+
+        let latest = changelog().last().map(|c| c.version);
+        assert_eq!(latest, Some(SCHEMA_VERSION));
+    }
+}
@@ -0,0 +1,100 @@
+//! 机器客户端（边缘网关）的 `X-Api-Key` 认证
+
+use crate::app_state::AppState;
+use crate::models::api_key::Model as ApiKey;
+use crate::services::api_key::{self as api_key_service, SCOPE_ADMIN, SCOPE_CONTROL, SCOPE_INGEST};
+use crate::utils::error::AppError;
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{request::Parts, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// 已通过认证的 API Key，写入请求扩展供后续处理使用
+#[derive(Debug, Clone)]
+pub struct ApiKeyIdentity {
+    pub id: i32,
+    pub name: String,
+    pub organization_id: Option<i32>,
+}
+
+/// 数据上报接口的认证，要求 Key 具备 ingest 权限
+pub async fn require_ingest_key(
+    State(state): State<Arc<AppState>>,
+    mut request: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !state.settings.api_keys.require_for_ingest {
+        return Ok(next.run(request).await);
+    }
+
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AppError::InvalidCredentials)?
+        .to_string();
+
+    let api_key =
+        api_key_service::authenticate(state.db.get_connection(), &key, SCOPE_INGEST).await?;
+
+    request.extensions_mut().insert(ApiKeyIdentity {
+        id: api_key.id,
+        name: api_key.name,
+        organization_id: api_key.organization_id,
+    });
+
+    Ok(next.run(request).await)
+}
+
+/// 读取请求头中的 Key 并校验权限范围
+async fn authenticate_caller(
+    parts: &Parts,
+    state: &Arc<AppState>,
+    scope: &str,
+) -> Result<ApiKey, AppError> {
+    let key = parts
+        .headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AppError::InvalidCredentials)?;
+    api_key_service::authenticate(state.db.get_connection(), key, scope).await
+}
+
+/// 管理类接口的调用方，要求请求携带具备 admin 权限的 Key
+///
+/// 不受 `require_for_ingest` 和多租户开关影响；首个 Key 通过 `guolu create-admin` 签发。
+#[derive(Debug, Clone)]
+pub struct AdminKey(pub ApiKey);
+
+impl FromRequestParts<Arc<AppState>> for AdminKey {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate_caller(parts, state, SCOPE_ADMIN).await.map(AdminKey)
+    }
+}
+
+/// 人工下发现场输出的调用方，要求请求携带具备 control 权限的 Key
+///
+/// 操作记录中记录 Key 而不是 `X-Operator`，后者可由调用方任意填写。
+#[derive(Debug, Clone)]
+pub struct ControlKey(pub ApiKey);
+
+impl FromRequestParts<Arc<AppState>> for ControlKey {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate_caller(parts, state, SCOPE_CONTROL).await.map(ControlKey)
+    }
+}
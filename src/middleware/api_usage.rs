@@ -0,0 +1,54 @@
+//! 按客户端记录接口调用，统计见 [`crate::services::api_usage`]
+//!
+//! 作为路由层中间件执行以取得路由模板，被限流或只读模式拦截的请求不计入。
+
+use crate::app_state::AppState;
+use crate::middleware::api_key::API_KEY_HEADER;
+use crate::services::api_key::key_prefix;
+use axum::{
+    extract::{ConnectInfo, MatchedPath, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 识别客户端：带 API Key 时取明文前缀，否则取 IP
+fn client<B>(request: &Request<B>) -> String {
+    if let Some(key) = request.headers().get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return format!("key:{}", key_prefix(key));
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+pub async fn api_usage_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    if !state.settings.api_usage.enabled {
+        return next.run(request).await;
+    }
+    let Some(path) = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string())
+    else {
+        return next.run(request).await;
+    };
+    let client = client(&request);
+    let method = request.method().clone();
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    state.api_usage.record(
+        client,
+        method.as_str(),
+        &path,
+        response.status().as_u16(),
+        started.elapsed().as_millis() as u64,
+    );
+    response
+}
@@ -1,9 +1,36 @@
+//! 请求日志与关联 ID
+//!
+//! 每个请求使用客户端传入的 `X-Request-Id`，没有时生成一个。该 ID 记录在覆盖处理函数与数据库调用的
+//! tracing span 中，并通过响应头和错误响应体返回，便于把失败的接口调用与日志对应起来。
+
+use crate::utils::crypto;
 use axum::{
-    http::Request,
+    http::{HeaderValue, Request},
     middleware::Next,
     response::Response,
 };
-use tracing::info;
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 客户端传入的 ID 超过该长度或含非法字符时重新生成
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 当前请求的 ID，不在请求处理过程中时为 None
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
 
 pub async fn logging_middleware(
     request: Request<axum::body::Body>,
@@ -11,12 +38,46 @@ pub async fn logging_middleware(
 ) -> Response {
     let method = request.method().clone();
     let uri = request.uri().clone();
-    
-    info!("Processing request: {} {}", method, uri);
-    
-    let response = next.run(request).await;
-    
-    info!("Response status: {}", response.status());
-    
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| crypto::random_token(16));
+
+    let span = info_span!("request", request_id = %request_id, method = %method, path = %uri.path());
+    let started = Instant::now();
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), async move {
+            info!("Processing request: {} {}", method, uri);
+            let response = next.run(request).await;
+            info!(
+                "Response status: {} ({} ms)",
+                response.status(),
+                started.elapsed().as_millis()
+            );
+            response
+        }
+        .instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
     response
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_request_id() {
+        assert!(valid_request_id("3f2a9c1e-7b4d"));
+        assert!(!valid_request_id(""));
+        assert!(!valid_request_id("bad id"));
+        assert!(!valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
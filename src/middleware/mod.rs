@@ -1 +1,7 @@
-pub mod logging;
\ No newline at end of file
+pub mod api_key;
+pub mod api_usage;
+pub mod logging;
+pub mod network_policy;
+pub mod rate_limit;
+pub mod read_only;
+pub mod security;
\ No newline at end of file
@@ -0,0 +1,204 @@
+//! 网络区域访问策略
+//!
+//! 例如数据上报接口只允许 OT 网段访问、管理接口只允许管理网段访问。
+//! 规则按配置顺序匹配，第一条命中的规则决定允许的区域。
+
+use crate::config::security::NetworkPolicyConfig;
+use crate::utils::error::AppError;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tracing::warn;
+
+/// CIDR 网段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// 解析 `10.0.0.0/8`、`fd00::/8` 或单个地址
+    pub fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr.trim().parse::<IpAddr>().ok()?, Some(prefix.trim().parse::<u8>().ok()?)),
+            None => (s.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return None;
+        }
+        Some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 映射的 IPv6 地址按 IPv4 处理
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    path_prefix: String,
+    methods: Vec<Method>,
+    networks: Vec<Cidr>,
+    zones: Vec<String>,
+}
+
+/// 解析后的网络策略
+#[derive(Debug)]
+pub struct NetworkPolicy {
+    enabled: bool,
+    rules: Vec<Rule>,
+}
+
+impl NetworkPolicy {
+    pub fn from_config(config: &NetworkPolicyConfig) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let networks = rule
+                    .zones
+                    .iter()
+                    .flat_map(|zone| match config.zones.get(zone) {
+                        Some(cidrs) => cidrs.as_slice(),
+                        None => {
+                            warn!("Network policy references unknown zone: {}", zone);
+                            &[]
+                        }
+                    })
+                    .filter_map(|cidr| {
+                        let parsed = Cidr::parse(cidr);
+                        if parsed.is_none() {
+                            warn!("Ignoring invalid CIDR in network policy: {}", cidr);
+                        }
+                        parsed
+                    })
+                    .collect();
+
+                Rule {
+                    path_prefix: rule.path_prefix.clone(),
+                    methods: rule.methods.iter().filter_map(|m| m.parse().ok()).collect(),
+                    networks,
+                    zones: rule.zones.clone(),
+                }
+            })
+            .collect();
+
+        Self {
+            enabled: config.enabled,
+            rules,
+        }
+    }
+
+    fn matching_rule(&self, method: &Method, path: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| {
+            path.starts_with(&rule.path_prefix)
+                && (rule.methods.is_empty() || rule.methods.contains(method))
+        })
+    }
+
+    /// 判断客户端是否允许访问；返回拒绝时命中的规则区域
+    pub fn check(&self, method: &Method, path: &str, ip: Option<IpAddr>) -> Result<(), Vec<String>> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let Some(rule) = self.matching_rule(method, path) else {
+            return Ok(());
+        };
+
+        match ip {
+            Some(ip) if rule.networks.iter().any(|net| net.contains(ip)) => Ok(()),
+            _ => Err(rule.zones.clone()),
+        }
+    }
+}
+
+pub async fn network_policy_middleware(
+    State(policy): State<Arc<NetworkPolicy>>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Err(zones) = policy.check(request.method(), request.uri().path(), ip) {
+        warn!(
+            "Network policy denied {} {} from {:?} (allowed zones: {:?})",
+            request.method(),
+            request.uri().path(),
+            ip,
+            zones
+        );
+        return AppError::Forbidden.into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::security::NetworkRuleConfig;
+
+    #[test]
+    fn test_cidr_contains() {
+        let net = Cidr::parse("10.20.0.0/16").unwrap();
+        assert!(net.contains("10.20.3.4".parse().unwrap()));
+        assert!(!net.contains("10.21.0.1".parse().unwrap()));
+        assert!(net.contains("::ffff:10.20.0.9".parse().unwrap()));
+
+        let any = Cidr::parse("0.0.0.0/0").unwrap();
+        assert!(any.contains("8.8.8.8".parse().unwrap()));
+
+        assert!(Cidr::parse("10.0.0.0/33").is_none());
+        assert!(Cidr::parse("fd00::/8").unwrap().contains("fd12::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let mut config = NetworkPolicyConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        config.zones.insert("ot".to_string(), vec!["10.20.0.0/16".to_string()]);
+        config.rules.push(NetworkRuleConfig {
+            path_prefix: "/measurements".to_string(),
+            methods: vec!["POST".to_string()],
+            zones: vec!["ot".to_string()],
+        });
+        let policy = NetworkPolicy::from_config(&config);
+
+        let ot = Some("10.20.1.1".parse().unwrap());
+        let office = Some("192.168.1.10".parse().unwrap());
+        assert!(policy.check(&Method::POST, "/measurements", ot).is_ok());
+        assert!(policy.check(&Method::POST, "/measurements", office).is_err());
+        assert!(policy.check(&Method::GET, "/measurements", office).is_ok());
+        assert!(policy.check(&Method::POST, "/devices", office).is_ok());
+    }
+}
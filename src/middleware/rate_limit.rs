@@ -0,0 +1,240 @@
+//! 按客户端限流（令牌桶）
+//!
+//! 带有效 API Key 的请求按 Key 的 ID 计数，其余按客户端 IP 计数。请求头里的 Key 须在库中校验，
+//! 否则随意更换请求头就能绕过按 IP 的限额；未缓存的 Key 先扣 IP 的令牌再查库，
+//! 避免用大量伪造的 Key 把限流变成对数据库的放大攻击。
+//! 响应附带 `RateLimit-Limit` / `RateLimit-Remaining` / `RateLimit-Reset` 头，
+//! 超限时返回 429 及 `Retry-After`。
+
+use crate::config::rate_limit::RateLimitConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::services::api_key as api_key_service;
+use crate::utils::error::AppError;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 超过该数量的桶时清理长时间空闲的桶
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+const IDLE_EVICT_AFTER: Duration = Duration::from_secs(600);
+/// 已校验 Key 的缓存有效期，过期后重新查库，吊销的 Key 最迟在此之后按 IP 计数
+const KEY_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 一次判定的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// 距离下一个可用令牌（被拒绝时）或桶满（放行时）的秒数
+    pub reset_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+    /// Key 摘要 -> (Key ID, 校验时间)
+    keys: Arc<Mutex<HashMap<String, (i32, Instant)>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 为指定客户端消耗一个令牌
+    pub fn check(&self, key: &str, now: Instant) -> Decision {
+        let capacity = self.config.burst.max(1) as f64;
+        let rate = self.config.per_second.max(f64::EPSILON);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, b| now.duration_since(b.updated) < IDLE_EVICT_AFTER);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        let reset_secs = if allowed {
+            ((capacity - bucket.tokens) / rate).ceil() as u64
+        } else {
+            ((1.0 - bucket.tokens) / rate).ceil() as u64
+        };
+
+        Decision {
+            allowed,
+            limit: capacity as u32,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs,
+        }
+    }
+
+    /// 识别请求的客户端并消耗令牌，无效的 Key 按 IP 计数
+    ///
+    /// 缓存中没有的 Key 先扣 IP 的令牌，IP 已超限时直接拒绝，不再查库。
+    async fn decide(
+        &self,
+        db: &DbManager,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+        now: Instant,
+    ) -> Decision {
+        let ip_key = match peer {
+            Some(addr) => format!("ip:{}", addr.ip()),
+            None => "ip:unknown".to_string(),
+        };
+        let Some(key) = headers
+            .get(self.config.key_header.as_str())
+            .and_then(|v| v.to_str().ok())
+        else {
+            return self.check(&ip_key, now);
+        };
+
+        let hash = api_key_service::hash_key(key);
+        if let Some(id) = self.cached_key(&hash, now) {
+            return self.check(&format!("key:{}", id), now);
+        }
+
+        let by_ip = self.check(&ip_key, now);
+        if !by_ip.allowed {
+            return by_ip;
+        }
+        match api_key_service::identify(db.get_connection(), key).await {
+            Ok(api_key) => {
+                self.remember_key(hash, api_key.id, now);
+                self.check(&format!("key:{}", api_key.id), now)
+            }
+            Err(_) => by_ip,
+        }
+    }
+
+    fn cached_key(&self, hash: &str, now: Instant) -> Option<i32> {
+        let keys = self.keys.lock().unwrap();
+        keys.get(hash)
+            .filter(|(_, verified)| now.duration_since(*verified) < KEY_CACHE_TTL)
+            .map(|(id, _)| *id)
+    }
+
+    fn remember_key(&self, hash: String, id: i32, now: Instant) {
+        let mut keys = self.keys.lock().unwrap();
+        if keys.len() >= MAX_TRACKED_CLIENTS {
+            keys.retain(|_, (_, verified)| now.duration_since(*verified) < KEY_CACHE_TTL);
+            if keys.len() >= MAX_TRACKED_CLIENTS {
+                keys.clear();
+            }
+        }
+        keys.insert(hash, (id, now));
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset_secs));
+}
+
+pub async fn rate_limit_middleware(
+    State((limiter, db)): State<(RateLimiter, DbManager)>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    if !limiter.config.enabled {
+        return next.run(request).await;
+    }
+
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let decision = limiter
+        .decide(&db, request.headers(), peer, Instant::now())
+        .await;
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        let mut response = AppError::TooManyRequests.into_response();
+        response
+            .headers_mut()
+            .insert("retry-after", HeaderValue::from(decision.reset_secs));
+        response
+    };
+
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(burst: u32, per_second: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            burst,
+            per_second,
+            key_header: "x-api-key".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_burst_then_reject() {
+        let limiter = limiter(3, 1.0);
+        let now = Instant::now();
+
+        for expected_remaining in [2, 1, 0] {
+            let decision = limiter.check("ip:1.2.3.4", now);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+
+        let decision = limiter.check("ip:1.2.3.4", now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.reset_secs, 1);
+
+        // 其他客户端不受影响
+        assert!(limiter.check("ip:5.6.7.8", now).allowed);
+    }
+
+    #[test]
+    fn test_refill() {
+        let limiter = limiter(2, 2.0);
+        let now = Instant::now();
+
+        assert!(limiter.check("key:a", now).allowed);
+        assert!(limiter.check("key:a", now).allowed);
+        assert!(!limiter.check("key:a", now).allowed);
+
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check("key:a", later).allowed);
+    }
+}
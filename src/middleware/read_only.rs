@@ -0,0 +1,120 @@
+//! 只读模式下的请求拦截
+//!
+//! 修改类请求直接返回 503；数据上报请求在通过 API Key 认证、确认设备属于调用方组织后
+//! 写入暂存并返回 202。
+
+use crate::app_state::AppState;
+use crate::services::read_only::{BufferedMeasurement, ReadOnlyMode, INGEST_PATHS};
+use crate::utils::error::AppError;
+use crate::utils::tenant::Tenant;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRequestParts, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+/// 只读期间仍允许修改的接口（切换只读模式本身）
+pub const READ_ONLY_SWITCH_PATH: &str = "/system/read-only";
+
+/// broker 认证钩子，只做校验不修改数据
+pub const MQTT_AUTH_PATH: &str = "/mqtt/auth";
+
+/// 暂存上报时允许的最大请求体
+const MAX_INGEST_BODY: usize = 64 * 1024;
+
+/// 各上报接口请求体的公共字段
+#[derive(Debug, Deserialize)]
+struct IngestBody {
+    metric_type: Option<String>,
+    timestamp: DateTime<Utc>,
+    value: f64,
+    device_id: Option<i32>,
+    unit: Option<String>,
+}
+
+fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn ingest_metric(path: &str) -> Option<Option<&'static str>> {
+    INGEST_PATHS
+        .iter()
+        .find(|(ingest_path, _)| *ingest_path == path)
+        .map(|(_, metric)| *metric)
+}
+
+/// 全局拦截：只读期间拒绝修改类请求，上报请求交给 [`buffer_ingest`] 处理
+pub async fn read_only_middleware(
+    State(mode): State<ReadOnlyMode>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !mode.is_active() || !is_mutating(request.method()) {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path();
+    let is_ingest = *request.method() == Method::POST && ingest_metric(path).is_some();
+    if is_ingest || path == READ_ONLY_SWITCH_PATH || path == MQTT_AUTH_PATH {
+        return next.run(request).await;
+    }
+
+    AppError::ServiceUnavailable(mode.message().into()).into_response()
+}
+
+/// 上报接口：只读期间把数据写入暂存，退出只读后补写入库
+///
+/// 与上报接口一样按租户校验设备，不能借暂存写入其他组织的设备。
+pub async fn buffer_ingest(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let mode = &state.read_only;
+    if !mode.is_active() {
+        return Ok(next.run(request).await);
+    }
+    let Some(metric) = ingest_metric(request.uri().path()) else {
+        return Ok(next.run(request).await);
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let tenant = Tenant::from_request_parts(&mut parts, &state).await?;
+    let bytes = to_bytes(body, MAX_INGEST_BODY)
+        .await
+        .map_err(|_| AppError::InvalidInput("请求体过大".into()))?;
+    let body: IngestBody = serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::InvalidInput(format!("请求体格式错误: {}", e).into()))?;
+    tenant.check_device(state.db.get_connection(), body.device_id).await?;
+
+    let metric_type = match (metric, body.metric_type) {
+        (Some(metric), _) => metric.to_string(),
+        (None, Some(metric_type)) => metric_type,
+        (None, None) => return Err(AppError::InvalidInput("缺少 metric_type".into())),
+    };
+
+    mode.buffer(BufferedMeasurement {
+        metric_type,
+        timestamp: body.timestamp,
+        value: body.value,
+        device_id: body.device_id,
+        unit: body.unit,
+        suppressed_count: 0,
+    })
+    .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "buffered": true,
+            "message": "系统处于只读模式，数据已暂存，恢复后自动入库",
+        })),
+    )
+        .into_response())
+}
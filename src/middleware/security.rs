@@ -0,0 +1,98 @@
+//! 跨域与安全响应头
+
+use crate::config::security::{CorsConfig, SecurityHeadersConfig};
+use crate::middleware::logging::REQUEST_ID_HEADER;
+use axum::{
+    extract::State,
+    http::{header, HeaderName, HeaderValue, Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+use tracing::warn;
+
+/// 根据配置构建 CORS 层
+pub fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|m| match m.parse() {
+            Ok(method) => Some(method),
+            Err(_) => {
+                warn!("Ignoring invalid CORS method: {}", m);
+                None
+            }
+        })
+        .collect();
+
+    let headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|h| match h.parse() {
+            Ok(name) => Some(name),
+            Err(_) => {
+                warn!("Ignoring invalid CORS header: {}", h);
+                None
+            }
+        })
+        .collect();
+
+    let layer = CorsLayer::new()
+        .allow_methods(AllowMethods::list(methods))
+        .allow_headers(AllowHeaders::list(headers))
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+        .max_age(Duration::from_secs(config.max_age_secs));
+
+    if config.allowed_origins.iter().any(|o| o == "*") {
+        // 任意来源时浏览器不允许携带凭据
+        return layer.allow_origin(Any);
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .allowed_origins
+        .iter()
+        .filter_map(|o| match o.parse() {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                warn!("Ignoring invalid CORS origin: {}", o);
+                None
+            }
+        })
+        .collect();
+
+    layer
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_credentials(config.allow_credentials)
+}
+
+/// 为所有响应补充安全头（已存在的头不覆盖）
+pub async fn security_headers_middleware(
+    State(config): State<Arc<SecurityHeadersConfig>>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if !config.enabled {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    headers
+        .entry(header::X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    if let Ok(value) = HeaderValue::from_str(&config.frame_options) {
+        headers.entry(header::X_FRAME_OPTIONS).or_insert(value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&config.referrer_policy) {
+        headers.entry(header::REFERRER_POLICY).or_insert(value);
+    }
+    if config.hsts {
+        headers
+            .entry(header::STRICT_TRANSPORT_SECURITY)
+            .or_insert(HeaderValue::from_static("max-age=31536000; includeSubDomains"));
+    }
+
+    response
+}
@@ -12,10 +12,23 @@ pub struct Model {
     pub trigger_time: DateTime<Utc>, // 触发时间
     pub trigger_value: f64,      // 触发值
     pub is_processed: bool,      // 是否处理
+    pub device_id: Option<i32>,  // 关联设备，规则报警可为空
+    #[sea_orm(default_value = "warning")]
+    pub severity: String,        // critical / major / minor / warning
+    #[sea_orm(default_value = false)]
+    pub shelved: bool,           // 触发时该报警点处于搁置中，不转发
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+pub const SEVERITY_CRITICAL: &str = "critical";
+pub const SEVERITY_MAJOR: &str = "major";
+pub const SEVERITY_MINOR: &str = "minor";
+pub const SEVERITY_WARNING: &str = "warning";
+
+/// 严重程度从高到低
+pub const SEVERITIES: [&str; 4] = [SEVERITY_CRITICAL, SEVERITY_MAJOR, SEVERITY_MINOR, SEVERITY_WARNING];
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
@@ -12,6 +12,7 @@ pub struct Model {
     pub condition: String,    // 条件
     pub parameter: String,    // 参数
     pub value: f64,           // 值
+    pub organization_id: Option<i32>, // 所属组织
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 报警搁置记录，按规则名称和设备识别同一个报警点
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "alarm_shelves")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub rule_name: String,
+    pub device_id: Option<i32>,
+    pub alarm_log_id: i32,                // 发起搁置时选中的报警记录
+    pub reason: String,                   // 搁置原因，必填
+    pub shelved_by: String,
+    pub shelved_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,        // 到期自动解除
+    pub unshelved_at: Option<DateTime<Utc>>, // 提前解除的时间
+    pub unshelved_by: Option<String>,
+}
+
+impl Model {
+    /// 搁置实际结束的时间
+    pub fn ended_at(&self) -> DateTime<Utc> {
+        self.unshelved_at.map_or(self.expires_at, |at| at.min(self.expires_at))
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.shelved_at <= now && now < self.ended_at()
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "api_keys")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,                     // 客户端名称，例如网关编号
+    pub key_prefix: String,               // 明文前缀，仅用于辨认
+    #[serde(skip_serializing)]
+    #[sea_orm(unique)]
+    pub key_hash: String,                 // SHA-256 摘要
+    pub scopes: String,                   // 权限范围，逗号分隔，例如 "ingest,read"
+    pub revoked: bool,                    // 是否已吊销
+    pub organization_id: Option<i32>,     // 所属组织，为空时是平台管理 Key
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Model {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.split(',').map(str::trim).any(|s| s == scope)
+    }
+
+    /// 记入操作记录的调用方，例如 `api-key:3 中控室`
+    pub fn operator_label(&self) -> String {
+        format!("api-key:{} {}", self.id, self.name)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
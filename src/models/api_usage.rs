@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 客户端在一个统计周期内对某个接口的调用统计
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "api_usage")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub client: String,                   // key:<Key 前缀> 或 ip:<地址>
+    pub method: String,
+    pub path: String,                     // 路由模板，例如 /measurements/{id}
+    pub period_start: DateTime<Utc>,      // 统计周期起点（整小时）
+    pub requests: i64,
+    pub client_errors: i64,               // 4xx 响应数
+    pub server_errors: i64,               // 5xx 响应数
+    pub total_ms: i64,                    // 响应耗时合计
+    pub max_ms: i64,
+    #[sea_orm(column_type = "Text")]
+    pub latency_histogram: String,        // 各耗时区间的请求数，JSON 数组
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,8 +10,10 @@ pub struct Model {
     pub id: i32,
     pub action: String,              // 行为
     pub level: i32,                  // 等级
-    pub trigger_time_range: String,  // 触发时间端
+    pub trigger_time_range: String,  // 触发时间段，本地时刻 HH:MM-HH:MM，为空表示全天
+    pub site_id: Option<i32>,        // 所属厂站，时间段按厂站时区解释
     pub sync_alarm: bool,            // 是否同步报警
+    pub organization_id: Option<i32>, // 所属组织
     pub created_at: DateTime<Utc>,   // 创建时间
     pub updated_at: DateTime<Utc>,
 }
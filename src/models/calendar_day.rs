@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+/// 休息日：节假日或厂站停产检修日，不排班
+pub const KIND_HOLIDAY: &str = "holiday";
+/// 调休上班日：按正常工作日排班，不受班次星期限制
+pub const KIND_WORKDAY: &str = "workday";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "calendar_days")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub site_id: Option<i32>,         // 厂站，空表示法定节假日等全局日期
+    pub date: NaiveDate,              // 日期（本地）
+    pub kind: String,                 // holiday / workday
+    pub name: String,                 // 例如 `国庆节`
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "calendar_shifts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub site_id: Option<i32>,         // 厂站，空表示所有厂站
+    pub name: String,                 // 例如 `白班`
+    pub weekdays: String,             // 生效的星期，逗号分隔，1 为周一，例如 `1,2,3,4,5`
+    pub start_time: String,           // 开始时间 `HH:MM`（本地时间）
+    pub end_time: String,             // 结束时间，不晚于开始时间表示跨零点
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 现场校准记录
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "calibrations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub metric_type: String,
+    pub reference_value: f64,            // 标准值（零点或单点），指标默认单位
+    pub measured_value: f64,             // 校准时系统显示的读数
+    pub reference_value_2: Option<f64>,  // 两点校准的量程点标准值
+    pub measured_value_2: Option<f64>,
+    pub gain: f64,                       // 之后的读数按 gain·x + offset 修正，已与此前的校准叠加
+    pub offset: f64,
+    pub calibrated_at: DateTime<Utc>,
+    pub technician: String,
+    pub notes: Option<String>,
+    pub due_at: DateTime<Utc>,           // 下次校准到期时间
+    #[sea_orm(default_value = false)]
+    pub due_alarmed: bool,               // 已产生到期提醒
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,26 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const KIND_PIECEWISE: &str = "piecewise";
+pub const KIND_POLYNOMIAL: &str = "polynomial";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "calibration_curves")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,               // 设备（通道）
+    pub metric_type: String,          // 指标类型
+    pub kind: String,                 // 曲线类型：piecewise / polynomial
+    pub points: String,               // piecewise 为标定点 [[原始值, 实际值], ...]，polynomial 为系数 [c0, c1, ...]
+    pub enabled: bool,                // 是否启用
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
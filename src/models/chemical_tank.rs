@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 药剂储罐
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "chemical_tanks")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub chemical: String,             // 药剂，例如“硫酸”“氢氧化钠”“PAC”
+    pub capacity_l: f64,              // 有效容积 (L)
+    pub level_l: f64,                 // 当前存量 (L)，按加药泵运行量递减，补药时增加
+    pub low_level_l: f64,             // 低液位报警阈值 (L)
+    pub low_alarm_active: bool,       // 已产生低液位报警，补药到阈值以上后复位
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "config_revisions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub entity_type: String,              // 配置类型，例如 alarm_rule、automation_rule、device
+    pub entity_id: i32,                   // 配置对象ID
+    pub revision: i32,                    // 版本号，同一对象内递增
+    pub action: String,                   // create / update / delete / rollback
+    #[sea_orm(column_type = "Text", nullable)]
+    pub previous: Option<String>,         // 变更前的 JSON 快照，新建时为空
+    #[sea_orm(column_type = "Text", nullable)]
+    pub snapshot: Option<String>,         // 变更后的 JSON 快照，删除时为空
+    pub changed_by: Option<String>,       // 操作人
+    #[sea_orm(default_value = "applied")]
+    pub status: String,                   // applied / pending / rejected
+    pub reviewed_by: Option<String>,      // 审批人
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 已生效
+pub const STATUS_APPLIED: &str = "applied";
+/// 待审批，尚未写入配置表
+pub const STATUS_PENDING: &str = "pending";
+/// 已驳回
+pub const STATUS_REJECTED: &str = "rejected";
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
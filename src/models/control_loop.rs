@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 通过 PWM 通道输出
+pub const OUTPUT_PWM: &str = "pwm";
+/// 写入 Modbus 保持寄存器
+pub const OUTPUT_MODBUS: &str = "modbus";
+pub const OUTPUTS: [&str; 2] = [OUTPUT_PWM, OUTPUT_MODBUS];
+
+/// 正作用：测量值高于设定值时增大输出，例如投酸降 pH
+pub const ACTION_DIRECT: &str = "direct";
+/// 反作用：测量值低于设定值时增大输出，例如投碱升 pH
+pub const ACTION_REVERSE: &str = "reverse";
+
+pub const MODE_MANUAL: &str = "manual";
+pub const MODE_AUTO: &str = "auto";
+
+/// PID 控制回路
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "control_loops")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub device_id: i32,                 // 提供测量值的设备
+    pub input_metric: String,           // 测量值指标类型，例如 ph
+    pub output_kind: String,            // pwm / modbus
+    pub output_device_id: i32,          // PWM 通道或 Modbus 从站对应的设备
+    pub output_address: Option<i32>,    // Modbus 保持寄存器地址
+    pub action: String,                 // direct / reverse
+    pub setpoint: f64,
+    pub kp: f64,
+    pub ki: f64,                        // 每秒积分增益
+    pub kd: f64,                        // 微分增益 (s)
+    pub output_min: f64,
+    pub output_max: f64,
+    pub mode: String,                   // manual / auto
+    pub output: f64,                    // 当前输出，手动模式下即手动给定值
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+/// 设备每天的运行时长和报警次数，每台设备每天一行
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "daily_device_summaries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub day: NaiveDate,               // 统计日（UTC）
+    pub run_seconds: i64,             // 处于 running 状态的秒数
+    pub alarm_count: i64,             // 当天产生的报警条数
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
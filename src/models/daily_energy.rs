@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+/// 电能表读数之差
+pub const SOURCE_METER: &str = "meter";
+/// 功率小时平均值积分
+pub const SOURCE_POWER: &str = "power";
+/// 额定功率乘以运行时长估算
+pub const SOURCE_RATED: &str = "rated";
+
+/// 设备每日用电量
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "daily_energy")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub site_id: Option<i32>,         // 计算时设备所属厂站
+    pub area_id: Option<i32>,         // 计算时设备所属区域
+    pub day: NaiveDate,               // 统计日（UTC）
+    pub kwh: f64,
+    pub source: String,               // meter / power / rated
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+/// 设备每天每个指标的统计
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "daily_summaries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub day: NaiveDate,               // 统计日（UTC）
+    pub metric_type: String,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,                   // 参与统计的测量值条数
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const RECORD_MEASUREMENT: &str = "measurement";
+pub const RECORD_LAB_RESULT: &str = "lab_result";
+
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+
+/// 数据修正记录，保存被修改或删除前的原始版本
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "data_corrections")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub record_type: String,          // measurement / lab_result
+    pub record_id: i32,
+    pub version: i32,                 // 被替换的版本号，原始数据为 1
+    pub action: String,               // update / delete
+    #[sea_orm(column_type = "Text")]
+    pub previous: String,             // 修改前的 JSON 快照
+    #[sea_orm(column_type = "Text", nullable)]
+    pub snapshot: Option<String>,     // 修改后的 JSON 快照，删除时为空
+    pub reason: Option<String>,       // 修正原因
+    pub corrected_by: Option<String>, // 操作人
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
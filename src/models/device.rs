@@ -16,11 +16,18 @@ pub struct Model {
     pub model: String,              // 型号
     pub installation_date: DateTime<Utc>, // 安装日期
     pub last_maintenance: DateTime<Utc>,  // 上次维护时间
-    pub operational_hours: f64,     // 运行小时数
+    pub operational_hours: f64,     // 运行小时数，由运行状态事件自动累计
+    pub run_hours_accrued_at: Option<DateTime<Utc>>, // 运行小时数已结算到的时刻
     pub temperature: f64,           // 当前温度
     pub pressure: f64,              // 当前压力
     pub flow_rate: f64,             // 流量
     pub power_consumption: f64,     // 功耗
+    pub serial_number: Option<String>, // 序列号，设备自注册时上报
+    #[sea_orm(default_value = "active")]
+    pub provision_status: String,   // 注册状态：pending / active
+    pub site_id: Option<i32>,       // 所属厂站
+    pub area_id: Option<i32>,       // 所属区域
+    pub organization_id: Option<i32>, // 所属组织，见 utils::tenant
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -28,4 +35,7 @@ pub struct Model {
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
+impl ActiveModelBehavior for ActiveModel {}
+
+pub const PROVISION_PENDING: &str = "pending";
+pub const PROVISION_ACTIVE: &str = "active";
\ No newline at end of file
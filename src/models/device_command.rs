@@ -0,0 +1,42 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 已发布，等待设备确认
+pub const STATUS_PENDING: &str = "pending";
+/// 设备确认执行成功
+pub const STATUS_ACKED: &str = "acked";
+/// 设备确认但报告执行失败
+pub const STATUS_FAILED: &str = "failed";
+/// 超时未收到确认
+pub const STATUS_TIMED_OUT: &str = "timed_out";
+
+/// 下发的设备命令及其确认状态
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "device_commands")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub correlation_id: String,       // 设备确认时原样带回
+    pub device_id: i32,
+    pub command: String,
+    pub payload: String,              // 命令参数（JSON）
+    pub status: String,               // pending / acked / failed / timed_out
+    pub result: Option<String>,       // 设备返回的结果（JSON）
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,    // 超过该时间仍未确认则超时
+    pub acked_at: Option<DateTime<Utc>>,
+}
+
+impl Model {
+    pub fn is_pending(&self) -> bool {
+        self.status == STATUS_PENDING
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
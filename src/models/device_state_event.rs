@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 通信状态：online / offline
+pub const CATEGORY_CONNECTIVITY: &str = "connectivity";
+/// 运行状态：running / stopped / fault
+pub const CATEGORY_OPERATION: &str = "operation";
+
+pub const STATE_ONLINE: &str = "online";
+pub const STATE_OFFLINE: &str = "offline";
+pub const STATE_RUNNING: &str = "running";
+pub const STATE_STOPPED: &str = "stopped";
+pub const STATE_FAULT: &str = "fault";
+
+/// 通过设备管理接口修改状态
+pub const SOURCE_API: &str = "api";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "device_state_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub category: String,             // connectivity / operation
+    pub state: String,                // 进入的状态
+    pub previous_state: Option<String>, // 之前的状态，首条事件为空
+    pub source: String,               // 来源：api / mqtt / acquisition 等
+    pub timestamp: DateTime<Utc>,     // 状态变化时间
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 滚动平均值不得高于限值
+pub const LIMIT_MAX: &str = "max";
+/// 滚动平均值不得低于限值，例如 pH 下限
+pub const LIMIT_MIN: &str = "min";
+
+pub const PERIOD_HOURLY: &str = "hourly";
+pub const PERIOD_DAILY: &str = "daily";
+pub const PERIOD_WEEKLY: &str = "weekly";
+pub const PERIOD_MONTHLY: &str = "monthly";
+
+/// 排污许可限值
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "discharge_permits")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,                 // 例如 `排口 COD 日均值`
+    pub permit_number: Option<String>, // 排污许可证编号
+    pub device_id: i32,               // 排口监测设备
+    pub metric_type: String,          // 受限参数
+    pub limit_value: f64,
+    pub limit_type: String,           // max / min
+    pub averaging_period: String,     // hourly / daily / weekly / monthly，按滚动窗口计算
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 通过 PWM 通道调速
+pub const CONTROL_PWM: &str = "pwm";
+/// 通过 Modbus 保持寄存器写入转速百分比
+pub const CONTROL_MODBUS: &str = "modbus";
+/// 不受本系统控制，只按记录的转速计算用量
+pub const CONTROL_MANUAL: &str = "manual";
+pub const CONTROLS: [&str; 3] = [CONTROL_PWM, CONTROL_MODBUS, CONTROL_MANUAL];
+
+pub const MODE_MANUAL: &str = "manual";
+/// 按 pH 闭环自动调整转速
+pub const MODE_AUTO: &str = "auto";
+
+/// 投加酸，降低 pH
+pub const DIRECTION_LOWER: &str = "lower";
+/// 投加碱，提高 pH
+pub const DIRECTION_RAISE: &str = "raise";
+
+/// 加药泵
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "dosing_pumps")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub tank_id: i32,                 // 取药的储罐
+    pub max_rate_lph: f64,            // 转速 100% 时的投加量 (L/h)
+    pub control: String,              // pwm / modbus / manual
+    pub control_device_id: Option<i32>, // PWM 通道或 Modbus 从站对应的设备
+    pub control_address: Option<i32>, // Modbus 保持寄存器地址
+    pub speed_percent: f64,           // 当前转速 (%)
+    pub mode: String,                 // manual / auto
+    pub ph_device_id: Option<i32>,    // 自动模式使用的 pH 计
+    pub ph_setpoint: Option<f64>,
+    pub direction: Option<String>,    // lower / raise
+    pub gain: f64,                    // 比例系数，每偏离 1 个 pH 对应的转速 (%)
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
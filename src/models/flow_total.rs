@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+pub const PERIOD_DAY: &str = "day";
+pub const PERIOD_MONTH: &str = "month";
+
+/// 流量计自带累计器读数之差
+pub const SOURCE_TOTALIZER: &str = "totalizer";
+/// 瞬时流量对时间积分
+pub const SOURCE_INTEGRATED: &str = "integrated";
+/// 月累计中两种来源的天都有
+pub const SOURCE_MIXED: &str = "mixed";
+
+/// 设备每日、每月累计流量
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "flow_totals")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub site_id: Option<i32>,         // 计算时设备所属厂站
+    pub period: String,               // day / month
+    pub period_start: NaiveDate,      // 统计日，或统计月的第一天（UTC）
+    pub volume: f64,                  // m³
+    pub source: String,               // totalizer / integrated / mixed
+    pub resets: i32,                  // 累计器清零或溢出的次数
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// GPIO 输出写入记录，失败的写入同样记录
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "gpio_writes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,                     // 输出名称，对应配置中的 gpio.outputs
+    pub line: i32,
+    pub device_id: Option<i32>,
+    pub value: bool,                      // 请求的电平
+    pub operator: Option<String>,
+    pub status: String,                   // written / failed
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 已写入
+pub const STATUS_WRITTEN: &str = "written";
+/// 写入失败
+pub const STATUS_FAILED: &str = "failed";
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
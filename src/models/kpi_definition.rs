@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 自定义 KPI 公式
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "kpi_definitions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub name: String,                 // 例如 `cod_removal`
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub expression: String,           // 例如 `(inlet_cod_avg - outlet_cod_avg) / inlet_cod_avg * 100`
+    #[sea_orm(column_type = "Text")]
+    pub variables: String,            // 变量别名 -> {"device_id": 1, "metric_type": "cod"} 的 JSON
+    pub unit: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 人工录入的化验结果
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "lab_results")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub site_id: Option<i32>,
+    pub sample_point: String,         // 采样点，例如 `进水`、`二沉池出水`
+    pub device_id: Option<i32>,       // 采样点对应的监测设备，可为空
+    pub analyte: String,              // 化验项目，见 services::lab_result::ANALYTES
+    pub value: f64,
+    pub unit: String,
+    pub sampled_at: DateTime<Utc>,
+    pub analyzed_at: Option<DateTime<Utc>>,
+    pub method: Option<String>,       // 分析方法，例如 `HJ 828-2017`
+    pub analyst: String,              // 化验人员
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 按日历间隔维护
+pub const INTERVAL_CALENDAR: &str = "calendar";
+/// 按运行小时数维护
+pub const INTERVAL_OPERATING_HOURS: &str = "operating_hours";
+
+/// 设备维护计划
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "maintenance_plans")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub name: String,                 // 例如 `更换机械密封`
+    pub description: Option<String>,  // 作业内容，写入生成的工单
+    pub interval_kind: String,        // calendar / operating_hours
+    pub interval_days: Option<i32>,   // 日历间隔 (天)
+    pub interval_hours: Option<f64>,  // 运行小时间隔
+    pub overdue_days: i32,            // 工单打开超过该天数未关闭即报警
+    pub last_done_at: DateTime<Utc>,  // 上次完成时间
+    pub last_done_hours: f64,         // 上次完成时设备的运行小时数
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
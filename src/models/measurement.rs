@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "measurements")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub metric_type: String,         // 指标类型，见 services::metric_registry
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    pub unit: String,
+    #[sea_orm(default_value = 0)]
+    pub suppressed_count: i32,       // 此前被压缩丢弃的样本数，见 services::compression
+    #[sea_orm(default_value = "good")]
+    pub quality: String,             // good / suspect / bad / out_of_range / frozen，见 services::quality
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub const QUALITY_GOOD: &str = "good";
+/// 变化率异常（突跳），需人工确认
+pub const QUALITY_SUSPECT: &str = "suspect";
+/// 人工判定或设备报告的坏值
+pub const QUALITY_BAD: &str = "bad";
+/// 超出测量规则配置的量程
+pub const QUALITY_OUT_OF_RANGE: &str = "out_of_range";
+/// 数值长时间不变，传感器可能卡死
+pub const QUALITY_FROZEN: &str = "frozen";
+
+pub const QUALITIES: [&str; 5] =
+    [QUALITY_GOOD, QUALITY_SUSPECT, QUALITY_BAD, QUALITY_OUT_OF_RANGE, QUALITY_FROZEN];
+
+/// 聚合时默认排除的质量
+pub const EXCLUDED_QUALITIES: [&str; 2] = [QUALITY_BAD, QUALITY_OUT_OF_RANGE];
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -6,4 +6,48 @@ pub mod turbidity_value;
 pub mod flow_value;
 pub mod alarm_rule;
 pub mod alarm_log;
-pub mod automation_rule;
\ No newline at end of file
+pub mod automation_rule;
+pub mod measurement;
+pub mod api_key;
+pub mod device_credential;
+pub mod remote_session;
+pub mod config_revision;
+pub mod site;
+pub mod area;
+pub mod serial_session;
+pub mod calibration_curve;
+pub mod tank_geometry;
+pub mod pump_curve;
+pub mod vibration_record;
+pub mod vibration_limit;
+pub mod device_state_event;
+pub mod daily_summary;
+pub mod daily_device_summary;
+pub mod modbus_write;
+pub mod modbus_mapping;
+pub mod summary_dirty_day;
+pub mod calendar_shift;
+pub mod calendar_day;
+pub mod device_command;
+pub mod alarm_shelf;
+pub mod alarm_snapshot;
+pub mod api_usage;
+pub mod hourly_summary;
+pub mod kpi_definition;
+pub mod gpio_write;
+pub mod report;
+pub mod discharge_permit;
+pub mod permit_exceedance;
+pub mod safe_state_event;
+pub mod daily_energy;
+pub mod chemical_tank;
+pub mod dosing_pump;
+pub mod control_loop;
+pub mod maintenance_plan;
+pub mod work_order;
+pub mod pump_group;
+pub mod flow_total;
+pub mod calibration;
+pub mod lab_result;
+pub mod data_correction;
+pub mod organization;
\ No newline at end of file
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 功能码：读线圈 / 读离散输入 / 读保持寄存器 / 读输入寄存器
+pub const FUNCTION_CODES: &[i32] = &[1, 2, 3, 4];
+
+/// 线圈和离散输入的数据类型；寄存器的数据类型和字节序见 [`crate::utils::modbus`]
+pub const BIT_DATA_TYPE: &str = "bool";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "modbus_mappings")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,               // 设备
+    pub name: String,                 // 例如 `累计流量`
+    pub function_code: i32,           // 1 / 2 / 3 / 4
+    pub address: i32,                 // 起始地址（从 0 开始）
+    pub data_type: String,            // bool / u16 / i16 / u32 / i32 / f32 / u64 / i64 / f64
+    pub byte_order: String,           // ABCD / CDAB / BADC / DCBA，单寄存器类型忽略
+    pub scale: f64,                   // 实际值 = 原始值 * scale + offset
+    pub offset: f64,
+    pub metric_type: String,          // 写入的指标类型
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// Modbus 写入审计记录，被拒绝和失败的写入同样记录
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "modbus_writes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub kind: String,                     // holding / coil
+    pub address: i32,
+    pub requested_value: f64,             // 请求写入的工程值
+    pub written_value: Option<f64>,       // 截断后实际写入的工程值
+    pub source: String,                   // api / automation
+    pub operator: Option<String>,
+    pub override_interlocks: bool,        // 人工越过联锁
+    pub reason: Option<String>,           // 越过联锁的原因
+    pub status: String,                   // written / blocked / failed
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 已写入
+pub const STATUS_WRITTEN: &str = "written";
+/// 被安全检查拒绝
+pub const STATUS_BLOCKED: &str = "blocked";
+/// 通信失败
+pub const STATUS_FAILED: &str = "failed";
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 许可限值超标事件，连续超标的滚动窗口合并为一条，与运行报警分开记录
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "permit_exceedances")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub permit_id: i32,
+    pub device_id: i32,
+    pub metric_type: String,
+    pub averaging_period: String,
+    pub limit_value: f64,             // 超标时的限值
+    pub limit_type: String,
+    pub first_window_end: DateTime<Utc>, // 第一个超标窗口的结束时间
+    pub last_window_end: DateTime<Utc>,  // 最后一个超标窗口的结束时间
+    pub windows: i32,                 // 超标的窗口数（每小时一个）
+    pub peak_average: f64,            // 超标最严重的滚动平均值
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
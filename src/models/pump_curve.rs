@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "pump_curves")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub device_id: i32,               // 水泵设备
+    pub efficiency_curve: String,     // 厂家效率曲线 [[流量 m³/h, 效率 %], ...]
+    pub head_curve: Option<String>,   // 厂家扬程曲线 [[流量 m³/h, 扬程 m], ...]，仅用于展示
+    pub degradation_pct: f64,         // 效率低于曲线值该百分比时报警
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
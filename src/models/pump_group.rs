@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 主备泵组：`duty` 中的第一台为主泵（lead），其余为辅泵（lag），组内其他可用泵为备用（standby）
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "pump_groups")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    #[sea_orm(column_type = "Text")]
+    pub members: String,              // 组内泵的设备 ID，JSON 数组，按优先顺序
+    pub duty_count: i32,              // 同时运行的台数（主泵 + 辅泵）
+    pub rotation_hours: f64,          // 运行中的泵比最少运行的备用泵多出该小时数时轮换，0 不轮换
+    pub feedback_timeout_secs: i32,   // 启动后超过该时间没有运行反馈视为故障
+    #[sea_orm(column_type = "Text")]
+    pub duty: String,                 // 当前运行分配，JSON 数组，第一台为主泵
+    pub last_rotated_at: Option<DateTime<Utc>>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "remote_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub vendor: String,               // 厂商/人员
+    pub reason: String,               // 访问原因
+    pub target: String,               // 目标地址 host:port
+    pub client_ip: Option<String>,    // 允许接入的厂商来源 IP
+    pub listen_port: i32,             // 代理监听端口
+    pub recording_path: String,       // 流量记录文件
+    pub bytes_to_target: i64,         // 客户端 -> 设备字节数
+    pub bytes_from_target: i64,       // 设备 -> 客户端字节数
+    pub expires_at: DateTime<Utc>,    // 到期时间
+    pub closed_at: Option<DateTime<Utc>>, // 实际关闭时间
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
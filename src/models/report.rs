@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use utoipa::ToSchema;
+
+pub const KIND_DAILY: &str = "daily";
+pub const KIND_WEEKLY: &str = "weekly";
+
+pub const FORMAT_PDF: &str = "pdf";
+pub const FORMAT_XLSX: &str = "xlsx";
+
+/// 已生成的报表文件，每个周期每种格式一行
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "reports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub kind: String,                 // daily / weekly
+    pub period_start: NaiveDate,      // 统计周期首日（默认时区的本地日期）
+    pub period_end: NaiveDate,        // 统计周期末日之后的一天
+    pub format: String,               // pdf / xlsx
+    pub storage: String,              // local / s3
+    pub location: String,             // 本地路径或对象键
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
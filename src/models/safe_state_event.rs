@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const EVENT_TELEMETRY_LOST: &str = "telemetry_lost";
+pub const EVENT_TELEMETRY_RESTORED: &str = "telemetry_restored";
+
+/// 已下发安全状态
+pub const STATUS_APPLIED: &str = "applied";
+/// 策略为 hold，未下发
+pub const STATUS_HELD: &str = "held";
+pub const STATUS_FAILED: &str = "failed";
+
+/// 遥测中断时的安全状态动作
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "safe_state_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub actuator: String,             // 配置中的执行器名称
+    pub event: String,                // telemetry_lost / telemetry_restored
+    pub policy: String,               // hold / open / close / stop
+    pub status: Option<String>,       // applied / held / failed，恢复事件为空
+    pub value: Option<f64>,           // 下发的值
+    #[sea_orm(column_type = "Text")]
+    pub stale_sensors: String,        // 超时的传感器，例如 `3:flow, 4:level`
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,26 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "serial_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub port: String,                 // 串口名称
+    pub operator: String,             // 操作人
+    pub reason: String,               // 访问原因
+    pub remote_addr: String,          // 客户端地址
+    pub recording_path: String,       // 收发记录文件
+    pub bytes_to_device: i64,         // 客户端 -> 设备字节数
+    pub bytes_from_device: i64,       // 设备 -> 客户端字节数
+    pub closed_at: Option<DateTime<Utc>>, // 断开时间
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
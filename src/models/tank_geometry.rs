@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const SHAPE_CYLINDER: &str = "cylinder";
+pub const SHAPE_CONE: &str = "cone";
+pub const SHAPE_STRAPPING: &str = "strapping";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "tank_geometries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub device_id: i32,               // 液位计设备
+    pub shape: String,                // 罐体形状：cylinder / cone / strapping
+    pub sensor_offset: f64,           // 探头到罐底的距离 (m)
+    pub height: f64,                  // 最高液位 (m)
+    pub diameter: Option<f64>,        // 直径 (m)，cylinder / cone 使用
+    pub cone_height: Option<f64>,     // 锥底高度 (m)，cone 使用
+    pub strapping_table: Option<String>, // 容积表 [[液位, 容积], ...]，strapping 使用
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -19,4 +19,18 @@ pub struct Model {
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<super::measurement::Model> for Model {
+    fn from(m: super::measurement::Model) -> Self {
+        Self {
+            id: m.id,
+            timestamp: m.timestamp,
+            value: m.value,
+            device_id: m.device_id,
+            unit: m.unit,
+            created_at: m.created_at,
+            updated_at: m.updated_at,
+        }
+    }
+}
\ No newline at end of file
@@ -8,4 +8,6 @@ pub struct Model {
     pub email: String,
     pub password: String,
     pub permission: String,
+    #[serde(default)]
+    pub organization_id: Option<i32>,
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 频带值（传感器侧已完成 FFT，每个频带一个幅值）
+pub const KIND_BANDS: &str = "bands";
+/// 原始波形片段
+pub const KIND_BURST: &str = "burst";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "vibration_records")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub device_id: i32,
+    pub timestamp: DateTime<Utc>,
+    pub kind: String,                 // bands / burst
+    pub sample_rate: Option<f64>,     // 采样率 (Hz)，burst 使用
+    pub band_width: Option<f64>,      // 每个频带的宽度 (Hz)，bands 使用
+    #[sea_orm(column_type = "Text")]
+    pub values: String,               // 频带幅值或波形采样点 [v0, v1, ...]
+    pub overall_rms: f64,             // 总振动有效值
+    pub peak: f64,                    // 峰值
+    pub temperature: Option<f64>,     // 轴承温度 (°C)
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
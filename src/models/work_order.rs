@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_CLOSED: &str = "closed";
+
+/// 维护工单，由维护计划到期时生成或人工创建
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, ToSchema)]
+#[sea_orm(table_name = "work_orders")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub plan_id: Option<i32>,         // 来源维护计划，人工创建时为空
+    pub device_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,               // open / closed
+    pub due_at: DateTime<Utc>,        // 应完成时间，超过后报警
+    pub overdue_alarmed: bool,        // 已产生超期报警
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closed_by: Option<String>,
+    pub notes: Option<String>,        // 技术员关闭时填写的处理记录
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,73 @@
+//! MQTT 消息分发
+//!
+//! 根据配置连接 broker，各服务在这里把关心的主题注册到 [`TopicRouter`]。
+
+use crate::config::mqtt::MqttConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::mqtt::router::{Message, TopicRouter};
+use crate::mqtt::rumqtt::MqttManager;
+use crate::mqtt::sparkplug::Sparkplug;
+use crate::services::cache::HotCache;
+use crate::services::device_command::{self, CommandTracker};
+use crate::services::provisioning;
+use crate::services::read_only::ReadOnlyMode;
+use rumqttc::QoS;
+use std::error::Error;
+
+/// 注册各服务的主题处理函数
+fn router(db: DbManager, commands: CommandTracker, sparkplug: Option<Sparkplug>) -> TopicRouter {
+    let provisioning_db = db.clone();
+    let router = TopicRouter::new()
+        .route(provisioning::REQUEST_TOPIC, QoS::AtLeastOnce, move |message: Message| {
+            let db = provisioning_db.clone();
+            async move {
+                provisioning::handle_request(db, message.payload).await;
+                Ok(())
+            }
+        })
+        .route(device_command::ACK_TOPIC, QoS::AtLeastOnce, move |message: Message| {
+            let (db, commands) = (db.clone(), commands.clone());
+            async move {
+                let device_id = message
+                    .param::<i32>(0)
+                    .ok_or_else(|| anyhow::anyhow!("无效的设备ID: {}", message.topic))?;
+                commands.handle_ack(db.get_connection(), device_id, &message.payload).await
+            }
+        });
+    match sparkplug {
+        Some(sparkplug) => {
+            let pattern = sparkplug.subscription();
+            router.route(&pattern, QoS::AtLeastOnce, move |message: Message| {
+                let sparkplug = sparkplug.clone();
+                async move { sparkplug.handle(message).await }
+            })
+        }
+        None => router,
+    }
+}
+
+/// 启动 MQTT 客户端及消息分发
+pub async fn start(
+    config: &MqttConfig,
+    db: DbManager,
+    cache: HotCache,
+    read_only: ReadOnlyMode,
+    commands: CommandTracker,
+) -> Result<MqttManager, Box<dyn Error>> {
+    let mqtt = MqttManager::new(
+        &config.client_id,
+        &config.host,
+        config.port,
+        config.keep_alive_secs,
+        &config.outbox_path,
+        config.outbox_max_messages,
+    )
+    .await?;
+
+    let sparkplug = config.sparkplug.enabled.then(|| {
+        Sparkplug::new(config.sparkplug.clone(), db.clone(), cache, read_only, mqtt.clone())
+    });
+    mqtt.start_router(router(db, commands, sparkplug)).await?;
+
+    Ok(mqtt)
+}
@@ -1 +1,4 @@
+pub mod dispatcher;
+pub mod router;
 pub mod rumqtt;
+pub mod sparkplug;
\ No newline at end of file
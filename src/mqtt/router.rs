@@ -0,0 +1,154 @@
+//! MQTT 主题路由
+//!
+//! 各模块按主题模式注册异步处理函数，支持 `+`（单级）和 `#`（多级，只能在末尾）通配符。
+//! 每个匹配的处理函数在独立任务中执行，返回错误或 panic 只影响自身，
+//! 不影响事件循环和其他处理函数。
+
+use rumqttc::QoS;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+/// 收到的消息
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// 通配符匹配到的层级，按出现顺序；`#` 匹配的剩余部分作为一项
+    pub params: Vec<String>,
+}
+
+impl Message {
+    /// 第 `index` 个通配符参数解析为数字，例如 `devices/+/status` 中的设备ID
+    pub fn param<T: std::str::FromStr>(&self, index: usize) -> Option<T> {
+        self.params.get(index)?.parse().ok()
+    }
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+type Handler = Arc<dyn Fn(Message) -> BoxFuture + Send + Sync>;
+
+struct Route {
+    pattern: String,
+    qos: QoS,
+    handler: Handler,
+}
+
+#[derive(Clone, Default)]
+pub struct TopicRouter {
+    routes: Arc<Vec<Route>>,
+}
+
+impl std::fmt::Debug for TopicRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.routes.iter().map(|r| &r.pattern)).finish()
+    }
+}
+
+/// 按 MQTT 规则匹配主题，匹配时返回通配符参数
+pub fn topic_matches(pattern: &str, topic: &str) -> Option<Vec<String>> {
+    let mut params = Vec::new();
+    let mut levels = topic.split('/');
+    let mut filters = pattern.split('/').peekable();
+    while let Some(filter) = filters.next() {
+        if filter == "#" {
+            // `#` 也匹配父级本身，例如 `a/#` 匹配 `a`
+            let rest: Vec<&str> = levels.collect();
+            params.push(rest.join("/"));
+            return filters.peek().is_none().then_some(params);
+        }
+        let level = levels.next()?;
+        match filter {
+            "+" => params.push(level.to_string()),
+            _ if filter == level => {}
+            _ => return None,
+        }
+    }
+    levels.next().is_none().then_some(params)
+}
+
+impl TopicRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册处理函数，同一主题可以匹配多个处理函数
+    pub fn route<F, Fut>(mut self, pattern: &str, qos: QoS, handler: F) -> Self
+    where
+        F: Fn(Message) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |message| Box::pin(handler(message)));
+        Arc::get_mut(&mut self.routes)
+            .expect("routes are only added before the router is shared")
+            .push(Route { pattern: pattern.to_string(), qos, handler });
+        self
+    }
+
+    /// 需要订阅的主题模式
+    pub fn subscriptions(&self) -> Vec<(String, QoS)> {
+        self.routes.iter().map(|r| (r.pattern.clone(), r.qos)).collect()
+    }
+
+    /// 分发一条消息，返回匹配的处理函数数量
+    pub fn dispatch(&self, topic: &str, payload: &[u8]) -> usize {
+        let mut matched = 0;
+        for route in self.routes.iter() {
+            let Some(params) = topic_matches(&route.pattern, topic) else {
+                continue;
+            };
+            matched += 1;
+            let message = Message { topic: topic.to_string(), payload: payload.to_vec(), params };
+            let pattern = route.pattern.clone();
+            let task = tokio::spawn((route.handler)(message));
+            tokio::spawn(async move {
+                match task.await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!("MQTT handler for {} failed: {:#}", pattern, e),
+                    Err(e) => error!("MQTT handler for {} panicked: {}", pattern, e),
+                }
+            });
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        assert_eq!(topic_matches("sensors/+/ph", "sensors/3/ph"), Some(vec!["3".to_string()]));
+        assert_eq!(topic_matches("sensors/+/ph", "sensors/3/tds"), None);
+        assert_eq!(topic_matches("sensors/+/ph", "sensors/3/ph/raw"), None);
+        assert_eq!(topic_matches("provision/request", "provision/request"), Some(vec![]));
+        assert_eq!(
+            topic_matches("devices/+/cmd/#", "devices/7/cmd/ack/1"),
+            Some(vec!["7".to_string(), "ack/1".to_string()])
+        );
+        assert_eq!(topic_matches("devices/#", "devices"), Some(vec![String::new()]));
+        assert_eq!(topic_matches("a/#/b", "a/x/b"), None);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_isolates_handlers() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let router = TopicRouter::new()
+            .route("devices/+/status", QoS::AtLeastOnce, |_| async {
+                anyhow::bail!("broken handler")
+            })
+            .route("devices/+/status", QoS::AtLeastOnce, move |message: Message| {
+                let tx = tx.clone();
+                async move {
+                    tx.send(message.param::<i32>(0))?;
+                    Ok(())
+                }
+            });
+        assert_eq!(router.subscriptions().len(), 2);
+        assert_eq!(router.dispatch("devices/5/status", b"online"), 2);
+        assert_eq!(router.dispatch("devices/5/other", b""), 0);
+        assert_eq!(rx.recv().await, Some(Some(5)));
+    }
+}
@@ -1,19 +1,59 @@
-use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
-use std::{collections::HashSet, error::Error, sync::Arc, time::Duration};
+use crate::database::redb::DbManager as RedbManager;
+use crate::mqtt::router::TopicRouter;
+use crate::services::fault_injection;
+use bincode::{Decode, Encode};
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Outgoing, Packet, QoS};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{
-    sync::{mpsc, Mutex},
+    sync::{Mutex, Notify},
     task, time,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// 离线队列表名
+///
+/// 待发送的消息先写入 redb，收到 broker 确认（QoS 0 为写出）后才删除，
+/// 进程重启或 broker 断线期间的消息在恢复连接后按顺序补发。
+const OUTBOX_TABLE: &str = "mqtt_outbox";
+/// 每次从离线队列读取的条数
+const DRAIN_BATCH: usize = 50;
 
-/// 消息队列条目
-#[derive(Clone, Debug)]
+/// 离线队列条目
+#[derive(Encode, Decode, Debug)]
 struct PendingMessage {
     topic: String,
     payload: Vec<u8>,
-    qos: QoS,
-    retries: u8,
-    id: u64, // 消息唯一 ID，用于幂等或日志
+    qos: u8,
+}
+
+/// 消息 ID 补零作为键，保证按字符串排序即按写入顺序
+fn outbox_key(id: u64) -> String {
+    format!("{:020}", id)
+}
+
+fn qos_from_u8(qos: u8) -> QoS {
+    match qos {
+        0 => QoS::AtMostOnce,
+        2 => QoS::ExactlyOnce,
+        _ => QoS::AtLeastOnce,
+    }
+}
+
+/// 已交给客户端、等待 broker 确认的消息
+#[derive(Debug, Default)]
+struct Delivery {
+    /// 已读取到的最大消息 ID，之后只读取更新的消息
+    cursor: u64,
+    /// 已调用 publish、尚未分配 pkid 的消息，按发送顺序排列
+    handed_off: VecDeque<u64>,
+    /// pkid -> 消息 ID
+    awaiting_ack: HashMap<u16, u64>,
 }
 
 /// 异步 MQTT 工具类
@@ -21,34 +61,57 @@ struct PendingMessage {
 pub struct MqttManager {
     client: AsyncClient,
     eventloop: Arc<Mutex<EventLoop>>,
-    tx: mpsc::Sender<PendingMessage>,
-    rx: Arc<Mutex<mpsc::Receiver<PendingMessage>>>,
+    outbox: Arc<RedbManager>,
+    outbox_max_messages: u64,
+    delivery: Arc<Mutex<Delivery>>,
+    connected: Arc<AtomicBool>,
+    wake: Arc<Notify>,
     subscribed_topics: Arc<Mutex<HashSet<String>>>, // 自动重连用
-    msg_counter: Arc<Mutex<u64>>,                   // 消息 ID
+    msg_counter: Arc<Mutex<u64>>,                   // 消息 ID，同时作为离线队列的键
+}
+
+impl std::fmt::Debug for MqttManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttManager").finish_non_exhaustive()
+    }
 }
 
 impl MqttManager {
-    /// 创建 MQTT 客户端
+    /// 创建 MQTT 客户端，`outbox_path` 为离线队列文件
     pub async fn new(
         client_id: &str,
         broker: &str,
         port: u16,
         keep_alive_secs: u64,
+        outbox_path: &str,
+        outbox_max_messages: u64,
     ) -> Result<Self, Box<dyn Error>> {
         let mut mqttoptions = MqttOptions::new(client_id, broker, port);
         mqttoptions.set_keep_alive(Duration::from_secs(keep_alive_secs));
         mqttoptions.set_clean_session(false);
 
         let (client, eventloop) = AsyncClient::new(mqttoptions, 10);
-        let (tx, rx) = mpsc::channel(100);
+
+        let outbox = RedbManager::new(outbox_path)?;
+        let last_id = outbox
+            .last_key(OUTBOX_TABLE)?
+            .and_then(|key| key.parse::<u64>().ok())
+            .unwrap_or(0);
+        let pending = outbox.len(OUTBOX_TABLE)?;
+        if pending > 0 {
+            info!("{} unsent MQTT messages restored from {}", pending, outbox_path);
+        }
 
         Ok(MqttManager {
             client,
             eventloop: Arc::new(Mutex::new(eventloop)),
-            tx,
-            rx: Arc::new(Mutex::new(rx)),
+            outbox: Arc::new(outbox),
+            outbox_max_messages,
+            delivery: Arc::new(Mutex::new(Delivery::default())),
+            connected: Arc::new(AtomicBool::new(false)),
+            wake: Arc::new(Notify::new()),
             subscribed_topics: Arc::new(Mutex::new(HashSet::new())),
-            msg_counter: Arc::new(Mutex::new(0)),
+            msg_counter: Arc::new(Mutex::new(last_id)),
         })
     }
 
@@ -61,52 +124,148 @@ impl MqttManager {
         Ok(())
     }
 
-    /// 将消息加入发送队列
+    /// 将消息写入离线队列，连接可用时按顺序发送
     pub async fn enqueue_publish(&self, topic: &str, payload: Vec<u8>, qos: QoS) {
+        // 持有计数锁直到写入完成，保证 ID 顺序与写入顺序一致
         let mut counter = self.msg_counter.lock().await;
         *counter += 1;
+        let id = *counter;
         let msg = PendingMessage {
             topic: topic.to_string(),
             payload,
-            qos,
-            retries: 0,
-            id: *counter,
+            qos: qos as u8,
         };
-        let _ = self.tx.send(msg).await;
+
+        let outbox = self.outbox.clone();
+        let max_messages = self.outbox_max_messages;
+        let result = task::spawn_blocking(move || {
+            if outbox.len(OUTBOX_TABLE)? >= max_messages {
+                for (key, oldest) in outbox.range_after::<PendingMessage>(OUTBOX_TABLE, None, 1)? {
+                    warn!("MQTT outbox full, dropping oldest message to {}", oldest.topic);
+                    outbox.delete(OUTBOX_TABLE, &key)?;
+                }
+            }
+            outbox.put(OUTBOX_TABLE, &outbox_key(id), &msg)
+        })
+        .await;
+        drop(counter);
+
+        match result {
+            Ok(Ok(())) => self.wake.notify_one(),
+            Ok(Err(e)) => error!("Failed to persist MQTT message {} to {}: {}", id, topic, e),
+            Err(e) => error!("Failed to persist MQTT message {} to {}: {}", id, topic, e),
+        }
     }
 
-    /// 循环处理消息队列，自动重发
-    async fn process_queue(&self) {
-        let rx = self.rx.clone();
-        let client = self.client.clone();
+    /// 离线队列中尚未确认的消息数
+    pub fn pending_messages(&self) -> u64 {
+        self.outbox.len(OUTBOX_TABLE).unwrap_or(0)
+    }
 
+    /// 循环读取离线队列并发送，断线时暂停，恢复后从最早未确认的消息继续
+    async fn process_queue(&self) {
         loop {
-            let mut rx_lock = rx.lock().await;
-            if let Some(mut msg) = rx_lock.recv().await {
-                drop(rx_lock);
+            if !self.connected.load(Ordering::Acquire) {
+                self.wake.notified().await;
+                continue;
+            }
+
+            let cursor = self.delivery.lock().await.cursor;
+            let outbox = self.outbox.clone();
+            let batch = task::spawn_blocking(move || {
+                let after = (cursor > 0).then(|| outbox_key(cursor));
+                outbox.range_after::<PendingMessage>(OUTBOX_TABLE, after.as_deref(), DRAIN_BATCH)
+            })
+            .await;
+
+            let batch = match batch {
+                Ok(Ok(batch)) => batch,
+                Ok(Err(e)) => {
+                    error!("Failed to read MQTT outbox: {}", e);
+                    time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to read MQTT outbox: {}", e);
+                    time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+            if batch.is_empty() {
+                self.wake.notified().await;
+                continue;
+            }
 
-                let result = client
-                    .publish(&msg.topic, msg.qos, false, msg.payload.clone())
+            for (key, msg) in batch {
+                if !self.connected.load(Ordering::Acquire) {
+                    break;
+                }
+                let Ok(id) = key.parse::<u64>() else {
+                    continue;
+                };
+
+                // 注入的丢包按发送失败处理，消息留在离线队列中等待重发
+                if fault_injection::drop_mqtt_message(&msg.topic) {
+                    time::sleep(Duration::from_secs(1)).await;
+                    break;
+                }
+
+                {
+                    let mut delivery = self.delivery.lock().await;
+                    delivery.cursor = id;
+                    delivery.handed_off.push_back(id);
+                }
+
+                let result = self
+                    .client
+                    .publish(&msg.topic, qos_from_u8(msg.qos), false, msg.payload)
                     .await;
 
                 if let Err(e) = result {
-                    error!(
-                        "Publish error: {:?}, msg_id: {}, retries: {}",
-                        e, msg.id, msg.retries
-                    );
-                    if msg.retries < 5 {
-                        msg.retries += 1;
-                        time::sleep(Duration::from_secs(1)).await;
-                        let _ = self.tx.send(msg).await;
-                    } else {
-                        error!("Message dropped after 5 retries: {:?}", msg);
-                    }
-                } else {
-                    info!("Published message to {} (id={})", msg.topic, msg.id);
-                    time::sleep(Duration::from_millis(50)).await; // 节流
+                    // 消息仍在离线队列中，重连后重发
+                    error!("Publish error: {:?}, msg_id: {}", e, id);
+                    self.delivery.lock().await.handed_off.pop_back();
+                    time::sleep(Duration::from_secs(1)).await;
+                    break;
                 }
-            } else {
-                time::sleep(Duration::from_millis(10)).await;
+                info!("Published message to {} (id={})", msg.topic, id);
+                time::sleep(Duration::from_millis(50)).await; // 节流
+            }
+        }
+    }
+
+    /// 连接建立后从队首重新发送全部未确认的消息
+    async fn on_connected(&self) {
+        *self.delivery.lock().await = Delivery::default();
+        self.connected.store(true, Ordering::Release);
+        self.wake.notify_one();
+    }
+
+    /// 根据发送与确认事件从离线队列删除已送达的消息
+    async fn track_delivery(&self, event: &Event) {
+        let delivered = {
+            let mut delivery = self.delivery.lock().await;
+            match event {
+                Event::Outgoing(Outgoing::Publish(pkid)) => match delivery.handed_off.pop_front() {
+                    // QoS 0 没有确认，写出即视为送达
+                    Some(id) if *pkid == 0 => Some(id),
+                    Some(id) => {
+                        delivery.awaiting_ack.insert(*pkid, id);
+                        None
+                    }
+                    None => None,
+                },
+                Event::Incoming(Packet::PubAck(ack)) => delivery.awaiting_ack.remove(&ack.pkid),
+                Event::Incoming(Packet::PubComp(comp)) => delivery.awaiting_ack.remove(&comp.pkid),
+                _ => None,
+            }
+        };
+
+        if let Some(id) = delivered {
+            let outbox = self.outbox.clone();
+            let result = task::spawn_blocking(move || outbox.delete(OUTBOX_TABLE, &outbox_key(id))).await;
+            if let Ok(Err(e)) = result {
+                error!("Failed to remove delivered MQTT message {}: {}", id, e);
             }
         }
     }
@@ -152,8 +311,10 @@ impl MqttManager {
                 match event_result {
                     Ok(event) => {
                         // callback 在锁外执行
+                        manager_for_loop.track_delivery(&event).await;
                         match &event {
                             Event::Incoming(Packet::ConnAck(connack)) => {
+                                manager_for_loop.on_connected().await;
                                 if connack.session_present {
                                     info!("MQTT session resumed, resubscribing topics...");
                                     manager_for_loop.resubscribe_all().await;
@@ -166,6 +327,7 @@ impl MqttManager {
                         callback(event);
                     }
                     Err(e) => {
+                        manager_for_loop.connected.store(false, Ordering::Release);
                         error!("MQTT event loop error: {:?}, retrying in 5s...", e);
                         time::sleep(Duration::from_secs(5)).await;
                     }
@@ -173,6 +335,30 @@ impl MqttManager {
             }
         });
     }
+
+    /// 按主题路由分发收到的消息，并订阅路由中的全部主题
+    pub async fn start_router(&self, router: TopicRouter) -> Result<(), Box<dyn Error>> {
+        let subscriptions = router.subscriptions();
+        self.start_event_loop(move |event| {
+            if let Event::Incoming(Packet::Publish(publish)) = event {
+                if fault_injection::drop_mqtt_message(&publish.topic) {
+                    return;
+                }
+                if router.dispatch(&publish.topic, &publish.payload) == 0 {
+                    warn!("No MQTT handler for topic {}", publish.topic);
+                }
+            }
+        })
+        .await;
+
+        let mut subscribed = HashSet::new();
+        for (pattern, qos) in subscriptions {
+            if subscribed.insert(pattern.clone()) {
+                self.subscribe(&pattern, qos).await?;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// 测试函数
@@ -180,7 +366,7 @@ pub async fn mqtt_test() -> Result<(), Box<dyn Error>> {
     // 初始化 tracing 日志
     tracing_subscriber::fmt::init();
 
-    let mqtt = MqttManager::new("rust-client", "192.168.100.100", 1883, 30).await?;
+    let mqtt = MqttManager::new("rust-client", "192.168.100.100", 1883, 30, "mqtt_outbox.redb", 10_000).await?;
 
     // 启动事件循环
     mqtt.start_event_loop(|event| match event {
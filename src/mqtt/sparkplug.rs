@@ -0,0 +1,440 @@
+//! Sparkplug B 接入
+//!
+//! 主题格式为 `spBv1.0/{组}/{消息类型}/{边缘节点}[/{设备}]`，载荷为 protobuf。
+//! NBIRTH/DBIRTH 记录指标别名并把映射的设备置为在线，NDATA/DDATA 按配置的指标映射
+//! 写入测量值，NDEATH/DDEATH 把设备置为离线。NDEATH 的 bdSeq 与最近一次 NBIRTH 不一致时
+//! 是旧连接的遗嘱，忽略；收到无法解析的别名时向边缘节点发送重发 BIRTH 的请求。
+
+use crate::config::mqtt::{SparkplugConfig, SparkplugDevice};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device_state_event::{STATE_OFFLINE, STATE_ONLINE};
+use crate::mqtt::router::Message;
+use crate::mqtt::rumqtt::MqttManager;
+use crate::services::cache::HotCache;
+use crate::services::device_state;
+use crate::services::measurement::NewMeasurement;
+use crate::services::read_only::ReadOnlyMode;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use prost::Message as _;
+use proto::payload::metric::Value;
+use proto::payload::Metric;
+use proto::Payload;
+use rumqttc::QoS;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+
+pub mod proto {
+    tonic::include_proto!("org.eclipse.tahu.protobuf");
+}
+
+pub const NAMESPACE: &str = "spBv1.0";
+
+/// 节点出生/死亡证书中的会话序号
+const BD_SEQ: &str = "bdSeq";
+const REBIRTH: &str = "Node Control/Rebirth";
+/// Sparkplug 数据类型 Boolean
+const DATATYPE_BOOLEAN: u32 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    NBirth,
+    NDeath,
+    DBirth,
+    DDeath,
+    NData,
+    DData,
+    NCmd,
+    DCmd,
+}
+
+impl MessageType {
+    fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "NBIRTH" => Self::NBirth,
+            "NDEATH" => Self::NDeath,
+            "DBIRTH" => Self::DBirth,
+            "DDEATH" => Self::DDeath,
+            "NDATA" => Self::NData,
+            "DDATA" => Self::DData,
+            "NCMD" => Self::NCmd,
+            "DCMD" => Self::DCmd,
+            _ => return None,
+        })
+    }
+
+    fn is_device(self) -> bool {
+        matches!(self, Self::DBirth | Self::DDeath | Self::DData | Self::DCmd)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topic {
+    pub group_id: String,
+    pub message_type: MessageType,
+    pub edge_node: String,
+    /// 节点级消息为空
+    pub device: Option<String>,
+}
+
+/// 解析 Sparkplug 主题，STATE 等非节点消息返回 None
+pub fn parse_topic(topic: &str) -> Option<Topic> {
+    let levels: Vec<&str> = topic.split('/').collect();
+    let (namespace, group_id, message_type, edge_node) =
+        (levels.first()?, levels.get(1)?, levels.get(2)?, levels.get(3)?);
+    if *namespace != NAMESPACE {
+        return None;
+    }
+    let message_type = MessageType::parse(message_type)?;
+    let device = match (message_type.is_device(), &levels[4..]) {
+        (false, []) => None,
+        (true, [device]) => Some(device.to_string()),
+        _ => return None,
+    };
+    Some(Topic {
+        group_id: group_id.to_string(),
+        message_type,
+        edge_node: edge_node.to_string(),
+        device,
+    })
+}
+
+pub fn decode(payload: &[u8]) -> Result<Payload, prost::DecodeError> {
+    Payload::decode(payload)
+}
+
+pub fn encode(payload: &Payload) -> Vec<u8> {
+    payload.encode_to_vec()
+}
+
+/// 指标的数值，布尔值为 0/1，字符串和空值返回 None
+pub fn metric_value(metric: &Metric) -> Option<f64> {
+    if metric.is_null() {
+        return None;
+    }
+    match metric.value.as_ref()? {
+        Value::IntValue(v) => Some(*v as f64),
+        Value::LongValue(v) => Some(*v as f64),
+        Value::FloatValue(v) => Some(*v as f64),
+        Value::DoubleValue(v) => Some(*v),
+        Value::BooleanValue(v) => Some(if *v { 1.0 } else { 0.0 }),
+        Value::StringValue(_) | Value::BytesValue(_) => None,
+    }
+}
+
+fn bd_seq(payload: &Payload) -> Option<u64> {
+    payload.metrics.iter().find(|m| m.name() == BD_SEQ).and_then(|m| match m.value {
+        Some(Value::IntValue(v)) => Some(v as u64),
+        Some(Value::LongValue(v)) => Some(v),
+        _ => None,
+    })
+}
+
+fn timestamp(millis: Option<u64>) -> DateTime<Utc> {
+    millis
+        .and_then(|ms| DateTime::from_timestamp_millis(ms as i64))
+        .unwrap_or_else(Utc::now)
+}
+
+/// 请求边缘节点重发 BIRTH 的 NCMD 主题和载荷
+pub fn rebirth_request(group_id: &str, edge_node: &str) -> (String, Vec<u8>) {
+    let now = Utc::now().timestamp_millis() as u64;
+    let payload = Payload {
+        timestamp: Some(now),
+        metrics: vec![Metric {
+            name: Some(REBIRTH.to_string()),
+            timestamp: Some(now),
+            datatype: Some(DATATYPE_BOOLEAN),
+            value: Some(Value::BooleanValue(true)),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    (format!("{}/{}/NCMD/{}", NAMESPACE, group_id, edge_node), encode(&payload))
+}
+
+/// 用 BIRTH 中的别名补全指标名，返回是否有无法解析的别名
+fn resolve<'a>(
+    aliases: Option<&HashMap<u64, String>>,
+    metrics: &'a [Metric],
+) -> (Vec<(String, &'a Metric)>, bool) {
+    let mut resolved = Vec::with_capacity(metrics.len());
+    let mut unknown = false;
+    for metric in metrics {
+        let name = match (&metric.name, metric.alias) {
+            (Some(name), _) => Some(name.clone()),
+            (None, Some(alias)) => aliases.and_then(|a| a.get(&alias)).cloned(),
+            (None, None) => None,
+        };
+        match name {
+            Some(name) => resolved.push((name, metric)),
+            None => unknown = true,
+        }
+    }
+    (resolved, unknown)
+}
+
+/// 一个边缘节点的会话
+#[derive(Debug, Default)]
+struct NodeSession {
+    bd_seq: Option<u64>,
+    /// 按设备（节点自身为 None）保存的别名表
+    aliases: HashMap<Option<String>, HashMap<u64, String>>,
+    rebirth_requested: bool,
+}
+
+type NodeKey = (String, String);
+
+#[derive(Clone)]
+pub struct Sparkplug {
+    config: Arc<SparkplugConfig>,
+    db: DbManager,
+    cache: HotCache,
+    read_only: ReadOnlyMode,
+    mqtt: MqttManager,
+    sessions: Arc<Mutex<HashMap<NodeKey, NodeSession>>>,
+}
+
+impl Sparkplug {
+    pub fn new(
+        config: SparkplugConfig,
+        db: DbManager,
+        cache: HotCache,
+        read_only: ReadOnlyMode,
+        mqtt: MqttManager,
+    ) -> Self {
+        Self {
+            config: Arc::new(config),
+            db,
+            cache,
+            read_only,
+            mqtt,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 订阅的主题模式
+    pub fn subscription(&self) -> String {
+        format!("{}/{}/#", NAMESPACE, self.config.group_id)
+    }
+
+    fn mapping(&self, edge_node: &str, device: Option<&str>) -> Option<&SparkplugDevice> {
+        self.config
+            .devices
+            .iter()
+            .find(|d| d.edge_node == edge_node && d.device.as_deref() == device)
+    }
+
+    pub async fn handle(&self, message: Message) -> anyhow::Result<()> {
+        let Some(topic) = parse_topic(&message.topic) else {
+            return Ok(());
+        };
+        let payload = decode(&message.payload)?;
+        let key = (topic.group_id.clone(), topic.edge_node.clone());
+        let device = topic.device.as_deref();
+
+        match topic.message_type {
+            MessageType::NBirth | MessageType::DBirth => {
+                let aliases: HashMap<u64, String> = payload
+                    .metrics
+                    .iter()
+                    .filter_map(|m| Some((m.alias?, m.name.clone()?)))
+                    .collect();
+                {
+                    let mut sessions = self.sessions.lock().unwrap();
+                    if topic.message_type == MessageType::NBirth {
+                        // 新会话，之前的设备别名全部作废
+                        sessions.insert(
+                            key.clone(),
+                            NodeSession { bd_seq: bd_seq(&payload), ..Default::default() },
+                        );
+                    }
+                    let session = sessions.entry(key).or_default();
+                    session.aliases.insert(topic.device.clone(), aliases);
+                }
+                info!("Sparkplug {:?} from {}", topic.message_type, message.topic);
+                let at = timestamp(payload.timestamp);
+                self.set_state(&topic.edge_node, device, STATE_ONLINE, at).await;
+                let metrics: Vec<_> =
+                    payload.metrics.iter().map(|m| (m.name().to_string(), m)).collect();
+                self.store(&topic, &payload, &metrics).await;
+            }
+            MessageType::NData | MessageType::DData => {
+                let (metrics, unknown) = {
+                    let sessions = self.sessions.lock().unwrap();
+                    let aliases = sessions.get(&key).and_then(|s| s.aliases.get(&topic.device));
+                    resolve(aliases, &payload.metrics)
+                };
+                if unknown {
+                    self.request_rebirth(&key).await;
+                }
+                self.store(&topic, &payload, &metrics).await;
+            }
+            MessageType::NDeath => {
+                let ended = {
+                    let mut sessions = self.sessions.lock().unwrap();
+                    let current = sessions.get(&key).and_then(|s| s.bd_seq);
+                    let stale = matches!((current, bd_seq(&payload)), (Some(a), Some(b)) if a != b);
+                    if !stale {
+                        sessions.remove(&key);
+                    }
+                    !stale
+                };
+                if !ended {
+                    info!("Ignoring stale Sparkplug NDEATH from {}", message.topic);
+                    return Ok(());
+                }
+                let at = timestamp(payload.timestamp);
+                // 节点离线时其下所有设备一并离线
+                let devices = self.config.devices.iter().filter(|d| d.edge_node == topic.edge_node);
+                for mapping in devices {
+                    self.record_state(mapping.device_id, STATE_OFFLINE, at).await;
+                }
+            }
+            MessageType::DDeath => {
+                if let Some(session) = self.sessions.lock().unwrap().get_mut(&key) {
+                    session.aliases.remove(&topic.device);
+                }
+                let at = timestamp(payload.timestamp);
+                self.set_state(&topic.edge_node, device, STATE_OFFLINE, at).await;
+            }
+            MessageType::NCmd | MessageType::DCmd => {}
+        }
+        Ok(())
+    }
+
+    async fn request_rebirth(&self, key: &NodeKey) {
+        if !self.config.request_rebirth {
+            return;
+        }
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = sessions.entry(key.clone()).or_default();
+            // 每个会话只请求一次，等待 NBIRTH 重置
+            if session.rebirth_requested {
+                return;
+            }
+            session.rebirth_requested = true;
+        }
+        let (topic, payload) = rebirth_request(&key.0, &key.1);
+        warn!("Unknown Sparkplug alias from {}/{}, requesting rebirth", key.0, key.1);
+        self.mqtt.enqueue_publish(&topic, payload, QoS::AtLeastOnce).await;
+    }
+
+    async fn set_state(
+        &self,
+        edge_node: &str,
+        device: Option<&str>,
+        state: &str,
+        at: DateTime<Utc>,
+    ) {
+        if let Some(mapping) = self.mapping(edge_node, device) {
+            self.record_state(mapping.device_id, state, at).await;
+        }
+    }
+
+    async fn record_state(&self, device_id: i32, state: &str, at: DateTime<Utc>) {
+        let conn = self.db.get_connection();
+        match device_state::record(conn, &self.cache, device_id, state, "sparkplug", at).await {
+            Ok(_) => {}
+            Err(AppError::InvalidInput(msg)) => {
+                warn!("Rejected Sparkplug state for device {}: {}", device_id, msg)
+            }
+            Err(e) => error!("Failed to record Sparkplug state for device {}: {:?}", device_id, e),
+        }
+    }
+
+    async fn store(&self, topic: &Topic, payload: &Payload, metrics: &[(String, &Metric)]) {
+        let Some(mapping) = self.mapping(&topic.edge_node, topic.device.as_deref()) else {
+            return;
+        };
+        for (name, metric) in metrics {
+            let Some(target) = mapping.metrics.iter().find(|m| m.name == *name) else {
+                continue;
+            };
+            let Some(value) = metric_value(metric) else {
+                continue;
+            };
+            let measurement = NewMeasurement {
+                metric_type: target.metric_type.clone(),
+                timestamp: timestamp(metric.timestamp.or(payload.timestamp)),
+                value: value * target.scale,
+                device_id: Some(mapping.device_id),
+                unit: None,
+            };
+            let conn = self.db.get_connection();
+            match self.read_only.ingest(conn, &self.cache, measurement).await {
+                Ok(_) => {}
+                Err(AppError::InvalidInput(msg)) => {
+                    warn!("Rejected Sparkplug value for device {}: {}", mapping.device_id, msg)
+                }
+                Err(e) => error!("Failed to store Sparkplug value: {:?}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topic() {
+        let topic = parse_topic("spBv1.0/plant/DDATA/gw1/pump3").unwrap();
+        assert_eq!(topic.message_type, MessageType::DData);
+        assert_eq!(topic.edge_node, "gw1");
+        assert_eq!(topic.device.as_deref(), Some("pump3"));
+        assert_eq!(parse_topic("spBv1.0/plant/NBIRTH/gw1").unwrap().device, None);
+        assert_eq!(parse_topic("spBv1.0/plant/NDATA/gw1/pump3"), None);
+        assert_eq!(parse_topic("spBv1.0/plant/DDATA/gw1"), None);
+        assert_eq!(parse_topic("spBv1.0/STATE/host1"), None);
+        assert_eq!(parse_topic("spAv1.0/plant/NDATA/gw1"), None);
+    }
+
+    #[test]
+    fn test_round_trip_and_alias() {
+        let birth = Payload {
+            timestamp: Some(1_700_000_000_000),
+            metrics: vec![
+                Metric {
+                    name: Some(BD_SEQ.to_string()),
+                    value: Some(Value::LongValue(4)),
+                    ..Default::default()
+                },
+                Metric {
+                    name: Some("Inlet/Flow".to_string()),
+                    alias: Some(7),
+                    value: Some(Value::FloatValue(12.5)),
+                    ..Default::default()
+                },
+            ],
+            seq: Some(0),
+            ..Default::default()
+        };
+        let decoded = decode(&encode(&birth)).unwrap();
+        assert_eq!(decoded, birth);
+        assert_eq!(bd_seq(&decoded), Some(4));
+        assert_eq!(metric_value(&decoded.metrics[1]), Some(12.5));
+
+        let aliases = HashMap::from([(7, "Inlet/Flow".to_string())]);
+        let data = vec![
+            Metric { alias: Some(7), value: Some(Value::BooleanValue(true)), ..Default::default() },
+            Metric { alias: Some(8), value: Some(Value::IntValue(1)), ..Default::default() },
+        ];
+        let (resolved, unknown) = resolve(Some(&aliases), &data);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0, "Inlet/Flow");
+        assert_eq!(metric_value(resolved[0].1), Some(1.0));
+        assert!(unknown);
+    }
+
+    #[test]
+    fn test_rebirth_request() {
+        let (topic, payload) = rebirth_request("plant", "gw1");
+        assert_eq!(topic, "spBv1.0/plant/NCMD/gw1");
+        let payload = decode(&payload).unwrap();
+        assert_eq!(payload.metrics[0].name(), REBIRTH);
+        assert_eq!(payload.metrics[0].value, Some(Value::BooleanValue(true)));
+    }
+}
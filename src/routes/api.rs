@@ -1,5 +1,13 @@
-use crate::{handlers::{user, device, ph_value, tds_value, turbidity_value, flow_value, alarm_rule, alarm_log, automation_rule}, app_state::AppState};
-use axum::{routing::get, Router};
+use crate::{handlers::{user, device, ph_value, tds_value, turbidity_value, flow_value, level_value, pressure_value, alarm_rule, alarm_log, automation_rule, measurement, api_key, system, remote_session, config_revision, site, area, config_bundle, serial_console, calibration_curve, tank_geometry, pump_curve, vibration, device_state, daily_summary, pwm, modbus, modbus_mapping, calendar, grafana, alarm_shelving, message_queue, alarm_kpi, trend, api_usage, kpi, actuator, report, permit, energy, dosing, control_loop, maintenance, pump_group, flow_total, calibration, lab_result, data_correction, organization, simulation}, app_state::AppState};
+use crate::middleware::api_key::require_ingest_key;
+use crate::middleware::api_usage::api_usage_middleware;
+use crate::middleware::logging::logging_middleware;
+use crate::middleware::network_policy::{network_policy_middleware, NetworkPolicy};
+use crate::middleware::rate_limit::{rate_limit_middleware, RateLimiter};
+use crate::middleware::read_only::{buffer_ingest, read_only_middleware};
+use crate::middleware::security::{cors_layer, security_headers_middleware};
+use crate::routes::static_files::create_static_router;
+use axum::{routing::{get, post}, Router};
 use std::sync::Arc;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -17,6 +25,8 @@ use utoipa_swagger_ui::SwaggerUi;
         device::create_device,
         device::update_device,
         device::delete_device,
+        device::approve_device,
+        device::authenticate_mqtt_client,
         ph_value::get_ph_values,
         ph_value::get_ph_value,
         ph_value::create_ph_value,
@@ -37,21 +47,237 @@ use utoipa_swagger_ui::SwaggerUi;
         flow_value::create_flow_value,
         flow_value::update_flow_value,
         flow_value::delete_flow_value,
+        level_value::get_level_values,
+        level_value::get_level_value,
+        level_value::create_level_value,
+        level_value::update_level_value,
+        level_value::delete_level_value,
+        pressure_value::get_pressure_values,
+        pressure_value::get_pressure_value,
+        pressure_value::create_pressure_value,
+        pressure_value::update_pressure_value,
+        pressure_value::delete_pressure_value,
+        measurement::get_metric_types,
+        measurement::get_measurements,
+        measurement::get_measurement,
+        measurement::create_measurement,
+        measurement::update_measurement,
+        measurement::delete_measurement,
+        measurement::get_measurement_corrections,
+        api_key::get_api_keys,
+        api_key::create_api_key,
+        api_key::rotate_api_key,
+        api_key::revoke_api_key,
+        system::get_network_policy,
+        remote_session::get_remote_sessions,
+        remote_session::get_remote_session,
+        remote_session::create_remote_session,
+        remote_session::close_remote_session,
         alarm_rule::get_alarm_rules,
         alarm_rule::get_alarm_rule,
         alarm_rule::create_alarm_rule,
         alarm_rule::update_alarm_rule,
         alarm_rule::delete_alarm_rule,
+        alarm_rule::get_alarm_rule_templates,
+        alarm_rule::create_alarm_rule_from_template,
         alarm_log::get_alarm_logs,
         alarm_log::get_alarm_log,
         alarm_log::create_alarm_log,
         alarm_log::update_alarm_log,
         alarm_log::delete_alarm_log,
         automation_rule::get_automation_rules,
+        automation_rule::get_active_automation_rules,
         automation_rule::get_automation_rule,
         automation_rule::create_automation_rule,
         automation_rule::update_automation_rule,
         automation_rule::delete_automation_rule,
+        device::import_devices,
+        device::export_devices,
+        config_revision::get_config_revisions,
+        config_revision::get_config_revision,
+        config_revision::rollback_config_revision,
+        config_revision::approve_config_revision,
+        config_revision::reject_config_revision,
+        site::get_sites,
+        site::get_site,
+        site::create_site,
+        site::update_site,
+        site::delete_site,
+        site::get_site_devices,
+        site::get_site_latest,
+        area::get_areas,
+        area::get_area,
+        area::create_area,
+        area::update_area,
+        area::delete_area,
+        area::get_area_devices,
+        area::get_area_latest,
+        config_bundle::export_config_bundle,
+        config_bundle::import_config_bundle,
+        device::get_devices_latest,
+        device::get_device_latest,
+        system::get_read_only,
+        system::set_read_only,
+        system::get_queues,
+        serial_console::get_serial_ports,
+        serial_console::connect_serial_console,
+        serial_console::get_serial_sessions,
+        device::send_device_command,
+        device::get_device_commands,
+        device::get_device_command,
+        device::stream_device_command,
+        calibration_curve::get_calibration_curves,
+        calibration_curve::get_calibration_curve,
+        calibration_curve::create_calibration_curve,
+        calibration_curve::update_calibration_curve,
+        calibration_curve::delete_calibration_curve,
+        calibration_curve::preview_calibration_curve,
+        calibration::get_calibrations,
+        calibration::get_calibration,
+        calibration::create_calibration,
+        calibration::get_due_calibrations,
+        calibration::delete_calibration,
+        lab_result::get_analytes,
+        lab_result::get_lab_results,
+        lab_result::get_lab_result,
+        lab_result::create_lab_result,
+        lab_result::update_lab_result,
+        lab_result::delete_lab_result,
+        lab_result::get_lab_result_corrections,
+        data_correction::get_data_corrections,
+        organization::get_organizations,
+        organization::get_organization,
+        organization::create_organization,
+        organization::update_organization,
+        organization::delete_organization,
+        tank_geometry::get_tank_geometry,
+        tank_geometry::put_tank_geometry,
+        tank_geometry::delete_tank_geometry,
+        pump_curve::get_pump_curve,
+        pump_curve::put_pump_curve,
+        pump_curve::delete_pump_curve,
+        pump_curve::get_pump_efficiency,
+        vibration::create_vibration,
+        vibration::get_vibration,
+        vibration::get_vibration_trend,
+        vibration::get_vibration_limit,
+        vibration::put_vibration_limit,
+        vibration::delete_vibration_limit,
+        device_state::create_state_event,
+        device_state::get_state_events,
+        device_state::get_state_durations,
+        device_state::get_run_hours,
+        flow_total::get_flow_total,
+        device_state::get_fleet_run_hours,
+        daily_summary::get_device_daily_summaries,
+        daily_summary::rebuild_daily_summary,
+        pwm::get_pwm,
+        pwm::set_pwm,
+        modbus::write_register,
+        modbus::get_writes,
+        measurement::get_measurement_aggregate,
+        measurement::create_measurements_binary,
+        system::get_network,
+        measurement::get_measurement_interpolated,
+        system::get_stats,
+        system::get_query_metrics,
+        system::reset_query_metrics,
+        modbus_mapping::get_modbus_mappings,
+        modbus_mapping::get_modbus_mapping,
+        modbus_mapping::create_modbus_mapping,
+        modbus_mapping::update_modbus_mapping,
+        modbus_mapping::delete_modbus_mapping,
+        calendar::get_shifts,
+        calendar::create_shift,
+        calendar::update_shift,
+        calendar::delete_shift,
+        calendar::get_days,
+        calendar::create_day,
+        calendar::update_day,
+        calendar::delete_day,
+        calendar::get_status,
+        calendar::get_periods,
+        grafana::health,
+        grafana::search,
+        grafana::query,
+        grafana::annotations,
+        alarm_shelving::shelve_alarm,
+        alarm_shelving::get_alarm_shelves,
+        alarm_shelving::unshelve_alarm,
+        alarm_shelving::get_shelving_stats,
+        message_queue::get_dead_letters,
+        message_queue::replay_dead_letters,
+        message_queue::get_event_schemas,
+        message_queue::get_event_schema_changelog,
+        alarm_kpi::get_alarm_kpis,
+        alarm_log::get_alarm_snapshot,
+        trend::get_trend,
+        api_usage::get_api_usage,
+        kpi::get_kpi_definitions,
+        kpi::get_kpi_definition,
+        kpi::create_kpi_definition,
+        kpi::update_kpi_definition,
+        kpi::delete_kpi_definition,
+        kpi::validate_kpi_definition,
+        kpi::get_kpi_value,
+        kpi::get_kpi_values,
+        actuator::set_gpio_output,
+        actuator::get_reconciliation,
+        report::get_reports,
+        report::download_report,
+        report::generate_report,
+        permit::get_permits,
+        permit::get_permit,
+        permit::create_permit,
+        permit::update_permit,
+        permit::delete_permit,
+        permit::get_exceedances,
+        permit::get_compliance_summary,
+        actuator::get_safe_state,
+        actuator::get_safe_state_events,
+        energy::get_device_energy,
+        energy::get_area_energy,
+        energy::get_energy_kpis,
+        dosing::get_tanks,
+        dosing::get_tank,
+        dosing::create_tank,
+        dosing::update_tank,
+        dosing::delete_tank,
+        dosing::refill_tank,
+        dosing::get_pumps,
+        dosing::get_pump,
+        dosing::create_pump,
+        dosing::update_pump,
+        dosing::delete_pump,
+        dosing::set_pump_speed,
+        system::get_fault_injection,
+        system::set_fault_injection,
+        simulation::inject,
+        control_loop::get_control_loops,
+        control_loop::get_control_loop,
+        control_loop::create_control_loop,
+        control_loop::update_control_loop,
+        control_loop::delete_control_loop,
+        control_loop::tune_control_loop,
+        control_loop::set_control_mode,
+        control_loop::get_control_status,
+        maintenance::get_plans,
+        maintenance::get_plan,
+        maintenance::create_plan,
+        maintenance::update_plan,
+        maintenance::delete_plan,
+        maintenance::get_work_orders,
+        maintenance::get_work_order,
+        maintenance::create_work_order,
+        maintenance::close_work_order,
+        pump_group::get_pump_groups,
+        pump_group::get_pump_group,
+        pump_group::create_pump_group,
+        pump_group::update_pump_group,
+        pump_group::delete_pump_group,
+        pump_group::get_duty,
+        pump_group::get_group_duty,
+        pump_group::reset_pump_group,
     ),
     components(
         schemas(
@@ -64,10 +290,17 @@ use utoipa_swagger_ui::SwaggerUi;
             crate::models::alarm_rule::Model,
             crate::models::alarm_log::Model,
             crate::models::automation_rule::Model,
+            crate::models::measurement::Model,
+            crate::models::api_key::Model,
+            crate::models::remote_session::Model,
             user::CreateUserRequest,
             user::UpdateUserRequest,
             device::CreateDeviceRequest,
             device::UpdateDeviceRequest,
+            device::ApproveDeviceResponse,
+            device::MqttAuthRequest,
+            device::MqttAuthResponse,
+            crate::services::provisioning::IssuedCredentials,
             ph_value::CreatePhValueRequest,
             ph_value::UpdatePhValueRequest,
             tds_value::CreateTdsValueRequest,
@@ -76,12 +309,199 @@ use utoipa_swagger_ui::SwaggerUi;
             turbidity_value::UpdateTurbidityValueRequest,
             flow_value::CreateFlowValueRequest,
             flow_value::UpdateFlowValueRequest,
+            level_value::CreateLevelValueRequest,
+            level_value::UpdateLevelValueRequest,
+            pressure_value::CreatePressureValueRequest,
+            pressure_value::UpdatePressureValueRequest,
             alarm_rule::CreateAlarmRuleRequest,
             alarm_rule::UpdateAlarmRuleRequest,
+            alarm_rule::CreateFromTemplateRequest,
+            crate::services::alarm_template::AlarmRuleTemplate,
             alarm_log::CreateAlarmLogRequest,
             alarm_log::UpdateAlarmLogRequest,
             automation_rule::CreateAutomationRuleRequest,
             automation_rule::UpdateAutomationRuleRequest,
+            measurement::CreateMeasurementRequest,
+            measurement::UpdateMeasurementRequest,
+            crate::services::metric_registry::MetricTypeResponse,
+            api_key::CreateApiKeyRequest,
+            api_key::IssuedApiKeyResponse,
+            crate::config::security::NetworkPolicyConfig,
+            crate::config::security::NetworkRuleConfig,
+            remote_session::CreateRemoteSessionRequest,
+            device::ImportReport,
+            device::ImportRowError,
+            device::TransferFormat,
+            crate::models::config_revision::Model,
+            config_revision::ConfigRevisionDetail,
+            crate::services::config_revision::FieldChange,
+            crate::models::site::Model,
+            crate::models::area::Model,
+            site::CreateSiteRequest,
+            site::UpdateSiteRequest,
+            area::CreateAreaRequest,
+            area::UpdateAreaRequest,
+            crate::services::config_bundle::ConfigBundle,
+            crate::services::config_bundle::BundlePayload,
+            crate::services::config_bundle::BundleImportSummary,
+            crate::services::config_bundle::ImportCount,
+            crate::services::latest::DeviceLatest,
+            crate::services::latest::LatestValue,
+            system::SetReadOnlyRequest,
+            crate::services::read_only::ReadOnlyStatus,
+            system::QueueDepths,
+            crate::models::serial_session::Model,
+            device::DeviceCommandRequest,
+            device::DeviceCommandResponse,
+            crate::models::device_command::Model,
+            crate::models::calibration_curve::Model,
+            calibration_curve::CreateCalibrationCurveRequest,
+            calibration_curve::UpdateCalibrationCurveRequest,
+            calibration_curve::PreviewResponse,
+            crate::models::calibration::Model,
+            calibration::CalibrationPoint,
+            calibration::CreateCalibrationRequest,
+            crate::models::lab_result::Model,
+            lab_result::CreateLabResultRequest,
+            lab_result::UpdateLabResultRequest,
+            crate::services::lab_result::Analyte,
+            crate::models::data_correction::Model,
+            crate::models::organization::Model,
+            organization::CreateOrganizationRequest,
+            organization::UpdateOrganizationRequest,
+            crate::models::tank_geometry::Model,
+            tank_geometry::TankGeometryRequest,
+            crate::models::pump_curve::Model,
+            pump_curve::PumpCurveRequest,
+            crate::services::pump::PumpEfficiency,
+            crate::models::vibration_record::Model,
+            crate::models::vibration_limit::Model,
+            vibration::VibrationRequest,
+            vibration::VibrationLimitRequest,
+            crate::services::vibration::BandLimit,
+            crate::services::vibration::TrendPoint,
+            crate::models::device_state_event::Model,
+            device_state::CreateStateEventRequest,
+            crate::services::device_state::StateDuration,
+            crate::services::run_hours::DeviceRunHours,
+            crate::services::run_hours::RunDay,
+            crate::services::flow_total::DeviceFlowTotal,
+            crate::models::flow_total::Model,
+            crate::services::daily_summary::DeviceDay,
+            crate::services::daily_summary::MetricSummary,
+            daily_summary::RebuildSummaryRequest,
+            daily_summary::RebuildSummaryResponse,
+            pwm::SetPwmRequest,
+            crate::models::modbus_write::Model,
+            modbus::WriteRegisterRequest,
+            crate::services::pwm::PwmStatus,
+            crate::services::measurement::AggregatePoint,
+            measurement::BinaryIngestSummary,
+            measurement::BinaryIngestError,
+            crate::services::network::InterfaceStatus,
+            crate::utils::ethernet::InterfaceCounters,
+            crate::services::compression::InterpolatedPoint,
+            crate::services::system::SystemStats,
+            crate::database::query_metrics::QueryMetricsSnapshot,
+            crate::database::query_metrics::QueryStats,
+            crate::database::query_metrics::HistogramBucket,
+            crate::models::modbus_mapping::Model,
+            modbus_mapping::CreateModbusMappingRequest,
+            modbus_mapping::UpdateModbusMappingRequest,
+            crate::models::calendar_shift::Model,
+            crate::models::calendar_day::Model,
+            calendar::CreateCalendarShiftRequest,
+            calendar::UpdateCalendarShiftRequest,
+            calendar::CreateCalendarDayRequest,
+            calendar::UpdateCalendarDayRequest,
+            crate::services::calendar::CalendarStatus,
+            crate::services::calendar::ShiftPeriod,
+            grafana::TimeRange,
+            grafana::SearchRequest,
+            grafana::QueryTarget,
+            grafana::QueryRequest,
+            grafana::AnnotationQuery,
+            grafana::AnnotationRequest,
+            crate::services::grafana::SearchResult,
+            crate::services::grafana::TimeSeries,
+            crate::services::grafana::Annotation,
+            crate::models::alarm_shelf::Model,
+            alarm_shelving::ShelveAlarmRequest,
+            crate::services::alarm_shelving::ShelvingStats,
+            crate::message_queue::rabbitmq::DeadLetter,
+            message_queue::ReplayDeadLettersRequest,
+            message_queue::ReplayDeadLettersResponse,
+            crate::message_queue::schema::SchemaChange,
+            crate::services::alarm_kpi::AlarmKpis,
+            crate::services::alarm_kpi::ChatteringAlarm,
+            crate::services::alarm_kpi::StandingAlarm,
+            crate::services::alarm_snapshot::AlarmSnapshotView,
+            crate::services::alarm_snapshot::SnapshotSeries,
+            crate::services::alarm_snapshot::SnapshotPoint,
+            crate::services::trend::Trend,
+            crate::services::trend::TrendPoint,
+            crate::services::trend::Resolution,
+            crate::services::api_usage::EndpointUsage,
+            crate::models::kpi_definition::Model,
+            kpi::CreateKpiRequest,
+            kpi::UpdateKpiRequest,
+            kpi::KpiValidation,
+            crate::services::kpi::Binding,
+            crate::services::kpi::KpiValue,
+            crate::models::gpio_write::Model,
+            actuator::SetGpioOutputRequest,
+            crate::services::reconcile::Reconciliation,
+            crate::services::reconcile::ActuatorCheck,
+            crate::models::report::Model,
+            report::ReportEntry,
+            report::GenerateReportRequest,
+            crate::models::discharge_permit::Model,
+            crate::models::permit_exceedance::Model,
+            permit::CreatePermitRequest,
+            permit::UpdatePermitRequest,
+            crate::services::permit::ComplianceSummary,
+            crate::services::permit::PermitCompliance,
+            crate::models::safe_state_event::Model,
+            crate::services::safe_state::ActuatorSafeState,
+            crate::models::daily_energy::Model,
+            crate::services::energy::DeviceEnergy,
+            crate::services::energy::AreaEnergy,
+            crate::services::energy::DailyEnergyKpi,
+            crate::services::energy::EnergyKpis,
+            crate::models::chemical_tank::Model,
+            crate::models::dosing_pump::Model,
+            dosing::CreateTankRequest,
+            dosing::UpdateTankRequest,
+            dosing::RefillRequest,
+            dosing::CreatePumpRequest,
+            dosing::UpdatePumpRequest,
+            dosing::PumpSpeedRequest,
+            system::SetFaultInjectionRequest,
+            crate::services::fault_injection::FaultInjectionStatus,
+            simulation::InjectRequest,
+            crate::services::simulation::ScenarioStep,
+            crate::services::simulation::SensorFault,
+            crate::services::simulation::SampleOutcome,
+            crate::services::simulation::InjectedSample,
+            crate::services::simulation::InjectionReport,
+            crate::models::control_loop::Model,
+            control_loop::CreateControlLoopRequest,
+            control_loop::UpdateControlLoopRequest,
+            control_loop::TuneControlLoopRequest,
+            control_loop::SetControlModeRequest,
+            crate::control::LoopStatus,
+            crate::models::maintenance_plan::Model,
+            crate::models::work_order::Model,
+            maintenance::CreatePlanRequest,
+            maintenance::UpdatePlanRequest,
+            maintenance::CreateWorkOrderRequest,
+            maintenance::CloseWorkOrderRequest,
+            crate::models::pump_group::Model,
+            pump_group::CreatePumpGroupRequest,
+            pump_group::UpdatePumpGroupRequest,
+            pump_group::ResetPumpGroupResponse,
+            crate::services::pump_group::DutyStatus,
+            crate::services::pump_group::MemberFault,
         )
     ),
     tags(
@@ -91,15 +511,56 @@ use utoipa_swagger_ui::SwaggerUi;
         (name = "TDS Values", description = "TDS值数据接口"),
         (name = "Turbidity Values", description = "浊度值数据接口"),
         (name = "Flow Values", description = "流量值数据接口"),
+        (name = "Level Values", description = "液位值数据接口"),
+        (name = "Pressure Values", description = "压力值数据接口"),
         (name = "Alarm Rules", description = "报警规则接口"),
         (name = "Alarm Logs", description = "报警日志接口"),
         (name = "Automation Rules", description = "自动化规则接口"),
+        (name = "Measurements", description = "通用测量值接口"),
+        (name = "API Keys", description = "机器客户端API Key管理接口"),
+        (name = "System", description = "系统配置与状态接口"),
+        (name = "Simulation", description = "传感器模拟注入接口（仅调试构建）"),
+        (name = "Remote Access", description = "厂商远程访问代理接口"),
+        (name = "Config Revisions", description = "配置版本与回滚API"),
+        (name = "Sites", description = "厂站与区域管理API"),
+        (name = "Config Bundle", description = "配置包导出/导入API"),
+        (name = "Serial Console", description = "串口远程控制台接口"),
+        (name = "Calibration", description = "传感器标定曲线与现场校准接口"),
+        (name = "Lab Results", description = "化验结果录入与修正接口"),
+        (name = "Data Corrections", description = "数据修正记录接口"),
+        (name = "Organizations", description = "组织（租户）管理接口"),
+        (name = "Vibration", description = "振动状态监测"),
+        (name = "Summaries", description = "每日汇总"),
+        (name = "Modbus", description = "Modbus 寄存器映射接口"),
+        (name = "Calendar", description = "班次与节假日日历接口"),
+        (name = "Grafana", description = "Grafana JSON 数据源接口"),
+        (name = "Alarm Shelving", description = "报警搁置接口"),
+        (name = "Message Queue", description = "消息队列死信与事件 Schema 接口"),
+        (name = "API Usage", description = "接口调用统计"),
+        (name = "KPI", description = "自定义 KPI 公式"),
+        (name = "Actuators", description = "执行器输出与状态核对"),
+        (name = "Reports", description = "合规报表"),
+        (name = "Compliance", description = "排污许可限值与合规"),
+        (name = "Energy", description = "能耗计量与单位水量能耗"),
+        (name = "Dosing", description = "加药泵与药剂储罐"),
+        (name = "Control", description = "PID 控制回路"),
+        (name = "Maintenance", description = "维护计划与工单"),
+        (name = "Pump Groups", description = "主备泵组轮换与故障切换"),
     )
 )]
 struct ApiDoc;
 
-pub fn create_api_router() -> Router<Arc<AppState>> {
-    Router::new()
+pub fn create_api_router(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    let settings = &state.settings;
+    let rate_limiter = RateLimiter::new(settings.rate_limit.clone());
+    // 数据上报接口要求 X-Api-Key
+    let ingest_auth = axum::middleware::from_fn_with_state(state.clone(), require_ingest_key);
+    // 只读期间上报数据写入暂存（在认证之后执行）
+    let ingest_buffer = axum::middleware::from_fn_with_state(state.clone(), buffer_ingest);
+    let security_headers = Arc::new(settings.security_headers.clone());
+    let network_policy = Arc::new(NetworkPolicy::from_config(&settings.network_policy));
+
+    let router = Router::new()
         // 用户管理路由
         .route("/users", get(user::get_users).post(user::create_user))
         .route(
@@ -116,8 +577,47 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
                 .put(device::update_device)
                 .delete(device::delete_device),
         )
+        .route("/devices/{id}/approve", post(device::approve_device))
+        .route("/mqtt/auth", post(device::authenticate_mqtt_client))
+        .route("/devices/{id}/commands", get(device::get_device_commands).post(device::send_device_command))
+        .route("/devices/{id}/commands/{command_id}", get(device::get_device_command))
+        .route("/devices/{id}/commands/{command_id}/events", get(device::stream_device_command))
+        .route(
+            "/devices/{id}/tank-geometry",
+            get(tank_geometry::get_tank_geometry)
+                .put(tank_geometry::put_tank_geometry)
+                .delete(tank_geometry::delete_tank_geometry),
+        )
+        .route(
+            "/devices/{id}/pump-curve",
+            get(pump_curve::get_pump_curve)
+                .put(pump_curve::put_pump_curve)
+                .delete(pump_curve::delete_pump_curve),
+        )
+        .route("/devices/{id}/pump-efficiency", get(pump_curve::get_pump_efficiency))
+        .route("/devices/{id}/vibration", get(vibration::get_vibration).merge(post(vibration::create_vibration).route_layer(ingest_auth.clone())))
+        .route("/devices/{id}/vibration/trend", get(vibration::get_vibration_trend))
+        .route(
+            "/devices/{id}/vibration-limits",
+            get(vibration::get_vibration_limit)
+                .put(vibration::put_vibration_limit)
+                .delete(vibration::delete_vibration_limit),
+        )
+        .route("/devices/{id}/state-events", get(device_state::get_state_events).post(device_state::create_state_event))
+        .route("/devices/{id}/state-durations", get(device_state::get_state_durations))
+        .route("/devices/{id}/run-hours", get(device_state::get_run_hours))
+        .route("/devices/{id}/flow-total", get(flow_total::get_flow_total))
+        .route("/run-hours", get(device_state::get_fleet_run_hours))
+        .route("/devices/{id}/daily-summaries", get(daily_summary::get_device_daily_summaries))
+        .route("/daily-summaries/rebuild", post(daily_summary::rebuild_daily_summary))
+        .route("/devices/{id}/pwm", get(pwm::get_pwm).post(pwm::set_pwm))
+        .route("/devices/{id}/modbus/writes", get(modbus::get_writes).post(modbus::write_register))
+        .route("/devices/import", post(device::import_devices))
+        .route("/devices/export", get(device::export_devices))
+        .route("/devices/latest", get(device::get_devices_latest))
+        .route("/devices/{id}/latest", get(device::get_device_latest))
         // PH值管理路由
-        .route("/ph-values", get(ph_value::get_ph_values).post(ph_value::create_ph_value))
+        .route("/ph-values", get(ph_value::get_ph_values).merge(post(ph_value::create_ph_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
         .route(
             "/ph-values/{id}",
             get(ph_value::get_ph_value)
@@ -125,7 +625,7 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
                 .delete(ph_value::delete_ph_value),
         )
         // TDS值管理路由
-        .route("/tds-values", get(tds_value::get_tds_values).post(tds_value::create_tds_value))
+        .route("/tds-values", get(tds_value::get_tds_values).merge(post(tds_value::create_tds_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
         .route(
             "/tds-values/{id}",
             get(tds_value::get_tds_value)
@@ -133,7 +633,7 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
                 .delete(tds_value::delete_tds_value),
         )
         // 浊度值管理路由
-        .route("/turbidity-values", get(turbidity_value::get_turbidity_values).post(turbidity_value::create_turbidity_value))
+        .route("/turbidity-values", get(turbidity_value::get_turbidity_values).merge(post(turbidity_value::create_turbidity_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
         .route(
             "/turbidity-values/{id}",
             get(turbidity_value::get_turbidity_value)
@@ -141,15 +641,242 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
                 .delete(turbidity_value::delete_turbidity_value),
         )
         // 流量值管理路由
-        .route("/flow-values", get(flow_value::get_flow_values).post(flow_value::create_flow_value))
+        .route("/flow-values", get(flow_value::get_flow_values).merge(post(flow_value::create_flow_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
         .route(
             "/flow-values/{id}",
             get(flow_value::get_flow_value)
                 .put(flow_value::update_flow_value)
                 .delete(flow_value::delete_flow_value),
         )
+        // 液位值管理路由
+        .route("/level-values", get(level_value::get_level_values).merge(post(level_value::create_level_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
+        .route(
+            "/level-values/{id}",
+            get(level_value::get_level_value)
+                .put(level_value::update_level_value)
+                .delete(level_value::delete_level_value),
+        )
+        // 压力值管理路由
+        .route("/pressure-values", get(pressure_value::get_pressure_values).merge(post(pressure_value::create_pressure_value).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
+        .route(
+            "/pressure-values/{id}",
+            get(pressure_value::get_pressure_value)
+                .put(pressure_value::update_pressure_value)
+                .delete(pressure_value::delete_pressure_value),
+        )
+        // 通用测量值路由
+        .route("/metric-types", get(measurement::get_metric_types))
+        .route("/measurements", get(measurement::get_measurements).merge(post(measurement::create_measurement).route_layer(ingest_buffer.clone()).route_layer(ingest_auth.clone())))
+        .route("/measurements/binary", post(measurement::create_measurements_binary).route_layer(ingest_auth.clone()))
+        .route("/measurements/aggregate", get(measurement::get_measurement_aggregate))
+        .route("/trend", get(trend::get_trend))
+        .route("/measurements/interpolated", get(measurement::get_measurement_interpolated))
+        .route(
+            "/measurements/{id}",
+            get(measurement::get_measurement)
+                .put(measurement::update_measurement)
+                .delete(measurement::delete_measurement),
+        )
+        .route("/measurements/{id}/corrections", get(measurement::get_measurement_corrections))
+        // API Key管理路由
+        .route("/api-keys", get(api_key::get_api_keys).post(api_key::create_api_key))
+        .route("/api-keys/{id}", axum::routing::delete(api_key::revoke_api_key))
+        .route("/api-keys/{id}/rotate", post(api_key::rotate_api_key))
+        // 系统路由
+        .route("/system/network-policy", get(system::get_network_policy))
+        .route("/system/network", get(system::get_network))
+        .route("/system/stats", get(system::get_stats))
+        .route("/system/queries", get(system::get_query_metrics).delete(system::reset_query_metrics))
+        .route("/system/read-only", get(system::get_read_only).put(system::set_read_only))
+        .route("/system/queues", get(system::get_queues))
+        .route("/system/fault-injection", get(system::get_fault_injection).put(system::set_fault_injection))
+        // 模拟注入路由
+        .route("/simulation/devices/{id}/inject", post(simulation::inject))
+        // 远程访问代理路由
+        .route("/remote-sessions", get(remote_session::get_remote_sessions).post(remote_session::create_remote_session))
+        .route(
+            "/remote-sessions/{id}",
+            get(remote_session::get_remote_session)
+                .delete(remote_session::close_remote_session),
+        )
+        // 串口控制台路由
+        .route("/serial-console/ports", get(serial_console::get_serial_ports))
+        .route("/serial-console/{port}", get(serial_console::connect_serial_console))
+        .route("/serial-sessions", get(serial_console::get_serial_sessions))
+        // 标定曲线路由
+        .route("/calibration-curves", get(calibration_curve::get_calibration_curves).post(calibration_curve::create_calibration_curve))
+        .route(
+            "/calibration-curves/{id}",
+            get(calibration_curve::get_calibration_curve)
+                .put(calibration_curve::update_calibration_curve)
+                .delete(calibration_curve::delete_calibration_curve),
+        )
+        .route("/calibration-curves/{id}/preview", get(calibration_curve::preview_calibration_curve))
+        // 现场校准路由
+        .route("/calibrations", get(calibration::get_calibrations).post(calibration::create_calibration))
+        .route("/calibrations/due", get(calibration::get_due_calibrations))
+        .route("/calibrations/{id}", get(calibration::get_calibration).delete(calibration::delete_calibration))
+        // 化验结果与数据修正记录路由
+        .route("/lab-results", get(lab_result::get_lab_results).post(lab_result::create_lab_result))
+        .route("/lab-results/analytes", get(lab_result::get_analytes))
+        .route(
+            "/lab-results/{id}",
+            get(lab_result::get_lab_result)
+                .put(lab_result::update_lab_result)
+                .delete(lab_result::delete_lab_result),
+        )
+        .route("/lab-results/{id}/corrections", get(lab_result::get_lab_result_corrections))
+        .route("/data-corrections", get(data_correction::get_data_corrections))
+        // 组织（租户）路由
+        .route("/organizations", get(organization::get_organizations).post(organization::create_organization))
+        .route(
+            "/organizations/{id}",
+            get(organization::get_organization)
+                .put(organization::update_organization)
+                .delete(organization::delete_organization),
+        )
+        // Modbus 寄存器映射路由
+        .route("/modbus-mappings", get(modbus_mapping::get_modbus_mappings).post(modbus_mapping::create_modbus_mapping))
+        .route(
+            "/modbus-mappings/{id}",
+            get(modbus_mapping::get_modbus_mapping)
+                .put(modbus_mapping::update_modbus_mapping)
+                .delete(modbus_mapping::delete_modbus_mapping),
+        )
+        // 班次日历路由
+        .route("/calendar/shifts", get(calendar::get_shifts).post(calendar::create_shift))
+        .route(
+            "/calendar/shifts/{id}",
+            axum::routing::put(calendar::update_shift).delete(calendar::delete_shift),
+        )
+        .route("/calendar/days", get(calendar::get_days).post(calendar::create_day))
+        .route(
+            "/calendar/days/{id}",
+            axum::routing::put(calendar::update_day).delete(calendar::delete_day),
+        )
+        .route("/calendar/status", get(calendar::get_status))
+        .route("/calendar/periods", get(calendar::get_periods))
+        // Grafana JSON 数据源路由
+        .route("/grafana", get(grafana::health))
+        .route("/grafana/search", post(grafana::search))
+        .route("/grafana/query", post(grafana::query))
+        .route("/grafana/annotations", post(grafana::annotations))
+        .route("/alarms/kpis", get(alarm_kpi::get_alarm_kpis))
+        // 报警搁置路由
+        .route("/alarm-logs/{id}/shelve", post(alarm_shelving::shelve_alarm))
+        .route("/alarm-shelves", get(alarm_shelving::get_alarm_shelves))
+        .route("/alarm-shelves/stats", get(alarm_shelving::get_shelving_stats))
+        .route("/alarm-shelves/{id}", axum::routing::delete(alarm_shelving::unshelve_alarm))
+        // 消息队列死信路由
+        .route("/message-queue/dead-letters", get(message_queue::get_dead_letters))
+        .route("/message-queue/dead-letters/replay", post(message_queue::replay_dead_letters))
+        .route("/schemas/events", get(message_queue::get_event_schemas))
+        .route("/schemas/events/changelog", get(message_queue::get_event_schema_changelog))
+        .route("/api-usage", get(api_usage::get_api_usage))
+        // 自定义 KPI 路由
+        .route("/kpi-definitions", get(kpi::get_kpi_definitions).post(kpi::create_kpi_definition))
+        .route("/kpi-definitions/validate", post(kpi::validate_kpi_definition))
+        .route(
+            "/kpi-definitions/{id}",
+            get(kpi::get_kpi_definition)
+                .put(kpi::update_kpi_definition)
+                .delete(kpi::delete_kpi_definition),
+        )
+        .route("/kpi-definitions/{id}/value", get(kpi::get_kpi_value))
+        .route("/kpis", get(kpi::get_kpi_values))
+        // 执行器路由
+        .route("/gpio/outputs/{name}", post(actuator::set_gpio_output))
+        .route("/actuators/reconciliation", get(actuator::get_reconciliation))
+        .route("/actuators/safe-state", get(actuator::get_safe_state))
+        .route("/actuators/safe-state/events", get(actuator::get_safe_state_events))
+        // 报表路由
+        .route("/reports", get(report::get_reports).post(report::generate_report))
+        .route("/reports/{id}/download", get(report::download_report))
+        // 排污许可路由
+        .route("/discharge-permits", get(permit::get_permits).post(permit::create_permit))
+        .route(
+            "/discharge-permits/{id}",
+            get(permit::get_permit).put(permit::update_permit).delete(permit::delete_permit),
+        )
+        .route("/permit-exceedances", get(permit::get_exceedances))
+        .route("/compliance/summary", get(permit::get_compliance_summary))
+        // 能耗路由
+        .route("/energy/devices", get(energy::get_device_energy))
+        .route("/energy/areas", get(energy::get_area_energy))
+        .route("/energy/kpis", get(energy::get_energy_kpis))
+        // 加药路由
+        .route("/chemical-tanks", get(dosing::get_tanks).post(dosing::create_tank))
+        .route(
+            "/chemical-tanks/{id}",
+            get(dosing::get_tank).put(dosing::update_tank).delete(dosing::delete_tank),
+        )
+        .route("/chemical-tanks/{id}/refill", post(dosing::refill_tank))
+        .route("/dosing-pumps", get(dosing::get_pumps).post(dosing::create_pump))
+        .route(
+            "/dosing-pumps/{id}",
+            get(dosing::get_pump).put(dosing::update_pump).delete(dosing::delete_pump),
+        )
+        .route("/dosing-pumps/{id}/speed", post(dosing::set_pump_speed))
+        // PID 控制回路路由
+        .route("/control-loops", get(control_loop::get_control_loops).post(control_loop::create_control_loop))
+        .route("/control-loops/status", get(control_loop::get_control_status))
+        .route(
+            "/control-loops/{id}",
+            get(control_loop::get_control_loop)
+                .put(control_loop::update_control_loop)
+                .delete(control_loop::delete_control_loop),
+        )
+        .route("/control-loops/{id}/tuning", axum::routing::put(control_loop::tune_control_loop))
+        .route("/control-loops/{id}/mode", axum::routing::put(control_loop::set_control_mode))
+        // 维护计划与工单路由
+        .route("/maintenance-plans", get(maintenance::get_plans).post(maintenance::create_plan))
+        .route(
+            "/maintenance-plans/{id}",
+            get(maintenance::get_plan).put(maintenance::update_plan).delete(maintenance::delete_plan),
+        )
+        .route("/work-orders", get(maintenance::get_work_orders).post(maintenance::create_work_order))
+        .route("/work-orders/{id}", get(maintenance::get_work_order))
+        .route("/work-orders/{id}/close", post(maintenance::close_work_order))
+        .route("/pump-groups", get(pump_group::get_pump_groups).post(pump_group::create_pump_group))
+        .route("/pump-groups/duty", get(pump_group::get_duty))
+        .route(
+            "/pump-groups/{id}",
+            get(pump_group::get_pump_group).put(pump_group::update_pump_group).delete(pump_group::delete_pump_group),
+        )
+        .route("/pump-groups/{id}/duty", get(pump_group::get_group_duty))
+        .route("/pump-groups/{id}/reset", post(pump_group::reset_pump_group))
+        // 配置版本路由
+        .route("/config-revisions", get(config_revision::get_config_revisions))
+        .route("/config-revisions/{id}", get(config_revision::get_config_revision))
+        .route("/config-revisions/{id}/rollback", post(config_revision::rollback_config_revision))
+        .route("/config-revisions/{id}/approve", post(config_revision::approve_config_revision))
+        .route("/config-revisions/{id}/reject", post(config_revision::reject_config_revision))
+        // 厂站与区域路由
+        .route("/sites", get(site::get_sites).post(site::create_site))
+        .route(
+            "/sites/{id}",
+            get(site::get_site)
+                .put(site::update_site)
+                .delete(site::delete_site),
+        )
+        .route("/sites/{id}/devices", get(site::get_site_devices))
+        .route("/sites/{id}/latest", get(site::get_site_latest))
+        .route("/areas", get(area::get_areas).post(area::create_area))
+        .route(
+            "/areas/{id}",
+            get(area::get_area)
+                .put(area::update_area)
+                .delete(area::delete_area),
+        )
+        .route("/areas/{id}/devices", get(area::get_area_devices))
+        .route("/areas/{id}/latest", get(area::get_area_latest))
+        // 配置包路由
+        .route("/config-bundle/export", get(config_bundle::export_config_bundle))
+        .route("/config-bundle/import", post(config_bundle::import_config_bundle))
         // 报警规则管理路由
         .route("/alarm-rules", get(alarm_rule::get_alarm_rules).post(alarm_rule::create_alarm_rule))
+        .route("/alarm-rules/templates", get(alarm_rule::get_alarm_rule_templates))
+        .route("/alarm-rules/templates/{key}", post(alarm_rule::create_alarm_rule_from_template))
         .route(
             "/alarm-rules/{id}",
             get(alarm_rule::get_alarm_rule)
@@ -164,16 +891,47 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
                 .put(alarm_log::update_alarm_log)
                 .delete(alarm_log::delete_alarm_log),
         )
+        .route("/alarm-logs/{id}/snapshot", get(alarm_log::get_alarm_snapshot))
         // 自动化规则管理路由
         .route("/automation-rules", get(automation_rule::get_automation_rules).post(automation_rule::create_automation_rule))
+        // 当前处于触发时间段内的自动化规则
+        .route("/automation-rules/active", get(automation_rule::get_active_automation_rules))
         .route(
             "/automation-rules/{id}",
             get(automation_rule::get_automation_rule)
                 .put(automation_rule::update_automation_rule)
                 .delete(automation_rule::delete_automation_rule),
         )
+        // 按客户端统计调用，须在路由层取得路由模板
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), api_usage_middleware))
+        // 只读模式
+        .layer(axum::middleware::from_fn_with_state(state.read_only.clone(), read_only_middleware))
+        // 按客户端限流
+        .layer(axum::middleware::from_fn_with_state(
+            (rate_limiter, state.db.clone()),
+            rate_limit_middleware,
+        ))
         .merge(
             SwaggerUi::new("/swagger") // 用於 UI 的 endpoint
                 .url("/api-doc/openapi.json", ApiDoc::openapi()) // 提供 openapi.json
         )
+        // 内置管理后台
+        .merge(if settings.server.admin_ui {
+            create_static_router()
+        } else {
+            Router::new()
+        })
+        // 网络区域策略
+        .layer(axum::middleware::from_fn_with_state(network_policy, network_policy_middleware))
+        // 安全响应头
+        .layer(axum::middleware::from_fn_with_state(security_headers, security_headers_middleware))
+        // 请求日志与 X-Request-Id
+        .layer(axum::middleware::from_fn(logging_middleware));
+
+    // 跨域放在最外层，预检请求不进入后续中间件
+    if settings.cors.enabled {
+        router.layer(cors_layer(&settings.cors))
+    } else {
+        router
+    }
 }
\ No newline at end of file
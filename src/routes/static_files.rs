@@ -1,42 +1,64 @@
+//! 内置管理后台
+//!
+//! `dist/` 下的页面在编译时打包进二进制（rust-embed），没有单独部署看板的小站点
+//! 也可以通过 `/admin/` 管理设备、报警规则和自动化规则。
+
 use axum::{
     routing::get,
     Router,
-    response::{Html, IntoResponse},
-    http::{StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    http::{header, StatusCode, Uri},
 };
+use rust_embed::RustEmbed;
 use std::sync::Arc;
 use crate::app_state::AppState;
 
+pub const ADMIN_PREFIX: &str = "/admin";
+
+#[derive(RustEmbed)]
+#[folder = "dist/"]
+struct AdminAssets;
+
 pub fn create_static_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/", get(serve_index))
-        .route("/{*file}", get(serve_static_file))
+        .route(ADMIN_PREFIX, get(|| async { Redirect::permanent("/admin/") }))
+        .route("/admin/", get(serve_index))
+        .route("/admin/{*file}", get(serve_static_file))
 }
 
-async fn serve_index() -> impl IntoResponse {
-    serve_file("dist/index.html").await
+async fn serve_index() -> Response {
+    serve_file("index.html")
 }
 
-async fn serve_static_file(uri: Uri) -> impl IntoResponse {
+async fn serve_static_file(uri: Uri) -> Response {
     let path = uri.path();
-    let path = path.strip_prefix("/").unwrap_or(path);
-    
+    let path = path
+        .strip_prefix(ADMIN_PREFIX)
+        .unwrap_or(path)
+        .trim_start_matches('/');
+
     // 确保路径安全，防止目录遍历攻击
     if path.contains("..") {
-        return Err(StatusCode::BAD_REQUEST);
+        return StatusCode::BAD_REQUEST.into_response();
     }
-    
-    let file_path = format!("dist/{}", path);
-    serve_file(&file_path).await
+
+    serve_file(path)
 }
 
-async fn serve_file(file_path: &str) -> Result<Html<String>, StatusCode> {
-    // 尝试读取请求的文件
-    let content = match tokio::fs::read_to_string(file_path).await {
-        Ok(content) => content,
-        Err(_) => tokio::fs::read_to_string("dist/index.html").await
-            .map_err(|_| StatusCode::NOT_FOUND)?,
+fn serve_file(path: &str) -> Response {
+    // 找不到的路径返回首页，由页面自己处理
+    let (path, file) = match AdminAssets::get(path) {
+        Some(file) => (path, file),
+        None => match AdminAssets::get("index.html") {
+            Some(file) => ("index.html", file),
+            None => return StatusCode::NOT_FOUND.into_response(),
+        },
     };
-    
-    Ok(Html(content))
-}
\ No newline at end of file
+
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    (
+        [(header::CONTENT_TYPE, mime.as_ref().to_string())],
+        file.data.into_owned(),
+    )
+        .into_response()
+}
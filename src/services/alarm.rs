@@ -0,0 +1,49 @@
+//! 报警记录
+//!
+//! 后台检测（采集失败、设备异常等）产生的报警统一写入 `alarm_logs`，
+//! 严重程度由报警来源决定，见 [`SEVERITIES`](crate::models::alarm_log::SEVERITIES)。
+
+use crate::models::alarm_log::{ActiveModel as AlarmLogActiveModel, Entity as AlarmLogEntity, Model as AlarmLog};
+use crate::services::alarm_shelving;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use tracing::{info, warn};
+
+/// 写入一条未处理的报警
+pub async fn raise(
+    conn: &DatabaseConnection,
+    device_id: Option<i32>,
+    rule_name: String,
+    trigger_value: f64,
+    severity: &str,
+) -> Result<AlarmLog, AppError> {
+    let now = Utc::now();
+    // 报警点搁置期间仍记录报警，但标记为已搁置
+    let shelved = alarm_shelving::active_shelf(conn, &rule_name, device_id, now)
+        .await?
+        .is_some();
+    if shelved {
+        info!("Alarm raised while shelved [{}]: {} ({})", severity, rule_name, trigger_value);
+    } else {
+        warn!("Alarm raised [{}]: {} ({})", severity, rule_name, trigger_value);
+    }
+
+    let active_model = AlarmLogActiveModel {
+        rule_name: Set(rule_name),
+        trigger_time: Set(now),
+        trigger_value: Set(trigger_value),
+        is_processed: Set(false),
+        device_id: Set(device_id),
+        severity: Set(severity.to_string()),
+        shelved: Set(shelved),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    AlarmLogEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
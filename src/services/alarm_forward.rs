@@ -0,0 +1,231 @@
+//! 报警转发到网管中心
+//!
+//! 后台任务按 id 顺序读取新写入的报警，按各接收端配置的严重程度过滤后，
+//! 以 SNMPv2c Trap 和 RFC 5424 syslog 发出。发送失败只记日志不重发，首次启动
+//! 从当前最新一条报警之后开始，不补发历史报警。
+
+use crate::config::alarm_forward::{AlarmForwardConfig, SyslogTarget, TrapTarget};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::{
+    Column as AlarmLogColumn, Entity as AlarmLogEntity, Model as AlarmLog, SEVERITY_CRITICAL,
+    SEVERITY_MAJOR, SEVERITY_MINOR,
+};
+use crate::utils::snmp_trap::{self, TrapValue};
+use chrono::SecondsFormat;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tracing::{error, info, warn};
+
+/// 单次读取的报警条数上限
+const BATCH_SIZE: u64 = 100;
+/// syslog 结构化数据的 SD-ID，32473 为 RFC 5612 中供文档示例使用的企业号
+const SD_ID: &str = "alarm@32473";
+
+/// 报警严重程度对应的 syslog 级别
+fn syslog_severity(severity: &str) -> u8 {
+    match severity {
+        SEVERITY_CRITICAL => 2,
+        SEVERITY_MAJOR => 3,
+        SEVERITY_MINOR => 4,
+        _ => 5,
+    }
+}
+
+/// 结构化数据参数值需转义 `"`、`\` 和 `]`
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 格式化为 RFC 5424 报文
+fn format_syslog(target: &SyslogTarget, alarm: &AlarmLog) -> String {
+    let priority = target.facility as u16 * 8 + syslog_severity(&alarm.severity) as u16;
+    let device = alarm.device_id.map_or("-".to_string(), |id| id.to_string());
+    format!(
+        "<{}>1 {} {} {} - ALARM [{} id=\"{}\" severity=\"{}\" device=\"{}\" value=\"{}\"] \
+         \u{FEFF}{}",
+        priority,
+        alarm.trigger_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        target.hostname.as_deref().filter(|h| !h.is_empty()).unwrap_or("-"),
+        target.app_name,
+        SD_ID,
+        alarm.id,
+        escape_param(&alarm.severity),
+        device,
+        alarm.trigger_value,
+        alarm.rule_name,
+    )
+}
+
+/// Trap 的变量：报警ID、规则名、严重程度、设备ID（无设备为 0）、触发值、触发时间
+fn trap_variables(base: &str, alarm: &AlarmLog) -> Vec<(String, TrapValue)> {
+    let field = |n: u32| format!("{}.1.{}", base, n);
+    vec![
+        (field(1), TrapValue::Integer(alarm.id as i64)),
+        (field(2), TrapValue::OctetString(alarm.rule_name.clone())),
+        (field(3), TrapValue::OctetString(alarm.severity.clone())),
+        (field(4), TrapValue::Integer(alarm.device_id.unwrap_or(0) as i64)),
+        (field(5), TrapValue::OctetString(alarm.trigger_value.to_string())),
+        (field(6), TrapValue::OctetString(alarm.trigger_time.to_rfc3339())),
+    ]
+}
+
+async fn send_trap(target: &TrapTarget, alarm: &AlarmLog, uptime: u32) -> Result<(), String> {
+    let address: SocketAddr = target.address.parse().map_err(|e| e.to_string())?;
+    let base = target.enterprise_oid.trim_end_matches('.');
+    let trap_oid = format!("{}.0.1", base);
+    let packet = snmp_trap::encode(
+        &target.community,
+        alarm.id,
+        uptime,
+        &trap_oid,
+        &trap_variables(base, alarm),
+    )
+    .ok_or_else(|| format!("无效的 OID: {}", target.enterprise_oid))?;
+    snmp_trap::send(address, &packet).await.map_err(|e| e.to_string())
+}
+
+async fn send_syslog(target: &SyslogTarget, alarm: &AlarmLog) -> Result<(), String> {
+    let address: SocketAddr = target.address.parse().map_err(|e| e.to_string())?;
+    let message = format_syslog(target, alarm);
+    match target.protocol.as_str() {
+        "tcp" => {
+            let mut stream = TcpStream::connect(address).await.map_err(|e| e.to_string())?;
+            // RFC 6587 octet counting
+            let frame = format!("{} {}", message.len(), message);
+            stream.write_all(frame.as_bytes()).await.map_err(|e| e.to_string())
+        }
+        "udp" => {
+            let bind = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+            let socket = UdpSocket::bind(bind).await.map_err(|e| e.to_string())?;
+            socket.send_to(message.as_bytes(), address).await.map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        other => Err(format!("未知的协议: {}", other)),
+    }
+}
+
+async fn forward(config: &AlarmForwardConfig, alarm: &AlarmLog, uptime: u32) {
+    // 已搁置的报警不打扰网管中心
+    if alarm.shelved {
+        return;
+    }
+    let wanted = |severities: &[String]| severities.iter().any(|s| *s == alarm.severity);
+    for target in config.snmp_traps.iter().filter(|t| wanted(&t.severities)) {
+        if let Err(e) = send_trap(target, alarm, uptime).await {
+            warn!("Failed to send alarm {} trap to {}: {}", alarm.id, target.address, e);
+        }
+    }
+    for target in config.syslog.iter().filter(|t| wanted(&t.severities)) {
+        if let Err(e) = send_syslog(target, alarm).await {
+            warn!("Failed to send alarm {} syslog to {}: {}", alarm.id, target.address, e);
+        }
+    }
+}
+
+async fn next_batch(
+    conn: &DatabaseConnection,
+    after: i32,
+) -> Result<Vec<AlarmLog>, sea_orm::DbErr> {
+    AlarmLogEntity::find()
+        .filter(AlarmLogColumn::Id.gt(after))
+        .order_by_asc(AlarmLogColumn::Id)
+        .limit(BATCH_SIZE)
+        .all(conn)
+        .await
+}
+
+async fn latest_id(conn: &DatabaseConnection) -> Result<i32, sea_orm::DbErr> {
+    let latest = AlarmLogEntity::find()
+        .order_by_desc(AlarmLogColumn::Id)
+        .one(conn)
+        .await?;
+    Ok(latest.map_or(0, |a| a.id))
+}
+
+/// 后台任务：持续转发新报警
+pub async fn run_forwarder(config: AlarmForwardConfig, db: DbManager) {
+    let conn = db.get_connection();
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    let started = Instant::now();
+
+    let mut cursor = loop {
+        match latest_id(conn).await {
+            Ok(id) => break id,
+            Err(e) => {
+                error!("Failed to read latest alarm id: {:?}", e);
+                tokio::time::sleep(interval).await;
+            }
+        }
+    };
+    info!(
+        "Alarm forwarding started after alarm {}, {} trap and {} syslog targets",
+        cursor,
+        config.snmp_traps.len(),
+        config.syslog.len()
+    );
+
+    loop {
+        match next_batch(conn, cursor).await {
+            Ok(batch) => {
+                // sysUpTime 以百分之一秒计，约 497 天后回绕
+                let uptime = (started.elapsed().as_millis() / 10) as u32;
+                for alarm in &batch {
+                    forward(&config, alarm, uptime).await;
+                    cursor = alarm.id;
+                }
+                if batch.len() as u64 == BATCH_SIZE {
+                    continue;
+                }
+            }
+            Err(e) => error!("Failed to read alarms for forwarding: {:?}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_format_syslog() {
+        let target = SyslogTarget {
+            address: "127.0.0.1:514".to_string(),
+            protocol: "udp".to_string(),
+            facility: 16,
+            hostname: Some("gw-01".to_string()),
+            app_name: "guolu".to_string(),
+            severities: vec!["critical".to_string()],
+        };
+        let time = Utc.with_ymd_and_hms(2024, 5, 1, 8, 30, 0).unwrap();
+        let alarm = AlarmLog {
+            id: 7,
+            rule_name: "液位过高: 1号池".to_string(),
+            trigger_time: time,
+            trigger_value: 4.5,
+            is_processed: false,
+            device_id: Some(3),
+            severity: "critical".to_string(),
+            shelved: false,
+            created_at: time,
+            updated_at: time,
+        };
+        assert_eq!(
+            format_syslog(&target, &alarm),
+            "<130>1 2024-05-01T08:30:00.000Z gw-01 guolu - ALARM [alarm@32473 id=\"7\" \
+             severity=\"critical\" device=\"3\" value=\"4.5\"] \u{FEFF}液位过高: 1号池"
+        );
+        assert_eq!(escape_param(r#"a"b]\c"#), r#"a\"b\]\\c"#);
+    }
+}
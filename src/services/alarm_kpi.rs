@@ -0,0 +1,246 @@
+//! 报警管理 KPI（ISA-18.2）
+//!
+//! 统计时间段内的报警负荷供报警合理化评审：按一个操作台计算每小时报警数、
+//! 10 分钟峰值和泛滥时段占比，列出振荡报警和常驻报警。搁置期间的报警
+//! 没有呈现给操作员，不计入负荷，单独统计条数。
+
+use crate::config::alarm::AlarmConfig;
+use crate::models::alarm_log::{
+    Column as AlarmLogColumn, Entity as AlarmLogEntity, Model as AlarmLog,
+};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// 峰值统计的时间段长度
+const PERIOD_MINUTES: i64 = 10;
+/// 单次统计的最长时间段
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ChatteringAlarm {
+    pub rule_name: String,
+    pub device_id: Option<i32>,
+    /// 期间报警总数
+    pub alarm_count: usize,
+    /// 单个窗口内的最多报警数
+    pub max_in_window: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct StandingAlarm {
+    pub id: i32,
+    pub rule_name: String,
+    pub device_id: Option<i32>,
+    pub severity: String,
+    pub trigger_time: DateTime<Utc>,
+    /// 截至统计结束时未处理的小时数
+    pub standing_hours: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct AlarmKpis {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 呈现给操作员的报警数
+    pub total_alarms: usize,
+    /// 搁置期间未呈现的报警数
+    pub shelved_alarms: usize,
+    /// 每操作员小时报警数
+    pub alarms_per_hour: f64,
+    /// 10 分钟时段内的最多报警数
+    pub peak_10min: usize,
+    pub peak_10min_start: Option<DateTime<Utc>>,
+    /// 报警泛滥的 10 分钟时段占比（%）
+    pub flood_percent: f64,
+    /// 振荡报警，按报警数从多到少
+    pub chattering: Vec<ChatteringAlarm>,
+    /// 常驻报警，按触发时间从早到晚
+    pub standing: Vec<StandingAlarm>,
+}
+
+/// 每个时间点所在窗口内的最多报警数，`times` 须已排序
+fn max_in_window(times: &[DateTime<Utc>], window: Duration) -> usize {
+    let mut max = 0;
+    let mut first = 0;
+    for (i, time) in times.iter().enumerate() {
+        while *time - times[first] >= window {
+            first += 1;
+        }
+        max = max.max(i - first + 1);
+    }
+    max
+}
+
+/// 按时间段内的报警（按触发时间排序）和未处理报警计算 KPI
+pub fn compute(
+    alarms: &[AlarmLog],
+    unprocessed: &[AlarmLog],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &AlarmConfig,
+) -> AlarmKpis {
+    let annunciated: Vec<&AlarmLog> = alarms.iter().filter(|a| !a.shelved).collect();
+    let hours = (end - start).num_seconds() as f64 / 3600.0;
+
+    let period = Duration::minutes(PERIOD_MINUTES);
+    let periods = ((end - start).num_seconds() + period.num_seconds() - 1) / period.num_seconds();
+    let mut counts = vec![0usize; periods.max(0) as usize];
+    for alarm in &annunciated {
+        let index = ((alarm.trigger_time - start).num_seconds() / period.num_seconds()) as usize;
+        if let Some(count) = counts.get_mut(index) {
+            *count += 1;
+        }
+    }
+    let peak = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, count)| (**count, std::cmp::Reverse(*i)))
+        .filter(|(_, count)| **count > 0);
+    let floods = counts.iter().filter(|c| **c > config.flood_threshold).count();
+
+    let mut points: HashMap<(&str, Option<i32>), Vec<DateTime<Utc>>> = HashMap::new();
+    for alarm in &annunciated {
+        points
+            .entry((alarm.rule_name.as_str(), alarm.device_id))
+            .or_default()
+            .push(alarm.trigger_time);
+    }
+    let window = Duration::seconds(config.chattering_window_secs as i64);
+    let mut chattering: Vec<ChatteringAlarm> = points
+        .into_iter()
+        .filter_map(|((rule_name, device_id), times)| {
+            let max = max_in_window(&times, window);
+            (max >= config.chattering_count.max(2)).then(|| ChatteringAlarm {
+                rule_name: rule_name.to_string(),
+                device_id,
+                alarm_count: times.len(),
+                max_in_window: max,
+            })
+        })
+        .collect();
+    chattering.sort_by(|a, b| {
+        b.alarm_count.cmp(&a.alarm_count).then_with(|| a.rule_name.cmp(&b.rule_name))
+    });
+
+    let standing_after = Duration::hours(config.standing_hours as i64);
+    let mut standing: Vec<StandingAlarm> = unprocessed
+        .iter()
+        .filter(|a| !a.is_processed && end - a.trigger_time >= standing_after)
+        .map(|a| StandingAlarm {
+            id: a.id,
+            rule_name: a.rule_name.clone(),
+            device_id: a.device_id,
+            severity: a.severity.clone(),
+            trigger_time: a.trigger_time,
+            standing_hours: (end - a.trigger_time).num_seconds() as f64 / 3600.0,
+        })
+        .collect();
+    standing.sort_by_key(|a| (a.trigger_time, a.id));
+
+    AlarmKpis {
+        start,
+        end,
+        total_alarms: annunciated.len(),
+        shelved_alarms: alarms.len() - annunciated.len(),
+        alarms_per_hour: if hours > 0.0 { annunciated.len() as f64 / hours } else { 0.0 },
+        peak_10min: peak.map_or(0, |(_, count)| *count),
+        peak_10min_start: peak.map(|(i, _)| start + period * i as i32),
+        flood_percent: if counts.is_empty() {
+            0.0
+        } else {
+            floods as f64 * 100.0 / counts.len() as f64
+        },
+        chattering,
+        standing,
+    }
+}
+
+pub async fn kpis(
+    conn: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &AlarmConfig,
+) -> Result<AlarmKpis, AppError> {
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间必须早于结束时间".into()));
+    }
+    if end - start > Duration::days(MAX_RANGE_DAYS) {
+        return Err(AppError::InvalidInput(
+            format!("统计时间段不能超过 {} 天", MAX_RANGE_DAYS).into(),
+        ));
+    }
+
+    let alarms = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::TriggerTime.gte(start))
+        .filter(AlarmLogColumn::TriggerTime.lt(end))
+        .order_by_asc(AlarmLogColumn::TriggerTime)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let standing_before = end - Duration::hours(config.standing_hours as i64);
+    let unprocessed = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::IsProcessed.eq(false))
+        .filter(AlarmLogColumn::TriggerTime.lte(standing_before))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(compute(&alarms, &unprocessed, start, end, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn alarm(id: i32, rule_name: &str, minute: i64, second: i64) -> AlarmLog {
+        let base = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let time = base + Duration::minutes(minute) + Duration::seconds(second);
+        AlarmLog {
+            id,
+            rule_name: rule_name.to_string(),
+            trigger_time: time,
+            trigger_value: 1.0,
+            is_processed: true,
+            device_id: Some(1),
+            severity: "minor".to_string(),
+            shelved: false,
+            created_at: time,
+            updated_at: time,
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let end = start + Duration::hours(2);
+        let mut alarms = vec![
+            alarm(1, "液位高", 12, 0),
+            alarm(2, "液位高", 12, 20),
+            alarm(3, "液位高", 12, 50),
+            alarm(4, "压力低", 15, 0),
+            alarm(5, "压力低", 40, 0),
+            alarm(6, "压力低", 90, 0),
+        ];
+        alarms[5].shelved = true;
+        let mut old = alarm(7, "泵故障", -26 * 60, 0);
+        old.is_processed = false;
+
+        let kpis = compute(&alarms, &[old], start, end, &AlarmConfig::default());
+        assert_eq!(kpis.total_alarms, 5);
+        assert_eq!(kpis.shelved_alarms, 1);
+        assert_eq!(kpis.alarms_per_hour, 2.5);
+        assert_eq!(kpis.peak_10min, 4);
+        assert_eq!(kpis.peak_10min_start, Some(start + Duration::minutes(10)));
+        assert_eq!(kpis.flood_percent, 0.0);
+        assert_eq!(kpis.chattering.len(), 1);
+        assert_eq!(kpis.chattering[0].rule_name, "液位高");
+        assert_eq!(kpis.chattering[0].max_in_window, 3);
+        assert_eq!(kpis.standing.len(), 1);
+        assert_eq!(kpis.standing[0].standing_hours, 28.0);
+    }
+}
@@ -0,0 +1,260 @@
+//! 报警搁置（ISA-18.2）
+//!
+//! 操作员可以把干扰报警点临时搁置，必须填写原因，时长不超过配置的上限，到期自动解除，
+//! 也可以提前手动解除。报警点以规则名称和设备识别。搁置期间该报警点的新报警仍然记录，
+//! 但标记为 `shelved` 且不转发；搁置次数、时长和被搁置的报警数计入统计。
+
+use crate::models::alarm_log::{
+    Column as AlarmLogColumn, Entity as AlarmLogEntity, Model as AlarmLog,
+};
+use crate::models::alarm_shelf::{
+    ActiveModel as AlarmShelfActiveModel, Column as AlarmShelfColumn, Entity as AlarmShelfEntity,
+    Model as AlarmShelf,
+};
+use crate::utils::error::AppError;
+use crate::utils::tenant;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 校验搁置时长和原因
+pub fn validate(minutes: u64, reason: &str, max_minutes: u64) -> Result<(), AppError> {
+    if reason.trim().is_empty() {
+        return Err(AppError::InvalidInput("必须填写搁置原因".into()));
+    }
+    if minutes == 0 || minutes > max_minutes {
+        return Err(AppError::InvalidInput(
+            format!("搁置时长须在 1 到 {} 分钟之间", max_minutes).into(),
+        ));
+    }
+    Ok(())
+}
+
+fn same_point(rule_name: &str, device_id: Option<i32>) -> Condition {
+    let device = match device_id {
+        Some(device_id) => AlarmShelfColumn::DeviceId.eq(device_id),
+        None => AlarmShelfColumn::DeviceId.is_null(),
+    };
+    Condition::all().add(AlarmShelfColumn::RuleName.eq(rule_name)).add(device)
+}
+
+/// 报警点当前生效的搁置
+pub async fn active_shelf(
+    conn: &DatabaseConnection,
+    rule_name: &str,
+    device_id: Option<i32>,
+    now: DateTime<Utc>,
+) -> Result<Option<AlarmShelf>, AppError> {
+    let shelves = AlarmShelfEntity::find()
+        .filter(same_point(rule_name, device_id))
+        .filter(AlarmShelfColumn::ExpiresAt.gt(now))
+        .filter(AlarmShelfColumn::UnshelvedAt.is_null())
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(shelves.into_iter().find(|s| s.is_active(now)))
+}
+
+/// 搁置报警记录所在的报警点
+pub async fn shelve(
+    conn: &DatabaseConnection,
+    alarm_log_id: i32,
+    minutes: u64,
+    reason: &str,
+    operator: &str,
+    max_minutes: u64,
+) -> Result<AlarmShelf, AppError> {
+    validate(minutes, reason, max_minutes)?;
+    let alarm: AlarmLog = AlarmLogEntity::find_by_id(alarm_log_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)?;
+
+    let now = Utc::now();
+    if active_shelf(conn, &alarm.rule_name, alarm.device_id, now).await?.is_some() {
+        return Err(AppError::InvalidInput("该报警点已在搁置中".into()));
+    }
+
+    let shelf = AlarmShelfActiveModel {
+        rule_name: Set(alarm.rule_name),
+        device_id: Set(alarm.device_id),
+        alarm_log_id: Set(alarm.id),
+        reason: Set(reason.trim().to_string()),
+        shelved_by: Set(operator.to_string()),
+        shelved_at: Set(now),
+        expires_at: Set(now + Duration::minutes(minutes as i64)),
+        unshelved_at: Set(None),
+        unshelved_by: Set(None),
+        ..Default::default()
+    };
+    AlarmShelfEntity::insert(shelf)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+pub async fn get(conn: &DatabaseConnection, id: i32) -> Result<AlarmShelf, AppError> {
+    AlarmShelfEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)
+}
+
+/// 提前解除搁置，已到期或已解除的不能再解除
+pub async fn unshelve(
+    conn: &DatabaseConnection,
+    id: i32,
+    operator: &str,
+) -> Result<AlarmShelf, AppError> {
+    let shelf = get(conn, id).await?;
+    let now = Utc::now();
+    if !shelf.is_active(now) {
+        return Err(AppError::InvalidInput("搁置已结束".into()));
+    }
+
+    let mut active = shelf.into_active_model();
+    active.unshelved_at = Set(Some(now));
+    active.unshelved_by = Set(Some(operator.to_string()));
+    AlarmShelfEntity::update(active)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 搁置记录，按搁置时间倒序；`active_only` 只返回生效中的，指定组织时只返回本组织设备的
+pub async fn list(
+    conn: &DatabaseConnection,
+    active_only: bool,
+    organization_id: Option<i32>,
+) -> Result<Vec<AlarmShelf>, AppError> {
+    let now = Utc::now();
+    let mut select = AlarmShelfEntity::find();
+    if active_only {
+        select = select
+            .filter(AlarmShelfColumn::ExpiresAt.gt(now))
+            .filter(AlarmShelfColumn::UnshelvedAt.is_null());
+    }
+    if let Some(organization_id) = organization_id {
+        select = select
+            .filter(tenant::organization_devices(AlarmShelfColumn::DeviceId, organization_id));
+    }
+    let shelves = select
+        .order_by_desc(AlarmShelfColumn::ShelvedAt)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(shelves.into_iter().filter(|s| !active_only || s.is_active(now)).collect())
+}
+
+/// 搁置统计
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ShelvingStats {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 期间发起的搁置次数
+    pub shelve_count: u64,
+    /// 其中提前手动解除的次数，其余到期自动解除或仍在搁置中
+    pub manual_unshelve_count: u64,
+    /// 期间各报警点处于搁置的总时长
+    pub shelved_seconds: i64,
+    /// 期间因搁置未转发的报警条数
+    pub suppressed_alarms: u64,
+    /// 统计结束时仍在搁置中的报警点数
+    pub active_at_end: u64,
+}
+
+/// 各搁置与时间段重叠的秒数之和
+fn overlap_seconds(shelves: &[AlarmShelf], start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
+    shelves
+        .iter()
+        .map(|s| (s.ended_at().min(end) - s.shelved_at.max(start)).num_seconds().max(0))
+        .sum()
+}
+
+pub async fn stats(
+    conn: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<ShelvingStats, AppError> {
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间必须早于结束时间".into()));
+    }
+    // 与时间段有重叠的搁置：开始早于结束时间，到期晚于开始时间
+    let shelves = AlarmShelfEntity::find()
+        .filter(AlarmShelfColumn::ShelvedAt.lt(end))
+        .filter(AlarmShelfColumn::ExpiresAt.gt(start))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let started: Vec<&AlarmShelf> = shelves.iter().filter(|s| s.shelved_at >= start).collect();
+
+    let suppressed_alarms = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::Shelved.eq(true))
+        .filter(AlarmLogColumn::TriggerTime.gte(start))
+        .filter(AlarmLogColumn::TriggerTime.lt(end))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(ShelvingStats {
+        start,
+        end,
+        shelve_count: started.len() as u64,
+        manual_unshelve_count: started.iter().filter(|s| s.unshelved_at.is_some()).count() as u64,
+        shelved_seconds: overlap_seconds(&shelves, start, end),
+        suppressed_alarms,
+        active_at_end: shelves
+            .iter()
+            .filter(|s| s.is_active(end - Duration::milliseconds(1)))
+            .count() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn shelf(start_hour: u32, minutes: i64, unshelved_minutes: Option<i64>) -> AlarmShelf {
+        let shelved_at = Utc.with_ymd_and_hms(2024, 5, 1, start_hour, 0, 0).unwrap();
+        AlarmShelf {
+            id: 1,
+            rule_name: "振动超限: 设备3 1X".to_string(),
+            device_id: Some(3),
+            alarm_log_id: 1,
+            reason: "传感器松动，已报修".to_string(),
+            shelved_by: "张三".to_string(),
+            shelved_at,
+            expires_at: shelved_at + Duration::minutes(minutes),
+            unshelved_at: unshelved_minutes.map(|m| shelved_at + Duration::minutes(m)),
+            unshelved_by: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(60, "传感器检修", 480).is_ok());
+        assert!(validate(60, "  ", 480).is_err());
+        assert!(validate(0, "传感器检修", 480).is_err());
+        assert!(validate(481, "传感器检修", 480).is_err());
+    }
+
+    #[test]
+    fn test_active_and_overlap() {
+        let s = shelf(8, 120, Some(30));
+        let at = |h: u32, m: u32| Utc.with_ymd_and_hms(2024, 5, 1, h, m, 0).unwrap();
+        assert!(s.is_active(at(8, 10)));
+        assert!(!s.is_active(at(8, 30)));
+        assert!(!s.is_active(at(7, 59)));
+
+        let shelves = vec![s, shelf(9, 120, None)];
+        // 8:00-8:30 手动解除，9:00-11:00 到期；统计 8:15-10:00
+        assert_eq!(overlap_seconds(&shelves, at(8, 15), at(10, 0)), 15 * 60 + 60 * 60);
+    }
+}
@@ -0,0 +1,305 @@
+//! 报警过程数据快照
+//!
+//! 报警触发后等待 `after_minutes`，把报警前后窗口内相关参数的原始测量值存入
+//! `alarm_snapshots`，原始数据之后被压缩或清理也不影响事故分析。相关参数为报警设备
+//! 自身的全部指标加上按规则名称前缀配置的参数。后台任务按报警 id 顺序处理，
+//! 从已有快照的最大报警 id 之后继续，首次启用时从当前最新报警之后开始。
+
+use crate::config::alarm::{AlarmSnapshotConfig, SnapshotParameter};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::{
+    Column as AlarmLogColumn, Entity as AlarmLogEntity, Model as AlarmLog,
+};
+use crate::models::alarm_snapshot::{
+    ActiveModel as AlarmSnapshotActiveModel, Column as AlarmSnapshotColumn,
+    Entity as AlarmSnapshotEntity, Model as AlarmSnapshot,
+};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::services::measurement;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 无待处理报警时的检查间隔
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// 单轮处理的报警数上限
+const BATCH_SIZE: u64 = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SnapshotPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SnapshotSeries {
+    pub metric_type: String,
+    pub device_id: Option<i32>,
+    pub points: Vec<SnapshotPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AlarmSnapshotView {
+    pub alarm_log_id: i32,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub captured_at: DateTime<Utc>,
+    pub series: Vec<SnapshotSeries>,
+}
+
+/// 报警的相关参数：设备自身指标在前，配置的参数在后，去重
+fn parameters_for(
+    alarm: &AlarmLog,
+    config: &AlarmSnapshotConfig,
+    device_metrics: &[String],
+) -> Vec<SnapshotParameter> {
+    let own = alarm.device_id.into_iter().flat_map(|device_id| {
+        device_metrics.iter().map(move |metric_type| SnapshotParameter {
+            metric_type: metric_type.clone(),
+            device_id: Some(device_id),
+        })
+    });
+    let related = config
+        .related
+        .iter()
+        .filter(|rule| alarm.rule_name.starts_with(&rule.rule_prefix))
+        .flat_map(|rule| rule.parameters.iter().cloned());
+
+    let mut parameters: Vec<SnapshotParameter> = Vec::new();
+    for parameter in own.chain(related) {
+        if !parameters.contains(&parameter) {
+            parameters.push(parameter);
+        }
+    }
+    parameters
+}
+
+/// 点数超过上限时均匀抽取，保留首尾
+fn decimate(points: Vec<SnapshotPoint>, max_points: usize) -> Vec<SnapshotPoint> {
+    let len = points.len();
+    if len <= max_points || max_points < 2 {
+        return points;
+    }
+    let step = (len - 1) as f64 / (max_points - 1) as f64;
+    (0..max_points).map(|i| points[(i as f64 * step).round() as usize].clone()).collect()
+}
+
+/// 设备在时间段内有数据的指标
+async fn device_metrics(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<String>, AppError> {
+    MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::MetricType)
+        .distinct()
+        .filter(MeasurementColumn::DeviceId.eq(device_id))
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lte(end))
+        .into_tuple::<String>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 采集单条报警的快照；没有任何相关数据时不保存
+pub async fn capture(
+    conn: &DatabaseConnection,
+    config: &AlarmSnapshotConfig,
+    alarm: &AlarmLog,
+) -> Result<Option<AlarmSnapshot>, AppError> {
+    let start = alarm.trigger_time - Duration::minutes(config.before_minutes as i64);
+    let end = alarm.trigger_time + Duration::minutes(config.after_minutes as i64);
+
+    let own_metrics = match alarm.device_id {
+        Some(device_id) if config.include_device_metrics => {
+            device_metrics(conn, device_id, start, end).await?
+        }
+        _ => Vec::new(),
+    };
+
+    let mut series = Vec::new();
+    for parameter in parameters_for(alarm, config, &own_metrics) {
+        let points: Vec<SnapshotPoint> =
+            measurement::series(conn, &parameter.metric_type, parameter.device_id, start, end)
+                .await?
+                .into_iter()
+                .filter(|(timestamp, _)| *timestamp >= start && *timestamp <= end)
+                .map(|(timestamp, value)| SnapshotPoint { timestamp, value })
+                .collect();
+        if !points.is_empty() {
+            series.push(SnapshotSeries {
+                metric_type: parameter.metric_type,
+                device_id: parameter.device_id,
+                points: decimate(points, config.max_points),
+            });
+        }
+    }
+    if series.is_empty() {
+        return Ok(None);
+    }
+
+    let snapshot = AlarmSnapshotActiveModel {
+        alarm_log_id: Set(alarm.id),
+        window_start: Set(start),
+        window_end: Set(end),
+        series: Set(serde_json::to_string(&series).map_err(|_| AppError::InternalError)?),
+        captured_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    AlarmSnapshotEntity::insert(snapshot)
+        .exec_with_returning(conn)
+        .await
+        .map(Some)
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 报警的快照
+pub async fn get(
+    conn: &DatabaseConnection,
+    alarm_log_id: i32,
+) -> Result<AlarmSnapshotView, AppError> {
+    let snapshot = AlarmSnapshotEntity::find()
+        .filter(AlarmSnapshotColumn::AlarmLogId.eq(alarm_log_id))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)?;
+    let series = serde_json::from_str(&snapshot.series).map_err(|_| AppError::InternalError)?;
+    Ok(AlarmSnapshotView {
+        alarm_log_id: snapshot.alarm_log_id,
+        window_start: snapshot.window_start,
+        window_end: snapshot.window_end,
+        captured_at: snapshot.captured_at,
+        series,
+    })
+}
+
+async fn start_cursor(conn: &DatabaseConnection) -> Result<i32, sea_orm::DbErr> {
+    let snapshot = AlarmSnapshotEntity::find()
+        .order_by_desc(AlarmSnapshotColumn::AlarmLogId)
+        .one(conn)
+        .await?;
+    if let Some(snapshot) = snapshot {
+        return Ok(snapshot.alarm_log_id);
+    }
+    let alarm = AlarmLogEntity::find().order_by_desc(AlarmLogColumn::Id).one(conn).await?;
+    Ok(alarm.map_or(0, |a| a.id))
+}
+
+/// 处理一轮到期的报警，返回新的游标和是否还有到期报警
+async fn capture_due(
+    conn: &DatabaseConnection,
+    config: &AlarmSnapshotConfig,
+    cursor: i32,
+) -> Result<(i32, bool), AppError> {
+    let due_before = Utc::now() - Duration::minutes(config.after_minutes as i64);
+    let alarms = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::Id.gt(cursor))
+        .order_by_asc(AlarmLogColumn::Id)
+        .limit(BATCH_SIZE)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut cursor = cursor;
+    for alarm in &alarms {
+        // 按 id 顺序处理，遇到窗口未结束的报警就等下一轮
+        if alarm.trigger_time > due_before {
+            return Ok((cursor, false));
+        }
+        capture(conn, config, alarm).await?;
+        cursor = alarm.id;
+    }
+    Ok((cursor, alarms.len() as u64 == BATCH_SIZE))
+}
+
+/// 后台任务：报警窗口结束后采集快照
+pub async fn run_capture(config: AlarmSnapshotConfig, db: DbManager) {
+    let conn = db.get_connection();
+    let mut cursor = loop {
+        match start_cursor(conn).await {
+            Ok(cursor) => break cursor,
+            Err(e) => {
+                error!("Failed to read alarm snapshot cursor: {:?}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    };
+    info!("Alarm snapshots start after alarm {}", cursor);
+
+    loop {
+        match capture_due(conn, &config, cursor).await {
+            Ok((next, more)) => {
+                cursor = next;
+                if more {
+                    continue;
+                }
+            }
+            Err(e) => error!("Failed to capture alarm snapshots: {:?}", e),
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::alarm::SnapshotRule;
+
+    #[test]
+    fn test_parameters_for() {
+        let now = Utc::now();
+        let alarm = AlarmLog {
+            id: 1,
+            rule_name: "液位过高: 1号池".to_string(),
+            trigger_time: now,
+            trigger_value: 4.5,
+            is_processed: false,
+            device_id: Some(1),
+            severity: "major".to_string(),
+            shelved: false,
+            created_at: now,
+            updated_at: now,
+        };
+        let parameter = |metric_type: &str, device_id| SnapshotParameter {
+            metric_type: metric_type.to_string(),
+            device_id,
+        };
+        let config = AlarmSnapshotConfig {
+            related: vec![
+                SnapshotRule {
+                    rule_prefix: "液位过高".to_string(),
+                    parameters: vec![parameter("flow", Some(2)), parameter("level", Some(1))],
+                },
+                SnapshotRule {
+                    rule_prefix: "压力".to_string(),
+                    parameters: vec![parameter("pressure", Some(3))],
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            parameters_for(&alarm, &config, &["level".to_string()]),
+            vec![parameter("level", Some(1)), parameter("flow", Some(2))]
+        );
+    }
+
+    #[test]
+    fn test_decimate() {
+        let now = Utc::now();
+        let points: Vec<SnapshotPoint> = (0..10)
+            .map(|i| SnapshotPoint { timestamp: now, value: i as f64 })
+            .collect();
+        let values: Vec<f64> = decimate(points.clone(), 4).iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![0.0, 3.0, 6.0, 9.0]);
+        assert_eq!(decimate(points, 20).len(), 10);
+    }
+}
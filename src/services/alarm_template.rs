@@ -0,0 +1,94 @@
+//! 报警规则模板
+//!
+//! 提升泵站常用的高/低液位、高/低压力报警。模板给出条件、参数和默认限值，
+//! 由模板创建的仍是普通报警规则，之后可按现场情况修改限值。条件为 `>` 或 `<`，
+//! 参数为测量值的指标类型，限值使用该指标的默认单位。
+
+use crate::services::metric_registry::{LEVEL, PRESSURE};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 报警规则模板
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AlarmRuleTemplate {
+    pub key: &'static str,
+    /// 创建规则时的默认名称
+    pub name: &'static str,
+    pub condition: &'static str,
+    pub parameter: &'static str,
+    /// 默认限值，指标默认单位
+    pub default_value: f64,
+    pub description: &'static str,
+}
+
+pub const TEMPLATES: [AlarmRuleTemplate; 6] = [
+    AlarmRuleTemplate {
+        key: "high_high_level",
+        name: "高高液位",
+        condition: ">",
+        parameter: LEVEL,
+        default_value: 3.5,
+        description: "湿井即将溢流，应确认全部泵已投入运行",
+    },
+    AlarmRuleTemplate {
+        key: "high_level",
+        name: "高液位",
+        condition: ">",
+        parameter: LEVEL,
+        default_value: 3.0,
+        description: "湿井或水池液位超过启泵液位，泵未及时启动或来水过大",
+    },
+    AlarmRuleTemplate {
+        key: "low_level",
+        name: "低液位",
+        condition: "<",
+        parameter: LEVEL,
+        default_value: 0.5,
+        description: "液位低于停泵液位，泵有空转、吸入空气的风险",
+    },
+    AlarmRuleTemplate {
+        key: "low_low_level",
+        name: "低低液位",
+        condition: "<",
+        parameter: LEVEL,
+        default_value: 0.3,
+        description: "液位低于泵的最低淹没深度，应停泵保护",
+    },
+    AlarmRuleTemplate {
+        key: "high_pressure",
+        name: "出口高压",
+        condition: ">",
+        parameter: PRESSURE,
+        default_value: 0.6,
+        description: "压力管道出口压力过高，可能阀门关闭或管道堵塞",
+    },
+    AlarmRuleTemplate {
+        key: "low_pressure",
+        name: "出口低压",
+        condition: "<",
+        parameter: PRESSURE,
+        default_value: 0.05,
+        description: "泵运行时出口压力过低，可能管道破裂或泵未出水",
+    },
+];
+
+pub fn lookup(key: &str) -> Option<&'static AlarmRuleTemplate> {
+    TEMPLATES.iter().find(|t| t.key == key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::metric_registry;
+
+    #[test]
+    fn test_templates() {
+        for template in &TEMPLATES {
+            let metric = metric_registry::lookup(template.parameter).unwrap();
+            assert!(metric.in_range(template.default_value), "{}", template.key);
+            assert!(template.condition == ">" || template.condition == "<");
+        }
+        assert_eq!(lookup("high_level").map(|t| t.parameter), Some(LEVEL));
+        assert!(lookup("unknown").is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! API Key 的生成、摘要与校验
+//!
+//! 数据库只保存 SHA-256 摘要，明文只在签发/轮换时返回一次。
+
+use crate::models::api_key::{
+    ActiveModel as ApiKeyActiveModel, Column as ApiKeyColumn, Entity as ApiKeyEntity,
+    Model as ApiKey,
+};
+use crate::utils::error::AppError;
+use crate::utils::crypto;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, Set};
+
+pub const SCOPE_INGEST: &str = "ingest";
+pub const SCOPE_READ: &str = "read";
+pub const SCOPE_ADMIN: &str = "admin";
+/// 人工下发现场输出（Modbus 写入、PWM）
+pub const SCOPE_CONTROL: &str = "control";
+/// 人工写入时越过联锁检查
+pub const SCOPE_OVERRIDE: &str = "override";
+pub const ALL_SCOPES: &[&str] =
+    &[SCOPE_INGEST, SCOPE_READ, SCOPE_ADMIN, SCOPE_CONTROL, SCOPE_OVERRIDE];
+
+const KEY_PREFIX: &str = "gk_";
+/// 最后使用时间的刷新间隔，避免每次请求都写库
+const LAST_USED_REFRESH_SECS: i64 = 60;
+const PREFIX_LEN: usize = 11; // "gk_" + 8 位
+
+/// 生成新的明文 Key
+pub fn generate_key() -> String {
+    format!("{}{}", KEY_PREFIX, crypto::random_token(24))
+}
+
+/// 计算 Key 摘要
+pub fn hash_key(key: &str) -> String {
+    crypto::sha256_hex(key.as_bytes())
+}
+
+/// 明文前缀，用于在列表中辨认 Key
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(PREFIX_LEN).collect()
+}
+
+/// 校验权限范围并规范化为逗号分隔字符串
+pub fn normalize_scopes(scopes: &[String]) -> Result<String, AppError> {
+    if scopes.is_empty() {
+        return Err(AppError::InvalidInput("至少需要一个权限范围".into()));
+    }
+    for scope in scopes {
+        if !ALL_SCOPES.contains(&scope.as_str()) {
+            return Err(AppError::InvalidInput(format!("未知的权限范围: {}", scope).into()));
+        }
+    }
+    Ok(scopes.join(","))
+}
+
+/// 签发新 Key，返回登记记录和明文
+pub async fn issue(
+    conn: &DatabaseConnection,
+    name: String,
+    scopes: &[String],
+    organization_id: Option<i32>,
+) -> Result<(ApiKey, String), AppError> {
+    let scopes = normalize_scopes(scopes)?;
+    let key = generate_key();
+    let now = chrono::Utc::now();
+
+    let new_api_key = ApiKeyActiveModel {
+        name: Set(name),
+        key_prefix: Set(key_prefix(&key)),
+        key_hash: Set(hash_key(&key)),
+        scopes: Set(scopes),
+        revoked: Set(false),
+        organization_id: Set(organization_id),
+        last_used_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+    let api_key = ApiKeyEntity::insert(new_api_key)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok((api_key, key))
+}
+
+/// 轮换 Key，旧明文立即失效，返回更新后的记录和新明文
+pub async fn rotate(
+    conn: &DatabaseConnection,
+    existing: ApiKey,
+) -> Result<(ApiKey, String), AppError> {
+    let key = generate_key();
+    let mut active_model = existing.into_active_model();
+    active_model.key_prefix = Set(key_prefix(&key));
+    active_model.key_hash = Set(hash_key(&key));
+    active_model.revoked = Set(false);
+    active_model.updated_at = Set(chrono::Utc::now());
+
+    let api_key = ApiKeyEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok((api_key, key))
+}
+
+/// 查找未吊销的 Key，不校验权限范围
+pub async fn identify(conn: &DatabaseConnection, key: &str) -> Result<ApiKey, AppError> {
+    let api_key = ApiKeyEntity::find()
+        .filter(ApiKeyColumn::KeyHash.eq(hash_key(key)))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    if api_key.revoked {
+        return Err(AppError::InvalidCredentials);
+    }
+    Ok(api_key)
+}
+
+/// 校验明文 Key，成功时更新最后使用时间
+pub async fn authenticate(
+    conn: &DatabaseConnection,
+    key: &str,
+    scope: &str,
+) -> Result<ApiKey, AppError> {
+    let api_key = identify(conn, key).await?;
+    if !api_key.has_scope(scope) {
+        return Err(AppError::Forbidden);
+    }
+
+    let now = chrono::Utc::now();
+    let stale = api_key
+        .last_used_at
+        .is_none_or(|t| (now - t).num_seconds() >= LAST_USED_REFRESH_SECS);
+    if stale {
+        let mut active_model = api_key.clone().into_active_model();
+        active_model.last_used_at = Set(Some(now));
+        ApiKeyEntity::update(active_model)
+            .exec(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+
+    Ok(api_key)
+}
@@ -0,0 +1,331 @@
+//! 按客户端统计接口调用
+//!
+//! 每个请求按（客户端、方法、路由模板、整小时）在内存中累计请求数、4xx/5xx 数和耗时分布，
+//! 定期写入 `api_usage` 表。同一周期会写入多行，查询时合并；p95 耗时由各耗时区间的请求数估算，
+//! 取累计达到 95% 的区间上限。客户端带 `X-Api-Key` 时按 Key 前缀识别，否则按 IP 识别。
+
+use crate::config::api_usage::ApiUsageConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::api_key::Entity as ApiKeyEntity;
+use crate::models::api_usage::{
+    ActiveModel as ApiUsageActiveModel, Column as ApiUsageColumn, Entity as ApiUsageEntity,
+    Model as ApiUsage,
+};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// 耗时区间上限（毫秒），最后一个区间不设上限
+const LATENCY_BOUNDS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+const HISTOGRAM_LEN: usize = LATENCY_BOUNDS_MS.len() + 1;
+
+/// 返回原始数据的接口，调用量大的客户端应改用聚合接口
+const RAW_DATA_PATHS: [&str; 6] = [
+    "/measurements",
+    "/ph-values",
+    "/tds-values",
+    "/turbidity-values",
+    "/flow-values",
+    "/measurements/interpolated",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct UsageKey {
+    client: String,
+    method: String,
+    path: String,
+    period_start: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Counters {
+    requests: i64,
+    client_errors: i64,
+    server_errors: i64,
+    total_ms: i64,
+    max_ms: i64,
+    histogram: [i64; HISTOGRAM_LEN],
+}
+
+impl Counters {
+    fn record(&mut self, status: u16, elapsed_ms: u64) {
+        self.requests += 1;
+        match status {
+            400..=499 => self.client_errors += 1,
+            500..=599 => self.server_errors += 1,
+            _ => {}
+        }
+        let elapsed = elapsed_ms.min(i64::MAX as u64) as i64;
+        self.total_ms = self.total_ms.saturating_add(elapsed);
+        self.max_ms = self.max_ms.max(elapsed);
+        let index = LATENCY_BOUNDS_MS
+            .iter()
+            .position(|bound| elapsed_ms <= *bound)
+            .unwrap_or(LATENCY_BOUNDS_MS.len());
+        self.histogram[index] += 1;
+    }
+
+    fn merge(&mut self, other: &Counters) {
+        self.requests += other.requests;
+        self.client_errors += other.client_errors;
+        self.server_errors += other.server_errors;
+        self.total_ms = self.total_ms.saturating_add(other.total_ms);
+        self.max_ms = self.max_ms.max(other.max_ms);
+        for (count, other) in self.histogram.iter_mut().zip(other.histogram) {
+            *count += other;
+        }
+    }
+
+    /// 累计达到 95% 的耗时区间上限，落在最后一个区间时取最大耗时
+    fn p95_ms(&self) -> i64 {
+        let total: i64 = self.histogram.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let target = (total * 95).div_ceil(100);
+        let mut seen = 0;
+        for (i, count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return match LATENCY_BOUNDS_MS.get(i) {
+                    Some(bound) => (*bound as i64).min(self.max_ms),
+                    None => self.max_ms,
+                };
+            }
+        }
+        self.max_ms
+    }
+}
+
+/// 内存中的调用统计
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecorder {
+    pending: Arc<Mutex<HashMap<UsageKey, Counters>>>,
+}
+
+impl UsageRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次请求
+    pub fn record(&self, client: String, method: &str, path: &str, status: u16, elapsed_ms: u64) {
+        let now = Utc::now();
+        let key = UsageKey {
+            client,
+            method: method.to_string(),
+            path: path.to_string(),
+            period_start: now.duration_trunc(Duration::hours(1)).unwrap_or(now),
+        };
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.entry(key).or_default().record(status, elapsed_ms);
+    }
+
+    /// 把累计的统计写入数据库，失败时放回内存等待下次写入
+    pub async fn flush(&self, conn: &DatabaseConnection) -> Result<usize, sea_orm::DbErr> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let now = Utc::now();
+        let rows: Vec<ApiUsageActiveModel> = pending
+            .iter()
+            .map(|(key, counters)| ApiUsageActiveModel {
+                client: Set(key.client.clone()),
+                method: Set(key.method.clone()),
+                path: Set(key.path.clone()),
+                period_start: Set(key.period_start),
+                requests: Set(counters.requests),
+                client_errors: Set(counters.client_errors),
+                server_errors: Set(counters.server_errors),
+                total_ms: Set(counters.total_ms),
+                max_ms: Set(counters.max_ms),
+                latency_histogram: Set(
+                    serde_json::to_string(&counters.histogram).unwrap_or_default(),
+                ),
+                created_at: Set(now),
+                ..Default::default()
+            })
+            .collect();
+        let count = rows.len();
+
+        if let Err(e) = ApiUsageEntity::insert_many(rows).exec(conn).await {
+            let mut current = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            for (key, counters) in pending {
+                current.entry(key).or_default().merge(&counters);
+            }
+            return Err(e);
+        }
+        Ok(count)
+    }
+}
+
+/// 客户端对某个接口的调用统计
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EndpointUsage {
+    pub client: String,
+    /// 客户端为 API Key 时的名称
+    pub client_name: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// 是否为原始数据接口
+    pub raw_data: bool,
+    pub requests: i64,
+    pub client_errors: i64,
+    pub server_errors: i64,
+    /// 4xx 与 5xx 占比（%）
+    pub error_rate: f64,
+    pub avg_ms: f64,
+    pub p95_ms: i64,
+    pub max_ms: i64,
+}
+
+fn counters(row: &ApiUsage) -> Counters {
+    let mut histogram = [0; HISTOGRAM_LEN];
+    let values: Vec<i64> = serde_json::from_str(&row.latency_histogram).unwrap_or_default();
+    for (slot, value) in histogram.iter_mut().zip(values) {
+        *slot = value;
+    }
+    Counters {
+        requests: row.requests,
+        client_errors: row.client_errors,
+        server_errors: row.server_errors,
+        total_ms: row.total_ms,
+        max_ms: row.max_ms,
+        histogram,
+    }
+}
+
+/// 合并各周期的记录，按请求数从多到少
+fn summarize(rows: &[ApiUsage], key_names: &HashMap<String, String>) -> Vec<EndpointUsage> {
+    let mut merged: HashMap<(&str, &str, &str), Counters> = HashMap::new();
+    for row in rows {
+        merged
+            .entry((row.client.as_str(), row.method.as_str(), row.path.as_str()))
+            .or_default()
+            .merge(&counters(row));
+    }
+
+    let mut usage: Vec<EndpointUsage> = merged
+        .into_iter()
+        .map(|((client, method, path), c)| EndpointUsage {
+            client: client.to_string(),
+            client_name: client.strip_prefix("key:").and_then(|p| key_names.get(p)).cloned(),
+            method: method.to_string(),
+            path: path.to_string(),
+            raw_data: method == "GET" && RAW_DATA_PATHS.contains(&path),
+            requests: c.requests,
+            client_errors: c.client_errors,
+            server_errors: c.server_errors,
+            error_rate: if c.requests > 0 {
+                (c.client_errors + c.server_errors) as f64 * 100.0 / c.requests as f64
+            } else {
+                0.0
+            },
+            avg_ms: if c.requests > 0 { c.total_ms as f64 / c.requests as f64 } else { 0.0 },
+            p95_ms: c.p95_ms(),
+            max_ms: c.max_ms,
+        })
+        .collect();
+    usage.sort_by(|a, b| {
+        b.requests
+            .cmp(&a.requests)
+            .then_with(|| a.client.cmp(&b.client))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    usage
+}
+
+/// 时间段内的调用统计，`client` 为空时返回全部客户端
+pub async fn report(
+    conn: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    client: Option<&str>,
+) -> Result<Vec<EndpointUsage>, AppError> {
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间必须早于结束时间".into()));
+    }
+
+    // 统计周期为整小时，包含开始时间所在的周期
+    let mut select = ApiUsageEntity::find()
+        .filter(ApiUsageColumn::PeriodStart.gt(start - Duration::hours(1)))
+        .filter(ApiUsageColumn::PeriodStart.lt(end));
+    if let Some(client) = client {
+        select = select.filter(ApiUsageColumn::Client.eq(client));
+    }
+    let rows = select.all(conn).await.map_err(|_| AppError::InternalError)?;
+
+    let key_names: HashMap<String, String> = ApiKeyEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|key| (key.key_prefix, key.name))
+        .collect();
+
+    Ok(summarize(&rows, &key_names))
+}
+
+/// 后台任务：定期写入统计并清理过期记录
+pub async fn run_flusher(recorder: UsageRecorder, config: ApiUsageConfig, db: DbManager) {
+    let conn = db.get_connection();
+    let interval = std::time::Duration::from_secs(config.flush_interval_secs.max(1));
+    info!("API usage statistics flushed every {:?}", interval);
+
+    let mut last_prune: Option<DateTime<Utc>> = None;
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = recorder.flush(conn).await {
+            warn!("Failed to flush API usage statistics: {:?}", e);
+        }
+
+        let now = Utc::now();
+        if last_prune.is_none_or(|at| now - at >= Duration::hours(1)) {
+            let cutoff = now - Duration::days(config.retention_days as i64);
+            match ApiUsageEntity::delete_many()
+                .filter(ApiUsageColumn::PeriodStart.lt(cutoff))
+                .exec(conn)
+                .await
+            {
+                Ok(_) => last_prune = Some(now),
+                Err(e) => error!("Failed to prune API usage statistics: {:?}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let mut counters = Counters::default();
+        for _ in 0..18 {
+            counters.record(200, 8);
+        }
+        counters.record(404, 40);
+        counters.record(503, 3000);
+        assert_eq!(counters.requests, 20);
+        assert_eq!(counters.client_errors, 1);
+        assert_eq!(counters.server_errors, 1);
+        assert_eq!(counters.p95_ms(), 50);
+        assert_eq!(counters.max_ms, 3000);
+
+        counters.record(200, 20000);
+        assert_eq!(counters.p95_ms(), 5000);
+
+        let mut merged = Counters::default();
+        merged.merge(&counters);
+        merged.merge(&counters);
+        assert_eq!(merged.requests, 42);
+        assert_eq!(merged.histogram[1], 36);
+    }
+}
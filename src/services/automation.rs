@@ -0,0 +1,123 @@
+//! 自动化规则的生效时段
+//!
+//! `trigger_time_range` 为 `HH:MM-HH:MM` 形式的本地时段，按规则所属厂站的时区解释，未关联厂站时
+//! 用默认时区；结束时刻不晚于开始时刻表示跨零点，为空表示全天。按本地钟面时刻判断，夏令时拨快
+//! 跳过的时刻不会落在时段内，拨慢重复的时刻两次都按同一时刻判断。
+
+use crate::config::timezone::TimezoneConfig;
+use crate::models::automation_rule::Model as AutomationRule;
+use crate::services::calendar::parse_time;
+use crate::services::site as site_service;
+use crate::utils::error::AppError;
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+
+/// 本地时段，`end` 不晚于 `start` 时跨零点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.end <= self.start {
+            time >= self.start || time < self.end
+        } else {
+            time >= self.start && time < self.end
+        }
+    }
+}
+
+/// 解析 `HH:MM-HH:MM`，空字符串表示全天，返回 `None`
+pub fn parse_window(s: &str) -> Result<Option<TimeWindow>, AppError> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    let window = s.split_once('-').and_then(|(start, end)| {
+        Some(TimeWindow { start: parse_time(start)?, end: parse_time(end)? })
+    });
+    window.map(Some).ok_or_else(|| {
+        AppError::InvalidInput(format!("触发时间段 {} 无效，应为 HH:MM-HH:MM", s).into())
+    })
+}
+
+/// 规则在 `at` 时刻是否处于生效时段，时段无效的规则不生效
+pub fn is_active(rule: &AutomationRule, tz: Tz, at: DateTime<Utc>) -> bool {
+    match parse_window(&rule.trigger_time_range) {
+        Ok(None) => true,
+        Ok(Some(window)) => window.contains(at.with_timezone(&tz).time()),
+        Err(_) => false,
+    }
+}
+
+/// 从规则中筛出 `at` 时刻生效的规则，各规则按所属厂站的时区判断
+pub async fn active_rules(
+    conn: &DatabaseConnection,
+    config: &TimezoneConfig,
+    rules: Vec<AutomationRule>,
+    at: DateTime<Utc>,
+) -> Result<Vec<AutomationRule>, AppError> {
+    let mut zones: HashMap<Option<i32>, Tz> = HashMap::new();
+    let mut active = Vec::new();
+    for rule in rules {
+        let tz = match zones.get(&rule.site_id) {
+            Some(tz) => *tz,
+            None => {
+                let tz = site_service::site_timezone(conn, config, rule.site_id).await?;
+                zones.insert(rule.site_id, tz);
+                tz
+            }
+        };
+        if is_active(&rule, tz, at) {
+            active.push(rule);
+        }
+    }
+    Ok(active)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::timezone;
+    use chrono::TimeZone;
+
+    fn rule(trigger_time_range: &str) -> AutomationRule {
+        AutomationRule {
+            id: 1,
+            action: "aerate".to_string(),
+            level: 1,
+            trigger_time_range: trigger_time_range.to_string(),
+            site_id: None,
+            sync_alarm: false,
+            organization_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_windows() {
+        assert!(parse_window("").unwrap().is_none());
+        assert!(parse_window("08:00").is_err());
+        assert!(parse_window("08:00-25:00").is_err());
+
+        let night = parse_window("22:00-06:00").unwrap().unwrap();
+        assert!(night.contains(parse_time("23:30").unwrap()));
+        assert!(night.contains(parse_time("05:59").unwrap()));
+        assert!(!night.contains(parse_time("06:00").unwrap()));
+
+        // 纽约 02:00-03:00 在 2026-03-08 拨快跳过，在 2026-11-01 之后按本地时刻判断
+        let new_york = timezone::parse("America/New_York").unwrap();
+        let early = rule("02:00-03:00");
+        let skipped = Utc.with_ymd_and_hms(2026, 3, 8, 7, 0, 0).unwrap(); // 本地 03:00
+        assert!(!is_active(&early, new_york, skipped));
+        let winter = Utc.with_ymd_and_hms(2026, 11, 2, 7, 30, 0).unwrap(); // 本地 02:30
+        assert!(is_active(&early, new_york, winter));
+        assert!(!is_active(&early, Tz::UTC, winter));
+        assert!(!is_active(&rule("always"), Tz::UTC, winter));
+        assert!(is_active(&rule(""), Tz::UTC, winter));
+    }
+}
@@ -0,0 +1,220 @@
+//! 二进制上报帧解码
+//!
+//! 供蜂窝网关等带宽受限的场景使用，所有整数和浮点数均为小端序。请求体由一个或多个帧直接拼接：
+//!
+//! ```text
+//! 帧头（18 字节）
+//!   0..2   magic      0x57 0x42（"WB"）
+//!   2      version    当前为 1
+//!   3      flags      保留，必须为 0
+//!   4..8   device_id  u32，0 表示不关联设备
+//!   8..16  base_time  i64，Unix 毫秒时间戳
+//!   16..18 count      u16，记录条数
+//! 记录（每条 9 字节，紧随帧头）
+//!   0      metric     u8，指标编码，见 METRIC_CODES
+//!   1..5   offset     u32，相对 base_time 的毫秒数
+//!   5..9   value      f32
+//! ```
+//!
+//! 帧结构错误时整个请求被拒绝；单条记录的错误（未知指标、数值超出范围等）只跳过该条。
+
+use crate::services::metric_registry::{
+    DISTANCE, FLOW, LEVEL, PERCENT_FULL, PH, POWER, PRESSURE, PUMP_EFFICIENCY, TDS, TEMPERATURE,
+    TURBIDITY, VIBRATION_RMS, VOLUME,
+};
+use chrono::{DateTime, Utc};
+
+pub const MAGIC: [u8; 2] = [0x57, 0x42];
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 18;
+pub const RECORD_LEN: usize = 9;
+
+/// 指标编码，只能追加，不能修改已有编码
+pub const METRIC_CODES: &[(u8, &str)] = &[
+    (1, PH),
+    (2, TDS),
+    (3, TURBIDITY),
+    (4, FLOW),
+    (5, TEMPERATURE),
+    (6, PRESSURE),
+    (7, DISTANCE),
+    (8, LEVEL),
+    (9, VOLUME),
+    (10, PERCENT_FULL),
+    (11, POWER),
+    (12, PUMP_EFFICIENCY),
+    (13, VIBRATION_RMS),
+];
+
+fn metric_for(code: u8) -> Option<&'static str> {
+    METRIC_CODES.iter().find(|(c, _)| *c == code).map(|(_, key)| *key)
+}
+
+/// 解码出的一条记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub metric_type: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+}
+
+/// 一个帧，记录部分在迭代时才解码
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    pub device_id: Option<i32>,
+    pub base_time_ms: i64,
+    records: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    pub fn record_count(&self) -> usize {
+        self.records.len() / RECORD_LEN
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = Result<Record, String>> + 'a {
+        let device_id = self.device_id;
+        let base_time_ms = self.base_time_ms;
+        self.records
+            .chunks_exact(RECORD_LEN)
+            .map(move |r| decode_record(r, device_id, base_time_ms))
+    }
+}
+
+fn decode_record(r: &[u8], device_id: Option<i32>, base_time_ms: i64) -> Result<Record, String> {
+    let metric_type = metric_for(r[0]).ok_or_else(|| format!("未知的指标编码: {}", r[0]))?;
+    let offset = u32::from_le_bytes([r[1], r[2], r[3], r[4]]);
+    let value = f32::from_le_bytes([r[5], r[6], r[7], r[8]]);
+    if !value.is_finite() {
+        return Err("数值无效".to_string());
+    }
+    let timestamp = base_time_ms
+        .checked_add(offset as i64)
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .ok_or_else(|| "时间戳无效".to_string())?;
+    Ok(Record {
+        metric_type,
+        timestamp,
+        value: value as f64,
+        device_id,
+    })
+}
+
+/// 切分请求体中的所有帧，只校验帧结构
+pub fn decode_frames(mut body: &[u8]) -> Result<Vec<Frame<'_>>, String> {
+    if body.is_empty() {
+        return Err("请求体为空".to_string());
+    }
+    let mut frames = Vec::new();
+    while !body.is_empty() {
+        let index = frames.len();
+        if body.len() < HEADER_LEN {
+            return Err(format!("第 {} 帧帧头不完整", index));
+        }
+        if body[0..2] != MAGIC {
+            return Err(format!("第 {} 帧标识错误", index));
+        }
+        if body[2] != VERSION {
+            return Err(format!("第 {} 帧版本不支持: {}", index, body[2]));
+        }
+        if body[3] != 0 {
+            return Err(format!("第 {} 帧保留字段不为 0", index));
+        }
+        let device_id = match u32::from_le_bytes([body[4], body[5], body[6], body[7]]) {
+            0 => None,
+            id => match i32::try_from(id) {
+                Ok(id) => Some(id),
+                Err(_) => return Err(format!("第 {} 帧设备ID超出范围", index)),
+            },
+        };
+        let mut base_time = [0u8; 8];
+        base_time.copy_from_slice(&body[8..16]);
+        let count = u16::from_le_bytes([body[16], body[17]]) as usize;
+
+        let end = HEADER_LEN + count * RECORD_LEN;
+        if body.len() < end {
+            return Err(format!("第 {} 帧记录不完整", index));
+        }
+        frames.push(Frame {
+            device_id,
+            base_time_ms: i64::from_le_bytes(base_time),
+            records: &body[HEADER_LEN..end],
+        });
+        body = &body[end..];
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    // 一致性测试向量，网关端实现应能生成完全相同的字节
+    // 设备 7，base_time 2024-01-01T00:00:00Z (1704067200000 ms)，两条记录：
+    //   ph     +0 ms     7.25
+    //   flow   +1000 ms  120.5
+    const VECTOR_TWO_RECORDS: &str = "5742 01 00 07000000 00f451c28c010000 0200
+                                      01 00000000 0000e840
+                                      04 e8030000 0000f142";
+
+    #[test]
+    fn test_vector_two_records() {
+        let body = hex(VECTOR_TWO_RECORDS);
+        let frames = decode_frames(&body).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].record_count(), 2);
+
+        let records: Vec<Record> = frames[0].records().map(Result::unwrap).collect();
+        let base = DateTime::<Utc>::from_timestamp_millis(1_704_067_200_000).unwrap();
+        assert_eq!(
+            records[0],
+            Record { metric_type: PH, timestamp: base, value: 7.25, device_id: Some(7) }
+        );
+        assert_eq!(records[1].metric_type, FLOW);
+        assert_eq!(records[1].value, 120.5);
+        assert_eq!(records[1].timestamp, base + chrono::Duration::seconds(1));
+    }
+
+    #[test]
+    fn test_vector_concatenated_frames() {
+        // 第二帧不关联设备，零条记录
+        let second = "5742 01 00 00000000 00f451c28c010000 0000";
+        let body = hex(&format!("{} {}", VECTOR_TWO_RECORDS, second));
+        let frames = decode_frames(&body).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].device_id, None);
+        assert_eq!(frames[1].record_count(), 0);
+    }
+
+    #[test]
+    fn test_vector_record_errors() {
+        // 未知指标编码 0xff；温度为 NaN
+        let body = hex("5742 01 00 01000000 00f451c28c010000 0200
+                        ff 00000000 0000803f
+                        05 00000000 0000c07f");
+        let frames = decode_frames(&body).unwrap();
+        let results: Vec<_> = frames[0].records().collect();
+        assert!(results[0].is_err());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn test_vector_malformed_frames() {
+        for vector in [
+            "",
+            "5742 01 00 07000000",
+            "5743 01 00 07000000 00f451c28c010000 0000",
+            "5742 02 00 07000000 00f451c28c010000 0000",
+            "5742 01 01 07000000 00f451c28c010000 0000",
+            "5742 01 00 ffffffff 00f451c28c010000 0000",
+            "5742 01 00 07000000 00f451c28c010000 0100 01 00000000",
+        ] {
+            assert!(decode_frames(&hex(vector)).is_err(), "vector {:?}", vector);
+        }
+    }
+}
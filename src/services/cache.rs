@@ -0,0 +1,185 @@
+//! 热点数据缓存
+//!
+//! 设备最新值与设备状态按设备ID缓存在本地 redb 文件中，读多写少的接口优先读缓存，
+//! 减少对 SQLite 的并发读。数据写入后按设备失效，下次读取时重新计算。
+//! 缓存只是加速手段：打开失败或读写出错时记录日志并直接查询数据库。
+
+use crate::config::cache::CacheConfig;
+use crate::database::redb::DbManager as RedbManager;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// 设备各指标最新值
+pub const LATEST_VALUES: &str = "latest_values";
+/// 设备状态
+pub const DEVICE_STATUS: &str = "device_status";
+
+const TABLES: [&str; 2] = [LATEST_VALUES, DEVICE_STATUS];
+
+struct Inner {
+    db: RedbManager,
+    /// 失效计数：读取时记下，回填前若已变化说明期间有写入，放弃回填
+    generation: Mutex<u64>,
+}
+
+#[derive(Clone)]
+pub struct HotCache {
+    inner: Option<Arc<Inner>>,
+}
+
+impl std::fmt::Debug for HotCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotCache")
+            .field("enabled", &self.inner.is_some())
+            .finish()
+    }
+}
+
+impl HotCache {
+    /// 打开缓存文件并清空上次运行留下的数据
+    pub fn open(config: &CacheConfig) -> Self {
+        if !config.enabled {
+            return Self::disabled();
+        }
+
+        let db = match RedbManager::new(&config.path) {
+            Ok(db) => db,
+            Err(e) => {
+                warn!("Failed to open cache {}: {}; caching disabled", config.path, e);
+                return Self::disabled();
+            }
+        };
+        for table in TABLES {
+            if let Err(e) = db.clear(table) {
+                warn!("Failed to clear cache table {}: {}", table, e);
+            }
+        }
+
+        info!("Hot read cache enabled at {}", config.path);
+        Self {
+            inner: Some(Arc::new(Inner {
+                db,
+                generation: Mutex::new(0),
+            })),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    /// 当前失效计数，回填缓存前读取
+    pub fn generation(&self) -> u64 {
+        match &self.inner {
+            Some(inner) => *inner.generation.lock().unwrap_or_else(|e| e.into_inner()),
+            None => 0,
+        }
+    }
+
+    pub async fn get<T>(&self, table: &'static str, id: i32) -> Option<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let inner = self.inner.clone()?;
+        let result = tokio::task::spawn_blocking(move || inner.db.get::<String>(table, &id.to_string()))
+            .await
+            .ok()?;
+
+        match result {
+            Ok(Some(json)) => serde_json::from_str(&json).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Cache read from {} failed: {}", table, e);
+                None
+            }
+        }
+    }
+
+    /// 回填缓存；`generation` 之后发生过失效则丢弃，避免写回过期数据
+    pub async fn put<T: Serialize>(&self, table: &'static str, id: i32, value: &T, generation: u64) {
+        let Some(inner) = self.inner.clone() else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(value) else {
+            return;
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let current = inner.generation.lock().unwrap_or_else(|e| e.into_inner());
+            if *current != generation {
+                return Ok(());
+            }
+            inner.db.put(table, &id.to_string(), &json)
+        })
+        .await;
+
+        if let Ok(Err(e)) = result {
+            warn!("Cache write to {} failed: {}", table, e);
+        }
+    }
+
+    /// 设备的测量值变化后调用
+    pub async fn invalidate_latest(&self, device_ids: &[i32]) {
+        self.invalidate(&[LATEST_VALUES], device_ids).await;
+    }
+
+    /// 设备本身变化（修改、删除、审批）后调用
+    pub async fn invalidate_device(&self, device_id: i32) {
+        self.invalidate(&TABLES, &[device_id]).await;
+    }
+
+    async fn invalidate(&self, tables: &'static [&'static str], device_ids: &[i32]) {
+        let Some(inner) = self.inner.clone() else {
+            return;
+        };
+        let keys: Vec<String> = device_ids.iter().map(|id| id.to_string()).collect();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut generation = inner.generation.lock().unwrap_or_else(|e| e.into_inner());
+            *generation += 1;
+            for table in tables {
+                for key in &keys {
+                    inner.db.delete(table, key)?;
+                }
+            }
+            Ok::<_, crate::database::redb::DbError>(())
+        })
+        .await;
+
+        if let Ok(Err(e)) = result {
+            warn!("Cache invalidation failed: {}", e);
+        }
+    }
+
+    /// 批量变更（导入、回滚等）后清空全部缓存
+    pub async fn clear(&self) {
+        self.clear_tables(&TABLES).await;
+    }
+
+    /// 批量删除测量值（保留期清理）后清空全部最新值，受影响的设备无法逐个确定
+    pub async fn clear_latest(&self) {
+        self.clear_tables(&[LATEST_VALUES]).await;
+    }
+
+    async fn clear_tables(&self, tables: &'static [&'static str]) {
+        let Some(inner) = self.inner.clone() else {
+            return;
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut generation = inner.generation.lock().unwrap_or_else(|e| e.into_inner());
+            *generation += 1;
+            for table in tables {
+                inner.db.clear(table)?;
+            }
+            Ok::<_, crate::database::redb::DbError>(())
+        })
+        .await;
+
+        if let Ok(Err(e)) = result {
+            warn!("Cache clear failed: {}", e);
+        }
+    }
+}
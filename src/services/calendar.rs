@@ -0,0 +1,355 @@
+//! 班次与节假日日历
+//!
+//! 自动化计划、报表周期、值班通知等需要判断“工作时间”的地方统一通过 [`Calendar`] 查询，
+//! 不再各自硬编码时间段。日期、时刻均按厂站时区解释（见 [`crate::utils::timezone`]），
+//! 夏令时切换日跨越跳过时段的班次相应缩短，跨越重复时段的班次相应延长。
+//!
+//! 厂站有自己的班次时只使用厂站班次，否则使用全局班次；同一天厂站的特殊日期优先于全局日期。
+
+use crate::models::calendar_day::{
+    Column as DayColumn, Entity as DayEntity, Model as CalendarDay, KIND_HOLIDAY, KIND_WORKDAY,
+};
+use crate::models::calendar_shift::{
+    Column as ShiftColumn, Entity as ShiftEntity, Model as CalendarShift,
+};
+use crate::config::timezone::TimezoneConfig;
+use crate::services::site as site_service;
+use crate::utils::error::AppError;
+use crate::utils::timezone;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// 解析 `1,2,3,4,5` 形式的星期列表（1 为周一，7 为周日）
+pub fn parse_weekdays(s: &str) -> Option<Vec<u32>> {
+    let mut days = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let day: u32 = part.parse().ok()?;
+        if !(1..=7).contains(&day) {
+            return None;
+        }
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    (!days.is_empty()).then_some(days)
+}
+
+/// 解析 `HH:MM` 形式的时刻
+pub fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// 校验班次定义
+pub fn validate_shift(shift: &CalendarShift) -> Result<(), AppError> {
+    let invalid = |msg: String| Err(AppError::InvalidInput(format!("班次无效: {}", msg).into()));
+
+    if shift.name.trim().is_empty() {
+        return invalid("名称不能为空".to_string());
+    }
+    if parse_weekdays(&shift.weekdays).is_none() {
+        return invalid(format!("星期列表 {} 无效，应为 1-7 的逗号分隔列表", shift.weekdays));
+    }
+    for time in [&shift.start_time, &shift.end_time] {
+        if parse_time(time).is_none() {
+            return invalid(format!("时刻 {} 无效，应为 HH:MM", time));
+        }
+    }
+    Ok(())
+}
+
+/// 校验特殊日期定义
+pub fn validate_day(day: &CalendarDay) -> Result<(), AppError> {
+    if day.kind != KIND_HOLIDAY && day.kind != KIND_WORKDAY {
+        return Err(AppError::InvalidInput(
+            format!("日期类型 {} 无效，应为 holiday 或 workday", day.kind).into(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct Shift {
+    name: String,
+    weekdays: Vec<u32>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Shift {
+    /// 结束时刻不晚于开始时刻的班次跨零点（相等表示 24 小时）
+    fn overnight(&self) -> bool {
+        self.end <= self.start
+    }
+}
+
+/// 某个班次在某天的起止时间
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ShiftPeriod {
+    pub shift: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// 某一时刻的日历状态
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CalendarStatus {
+    pub at: DateTime<Utc>,
+    /// 本地日期
+    pub date: NaiveDate,
+    pub working_day: bool,
+    /// holiday / workday，普通日期为空
+    pub day_kind: Option<String>,
+    pub day_name: Option<String>,
+    /// 当前所在班次，不在班次内为空
+    pub shift: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    shifts: Vec<Shift>,
+    days: HashMap<NaiveDate, CalendarDay>,
+    tz: Tz,
+}
+
+impl Calendar {
+    /// 由数据库记录构建，无效或停用的班次忽略；时区为 UTC
+    pub fn new(shifts: Vec<CalendarShift>, days: Vec<CalendarDay>) -> Self {
+        let site_specific = shifts.iter().any(|s| s.enabled && s.site_id.is_some());
+        let shifts = shifts
+            .into_iter()
+            .filter(|s| s.enabled && s.site_id.is_some() == site_specific)
+            .filter_map(|s| {
+                Some(Shift {
+                    weekdays: parse_weekdays(&s.weekdays)?,
+                    start: parse_time(&s.start_time)?,
+                    end: parse_time(&s.end_time)?,
+                    name: s.name,
+                })
+            })
+            .collect();
+
+        let mut by_date: HashMap<NaiveDate, CalendarDay> = HashMap::new();
+        for day in days {
+            let replace = by_date
+                .get(&day.date)
+                .is_none_or(|existing| existing.site_id.is_none() && day.site_id.is_some());
+            if replace {
+                by_date.insert(day.date, day);
+            }
+        }
+        Self { shifts, days: by_date, tz: Tz::UTC }
+    }
+
+    pub fn with_timezone(mut self, tz: Tz) -> Self {
+        self.tz = tz;
+        self
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// 加载厂站（含全局）的日历，按厂站时区解释；`site_id` 为空时只加载全局定义，用默认时区
+    pub async fn load(
+        conn: &DatabaseConnection,
+        config: &TimezoneConfig,
+        site_id: Option<i32>,
+    ) -> Result<Self, AppError> {
+        let tz = site_service::site_timezone(conn, config, site_id).await?;
+        let mut shift_scope = Condition::any().add(ShiftColumn::SiteId.is_null());
+        let mut day_scope = Condition::any().add(DayColumn::SiteId.is_null());
+        if let Some(site_id) = site_id {
+            shift_scope = shift_scope.add(ShiftColumn::SiteId.eq(site_id));
+            day_scope = day_scope.add(DayColumn::SiteId.eq(site_id));
+        }
+        let shifts = ShiftEntity::find()
+            .filter(shift_scope)
+            .order_by_asc(ShiftColumn::StartTime)
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        let days = DayEntity::find()
+            .filter(day_scope)
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        Ok(Self::new(shifts, days).with_timezone(tz))
+    }
+
+    fn day(&self, date: NaiveDate) -> Option<&CalendarDay> {
+        self.days.get(&date)
+    }
+
+    /// 班次在某天是否排班（班次从这一天开始）
+    fn runs_on(&self, shift: &Shift, date: NaiveDate) -> bool {
+        match self.day(date).map(|d| d.kind.as_str()) {
+            Some(KIND_HOLIDAY) => false,
+            Some(KIND_WORKDAY) => true,
+            _ => shift.weekdays.contains(&date.weekday().number_from_monday()),
+        }
+    }
+
+    /// 是否为工作日：有班次排班的日期
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.shifts.iter().any(|shift| self.runs_on(shift, date))
+    }
+
+    /// 本地时刻所在的班次
+    pub fn shift_at(&self, at: NaiveDateTime) -> Option<&str> {
+        let (date, time) = (at.date(), at.time());
+        self.shifts
+            .iter()
+            .find(|shift| {
+                if shift.overnight() {
+                    (time >= shift.start && self.runs_on(shift, date))
+                        || (time < shift.end && self.runs_on(shift, date - Duration::days(1)))
+                } else {
+                    time >= shift.start && time < shift.end && self.runs_on(shift, date)
+                }
+            })
+            .map(|shift| shift.name.as_str())
+    }
+
+    /// 是否在工作时间（任一班次内）
+    pub fn is_business_time(&self, at: DateTime<Utc>) -> bool {
+        self.shift_at(at.with_timezone(&self.tz).naive_local()).is_some()
+    }
+
+    /// 某天各班次的起止时间，用于按班次统计报表
+    pub fn periods(&self, date: NaiveDate) -> Vec<ShiftPeriod> {
+        self.shifts
+            .iter()
+            .filter(|shift| self.runs_on(shift, date))
+            .map(|shift| {
+                let end_date = if shift.overnight() { date + Duration::days(1) } else { date };
+                ShiftPeriod {
+                    shift: shift.name.clone(),
+                    start: timezone::to_utc(self.tz, date.and_time(shift.start)),
+                    end: timezone::to_utc(self.tz, end_date.and_time(shift.end)),
+                }
+            })
+            .collect()
+    }
+
+    pub fn status(&self, at: DateTime<Utc>) -> CalendarStatus {
+        let local = at.with_timezone(&self.tz).naive_local();
+        let day = self.day(local.date());
+        CalendarStatus {
+            at,
+            date: local.date(),
+            working_day: self.is_working_day(local.date()),
+            day_kind: day.map(|d| d.kind.clone()),
+            day_name: day.map(|d| d.name.clone()),
+            shift: self.shift_at(local).map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn shift(
+        site_id: Option<i32>,
+        name: &str,
+        weekdays: &str,
+        start: &str,
+        end: &str,
+    ) -> CalendarShift {
+        CalendarShift {
+            id: 0,
+            site_id,
+            name: name.to_string(),
+            weekdays: weekdays.to_string(),
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn day(site_id: Option<i32>, date: NaiveDate, kind: &str) -> CalendarDay {
+        CalendarDay {
+            id: 0,
+            site_id,
+            date,
+            kind: kind.to_string(),
+            name: String::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+    }
+
+    fn at(date: NaiveDate, time: &str) -> NaiveDateTime {
+        date.and_time(parse_time(time).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_weekdays("1, 2,5,5"), Some(vec![1, 2, 5]));
+        assert_eq!(parse_weekdays("0,1"), None);
+        assert_eq!(parse_weekdays(""), None);
+        assert!(parse_time("08:30").is_some());
+        assert!(parse_time("24:00").is_none());
+    }
+
+    #[test]
+    fn test_shifts_and_days() {
+        // 2026-10-16 为周五
+        let calendar = Calendar::new(
+            vec![
+                shift(None, "白班", "1,2,3,4,5", "08:00", "16:00"),
+                shift(None, "夜班", "1,2,3,4,5", "22:00", "06:00"),
+            ],
+            vec![day(None, date(10, 1), KIND_HOLIDAY), day(None, date(10, 11), KIND_WORKDAY)],
+        );
+        assert_eq!(calendar.shift_at(at(date(10, 16), "09:00")), Some("白班"));
+        assert_eq!(calendar.shift_at(at(date(10, 16), "17:00")), None);
+        // 周五夜班延续到周六早上
+        assert_eq!(calendar.shift_at(at(date(10, 17), "05:00")), Some("夜班"));
+        assert_eq!(calendar.shift_at(at(date(10, 17), "09:00")), None);
+        assert!(!calendar.is_working_day(date(10, 17)));
+        // 节假日不排班，调休的周日排班
+        assert!(!calendar.is_working_day(date(10, 1)));
+        assert_eq!(calendar.shift_at(at(date(10, 11), "10:00")), Some("白班"));
+        assert_eq!(calendar.periods(date(10, 16)).len(), 2);
+    }
+
+    #[test]
+    fn test_site_overrides() {
+        let calendar = Calendar::new(
+            vec![
+                shift(None, "白班", "1,2,3,4,5", "08:00", "16:00"),
+                shift(Some(1), "全天", "1,2,3,4,5,6,7", "00:00", "00:00"),
+            ],
+            vec![day(None, date(10, 1), KIND_HOLIDAY), day(Some(1), date(10, 1), KIND_WORKDAY)],
+        );
+        assert_eq!(calendar.shift_at(at(date(10, 1), "03:00")), Some("全天"));
+        assert_eq!(calendar.shift_at(at(date(10, 18), "20:00")), Some("全天"));
+    }
+
+    #[test]
+    fn test_timezone() {
+        let shifts = vec![shift(None, "夜班", "1,2,3,4,5,6,7", "22:00", "06:00")];
+        let new_york = timezone::parse("America/New_York").unwrap();
+        let calendar = Calendar::new(shifts, vec![]).with_timezone(new_york);
+        // 纽约 2026-11-01 凌晨拨慢一小时，夜班为 9 小时
+        let period = &calendar.periods(date(10, 31))[0];
+        assert_eq!(period.start, Utc.with_ymd_and_hms(2026, 11, 1, 2, 0, 0).unwrap());
+        assert_eq!((period.end - period.start).num_hours(), 9);
+        // 本地 10-15 23:00
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap();
+        assert!(calendar.is_business_time(at));
+        assert_eq!(calendar.status(at).date, date(10, 15));
+    }
+}
@@ -0,0 +1,390 @@
+//! 多点标定曲线
+//!
+//! 非线性的模拟量传感器按设备、指标配置标定曲线，上报时先把原始值换算为实际值再校验入库。
+//! 分段线性曲线在标定点之间线性插值，超出范围时沿首/末段外推；多项式曲线按系数计算。
+//!
+//! 现场校准（对标准液、标准表比对）记录在 `calibrations` 中，用于补偿传感器漂移：单点校准只修正
+//! 零点偏移，两点校准同时修正斜率。校准点的测量值是校准时系统显示的读数，已含此前校准的修正，
+//! 因此修正系数与上一次校准叠加保存。校准时刻之后入库的数据换算为默认单位后按 `gain·x + offset`
+//! 修正，之前的数据不变。每次校准按周期设定下次到期时间，到期前 `remind_days` 天产生一次提醒报警。
+
+use crate::config::calibration::CalibrationConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::calibration::{
+    ActiveModel as CalibrationActiveModel, Column as CalibrationColumn,
+    Entity as CalibrationEntity, Model as Calibration,
+};
+use crate::models::calibration_curve::{
+    Column as CurveColumn, Entity as CurveEntity, Model as CalibrationCurve, KIND_PIECEWISE,
+    KIND_POLYNOMIAL,
+};
+use crate::services::alarm;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use std::collections::BTreeMap;
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+
+/// 解析后的曲线
+#[derive(Debug, Clone, PartialEq)]
+pub enum Curve {
+    /// 按原始值升序排列的标定点
+    Piecewise(Vec<(f64, f64)>),
+    /// 系数 c0 + c1·x + c2·x² + ...
+    Polynomial(Vec<f64>),
+}
+
+impl Curve {
+    pub fn parse(kind: &str, points: &str) -> Result<Self, AppError> {
+        let invalid = |msg: &str| AppError::InvalidInput(format!("标定曲线无效: {}", msg).into());
+
+        match kind {
+            KIND_PIECEWISE => {
+                let mut points: Vec<(f64, f64)> = serde_json::from_str::<Vec<[f64; 2]>>(points)
+                    .map_err(|_| invalid("标定点格式应为 [[原始值, 实际值], ...]"))?
+                    .into_iter()
+                    .map(|[raw, actual]| (raw, actual))
+                    .collect();
+                if points.len() < 2 {
+                    return Err(invalid("至少需要两个标定点"));
+                }
+                if points.iter().any(|(raw, actual)| !raw.is_finite() || !actual.is_finite()) {
+                    return Err(invalid("标定点必须是有限数值"));
+                }
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                if points.windows(2).any(|w| w[0].0 == w[1].0) {
+                    return Err(invalid("标定点的原始值不能重复"));
+                }
+                Ok(Curve::Piecewise(points))
+            }
+            KIND_POLYNOMIAL => {
+                let coefficients: Vec<f64> = serde_json::from_str(points)
+                    .map_err(|_| invalid("多项式系数格式应为 [c0, c1, ...]"))?;
+                if coefficients.is_empty() || coefficients.iter().any(|c| !c.is_finite()) {
+                    return Err(invalid("至少需要一个有限数值系数"));
+                }
+                Ok(Curve::Polynomial(coefficients))
+            }
+            other => Err(invalid(&format!("未知的曲线类型 {}", other))),
+        }
+    }
+
+    /// 原始值换算为实际值
+    pub fn apply(&self, raw: f64) -> f64 {
+        match self {
+            Curve::Piecewise(points) => {
+                // 找到 raw 所在区间，超出范围时使用首/末段
+                let index = points
+                    .partition_point(|(x, _)| *x <= raw)
+                    .clamp(1, points.len() - 1);
+                let (x0, y0) = points[index - 1];
+                let (x1, y1) = points[index];
+                y0 + (raw - x0) * (y1 - y0) / (x1 - x0)
+            }
+            Curve::Polynomial(coefficients) => coefficients
+                .iter()
+                .rev()
+                .fold(0.0, |acc, c| acc * raw + c),
+        }
+    }
+}
+
+/// 校验曲线定义
+pub fn validate(curve: &CalibrationCurve) -> Result<Curve, AppError> {
+    Curve::parse(&curve.kind, &curve.points)
+}
+
+/// 查找设备该指标的启用曲线
+pub async fn find_enabled(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    metric_type: &str,
+) -> Result<Option<CalibrationCurve>, AppError> {
+    CurveEntity::find()
+        .filter(CurveColumn::DeviceId.eq(device_id))
+        .filter(CurveColumn::MetricType.eq(metric_type))
+        .filter(CurveColumn::Enabled.eq(true))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 按标定曲线换算上报值，没有曲线时原样返回
+pub async fn apply(
+    conn: &DatabaseConnection,
+    device_id: Option<i32>,
+    metric_type: &str,
+    raw: f64,
+) -> Result<f64, AppError> {
+    let Some(device_id) = device_id else {
+        return Ok(raw);
+    };
+    match find_enabled(conn, device_id, metric_type).await? {
+        Some(curve) => Ok(validate(&curve)?.apply(raw)),
+        None => Ok(raw),
+    }
+}
+
+/// 由校准点（标准值, 测量值）计算修正系数 (gain, offset)
+pub fn correction(points: &[(f64, f64)]) -> Result<(f64, f64), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("校准参数无效: {}", msg).into()));
+
+    if points.iter().any(|(reference, measured)| !reference.is_finite() || !measured.is_finite()) {
+        return invalid("标准值和测量值必须是有限数值");
+    }
+    match points {
+        [(reference, measured)] => Ok((1.0, reference - measured)),
+        [(r1, m1), (r2, m2)] => {
+            if m1 == m2 {
+                return invalid("两个校准点的测量值不能相同");
+            }
+            let gain = (r2 - r1) / (m2 - m1);
+            if !(gain.is_finite() && gain > 0.0) {
+                return invalid("标准值与测量值的变化方向相反");
+            }
+            Ok((gain, r1 - gain * m1))
+        }
+        _ => invalid("需要一个或两个校准点"),
+    }
+}
+
+/// 在上一次校准的修正之上叠加本次修正
+pub fn compose(previous: Option<(f64, f64)>, correction: (f64, f64)) -> (f64, f64) {
+    let (gain, offset) = correction;
+    match previous {
+        Some((previous_gain, previous_offset)) => {
+            (gain * previous_gain, gain * previous_offset + offset)
+        }
+        None => (gain, offset),
+    }
+}
+
+/// `at` 时刻生效的校准，即此前最近的一次
+pub async fn effective(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    metric_type: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<Calibration>, AppError> {
+    CalibrationEntity::find()
+        .filter(CalibrationColumn::DeviceId.eq(device_id))
+        .filter(CalibrationColumn::MetricType.eq(metric_type))
+        .filter(CalibrationColumn::CalibratedAt.lte(at))
+        .order_by_desc(CalibrationColumn::CalibratedAt)
+        .order_by_desc(CalibrationColumn::Id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 设备该指标最近一次校准
+pub async fn last(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    metric_type: &str,
+) -> Result<Option<Calibration>, AppError> {
+    CalibrationEntity::find()
+        .filter(CalibrationColumn::DeviceId.eq(device_id))
+        .filter(CalibrationColumn::MetricType.eq(metric_type))
+        .order_by_desc(CalibrationColumn::CalibratedAt)
+        .order_by_desc(CalibrationColumn::Id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 按 `timestamp` 时生效的校准修正漂移，`value` 为默认单位的值；没有校准时原样返回
+pub async fn compensate(
+    conn: &DatabaseConnection,
+    device_id: Option<i32>,
+    metric_type: &str,
+    timestamp: DateTime<Utc>,
+    value: f64,
+) -> Result<f64, AppError> {
+    let Some(device_id) = device_id else {
+        return Ok(value);
+    };
+    match effective(conn, device_id, metric_type, timestamp).await? {
+        Some(calibration) => Ok(calibration.gain * value + calibration.offset),
+        None => Ok(value),
+    }
+}
+
+/// 待记录的校准
+#[derive(Debug, Clone)]
+pub struct NewCalibration {
+    pub device_id: i32,
+    pub metric_type: String,
+    /// (标准值, 测量值)，一个或两个
+    pub points: Vec<(f64, f64)>,
+    pub calibrated_at: DateTime<Utc>,
+    pub technician: String,
+    pub notes: Option<String>,
+    /// 校准周期，缺省为 `default_interval_days`
+    pub interval_days: Option<u32>,
+}
+
+/// 记录一次校准，之后入库的数据按叠加后的系数修正
+pub async fn record(
+    conn: &DatabaseConnection,
+    config: &CalibrationConfig,
+    new: NewCalibration,
+) -> Result<Calibration, AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("校准参数无效: {}", msg).into()));
+
+    if new.technician.trim().is_empty() {
+        return invalid("须填写校准人员");
+    }
+    let interval_days = new.interval_days.unwrap_or(config.default_interval_days);
+    if interval_days == 0 {
+        return invalid("校准周期须大于 0 天");
+    }
+    let correction = correction(&new.points)?;
+    let previous = last(conn, new.device_id, &new.metric_type).await?;
+    if previous.as_ref().is_some_and(|p| new.calibrated_at < p.calibrated_at) {
+        return invalid("校准时间不能早于最近一次校准");
+    }
+    let (gain, offset) = compose(previous.map(|p| (p.gain, p.offset)), correction);
+
+    let active_model = CalibrationActiveModel {
+        device_id: Set(new.device_id),
+        metric_type: Set(new.metric_type),
+        reference_value: Set(new.points[0].0),
+        measured_value: Set(new.points[0].1),
+        reference_value_2: Set(new.points.get(1).map(|p| p.0)),
+        measured_value_2: Set(new.points.get(1).map(|p| p.1)),
+        gain: Set(gain),
+        offset: Set(offset),
+        calibrated_at: Set(new.calibrated_at),
+        technician: Set(new.technician),
+        notes: Set(new.notes),
+        due_at: Set(new.calibrated_at + Duration::days(interval_days as i64)),
+        due_alarmed: Set(false),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    CalibrationEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 各设备各指标最近一次校准，按到期时间排序
+pub async fn latest(conn: &DatabaseConnection) -> Result<Vec<Calibration>, AppError> {
+    let calibrations = CalibrationEntity::find()
+        .order_by_asc(CalibrationColumn::CalibratedAt)
+        .order_by_asc(CalibrationColumn::Id)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let mut latest: BTreeMap<(i32, String), Calibration> = BTreeMap::new();
+    for calibration in calibrations {
+        latest.insert((calibration.device_id, calibration.metric_type.clone()), calibration);
+    }
+    let mut latest: Vec<Calibration> = latest.into_values().collect();
+    latest.sort_by_key(|c| c.due_at);
+    Ok(latest)
+}
+
+/// 对即将到期或已到期的校准提醒，每次校准只提醒一次
+pub async fn alarm_due(
+    conn: &DatabaseConnection,
+    config: &CalibrationConfig,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let horizon = now + Duration::days(config.remind_days as i64);
+    let due: Vec<Calibration> = latest(conn)
+        .await?
+        .into_iter()
+        .filter(|c| !c.due_alarmed && c.due_at <= horizon)
+        .collect();
+
+    let count = due.len();
+    for calibration in due {
+        let days = (calibration.due_at - now).num_days();
+        let rule_name = format!(
+            "设备 {} 的 {} 校准于 {} 到期",
+            calibration.device_id,
+            calibration.metric_type,
+            calibration.due_at.date_naive()
+        );
+        alarm::raise(conn, Some(calibration.device_id), rule_name, days as f64, &config.severity)
+            .await?;
+
+        let mut active_model = calibration.into_active_model();
+        active_model.due_alarmed = Set(true);
+        active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    }
+    Ok(count)
+}
+
+/// 后台任务：检查校准到期
+pub async fn run_scheduler(config: CalibrationConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        match alarm_due(db.get_connection(), &config, Utc::now()).await {
+            Ok(0) => {}
+            Ok(count) => info!("Raised {} calibration due reminders", count),
+            Err(e) => error!("Calibration due check failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piecewise() {
+        let curve = Curve::parse(KIND_PIECEWISE, "[[20, 100], [4, 0], [12, 40]]").unwrap();
+
+        assert_eq!(curve.apply(4.0), 0.0);
+        assert_eq!(curve.apply(8.0), 20.0);
+        assert_eq!(curve.apply(12.0), 40.0);
+        assert_eq!(curve.apply(16.0), 70.0);
+        // 超出范围沿首/末段外推
+        assert_eq!(curve.apply(2.0), -10.0);
+        assert_eq!(curve.apply(22.0), 115.0);
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let curve = Curve::parse(KIND_POLYNOMIAL, "[1, 2, 0.5]").unwrap();
+
+        assert_eq!(curve.apply(0.0), 1.0);
+        assert_eq!(curve.apply(2.0), 7.0);
+    }
+
+    #[test]
+    fn test_invalid_curves() {
+        assert!(Curve::parse(KIND_PIECEWISE, "[[1, 2]]").is_err());
+        assert!(Curve::parse(KIND_PIECEWISE, "[[1, 2], [1, 3]]").is_err());
+        assert!(Curve::parse(KIND_POLYNOMIAL, "[]").is_err());
+        assert!(Curve::parse("spline", "[1]").is_err());
+    }
+
+    #[test]
+    fn test_drift_correction() {
+        // 单点：零点偏移 +0.2
+        let (gain, offset) = correction(&[(7.0, 6.8)]).unwrap();
+        assert_eq!(gain, 1.0);
+        assert!((offset - 0.2).abs() < 1e-9);
+        // 两点：pH 4.0 读 4.2，pH 10.0 读 9.7
+        let (gain, offset) = correction(&[(4.0, 4.2), (10.0, 9.7)]).unwrap();
+        assert!((gain * 4.2 + offset - 4.0).abs() < 1e-9);
+        assert!((gain * 9.7 + offset - 10.0).abs() < 1e-9);
+        assert!(correction(&[(4.0, 5.0), (10.0, 5.0)]).is_err());
+        assert!(correction(&[(4.0, 9.0), (10.0, 3.0)]).is_err());
+        assert!(correction(&[]).is_err());
+
+        // 第二次校准看到的读数已含第一次的修正：原始 x 先修正为 2x + 1，再修正为 (2x + 1) - 0.5
+        let (gain, offset) = compose(Some((2.0, 1.0)), (1.0, -0.5));
+        assert_eq!((gain, offset), (2.0, 0.5));
+        assert_eq!(compose(None, (1.1, 0.3)), (1.1, 0.3));
+    }
+}
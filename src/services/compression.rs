@@ -0,0 +1,307 @@
+//! 上报数据压缩
+//!
+//! 在入库前按规则丢弃冗余样本，被丢弃的条数记在下一条入库记录的 `suppressed_count` 上。
+//! 死区压缩只看与上次入库值的差；旋转门压缩（SDT）保证入库点之间线性插值的偏差不超过容差，
+//! 读取时用 [`interpolate`] 还原任意时刻的值。
+//! 状态只保存在内存中，重启后每个序列的第一条数据总会入库，旋转门尚未入库的最后一个样本会丢失。
+
+use crate::config::compression::{CompressionConfig, CompressionMethod, CompressionRule};
+use crate::services::measurement::NewMeasurement;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// 旋转门中尚未入库的最新样本，以及门的上下斜率（每秒）
+#[derive(Debug, Clone)]
+struct Held {
+    sample: NewMeasurement,
+    upper: f64,
+    lower: f64,
+}
+
+/// 单个序列的压缩状态
+#[derive(Debug, Clone)]
+struct Series {
+    /// 上一次入库的样本
+    timestamp: DateTime<Utc>,
+    value: f64,
+    held: Option<Held>,
+    suppressed: i32,
+}
+
+impl Series {
+    fn stored(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self {
+            timestamp,
+            value,
+            held: None,
+            suppressed: 0,
+        }
+    }
+
+    /// 从上次入库点出发、经过 (timestamp, value ± tolerance) 的两条斜率
+    fn slopes(&self, timestamp: DateTime<Utc>, value: f64, tolerance: f64) -> (f64, f64) {
+        let dt = (timestamp - self.timestamp).num_milliseconds() as f64 / 1000.0;
+        (
+            (value + tolerance - self.value) / dt,
+            (value - tolerance - self.value) / dt,
+        )
+    }
+}
+
+/// 压缩判断结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// 入库，附带此前被丢弃的条数
+    Store { suppressed: i32 },
+    Suppress,
+    /// 旋转门关闭：把此前暂存的样本入库，当前样本转为暂存
+    StoreHeld { held: NewMeasurement, suppressed: i32 },
+}
+
+#[derive(Clone)]
+pub struct Compressor {
+    config: Arc<CompressionConfig>,
+    series: Arc<Mutex<HashMap<(Option<i32>, String), Series>>>,
+}
+
+impl std::fmt::Debug for Compressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Compressor")
+            .field("enabled", &self.config.enabled)
+            .finish_non_exhaustive()
+    }
+}
+
+fn deadband(rule: &CompressionRule, series: &mut Series, new: &NewMeasurement) -> Decision {
+    let elapsed = (new.timestamp - series.timestamp).num_seconds();
+    let within = (new.value - series.value).abs() <= rule.tolerance;
+    if within && elapsed < rule.max_interval_secs as i64 {
+        series.suppressed = series.suppressed.saturating_add(1);
+        return Decision::Suppress;
+    }
+    let suppressed = series.suppressed;
+    *series = Series::stored(new.timestamp, new.value);
+    Decision::Store { suppressed }
+}
+
+fn swinging_door(rule: &CompressionRule, series: &mut Series, new: &NewMeasurement) -> Decision {
+    let (upper, lower) = series.slopes(new.timestamp, new.value, rule.tolerance);
+    let Some(held) = series.held.take() else {
+        series.held = Some(Held {
+            sample: new.clone(),
+            upper,
+            lower,
+        });
+        return Decision::Suppress;
+    };
+
+    let upper = upper.min(held.upper);
+    let lower = lower.max(held.lower);
+    let elapsed = (new.timestamp - series.timestamp).num_seconds();
+    if lower <= upper && elapsed < rule.max_interval_secs as i64 {
+        series.suppressed = series.suppressed.saturating_add(1);
+        series.held = Some(Held {
+            sample: new.clone(),
+            upper,
+            lower,
+        });
+        return Decision::Suppress;
+    }
+
+    // 门已打开：暂存的样本入库，以它为新的起点重新开门
+    let suppressed = series.suppressed;
+    *series = Series::stored(held.sample.timestamp, held.sample.value);
+    let (upper, lower) = series.slopes(new.timestamp, new.value, rule.tolerance);
+    series.held = Some(Held {
+        sample: new.clone(),
+        upper,
+        lower,
+    });
+    Decision::StoreHeld {
+        held: held.sample,
+        suppressed,
+    }
+}
+
+impl Compressor {
+    pub fn new(config: CompressionConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            series: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 判断一条上报数据是否入库，并更新序列状态
+    pub fn check(&self, new: &NewMeasurement) -> Decision {
+        let Some(rule) = self.config.rule_for(new.device_id, &new.metric_type) else {
+            return Decision::Store { suppressed: 0 };
+        };
+
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (new.device_id, new.metric_type.clone());
+        let Some(state) = series.get_mut(&key) else {
+            series.insert(key, Series::stored(new.timestamp, new.value));
+            return Decision::Store { suppressed: 0 };
+        };
+
+        // 乱序到达的旧数据直接入库，不影响序列状态
+        let latest = state.held.as_ref().map_or(state.timestamp, |h| h.sample.timestamp);
+        if new.timestamp <= latest {
+            return Decision::Store { suppressed: 0 };
+        }
+
+        match rule.method {
+            CompressionMethod::Deadband => deadband(rule, state, new),
+            CompressionMethod::SwingingDoor => swinging_door(rule, state, new),
+        }
+    }
+}
+
+/// 插值还原的一个点
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct InterpolatedPoint {
+    pub timestamp: DateTime<Utc>,
+    /// 前后入库点相距超过 `max_gap_secs` 或不在数据范围内时为空
+    pub value: Option<f64>,
+}
+
+/// 按固定间隔在入库点之间线性插值，`points` 须按时间升序
+pub fn interpolate(
+    points: &[(DateTime<Utc>, f64)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step_secs: i64,
+    max_gap_secs: Option<i64>,
+) -> Vec<InterpolatedPoint> {
+    let mut result = Vec::new();
+    let mut next = 0;
+    let mut timestamp = start;
+    while timestamp <= end {
+        while next < points.len() && points[next].0 < timestamp {
+            next += 1;
+        }
+        let value = match (next.checked_sub(1).map(|i| points[i]), points.get(next)) {
+            (_, Some(&(t, v))) if t == timestamp => Some(v),
+            (Some((t0, v0)), Some(&(t1, v1))) => {
+                let gap = (t1 - t0).num_seconds();
+                if max_gap_secs.is_some_and(|max| gap > max) {
+                    None
+                } else {
+                    let span = (t1 - t0).num_milliseconds() as f64;
+                    let offset = (timestamp - t0).num_milliseconds() as f64;
+                    Some(v0 + (v1 - v0) * offset / span)
+                }
+            }
+            _ => None,
+        };
+        result.push(InterpolatedPoint { timestamp, value });
+        timestamp += Duration::seconds(step_secs);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(method: CompressionMethod, device_id: Option<i32>, tolerance: f64) -> CompressionRule {
+        CompressionRule {
+            metric_type: "ph".to_string(),
+            device_id,
+            method,
+            tolerance,
+            max_interval_secs: 60,
+        }
+    }
+
+    fn compressor(rules: Vec<CompressionRule>) -> Compressor {
+        Compressor::new(CompressionConfig { enabled: true, rules })
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + Duration::seconds(secs)
+    }
+
+    fn sample(device_id: i32, secs: i64, value: f64) -> NewMeasurement {
+        NewMeasurement {
+            metric_type: "ph".to_string(),
+            timestamp: at(secs),
+            value,
+            device_id: Some(device_id),
+            unit: None,
+        }
+    }
+
+    #[test]
+    fn test_deadband() {
+        let c = compressor(vec![rule(CompressionMethod::Deadband, None, 0.1)]);
+        assert_eq!(c.check(&sample(1, 0, 7.0)), Decision::Store { suppressed: 0 });
+        assert_eq!(c.check(&sample(1, 10, 7.05)), Decision::Suppress);
+        assert_eq!(c.check(&sample(1, 20, 6.95)), Decision::Suppress);
+        assert_eq!(c.check(&sample(1, 30, 7.2)), Decision::Store { suppressed: 2 });
+        // 超过最长间隔强制入库
+        assert_eq!(c.check(&sample(1, 100, 7.2)), Decision::Store { suppressed: 0 });
+        // 乱序数据直接入库，不影响序列状态
+        assert_eq!(c.check(&sample(1, 50, 7.2)), Decision::Store { suppressed: 0 });
+        assert_eq!(c.check(&sample(1, 110, 7.25)), Decision::Suppress);
+    }
+
+    #[test]
+    fn test_device_rule_takes_precedence() {
+        let c = compressor(vec![
+            rule(CompressionMethod::Deadband, None, 0.1),
+            rule(CompressionMethod::Deadband, Some(2), 1.0),
+        ]);
+        c.check(&sample(2, 0, 7.0));
+        assert_eq!(c.check(&sample(2, 10, 7.5)), Decision::Suppress);
+        c.check(&sample(3, 0, 7.0));
+        assert_eq!(c.check(&sample(3, 10, 7.5)), Decision::Store { suppressed: 0 });
+    }
+
+    #[test]
+    fn test_swinging_door_keeps_ramp_endpoints() {
+        let c = compressor(vec![rule(CompressionMethod::SwingingDoor, None, 0.01)]);
+        assert_eq!(c.check(&sample(1, 0, 7.0)), Decision::Store { suppressed: 0 });
+        // 匀速上升的样本都在门内
+        for i in 1..=5 {
+            assert_eq!(c.check(&sample(1, i * 5, 7.0 + 0.1 * i as f64)), Decision::Suppress);
+        }
+        // 转为持平，门打开，转折点 (25s, 7.5) 入库
+        assert_eq!(
+            c.check(&sample(1, 30, 7.5)),
+            Decision::StoreHeld {
+                held: sample(1, 25, 7.5),
+                suppressed: 4,
+            }
+        );
+        assert_eq!(c.check(&sample(1, 35, 7.5)), Decision::Suppress);
+    }
+
+    #[test]
+    fn test_swinging_door_max_interval() {
+        let c = compressor(vec![rule(CompressionMethod::SwingingDoor, None, 1.0)]);
+        c.check(&sample(1, 0, 7.0));
+        assert_eq!(c.check(&sample(1, 30, 7.0)), Decision::Suppress);
+        assert_eq!(
+            c.check(&sample(1, 60, 7.0)),
+            Decision::StoreHeld {
+                held: sample(1, 30, 7.0),
+                suppressed: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let points = [(at(0), 1.0), (at(10), 2.0), (at(100), 0.0)];
+        let result = interpolate(&points, at(-5), at(20), 5, Some(60));
+        let values: Vec<Option<f64>> = result.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![None, Some(1.0), Some(1.5), Some(2.0), None, None]);
+
+        let result = interpolate(&points, at(55), at(55), 5, None);
+        assert_eq!(result[0].value, Some(1.0));
+    }
+}
@@ -0,0 +1,434 @@
+//! 配置包导出/导入
+//!
+//! 把厂站、区域、设备、报警规则、自动化规则、Modbus 寄存器映射、标定曲线、罐体参数和水泵曲线
+//! （不含测量数据）打包为带 HMAC 签名的 JSON，在测试环境验证后导入生产环境。导入按ID覆盖，
+//! 包中没有的对象保持不变；整个导入在一个事务中完成，任一对象失败则全部回滚。
+//!
+//! 开启 `change_control.require_review` 后，包中有变化的报警规则和自动化规则只生成待审批版本，
+//! 与通过接口修改相同；新建的规则审批通过后由数据库分配ID。
+
+use crate::config::bundle::BundleConfig;
+use crate::config::change_control::ChangeControlConfig;
+use crate::models::{
+    alarm_rule, area, automation_rule, calibration_curve, device, modbus_mapping, pump_curve,
+    site, tank_geometry,
+};
+use crate::services::config_revision::{self, Versioned};
+use crate::utils::crypto;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection,
+    EntityTrait, IntoActiveModel, PrimaryKeyTrait, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 版本 2 起包含寄存器映射、标定曲线、罐体参数和水泵曲线
+pub const FORMAT_VERSION: u32 = 2;
+
+/// 配置包内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BundlePayload {
+    pub sites: Vec<site::Model>,
+    pub areas: Vec<area::Model>,
+    pub devices: Vec<device::Model>,
+    pub alarm_rules: Vec<alarm_rule::Model>,
+    pub automation_rules: Vec<automation_rule::Model>,
+    pub modbus_mappings: Vec<modbus_mapping::Model>,
+    pub calibration_curves: Vec<calibration_curve::Model>,
+    pub tank_geometries: Vec<tank_geometry::Model>,
+    pub pump_curves: Vec<pump_curve::Model>,
+}
+
+/// 签名后的配置包
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfigBundle {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub payload: BundlePayload,
+    /// 对除签名外全部内容的 HMAC-SHA256（十六进制）
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// 参与签名的内容
+#[derive(Serialize)]
+struct SignedContent<'a> {
+    format_version: u32,
+    exported_at: &'a DateTime<Utc>,
+    payload: &'a BundlePayload,
+}
+
+impl ConfigBundle {
+    fn signed_bytes(&self) -> Result<Vec<u8>, AppError> {
+        let content = SignedContent {
+            format_version: self.format_version,
+            exported_at: &self.exported_at,
+            payload: &self.payload,
+        };
+        serde_json::to_vec(&content).map_err(|_| AppError::InternalError)
+    }
+}
+
+/// 单类对象的导入统计
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ImportCount {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// 审批模式下生成的待审批版本数，审批通过前配置表保持不变
+    pub pending: usize,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct BundleImportSummary {
+    pub dry_run: bool,
+    pub sites: ImportCount,
+    pub areas: ImportCount,
+    pub devices: ImportCount,
+    pub alarm_rules: ImportCount,
+    pub automation_rules: ImportCount,
+    pub modbus_mappings: ImportCount,
+    pub calibration_curves: ImportCount,
+    pub tank_geometries: ImportCount,
+    pub pump_curves: ImportCount,
+}
+
+fn signing_key(config: &BundleConfig) -> Result<&[u8], AppError> {
+    if config.signing_key.is_empty() {
+        return Err(AppError::InvalidInput("未配置配置包签名密钥".into()));
+    }
+    Ok(config.signing_key.as_bytes())
+}
+
+/// 导出当前配置并签名
+pub async fn export(conn: &DatabaseConnection, config: &BundleConfig) -> Result<ConfigBundle, AppError> {
+    let key = signing_key(config)?;
+
+    let payload = BundlePayload {
+        sites: site::Entity::find().all(conn).await.map_err(|_| AppError::InternalError)?,
+        areas: area::Entity::find().all(conn).await.map_err(|_| AppError::InternalError)?,
+        devices: device::Entity::find().all(conn).await.map_err(|_| AppError::InternalError)?,
+        alarm_rules: alarm_rule::Entity::find().all(conn).await.map_err(|_| AppError::InternalError)?,
+        automation_rules: automation_rule::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?,
+        modbus_mappings: modbus_mapping::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?,
+        calibration_curves: calibration_curve::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?,
+        tank_geometries: tank_geometry::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?,
+        pump_curves: pump_curve::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?,
+    };
+
+    let mut bundle = ConfigBundle {
+        format_version: FORMAT_VERSION,
+        exported_at: Utc::now(),
+        payload,
+        signature: String::new(),
+    };
+    bundle.signature = crypto::hmac_sha256_hex(key, &bundle.signed_bytes()?);
+
+    Ok(bundle)
+}
+
+/// 校验版本与签名
+pub fn verify(bundle: &ConfigBundle, config: &BundleConfig) -> Result<(), AppError> {
+    let key = signing_key(config)?;
+
+    if bundle.format_version != FORMAT_VERSION {
+        return Err(AppError::InvalidInput(
+            format!("不支持的配置包版本: {}", bundle.format_version).into(),
+        ));
+    }
+    if !crypto::verify_hmac_sha256(key, &bundle.signed_bytes()?, &bundle.signature) {
+        return Err(AppError::InvalidInput("配置包签名校验失败".into()));
+    }
+
+    Ok(())
+}
+
+enum Outcome<M> {
+    Created,
+    Updated(M),
+    Unchanged,
+}
+
+/// 按ID写入：存在则覆盖，不存在则按原ID插入
+async fn upsert<C, E>(conn: &C, id: i32, model: E::Model) -> Result<Outcome<E::Model>, AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: PartialEq + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let existing = E::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    match existing {
+        Some(existing) if existing == model => Ok(Outcome::Unchanged),
+        Some(existing) => {
+            model
+                .into_active_model()
+                .reset_all()
+                .update(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            Ok(Outcome::Updated(existing))
+        }
+        None => {
+            model
+                .into_active_model()
+                .reset_all()
+                .insert(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            Ok(Outcome::Created)
+        }
+    }
+}
+
+async fn import_models<C, E>(
+    conn: &C,
+    models: Vec<E::Model>,
+    id: fn(&E::Model) -> i32,
+) -> Result<ImportCount, AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: PartialEq + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let mut count = ImportCount::default();
+    for model in models {
+        match upsert::<C, E>(conn, id(&model), model).await? {
+            Outcome::Created => count.created += 1,
+            Outcome::Updated(_) => count.updated += 1,
+            Outcome::Unchanged => count.unchanged += 1,
+        }
+    }
+    Ok(count)
+}
+
+/// 导入需要版本记录的配置，每个变更写入 `config_revisions`；需要审批的类型只提交待审批版本
+async fn import_versioned<C, E>(
+    conn: &C,
+    change_control: &ChangeControlConfig,
+    models: Vec<E::Model>,
+    changed_by: &Option<String>,
+) -> Result<ImportCount, AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: Versioned + Clone + PartialEq + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let mut count = ImportCount::default();
+    if config_revision::requires_review(change_control, <E::Model as Versioned>::ENTITY_TYPE) {
+        for model in models {
+            let existing = E::find_by_id(model.entity_id())
+                .one(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            match existing {
+                Some(existing) if existing == model => count.unchanged += 1,
+                Some(existing) => {
+                    config_revision::propose(
+                        conn,
+                        config_revision::ACTION_UPDATE,
+                        Some(&existing),
+                        Some(&model),
+                        changed_by.clone(),
+                    )
+                    .await?;
+                    count.pending += 1;
+                }
+                None => {
+                    config_revision::propose(
+                        conn,
+                        config_revision::ACTION_CREATE,
+                        None,
+                        Some(&model),
+                        changed_by.clone(),
+                    )
+                    .await?;
+                    count.pending += 1;
+                }
+            }
+        }
+        return Ok(count);
+    }
+
+    for model in models {
+        let imported = model.clone();
+        match upsert::<C, E>(conn, model.entity_id(), model).await? {
+            Outcome::Created => {
+                config_revision::record_create(conn, &imported, changed_by.clone()).await?;
+                count.created += 1;
+            }
+            Outcome::Updated(previous) => {
+                config_revision::record_update(conn, &previous, &imported, changed_by.clone()).await?;
+                count.updated += 1;
+            }
+            Outcome::Unchanged => count.unchanged += 1,
+        }
+    }
+    Ok(count)
+}
+
+/// PostgreSQL 按显式ID插入后需要同步自增序列
+async fn sync_sequences<C: ConnectionTrait>(conn: &C) -> Result<(), AppError> {
+    if conn.get_database_backend() != DatabaseBackend::Postgres {
+        return Ok(());
+    }
+
+    for table in [
+        "sites",
+        "areas",
+        "devices",
+        "alarm_rules",
+        "automation_rules",
+        "modbus_mappings",
+        "calibration_curves",
+        "tank_geometries",
+        "pump_curves",
+    ] {
+        let sql = format!(
+            "SELECT setval(pg_get_serial_sequence('{table}', 'id'), COALESCE((SELECT MAX(id) FROM {table}), 0) + 1, false)"
+        );
+        conn.execute(Statement::from_string(DatabaseBackend::Postgres, sql))
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+    Ok(())
+}
+
+/// 校验并导入配置包；`dry_run` 时在事务中执行后回滚，只返回统计
+pub async fn import(
+    conn: &DatabaseConnection,
+    config: &BundleConfig,
+    change_control: &ChangeControlConfig,
+    bundle: ConfigBundle,
+    dry_run: bool,
+    changed_by: Option<String>,
+) -> Result<BundleImportSummary, AppError> {
+    verify(&bundle, config)?;
+    let payload = bundle.payload;
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+
+    let summary = BundleImportSummary {
+        dry_run,
+        sites: import_models::<_, site::Entity>(&txn, payload.sites, |m| m.id).await?,
+        areas: import_models::<_, area::Entity>(&txn, payload.areas, |m| m.id).await?,
+        devices: import_versioned::<_, device::Entity>(
+            &txn,
+            change_control,
+            payload.devices,
+            &changed_by,
+        )
+        .await?,
+        alarm_rules: import_versioned::<_, alarm_rule::Entity>(
+            &txn,
+            change_control,
+            payload.alarm_rules,
+            &changed_by,
+        )
+        .await?,
+        automation_rules: import_versioned::<_, automation_rule::Entity>(
+            &txn,
+            change_control,
+            payload.automation_rules,
+            &changed_by,
+        )
+        .await?,
+        modbus_mappings: import_versioned::<_, modbus_mapping::Entity>(
+            &txn,
+            change_control,
+            payload.modbus_mappings,
+            &changed_by,
+        )
+        .await?,
+        calibration_curves: import_versioned::<_, calibration_curve::Entity>(
+            &txn,
+            change_control,
+            payload.calibration_curves,
+            &changed_by,
+        )
+        .await?,
+        tank_geometries: import_versioned::<_, tank_geometry::Entity>(
+            &txn,
+            change_control,
+            payload.tank_geometries,
+            &changed_by,
+        )
+        .await?,
+        pump_curves: import_versioned::<_, pump_curve::Entity>(
+            &txn,
+            change_control,
+            payload.pump_curves,
+            &changed_by,
+        )
+        .await?,
+    };
+
+    if dry_run {
+        txn.rollback().await.map_err(|_| AppError::InternalError)?;
+    } else {
+        sync_sequences(&txn).await?;
+        txn.commit().await.map_err(|_| AppError::InternalError)?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        let config = BundleConfig {
+            signing_key: "secret".to_string(),
+        };
+        let mut bundle = ConfigBundle {
+            format_version: FORMAT_VERSION,
+            exported_at: Utc::now(),
+            payload: BundlePayload::default(),
+            signature: String::new(),
+        };
+        bundle.signature = crypto::hmac_sha256_hex(b"secret", &bundle.signed_bytes().unwrap());
+        assert!(verify(&bundle, &config).is_ok());
+
+        // 传输后重新解析仍能通过校验
+        let json = serde_json::to_string(&bundle).unwrap();
+        let parsed: ConfigBundle = serde_json::from_str(&json).unwrap();
+        assert!(verify(&parsed, &config).is_ok());
+
+        bundle.exported_at = bundle.exported_at + chrono::Duration::seconds(1);
+        assert!(verify(&bundle, &config).is_err());
+
+        let other = BundleConfig {
+            signing_key: "other".to_string(),
+        };
+        assert!(verify(&parsed, &other).is_err());
+    }
+}
@@ -0,0 +1,719 @@
+//! 配置版本记录与回滚
+//!
+//! 报警规则、自动化规则、设备配置、标定曲线、罐体参数、水泵曲线、振动阈值、Modbus 寄存器映射、
+//! 班次日历、KPI 公式、排污许可限值的每次新建/修改/删除都会写入 `config_revisions`，
+//! 保存变更前后的 JSON 快照，可以查看差异并一键恢复到任意版本。
+//!
+//! 开启 `change_control.require_review` 后，规则的修改只生成待审批版本，
+//! 由另一名工程师审批通过后才写入配置表。
+
+use crate::config::change_control::ChangeControlConfig;
+use crate::models::config_revision::{
+    ActiveModel as ConfigRevisionActiveModel, Column as ConfigRevisionColumn,
+    Entity as ConfigRevisionEntity, Model as ConfigRevision, STATUS_APPLIED, STATUS_PENDING,
+    STATUS_REJECTED,
+};
+use crate::models::{
+    alarm_rule, automation_rule, calendar_day, calendar_shift, calibration_curve, device,
+    discharge_permit, kpi_definition, modbus_mapping, pump_curve, tank_geometry, vibration_limit,
+};
+use crate::utils::error::AppError;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, IntoActiveModel, PrimaryKeyTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait, TryIntoModel,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use utoipa::ToSchema;
+
+pub const ALARM_RULE: &str = "alarm_rule";
+pub const AUTOMATION_RULE: &str = "automation_rule";
+pub const DEVICE: &str = "device";
+pub const CALIBRATION_CURVE: &str = "calibration_curve";
+pub const TANK_GEOMETRY: &str = "tank_geometry";
+pub const PUMP_CURVE: &str = "pump_curve";
+pub const VIBRATION_LIMIT: &str = "vibration_limit";
+pub const MODBUS_MAPPING: &str = "modbus_mapping";
+pub const CALENDAR_SHIFT: &str = "calendar_shift";
+pub const CALENDAR_DAY: &str = "calendar_day";
+pub const KPI_DEFINITION: &str = "kpi_definition";
+pub const DISCHARGE_PERMIT: &str = "discharge_permit";
+
+pub const ACTION_CREATE: &str = "create";
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+pub const ACTION_ROLLBACK: &str = "rollback";
+
+/// 开启审批模式后需要审批的配置类型
+const REVIEWED_TYPES: &[&str] = &[ALARM_RULE, AUTOMATION_RULE];
+
+/// 比较差异时忽略的字段
+const IGNORED_FIELDS: &[&str] = &["created_at", "updated_at"];
+
+/// 需要记录版本的配置
+pub trait Versioned: Serialize {
+    const ENTITY_TYPE: &'static str;
+
+    fn entity_id(&self) -> i32;
+}
+
+impl Versioned for alarm_rule::Model {
+    const ENTITY_TYPE: &'static str = ALARM_RULE;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for automation_rule::Model {
+    const ENTITY_TYPE: &'static str = AUTOMATION_RULE;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for device::Model {
+    const ENTITY_TYPE: &'static str = DEVICE;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for calibration_curve::Model {
+    const ENTITY_TYPE: &'static str = CALIBRATION_CURVE;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for tank_geometry::Model {
+    const ENTITY_TYPE: &'static str = TANK_GEOMETRY;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for pump_curve::Model {
+    const ENTITY_TYPE: &'static str = PUMP_CURVE;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for vibration_limit::Model {
+    const ENTITY_TYPE: &'static str = VIBRATION_LIMIT;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for modbus_mapping::Model {
+    const ENTITY_TYPE: &'static str = MODBUS_MAPPING;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for calendar_shift::Model {
+    const ENTITY_TYPE: &'static str = CALENDAR_SHIFT;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for calendar_day::Model {
+    const ENTITY_TYPE: &'static str = CALENDAR_DAY;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for kpi_definition::Model {
+    const ENTITY_TYPE: &'static str = KPI_DEFINITION;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Versioned for discharge_permit::Model {
+    const ENTITY_TYPE: &'static str = DISCHARGE_PERMIT;
+
+    fn entity_id(&self) -> i32 {
+        self.id
+    }
+}
+
+/// 单个字段的变更
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// 版本查询条件
+#[derive(Debug, Default, Clone)]
+pub struct RevisionFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    pub status: Option<String>,
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|_| AppError::InternalError)
+}
+
+async fn next_revision<C: ConnectionTrait>(
+    conn: &C,
+    entity_type: &str,
+    entity_id: i32,
+) -> Result<i32, AppError> {
+    let latest = ConfigRevisionEntity::find()
+        .filter(ConfigRevisionColumn::EntityType.eq(entity_type))
+        .filter(ConfigRevisionColumn::EntityId.eq(entity_id))
+        .filter(ConfigRevisionColumn::Status.eq(STATUS_APPLIED))
+        .order_by_desc(ConfigRevisionColumn::Revision)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(latest.map(|r| r.revision + 1).unwrap_or(1))
+}
+
+struct NewRevision<'a> {
+    entity_type: &'a str,
+    entity_id: i32,
+    action: &'a str,
+    previous: Option<String>,
+    snapshot: Option<String>,
+    changed_by: Option<String>,
+    status: &'a str,
+}
+
+async fn insert_revision<C: ConnectionTrait>(
+    conn: &C,
+    new: NewRevision<'_>,
+) -> Result<ConfigRevision, AppError> {
+    // 待审批的版本在生效时才分配版本号
+    let revision = if new.status == STATUS_APPLIED {
+        next_revision(conn, new.entity_type, new.entity_id).await?
+    } else {
+        0
+    };
+
+    let new_revision = ConfigRevisionActiveModel {
+        entity_type: Set(new.entity_type.to_string()),
+        entity_id: Set(new.entity_id),
+        revision: Set(revision),
+        action: Set(new.action.to_string()),
+        previous: Set(new.previous),
+        snapshot: Set(new.snapshot),
+        changed_by: Set(new.changed_by),
+        status: Set(new.status.to_string()),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+
+    ConfigRevisionEntity::insert(new_revision)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+async fn record<C, T>(
+    conn: &C,
+    action: &str,
+    before: Option<&T>,
+    after: Option<&T>,
+    changed_by: Option<String>,
+    status: &str,
+) -> Result<ConfigRevision, AppError>
+where
+    C: ConnectionTrait,
+    T: Versioned,
+{
+    let entity_id = before.or(after).map(|m| m.entity_id()).unwrap_or_default();
+    let new = NewRevision {
+        entity_type: T::ENTITY_TYPE,
+        entity_id,
+        action,
+        previous: before.map(to_json).transpose()?,
+        snapshot: after.map(to_json).transpose()?,
+        changed_by,
+        status,
+    };
+    insert_revision(conn, new).await
+}
+
+/// 记录新建
+pub async fn record_create<C, T>(
+    conn: &C,
+    after: &T,
+    changed_by: Option<String>,
+) -> Result<ConfigRevision, AppError>
+where
+    C: ConnectionTrait,
+    T: Versioned,
+{
+    record(conn, ACTION_CREATE, None, Some(after), changed_by, STATUS_APPLIED).await
+}
+
+/// 记录修改
+pub async fn record_update<C, T>(
+    conn: &C,
+    before: &T,
+    after: &T,
+    changed_by: Option<String>,
+) -> Result<ConfigRevision, AppError>
+where
+    C: ConnectionTrait,
+    T: Versioned,
+{
+    record(conn, ACTION_UPDATE, Some(before), Some(after), changed_by, STATUS_APPLIED).await
+}
+
+/// 记录删除
+pub async fn record_delete<C, T>(
+    conn: &C,
+    before: &T,
+    changed_by: Option<String>,
+) -> Result<ConfigRevision, AppError>
+where
+    C: ConnectionTrait,
+    T: Versioned,
+{
+    record(conn, ACTION_DELETE, Some(before), None, changed_by, STATUS_APPLIED).await
+}
+
+/// 该类型的修改是否需要审批
+pub fn requires_review(config: &ChangeControlConfig, entity_type: &str) -> bool {
+    config.require_review && REVIEWED_TYPES.contains(&entity_type)
+}
+
+/// 提交待审批的变更，配置表保持不变
+///
+/// 新建时 `after` 的ID无意义，审批通过后由数据库分配。
+pub async fn propose<C, T>(
+    conn: &C,
+    action: &str,
+    before: Option<&T>,
+    after: Option<&T>,
+    changed_by: Option<String>,
+) -> Result<ConfigRevision, AppError>
+where
+    C: ConnectionTrait,
+    T: Versioned,
+{
+    if changed_by.is_none() {
+        return Err(AppError::InvalidInput("审批模式下必须通过 X-Operator 提供提交人".into()));
+    }
+    record(conn, action, before, after, changed_by, STATUS_PENDING).await
+}
+
+pub async fn list(
+    conn: &DatabaseConnection,
+    filter: &RevisionFilter,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<ConfigRevision>, AppError> {
+    let mut query = ConfigRevisionEntity::find();
+
+    if let Some(entity_type) = &filter.entity_type {
+        query = query.filter(ConfigRevisionColumn::EntityType.eq(entity_type.as_str()));
+    }
+    if let Some(entity_id) = filter.entity_id {
+        query = query.filter(ConfigRevisionColumn::EntityId.eq(entity_id));
+    }
+    if let Some(status) = &filter.status {
+        query = query.filter(ConfigRevisionColumn::Status.eq(status.as_str()));
+    }
+
+    let page = page.max(1);
+    query
+        .order_by_desc(ConfigRevisionColumn::Id)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+pub async fn get(conn: &DatabaseConnection, id: i32) -> Result<ConfigRevision, AppError> {
+    ConfigRevisionEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+/// 比较两个 JSON 快照，返回发生变化的字段
+pub fn diff(previous: Option<&str>, snapshot: Option<&str>) -> Vec<FieldChange> {
+    let parse = |s: Option<&str>| -> Map<String, Value> {
+        s.and_then(|s| serde_json::from_str::<Value>(s).ok())
+            .and_then(|v| match v {
+                Value::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default()
+    };
+    let before = parse(previous);
+    let after = parse(snapshot);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = before.get(field);
+            let new = after.get(field);
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                before: old.cloned(),
+                after: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// 对象当前状态的 JSON 快照，不存在时为 `None`
+async fn current_json<C, E>(conn: &C, entity_id: i32) -> Result<Option<String>, AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: Serialize,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let current = E::find_by_id(entity_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    current.as_ref().map(to_json).transpose()
+}
+
+/// 将对象恢复为快照内容；快照为空表示恢复为“已删除”。返回恢复前的快照
+async fn restore<C, E>(
+    conn: &C,
+    entity_id: i32,
+    snapshot: Option<&str>,
+) -> Result<Option<String>, AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: Serialize + DeserializeOwned + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let current_json = current_json::<C, E>(conn, entity_id).await?;
+
+    match snapshot {
+        Some(snapshot) => {
+            let model: E::Model = serde_json::from_str(snapshot).map_err(|_| AppError::InternalError)?;
+            let active_model = model.into_active_model().reset_all();
+            // 已删除的对象按原ID重新插入，保证版本记录仍能关联
+            if current_json.is_some() {
+                active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+            } else {
+                active_model.insert(conn).await.map_err(|_| AppError::InternalError)?;
+            }
+        }
+        None if current_json.is_some() => {
+            E::delete_by_id(entity_id)
+                .exec(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+        }
+        None => {}
+    }
+
+    Ok(current_json)
+}
+
+/// 应用待审批的变更，返回 (对象ID, 变更前快照, 变更后快照)
+async fn apply_pending<C, E>(
+    conn: &C,
+    pending: &ConfigRevision,
+) -> Result<(i32, Option<String>, Option<String>), AppError>
+where
+    C: ConnectionTrait,
+    E: EntityTrait,
+    E::Model: Versioned + DeserializeOwned + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + TryIntoModel<E::Model> + Send,
+    i32: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    if pending.action == ACTION_CREATE {
+        let snapshot = pending.snapshot.as_deref().ok_or(AppError::InternalError)?;
+        let mut json: Value = serde_json::from_str(snapshot).map_err(|_| AppError::InternalError)?;
+        // 去掉ID，由数据库分配
+        if let Value::Object(map) = &mut json {
+            map.remove("id");
+        }
+        let model = <E::ActiveModel as ActiveModelTrait>::from_json(json)
+            .map_err(|_| AppError::InternalError)?
+            .insert(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        return Ok((model.entity_id(), None, Some(to_json(&model)?)));
+    }
+
+    // 提交后对象已被其他变更修改时拒绝，避免覆盖
+    let current = current_json::<C, E>(conn, pending.entity_id).await?;
+    if current.is_none() || !diff(current.as_deref(), pending.previous.as_deref()).is_empty() {
+        return Err(AppError::InvalidInput("配置在提交后已被修改，请重新提交".into()));
+    }
+
+    let previous = restore::<C, E>(conn, pending.entity_id, pending.snapshot.as_deref()).await?;
+    Ok((pending.entity_id, previous, pending.snapshot.clone()))
+}
+
+/// 按配置类型分派到对应的实体
+macro_rules! with_entity {
+    ($entity_type:expr, $entity:ident => $body:expr) => {
+        match $entity_type {
+            ALARM_RULE => {
+                type $entity = alarm_rule::Entity;
+                $body
+            }
+            AUTOMATION_RULE => {
+                type $entity = automation_rule::Entity;
+                $body
+            }
+            DEVICE => {
+                type $entity = device::Entity;
+                $body
+            }
+            CALIBRATION_CURVE => {
+                type $entity = calibration_curve::Entity;
+                $body
+            }
+            TANK_GEOMETRY => {
+                type $entity = tank_geometry::Entity;
+                $body
+            }
+            PUMP_CURVE => {
+                type $entity = pump_curve::Entity;
+                $body
+            }
+            VIBRATION_LIMIT => {
+                type $entity = vibration_limit::Entity;
+                $body
+            }
+            MODBUS_MAPPING => {
+                type $entity = modbus_mapping::Entity;
+                $body
+            }
+            CALENDAR_SHIFT => {
+                type $entity = calendar_shift::Entity;
+                $body
+            }
+            CALENDAR_DAY => {
+                type $entity = calendar_day::Entity;
+                $body
+            }
+            KPI_DEFINITION => {
+                type $entity = kpi_definition::Entity;
+                $body
+            }
+            DISCHARGE_PERMIT => {
+                type $entity = discharge_permit::Entity;
+                $body
+            }
+            other => {
+                return Err(AppError::InvalidInput(format!("不支持的配置类型: {}", other).into()));
+            }
+        }
+    };
+}
+
+/// 回滚到指定版本（恢复该版本变更后的状态），回滚本身也记录为新版本
+///
+/// 需要审批的类型只生成待审批的回滚版本。
+pub async fn rollback(
+    conn: &DatabaseConnection,
+    config: &ChangeControlConfig,
+    revision_id: i32,
+    changed_by: Option<String>,
+) -> Result<ConfigRevision, AppError> {
+    let target = get(conn, revision_id).await?;
+    if target.status != STATUS_APPLIED {
+        return Err(AppError::InvalidInput("只能回滚到已生效的版本".into()));
+    }
+    let snapshot = target.snapshot.as_deref();
+
+    if requires_review(config, &target.entity_type) {
+        if changed_by.is_none() {
+            return Err(AppError::InvalidInput("审批模式下必须通过 X-Operator 提供提交人".into()));
+        }
+        let previous = with_entity!(target.entity_type.as_str(), E => {
+            current_json::<_, E>(conn, target.entity_id).await?
+        });
+        let new = NewRevision {
+            entity_type: &target.entity_type,
+            entity_id: target.entity_id,
+            action: ACTION_ROLLBACK,
+            previous,
+            snapshot: target.snapshot.clone(),
+            changed_by,
+            status: STATUS_PENDING,
+        };
+        return insert_revision(conn, new).await;
+    }
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+
+    let previous = with_entity!(target.entity_type.as_str(), E => {
+        restore::<_, E>(&txn, target.entity_id, snapshot).await?
+    });
+
+    let new = NewRevision {
+        entity_type: &target.entity_type,
+        entity_id: target.entity_id,
+        action: ACTION_ROLLBACK,
+        previous,
+        snapshot: target.snapshot.clone(),
+        changed_by,
+        status: STATUS_APPLIED,
+    };
+    let revision = insert_revision(&txn, new).await?;
+
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    Ok(revision)
+}
+
+/// 检查审批人：版本须处于待审批状态，且审批人不能是提交人
+fn check_reviewer(pending: &ConfigRevision, reviewer: Option<&str>) -> Result<(), AppError> {
+    if pending.status != STATUS_PENDING {
+        return Err(AppError::InvalidInput("该版本不处于待审批状态".into()));
+    }
+    let Some(reviewer) = reviewer else {
+        return Err(AppError::InvalidInput("必须通过 X-Operator 提供审批人".into()));
+    };
+    if pending.changed_by.as_deref() == Some(reviewer) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 审批通过，变更写入配置表
+pub async fn approve(
+    conn: &DatabaseConnection,
+    id: i32,
+    reviewer: Option<String>,
+) -> Result<ConfigRevision, AppError> {
+    let pending = get(conn, id).await?;
+    check_reviewer(&pending, reviewer.as_deref())?;
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+
+    let (entity_id, previous, snapshot) = with_entity!(pending.entity_type.as_str(), E => {
+        apply_pending::<_, E>(&txn, &pending).await?
+    });
+    let revision = next_revision(&txn, &pending.entity_type, entity_id).await?;
+
+    let mut active_model = pending.into_active_model();
+    active_model.entity_id = Set(entity_id);
+    active_model.revision = Set(revision);
+    active_model.previous = Set(previous);
+    active_model.snapshot = Set(snapshot);
+    active_model.status = Set(STATUS_APPLIED.to_string());
+    active_model.reviewed_by = Set(reviewer);
+    active_model.reviewed_at = Set(Some(Utc::now()));
+    let approved = ConfigRevisionEntity::update(active_model)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    Ok(approved)
+}
+
+/// 驳回待审批的变更
+pub async fn reject(
+    conn: &DatabaseConnection,
+    id: i32,
+    reviewer: Option<String>,
+) -> Result<ConfigRevision, AppError> {
+    let pending = get(conn, id).await?;
+    check_reviewer(&pending, reviewer.as_deref())?;
+
+    let mut active_model = pending.into_active_model();
+    active_model.status = Set(STATUS_REJECTED.to_string());
+    active_model.reviewed_by = Set(reviewer);
+    active_model.reviewed_at = Set(Some(Utc::now()));
+
+    ConfigRevisionEntity::update(active_model)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff() {
+        let before = r#"{"id":1,"name":"pH高","value":8.5,"updated_at":"2024-01-01T00:00:00Z"}"#;
+        let after = r#"{"id":1,"name":"pH高","value":9.0,"updated_at":"2024-02-01T00:00:00Z"}"#;
+
+        let changes = diff(Some(before), Some(after));
+        assert_eq!(
+            changes,
+            vec![FieldChange {
+                field: "value".to_string(),
+                before: Some(json!(8.5)),
+                after: Some(json!(9.0)),
+            }]
+        );
+
+        let created = diff(None, Some(after));
+        assert_eq!(created.len(), 3);
+        assert!(created.iter().all(|c| c.before.is_none()));
+    }
+
+    #[test]
+    fn test_check_reviewer() {
+        let pending = ConfigRevision {
+            id: 1,
+            entity_type: ALARM_RULE.to_string(),
+            entity_id: 3,
+            revision: 0,
+            action: ACTION_UPDATE.to_string(),
+            previous: None,
+            snapshot: None,
+            changed_by: Some("zhang".to_string()),
+            status: STATUS_PENDING.to_string(),
+            reviewed_by: None,
+            reviewed_at: None,
+            created_at: Utc::now(),
+        };
+
+        assert!(check_reviewer(&pending, Some("li")).is_ok());
+        assert!(matches!(check_reviewer(&pending, Some("zhang")), Err(AppError::Forbidden)));
+        assert!(check_reviewer(&pending, None).is_err());
+
+        let applied = ConfigRevision {
+            status: STATUS_APPLIED.to_string(),
+            ..pending
+        };
+        assert!(check_reviewer(&applied, Some("li")).is_err());
+    }
+}
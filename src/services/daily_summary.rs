@@ -0,0 +1,335 @@
+//! 每日汇总
+//!
+//! 每天把各设备的指标最小/最大/平均值、运行时长和报警次数预先汇总到
+//! `daily_summaries` / `daily_device_summaries`，报表和看板直接读汇总表，不再扫描原始测量值。
+//! 汇总按 UTC 自然日计算。补录、修改或删除过去日期的数据（离线暂存补写、手动导入等）时，
+//! 该日期会被标记为待重算，由定时任务重新汇总，也可以手动重算。指标汇总不计入质量为
+//! bad / out_of_range 的数据。
+
+use crate::config::summary::DailySummaryConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::{Column as AlarmLogColumn, Entity as AlarmLogEntity};
+use crate::models::daily_device_summary::{
+    ActiveModel as DailyDeviceSummaryActiveModel, Column as DailyDeviceSummaryColumn,
+    Entity as DailyDeviceSummaryEntity, Model as DailyDeviceSummary,
+};
+use crate::models::daily_summary::{
+    ActiveModel as DailySummaryActiveModel, Column as DailySummaryColumn,
+    Entity as DailySummaryEntity, Model as DailySummary,
+};
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::device_state_event::{CATEGORY_OPERATION, STATE_RUNNING};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::models::summary_dirty_day::{
+    ActiveModel as DirtyDayActiveModel, Column as DirtyDayColumn, Entity as DirtyDayEntity,
+};
+use crate::services::{device_state, quality, rollup};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::sea_query::{Expr, Func, OnConflict};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration as StdDuration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+#[derive(Debug, FromQueryResult)]
+struct MetricAggregate {
+    device_id: i32,
+    metric_type: String,
+    min: f64,
+    max: f64,
+    avg: f64,
+    count: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AlarmCount {
+    device_id: i32,
+    count: i64,
+}
+
+/// 单日指标统计
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MetricSummary {
+    pub metric_type: String,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,
+}
+
+/// 设备单日汇总
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeviceDay {
+    pub day: NaiveDate,
+    pub run_hours: f64,
+    pub alarm_count: i64,
+    pub metrics: Vec<MetricSummary>,
+}
+
+fn day_range(day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = day.and_time(chrono::NaiveTime::MIN).and_utc();
+    (start, start + Duration::days(1))
+}
+
+/// 重新计算某一天的汇总，返回写入的设备数
+pub async fn materialize(conn: &DatabaseConnection, day: NaiveDate) -> Result<usize, AppError> {
+    let (start, end) = day_range(day);
+    if start >= Utc::now() {
+        return Err(AppError::InvalidInput("不能汇总尚未开始的日期".into()));
+    }
+
+    let metrics = MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::DeviceId)
+        .column(MeasurementColumn::MetricType)
+        .column_as(MeasurementColumn::Value.min(), "min")
+        .column_as(MeasurementColumn::Value.max(), "max")
+        .column_as(Func::avg(Expr::col(MeasurementColumn::Value)), "avg")
+        .column_as(MeasurementColumn::Id.count(), "count")
+        .filter(MeasurementColumn::DeviceId.is_not_null())
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lt(end))
+        .filter(quality::usable())
+        .group_by(MeasurementColumn::DeviceId)
+        .group_by(MeasurementColumn::MetricType)
+        .into_model::<MetricAggregate>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let alarms: HashMap<i32, i64> = AlarmLogEntity::find()
+        .select_only()
+        .column(AlarmLogColumn::DeviceId)
+        .column_as(AlarmLogColumn::Id.count(), "count")
+        .filter(AlarmLogColumn::DeviceId.is_not_null())
+        .filter(AlarmLogColumn::TriggerTime.gte(start))
+        .filter(AlarmLogColumn::TriggerTime.lt(end))
+        .group_by(AlarmLogColumn::DeviceId)
+        .into_model::<AlarmCount>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|row| (row.device_id, row.count))
+        .collect();
+
+    let devices = DeviceEntity::find().all(conn).await.map_err(|_| AppError::InternalError)?;
+    let mut device_rows = Vec::with_capacity(devices.len());
+    for device in &devices {
+        let run_seconds: i64 = device_state::durations(conn, device.id, CATEGORY_OPERATION, start, end, false)
+            .await?
+            .iter()
+            .filter(|d| d.state == STATE_RUNNING)
+            .map(|d| d.seconds)
+            .sum();
+        device_rows.push((device.id, run_seconds, alarms.get(&device.id).copied().unwrap_or(0)));
+    }
+
+    let now = Utc::now();
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    DailySummaryEntity::delete_many()
+        .filter(DailySummaryColumn::Day.eq(day))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    DailyDeviceSummaryEntity::delete_many()
+        .filter(DailyDeviceSummaryColumn::Day.eq(day))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    if !metrics.is_empty() {
+        let rows = metrics.into_iter().map(|row| DailySummaryActiveModel {
+            device_id: Set(row.device_id),
+            day: Set(day),
+            metric_type: Set(row.metric_type),
+            min: Set(row.min),
+            max: Set(row.max),
+            avg: Set(row.avg),
+            count: Set(row.count),
+            created_at: Set(now),
+            ..Default::default()
+        });
+        DailySummaryEntity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+    if !device_rows.is_empty() {
+        let rows = device_rows.iter().map(|&(device_id, run_seconds, alarm_count)| DailyDeviceSummaryActiveModel {
+            device_id: Set(device_id),
+            day: Set(day),
+            run_seconds: Set(run_seconds),
+            alarm_count: Set(alarm_count),
+            created_at: Set(now),
+            ..Default::default()
+        });
+        DailyDeviceSummaryEntity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    // 当天的小时汇总一并重算
+    rollup::materialize_hours(conn, start, end.min(Utc::now())).await?;
+    Ok(device_rows.len())
+}
+
+/// 某天是否已汇总
+async fn is_materialized(conn: &DatabaseConnection, day: NaiveDate) -> Result<bool, AppError> {
+    let rows = DailyDeviceSummaryEntity::find()
+        .filter(DailyDeviceSummaryColumn::Day.eq(day))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(rows > 0)
+}
+
+/// 读取设备在日期区间（含两端）内的汇总
+pub async fn device_days(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DeviceDay>, AppError> {
+    let devices = DailyDeviceSummaryEntity::find()
+        .filter(DailyDeviceSummaryColumn::DeviceId.eq(device_id))
+        .filter(DailyDeviceSummaryColumn::Day.between(from, to))
+        .order_by_asc(DailyDeviceSummaryColumn::Day)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut metrics: HashMap<NaiveDate, Vec<MetricSummary>> = HashMap::new();
+    let rows: Vec<DailySummary> = DailySummaryEntity::find()
+        .filter(DailySummaryColumn::DeviceId.eq(device_id))
+        .filter(DailySummaryColumn::Day.between(from, to))
+        .order_by_asc(DailySummaryColumn::MetricType)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    for row in rows {
+        metrics.entry(row.day).or_default().push(MetricSummary {
+            metric_type: row.metric_type,
+            min: row.min,
+            max: row.max,
+            avg: row.avg,
+            count: row.count,
+        });
+    }
+
+    Ok(devices
+        .into_iter()
+        .map(|row: DailyDeviceSummary| DeviceDay {
+            day: row.day,
+            run_hours: row.run_seconds as f64 / 3600.0,
+            alarm_count: row.alarm_count,
+            metrics: metrics.remove(&row.day).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// 标记日期待重算，今天及以后的日期还没有汇总，不需要标记
+///
+/// 标记失败只记录日志，不影响数据本身的写入。
+async fn mark_days(conn: &DatabaseConnection, days: impl Iterator<Item = NaiveDate>) {
+    let today = Utc::now().date_naive();
+    let now = Utc::now();
+    let rows: Vec<DirtyDayActiveModel> = days
+        .filter(|day| *day < today)
+        .map(|day| DirtyDayActiveModel {
+            day: Set(day),
+            marked_at: Set(now),
+            ..Default::default()
+        })
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+
+    let result = DirtyDayEntity::insert_many(rows)
+        .on_conflict(
+            OnConflict::column(DirtyDayColumn::Day)
+                .update_column(DirtyDayColumn::MarkedAt)
+                .to_owned(),
+        )
+        .exec(conn)
+        .await;
+    if let Err(e) = result {
+        warn!("Failed to mark daily summary dirty: {:?}", e);
+    }
+}
+
+/// 某个时刻的数据发生变化，标记其所在日期待重算
+pub async fn mark_dirty(conn: &DatabaseConnection, timestamp: DateTime<Utc>) {
+    mark_days(conn, std::iter::once(timestamp.date_naive())).await;
+}
+
+/// 某个时刻之后的运行状态发生变化，标记从该日期到昨天的所有日期待重算
+pub async fn mark_dirty_since(conn: &DatabaseConnection, timestamp: DateTime<Utc>) {
+    let today = Utc::now().date_naive();
+    let days = timestamp.date_naive().iter_days().take_while(|day| *day < today);
+    mark_days(conn, days).await;
+}
+
+/// 重算所有待重算的日期，返回重算的天数
+async fn recompute_dirty(conn: &DatabaseConnection) -> Result<usize, AppError> {
+    let dirty = DirtyDayEntity::find()
+        .order_by_asc(DirtyDayColumn::Day)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    for row in &dirty {
+        materialize(conn, row.day).await?;
+        // 重算期间再次被标记的日期保留，下次继续重算
+        DirtyDayEntity::delete_many()
+            .filter(DirtyDayColumn::Day.eq(row.day))
+            .filter(DirtyDayColumn::MarkedAt.lte(row.marked_at))
+            .exec(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        info!("Recomputed daily summary for {} after late data", row.day);
+    }
+    Ok(dirty.len())
+}
+
+/// 定期补算最近几天中尚未汇总的日期（不含今天），并重算有补录数据的日期
+pub async fn run_scheduler(config: DailySummaryConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.check_interval_secs.max(60)));
+
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        let today = Utc::now().date_naive();
+
+        for offset in (1..=config.backfill_days as i64).rev() {
+            let day = today - Duration::days(offset);
+            match is_materialized(conn, day).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to check daily summary for {}: {:?}", day, e);
+                    continue;
+                }
+            }
+            match materialize(conn, day).await {
+                Ok(0) => {}
+                Ok(devices) => info!("Materialized daily summary for {} ({} devices)", day, devices),
+                Err(e) => error!("Failed to materialize daily summary for {}: {:?}", day, e),
+            }
+        }
+
+        if let Err(e) = recompute_dirty(conn).await {
+            error!("Failed to recompute dirty daily summaries: {:?}", e);
+        }
+    }
+}
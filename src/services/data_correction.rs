@@ -0,0 +1,132 @@
+//! 数据修正留痕
+//!
+//! 仪表测量值和化验结果修改、删除前，把原始版本的 JSON 快照写入 `data_corrections`，
+//! 记录修正原因和操作人，审计时可以查到每条数据的全部历史版本，数据不会被悄悄覆盖。
+//! 同一条数据的版本号依次递增，原始数据为第 1 版，当前数据的版本号为修正次数加 1。
+
+use crate::models::data_correction::{
+    ActiveModel as DataCorrectionActiveModel, Column as DataCorrectionColumn,
+    Entity as DataCorrectionEntity, Model as DataCorrection,
+};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use serde::Serialize;
+
+/// 修正原因和操作人
+#[derive(Debug, Clone, Default)]
+pub struct CorrectionNote {
+    pub reason: Option<String>,
+    pub corrected_by: Option<String>,
+}
+
+/// 修正记录查询条件
+#[derive(Debug, Clone, Default)]
+pub struct CorrectionFilter {
+    pub record_type: Option<String>,
+    pub record_id: Option<i32>,
+    pub corrected_by: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|_| AppError::InternalError)
+}
+
+/// 数据当前的版本号
+pub async fn current_version<C: ConnectionTrait>(
+    conn: &C,
+    record_type: &str,
+    record_id: i32,
+) -> Result<i32, AppError> {
+    let corrections = DataCorrectionEntity::find()
+        .filter(DataCorrectionColumn::RecordType.eq(record_type))
+        .filter(DataCorrectionColumn::RecordId.eq(record_id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(corrections as i32 + 1)
+}
+
+/// 保存被替换的版本，`current` 为修改后的数据，删除时为 `None`；与数据修改在同一事务中调用
+pub async fn record<C: ConnectionTrait, T: Serialize>(
+    conn: &C,
+    record_type: &str,
+    record_id: i32,
+    action: &str,
+    previous: &T,
+    current: Option<&T>,
+    note: CorrectionNote,
+) -> Result<DataCorrection, AppError> {
+    let version = current_version(conn, record_type, record_id).await?;
+    let correction = DataCorrectionActiveModel {
+        record_type: Set(record_type.to_string()),
+        record_id: Set(record_id),
+        version: Set(version),
+        action: Set(action.to_string()),
+        previous: Set(to_json(previous)?),
+        snapshot: Set(current.map(to_json).transpose()?),
+        reason: Set(note.reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty())),
+        corrected_by: Set(note.corrected_by),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    DataCorrectionEntity::insert(correction)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 一条数据的修正历史，按版本顺序
+pub async fn history(
+    conn: &DatabaseConnection,
+    record_type: &str,
+    record_id: i32,
+) -> Result<Vec<DataCorrection>, AppError> {
+    DataCorrectionEntity::find()
+        .filter(DataCorrectionColumn::RecordType.eq(record_type))
+        .filter(DataCorrectionColumn::RecordId.eq(record_id))
+        .order_by_asc(DataCorrectionColumn::Version)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 分页查询修正记录，按时间倒序
+pub async fn list(
+    conn: &DatabaseConnection,
+    filter: &CorrectionFilter,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<DataCorrection>, AppError> {
+    let mut query = DataCorrectionEntity::find();
+
+    if let Some(record_type) = &filter.record_type {
+        query = query.filter(DataCorrectionColumn::RecordType.eq(record_type.as_str()));
+    }
+    if let Some(record_id) = filter.record_id {
+        query = query.filter(DataCorrectionColumn::RecordId.eq(record_id));
+    }
+    if let Some(corrected_by) = &filter.corrected_by {
+        query = query.filter(DataCorrectionColumn::CorrectedBy.eq(corrected_by.as_str()));
+    }
+    if let Some(start) = filter.start {
+        query = query.filter(DataCorrectionColumn::CreatedAt.gte(start));
+    }
+    if let Some(end) = filter.end {
+        query = query.filter(DataCorrectionColumn::CreatedAt.lte(end));
+    }
+
+    let page = page.max(1);
+    query
+        .order_by_desc(DataCorrectionColumn::Id)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
@@ -0,0 +1,287 @@
+//! 设备命令下发
+//!
+//! 命令以 JSON 发布到设备专属主题 `devices/{id}/cmd`，经 MQTT 离线队列保证送达 broker。
+//! 每条命令带随机的 `correlation_id` 并记录到 `device_commands`，设备执行后在
+//! `devices/{id}/cmd/ack` 上带回该 ID 确认；超过等待时长仍未确认的命令由后台任务标记为超时。
+//! 状态变化同时广播给订阅者，客户端可以轮询命令记录，也可以用 SSE 等待结果。
+
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{Entity as DeviceEntity, Model as Device};
+use crate::models::device_command::{
+    ActiveModel as DeviceCommandActiveModel, Column as DeviceCommandColumn,
+    Entity as DeviceCommandEntity, Model as DeviceCommand, STATUS_ACKED, STATUS_FAILED,
+    STATUS_PENDING, STATUS_TIMED_OUT,
+};
+use crate::mqtt::rumqtt::MqttManager;
+use crate::utils::crypto;
+use crate::utils::error::AppError;
+use chrono::{Duration, Utc};
+use rumqttc::QoS;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+/// 设备确认主题
+pub const ACK_TOPIC: &str = "devices/+/cmd/ack";
+
+/// 单次查询最多返回的命令条数
+const MAX_LIST: u64 = 500;
+/// 超时检查间隔
+const EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub fn command_topic(device_id: i32) -> String {
+    format!("devices/{}/cmd", device_id)
+}
+
+/// 设备发回的确认
+#[derive(Debug, Deserialize)]
+struct Ack {
+    correlation_id: String,
+    /// 执行是否成功，缺省视为成功
+    #[serde(default = "default_success")]
+    success: bool,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+fn parse_ack(payload: &[u8]) -> Result<Ack, String> {
+    let ack: Ack = serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+    if ack.correlation_id.trim().is_empty() {
+        return Err("correlation_id 为空".to_string());
+    }
+    Ok(ack)
+}
+
+/// 仍在等待确认时才更新状态，避免确认与超时互相覆盖
+async fn finish(
+    conn: &DatabaseConnection,
+    mut record: DeviceCommand,
+    status: &str,
+    result: Option<String>,
+) -> Result<Option<DeviceCommand>, sea_orm::DbErr> {
+    let acked_at = (status != STATUS_TIMED_OUT).then(Utc::now);
+    let updated = DeviceCommandEntity::update_many()
+        .col_expr(DeviceCommandColumn::Status, Expr::value(status))
+        .col_expr(DeviceCommandColumn::Result, Expr::value(result.clone()))
+        .col_expr(DeviceCommandColumn::AckedAt, Expr::value(acked_at))
+        .filter(DeviceCommandColumn::Id.eq(record.id))
+        .filter(DeviceCommandColumn::Status.eq(STATUS_PENDING))
+        .exec(conn)
+        .await?;
+    if updated.rows_affected == 0 {
+        return Ok(None);
+    }
+    record.status = status.to_string();
+    record.result = result;
+    record.acked_at = acked_at;
+    Ok(Some(record))
+}
+
+/// 命令确认跟踪
+#[derive(Debug, Clone)]
+pub struct CommandTracker {
+    timeout: Duration,
+    updates: broadcast::Sender<DeviceCommand>,
+}
+
+impl CommandTracker {
+    pub fn new(timeout_secs: u64) -> Self {
+        let (updates, _) = broadcast::channel(256);
+        Self {
+            timeout: Duration::seconds(timeout_secs.max(1) as i64),
+            updates,
+        }
+    }
+
+    /// 订阅命令状态变化
+    pub fn subscribe(&self) -> broadcast::Receiver<DeviceCommand> {
+        self.updates.subscribe()
+    }
+
+    /// 下发命令，返回等待确认的命令记录
+    pub async fn send(
+        &self,
+        conn: &DatabaseConnection,
+        mqtt: Option<&MqttManager>,
+        device_id: i32,
+        command: &str,
+        payload: serde_json::Value,
+    ) -> Result<DeviceCommand, AppError> {
+        let mqtt =
+            mqtt.ok_or_else(|| AppError::ServiceUnavailable("MQTT 未启用，无法下发命令".into()))?;
+        if command.trim().is_empty() {
+            return Err(AppError::InvalidInput("命令不能为空".into()));
+        }
+
+        let device: Device = DeviceEntity::find_by_id(device_id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or(AppError::NotFound)?;
+        if device.provision_status != "active" {
+            return Err(AppError::InvalidInput("设备尚未审批，不能下发命令".into()));
+        }
+
+        let now = Utc::now();
+        let record = DeviceCommandActiveModel {
+            correlation_id: Set(crypto::random_token(16)),
+            device_id: Set(device.id),
+            command: Set(command.to_string()),
+            payload: Set(payload.to_string()),
+            status: Set(STATUS_PENDING.to_string()),
+            result: Set(None),
+            issued_at: Set(now),
+            expires_at: Set(now + self.timeout),
+            acked_at: Set(None),
+            ..Default::default()
+        };
+        let record = DeviceCommandEntity::insert(record)
+            .exec_with_returning(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        let message = json!({
+            "correlation_id": record.correlation_id,
+            "command": command,
+            "payload": payload,
+            "issued_at": now,
+        });
+        mqtt.enqueue_publish(
+            &command_topic(device.id),
+            message.to_string().into_bytes(),
+            QoS::AtLeastOnce,
+        )
+        .await;
+
+        let _ = self.updates.send(record.clone());
+        Ok(record)
+    }
+
+    /// 处理设备确认；未知、已超时或不属于该设备的确认只记日志
+    pub async fn handle_ack(
+        &self,
+        conn: &DatabaseConnection,
+        device_id: i32,
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        let ack = parse_ack(payload).map_err(|e| anyhow::anyhow!("无效的命令确认: {}", e))?;
+        let Some(record) = DeviceCommandEntity::find()
+            .filter(DeviceCommandColumn::CorrelationId.eq(ack.correlation_id.as_str()))
+            .one(conn)
+            .await?
+        else {
+            warn!("Ack for unknown command {} from device {}", ack.correlation_id, device_id);
+            return Ok(());
+        };
+        if record.device_id != device_id {
+            warn!(
+                "Device {} acked command {} of device {}",
+                device_id, record.correlation_id, record.device_id
+            );
+            return Ok(());
+        }
+        let correlation_id = record.correlation_id.clone();
+        let status = if ack.success { STATUS_ACKED } else { STATUS_FAILED };
+        let result = ack.result.map(|r| r.to_string());
+        match finish(conn, record, status, result).await? {
+            Some(record) => {
+                let _ = self.updates.send(record);
+            }
+            None => info!("Late ack for command {}", correlation_id),
+        }
+        Ok(())
+    }
+
+    /// 把超过等待时长的命令标记为超时，返回标记的条数
+    pub async fn expire(&self, conn: &DatabaseConnection) -> Result<usize, AppError> {
+        let expired = DeviceCommandEntity::find()
+            .filter(DeviceCommandColumn::Status.eq(STATUS_PENDING))
+            .filter(DeviceCommandColumn::ExpiresAt.lt(Utc::now()))
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        let mut count = 0;
+        for record in expired {
+            let finished = finish(conn, record, STATUS_TIMED_OUT, None)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+            if let Some(record) = finished {
+                warn!("Command {} to device {} timed out", record.correlation_id, record.device_id);
+                let _ = self.updates.send(record);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// 后台任务：定期标记超时的命令
+pub async fn run_expiry(tracker: CommandTracker, db: DbManager) {
+    let mut ticker = tokio::time::interval(EXPIRY_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = tracker.expire(db.get_connection()).await {
+            error!("Failed to expire device commands: {:?}", e);
+        }
+    }
+}
+
+/// 设备的命令记录，按下发时间倒序
+pub async fn list(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    status: Option<&str>,
+    limit: Option<u64>,
+) -> Result<Vec<DeviceCommand>, AppError> {
+    let mut select =
+        DeviceCommandEntity::find().filter(DeviceCommandColumn::DeviceId.eq(device_id));
+    if let Some(status) = status {
+        select = select.filter(DeviceCommandColumn::Status.eq(status));
+    }
+    select
+        .order_by_desc(DeviceCommandColumn::IssuedAt)
+        .order_by_desc(DeviceCommandColumn::Id)
+        .limit(limit.unwrap_or(100).min(MAX_LIST))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+pub async fn get(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    command_id: i32,
+) -> Result<DeviceCommand, AppError> {
+    DeviceCommandEntity::find_by_id(command_id)
+        .filter(DeviceCommandColumn::DeviceId.eq(device_id))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or(AppError::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ack() {
+        let ack = parse_ack(br#"{"correlation_id":"ab12","result":{"speed":30}}"#).unwrap();
+        assert_eq!(ack.correlation_id, "ab12");
+        assert!(ack.success);
+        assert_eq!(ack.result, Some(json!({"speed": 30})));
+        let ack = parse_ack(br#"{"correlation_id":"ab12","success":false}"#).unwrap();
+        assert!(!ack.success);
+        assert!(parse_ack(br#"{"correlation_id":" "}"#).is_err());
+        assert!(parse_ack(b"ok").is_err());
+    }
+}
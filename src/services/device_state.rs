@@ -0,0 +1,342 @@
+//! 设备状态事件
+//!
+//! 通信状态和运行状态的每次变化追加写入 `device_state_events`，不修改也不删除，
+//! 任意时间段内各状态的持续时间（例如 2 号泵每天的运行时长）都由事件流回放得出。
+//! `devices.status` 仍保留当前运行状态（0 停止、1 运行、2 故障），由运行状态事件同步更新，
+//! 离开运行状态时本次运行时长计入运行小时数（见 [`run_hours`]）。
+
+use crate::models::device::{ActiveModel as DeviceActiveModel, Entity as DeviceEntity};
+use crate::models::device_state_event::{
+    ActiveModel as DeviceStateEventActiveModel, Column as DeviceStateEventColumn,
+    Entity as DeviceStateEventEntity, Model as DeviceStateEvent, CATEGORY_CONNECTIVITY,
+    CATEGORY_OPERATION, STATE_FAULT, STATE_OFFLINE, STATE_ONLINE, STATE_RUNNING, STATE_STOPPED,
+};
+use crate::services::cache::HotCache;
+use crate::services::daily_summary;
+use crate::services::run_hours;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// 单次统计最多回放的事件数
+const DURATION_SCAN_LIMIT: u64 = 50000;
+
+/// 状态所属的类别
+pub fn category_of(state: &str) -> Option<&'static str> {
+    match state {
+        STATE_ONLINE | STATE_OFFLINE => Some(CATEGORY_CONNECTIVITY),
+        STATE_RUNNING | STATE_STOPPED | STATE_FAULT => Some(CATEGORY_OPERATION),
+        _ => None,
+    }
+}
+
+/// `devices.status` 与运行状态的对应关系
+pub fn state_for_status(status: i32) -> Option<&'static str> {
+    match status {
+        0 => Some(STATE_STOPPED),
+        1 => Some(STATE_RUNNING),
+        2 => Some(STATE_FAULT),
+        _ => None,
+    }
+}
+
+fn status_for_state(state: &str) -> Option<i32> {
+    match state {
+        STATE_STOPPED => Some(0),
+        STATE_RUNNING => Some(1),
+        STATE_FAULT => Some(2),
+        _ => None,
+    }
+}
+
+/// 某个时间段内处于某状态的时长
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct StateDuration {
+    /// 分组起点；不分组时为查询起点
+    pub period_start: DateTime<Utc>,
+    pub state: String,
+    pub seconds: i64,
+}
+
+/// 截至某时刻最后一条事件
+async fn last_event(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    category: &str,
+    before: Option<DateTime<Utc>>,
+) -> Result<Option<DeviceStateEvent>, AppError> {
+    let mut query = DeviceStateEventEntity::find()
+        .filter(DeviceStateEventColumn::DeviceId.eq(device_id))
+        .filter(DeviceStateEventColumn::Category.eq(category));
+    if let Some(before) = before {
+        query = query.filter(DeviceStateEventColumn::Timestamp.lt(before));
+    }
+
+    query
+        .order_by_desc(DeviceStateEventColumn::Timestamp)
+        .order_by_desc(DeviceStateEventColumn::Id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 某类别的当前状态，没有事件时返回 None
+pub async fn current(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    category: &str,
+) -> Result<Option<String>, AppError> {
+    Ok(last_event(conn, device_id, category, None).await?.map(|e| e.state))
+}
+
+/// 当前处于运行状态时返回进入运行的时间
+pub async fn running_since(
+    conn: &DatabaseConnection,
+    device_id: i32,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let last = last_event(conn, device_id, CATEGORY_OPERATION, None).await?;
+    Ok(last.filter(|e| e.state == STATE_RUNNING).map(|e| e.timestamp))
+}
+
+/// 记录状态变化，与当前状态相同时不写入并返回 None
+pub async fn record(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    device_id: i32,
+    state: &str,
+    source: &str,
+    timestamp: DateTime<Utc>,
+) -> Result<Option<DeviceStateEvent>, AppError> {
+    let category = category_of(state)
+        .ok_or_else(|| AppError::InvalidInput(format!("未知的设备状态: {}", state).into()))?;
+
+    let previous = last_event(conn, device_id, category, None).await?;
+    if let Some(previous) = &previous {
+        if previous.state == state {
+            return Ok(None);
+        }
+        // 事件流只追加，不接受早于最后一条事件的补录
+        if timestamp < previous.timestamp {
+            return Err(AppError::InvalidInput("状态变化时间早于最后一条事件".into()));
+        }
+    }
+
+    if let Some(previous) = previous.as_ref().filter(|p| p.state == STATE_RUNNING) {
+        run_hours::accrue(conn, device_id, previous.timestamp, timestamp).await?;
+    }
+
+    let active_model = DeviceStateEventActiveModel {
+        device_id: Set(device_id),
+        category: Set(category.to_string()),
+        state: Set(state.to_string()),
+        previous_state: Set(previous.map(|p| p.state)),
+        source: Set(source.to_string()),
+        timestamp: Set(timestamp),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    let event = DeviceStateEventEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    // 补录的状态变化影响此后每天的运行时长
+    daily_summary::mark_dirty_since(conn, timestamp).await;
+
+    if let Some(status) = status_for_state(state) {
+        let device = DeviceActiveModel {
+            id: Set(device_id),
+            status: Set(status),
+            updated_at: Set(Utc::now()),
+            ..Default::default()
+        };
+        device.update(conn).await.map_err(|_| AppError::InternalError)?;
+        cache.invalidate_device(device_id).await;
+    }
+
+    Ok(Some(event))
+}
+
+/// 按时间倒序列出事件
+pub async fn list(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    category: Option<&str>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<DeviceStateEvent>, AppError> {
+    let mut query = DeviceStateEventEntity::find().filter(DeviceStateEventColumn::DeviceId.eq(device_id));
+    if let Some(category) = category {
+        query = query.filter(DeviceStateEventColumn::Category.eq(category));
+    }
+    if let Some(start) = start {
+        query = query.filter(DeviceStateEventColumn::Timestamp.gte(start));
+    }
+    if let Some(end) = end {
+        query = query.filter(DeviceStateEventColumn::Timestamp.lte(end));
+    }
+
+    let page = page.max(1);
+    query
+        .order_by_desc(DeviceStateEventColumn::Timestamp)
+        .order_by_desc(DeviceStateEventColumn::Id)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 回放事件，统计 [start, end) 内各状态的时长，`bucket` 为 None 时不分组
+///
+/// `initial` 是 start 时刻所处的状态，`events` 须按时间升序且都在 start 之后。
+fn accumulate(
+    initial: Option<&str>,
+    events: &[(DateTime<Utc>, String)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket: Option<Duration>,
+) -> Vec<StateDuration> {
+    let mut totals: BTreeMap<(DateTime<Utc>, String), i64> = BTreeMap::new();
+    let mut add = |from: DateTime<Utc>, to: DateTime<Utc>, state: &str| {
+        let mut cursor = from;
+        while cursor < to {
+            let (period_start, period_end) = match bucket {
+                Some(bucket) => {
+                    let period_start = cursor.duration_trunc(bucket).unwrap_or(cursor);
+                    (period_start, (period_start + bucket).min(to))
+                }
+                None => (start, to),
+            };
+            *totals.entry((period_start, state.to_string())).or_default() +=
+                (period_end - cursor).num_seconds();
+            cursor = period_end;
+        }
+    };
+
+    let mut current = initial.map(str::to_string);
+    let mut since = start;
+    for (timestamp, state) in events {
+        let at = (*timestamp).clamp(start, end);
+        if let Some(current) = &current {
+            add(since, at, current);
+        }
+        current = Some(state.clone());
+        since = at;
+    }
+    if let Some(current) = &current {
+        add(since, end, current);
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|((period_start, state), seconds)| StateDuration { period_start, state, seconds })
+        .collect()
+}
+
+/// 统计时间段内各状态时长，`by_day` 时按 UTC 自然日分组
+pub async fn durations(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    category: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    by_day: bool,
+) -> Result<Vec<StateDuration>, AppError> {
+    if category != CATEGORY_CONNECTIVITY && category != CATEGORY_OPERATION {
+        return Err(AppError::InvalidInput(format!("未知的状态类别: {}", category).into()));
+    }
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间须早于结束时间".into()));
+    }
+    // 未来的时间段不计入
+    let end = end.min(Utc::now());
+    if start >= end {
+        return Ok(Vec::new());
+    }
+
+    let initial = last_event(conn, device_id, category, Some(start)).await?;
+    let events: Vec<(DateTime<Utc>, String)> = DeviceStateEventEntity::find()
+        .filter(DeviceStateEventColumn::DeviceId.eq(device_id))
+        .filter(DeviceStateEventColumn::Category.eq(category))
+        .filter(DeviceStateEventColumn::Timestamp.gte(start))
+        .filter(DeviceStateEventColumn::Timestamp.lt(end))
+        .order_by_asc(DeviceStateEventColumn::Timestamp)
+        .order_by_asc(DeviceStateEventColumn::Id)
+        .limit(DURATION_SCAN_LIMIT)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|event| (event.timestamp, event.state))
+        .collect();
+
+    let bucket = by_day.then(|| Duration::days(1));
+    Ok(accumulate(initial.as_ref().map(|e| e.state.as_str()), &events, start, end, bucket))
+}
+
+/// 确认设备存在
+pub async fn ensure_device(conn: &DatabaseConnection, device_id: i32) -> Result<(), AppError> {
+    DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_accumulate_total() {
+        let events = vec![(at(1, 6), STATE_RUNNING.to_string()), (at(1, 18), STATE_STOPPED.to_string())];
+        let result = accumulate(Some(STATE_STOPPED), &events, at(1, 0), at(2, 0), None);
+
+        assert_eq!(
+            result,
+            vec![
+                StateDuration { period_start: at(1, 0), state: STATE_RUNNING.to_string(), seconds: 12 * 3600 },
+                StateDuration { period_start: at(1, 0), state: STATE_STOPPED.to_string(), seconds: 12 * 3600 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_accumulate_by_day() {
+        // 跨零点运行：第一天 22 点启动，第二天 4 点停止，起点之前的状态未知
+        let events = vec![(at(1, 22), STATE_RUNNING.to_string()), (at(2, 4), STATE_STOPPED.to_string())];
+        let result = accumulate(None, &events, at(1, 0), at(3, 0), Some(Duration::days(1)));
+
+        let running: Vec<(DateTime<Utc>, i64)> = result
+            .iter()
+            .filter(|d| d.state == STATE_RUNNING)
+            .map(|d| (d.period_start, d.seconds))
+            .collect();
+        assert_eq!(running, vec![(at(1, 0), 2 * 3600), (at(2, 0), 4 * 3600)]);
+
+        let stopped: i64 = result.iter().filter(|d| d.state == STATE_STOPPED).map(|d| d.seconds).sum();
+        assert_eq!(stopped, 20 * 3600);
+    }
+
+    #[test]
+    fn test_category_of() {
+        assert_eq!(category_of(STATE_OFFLINE), Some(CATEGORY_CONNECTIVITY));
+        assert_eq!(category_of(STATE_FAULT), Some(CATEGORY_OPERATION));
+        assert_eq!(category_of("idle"), None);
+    }
+}
@@ -0,0 +1,324 @@
+//! 加药管理
+//!
+//! 加药泵从药剂储罐取药，储罐存量按泵的转速和最大投加量逐周期递减，低于阈值时报警，
+//! 储罐见底时停泵防止空转。自动模式的加药泵按 pH 偏差比例调整转速，经 PWM 通道或
+//! Modbus 保持寄存器下发；pH 读数过期时停泵，不在失去测量的情况下继续投加。
+
+use crate::app_state::AppState;
+use crate::config::dosing::DosingConfig;
+use crate::config::modbus::RegisterKind;
+use crate::models::chemical_tank::{Entity as ChemicalTankEntity, Model as ChemicalTank};
+use crate::models::control_loop::{Column as ControlLoopColumn, Entity as ControlLoopEntity};
+use crate::models::dosing_pump::{
+    Column as DosingPumpColumn, Entity as DosingPumpEntity, Model as DosingPump, CONTROLS,
+    CONTROL_MANUAL, CONTROL_MODBUS, CONTROL_PWM, DIRECTION_LOWER, DIRECTION_RAISE, MODE_AUTO,
+    MODE_MANUAL,
+};
+use crate::services::metric_registry::PH;
+use crate::services::modbus_write::{self, WriteRequest, WriteSource};
+use crate::services::{alarm, latest};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use tracing::{error, info, warn};
+
+/// 转速变化小于该值时不重新下发
+const SPEED_TOLERANCE: f64 = 0.5;
+
+pub fn validate_tank(tank: &ChemicalTank) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("储罐参数无效: {}", msg).into()));
+
+    if tank.name.trim().is_empty() || tank.chemical.trim().is_empty() {
+        return invalid("名称和药剂不能为空");
+    }
+    if !(tank.capacity_l.is_finite() && tank.capacity_l > 0.0) {
+        return invalid("容积必须大于 0");
+    }
+    if !(tank.level_l >= 0.0 && tank.level_l <= tank.capacity_l) {
+        return invalid("存量须在 0 到容积之间");
+    }
+    if !(tank.low_level_l >= 0.0 && tank.low_level_l < tank.capacity_l) {
+        return invalid("低液位阈值须在 0 到容积之间");
+    }
+    Ok(())
+}
+
+pub fn validate_pump(pump: &DosingPump) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("加药泵参数无效: {}", msg).into()));
+
+    if pump.name.trim().is_empty() {
+        return invalid("名称不能为空");
+    }
+    if !(pump.max_rate_lph.is_finite() && pump.max_rate_lph > 0.0) {
+        return invalid("最大投加量必须大于 0");
+    }
+    if !CONTROLS.contains(&pump.control.as_str()) {
+        return invalid("控制方式须为 pwm / modbus / manual");
+    }
+    if pump.control != CONTROL_MANUAL && pump.control_device_id.is_none() {
+        return invalid("PWM / Modbus 控制须指定控制设备");
+    }
+    if pump.control == CONTROL_MODBUS
+        && !pump.control_address.is_some_and(|a| (0..=u16::MAX as i32).contains(&a))
+    {
+        return invalid("Modbus 控制须指定有效的寄存器地址");
+    }
+    if !(0.0..=100.0).contains(&pump.speed_percent) {
+        return invalid("转速须在 0 到 100 之间");
+    }
+    match pump.mode.as_str() {
+        MODE_MANUAL => {}
+        MODE_AUTO => {
+            if pump.control == CONTROL_MANUAL {
+                return invalid("自动模式须能控制加药泵");
+            }
+            let setpoint = pump.ph_setpoint.is_some_and(|sp| (0.0..=14.0).contains(&sp));
+            if pump.ph_device_id.is_none() || !setpoint {
+                return invalid("自动模式须指定 pH 计和 0 到 14 之间的设定值");
+            }
+            if !matches!(pump.direction.as_deref(), Some(DIRECTION_LOWER | DIRECTION_RAISE)) {
+                return invalid("自动模式须指定投加方向 lower / raise");
+            }
+            if !(pump.gain.is_finite() && pump.gain > 0.0) {
+                return invalid("比例系数必须大于 0");
+            }
+        }
+        _ => return invalid("运行模式须为 manual / auto"),
+    }
+    Ok(())
+}
+
+/// 自动模式的加药泵不能与控制回路共用同一输出对象
+pub async fn ensure_output_free(
+    conn: &DatabaseConnection,
+    pump: &DosingPump,
+) -> Result<(), AppError> {
+    if pump.mode != MODE_AUTO || pump.control == CONTROL_MANUAL {
+        return Ok(());
+    }
+    let mut query = ControlLoopEntity::find()
+        .filter(ControlLoopColumn::OutputKind.eq(pump.control.as_str()))
+        .filter(ControlLoopColumn::OutputDeviceId.eq(pump.control_device_id));
+    if pump.control == CONTROL_MODBUS {
+        query = query.filter(ControlLoopColumn::OutputAddress.eq(pump.control_address));
+    }
+    let control_loop = query.one(conn).await.map_err(|_| AppError::InternalError)?;
+    match control_loop {
+        Some(control_loop) => Err(AppError::InvalidInput(
+            format!("控制设备已由控制回路 {} 使用", control_loop.name).into(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// pH 比例控制的转速 (%)：投酸时 pH 高于设定值才投加，投碱时相反
+pub fn ph_speed(direction: &str, setpoint: f64, ph: f64, gain: f64, deadband: f64) -> f64 {
+    let error = if direction == DIRECTION_LOWER { ph - setpoint } else { setpoint - ph };
+    if error <= deadband {
+        return 0.0;
+    }
+    (error * gain).clamp(0.0, 100.0)
+}
+
+/// 运行 `elapsed` 的投加量 (L)
+pub fn consumed_l(max_rate_lph: f64, speed_percent: f64, elapsed: StdDuration) -> f64 {
+    max_rate_lph * speed_percent / 100.0 * elapsed.as_secs_f64() / 3600.0
+}
+
+/// 下发转速并记录，返回更新后的加药泵
+pub async fn command(
+    state: &AppState,
+    pump: &DosingPump,
+    speed_percent: f64,
+    source: WriteSource,
+    operator: Option<String>,
+) -> Result<DosingPump, AppError> {
+    if !(0.0..=100.0).contains(&speed_percent) {
+        return Err(AppError::InvalidInput("转速须在 0 到 100 之间".into()));
+    }
+    let conn = state.db.get_connection();
+    let speed_percent = match (pump.control.as_str(), pump.control_device_id) {
+        (CONTROL_PWM, Some(device_id)) => {
+            state.pwm.set(device_id, speed_percent, None, true).await?.target_duty_cycle
+        }
+        (CONTROL_MODBUS, Some(device_id)) => {
+            let request = WriteRequest {
+                device_id,
+                kind: RegisterKind::Holding,
+                address: pump.control_address.unwrap_or_default() as u16,
+                value: speed_percent,
+                source,
+                operator,
+                override_interlocks: false,
+                reason: Some(format!("加药泵 {} 调速", pump.name)),
+            };
+            let record =
+                modbus_write::write(conn, &state.cache, &state.settings.modbus, request).await?;
+            record.written_value.unwrap_or(speed_percent)
+        }
+        _ => speed_percent,
+    };
+
+    let mut active_model = pump.clone().into_active_model();
+    active_model.speed_percent = Set(speed_percent);
+    active_model.updated_at = Set(Utc::now());
+    let pump = active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    info!("Dosing pump {} set to {:.1}% ({})", pump.name, speed_percent, source.as_str());
+    Ok(pump)
+}
+
+/// 补药，存量不超过容积
+pub async fn refill(
+    conn: &DatabaseConnection,
+    tank_id: i32,
+    volume_l: f64,
+) -> Result<ChemicalTank, AppError> {
+    if !(volume_l.is_finite() && volume_l > 0.0) {
+        return Err(AppError::InvalidInput("补药量必须大于 0".into()));
+    }
+    let tank = ChemicalTankEntity::find_by_id(tank_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    let level_l = (tank.level_l + volume_l).min(tank.capacity_l);
+    let low_alarm_active = tank.low_alarm_active && level_l <= tank.low_level_l;
+
+    let mut active_model = tank.into_active_model();
+    active_model.level_l = Set(level_l);
+    active_model.low_alarm_active = Set(low_alarm_active);
+    active_model.updated_at = Set(Utc::now());
+    active_model.update(conn).await.map_err(|_| AppError::InternalError)
+}
+
+/// 自动模式的目标转速，pH 读数缺失或过期时为 0
+async fn auto_speed(
+    state: &AppState,
+    config: &DosingConfig,
+    pump: &DosingPump,
+    now: DateTime<Utc>,
+) -> Result<f64, AppError> {
+    let (Some(device_id), Some(setpoint), Some(direction)) =
+        (pump.ph_device_id, pump.ph_setpoint, pump.direction.as_deref())
+    else {
+        return Ok(0.0);
+    };
+    let conn = state.db.get_connection();
+    let ph = match latest::for_device(conn, &state.cache, device_id).await {
+        Ok(latest) => latest.values.get(PH).cloned(),
+        Err(AppError::NotFound) => None,
+        Err(e) => return Err(e),
+    };
+    let max_age = Duration::seconds(config.ph_max_age_secs as i64);
+    match ph.filter(|ph| now - ph.timestamp <= max_age) {
+        Some(ph) => Ok(ph_speed(direction, setpoint, ph.value, pump.gain, config.ph_deadband)),
+        None => {
+            if pump.speed_percent > 0.0 {
+                warn!("pH reading for dosing pump {} is missing or stale, stopping", pump.name);
+            }
+            Ok(0.0)
+        }
+    }
+}
+
+/// 一个周期：按上一周期的转速扣减存量、检查低液位，再调整转速
+async fn tick(
+    state: &AppState,
+    config: &DosingConfig,
+    elapsed: StdDuration,
+) -> Result<(), AppError> {
+    let conn = state.db.get_connection();
+    let now = Utc::now();
+    let pumps = DosingPumpEntity::find()
+        .filter(DosingPumpColumn::Enabled.eq(true))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut consumed: HashMap<i32, f64> = HashMap::new();
+    for pump in pumps.iter().filter(|p| p.speed_percent > 0.0) {
+        *consumed.entry(pump.tank_id).or_default() +=
+            consumed_l(pump.max_rate_lph, pump.speed_percent, elapsed);
+    }
+
+    let mut levels: HashMap<i32, f64> = HashMap::new();
+    for tank in ChemicalTankEntity::find().all(conn).await.map_err(|_| AppError::InternalError)? {
+        let level_l = (tank.level_l - consumed.get(&tank.id).copied().unwrap_or(0.0)).max(0.0);
+        levels.insert(tank.id, level_l);
+
+        let low = level_l <= tank.low_level_l;
+        if low && !tank.low_alarm_active {
+            let rule_name = format!("药剂储罐 {}（{}）低液位", tank.name, tank.chemical);
+            alarm::raise(conn, None, rule_name, level_l, &config.severity).await?;
+        }
+        if level_l == tank.level_l && low == tank.low_alarm_active {
+            continue;
+        }
+        let mut active_model = tank.into_active_model();
+        active_model.level_l = Set(level_l);
+        active_model.low_alarm_active = Set(low);
+        active_model.updated_at = Set(now);
+        active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    }
+
+    for pump in &pumps {
+        // 储罐见底时无论手动自动都停泵，防止空转
+        let empty = levels.get(&pump.tank_id).is_none_or(|level| *level <= 0.0);
+        let target = if empty {
+            0.0
+        } else if pump.mode == MODE_AUTO {
+            auto_speed(state, config, pump, now).await?
+        } else {
+            continue;
+        };
+        let settled = (target - pump.speed_percent).abs() < SPEED_TOLERANCE;
+        // 停泵总是下发
+        if settled && (target > 0.0 || pump.speed_percent == 0.0) {
+            continue;
+        }
+        if let Err(e) = command(state, pump, target, WriteSource::Automation, None).await {
+            error!("Failed to command dosing pump {}: {:?}", pump.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 后台任务：储罐存量递减、低液位报警和 pH 闭环
+pub async fn run(state: Arc<AppState>) {
+    let config = state.settings.dosing.clone();
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(1)));
+    let mut last = Instant::now();
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+        if let Err(e) = tick(&state, &config, now - last).await {
+            error!("Dosing cycle failed: {:?}", e);
+        }
+        last = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ph_speed() {
+        // 投酸：pH 8.0，设定 7.0，每 pH 40%
+        assert_eq!(ph_speed(DIRECTION_LOWER, 7.0, 8.0, 40.0, 0.05), 40.0);
+        assert_eq!(ph_speed(DIRECTION_LOWER, 7.0, 6.5, 40.0, 0.05), 0.0);
+        assert_eq!(ph_speed(DIRECTION_LOWER, 7.0, 7.04, 40.0, 0.05), 0.0);
+        assert_eq!(ph_speed(DIRECTION_LOWER, 7.0, 11.0, 40.0, 0.05), 100.0);
+        // 投碱
+        assert_eq!(ph_speed(DIRECTION_RAISE, 7.0, 6.5, 40.0, 0.05), 20.0);
+        assert_eq!(ph_speed(DIRECTION_RAISE, 7.0, 7.5, 40.0, 0.05), 0.0);
+
+        assert_eq!(consumed_l(12.0, 50.0, StdDuration::from_secs(1800)), 3.0);
+    }
+}
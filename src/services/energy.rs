@@ -0,0 +1,398 @@
+//! 能耗计量
+//!
+//! 后台任务每天按设备计算用电量写入 `daily_energy`，来源按优先级依次为：
+//! 电能表累计读数（`energy` 指标）之差、功率（`power` 指标）小时平均值积分、
+//! 设备额定功率（`power_consumption`）乘以当天运行时长。电能表读数变小（换表或清零）时
+//! 以当天最小读数为起点。最近 `backfill_days` 天每次都重算，今天的用电量随之更新，
+//! 但额定功率估算要等当天的日汇总完成后才有。
+//!
+//! 单位水量能耗（kWh/m³）的处理水量取 `energy.treated_flow_devices` 中流量计的小时平均流量之和。
+
+use crate::config::energy::EnergyConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::area::Entity as AreaEntity;
+use crate::models::daily_device_summary::{
+    Column as DeviceSummaryColumn, Entity as DeviceSummaryEntity,
+};
+use crate::models::daily_energy::{
+    ActiveModel as DailyEnergyActiveModel, Column as DailyEnergyColumn,
+    Entity as DailyEnergyEntity, Model as DailyEnergy, SOURCE_METER, SOURCE_POWER, SOURCE_RATED,
+};
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity};
+use crate::models::hourly_summary::{
+    Column as HourlySummaryColumn, Entity as HourlySummaryEntity, Model as HourlySummary,
+};
+use crate::services::metric_registry::{ENERGY, FLOW, POWER};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 电能表当天的读数范围，`previous` 为前一天最后一小时的最大读数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterReading {
+    pub previous: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl MeterReading {
+    pub fn kwh(&self) -> f64 {
+        // 读数比前一天小说明换表或清零，从当天最小读数算起
+        let base = self.previous.filter(|previous| *previous <= self.min).unwrap_or(self.min);
+        (self.max - base).max(0.0)
+    }
+}
+
+/// 按来源优先级计算单日用电量，没有任何依据时返回 None
+pub fn day_energy(
+    meter: Option<MeterReading>,
+    power_kwh: Option<f64>,
+    run_seconds: i64,
+    rated_kw: f64,
+) -> Option<(f64, &'static str)> {
+    if let Some(meter) = meter {
+        return Some((meter.kwh(), SOURCE_METER));
+    }
+    if let Some(kwh) = power_kwh {
+        return Some((kwh, SOURCE_POWER));
+    }
+    (run_seconds > 0 && rated_kw > 0.0)
+        .then(|| (rated_kw * run_seconds as f64 / 3600.0, SOURCE_RATED))
+}
+
+fn day_start(day: NaiveDate) -> DateTime<Utc> {
+    day.and_time(NaiveTime::MIN).and_utc()
+}
+
+async fn hours(
+    conn: &DatabaseConnection,
+    metric_type: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<HourlySummary>, AppError> {
+    HourlySummaryEntity::find()
+        .filter(HourlySummaryColumn::MetricType.eq(metric_type))
+        .filter(HourlySummaryColumn::Hour.gte(start))
+        .filter(HourlySummaryColumn::Hour.lt(end))
+        .order_by_asc(HourlySummaryColumn::Hour)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 重新计算某一天各设备的用电量，返回写入的设备数
+pub async fn materialize(conn: &DatabaseConnection, day: NaiveDate) -> Result<usize, AppError> {
+    let start = day_start(day);
+    let end = start + Duration::days(1);
+
+    let mut meters: HashMap<i32, MeterReading> = HashMap::new();
+    for hour in hours(conn, ENERGY, start, end).await? {
+        let reading = meters.entry(hour.device_id).or_insert(MeterReading {
+            previous: None,
+            min: hour.min,
+            max: hour.max,
+        });
+        reading.min = reading.min.min(hour.min);
+        reading.max = reading.max.max(hour.max);
+    }
+    // 小时汇总按时间升序，最后写入的即前一天最后一小时
+    for hour in hours(conn, ENERGY, start - Duration::days(1), start).await? {
+        if let Some(reading) = meters.get_mut(&hour.device_id) {
+            reading.previous = Some(hour.max);
+        }
+    }
+
+    let mut power: HashMap<i32, f64> = HashMap::new();
+    for hour in hours(conn, POWER, start, end).await? {
+        *power.entry(hour.device_id).or_default() += hour.avg;
+    }
+
+    let run_seconds: HashMap<i32, i64> = DeviceSummaryEntity::find()
+        .filter(DeviceSummaryColumn::Day.eq(day))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|row| (row.device_id, row.run_seconds))
+        .collect();
+
+    let devices = DeviceEntity::find().all(conn).await.map_err(|_| AppError::InternalError)?;
+    let now = Utc::now();
+    let rows: Vec<DailyEnergyActiveModel> = devices
+        .iter()
+        .filter_map(|device| {
+            let (kwh, source) = day_energy(
+                meters.get(&device.id).copied(),
+                power.get(&device.id).copied(),
+                run_seconds.get(&device.id).copied().unwrap_or(0),
+                device.power_consumption,
+            )?;
+            Some(DailyEnergyActiveModel {
+                device_id: Set(device.id),
+                site_id: Set(device.site_id),
+                area_id: Set(device.area_id),
+                day: Set(day),
+                kwh: Set(kwh),
+                source: Set(source.to_string()),
+                created_at: Set(now),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    let count = rows.len();
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    DailyEnergyEntity::delete_many()
+        .filter(DailyEnergyColumn::Day.eq(day))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if !rows.is_empty() {
+        DailyEnergyEntity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+    Ok(count)
+}
+
+/// 后台任务：定期重算最近几天的用电量
+pub async fn run_scheduler(config: EnergyConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        let today = Utc::now().date_naive();
+        for offset in (0..config.backfill_days.max(1) as i64).rev() {
+            let day = today - Duration::days(offset);
+            match materialize(conn, day).await {
+                Ok(0) => {}
+                Ok(devices) => info!("Materialized energy for {} ({} devices)", day, devices),
+                Err(e) => error!("Failed to materialize energy for {}: {:?}", day, e),
+            }
+        }
+    }
+}
+
+/// 日期区间（含两端）内的用电记录
+async fn records(
+    conn: &DatabaseConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    site_id: Option<i32>,
+) -> Result<Vec<DailyEnergy>, AppError> {
+    let mut select = DailyEnergyEntity::find()
+        .filter(DailyEnergyColumn::Day.gte(from))
+        .filter(DailyEnergyColumn::Day.lte(to));
+    if let Some(site_id) = site_id {
+        select = select.filter(DailyEnergyColumn::SiteId.eq(site_id));
+    }
+    select
+        .order_by_asc(DailyEnergyColumn::Day)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 设备在区间内的用电量
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeviceEnergy {
+    pub device_id: i32,
+    pub device_name: String,
+    pub area_id: Option<i32>,
+    pub kwh: f64,
+    /// 按来源（meter / power / rated）拆分的用电量
+    pub by_source: BTreeMap<String, f64>,
+}
+
+/// 区域在区间内的用电量
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AreaEnergy {
+    /// 为空表示未分配区域的设备
+    pub area_id: Option<i32>,
+    pub area_name: Option<String>,
+    pub kwh: f64,
+    /// 单位处理水量能耗，未配置处理水量流量计或没有水量时为空
+    pub kwh_per_m3: Option<f64>,
+    pub devices: usize,
+}
+
+/// 单日能耗指标
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DailyEnergyKpi {
+    pub day: NaiveDate,
+    pub kwh: f64,
+    pub treated_m3: Option<f64>,
+    pub kwh_per_m3: Option<f64>,
+}
+
+/// 全厂能耗指标
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EnergyKpis {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_kwh: f64,
+    pub treated_m3: Option<f64>,
+    pub kwh_per_m3: Option<f64>,
+    pub areas: Vec<AreaEnergy>,
+    pub days: Vec<DailyEnergyKpi>,
+}
+
+/// 设备用电量，从高到低
+pub async fn device_totals(
+    conn: &DatabaseConnection,
+    from: NaiveDate,
+    to: NaiveDate,
+    site_id: Option<i32>,
+) -> Result<Vec<DeviceEnergy>, AppError> {
+    let records = records(conn, from, to, site_id).await?;
+    let names: HashMap<i32, String> = DeviceEntity::find()
+        .filter(DeviceColumn::Id.is_in(records.iter().map(|r| r.device_id).collect::<Vec<_>>()))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|device| (device.id, device.name))
+        .collect();
+
+    let mut totals: BTreeMap<i32, DeviceEnergy> = BTreeMap::new();
+    for record in records {
+        let total = totals.entry(record.device_id).or_insert_with(|| DeviceEnergy {
+            device_id: record.device_id,
+            device_name: names.get(&record.device_id).cloned().unwrap_or_default(),
+            area_id: record.area_id,
+            kwh: 0.0,
+            by_source: BTreeMap::new(),
+        });
+        // 区间内换过区域的设备以最近一天为准
+        total.area_id = record.area_id;
+        total.kwh += record.kwh;
+        *total.by_source.entry(record.source).or_default() += record.kwh;
+    }
+    let mut totals: Vec<DeviceEnergy> = totals.into_values().collect();
+    totals.sort_by(|a, b| b.kwh.total_cmp(&a.kwh));
+    Ok(totals)
+}
+
+/// 处理水量（m³），按天拆分
+async fn treated_volume(
+    conn: &DatabaseConnection,
+    config: &EnergyConfig,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Option<BTreeMap<NaiveDate, f64>>, AppError> {
+    if config.treated_flow_devices.is_empty() {
+        return Ok(None);
+    }
+    let rows = HourlySummaryEntity::find()
+        .filter(HourlySummaryColumn::MetricType.eq(FLOW))
+        .filter(HourlySummaryColumn::DeviceId.is_in(config.treated_flow_devices.clone()))
+        .filter(HourlySummaryColumn::Hour.gte(day_start(from)))
+        .filter(HourlySummaryColumn::Hour.lt(day_start(to) + Duration::days(1)))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let mut days = BTreeMap::new();
+    for row in rows {
+        // 小时平均流量（m³/h）乘以 1 小时
+        *days.entry(row.hour.date_naive()).or_default() += row.avg;
+    }
+    Ok(Some(days))
+}
+
+fn per_m3(kwh: f64, m3: Option<f64>) -> Option<f64> {
+    m3.filter(|m3| *m3 > 0.0).map(|m3| kwh / m3)
+}
+
+/// 区域用电量及单位水量能耗
+pub async fn kpis(
+    conn: &DatabaseConnection,
+    config: &EnergyConfig,
+    from: NaiveDate,
+    to: NaiveDate,
+    site_id: Option<i32>,
+) -> Result<EnergyKpis, AppError> {
+    if from > to {
+        return Err(AppError::InvalidInput("开始日期不能晚于结束日期".into()));
+    }
+    let devices = device_totals(conn, from, to, site_id).await?;
+    let volume = treated_volume(conn, config, from, to).await?;
+    let treated_m3 = volume.as_ref().map(|days| days.values().sum::<f64>());
+    let total_kwh: f64 = devices.iter().map(|d| d.kwh).sum();
+
+    let area_names: HashMap<i32, String> = AreaEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|area| (area.id, area.name))
+        .collect();
+    let mut areas: BTreeMap<Option<i32>, AreaEnergy> = BTreeMap::new();
+    for device in &devices {
+        let area = areas.entry(device.area_id).or_insert_with(|| AreaEnergy {
+            area_id: device.area_id,
+            area_name: device.area_id.and_then(|id| area_names.get(&id).cloned()),
+            kwh: 0.0,
+            kwh_per_m3: None,
+            devices: 0,
+        });
+        area.kwh += device.kwh;
+        area.devices += 1;
+    }
+    let mut areas: Vec<AreaEnergy> = areas
+        .into_values()
+        .map(|area| AreaEnergy { kwh_per_m3: per_m3(area.kwh, treated_m3), ..area })
+        .collect();
+    areas.sort_by(|a, b| b.kwh.total_cmp(&a.kwh));
+
+    let mut daily: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for record in records(conn, from, to, site_id).await? {
+        *daily.entry(record.day).or_default() += record.kwh;
+    }
+    let days = daily
+        .into_iter()
+        .map(|(day, kwh)| {
+            let treated_m3 = volume.as_ref().map(|v| v.get(&day).copied().unwrap_or(0.0));
+            DailyEnergyKpi { day, kwh, treated_m3, kwh_per_m3: per_m3(kwh, treated_m3) }
+        })
+        .collect();
+
+    Ok(EnergyKpis {
+        from,
+        to,
+        total_kwh,
+        treated_m3,
+        kwh_per_m3: per_m3(total_kwh, treated_m3),
+        areas,
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_energy() {
+        let meter = MeterReading { previous: Some(1000.0), min: 1002.0, max: 1050.0 };
+        assert_eq!(day_energy(Some(meter), Some(10.0), 3600, 5.0), Some((50.0, SOURCE_METER)));
+        // 换表后读数从 0 开始
+        let replaced = MeterReading { previous: Some(1000.0), min: 0.0, max: 12.0 };
+        assert_eq!(replaced.kwh(), 12.0);
+        let first_day = MeterReading { previous: None, min: 10.0, max: 25.0 };
+        assert_eq!(first_day.kwh(), 15.0);
+        assert_eq!(day_energy(None, Some(36.5), 0, 0.0), Some((36.5, SOURCE_POWER)));
+        assert_eq!(day_energy(None, None, 7200, 7.5), Some((15.0, SOURCE_RATED)));
+        assert_eq!(day_energy(None, None, 0, 7.5), None);
+        assert_eq!(per_m3(120.0, Some(400.0)), Some(0.3));
+        assert_eq!(per_m3(120.0, Some(0.0)), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! 报警、设备上下线和测量值事件发布
+//!
+//! 报警和设备状态变化分散在各采集、监测任务中写入数据库，这里按 id 顺序读取新记录，
+//! 以 `alarm_triggered`、`device_online`、`device_offline` 事件发到事件总线；开启
+//! `relay_measurements` 时新入库的测量值也以 `measurement_recorded` 发出。
+//! 发布失败时游标不前进，下一轮重试；首次启动从当前最新记录之后开始。
+
+use crate::config::event_bus::EventBusConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::message_queue::events::{Event, EventBus};
+use crate::models::alarm_log::{Column as AlarmLogColumn, Entity as AlarmLogEntity};
+use crate::models::device_state_event::{
+    Column as StateEventColumn, Entity as StateEventEntity, Model as DeviceStateEvent,
+    CATEGORY_CONNECTIVITY, STATE_ONLINE,
+};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 单轮读取的记录数上限
+const BATCH_SIZE: u64 = 200;
+/// 单轮读取的测量值条数上限
+const MEASUREMENT_BATCH_SIZE: u64 = 1000;
+
+fn state_event(record: &DeviceStateEvent) -> Event {
+    if record.state == STATE_ONLINE {
+        Event::DeviceOnline {
+            device_id: record.device_id,
+            source: record.source.clone(),
+            timestamp: record.timestamp,
+        }
+    } else {
+        Event::DeviceOffline {
+            device_id: record.device_id,
+            source: record.source.clone(),
+            timestamp: record.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cursors {
+    alarm: i32,
+    state: i32,
+    /// 不发布测量值时为空
+    measurement: Option<i32>,
+}
+
+async fn latest_cursors(
+    conn: &DatabaseConnection,
+    measurements: bool,
+) -> Result<Cursors, sea_orm::DbErr> {
+    let alarm = AlarmLogEntity::find().order_by_desc(AlarmLogColumn::Id).one(conn).await?;
+    let state = StateEventEntity::find().order_by_desc(StateEventColumn::Id).one(conn).await?;
+    let measurement = if measurements {
+        let latest = MeasurementEntity::find()
+            .order_by_desc(MeasurementColumn::Id)
+            .one(conn)
+            .await?;
+        Some(latest.map_or(0, |m| m.id))
+    } else {
+        None
+    };
+    Ok(Cursors {
+        alarm: alarm.map_or(0, |a| a.id),
+        state: state.map_or(0, |s| s.id),
+        measurement,
+    })
+}
+
+/// 发布一轮新记录，返回是否还有未读完的记录
+async fn relay_once(
+    conn: &DatabaseConnection,
+    events: &EventBus,
+    cursors: &mut Cursors,
+) -> anyhow::Result<bool> {
+    let alarms = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::Id.gt(cursors.alarm))
+        .order_by_asc(AlarmLogColumn::Id)
+        .limit(BATCH_SIZE)
+        .all(conn)
+        .await?;
+    for alarm in &alarms {
+        events.send(Event::from(alarm)).await?;
+        cursors.alarm = alarm.id;
+    }
+
+    let states = StateEventEntity::find()
+        .filter(StateEventColumn::Id.gt(cursors.state))
+        .order_by_asc(StateEventColumn::Id)
+        .limit(BATCH_SIZE)
+        .all(conn)
+        .await?;
+    for record in &states {
+        if record.category == CATEGORY_CONNECTIVITY {
+            events.send(state_event(record)).await?;
+        }
+        cursors.state = record.id;
+    }
+
+    let mut more_measurements = false;
+    if let Some(cursor) = cursors.measurement {
+        let measurements = MeasurementEntity::find()
+            .filter(MeasurementColumn::Id.gt(cursor))
+            .order_by_asc(MeasurementColumn::Id)
+            .limit(MEASUREMENT_BATCH_SIZE)
+            .all(conn)
+            .await?;
+        for measurement in &measurements {
+            // 事件要求关联设备，未关联设备的测量值跳过
+            if let Some(device_id) = measurement.device_id {
+                let event = Event::MeasurementRecorded {
+                    device_id,
+                    metric: measurement.metric_type.clone(),
+                    value: measurement.value,
+                    timestamp: measurement.timestamp,
+                };
+                events.send(event).await?;
+            }
+            cursors.measurement = Some(measurement.id);
+        }
+        more_measurements = measurements.len() as u64 == MEASUREMENT_BATCH_SIZE;
+    }
+
+    Ok(alarms.len() as u64 == BATCH_SIZE
+        || states.len() as u64 == BATCH_SIZE
+        || more_measurements)
+}
+
+/// 后台任务：持续发布报警、设备上下线和测量值事件
+pub async fn run_relay(events: EventBus, db: DbManager, config: EventBusConfig) {
+    let conn = db.get_connection();
+    let interval = Duration::from_secs(config.relay_interval_secs.max(1));
+
+    let mut cursors = loop {
+        match latest_cursors(conn, config.relay_measurements).await {
+            Ok(cursors) => break cursors,
+            Err(e) => {
+                error!("Failed to read event relay cursors: {:?}", e);
+                tokio::time::sleep(interval).await;
+            }
+        }
+    };
+    info!("Event relay started on {} bus, {:?}", events.backend(), cursors);
+
+    loop {
+        match relay_once(conn, &events, &mut cursors).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => warn!("Failed to relay events: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
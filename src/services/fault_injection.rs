@@ -0,0 +1,119 @@
+//! 故障注入
+//!
+//! 用于验证离线队列、重试和消费者监管等机制在故障下的表现：按比例丢弃 MQTT 消息、
+//! 延迟测量值入库、强制关闭 RabbitMQ 消费者 channel。只在调试构建中提供管理接口，
+//! 发布构建中各注入点始终不生效。
+//!
+//! 注入点分散在 MQTT 客户端和测量值写入等不持有 `AppState` 的位置，状态因此放在
+//! 进程级的静态变量中。
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+/// 是否允许开启故障注入
+pub const AVAILABLE: bool = cfg!(debug_assertions);
+
+static MQTT_DROP_PERCENT: AtomicU8 = AtomicU8::new(0);
+static DB_COMMIT_DELAY_MS: AtomicU64 = AtomicU64::new(0);
+static MQTT_DROPPED: AtomicU64 = AtomicU64::new(0);
+static DB_COMMITS_DELAYED: AtomicU64 = AtomicU64::new(0);
+static RABBITMQ_CHANNELS_KILLED: AtomicU64 = AtomicU64::new(0);
+
+/// 当前注入的故障
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FaultInjectionStatus {
+    /// 丢弃收发 MQTT 消息的比例（0-100）
+    pub mqtt_drop_percent: u8,
+    /// 每次写入测量值前的延迟
+    pub db_commit_delay_ms: u64,
+    /// 已丢弃的 MQTT 消息数
+    pub mqtt_dropped: u64,
+    /// 已延迟的写入次数
+    pub db_commits_delayed: u64,
+    /// 已强制关闭的 RabbitMQ channel 数
+    pub rabbitmq_channels_killed: u64,
+}
+
+/// 当前注入的故障及累计次数
+pub fn status() -> FaultInjectionStatus {
+    FaultInjectionStatus {
+        mqtt_drop_percent: MQTT_DROP_PERCENT.load(Ordering::Relaxed),
+        db_commit_delay_ms: DB_COMMIT_DELAY_MS.load(Ordering::Relaxed),
+        mqtt_dropped: MQTT_DROPPED.load(Ordering::Relaxed),
+        db_commits_delayed: DB_COMMITS_DELAYED.load(Ordering::Relaxed),
+        rabbitmq_channels_killed: RABBITMQ_CHANNELS_KILLED.load(Ordering::Relaxed),
+    }
+}
+
+/// 设置 MQTT 丢包比例，超过 100 按 100 处理
+pub fn set_mqtt_drop_percent(percent: u8) {
+    let percent = percent.min(100);
+    MQTT_DROP_PERCENT.store(percent, Ordering::Relaxed);
+    warn!("Fault injection: dropping {}% of MQTT messages", percent);
+}
+
+pub fn set_db_commit_delay(delay: Duration) {
+    DB_COMMIT_DELAY_MS.store(delay.as_millis() as u64, Ordering::Relaxed);
+    warn!("Fault injection: delaying measurement writes by {:?}", delay);
+}
+
+/// 清除全部故障，累计次数保留
+pub fn clear() {
+    MQTT_DROP_PERCENT.store(0, Ordering::Relaxed);
+    DB_COMMIT_DELAY_MS.store(0, Ordering::Relaxed);
+}
+
+/// 记录被强制关闭的 channel 数
+pub fn record_channels_killed(count: u64) {
+    RABBITMQ_CHANNELS_KILLED.fetch_add(count, Ordering::Relaxed);
+}
+
+/// 本条 MQTT 消息是否应当丢弃
+pub fn drop_mqtt_message(topic: &str) -> bool {
+    let percent = MQTT_DROP_PERCENT.load(Ordering::Relaxed);
+    if percent == 0 || rand::thread_rng().gen_range(0..100) >= percent {
+        return false;
+    }
+    MQTT_DROPPED.fetch_add(1, Ordering::Relaxed);
+    debug!("Fault injection: dropped MQTT message on {}", topic);
+    true
+}
+
+/// 写入数据库前按配置的时长等待
+pub async fn delay_db_commit() {
+    let delay = DB_COMMIT_DELAY_MS.load(Ordering::Relaxed);
+    if delay == 0 {
+        return;
+    }
+    DB_COMMITS_DELAYED.fetch_add(1, Ordering::Relaxed);
+    tokio::time::sleep(Duration::from_millis(delay)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 状态是进程级的，放在同一个测试里避免并行测试互相干扰
+    #[tokio::test]
+    async fn test_faults_toggle() {
+        assert!(!drop_mqtt_message("t"));
+
+        set_mqtt_drop_percent(150);
+        assert_eq!(status().mqtt_drop_percent, 100);
+        assert!(drop_mqtt_message("t"));
+        assert!(status().mqtt_dropped >= 1);
+
+        set_db_commit_delay(Duration::from_millis(1));
+        let delayed = status().db_commits_delayed;
+        delay_db_commit().await;
+        assert_eq!(status().db_commits_delayed, delayed + 1);
+
+        clear();
+        assert!(!drop_mqtt_message("t"));
+        assert_eq!(status().db_commit_delay_ms, 0);
+    }
+}
@@ -0,0 +1,421 @@
+//! 累计流量（排放水量）
+//!
+//! 后台任务按设备计算每天的累计流量写入 `flow_totals`，并由当月各天汇总出月累计。
+//! 流量计上报了累计器读数（`flow_total` 指标）的，按读数之差计算；否则对瞬时流量
+//! （`flow` 指标）按梯形法对时间积分，相邻样本间隔超过 `max_gap_secs` 的区间视为中断，不计入。
+//! 累计器读数变小时，前一读数已接近 10 的整数次幂（不低于其 90%）视为计数器溢出回零，
+//! 否则视为清零或换表，从 0 算起；两种情况都计入 `resets`。
+//! 最近 `backfill_days` 天每次都重算，今天和本月的累计量随之更新。
+//! 统计日与合规报表一样按 `timezone.default` 时区的本地零点划分，夏令时切换日为 23 或 25 小时。
+//! 质量为 `bad` / `out_of_range` 的读数不参与计算。
+
+use crate::config::flow_total::FlowTotalConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{Entity as DeviceEntity, Model as Device};
+use crate::models::flow_total::{
+    ActiveModel as FlowTotalActiveModel, Column as FlowTotalColumn, Entity as FlowTotalEntity,
+    Model as FlowTotal, PERIOD_DAY, PERIOD_MONTH, SOURCE_INTEGRATED, SOURCE_MIXED,
+    SOURCE_TOTALIZER,
+};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::services::metric_registry::{FLOW, FLOW_TOTAL};
+use crate::services::quality;
+use crate::utils::error::AppError;
+use crate::utils::timezone;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 计数器溢出的判定比例：前一读数不低于溢出值的该比例时视为溢出
+const ROLLOVER_RATIO: f64 = 0.9;
+
+pub fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+/// 瞬时流量换算为 m³/h，不认识的单位返回 None
+fn to_m3_per_hour(value: f64, unit: &str) -> Option<f64> {
+    let factor = match unit {
+        "m³/h" | "m3/h" => 1.0,
+        "m³/s" | "m3/s" => 3600.0,
+        "m³/d" | "m3/d" => 1.0 / 24.0,
+        "L/s" => 3.6,
+        "L/min" => 0.06,
+        _ => return None,
+    };
+    Some(value * factor)
+}
+
+/// 对按时间升序的瞬时流量（m³/h）在 [start, end) 内积分，返回 m³
+pub fn integrate(
+    samples: &[(DateTime<Utc>, f64)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    max_gap_secs: i64,
+) -> f64 {
+    samples
+        .windows(2)
+        .map(|pair| {
+            let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+            let span = (t1 - t0).num_milliseconds() as f64 / 1000.0;
+            if span <= 0.0 || span > max_gap_secs as f64 {
+                return 0.0;
+            }
+            let (lo, hi) = (t0.max(start), t1.min(end));
+            if hi <= lo {
+                return 0.0;
+            }
+            // 区间被统计日截断时按线性插值取端点值
+            let at = |t: DateTime<Utc>| v0 + (v1 - v0) * (t - t0).num_milliseconds() as f64 / 1000.0 / span;
+            let hours = (hi - lo).num_milliseconds() as f64 / 3_600_000.0;
+            (at(lo) + at(hi)) / 2.0 * hours
+        })
+        .sum()
+}
+
+/// 累计器读数变小时的溢出值，前一读数不接近 10 的整数次幂时返回 None（视为清零）
+fn rollover_modulus(previous: f64) -> Option<f64> {
+    if previous <= 0.0 {
+        return None;
+    }
+    let modulus = 10f64.powi(previous.log10().floor() as i32 + 1);
+    (previous >= modulus * ROLLOVER_RATIO).then_some(modulus)
+}
+
+/// 累计器读数之差，`baseline` 为统计日之前的最后一个读数；返回水量和清零、溢出的次数
+pub fn counter_volume(baseline: Option<f64>, readings: &[f64]) -> (f64, i32) {
+    let Some(first) = baseline.or(readings.first().copied()) else {
+        return (0.0, 0);
+    };
+    let mut previous = first;
+    let mut volume = 0.0;
+    let mut resets = 0;
+    for &reading in readings {
+        if reading >= previous {
+            volume += reading - previous;
+        } else {
+            resets += 1;
+            volume += match rollover_modulus(previous) {
+                Some(modulus) => modulus - previous + reading,
+                None => reading,
+            };
+        }
+        previous = reading;
+    }
+    (volume, resets)
+}
+
+/// 区间内上报过指定指标的设备
+async fn reporting_devices(
+    conn: &DatabaseConnection,
+    metric_type: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<i32>, AppError> {
+    MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::DeviceId)
+        .distinct()
+        .filter(MeasurementColumn::MetricType.eq(metric_type))
+        .filter(MeasurementColumn::DeviceId.is_not_null())
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lt(end))
+        .filter(quality::usable())
+        .into_tuple::<Option<i32>>()
+        .all(conn)
+        .await
+        .map(|ids| ids.into_iter().flatten().collect())
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 设备在 [start, end) 内某指标的读数，按时间升序
+async fn readings(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    metric_type: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, f64, String)>, AppError> {
+    MeasurementEntity::find()
+        .select_only()
+        .columns([MeasurementColumn::Timestamp, MeasurementColumn::Value, MeasurementColumn::Unit])
+        .filter(MeasurementColumn::DeviceId.eq(device_id))
+        .filter(MeasurementColumn::MetricType.eq(metric_type))
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lt(end))
+        .filter(quality::usable())
+        .order_by_asc(MeasurementColumn::Timestamp)
+        .into_tuple()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 统计日之前最后一个累计器读数
+async fn counter_baseline(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    start: DateTime<Utc>,
+) -> Result<Option<f64>, AppError> {
+    MeasurementEntity::find()
+        .filter(MeasurementColumn::DeviceId.eq(device_id))
+        .filter(MeasurementColumn::MetricType.eq(FLOW_TOTAL))
+        .filter(MeasurementColumn::Timestamp.lt(start))
+        .filter(quality::usable())
+        .order_by_desc(MeasurementColumn::Timestamp)
+        .one(conn)
+        .await
+        .map(|reading| reading.map(|r| r.value))
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 单台设备单日的累计流量，没有任何数据时返回 None
+async fn device_day(
+    conn: &DatabaseConnection,
+    config: &FlowTotalConfig,
+    device_id: i32,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Option<(f64, &'static str, i32)>, AppError> {
+    let counter: Vec<f64> = readings(conn, device_id, FLOW_TOTAL, start, end)
+        .await?
+        .into_iter()
+        .map(|(_, value, _)| value)
+        .collect();
+    if !counter.is_empty() {
+        let baseline = counter_baseline(conn, device_id, start).await?;
+        let (volume, resets) = counter_volume(baseline, &counter);
+        return Ok(Some((volume, SOURCE_TOTALIZER, resets)));
+    }
+
+    // 前后各多取一段，积分跨越零点的区间
+    let gap = Duration::seconds(config.max_gap_secs.max(1));
+    let samples: Vec<(DateTime<Utc>, f64)> = readings(conn, device_id, FLOW, start - gap, end + gap)
+        .await?
+        .into_iter()
+        .filter_map(|(timestamp, value, unit)| Some((timestamp, to_m3_per_hour(value, &unit)?)))
+        .collect();
+    if !samples.iter().any(|(timestamp, _)| *timestamp >= start && *timestamp < end) {
+        return Ok(None);
+    }
+    Ok(Some((integrate(&samples, start, end, config.max_gap_secs), SOURCE_INTEGRATED, 0)))
+}
+
+/// 按当月各天重新汇总月累计
+fn month_rows(days: &[FlowTotal], month: NaiveDate, now: DateTime<Utc>) -> Vec<FlowTotalActiveModel> {
+    let mut totals: BTreeMap<i32, (Option<i32>, f64, String, i32)> = BTreeMap::new();
+    for day in days {
+        let total = totals
+            .entry(day.device_id)
+            .or_insert_with(|| (day.site_id, 0.0, day.source.clone(), 0));
+        // 月内换过厂站的设备以最近一天为准
+        total.0 = day.site_id;
+        total.1 += day.volume;
+        total.3 += day.resets;
+        if total.2 != day.source {
+            total.2 = SOURCE_MIXED.to_string();
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(device_id, (site_id, volume, source, resets))| FlowTotalActiveModel {
+            device_id: Set(device_id),
+            site_id: Set(site_id),
+            period: Set(PERIOD_MONTH.to_string()),
+            period_start: Set(month),
+            volume: Set(volume),
+            source: Set(source),
+            resets: Set(resets),
+            created_at: Set(now),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// 重新计算某一天（`tz` 的本地日期）各设备的累计流量及所在月的月累计，返回写入的设备数
+pub async fn materialize(
+    conn: &DatabaseConnection,
+    config: &FlowTotalConfig,
+    tz: Tz,
+    day: NaiveDate,
+) -> Result<usize, AppError> {
+    let start = timezone::midnight(tz, day);
+    let end = timezone::midnight(tz, day + Duration::days(1));
+
+    let mut device_ids = reporting_devices(conn, FLOW_TOTAL, start, end).await?;
+    device_ids.extend(reporting_devices(conn, FLOW, start, end).await?);
+    device_ids.sort_unstable();
+    device_ids.dedup();
+
+    let sites: HashMap<i32, Option<i32>> = DeviceEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|device| (device.id, device.site_id))
+        .collect();
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    for device_id in device_ids {
+        let Some((volume, source, resets)) = device_day(conn, config, device_id, start, end).await? else {
+            continue;
+        };
+        rows.push(FlowTotalActiveModel {
+            device_id: Set(device_id),
+            site_id: Set(sites.get(&device_id).copied().flatten()),
+            period: Set(PERIOD_DAY.to_string()),
+            period_start: Set(day),
+            volume: Set(volume),
+            source: Set(source.to_string()),
+            resets: Set(resets),
+            created_at: Set(now),
+            ..Default::default()
+        });
+    }
+
+    let count = rows.len();
+    let month = month_start(day);
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    FlowTotalEntity::delete_many()
+        .filter(FlowTotalColumn::Period.eq(PERIOD_DAY))
+        .filter(FlowTotalColumn::PeriodStart.eq(day))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if !rows.is_empty() {
+        FlowTotalEntity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+
+    let days = FlowTotalEntity::find()
+        .filter(FlowTotalColumn::Period.eq(PERIOD_DAY))
+        .filter(FlowTotalColumn::PeriodStart.gte(month))
+        .filter(FlowTotalColumn::PeriodStart.lt(month + Months::new(1)))
+        .order_by_asc(FlowTotalColumn::PeriodStart)
+        .all(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    FlowTotalEntity::delete_many()
+        .filter(FlowTotalColumn::Period.eq(PERIOD_MONTH))
+        .filter(FlowTotalColumn::PeriodStart.eq(month))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let months = month_rows(&days, month, now);
+    if !months.is_empty() {
+        FlowTotalEntity::insert_many(months)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+    Ok(count)
+}
+
+/// 后台任务：定期重算最近几天的累计流量
+pub async fn run_scheduler(config: FlowTotalConfig, tz: Tz, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        let today = timezone::local_date(tz, Utc::now());
+        for offset in (0..config.backfill_days.max(1) as i64).rev() {
+            let day = today - Duration::days(offset);
+            match materialize(conn, &config, tz, day).await {
+                Ok(0) => {}
+                Ok(devices) => info!("Materialized flow totals for {} ({} devices)", day, devices),
+                Err(e) => error!("Failed to materialize flow totals for {}: {:?}", day, e),
+            }
+        }
+    }
+}
+
+/// 设备在区间内的累计流量
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeviceFlowTotal {
+    pub device_id: i32,
+    pub device_name: String,
+    /// day / month
+    pub period: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub unit: String,
+    /// 区间内合计，m³
+    pub volume: f64,
+    /// 区间内累计器清零或溢出的次数
+    pub resets: i32,
+    /// 逐日或逐月明细，没有数据的日期不列出
+    pub totals: Vec<FlowTotal>,
+}
+
+/// 设备在 [start, end]（含两端）内的逐日或逐月累计流量
+pub async fn device_totals(
+    conn: &DatabaseConnection,
+    device: &Device,
+    period: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<DeviceFlowTotal, AppError> {
+    let totals = FlowTotalEntity::find()
+        .filter(FlowTotalColumn::DeviceId.eq(device.id))
+        .filter(FlowTotalColumn::Period.eq(period))
+        .filter(FlowTotalColumn::PeriodStart.gte(start))
+        .filter(FlowTotalColumn::PeriodStart.lte(end))
+        .order_by_asc(FlowTotalColumn::PeriodStart)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(DeviceFlowTotal {
+        device_id: device.id,
+        device_name: device.name.clone(),
+        period: period.to_string(),
+        start,
+        end,
+        unit: "m³".to_string(),
+        volume: totals.iter().map(|t| t.volume).sum(),
+        resets: totals.iter().map(|t| t.resets).sum(),
+        totals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_flow_total() {
+        let at = |hour, minute| Utc.with_ymd_and_hms(2024, 5, 2, hour, minute, 0).unwrap();
+        let start = at(0, 0);
+        let end = start + Duration::days(1);
+        // 前一天 23:00 到 01:00 恒定 10 m³/h，只计零点之后的 1 小时
+        let samples = vec![(start - Duration::hours(1), 10.0), (at(1, 0), 10.0)];
+        assert_eq!(integrate(&samples, start, end, 7200), 10.0);
+        // 0 到 20 m³/h 线性上升 2 小时，均值 10
+        assert_eq!(integrate(&[(at(2, 0), 0.0), (at(4, 0), 20.0)], start, end, 7200), 20.0);
+        // 中断超过 max_gap 不积分
+        assert_eq!(integrate(&[(at(2, 0), 5.0), (at(6, 0), 5.0)], start, end, 3600), 0.0);
+
+        assert_eq!(counter_volume(Some(100.0), &[110.0, 125.5]), (25.5, 0));
+        // 6 位计数器 999990 溢出回零
+        assert_eq!(counter_volume(Some(999_990.0), &[999_995.0, 15.0]), (25.0, 1));
+        // 换表清零，从 0 算起
+        assert_eq!(counter_volume(Some(5_000.0), &[5_010.0, 3.0]), (13.0, 1));
+        assert_eq!(counter_volume(None, &[50.0, 60.0]), (10.0, 0));
+        assert_eq!(counter_volume(None, &[]), (0.0, 0));
+
+        assert_eq!(to_m3_per_hour(2.0, "L/s"), Some(7.2));
+        assert_eq!(month_start(NaiveDate::from_ymd_opt(2024, 5, 17).unwrap()), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+    }
+}
@@ -0,0 +1,120 @@
+//! GPIO 开关量输出
+//!
+//! 继电器等输出按名称配置在 `gpio.outputs` 中。引脚只在第一次下发时切换为输出，
+//! 之后一直持有句柄；服务启动时不会主动写任何输出。每次下发都记录到 `gpio_writes`。
+
+use crate::config::gpio::{GpioConfig, GpioOutput};
+use crate::models::gpio_write::{
+    ActiveModel as GpioWriteActiveModel, Column as GpioWriteColumn, Entity as GpioWriteEntity,
+    Model as GpioWrite, STATUS_FAILED, STATUS_WRITTEN,
+};
+use crate::utils::error::AppError;
+use crate::utils::gpio::GpioController;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Default)]
+pub struct GpioOutputs {
+    config: Arc<GpioConfig>,
+    controllers: Arc<tokio::sync::Mutex<HashMap<u32, GpioController>>>,
+}
+
+impl GpioOutputs {
+    pub fn new(config: &GpioConfig) -> Self {
+        let mut config = config.clone();
+        if !config.enabled {
+            config.outputs.clear();
+        }
+        Self {
+            config: Arc::new(config),
+            controllers: Arc::default(),
+        }
+    }
+
+    pub fn outputs(&self) -> &[GpioOutput] {
+        &self.config.outputs
+    }
+
+    /// 打开引脚，不改变方向和电平
+    async fn controller(&self, line: u32) -> io::Result<GpioController> {
+        let mut controllers = self.controllers.lock().await;
+        if let Some(controller) = controllers.get(&line) {
+            return Ok(controller.clone());
+        }
+        let config = &self.config;
+        let controller = GpioController::open(&config.chip, line, config.sysfs_base).await?;
+        controllers.insert(line, controller.clone());
+        Ok(controller)
+    }
+
+    /// 读取输出的实际状态：有反馈输入时读反馈，否则读输出引脚电平
+    pub async fn read_back(&self, output: &GpioOutput) -> io::Result<bool> {
+        match output.feedback_line {
+            Some(line) => self.controller(line).await?.read().await,
+            None => self.controller(output.line).await?.read_output().await,
+        }
+    }
+
+    /// 设置输出电平并记录
+    pub async fn set(
+        &self,
+        conn: &DatabaseConnection,
+        name: &str,
+        value: bool,
+        operator: Option<String>,
+    ) -> Result<GpioWrite, AppError> {
+        let output = self.config.output(name).ok_or(AppError::NotFound)?;
+
+        let result = async { self.controller(output.line).await?.write(value).await }.await;
+        let (status, message) = match &result {
+            Ok(()) => {
+                info!("GPIO output {} set to {}", name, value as u8);
+                (STATUS_WRITTEN, None)
+            }
+            Err(e) => {
+                warn!("Failed to set GPIO output {}: {}", name, e);
+                (STATUS_FAILED, Some(e.to_string()))
+            }
+        };
+
+        let record = GpioWriteActiveModel {
+            name: Set(output.name.clone()),
+            line: Set(output.line as i32),
+            device_id: Set(output.device_id),
+            value: Set(value),
+            operator: Set(operator),
+            status: Set(status.to_string()),
+            message: Set(message),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+        if result.is_err() {
+            return Err(AppError::ServiceUnavailable("GPIO 输出失败".into()));
+        }
+        Ok(record)
+    }
+}
+
+/// 输出最后一次成功下发的记录
+pub async fn last_written(
+    conn: &DatabaseConnection,
+    name: &str,
+) -> Result<Option<GpioWrite>, AppError> {
+    GpioWriteEntity::find()
+        .filter(GpioWriteColumn::Name.eq(name))
+        .filter(GpioWriteColumn::Status.eq(STATUS_WRITTEN))
+        .order_by_desc(GpioWriteColumn::Id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
@@ -0,0 +1,182 @@
+//! Grafana JSON 数据源
+//!
+//! 实现 SimpleJSON 数据源约定：序列标识为 `设备ID:指标`，例如 `3:flow`，
+//! 查询按 Grafana 给出的间隔在数据库中取时间桶平均值；报警记录作为注释返回。
+
+use crate::models::alarm_log::{Column as AlarmLogColumn, Entity as AlarmLogEntity};
+use crate::services::cache::HotCache;
+use crate::services::measurement::MeasurementFilter;
+use crate::services::timeseries::TimeSeriesStore;
+use crate::services::{latest, metric_registry};
+use crate::utils::error::AppError;
+use crate::utils::tenant;
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 单次注释查询最多返回的报警条数
+const MAX_ANNOTATIONS: u64 = 1000;
+
+/// 解析 `设备ID:指标` 形式的序列标识
+pub fn parse_target(target: &str) -> Option<(i32, &str)> {
+    let (device_id, metric) = target.trim().split_once(':')?;
+    let metric = metric.trim();
+    metric_registry::lookup(metric)?;
+    Some((device_id.trim().parse().ok()?, metric))
+}
+
+/// 时间桶宽度：不小于 Grafana 的间隔，且点数不超过 `max_points`
+pub fn bucket_secs(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    interval_ms: Option<i64>,
+    max_points: Option<i64>,
+) -> i64 {
+    let interval = interval_ms.unwrap_or(0) / 1000;
+    let range = (end - start).num_seconds().max(1);
+    let by_points = match max_points {
+        Some(points) if points > 0 => (range + points - 1) / points,
+        _ => 0,
+    };
+    interval.max(by_points).max(1)
+}
+
+/// 可选的序列
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchResult {
+    pub text: String,
+    pub value: String,
+}
+
+/// 一条时间序列，`datapoints` 为 `[值, Unix 毫秒]`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TimeSeries {
+    pub target: String,
+    pub datapoints: Vec<(f64, i64)>,
+}
+
+/// 注释
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Annotation {
+    /// Unix 毫秒
+    pub time: i64,
+    pub title: String,
+    pub text: String,
+    pub tags: Vec<String>,
+}
+
+/// 列出有数据的序列，`filter` 匹配标识或名称；指定组织时只列本组织设备
+pub async fn search(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    filter: &str,
+    organization_id: Option<i32>,
+) -> Result<Vec<SearchResult>, AppError> {
+    let filter = filter.trim();
+    let mut results = Vec::new();
+    for device in latest::for_all_devices(conn, cache, organization_id).await? {
+        for metric in device.values.keys() {
+            let name = metric_registry::lookup(metric).map_or(metric.as_str(), |m| m.name);
+            let result = SearchResult {
+                text: format!("{} / {}", device.device_name, name),
+                value: format!("{}:{}", device.device_id, metric),
+            };
+            if filter.is_empty() || result.text.contains(filter) || result.value.contains(filter) {
+                results.push(result);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// 按时间桶平均值查询一条序列
+pub async fn query(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    target: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket_secs: i64,
+) -> Result<TimeSeries, AppError> {
+    let (device_id, metric) = parse_target(target)
+        .ok_or_else(|| AppError::InvalidInput(format!("无效的序列标识: {}", target).into()))?;
+    let filter = MeasurementFilter {
+        metric_type: Some(metric.to_string()),
+        device_id: Some(device_id),
+        start: Some(start),
+        end: Some(end),
+        ..Default::default()
+    };
+    let points = store.aggregate(conn, &filter, bucket_secs).await?;
+    Ok(TimeSeries {
+        target: target.to_string(),
+        datapoints: points.into_iter().map(|p| (p.avg, p.bucket * 1000)).collect(),
+    })
+}
+
+/// 时间范围内的报警记录，`device_id` 为空时返回全部设备，指定组织时只返回本组织设备的
+pub async fn annotations(
+    conn: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    device_id: Option<i32>,
+    organization_id: Option<i32>,
+) -> Result<Vec<Annotation>, AppError> {
+    let mut select = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::TriggerTime.gte(start))
+        .filter(AlarmLogColumn::TriggerTime.lte(end));
+    if let Some(device_id) = device_id {
+        select = select.filter(AlarmLogColumn::DeviceId.eq(device_id));
+    }
+    if let Some(organization_id) = organization_id {
+        let devices = tenant::organization_devices(AlarmLogColumn::DeviceId, organization_id);
+        select = select.filter(devices);
+    }
+    let alarms = select
+        .order_by_asc(AlarmLogColumn::TriggerTime)
+        .limit(MAX_ANNOTATIONS)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(alarms
+        .into_iter()
+        .map(|alarm| {
+            let mut tags = vec!["alarm".to_string()];
+            if let Some(device_id) = alarm.device_id {
+                tags.push(format!("device:{}", device_id));
+            }
+            tags.push(if alarm.is_processed { "processed" } else { "open" }.to_string());
+            Annotation {
+                time: alarm.trigger_time.timestamp_millis(),
+                title: alarm.rule_name,
+                text: format!("触发值 {}", alarm.trigger_value),
+                tags,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(parse_target("3:flow"), Some((3, "flow")));
+        assert_eq!(parse_target(" 3 : ph "), Some((3, "ph")));
+        assert_eq!(parse_target("3:unknown"), None);
+        assert_eq!(parse_target("flow"), None);
+    }
+
+    #[test]
+    fn test_bucket_secs() {
+        let end = Utc::now();
+        let start = end - Duration::hours(24);
+        assert_eq!(bucket_secs(start, end, Some(60_000), Some(100)), 864);
+        assert_eq!(bucket_secs(start, end, Some(3_600_000), Some(100)), 3600);
+        assert_eq!(bucket_secs(start, end, None, None), 1);
+    }
+}
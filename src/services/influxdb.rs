@@ -0,0 +1,413 @@
+//! InfluxDB 时序存储
+//!
+//! 后台任务按 id 顺序读取测量值，以 line protocol 批量写入 InfluxDB 2.x。
+//! 已写入位置保存在本地 redb 文件中，写入失败时不前移，重试间隔逐次加倍；同一序列同一
+//! 时间点重复写入会覆盖，重试不会产生重复数据。首次启动从第一条测量值开始补写历史数据。
+//! 聚合查询用 Flux 在 InfluxDB 中按时间桶计算，结果与 SQL 聚合一致。
+
+use crate::config::timeseries::InfluxDbConfig;
+use crate::database::redb::DbManager as RedbManager;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::measurement::{
+    Column as MeasurementColumn, Entity as MeasurementEntity, Model as Measurement,
+    EXCLUDED_QUALITIES, QUALITY_GOOD,
+};
+use crate::services::measurement::{AggregatePoint, MeasurementFilter};
+use crate::services::timeseries::{TimeSeriesStore, BACKEND_INFLUXDB};
+use crate::utils::error::AppError;
+use crate::utils::tenant;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+const STATE_TABLE: &str = "influx_export";
+const CURSOR_KEY: &str = "last_measurement_id";
+
+/// 转义 measurement 名、tag 键值中的逗号、等号和空格
+fn escape_key(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 转义字符串字段值、Flux 字符串字面量中的引号和反斜杠
+fn escape_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// 一条测量值的 line protocol，时间精度为纳秒
+fn line(measurement: &str, m: &Measurement) -> String {
+    let mut line =
+        format!("{},metric_type={}", escape_key(measurement), escape_key(&m.metric_type));
+    if let Some(device_id) = m.device_id {
+        line.push_str(&format!(",device_id={}", device_id));
+    }
+    // 只有非 good 的数据带 quality 标签，历史数据与 good 数据同属一个序列
+    if m.quality != QUALITY_GOOD {
+        line.push_str(&format!(",quality={}", escape_key(&m.quality)));
+    }
+    line.push_str(&format!(
+        " value={:?},unit=\"{}\" {}",
+        m.value,
+        escape_string(&m.unit),
+        m.timestamp.timestamp_nanos_opt().unwrap_or_default()
+    ));
+    line
+}
+
+fn flux_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// 按时间桶统计最小、最大、平均值和条数，时间桶按 Unix 纪元对齐
+fn aggregate_query(
+    config: &InfluxDbConfig,
+    filter: &MeasurementFilter,
+    devices: Option<&[i32]>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket_secs: i64,
+) -> String {
+    let mut predicates = vec![
+        format!("r._measurement == \"{}\"", escape_string(&config.measurement)),
+        "r._field == \"value\"".to_string(),
+    ];
+    if let Some(metric_type) = &filter.metric_type {
+        predicates.push(format!("r.metric_type == \"{}\"", escape_string(metric_type)));
+    }
+    if let Some(device_id) = filter.device_id {
+        predicates.push(format!("r.device_id == \"{}\"", device_id));
+    }
+    if let Some(devices) = devices {
+        let set: Vec<String> = devices.iter().map(|id| format!("\"{}\"", id)).collect();
+        predicates.push(format!("contains(value: r.device_id, set: [{}])", set.join(", ")));
+    }
+    if let Some(quality) = &filter.quality {
+        predicates.push(if quality == QUALITY_GOOD {
+            "not exists r.quality".to_string()
+        } else {
+            format!("r.quality == \"{}\"", escape_string(quality))
+        });
+    }
+    if !filter.include_bad {
+        let excluded: Vec<String> =
+            EXCLUDED_QUALITIES.iter().map(|q| format!("r.quality != \"{}\"", q)).collect();
+        predicates.push(format!("(not exists r.quality or ({}))", excluded.join(" and ")));
+    }
+
+    // range 不含结束时间，SQL 聚合包含，结束时间后移 1 纳秒
+    format!(
+        r#"import "math"
+from(bucket: "{bucket}")
+  |> range(start: {start}, stop: {stop})
+  |> filter(fn: (r) => {predicates})
+  |> group()
+  |> window(every: {bucket_secs}s)
+  |> reduce(
+      identity: {{count: 0, sum: 0.0, min: math.mInf(sign: 1), max: math.mInf(sign: -1)}},
+      fn: (r, accumulator) => ({{
+          count: accumulator.count + 1,
+          sum: accumulator.sum + r._value,
+          min: if r._value < accumulator.min then r._value else accumulator.min,
+          max: if r._value > accumulator.max then r._value else accumulator.max,
+      }}))
+  |> map(fn: (r) => ({{
+      bucket: int(v: r._start) / 1000000000,
+      min: r.min,
+      max: r.max,
+      avg: r.sum / float(v: r.count),
+      count: r.count,
+  }}))
+  |> group()
+  |> sort(columns: ["bucket"])"#,
+        bucket = escape_string(&config.bucket),
+        start = flux_time(start),
+        stop = flux_time(end + chrono::Duration::nanoseconds(1)),
+        predicates = predicates.join(" and "),
+        bucket_secs = bucket_secs.max(1),
+    )
+}
+
+/// 解析查询返回的 CSV，表头行含 `bucket` 列，空行分隔多个表
+fn parse_aggregate(body: &str) -> Result<Vec<AggregatePoint>, String> {
+    let records = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(body.as_bytes())
+        .into_records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut columns: Option<[usize; 5]> = None;
+    let mut points = Vec::new();
+
+    let mut rows = records.iter();
+    while let Some(record) = rows.next() {
+        let index = |name: &str| record.iter().position(|field| field == name);
+        // 查询出错时表头后一行是错误信息
+        if let Some(error) = index("error") {
+            let message = rows.next().and_then(|r| r.get(error)).unwrap_or("查询失败");
+            return Err(message.to_string());
+        }
+        if let (Some(bucket), Some(min), Some(max), Some(avg), Some(count)) =
+            (index("bucket"), index("min"), index("max"), index("avg"), index("count"))
+        {
+            columns = Some([bucket, min, max, avg, count]);
+            continue;
+        }
+        let Some([bucket, min, max, avg, count]) = columns else {
+            continue;
+        };
+        let field = |i: usize| record.get(i).unwrap_or_default();
+        let number = |i: usize| {
+            field(i).parse::<f64>().map_err(|_| format!("无效的数值: {}", field(i)))
+        };
+        let integer = |i: usize| {
+            field(i).parse::<i64>().map_err(|_| format!("无效的整数: {}", field(i)))
+        };
+        points.push(AggregatePoint {
+            bucket: integer(bucket)?,
+            min: number(min)?,
+            max: number(max)?,
+            avg: number(avg)?,
+            count: integer(count)?,
+        });
+    }
+    points.sort_by_key(|p| p.bucket);
+    Ok(points)
+}
+
+fn build_client(config: &InfluxDbConfig) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+        .build()
+}
+
+fn with_token(
+    request: reqwest::RequestBuilder,
+    config: &InfluxDbConfig,
+) -> reqwest::RequestBuilder {
+    match &config.token {
+        Some(token) => request.header("Authorization", format!("Token {}", token)),
+        None => request,
+    }
+}
+
+async fn check(response: reqwest::Response) -> Result<String, String> {
+    let status = response.status();
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("HTTP {}: {}", status, body.chars().take(500).collect::<String>()));
+    }
+    Ok(body)
+}
+
+async fn write(
+    client: &reqwest::Client,
+    config: &InfluxDbConfig,
+    lines: String,
+) -> Result<(), String> {
+    let url = format!("{}/api/v2/write", config.url.trim_end_matches('/'));
+    let params = [
+        ("org", config.org.as_str()),
+        ("bucket", config.bucket.as_str()),
+        ("precision", "ns"),
+    ];
+    let request = client
+        .post(url)
+        .query(&params)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(lines);
+    let response = with_token(request, config).send().await.map_err(|e| e.to_string())?;
+    check(response).await.map(|_| ())
+}
+
+#[derive(Debug, Clone)]
+pub struct InfluxStore {
+    client: reqwest::Client,
+    config: InfluxDbConfig,
+}
+
+impl InfluxStore {
+    pub fn new(config: &InfluxDbConfig) -> reqwest::Result<Self> {
+        Ok(Self { client: build_client(config)?, config: config.clone() })
+    }
+
+    async fn query(&self, flux: String) -> Result<String, String> {
+        let url = format!("{}/api/v2/query", self.config.url.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "query": flux,
+            "type": "flux",
+            "dialect": { "header": true, "annotations": [] },
+        });
+        let request = self
+            .client
+            .post(url)
+            .query(&[("org", self.config.org.as_str())])
+            .header("Accept", "application/csv")
+            .json(&body);
+        let response = with_token(request, &self.config).send().await.map_err(|e| e.to_string())?;
+        check(response).await
+    }
+}
+
+#[tonic::async_trait]
+impl TimeSeriesStore for InfluxStore {
+    fn backend(&self) -> &'static str {
+        BACKEND_INFLUXDB
+    }
+
+    async fn aggregate(
+        &self,
+        conn: &DatabaseConnection,
+        filter: &MeasurementFilter,
+        bucket_secs: i64,
+    ) -> Result<Vec<AggregatePoint>, AppError> {
+        let end = filter.end.unwrap_or_else(Utc::now);
+        let start = filter.start.unwrap_or(DateTime::UNIX_EPOCH);
+        // InfluxDB 中没有设备的组织，按组织的设备列表过滤
+        let devices = match filter.organization_id {
+            Some(organization_id) => {
+                Some(tenant::organization_device_ids(conn, organization_id).await?)
+            }
+            None => None,
+        };
+        let flux =
+            aggregate_query(&self.config, filter, devices.as_deref(), start, end, bucket_secs);
+        let body = self.query(flux).await.map_err(|e| {
+            warn!("InfluxDB query failed: {}", e);
+            AppError::ServiceUnavailable("时序数据库查询失败".into())
+        })?;
+        parse_aggregate(&body).map_err(|e| {
+            warn!("Failed to parse InfluxDB response: {}", e);
+            AppError::InternalError
+        })
+    }
+}
+
+async fn next_batch(
+    conn: &DatabaseConnection,
+    after: i32,
+    limit: u64,
+) -> Result<Vec<Measurement>, sea_orm::DbErr> {
+    MeasurementEntity::find()
+        .filter(MeasurementColumn::Id.gt(after))
+        .order_by_asc(MeasurementColumn::Id)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+/// 后台任务：持续把新测量值写入 InfluxDB
+pub async fn run_exporter(config: InfluxDbConfig, db: DbManager) {
+    let state = match RedbManager::new(&config.state_path) {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Failed to open InfluxDB export state {}: {:?}", config.state_path, e);
+            return;
+        }
+    };
+    let client = match build_client(&config) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to create InfluxDB client: {}", e);
+            return;
+        }
+    };
+    let conn = db.get_connection();
+
+    let mut cursor = state.get::<i32>(STATE_TABLE, CURSOR_KEY).ok().flatten().unwrap_or(0);
+    info!("InfluxDB export started after measurement {} to bucket {}", cursor, config.bucket);
+
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    let max_backoff = Duration::from_secs(config.max_backoff_secs).max(interval);
+    let mut backoff = interval;
+
+    loop {
+        let batch = match next_batch(conn, cursor, config.batch_size.max(1)).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                error!("Failed to read measurements for InfluxDB export: {:?}", e);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+        let Some(last) = batch.last().map(|m| m.id) else {
+            tokio::time::sleep(interval).await;
+            continue;
+        };
+
+        let lines: Vec<String> = batch.iter().map(|m| line(&config.measurement, m)).collect();
+        if let Err(e) = write(&client, &config, lines.join("\n")).await {
+            warn!("InfluxDB export failed, retrying in {:?}: {}", backoff, e);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(max_backoff);
+            continue;
+        }
+        backoff = interval;
+        cursor = last;
+        if let Err(e) = state.put(STATE_TABLE, CURSOR_KEY, &cursor) {
+            error!("Failed to save InfluxDB export cursor: {:?}", e);
+        }
+
+        if (batch.len() as u64) < config.batch_size.max(1) {
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_line() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let mut m = Measurement {
+            id: 1,
+            metric_type: "ph".to_string(),
+            timestamp,
+            value: 7.0,
+            device_id: Some(3),
+            unit: "pH".to_string(),
+            suppressed_count: 0,
+            quality: "good".to_string(),
+            created_at: timestamp,
+            updated_at: timestamp,
+        };
+        assert_eq!(
+            line("measurements", &m),
+            "measurements,metric_type=ph,device_id=3 value=7.0,unit=\"pH\" 1714550400000000000"
+        );
+        m.metric_type = "a b,c".to_string();
+        m.device_id = None;
+        m.unit = "\"x\"".to_string();
+        assert_eq!(
+            line("measurements", &m),
+            "measurements,metric_type=a\\ b\\,c value=7.0,unit=\"\\\"x\\\"\" 1714550400000000000"
+        );
+        m.quality = "frozen".to_string();
+        assert!(line("measurements", &m).starts_with("measurements,metric_type=a\\ b\\,c,quality=frozen value="));
+    }
+
+    #[test]
+    fn test_parse_aggregate() {
+        let body = ",result,table,bucket,min,max,avg,count\r\n\
+                    ,_result,0,3600,1,3,2,3\r\n\
+                    ,_result,0,0,5,5,5,1\r\n\r\n";
+        let points = parse_aggregate(body).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].bucket, 0);
+        assert_eq!(points[1].avg, 2.0);
+        assert_eq!(points[1].count, 3);
+
+        let error = ",error,reference\r\n,bucket not found,\r\n";
+        assert_eq!(parse_aggregate(error).unwrap_err(), "bucket not found");
+    }
+}
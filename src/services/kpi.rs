@@ -0,0 +1,238 @@
+//! 自定义 KPI
+//!
+//! KPI 由公式（见 [`crate::services::kpi_expr`]）和变量绑定组成。每个别名绑定一台设备的一个指标，
+//! 公式中用 `<别名>_<统计量>` 引用该指标在时间段内的 avg/min/max/count，例如绑定
+//! `inlet_cod` 后可以写 `(inlet_cod_avg - outlet_cod_avg) / inlet_cod_avg * 100`。
+//! 定义的每次修改都记录在配置版本中，可以回滚。
+
+use crate::models::kpi_definition::{
+    Column as KpiColumn, Entity as KpiEntity, Model as KpiDefinition,
+};
+use crate::services::kpi_expr::{self, Expr};
+use crate::services::measurement::MeasurementFilter;
+use crate::services::timeseries::TimeSeriesStore;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// 可引用的统计量
+pub const STATS: &[&str] = &["avg", "min", "max", "count"];
+
+/// 变量绑定的设备指标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Binding {
+    pub device_id: i32,
+    pub metric_type: String,
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 拆分 `<别名>_<统计量>`
+fn split_variable(name: &str) -> Option<(&str, &str)> {
+    let (alias, stat) = name.rsplit_once('_')?;
+    STATS.contains(&stat).then_some((alias, stat))
+}
+
+/// 解析变量绑定
+pub fn bindings(definition: &KpiDefinition) -> Result<BTreeMap<String, Binding>, AppError> {
+    serde_json::from_str(&definition.variables)
+        .map_err(|e| AppError::InvalidInput(format!("变量绑定格式错误: {}", e).into()))
+}
+
+/// 校验定义，返回解析后的公式
+pub fn validate(definition: &KpiDefinition) -> Result<Expr, AppError> {
+    let invalid = |msg: String| AppError::InvalidInput(format!("KPI 定义无效: {}", msg).into());
+
+    if !is_identifier(&definition.name) {
+        return Err(invalid("名称只能包含字母、数字和下划线，且不能以数字开头".to_string()));
+    }
+    let expr = kpi_expr::parse(&definition.expression).map_err(|e| invalid(e.to_string()))?;
+    let bindings = bindings(definition)?;
+    for (alias, binding) in &bindings {
+        if !is_identifier(alias) {
+            return Err(invalid(format!("变量别名 {} 无效", alias)));
+        }
+        if binding.metric_type.trim().is_empty() {
+            return Err(invalid(format!("变量 {} 未指定指标类型", alias)));
+        }
+    }
+    for name in expr.variables() {
+        match split_variable(name) {
+            Some((alias, _)) if bindings.contains_key(alias) => {}
+            Some((alias, _)) => return Err(invalid(format!("变量 {} 未绑定", alias))),
+            None => {
+                return Err(invalid(format!(
+                    "变量 {} 应写成 <别名>_<{}>",
+                    name,
+                    STATS.join("|")
+                )))
+            }
+        }
+    }
+    Ok(expr)
+}
+
+/// KPI 在时间段内的值
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KpiValue {
+    pub kpi_id: i32,
+    pub name: String,
+    pub unit: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 无法计算时为空
+    pub value: Option<f64>,
+    /// 公式引用的变量值
+    pub inputs: BTreeMap<String, f64>,
+    /// 无法计算的原因，例如变量在时间段内没有数据或除数为 0
+    pub error: Option<String>,
+}
+
+/// 指标在时间段内的统计；没有数据时只有 count
+async fn stats(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    binding: &Binding,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<HashMap<&'static str, f64>, AppError> {
+    let filter = MeasurementFilter {
+        metric_type: Some(binding.metric_type.clone()),
+        device_id: Some(binding.device_id),
+        start: Some(start),
+        end: Some(end),
+        ..Default::default()
+    };
+    // 时间桶按纪元对齐，整个时间段最多落在两个桶内，合并即可
+    let bucket_secs = (end - start).num_seconds().max(1);
+    let points = store.aggregate(conn, &filter, bucket_secs).await?;
+
+    let count: i64 = points.iter().map(|p| p.count).sum();
+    let mut stats = HashMap::from([("count", count as f64)]);
+    if count > 0 {
+        let sum: f64 = points.iter().map(|p| p.avg * p.count as f64).sum();
+        stats.insert("avg", sum / count as f64);
+        stats.insert("min", points.iter().map(|p| p.min).fold(f64::INFINITY, f64::min));
+        stats.insert("max", points.iter().map(|p| p.max).fold(f64::NEG_INFINITY, f64::max));
+    }
+    Ok(stats)
+}
+
+/// 计算 KPI 在 `[start, end]` 内的值
+pub async fn evaluate(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    definition: &KpiDefinition,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<KpiValue, AppError> {
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间必须早于结束时间".into()));
+    }
+    let expr = validate(definition)?;
+    let bindings = bindings(definition)?;
+
+    let mut inputs = BTreeMap::new();
+    let mut missing = Vec::new();
+    let mut cache: HashMap<&str, HashMap<&'static str, f64>> = HashMap::new();
+    for name in expr.variables() {
+        let Some((alias, stat)) = split_variable(name) else {
+            continue;
+        };
+        if !cache.contains_key(alias) {
+            let Some(binding) = bindings.get(alias) else {
+                continue;
+            };
+            cache.insert(alias, stats(conn, store, binding, start, end).await?);
+        }
+        match cache[alias].get(stat) {
+            Some(value) => {
+                inputs.insert(name.to_string(), *value);
+            }
+            None => missing.push(name),
+        }
+    }
+
+    let (value, error) = if !missing.is_empty() {
+        (None, Some(format!("变量在时间段内没有数据: {}", missing.join(", "))))
+    } else {
+        let vars: HashMap<String, f64> = inputs.clone().into_iter().collect();
+        match expr.eval(&vars) {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    };
+
+    Ok(KpiValue {
+        kpi_id: definition.id,
+        name: definition.name.clone(),
+        unit: definition.unit.clone(),
+        start,
+        end,
+        value,
+        inputs,
+        error,
+    })
+}
+
+/// 计算全部启用的 KPI
+pub async fn evaluate_all(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<KpiValue>, AppError> {
+    let definitions = KpiEntity::find()
+        .filter(KpiColumn::Enabled.eq(true))
+        .order_by_asc(KpiColumn::Name)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut values = Vec::with_capacity(definitions.len());
+    for definition in &definitions {
+        values.push(evaluate(conn, store, definition, start, end).await?);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition() -> KpiDefinition {
+        KpiDefinition {
+            id: 1,
+            name: "cod_removal".to_string(),
+            description: None,
+            expression: "(inlet_cod_avg - outlet_cod_avg) / inlet_cod_avg * 100".to_string(),
+            variables: r#"{"inlet_cod": {"device_id": 1, "metric_type": "cod"},
+                           "outlet_cod": {"device_id": 2, "metric_type": "cod"}}"#
+                .to_string(),
+            unit: Some("%".to_string()),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&definition()).is_ok());
+        assert!(validate(&KpiDefinition { name: "1st".to_string(), ..definition() }).is_err());
+        let unbound = "inlet_cod_avg / flow_avg".to_string();
+        assert!(validate(&KpiDefinition { expression: unbound, ..definition() }).is_err());
+        let bad_stat = KpiDefinition { expression: "inlet_cod_median".to_string(), ..definition() };
+        assert!(validate(&bad_stat).is_err());
+        let bad_json = KpiDefinition { variables: "[]".to_string(), ..definition() };
+        assert!(validate(&bad_json).is_err());
+        assert_eq!(split_variable("inlet_cod_max"), Some(("inlet_cod", "max")));
+    }
+}
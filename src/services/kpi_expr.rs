@@ -0,0 +1,342 @@
+//! KPI 公式
+//!
+//! 只支持数字、变量、四则运算、`%`、`^`、括号和少量内置函数（abs/min/max/round/sqrt/clamp），
+//! 没有赋值、循环和外部调用。公式长度和嵌套深度有上限，求值结果必须是有限数值，
+//! 除数为 0 时报错而不是返回无穷大。
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// 公式最大长度（字符）
+pub const MAX_LEN: usize = 1000;
+/// 最大嵌套深度
+const MAX_DEPTH: usize = 32;
+
+/// 内置函数及参数个数
+const FUNCTIONS: &[(&str, usize)] =
+    &[("abs", 1), ("min", 2), ("max", 2), ("round", 1), ("sqrt", 1), ("clamp", 3)];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    TooLong,
+    TooDeep,
+    Syntax(String),
+    UnknownFunction(String),
+    UnknownVariable(String),
+    DivisionByZero,
+    NotFinite,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::TooLong => write!(f, "公式超过 {} 个字符", MAX_LEN),
+            ExprError::TooDeep => write!(f, "公式嵌套超过 {} 层", MAX_DEPTH),
+            ExprError::Syntax(msg) => write!(f, "语法错误: {}", msg),
+            ExprError::UnknownFunction(name) => write!(f, "未知的函数 {}", name),
+            ExprError::UnknownVariable(name) => write!(f, "未知的变量 {}", name),
+            ExprError::DivisionByZero => write!(f, "除数为 0"),
+            ExprError::NotFinite => write!(f, "结果不是有限数值"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse()
+                    .map_err(|_| ExprError::Syntax(format!("无效的数字 {}", text)))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            other => return Err(ExprError::Syntax(format!("不支持的字符 '{}'", other))),
+        }
+    }
+    Ok(tokens)
+}
+
+/// 解析后的公式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::TooDeep);
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ExprError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(ExprError::Syntax(format!("缺少 {}", what))),
+        }
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, ExprError> {
+        self.enter()?;
+        let mut left = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+        self.depth -= 1;
+        Ok(left)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    /// unary := '-' unary | power
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            self.enter()?;
+            let operand = self.unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Neg(Box::new(operand)));
+        }
+        self.power()
+    }
+
+    /// power := primary ('^' unary)?，右结合
+    fn power(&mut self) -> Result<Expr, ExprError> {
+        let base = self.primary()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExprError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                self.pos += 1;
+                let arity = FUNCTIONS
+                    .iter()
+                    .find(|(f, _)| *f == name)
+                    .map(|(_, n)| *n)
+                    .ok_or_else(|| ExprError::UnknownFunction(name.clone()))?;
+                let mut args = vec![self.expr()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.expr()?);
+                }
+                self.expect(Token::RParen, "')'")?;
+                if args.len() != arity {
+                    return Err(ExprError::Syntax(format!(
+                        "函数 {} 需要 {} 个参数，实际 {} 个",
+                        name,
+                        arity,
+                        args.len()
+                    )));
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::LParen) => {
+                let inner = self.expr()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(inner)
+            }
+            Some(_) => Err(ExprError::Syntax("缺少操作数".to_string())),
+            None => Err(ExprError::Syntax("公式不完整".to_string())),
+        }
+    }
+}
+
+/// 解析公式
+pub fn parse(source: &str) -> Result<Expr, ExprError> {
+    if source.chars().count() > MAX_LEN {
+        return Err(ExprError::TooLong);
+    }
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+    let expr = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(ExprError::Syntax("公式末尾有多余的内容".to_string()));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// 公式引用的变量，按首次出现的顺序去重
+    pub fn variables(&self) -> Vec<&str> {
+        fn walk<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Variable(name) => {
+                    if !out.contains(&name.as_str()) {
+                        out.push(name);
+                    }
+                }
+                Expr::Neg(operand) => walk(operand, out),
+                Expr::Binary(_, left, right) => {
+                    walk(left, out);
+                    walk(right, out);
+                }
+                Expr::Call(_, args) => args.iter().for_each(|arg| walk(arg, out)),
+            }
+        }
+        let mut out = Vec::new();
+        walk(self, &mut out);
+        out
+    }
+
+    /// 求值
+    pub fn eval(&self, vars: &HashMap<String, f64>) -> Result<f64, ExprError> {
+        let value = match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(name) => *vars
+                .get(name)
+                .ok_or_else(|| ExprError::UnknownVariable(name.clone()))?,
+            Expr::Neg(operand) => -operand.eval(vars)?,
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (left.eval(vars)?, right.eval(vars)?);
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' | '%' if b == 0.0 => return Err(ExprError::DivisionByZero),
+                    '/' => a / b,
+                    '%' => a % b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|arg| arg.eval(vars)).collect::<Result<Vec<_>, _>>()?;
+                match (name.as_str(), args.as_slice()) {
+                    ("abs", [x]) => x.abs(),
+                    ("min", [a, b]) => a.min(*b),
+                    ("max", [a, b]) => a.max(*b),
+                    ("round", [x]) => x.round(),
+                    ("sqrt", [x]) => x.sqrt(),
+                    ("clamp", [x, lo, hi]) => x.max(*lo).min(*hi),
+                    _ => return Err(ExprError::UnknownFunction(name.clone())),
+                }
+            }
+        };
+        if !value.is_finite() {
+            return Err(ExprError::NotFinite);
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, vars: &[(&str, f64)]) -> Result<f64, ExprError> {
+        let vars = vars.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        parse(source)?.eval(&vars)
+    }
+
+    #[test]
+    fn test_eval() {
+        let vars = [("inlet_cod_avg", 400.0), ("outlet_cod_avg", 40.0)];
+        let removal = "(inlet_cod_avg - outlet_cod_avg) / inlet_cod_avg * 100";
+        assert_eq!(eval(removal, &vars), Ok(90.0));
+        assert_eq!(eval("1 + 2 * 3 - -4", &[]), Ok(11.0));
+        assert_eq!(eval("-2 ^ 2", &[]), Ok(-4.0));
+        assert_eq!(eval("2 ^ 3 ^ 2", &[]), Ok(512.0));
+        assert_eq!(eval("clamp(max(1, 7) % 4, 0, 2.5)", &[]), Ok(2.5));
+        assert_eq!(parse(removal).unwrap().variables(), vec!["inlet_cod_avg", "outlet_cod_avg"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("1 / (2 - 2)", &[]), Err(ExprError::DivisionByZero));
+        assert_eq!(eval("sqrt(-1)", &[]), Err(ExprError::NotFinite));
+        assert_eq!(eval("x + 1", &[]), Err(ExprError::UnknownVariable("x".to_string())));
+        assert_eq!(eval("exec(1)", &[]), Err(ExprError::UnknownFunction("exec".to_string())));
+        assert!(matches!(parse("min(1)"), Err(ExprError::Syntax(_))));
+        assert!(matches!(parse("(1 + 2"), Err(ExprError::Syntax(_))));
+        assert!(matches!(parse("1 2"), Err(ExprError::Syntax(_))));
+        assert!(matches!(parse("a; b"), Err(ExprError::Syntax(_))));
+        assert_eq!(parse(&"(".repeat(40)), Err(ExprError::TooDeep));
+        assert_eq!(parse(&"1+".repeat(600)), Err(ExprError::TooLong));
+    }
+}
@@ -0,0 +1,104 @@
+//! 化验结果
+//!
+//! BOD、COD、氨氮等指标没有在线仪表，由化验室取样分析后人工录入。修改、删除须说明原因，
+//! 原始版本保存在修正记录中，见 [`crate::services::data_correction`]。
+
+use crate::models::lab_result::Model as LabResult;
+use crate::utils::error::AppError;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 化验项目
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct Analyte {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub unit: &'static str,
+    /// 有效范围上限，下限为 0
+    pub max: f64,
+}
+
+pub const BOD: &str = "bod";
+pub const COD: &str = "cod";
+pub const AMMONIA: &str = "ammonia";
+pub const TOTAL_NITROGEN: &str = "total_nitrogen";
+pub const TOTAL_PHOSPHORUS: &str = "total_phosphorus";
+pub const SUSPENDED_SOLIDS: &str = "ss";
+
+pub const ANALYTES: &[Analyte] = &[
+    Analyte { key: BOD, name: "五日生化需氧量", unit: "mg/L", max: 100000.0 },
+    Analyte { key: COD, name: "化学需氧量", unit: "mg/L", max: 100000.0 },
+    Analyte { key: AMMONIA, name: "氨氮", unit: "mg/L", max: 10000.0 },
+    Analyte { key: TOTAL_NITROGEN, name: "总氮", unit: "mg/L", max: 10000.0 },
+    Analyte { key: TOTAL_PHOSPHORUS, name: "总磷", unit: "mg/L", max: 1000.0 },
+    Analyte { key: SUSPENDED_SOLIDS, name: "悬浮物", unit: "mg/L", max: 100000.0 },
+];
+
+/// 按标识查找化验项目
+pub fn lookup(key: &str) -> Option<&'static Analyte> {
+    ANALYTES.iter().find(|a| a.key == key)
+}
+
+/// 校验化验结果
+pub fn validate(result: &LabResult) -> Result<(), AppError> {
+    let invalid = |msg: String| Err(AppError::InvalidInput(format!("化验结果无效: {}", msg).into()));
+
+    let Some(analyte) = lookup(&result.analyte) else {
+        return invalid(format!("未知的化验项目 {}", result.analyte));
+    };
+    if result.unit != analyte.unit {
+        return invalid(format!("{}的单位应为 {}", analyte.name, analyte.unit));
+    }
+    if !(result.value.is_finite() && result.value >= 0.0 && result.value <= analyte.max) {
+        return invalid(format!(
+            "{}超出有效范围 [0, {}]: {}",
+            analyte.name, analyte.max, result.value
+        ));
+    }
+    if result.sample_point.trim().is_empty() {
+        return invalid("须填写采样点".to_string());
+    }
+    if result.analyst.trim().is_empty() {
+        return invalid("须填写化验人员".to_string());
+    }
+    if result.analyzed_at.is_some_and(|at| at < result.sampled_at) {
+        return invalid("分析时间不能早于采样时间".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_validate() {
+        let sampled_at = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let result = LabResult {
+            id: 0,
+            site_id: None,
+            sample_point: "出水".to_string(),
+            device_id: None,
+            analyte: COD.to_string(),
+            value: 35.0,
+            unit: "mg/L".to_string(),
+            sampled_at,
+            analyzed_at: Some(sampled_at + Duration::hours(3)),
+            method: None,
+            analyst: "张工".to_string(),
+            notes: None,
+            created_at: sampled_at,
+            updated_at: sampled_at,
+        };
+        assert!(validate(&result).is_ok());
+
+        assert!(validate(&LabResult { analyte: "chlorophyll".to_string(), ..result.clone() }).is_err());
+        assert!(validate(&LabResult { unit: "g/L".to_string(), ..result.clone() }).is_err());
+        assert!(validate(&LabResult { value: -1.0, ..result.clone() }).is_err());
+        assert!(validate(&LabResult { value: f64::NAN, ..result.clone() }).is_err());
+        assert!(validate(&LabResult { analyst: " ".to_string(), ..result.clone() }).is_err());
+        let early = Some(sampled_at - Duration::hours(1));
+        assert!(validate(&LabResult { analyzed_at: early, ..result }).is_err());
+    }
+}
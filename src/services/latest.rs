@@ -0,0 +1,147 @@
+//! 设备最新值快照
+//!
+//! 看板一次请求取回所有设备各指标的当前值。温度、压力没有测量记录时，
+//! 退回设备表上的当前温度/压力字段。结果按设备缓存，缓存未命中的设备才查询数据库。
+
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity, Model as Device};
+use crate::services::cache::{self, HotCache};
+use crate::services::{measurement, metric_registry};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// 单个指标的最新值
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LatestValue {
+    pub value: f64,
+    pub unit: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 单台设备的最新值，`values` 以指标标识为键，例如 `ph`、`flow`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeviceLatest {
+    pub device_id: i32,
+    pub device_name: String,
+    pub values: BTreeMap<String, LatestValue>,
+}
+
+fn device_fallback(device: &Device, metric: &str, value: f64, values: &mut BTreeMap<String, LatestValue>) {
+    if values.contains_key(metric) {
+        return;
+    }
+    let unit = metric_registry::lookup(metric).map(|m| m.unit).unwrap_or_default();
+    values.insert(
+        metric.to_string(),
+        LatestValue {
+            value,
+            unit: unit.to_string(),
+            timestamp: device.updated_at,
+        },
+    );
+}
+
+/// 计算给定设备的最新值快照
+async fn compute(conn: &DatabaseConnection, devices: &[&Device]) -> Result<Vec<DeviceLatest>, AppError> {
+    let device_ids: Vec<i32> = devices.iter().map(|d| d.id).collect();
+    let rows = measurement::latest_for_devices(conn, &device_ids).await?;
+
+    let mut by_device: HashMap<i32, BTreeMap<String, LatestValue>> = HashMap::new();
+    for row in rows {
+        let Some(device_id) = row.device_id else {
+            continue;
+        };
+        by_device.entry(device_id).or_default().insert(
+            row.metric_type,
+            LatestValue {
+                value: row.value,
+                unit: row.unit,
+                timestamp: row.timestamp,
+            },
+        );
+    }
+
+    Ok(devices
+        .iter()
+        .map(|device| {
+            let mut values = by_device.remove(&device.id).unwrap_or_default();
+            device_fallback(device, metric_registry::TEMPERATURE, device.temperature, &mut values);
+            device_fallback(device, metric_registry::PRESSURE, device.pressure, &mut values);
+            DeviceLatest {
+                device_id: device.id,
+                device_name: device.name.clone(),
+                values,
+            }
+        })
+        .collect())
+}
+
+/// 给定设备的最新值快照，优先读缓存
+pub async fn snapshot(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    devices: &[Device],
+) -> Result<Vec<DeviceLatest>, AppError> {
+    let generation = cache.generation();
+    let mut cached = HashMap::new();
+    let mut missing = Vec::new();
+    for device in devices {
+        match cache.get::<DeviceLatest>(cache::LATEST_VALUES, device.id).await {
+            Some(latest) => {
+                cached.insert(device.id, latest);
+            }
+            None => missing.push(device),
+        }
+    }
+
+    if !missing.is_empty() {
+        for latest in compute(conn, &missing).await? {
+            cache
+                .put(cache::LATEST_VALUES, latest.device_id, &latest, generation)
+                .await;
+            cached.insert(latest.device_id, latest);
+        }
+    }
+
+    Ok(devices
+        .iter()
+        .filter_map(|device| cached.remove(&device.id))
+        .collect())
+}
+
+/// 单台设备的最新值
+pub async fn for_device(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    device_id: i32,
+) -> Result<DeviceLatest, AppError> {
+    let device = DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    let mut latest = snapshot(conn, cache, std::slice::from_ref(&device)).await?;
+    latest.pop().ok_or(AppError::InternalError)
+}
+
+/// 全部设备的最新值，指定组织时只包含本组织的设备
+pub async fn for_all_devices(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    organization_id: Option<i32>,
+) -> Result<Vec<DeviceLatest>, AppError> {
+    let mut select = DeviceEntity::find();
+    if let Some(organization_id) = organization_id {
+        select = select.filter(DeviceColumn::OrganizationId.eq(organization_id));
+    }
+    let devices = select
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    snapshot(conn, cache, &devices).await
+}
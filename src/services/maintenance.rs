@@ -0,0 +1,263 @@
+//! 维护计划与工单
+//!
+//! 维护计划按日历天数或设备运行小时数设定间隔，到期时自动生成工单；同一计划同时只有一张
+//! 未关闭的工单。工单在 `overdue_days` 内未关闭时产生一次超期报警。技术员关闭工单时填写
+//! 处理记录，来源计划从关闭时刻重新计时，设备的 `last_maintenance` 同步更新。
+
+use crate::config::maintenance::MaintenanceConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{Entity as DeviceEntity, Model as Device};
+use crate::models::maintenance_plan::{
+    Column as PlanColumn, Entity as PlanEntity, Model as MaintenancePlan, INTERVAL_CALENDAR,
+    INTERVAL_OPERATING_HOURS,
+};
+use crate::models::work_order::{
+    ActiveModel as WorkOrderActiveModel, Column as WorkOrderColumn, Entity as WorkOrderEntity,
+    Model as WorkOrder, STATUS_CLOSED, STATUS_OPEN,
+};
+use crate::services::alarm;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set, TransactionTrait,
+};
+use std::collections::HashSet;
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+
+pub fn validate_plan(plan: &MaintenancePlan) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("维护计划参数无效: {}", msg).into()));
+
+    if plan.name.trim().is_empty() {
+        return invalid("名称不能为空");
+    }
+    match plan.interval_kind.as_str() {
+        INTERVAL_CALENDAR => {
+            if !plan.interval_days.is_some_and(|days| days > 0) {
+                return invalid("日历间隔须大于 0 天");
+            }
+        }
+        INTERVAL_OPERATING_HOURS => {
+            if !plan.interval_hours.is_some_and(|hours| hours.is_finite() && hours > 0.0) {
+                return invalid("运行小时间隔须大于 0");
+            }
+        }
+        _ => return invalid("间隔类型须为 calendar / operating_hours"),
+    }
+    if plan.overdue_days < 0 {
+        return invalid("超期天数不能为负");
+    }
+    if !(plan.last_done_hours.is_finite() && plan.last_done_hours >= 0.0) {
+        return invalid("上次完成时的运行小时数无效");
+    }
+    Ok(())
+}
+
+/// 计划是否到期，`operational_hours` 为设备当前的运行小时数
+pub fn is_due(plan: &MaintenancePlan, operational_hours: f64, now: DateTime<Utc>) -> bool {
+    match plan.interval_kind.as_str() {
+        INTERVAL_CALENDAR => plan
+            .interval_days
+            .is_some_and(|days| now >= plan.last_done_at + Duration::days(days as i64)),
+        INTERVAL_OPERATING_HOURS => plan
+            .interval_hours
+            .is_some_and(|hours| operational_hours >= plan.last_done_hours + hours),
+        _ => false,
+    }
+}
+
+async fn find_device(conn: &DatabaseConnection, device_id: i32) -> Result<Option<Device>, AppError> {
+    DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 为到期且没有未关闭工单的计划生成工单，返回生成的数量
+pub async fn open_due(conn: &DatabaseConnection, now: DateTime<Utc>) -> Result<usize, AppError> {
+    let plans = PlanEntity::find()
+        .filter(PlanColumn::Enabled.eq(true))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let open_plans: HashSet<i32> = WorkOrderEntity::find()
+        .filter(WorkOrderColumn::Status.eq(STATUS_OPEN))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .filter_map(|order| order.plan_id)
+        .collect();
+
+    let mut opened = 0;
+    for plan in plans.iter().filter(|plan| !open_plans.contains(&plan.id)) {
+        let Some(device) = find_device(conn, plan.device_id).await? else {
+            continue;
+        };
+        if !is_due(plan, device.operational_hours, now) {
+            continue;
+        }
+        let order = WorkOrderActiveModel {
+            plan_id: Set(Some(plan.id)),
+            device_id: Set(plan.device_id),
+            title: Set(format!("{}: {}", device.name, plan.name)),
+            description: Set(plan.description.clone()),
+            status: Set(STATUS_OPEN.to_string()),
+            due_at: Set(now + Duration::days(plan.overdue_days as i64)),
+            overdue_alarmed: Set(false),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+        info!("Opened work order {} for maintenance plan {}", order.id, plan.name);
+        opened += 1;
+    }
+    Ok(opened)
+}
+
+/// 对超期未关闭的工单报警，每张工单只报警一次
+pub async fn alarm_overdue(
+    conn: &DatabaseConnection,
+    config: &MaintenanceConfig,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let overdue = WorkOrderEntity::find()
+        .filter(WorkOrderColumn::Status.eq(STATUS_OPEN))
+        .filter(WorkOrderColumn::OverdueAlarmed.eq(false))
+        .filter(WorkOrderColumn::DueAt.lt(now))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let count = overdue.len();
+    for order in overdue {
+        let days = (now - order.due_at).num_days();
+        let rule_name = format!("维护工单 {} 超期未完成: {}", order.id, order.title);
+        alarm::raise(conn, Some(order.device_id), rule_name, days as f64, &config.severity).await?;
+
+        let mut active_model = order.into_active_model();
+        active_model.overdue_alarmed = Set(true);
+        active_model.updated_at = Set(now);
+        active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    }
+    Ok(count)
+}
+
+/// 关闭工单，来源计划从此刻重新计时并更新设备的上次维护时间
+pub async fn close(
+    conn: &DatabaseConnection,
+    id: i32,
+    notes: Option<String>,
+    operator: Option<String>,
+) -> Result<WorkOrder, AppError> {
+    let order = WorkOrderEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+    if order.status == STATUS_CLOSED {
+        return Err(AppError::Unprocessable("工单已关闭".into()));
+    }
+
+    let now = Utc::now();
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    let device = DeviceEntity::find_by_id(order.device_id)
+        .one(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if let Some(plan_id) = order.plan_id {
+        let plan = PlanEntity::find_by_id(plan_id)
+            .one(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        if let Some(plan) = plan {
+            let mut active_model = plan.into_active_model();
+            active_model.last_done_at = Set(now);
+            if let Some(device) = &device {
+                active_model.last_done_hours = Set(device.operational_hours);
+            }
+            active_model.updated_at = Set(now);
+            active_model.update(&txn).await.map_err(|_| AppError::InternalError)?;
+        }
+    }
+    if let Some(device) = device {
+        let mut active_model = device.into_active_model();
+        active_model.last_maintenance = Set(now);
+        active_model.updated_at = Set(now);
+        active_model.update(&txn).await.map_err(|_| AppError::InternalError)?;
+    }
+
+    let mut active_model = order.into_active_model();
+    active_model.status = Set(STATUS_CLOSED.to_string());
+    active_model.closed_at = Set(Some(now));
+    active_model.closed_by = Set(operator);
+    active_model.notes = Set(notes);
+    active_model.updated_at = Set(now);
+    let order = active_model.update(&txn).await.map_err(|_| AppError::InternalError)?;
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    info!("Work order {} closed by {:?}", order.id, order.closed_by);
+    Ok(order)
+}
+
+/// 后台任务：生成到期工单并检查超期
+pub async fn run_scheduler(config: MaintenanceConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        let now = Utc::now();
+        match open_due(conn, now).await {
+            Ok(0) => {}
+            Ok(count) => info!("Opened {} maintenance work orders", count),
+            Err(e) => error!("Maintenance scheduling failed: {:?}", e),
+        }
+        if let Err(e) = alarm_overdue(conn, &config, now).await {
+            error!("Overdue work order check failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn plan(kind: &str) -> MaintenancePlan {
+        let t0 = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        MaintenancePlan {
+            id: 1,
+            device_id: 1,
+            name: "更换机械密封".to_string(),
+            description: None,
+            interval_kind: kind.to_string(),
+            interval_days: Some(90),
+            interval_hours: Some(2000.0),
+            overdue_days: 7,
+            last_done_at: t0,
+            last_done_hours: 1000.0,
+            enabled: true,
+            created_at: t0,
+            updated_at: t0,
+        }
+    }
+
+    #[test]
+    fn test_is_due() {
+        let t0 = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let calendar = plan(INTERVAL_CALENDAR);
+        assert!(!is_due(&calendar, 0.0, t0 + Duration::days(89)));
+        assert!(is_due(&calendar, 0.0, t0 + Duration::days(90)));
+
+        let hours = plan(INTERVAL_OPERATING_HOURS);
+        assert!(!is_due(&hours, 2999.0, t0 + Duration::days(365)));
+        assert!(is_due(&hours, 3000.0, t0));
+
+        assert!(validate_plan(&calendar).is_ok());
+        assert!(validate_plan(&plan("weekly")).is_err());
+    }
+}
@@ -0,0 +1,545 @@
+//! 测量数据服务
+//!
+//! 通用 `/measurements` 接口与旧的 ph/tds/浊度/流量接口共用这里的读写逻辑。
+
+use crate::config::quality::QualityConfig;
+use crate::models::data_correction::{ACTION_DELETE, ACTION_UPDATE, RECORD_MEASUREMENT};
+use crate::models::measurement::{
+    ActiveModel as MeasurementActiveModel, Column as MeasurementColumn,
+    Entity as MeasurementEntity, Model as Measurement,
+};
+use crate::services::cache::HotCache;
+use crate::services::calibration;
+use crate::services::daily_summary;
+use crate::services::data_correction::{self, CorrectionNote};
+use crate::services::fault_injection;
+use crate::services::metric_registry;
+use crate::services::quality;
+use crate::services::tank;
+use crate::utils::error::AppError;
+use crate::utils::tenant;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Alias, Expr, Func, JoinType, Order, Query};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait,
+    FromQueryResult, IntoActiveModel, Iterable, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 查询最新值时每批的设备数，避免 IN 列表过长
+const LATEST_CHUNK_SIZE: usize = 500;
+
+/// 新增测量值
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewMeasurement {
+    pub metric_type: String,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    pub unit: Option<String>,
+}
+
+/// 修改测量值
+#[derive(Debug, Clone, Default)]
+pub struct MeasurementChanges {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub value: Option<f64>,
+    pub device_id: Option<Option<i32>>,
+    pub unit: Option<String>,
+    /// 人工设定的数据质量；未给出而修改了值时重新评估
+    pub quality: Option<String>,
+}
+
+/// 查询条件
+#[derive(Debug, Clone, Default)]
+pub struct MeasurementFilter {
+    pub metric_type: Option<String>,
+    pub device_id: Option<i32>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// 只查询该质量的数据
+    pub quality: Option<String>,
+    /// 聚合时包含 bad / out_of_range 的数据，默认排除
+    pub include_bad: bool,
+    /// 只查询该组织设备的测量值，见 [`crate::utils::tenant`]
+    pub organization_id: Option<i32>,
+}
+
+/// 校验指标类型、单位及取值范围，返回换算为默认单位后的值和入库的单位
+///
+/// 未给出单位时按默认单位处理。
+fn validate(metric_type: &str, value: f64, unit: Option<&str>) -> Result<(f64, String), AppError> {
+    let info = metric_registry::lookup(metric_type)
+        .ok_or_else(|| AppError::InvalidInput(format!("未知的指标类型: {}", metric_type).into()))?;
+
+    let unit = unit.unwrap_or(info.unit);
+    let value = info.to_default_unit(value, unit).ok_or_else(|| {
+        AppError::InvalidInput(format!("{}不支持单位 {}", info.name, unit).into())
+    })?;
+    // 已换算的值以默认单位入库
+    let unit = if info.aliases.iter().any(|(alias, _)| *alias == unit) { info.unit } else { unit };
+
+    if !info.in_range(value) {
+        return Err(AppError::InvalidInput(
+            format!(
+                "{}超出有效范围 [{}, {}]: {}",
+                info.name, info.min, info.max, value
+            )
+            .into(),
+        ));
+    }
+
+    Ok((value, unit.to_string()))
+}
+
+/// 分页查询测量值，按时间倒序
+pub async fn list(
+    conn: &DatabaseConnection,
+    filter: &MeasurementFilter,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<Measurement>, AppError> {
+    let mut query = MeasurementEntity::find();
+
+    if let Some(metric_type) = &filter.metric_type {
+        query = query.filter(MeasurementColumn::MetricType.eq(metric_type.as_str()));
+    }
+    if let Some(device_id) = filter.device_id {
+        query = query.filter(MeasurementColumn::DeviceId.eq(device_id));
+    }
+    if let Some(start) = filter.start {
+        query = query.filter(MeasurementColumn::Timestamp.gte(start));
+    }
+    if let Some(end) = filter.end {
+        query = query.filter(MeasurementColumn::Timestamp.lte(end));
+    }
+    if let Some(quality) = &filter.quality {
+        query = query.filter(MeasurementColumn::Quality.eq(quality.as_str()));
+    }
+    if let Some(organization_id) = filter.organization_id {
+        let devices = tenant::organization_devices(MeasurementColumn::DeviceId, organization_id);
+        query = query.filter(devices);
+    }
+
+    let page = page.max(1);
+    let measurements = query
+        .order_by_desc(MeasurementColumn::Timestamp)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(measurements)
+}
+
+/// 按时间桶聚合的一个点
+#[derive(Debug, Clone, Serialize, ToSchema, FromQueryResult)]
+pub struct AggregatePoint {
+    /// 桶起点（Unix 秒）
+    pub bucket: i64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,
+}
+
+/// 时间戳按 `bucket_secs` 取整的表达式，各数据库写法不同
+pub fn bucket_expr(backend: DatabaseBackend, bucket_secs: i64) -> String {
+    match backend {
+        DatabaseBackend::Sqlite => {
+            format!("CAST(strftime('%s', \"timestamp\") AS INTEGER) / {0} * {0}", bucket_secs)
+        }
+        DatabaseBackend::Postgres => {
+            format!("CAST(FLOOR(EXTRACT(EPOCH FROM \"timestamp\") / {0}) AS BIGINT) * {0}", bucket_secs)
+        }
+        DatabaseBackend::MySql => {
+            format!("CAST(FLOOR(UNIX_TIMESTAMP(`timestamp`) / {0}) AS SIGNED) * {0}", bucket_secs)
+        }
+    }
+}
+
+/// 在数据库中按时间桶聚合，返回按时间升序的点
+pub async fn aggregate(
+    conn: &DatabaseConnection,
+    filter: &MeasurementFilter,
+    bucket_secs: i64,
+) -> Result<Vec<AggregatePoint>, AppError> {
+    let bucket = bucket_expr(conn.get_database_backend(), bucket_secs.max(1));
+    aggregate_by(conn, filter, bucket).await
+}
+
+/// 按给定的桶起点表达式（Unix 秒）聚合
+pub async fn aggregate_by(
+    conn: &DatabaseConnection,
+    filter: &MeasurementFilter,
+    bucket: String,
+) -> Result<Vec<AggregatePoint>, AppError> {
+    let mut query = MeasurementEntity::find()
+        .select_only()
+        .column_as(Expr::cust(bucket.clone()), "bucket")
+        .column_as(MeasurementColumn::Value.min(), "min")
+        .column_as(MeasurementColumn::Value.max(), "max")
+        .column_as(Func::avg(Expr::col(MeasurementColumn::Value)), "avg")
+        .column_as(MeasurementColumn::Id.count(), "count");
+
+    if let Some(metric_type) = &filter.metric_type {
+        query = query.filter(MeasurementColumn::MetricType.eq(metric_type.as_str()));
+    }
+    if let Some(device_id) = filter.device_id {
+        query = query.filter(MeasurementColumn::DeviceId.eq(device_id));
+    }
+    if let Some(start) = filter.start {
+        query = query.filter(MeasurementColumn::Timestamp.gte(start));
+    }
+    if let Some(end) = filter.end {
+        query = query.filter(MeasurementColumn::Timestamp.lte(end));
+    }
+    if let Some(quality) = &filter.quality {
+        query = query.filter(MeasurementColumn::Quality.eq(quality.as_str()));
+    }
+    if !filter.include_bad {
+        query = query.filter(quality::usable());
+    }
+    if let Some(organization_id) = filter.organization_id {
+        let devices = tenant::organization_devices(MeasurementColumn::DeviceId, organization_id);
+        query = query.filter(devices);
+    }
+
+    query
+        .group_by(Expr::cust(bucket.clone()))
+        .order_by_asc(Expr::cust(bucket))
+        .into_model::<AggregatePoint>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 单个序列在时间范围内的入库点，按时间升序
+///
+/// 额外带上范围前后各一个点，插值时范围两端才有依据。
+pub async fn series(
+    conn: &DatabaseConnection,
+    metric_type: &str,
+    device_id: Option<i32>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, f64)>, AppError> {
+    let device = match device_id {
+        Some(device_id) => MeasurementColumn::DeviceId.eq(device_id),
+        None => MeasurementColumn::DeviceId.is_null(),
+    };
+    let base = MeasurementEntity::find()
+        .filter(MeasurementColumn::MetricType.eq(metric_type))
+        .filter(device);
+
+    let before = base
+        .clone()
+        .filter(MeasurementColumn::Timestamp.lt(start))
+        .order_by_desc(MeasurementColumn::Timestamp)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let within = base
+        .clone()
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lte(end))
+        .order_by_asc(MeasurementColumn::Timestamp)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let after = base
+        .filter(MeasurementColumn::Timestamp.gt(end))
+        .order_by_asc(MeasurementColumn::Timestamp)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    Ok(before
+        .into_iter()
+        .chain(within)
+        .chain(after)
+        .map(|m| (m.timestamp, m.value))
+        .collect())
+}
+
+/// 获取单条测量值，指定 `metric_type` 时要求类型一致
+pub async fn get(
+    conn: &DatabaseConnection,
+    id: i32,
+    metric_type: Option<&str>,
+) -> Result<Measurement, AppError> {
+    let measurement = MeasurementEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    match metric_type {
+        Some(metric_type) if measurement.metric_type != metric_type => Err(AppError::NotFound),
+        _ => Ok(measurement),
+    }
+}
+
+/// 各设备每个指标的最新值
+///
+/// 子查询按 (设备, 指标) 取最大时间戳，再与原表连接取回整行，
+/// 由数据库完成分组，不需要把历史数据加载到内存。
+pub async fn latest_for_devices(
+    conn: &DatabaseConnection,
+    device_ids: &[i32],
+) -> Result<Vec<Measurement>, AppError> {
+    let backend = conn.get_database_backend();
+    let latest = Alias::new("latest");
+    let groups = Alias::new("g");
+    let mut result = Vec::new();
+
+    for chunk in device_ids.chunks(LATEST_CHUNK_SIZE) {
+        let group_query = Query::select()
+            .column(MeasurementColumn::DeviceId)
+            .column(MeasurementColumn::MetricType)
+            .expr_as(Expr::col(MeasurementColumn::Timestamp).max(), latest.clone())
+            .from(MeasurementEntity)
+            .and_where(Expr::col(MeasurementColumn::DeviceId).is_in(chunk.iter().copied()))
+            .group_by_col(MeasurementColumn::DeviceId)
+            .group_by_col(MeasurementColumn::MetricType)
+            .to_owned();
+
+        let query = Query::select()
+            .columns(MeasurementColumn::iter().map(|c| (MeasurementEntity, c)))
+            .from(MeasurementEntity)
+            .join_subquery(
+                JoinType::InnerJoin,
+                group_query,
+                groups.clone(),
+                Condition::all()
+                    .add(
+                        Expr::col((MeasurementEntity, MeasurementColumn::DeviceId))
+                            .equals((groups.clone(), MeasurementColumn::DeviceId)),
+                    )
+                    .add(
+                        Expr::col((MeasurementEntity, MeasurementColumn::MetricType))
+                            .equals((groups.clone(), MeasurementColumn::MetricType)),
+                    )
+                    .add(
+                        Expr::col((MeasurementEntity, MeasurementColumn::Timestamp))
+                            .equals((groups.clone(), latest.clone())),
+                    ),
+            )
+            .order_by((MeasurementEntity, MeasurementColumn::DeviceId), Order::Asc)
+            .order_by((MeasurementEntity, MeasurementColumn::MetricType), Order::Asc)
+            .order_by((MeasurementEntity, MeasurementColumn::Id), Order::Desc)
+            .to_owned();
+
+        let mut rows = MeasurementEntity::find()
+            .from_raw_sql(backend.build(&query))
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        // 同一时间戳有多条时保留最后写入的一条
+        rows.dedup_by(|a, b| a.device_id == b.device_id && a.metric_type == b.metric_type);
+        result.extend(rows);
+    }
+
+    Ok(result)
+}
+
+/// 写入测量值
+pub async fn create(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    quality_config: &QualityConfig,
+    new: NewMeasurement,
+) -> Result<Measurement, AppError> {
+    create_compressed(conn, cache, quality_config, new, 0).await
+}
+
+/// 写入经过压缩的测量值，`suppressed_count` 为此前被丢弃的样本数
+pub async fn create_compressed(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    quality_config: &QualityConfig,
+    new: NewMeasurement,
+    suppressed_count: i32,
+) -> Result<Measurement, AppError> {
+    // 有标定曲线时先把原始值换算为实际值
+    let value = calibration::apply(conn, new.device_id, &new.metric_type, new.value).await?;
+    let new = NewMeasurement { value, ..new };
+    let measurement = insert(conn, quality_config, new, suppressed_count).await?;
+    daily_summary::mark_dirty(conn, measurement.timestamp).await;
+
+    if let Some(device_id) = measurement.device_id {
+        // 液位计上报距离时同时写入推算的液位、容积和充满度
+        for derived in tank::derive(conn, &measurement).await? {
+            insert(conn, quality_config, derived, 0).await?;
+        }
+        cache.invalidate_latest(&[device_id]).await;
+    }
+
+    Ok(measurement)
+}
+
+/// 校验并插入一条测量值
+async fn insert(
+    conn: &DatabaseConnection,
+    quality_config: &QualityConfig,
+    new: NewMeasurement,
+    suppressed_count: i32,
+) -> Result<Measurement, AppError> {
+    let (value, unit) = validate(&new.metric_type, new.value, new.unit.as_deref())?;
+    // 按现场校准结果修正传感器漂移
+    let value =
+        calibration::compensate(conn, new.device_id, &new.metric_type, new.timestamp, value).await?;
+    let quality = quality::assess(
+        conn,
+        quality_config,
+        &new.metric_type,
+        new.device_id,
+        new.timestamp,
+        value,
+    )
+    .await?;
+    let now = Utc::now();
+
+    let active_model = MeasurementActiveModel {
+        metric_type: Set(new.metric_type),
+        timestamp: Set(new.timestamp),
+        value: Set(value),
+        device_id: Set(new.device_id),
+        unit: Set(unit),
+        suppressed_count: Set(suppressed_count),
+        quality: Set(quality.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    fault_injection::delay_db_commit().await;
+    MeasurementEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 修改测量值，修改前的版本保存在修正记录中
+pub async fn update(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    quality_config: &QualityConfig,
+    id: i32,
+    metric_type: Option<&str>,
+    changes: MeasurementChanges,
+    note: CorrectionNote,
+) -> Result<Measurement, AppError> {
+    let existing = get(conn, id, metric_type).await?;
+    let normalized = if changes.value.is_some() || changes.unit.is_some() {
+        let value = changes.value.unwrap_or(existing.value);
+        let unit = changes.unit.as_deref().unwrap_or(&existing.unit);
+        Some(validate(&existing.metric_type, value, Some(unit))?)
+    } else {
+        None
+    };
+
+    let quality = match changes.quality.as_deref() {
+        Some(quality) => Some(quality::parse(quality)?),
+        None => match &normalized {
+            Some((value, _)) => Some(
+                quality::assess(
+                    conn,
+                    quality_config,
+                    &existing.metric_type,
+                    changes.device_id.unwrap_or(existing.device_id),
+                    changes.timestamp.unwrap_or(existing.timestamp),
+                    *value,
+                )
+                .await?,
+            ),
+            None => None,
+        },
+    };
+
+    let previous = existing.clone();
+    let previous_device_id = existing.device_id;
+    let previous_timestamp = existing.timestamp;
+    let mut active_model = existing.into_active_model();
+
+    if let Some(timestamp) = changes.timestamp {
+        active_model.timestamp = Set(timestamp);
+    }
+    if let Some((value, unit)) = normalized {
+        active_model.value = Set(value);
+        active_model.unit = Set(unit);
+    }
+    if let Some(device_id) = changes.device_id {
+        active_model.device_id = Set(device_id);
+    }
+    if let Some(quality) = quality {
+        active_model.quality = Set(quality.to_string());
+    }
+    active_model.updated_at = Set(Utc::now());
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    let measurement = MeasurementEntity::update(active_model)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    data_correction::record(
+        &txn,
+        RECORD_MEASUREMENT,
+        measurement.id,
+        ACTION_UPDATE,
+        &previous,
+        Some(&measurement),
+        note,
+    )
+    .await?;
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    daily_summary::mark_dirty(conn, previous_timestamp).await;
+    if measurement.timestamp != previous_timestamp {
+        daily_summary::mark_dirty(conn, measurement.timestamp).await;
+    }
+
+    let device_ids: Vec<i32> = previous_device_id.into_iter().chain(measurement.device_id).collect();
+    cache.invalidate_latest(&device_ids).await;
+
+    Ok(measurement)
+}
+
+/// 删除测量值，删除前的版本保存在修正记录中
+pub async fn delete(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    id: i32,
+    metric_type: Option<&str>,
+    note: CorrectionNote,
+) -> Result<(), AppError> {
+    let measurement = get(conn, id, metric_type).await?;
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    MeasurementEntity::delete_by_id(measurement.id)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    data_correction::record::<_, Measurement>(
+        &txn,
+        RECORD_MEASUREMENT,
+        measurement.id,
+        ACTION_DELETE,
+        &measurement,
+        None,
+        note,
+    )
+    .await?;
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+    daily_summary::mark_dirty(conn, measurement.timestamp).await;
+
+    if let Some(device_id) = measurement.device_id {
+        cache.invalidate_latest(&[device_id]).await;
+    }
+
+    Ok(())
+}
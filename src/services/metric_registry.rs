@@ -0,0 +1,136 @@
+//! 指标元数据注册表
+//!
+//! 新增传感器类型只需要在 `METRICS` 中登记一项，即可通过 `/measurements?metric=<key>` 读写。
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 指标元数据
+#[derive(Debug, Clone, Copy)]
+pub struct MetricInfo {
+    pub key: &'static str,  // 指标标识，对应 measurements.metric_type
+    pub name: &'static str, // 显示名称
+    pub unit: &'static str, // 默认单位
+    pub min: f64,           // 有效范围下限
+    pub max: f64,           // 有效范围上限
+    pub aliases: &'static [(&'static str, f64)], // 可接受的其他单位及换算到默认单位的系数
+}
+
+impl MetricInfo {
+    pub fn in_range(&self, value: f64) -> bool {
+        value.is_finite() && value >= self.min && value <= self.max
+    }
+
+    /// 把以 `unit` 表示的值换算为默认单位
+    ///
+    /// 未登记其他单位的指标不做换算，原样接受；登记了的指标只接受默认单位和登记的单位。
+    pub fn to_default_unit(&self, value: f64, unit: &str) -> Option<f64> {
+        if unit == self.unit || self.aliases.is_empty() {
+            return Some(value);
+        }
+        self.aliases.iter().find(|(alias, _)| *alias == unit).map(|(_, factor)| value * factor)
+    }
+}
+
+pub const PH: &str = "ph";
+pub const TDS: &str = "tds";
+pub const TURBIDITY: &str = "turbidity";
+pub const FLOW: &str = "flow";
+pub const FLOW_TOTAL: &str = "flow_total";
+pub const TEMPERATURE: &str = "temperature";
+pub const PRESSURE: &str = "pressure";
+pub const DISTANCE: &str = "distance";
+pub const LEVEL: &str = "level";
+pub const VOLUME: &str = "volume";
+pub const PERCENT_FULL: &str = "percent_full";
+pub const POWER: &str = "power";
+pub const PUMP_EFFICIENCY: &str = "pump_efficiency";
+pub const VIBRATION_RMS: &str = "vibration_rms";
+pub const ENERGY: &str = "energy";
+pub const DISSOLVED_OXYGEN: &str = "do";
+pub const DO_SATURATION: &str = "do_saturation";
+pub const ORP: &str = "orp";
+
+pub const METRICS: &[MetricInfo] = &[
+    MetricInfo { key: PH, name: "PH值", unit: "pH", min: 0.0, max: 14.0, aliases: &[] },
+    MetricInfo { key: TDS, name: "TDS值", unit: "ppm", min: 0.0, max: 10000.0, aliases: &[] },
+    MetricInfo { key: TURBIDITY, name: "浊度", unit: "NTU", min: 0.0, max: 4000.0, aliases: &[] },
+    MetricInfo { key: FLOW, name: "流量", unit: "m³/h", min: 0.0, max: 100000.0, aliases: &[] },
+    MetricInfo { key: FLOW_TOTAL, name: "累计流量", unit: "m³", min: 0.0, max: 1.0e12, aliases: &[("L", 0.001)] },
+    MetricInfo { key: TEMPERATURE, name: "温度", unit: "°C", min: -50.0, max: 200.0, aliases: &[] },
+    MetricInfo {
+        key: PRESSURE,
+        name: "压力",
+        unit: "MPa",
+        min: 0.0,
+        max: 100.0,
+        aliases: &[("kPa", 0.001), ("bar", 0.1), ("psi", 0.006894757)],
+    },
+    MetricInfo { key: DISTANCE, name: "液面距离", unit: "m", min: 0.0, max: 100.0, aliases: &[] },
+    MetricInfo { key: LEVEL, name: "液位", unit: "m", min: 0.0, max: 100.0, aliases: &[("cm", 0.01), ("mm", 0.001)] },
+    MetricInfo { key: VOLUME, name: "容积", unit: "m³", min: 0.0, max: 1000000.0, aliases: &[] },
+    MetricInfo { key: PERCENT_FULL, name: "充满度", unit: "%", min: 0.0, max: 100.0, aliases: &[] },
+    MetricInfo { key: POWER, name: "功率", unit: "kW", min: 0.0, max: 100000.0, aliases: &[] },
+    MetricInfo { key: PUMP_EFFICIENCY, name: "水泵效率", unit: "%", min: 0.0, max: 100.0, aliases: &[] },
+    MetricInfo { key: VIBRATION_RMS, name: "振动烈度", unit: "mm/s", min: 0.0, max: 1000.0, aliases: &[] },
+    MetricInfo { key: ENERGY, name: "电能表读数", unit: "kWh", min: 0.0, max: 1.0e12, aliases: &[] },
+    MetricInfo {
+        key: DISSOLVED_OXYGEN,
+        name: "溶解氧",
+        unit: "mg/L",
+        min: 0.0,
+        max: 20.0,
+        aliases: &[("ppm", 1.0), ("µg/L", 0.001), ("ug/L", 0.001)],
+    },
+    MetricInfo { key: DO_SATURATION, name: "溶解氧饱和度", unit: "%", min: 0.0, max: 300.0, aliases: &[] },
+    MetricInfo { key: ORP, name: "氧化还原电位", unit: "mV", min: -2000.0, max: 2000.0, aliases: &[("V", 1000.0)] },
+];
+
+/// 按标识查找指标
+pub fn lookup(key: &str) -> Option<&'static MetricInfo> {
+    METRICS.iter().find(|m| m.key == key)
+}
+
+/// 对外展示的指标元数据
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetricTypeResponse {
+    pub key: String,
+    pub name: String,
+    pub unit: String,
+    pub min: f64,
+    pub max: f64,
+    /// 上报时还可使用的其他单位，入库前换算为默认单位
+    pub aliases: Vec<String>,
+}
+
+impl From<&MetricInfo> for MetricTypeResponse {
+    fn from(info: &MetricInfo) -> Self {
+        Self {
+            key: info.key.to_string(),
+            name: info.name.to_string(),
+            unit: info.unit.to_string(),
+            min: info.min,
+            max: info.max,
+            aliases: info.aliases.iter().map(|(unit, _)| unit.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_default_unit() {
+        let orp = lookup(ORP).unwrap();
+        assert_eq!(orp.to_default_unit(0.25, "V"), Some(250.0));
+        assert_eq!(orp.to_default_unit(250.0, "mV"), Some(250.0));
+        assert_eq!(orp.to_default_unit(250.0, "mmHg"), None);
+        assert!(orp.in_range(-400.0));
+
+        let oxygen = lookup(DISSOLVED_OXYGEN).unwrap();
+        assert_eq!(oxygen.to_default_unit(2500.0, "ug/L"), Some(2.5));
+        // 未登记其他单位的指标原样接受
+        assert_eq!(lookup(FLOW).unwrap().to_default_unit(12.0, "L/s"), Some(12.0));
+    }
+}
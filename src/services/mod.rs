@@ -0,0 +1,68 @@
+//! 业务服务层
+//!
+//! REST 接口与后台任务共用的业务逻辑
+
+pub mod api_key;
+pub mod config_bundle;
+pub mod config_revision;
+pub mod latest;
+pub mod measurement;
+pub mod metric_registry;
+pub mod provisioning;
+pub mod remote_access;
+pub mod site;
+pub mod cache;
+pub mod read_only;
+pub mod serial_console;
+pub mod device_command;
+pub mod calibration;
+pub mod alarm;
+pub mod tank;
+pub mod pump;
+pub mod vibration;
+pub mod device_state;
+pub mod daily_summary;
+pub mod pwm;
+pub mod query_guard;
+pub mod binary_ingest;
+pub mod compression;
+pub mod network;
+pub mod system;
+pub mod modbus_write;
+pub mod retention;
+pub mod modbus_mapping;
+pub mod calendar;
+pub mod pi_export;
+pub mod modbus_server;
+pub mod grafana;
+pub mod alarm_forward;
+pub mod alarm_shelving;
+pub mod alarm_kpi;
+pub mod event_relay;
+pub mod alarm_snapshot;
+pub mod trend;
+pub mod influxdb;
+pub mod timeseries;
+pub mod api_usage;
+pub mod rollup;
+pub mod kpi_expr;
+pub mod kpi;
+pub mod gpio_output;
+pub mod reconcile;
+pub mod report;
+pub mod report_render;
+pub mod permit;
+pub mod safe_state;
+pub mod energy;
+pub mod dosing;
+pub mod fault_injection;
+pub mod maintenance;
+pub mod run_hours;
+pub mod pump_group;
+pub mod alarm_template;
+pub mod flow_total;
+pub mod quality;
+pub mod data_correction;
+pub mod lab_result;
+pub mod automation;
+pub mod simulation;
\ No newline at end of file
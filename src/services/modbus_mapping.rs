@@ -0,0 +1,114 @@
+//! Modbus 寄存器映射
+//!
+//! 每台设备的寄存器定义（地址、功能码、数据类型、字节序、比例、目标指标）保存在数据库中，
+//! 新型号仪表可以在界面上配置接入，不需要重新编译。
+
+use crate::models::modbus_mapping::{
+    Column as MappingColumn, Entity as MappingEntity, Model as ModbusMapping, BIT_DATA_TYPE,
+    FUNCTION_CODES,
+};
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use crate::utils::modbus::{ByteOrder, DataType};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+
+/// 校验映射定义
+pub fn validate(mapping: &ModbusMapping) -> Result<(), AppError> {
+    let invalid =
+        |msg: String| Err(AppError::InvalidInput(format!("寄存器映射无效: {}", msg).into()));
+
+    if mapping.name.trim().is_empty() {
+        return invalid("名称不能为空".to_string());
+    }
+    if !FUNCTION_CODES.contains(&mapping.function_code) {
+        return invalid(format!("不支持的功能码 {}", mapping.function_code));
+    }
+    // 线圈和离散输入按位读取，只能是 bool；寄存器按解码时使用的 DataType 解析
+    let count = if mapping.function_code <= 2 {
+        if mapping.data_type != BIT_DATA_TYPE {
+            return invalid("功能码 1/2 只能使用 bool 类型".to_string());
+        }
+        1
+    } else {
+        match DataType::parse(&mapping.data_type) {
+            Some(data_type) => data_type.register_count(),
+            None if mapping.data_type == BIT_DATA_TYPE => {
+                return invalid("功能码 3/4 不能使用 bool 类型".to_string());
+            }
+            None => return invalid(format!("未知的数据类型 {}", mapping.data_type)),
+        }
+    };
+    if ByteOrder::parse(&mapping.byte_order).is_none() {
+        return invalid(format!("未知的字节序 {}", mapping.byte_order));
+    }
+    let last = mapping.address as i64 + count as i64 - 1;
+    if mapping.address < 0 || last > u16::MAX as i64 {
+        return invalid(format!("地址 {} 超出范围", mapping.address));
+    }
+    if !mapping.scale.is_finite() || mapping.scale == 0.0 || !mapping.offset.is_finite() {
+        return invalid("比例系数和偏移必须是有限数值，比例系数不能为 0".to_string());
+    }
+    if metric_registry::lookup(&mapping.metric_type).is_none() {
+        return invalid(format!("未知的指标类型 {}", mapping.metric_type));
+    }
+    Ok(())
+}
+
+/// 设备启用的映射，按功能码、地址排序
+pub async fn for_device(
+    conn: &DatabaseConnection,
+    device_id: i32,
+) -> Result<Vec<ModbusMapping>, AppError> {
+    MappingEntity::find()
+        .filter(MappingColumn::DeviceId.eq(device_id))
+        .filter(MappingColumn::Enabled.eq(true))
+        .order_by_asc(MappingColumn::FunctionCode)
+        .order_by_asc(MappingColumn::Address)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn mapping() -> ModbusMapping {
+        ModbusMapping {
+            id: 1,
+            device_id: 1,
+            name: "累计流量".to_string(),
+            function_code: 3,
+            address: 100,
+            data_type: "f32".to_string(),
+            byte_order: "CDAB".to_string(),
+            scale: 1.0,
+            offset: 0.0,
+            metric_type: "flow".to_string(),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&mapping()).is_ok());
+        assert!(validate(&ModbusMapping { function_code: 5, ..mapping() }).is_err());
+        assert!(validate(&ModbusMapping { data_type: "f16".to_string(), ..mapping() }).is_err());
+        assert!(validate(&ModbusMapping { function_code: 1, ..mapping() }).is_err());
+        assert!(validate(&ModbusMapping { byte_order: "ACBD".to_string(), ..mapping() }).is_err());
+        assert!(validate(&ModbusMapping { address: 65535, ..mapping() }).is_err());
+        assert!(validate(&ModbusMapping { scale: 0.0, ..mapping() }).is_err());
+        let unknown = ModbusMapping { metric_type: "unknown".to_string(), ..mapping() };
+        assert!(validate(&unknown).is_err());
+        let coil = ModbusMapping { function_code: 1, data_type: "bool".to_string(), ..mapping() };
+        assert!(validate(&coil).is_ok());
+        assert!(validate(&ModbusMapping { data_type: "bool".to_string(), ..mapping() }).is_err());
+        // 与解码使用同一套解析，大小写不敏感
+        let lower =
+            ModbusMapping { data_type: "F32".to_string(), byte_order: "cdab".to_string(), ..mapping() };
+        assert!(validate(&lower).is_ok());
+    }
+}
@@ -0,0 +1,430 @@
+//! Modbus 从站映射
+//!
+//! 把设备最新值按配置写入 [`ModbusServer`] 的输入寄存器，设备运行/通信状态和测量值的
+//! 质量位写入离散输入，上位机写入保持寄存器的设定值换算为工程值后经 [`CommandTracker`] 下发，
+//! 迁移期间现有 SCADA 可以像轮询 PLC 一样轮询本系统。测量值超过 `max_age_secs` 时
+//! 寄存器保持最后的有效值，质量位清零。
+
+use crate::config::modbus::{ModbusServerConfig, ServerPoint, ServerSetpoint};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity};
+use crate::models::device_state_event::CATEGORY_CONNECTIVITY;
+use crate::mqtt::rumqtt::MqttManager;
+use crate::services::cache::HotCache;
+use crate::services::latest::{self, DeviceLatest};
+use crate::services::device_command::CommandTracker;
+use crate::services::{device_state, metric_registry};
+use crate::utils::error::AppError;
+use crate::utils::modbus::{self, ByteOrder, DataType, ModbusServer, RegisterWrite};
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 解析后的寄存器格式
+#[derive(Debug, Clone, Copy)]
+struct Format {
+    data_type: DataType,
+    order: ByteOrder,
+    count: u16,
+}
+
+fn format(address: u16, data_type: &str, byte_order: &str) -> Result<Format, String> {
+    let data_type = DataType::parse(data_type)
+        .ok_or_else(|| format!("寄存器 {}: 未知的数据类型 {}", address, data_type))?;
+    let order = ByteOrder::parse(byte_order)
+        .ok_or_else(|| format!("寄存器 {}: 未知的字节序 {}", address, byte_order))?;
+    Ok(Format { data_type, order, count: data_type.register_count() })
+}
+
+/// 校验配置：数据类型、字节序、指标及地址不重叠，返回各点的格式
+fn validate(config: &ModbusServerConfig) -> Result<(Vec<Format>, Vec<Format>), String> {
+    fn occupy(used: &mut HashSet<u16>, address: u16, count: u16) -> Result<(), String> {
+        for offset in 0..count {
+            let register = address
+                .checked_add(offset)
+                .ok_or_else(|| format!("寄存器 {} 超出地址范围", address))?;
+            if !used.insert(register) {
+                return Err(format!("寄存器 {} 重复映射", register));
+            }
+        }
+        Ok(())
+    }
+    let check_metric = |metric: &str| match metric_registry::lookup(metric) {
+        Some(_) => Ok(()),
+        None => Err(format!("未知的指标类型 {}", metric)),
+    };
+
+    let mut used = HashSet::new();
+    let mut points = Vec::with_capacity(config.input_registers.len());
+    for point in &config.input_registers {
+        check_metric(&point.metric_type)?;
+        let format = format(point.address, &point.data_type, &point.byte_order)?;
+        occupy(&mut used, point.address, format.count)?;
+        points.push(format);
+    }
+
+    let mut used = HashSet::new();
+    let mut setpoints = Vec::with_capacity(config.setpoints.len());
+    for setpoint in &config.setpoints {
+        if let Some(metric) = &setpoint.feedback_metric {
+            check_metric(metric)?;
+        }
+        if setpoint.scale == 0.0 || !setpoint.scale.is_finite() {
+            return Err(format!("寄存器 {}: 比例系数无效", setpoint.address));
+        }
+        let format = format(setpoint.address, &setpoint.data_type, &setpoint.byte_order)?;
+        occupy(&mut used, setpoint.address, format.count)?;
+        setpoints.push(format);
+    }
+
+    let mut used = HashSet::new();
+    let quality = config.input_registers.iter().filter_map(|p| p.quality_address);
+    for address in quality.chain(config.discrete_inputs.iter().map(|s| s.address)) {
+        if !used.insert(address) {
+            return Err(format!("离散输入 {} 重复映射", address));
+        }
+    }
+    for status in &config.discrete_inputs {
+        if device_state::category_of(&status.state).is_none() {
+            let (address, state) = (status.address, &status.state);
+            return Err(format!("离散输入 {}: 未知的设备状态 {}", address, state));
+        }
+    }
+    Ok((points, setpoints))
+}
+
+fn latest_value(latest: &[DeviceLatest], device_id: i32, metric_type: &str) -> Option<f64> {
+    latest
+        .iter()
+        .find(|l| l.device_id == device_id)
+        .and_then(|l| l.values.get(metric_type))
+        .map(|v| v.value)
+}
+
+/// 输入寄存器的工程值，没有值或已失效时返回 None
+fn point_value(latest: &[DeviceLatest], point: &ServerPoint, now: DateTime<Utc>) -> Option<f64> {
+    let value = latest
+        .iter()
+        .find(|l| l.device_id == point.device_id)
+        .and_then(|l| l.values.get(&point.metric_type))?;
+    let expired = point
+        .max_age_secs
+        .is_some_and(|max_age| (now - value.timestamp).num_seconds() > max_age as i64);
+    (!expired).then_some(value.value)
+}
+
+/// 一次刷新用到的数据
+struct Snapshot {
+    latest: Vec<DeviceLatest>,
+    /// 设备当前的运行状态和通信状态
+    states: HashMap<i32, Vec<String>>,
+}
+
+/// 把设定值寄存器换算为工程值并检查范围
+fn setpoint_value(
+    setpoint: &ServerSetpoint,
+    format: Format,
+    registers: &[u16],
+) -> Result<f64, String> {
+    let raw = modbus::decode(registers, format.data_type, format.order)
+        .ok_or_else(|| "寄存器数量不符".to_string())?;
+    let value = raw / setpoint.scale;
+    if !value.is_finite() {
+        return Err(format!("设定值无效: {}", value));
+    }
+    let below = setpoint.min.is_some_and(|min| value < min);
+    let above = setpoint.max.is_some_and(|max| value > max);
+    if below || above {
+        return Err(format!(
+            "设定值 {} 超出范围 [{:?}, {:?}]",
+            value, setpoint.min, setpoint.max
+        ));
+    }
+    Ok(value)
+}
+
+async fn load_snapshot(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    config: &ModbusServerConfig,
+    device_ids: &BTreeSet<i32>,
+) -> Result<Snapshot, AppError> {
+    let devices = DeviceEntity::find()
+        .filter(DeviceColumn::Id.is_in(device_ids.iter().copied()))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let latest = latest::snapshot(conn, cache, &devices).await?;
+
+    // 运行状态与 devices.status 同步，通信状态只有配置了才查询事件
+    let mut states: HashMap<i32, Vec<String>> = HashMap::new();
+    for device in &devices {
+        if let Some(state) = device_state::state_for_status(device.status) {
+            states.entry(device.id).or_default().push(state.to_string());
+        }
+    }
+    let connectivity: BTreeSet<i32> = config
+        .discrete_inputs
+        .iter()
+        .filter(|s| device_state::category_of(&s.state) == Some(CATEGORY_CONNECTIVITY))
+        .map(|s| s.device_id)
+        .collect();
+    for device_id in connectivity {
+        if let Some(state) = device_state::current(conn, device_id, CATEGORY_CONNECTIVITY).await? {
+            states.entry(device_id).or_default().push(state);
+        }
+    }
+    Ok(Snapshot { latest, states })
+}
+
+fn refresh(
+    server: &ModbusServer,
+    config: &ModbusServerConfig,
+    formats: &(Vec<Format>, Vec<Format>),
+    snapshot: &Snapshot,
+) {
+    let latest = &snapshot.latest;
+    let now = Utc::now();
+    let encode = |address: u16, value: f64, format: Format| {
+        let registers = modbus::encode(value, format.data_type, format.order);
+        if registers.is_none() {
+            warn!("Modbus server register {}: value {} out of range", address, value);
+        }
+        registers
+    };
+    for (point, format) in config.input_registers.iter().zip(&formats.0) {
+        let registers = point_value(latest, point, now)
+            .and_then(|value| encode(point.address, value * point.scale, *format));
+        if let Some(registers) = &registers {
+            server.set_input(point.address, registers);
+        }
+        if let Some(address) = point.quality_address {
+            server.set_discrete(address, &[registers.is_some()]);
+        }
+    }
+    for status in &config.discrete_inputs {
+        let active = snapshot
+            .states
+            .get(&status.device_id)
+            .is_some_and(|states| states.contains(&status.state));
+        server.set_discrete(status.address, &[active]);
+    }
+    for (setpoint, format) in config.setpoints.iter().zip(&formats.1) {
+        let Some(metric) = &setpoint.feedback_metric else {
+            continue;
+        };
+        let Some(value) = latest_value(latest, setpoint.device_id, metric) else {
+            continue;
+        };
+        if let Some(registers) = encode(setpoint.address, value * setpoint.scale, *format) {
+            server.set_holding(setpoint.address, &registers);
+        }
+    }
+}
+
+/// 处理一次写入：写入范围覆盖的设定值各下发一条命令
+async fn apply_write(
+    conn: &DatabaseConnection,
+    mqtt: Option<&MqttManager>,
+    commands: &CommandTracker,
+    server: &ModbusServer,
+    config: &ModbusServerConfig,
+    formats: &[Format],
+    write: &RegisterWrite,
+) {
+    let written = write.address as u32..write.address as u32 + write.values.len() as u32;
+    for (setpoint, format) in config.setpoints.iter().zip(formats) {
+        let start = setpoint.address as u32;
+        if start >= written.end || start + format.count as u32 <= written.start {
+            continue;
+        }
+        // 多寄存器的设定值只写了一部分时，与寄存器中其余部分拼接
+        let Some(registers) = server.holding(setpoint.address, format.count) else {
+            continue;
+        };
+        let value = match setpoint_value(setpoint, *format, &registers) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Modbus server setpoint {} rejected: {}", setpoint.address, e);
+                continue;
+            }
+        };
+        let payload = json!({ "value": value, "source": "modbus_server" });
+        let sent = commands.send(conn, mqtt, setpoint.device_id, &setpoint.command, payload).await;
+        match sent {
+            Ok(command) => info!(
+                "Modbus setpoint {} -> device {} {} {} ({})",
+                setpoint.address, command.device_id, command.command, value, command.correlation_id
+            ),
+            Err(e) => warn!("Modbus server setpoint {} not sent: {:?}", setpoint.address, e),
+        }
+    }
+}
+
+/// 后台任务：启动从站并定期刷新寄存器
+pub async fn run(
+    config: ModbusServerConfig,
+    db: DbManager,
+    cache: HotCache,
+    mqtt: Option<MqttManager>,
+    commands: CommandTracker,
+) {
+    let formats = match validate(&config) {
+        Ok(formats) => formats,
+        Err(e) => {
+            error!("Invalid Modbus server config: {}", e);
+            return;
+        }
+    };
+    let listen: SocketAddr = match config.listen.parse() {
+        Ok(listen) => listen,
+        Err(e) => {
+            error!("Invalid Modbus server listen address {}: {}", config.listen, e);
+            return;
+        }
+    };
+
+    let writable: HashSet<u16> = config
+        .setpoints
+        .iter()
+        .zip(&formats.1)
+        .flat_map(|(s, f)| (0..f.count).map(move |offset| s.address + offset))
+        .collect();
+    let (server, mut writes) = ModbusServer::new(writable);
+    // 设定值寄存器初始为 0，有回读指标的随刷新更新
+    for (setpoint, format) in config.setpoints.iter().zip(&formats.1) {
+        server.set_holding(setpoint.address, &vec![0; format.count as usize]);
+    }
+    let quality = config.input_registers.iter().filter_map(|p| p.quality_address);
+    for address in quality.chain(config.discrete_inputs.iter().map(|s| s.address)) {
+        server.set_discrete(address, &[false]);
+    }
+
+    let serving = server.clone();
+    tokio::spawn(async move {
+        info!("Modbus server listening on {}", listen);
+        if let Err(e) = serving.serve_tcp(listen).await {
+            error!("Modbus server stopped: {}", e);
+        }
+    });
+
+    let device_ids: BTreeSet<i32> = config
+        .input_registers
+        .iter()
+        .map(|p| p.device_id)
+        .chain(config.setpoints.iter().map(|s| s.device_id))
+        .chain(config.discrete_inputs.iter().map(|s| s.device_id))
+        .collect();
+    let conn = db.get_connection();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.refresh_secs.max(1)));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => match load_snapshot(conn, &cache, &config, &device_ids).await {
+                Ok(snapshot) => refresh(&server, &config, &formats, &snapshot),
+                Err(e) => warn!("Failed to refresh Modbus server registers: {:?}", e),
+            },
+            Some(write) = writes.recv() => {
+                let mqtt = mqtt.as_ref();
+                apply_write(conn, mqtt, &commands, &server, &config, &formats.1, &write).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::modbus::ServerStatus;
+    use crate::services::latest::LatestValue;
+    use chrono::Duration;
+
+    fn setpoint(address: u16, data_type: &str) -> ServerSetpoint {
+        ServerSetpoint {
+            address,
+            device_id: 1,
+            command: "set_speed".to_string(),
+            data_type: data_type.to_string(),
+            byte_order: "ABCD".to_string(),
+            scale: 10.0,
+            min: Some(0.0),
+            max: Some(50.0),
+            feedback_metric: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_overlap() {
+        let mut config = ModbusServerConfig {
+            setpoints: vec![setpoint(0, "f32"), setpoint(2, "u16")],
+            ..Default::default()
+        };
+        assert!(validate(&config).is_ok());
+        config.setpoints.push(setpoint(1, "u16"));
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_validate_discrete_inputs() {
+        let status = |address: u16, state: &str| ServerStatus {
+            address,
+            device_id: 1,
+            state: state.to_string(),
+        };
+        let mut config = ModbusServerConfig {
+            discrete_inputs: vec![status(0, "running"), status(1, "online")],
+            ..Default::default()
+        };
+        assert!(validate(&config).is_ok());
+        config.discrete_inputs.push(status(2, "open"));
+        assert!(validate(&config).is_err());
+        config.discrete_inputs[2] = status(1, "fault");
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_point_value_expires() {
+        let now = Utc::now();
+        let latest = vec![DeviceLatest {
+            device_id: 1,
+            device_name: "1号泵".to_string(),
+            values: [(
+                "flow".to_string(),
+                LatestValue {
+                    value: 12.5,
+                    unit: String::new(),
+                    timestamp: now - Duration::seconds(90),
+                },
+            )]
+            .into(),
+        }];
+        let mut point = ServerPoint {
+            address: 0,
+            device_id: 1,
+            metric_type: "flow".to_string(),
+            data_type: "f32".to_string(),
+            byte_order: "ABCD".to_string(),
+            scale: 1.0,
+            max_age_secs: None,
+            quality_address: Some(0),
+        };
+        assert_eq!(point_value(&latest, &point, now), Some(12.5));
+        point.max_age_secs = Some(60);
+        assert_eq!(point_value(&latest, &point, now), None);
+        point.metric_type = "ph".to_string();
+        point.max_age_secs = None;
+        assert_eq!(point_value(&latest, &point, now), None);
+    }
+
+    #[test]
+    fn test_setpoint_value() {
+        let sp = setpoint(0, "u16");
+        let format = format(0, "u16", "ABCD").unwrap();
+        assert_eq!(setpoint_value(&sp, format, &[455]), Ok(45.5));
+        assert!(setpoint_value(&sp, format, &[600]).is_err());
+    }
+}
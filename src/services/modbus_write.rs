@@ -0,0 +1,291 @@
+//! Modbus 写入安全检查
+//!
+//! 自动化和人工的写入都经过这里：只允许写配置中列出的寄存器，数值截断到安全范围，并检查联锁条件。
+//! 人工写入注明原因后可以越过联锁，但不能越过白名单和范围限制。每次写入请求无论结果都记录到 `modbus_writes`。
+
+use crate::config::modbus::{Interlock, ModbusConfig, RegisterKind, WritableRegister};
+use crate::models::modbus_write::{
+    ActiveModel as ModbusWriteActiveModel, Column as ModbusWriteColumn, Entity as ModbusWriteEntity,
+    Model as ModbusWrite, STATUS_BLOCKED, STATUS_FAILED, STATUS_WRITTEN,
+};
+use crate::services::cache::HotCache;
+use crate::services::latest::{self, LatestValue};
+use crate::utils::error::AppError;
+use crate::utils::modbus::ModbusClient;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 写入来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteSource {
+    Api,
+    Automation,
+}
+
+impl WriteSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WriteSource::Api => "api",
+            WriteSource::Automation => "automation",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WriteRequest {
+    pub device_id: i32,
+    pub kind: RegisterKind,
+    pub address: u16,
+    /// 工程值，线圈非 0 即为 1
+    pub value: f64,
+    pub source: WriteSource,
+    pub operator: Option<String>,
+    pub override_interlocks: bool,
+    pub reason: Option<String>,
+}
+
+/// 截断到寄存器的安全范围，线圈归一为 0/1
+fn clamp(register: &WritableRegister, value: f64) -> f64 {
+    if register.kind == RegisterKind::Coil {
+        return if value != 0.0 { 1.0 } else { 0.0 };
+    }
+    let value = register.min.map_or(value, |min| value.max(min));
+    register.max.map_or(value, |max| value.min(max))
+}
+
+/// 工程值换算为寄存器原始值
+fn raw_value(register: &WritableRegister, value: f64) -> Result<u16, String> {
+    if register.scale == 0.0 || !register.scale.is_finite() {
+        return Err(format!("寄存器 {} 的比例系数无效", register.address));
+    }
+    let raw = (value / register.scale).round();
+    if !(0.0..=u16::MAX as f64).contains(&raw) {
+        return Err(format!("原始值 {} 超出寄存器范围", raw));
+    }
+    Ok(raw as u16)
+}
+
+/// 检查一条联锁，`latest` 为联锁设备该指标的最新值
+fn check_interlock(
+    interlock: &Interlock,
+    value: f64,
+    latest: Option<&LatestValue>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    if interlock.when_value.is_some_and(|when| when != value) {
+        return Ok(());
+    }
+    let name = if interlock.description.is_empty() {
+        format!("设备 {} 的 {}", interlock.device_id, interlock.metric_type)
+    } else {
+        interlock.description.clone()
+    };
+    let latest = latest.ok_or_else(|| format!("联锁不满足: {}（无最新值）", name))?;
+    if (now - latest.timestamp).num_seconds() > interlock.max_age_secs as i64 {
+        return Err(format!("联锁不满足: {}（最新值已过期）", name));
+    }
+    let below = interlock.min.is_some_and(|min| latest.value < min);
+    let above = interlock.max.is_some_and(|max| latest.value > max);
+    if below || above {
+        return Err(format!("联锁不满足: {}（当前值 {}）", name, latest.value));
+    }
+    Ok(())
+}
+
+async fn record(
+    conn: &DatabaseConnection,
+    request: &WriteRequest,
+    written_value: Option<f64>,
+    status: &str,
+    message: Option<String>,
+) -> Result<ModbusWrite, AppError> {
+    ModbusWriteActiveModel {
+        device_id: Set(request.device_id),
+        kind: Set(request.kind.as_str().to_string()),
+        address: Set(request.address as i32),
+        requested_value: Set(request.value),
+        written_value: Set(written_value),
+        source: Set(request.source.as_str().to_string()),
+        operator: Set(request.operator.clone()),
+        override_interlocks: Set(request.override_interlocks),
+        reason: Set(request.reason.clone()),
+        status: Set(status.to_string()),
+        message: Set(message),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(|_| AppError::InternalError)
+}
+
+/// 拒绝写入并记录
+async fn block(
+    conn: &DatabaseConnection,
+    request: &WriteRequest,
+    message: String,
+) -> Result<ModbusWrite, AppError> {
+    warn!("Modbus write to device {} blocked: {}", request.device_id, message);
+    record(conn, request, None, STATUS_BLOCKED, Some(message.clone())).await?;
+    Err(AppError::Unprocessable(message.into()))
+}
+
+/// 经过安全检查后写入寄存器，返回审计记录
+pub async fn write(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    config: &ModbusConfig,
+    request: WriteRequest,
+) -> Result<ModbusWrite, AppError> {
+    if !config.enabled {
+        return Err(AppError::ServiceUnavailable("Modbus 未启用".into()));
+    }
+    if !request.value.is_finite() {
+        return Err(AppError::InvalidInput("写入值无效".into()));
+    }
+    let has_reason = request.reason.as_deref().is_some_and(|r| !r.trim().is_empty());
+    if request.override_interlocks && !has_reason {
+        return Err(AppError::InvalidInput("越过联锁必须填写原因".into()));
+    }
+
+    let Some(device) = config.device(request.device_id) else {
+        return block(conn, &request, "设备未配置 Modbus".to_string()).await;
+    };
+    let Some(register) = device.writable(request.kind, request.address) else {
+        let message = format!("寄存器 {} {} 不允许写入", request.kind.as_str(), request.address);
+        return block(conn, &request, message).await;
+    };
+
+    let value = clamp(register, request.value);
+    let raw = match raw_value(register, value) {
+        Ok(raw) => raw,
+        Err(message) => return block(conn, &request, message).await,
+    };
+
+    if !request.override_interlocks {
+        let now = Utc::now();
+        for interlock in &register.interlocks {
+            let latest = match latest::for_device(conn, cache, interlock.device_id).await {
+                Ok(latest) => latest.values.get(&interlock.metric_type).cloned(),
+                Err(AppError::NotFound) => None,
+                Err(e) => return Err(e),
+            };
+            if let Err(message) = check_interlock(interlock, value, latest.as_ref(), now) {
+                return block(conn, &request, message).await;
+            }
+        }
+    }
+
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let result = async {
+        let mut client = ModbusClient::connect(&device.target, device.unit_id, timeout).await?;
+        match register.kind {
+            RegisterKind::Holding => client.write_single_register(register.address, raw).await,
+            RegisterKind::Coil => client.write_single_coil(register.address, raw != 0).await,
+        }
+    }
+    .await;
+
+    if let Err(e) = result {
+        warn!("Modbus write to device {} failed: {}", request.device_id, e);
+        record(conn, &request, Some(value), STATUS_FAILED, Some(e.to_string())).await?;
+        return Err(AppError::ServiceUnavailable("Modbus 写入失败".into()));
+    }
+
+    info!(
+        "Modbus write device {} {} {} = {} ({})",
+        request.device_id,
+        register.kind.as_str(),
+        register.address,
+        value,
+        request.source.as_str()
+    );
+    let message = (value != request.value).then(|| format!("已截断到 {}", value));
+    record(conn, &request, Some(value), STATUS_WRITTEN, message).await
+}
+
+/// 设备的写入记录，最新的在前
+pub async fn list(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<ModbusWrite>, AppError> {
+    let page = page.max(1);
+    ModbusWriteEntity::find()
+        .filter(ModbusWriteColumn::DeviceId.eq(device_id))
+        .order_by_desc(ModbusWriteColumn::Id)
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(kind: RegisterKind) -> WritableRegister {
+        WritableRegister {
+            kind,
+            address: 10,
+            label: String::new(),
+            min: Some(0.0),
+            max: Some(50.0),
+            scale: 0.1,
+            interlocks: Vec::new(),
+        }
+    }
+
+    fn interlock() -> Interlock {
+        Interlock {
+            device_id: 2,
+            metric_type: "level".to_string(),
+            min: Some(0.5),
+            max: None,
+            max_age_secs: 60,
+            when_value: Some(1.0),
+            description: "集水井液位过低禁止启泵".to_string(),
+        }
+    }
+
+    fn latest(value: f64, timestamp: DateTime<Utc>) -> LatestValue {
+        LatestValue {
+            value,
+            unit: "m".to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_clamp_and_raw_value() {
+        let holding = register(RegisterKind::Holding);
+        assert_eq!(clamp(&holding, 75.0), 50.0);
+        assert_eq!(clamp(&holding, -1.0), 0.0);
+        assert_eq!(raw_value(&holding, 12.34), Ok(123));
+        assert!(raw_value(&WritableRegister { min: None, max: None, ..holding }, 7000.0).is_err());
+
+        let coil = register(RegisterKind::Coil);
+        assert_eq!(clamp(&coil, 5.0), 1.0);
+        assert_eq!(clamp(&coil, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_check_interlock() {
+        let now = Utc::now();
+        let interlock = interlock();
+        assert!(check_interlock(&interlock, 1.0, Some(&latest(1.2, now)), now).is_ok());
+        assert!(check_interlock(&interlock, 1.0, Some(&latest(0.3, now)), now).is_err());
+        assert!(check_interlock(&interlock, 1.0, None, now).is_err());
+        let stale = now - chrono::Duration::seconds(120);
+        assert!(check_interlock(&interlock, 1.0, Some(&latest(1.2, stale)), now).is_err());
+        // 停泵不受液位联锁限制
+        assert!(check_interlock(&interlock, 0.0, Some(&latest(0.3, now)), now).is_ok());
+    }
+}
@@ -0,0 +1,287 @@
+//! 网络接口监测
+//!
+//! 定期采样配置的接口，链路断开或收发字节长时间不变时写入报警，恢复后才会再次报警。
+//! 无人值守的泵站依靠这里发现通信中断。
+
+use crate::config::network::{NetworkInterfaceConfig, NetworkMonitorConfig};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::{SEVERITY_MAJOR, SEVERITY_MINOR};
+use crate::services::alarm;
+use crate::utils::ethernet::{EthernetController, InterfaceCounters};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 接口的最新状态
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InterfaceStatus {
+    pub name: String,
+    pub device_id: Option<i32>,
+    pub link_up: bool,
+    /// 读取失败时为空
+    pub counters: Option<InterfaceCounters>,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    /// 收发字节数持续未变化的秒数
+    pub idle_secs: i64,
+    pub sampled_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl InterfaceStatus {
+    fn pending(config: &NetworkInterfaceConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            device_id: config.device_id,
+            link_up: false,
+            counters: None,
+            rx_bytes_per_sec: 0.0,
+            tx_bytes_per_sec: 0.0,
+            idle_secs: 0,
+            sampled_at: None,
+            error: None,
+        }
+    }
+}
+
+/// 需要报警的变化
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    LinkDown,
+    NoTraffic,
+}
+
+/// 单个接口的采样历史和报警状态
+#[derive(Debug, Default)]
+struct Tracker {
+    last: Option<(DateTime<Utc>, InterfaceCounters)>,
+    last_traffic_at: Option<DateTime<Utc>>,
+    link_alarmed: bool,
+    idle_alarmed: bool,
+}
+
+impl Tracker {
+    /// 记录一次采样，返回新出现的报警
+    fn update(
+        &mut self,
+        status: &mut InterfaceStatus,
+        now: DateTime<Utc>,
+        sample: Result<InterfaceCounters, String>,
+        zero_throughput_secs: u64,
+    ) -> Vec<Event> {
+        let mut events = Vec::new();
+        status.sampled_at = Some(now);
+        status.rx_bytes_per_sec = 0.0;
+        status.tx_bytes_per_sec = 0.0;
+
+        let counters = match sample {
+            Ok(counters) => {
+                status.error = None;
+                counters
+            }
+            Err(e) => {
+                status.error = Some(e);
+                status.link_up = false;
+                status.counters = None;
+                self.last = None;
+                if !self.link_alarmed {
+                    self.link_alarmed = true;
+                    events.push(Event::LinkDown);
+                }
+                return events;
+            }
+        };
+
+        status.link_up = counters.link_up();
+        if status.link_up {
+            self.link_alarmed = false;
+        } else if !self.link_alarmed {
+            self.link_alarmed = true;
+            events.push(Event::LinkDown);
+        }
+
+        let changed = match &self.last {
+            Some((at, prev)) => {
+                let secs = (now - *at).num_milliseconds() as f64 / 1000.0;
+                if secs > 0.0 {
+                    // 计数器回绕或接口重置时不计算速率
+                    let rx = counters.rx_bytes.saturating_sub(prev.rx_bytes);
+                    let tx = counters.tx_bytes.saturating_sub(prev.tx_bytes);
+                    status.rx_bytes_per_sec = rx as f64 / secs;
+                    status.tx_bytes_per_sec = tx as f64 / secs;
+                }
+                counters.rx_bytes != prev.rx_bytes || counters.tx_bytes != prev.tx_bytes
+            }
+            None => true,
+        };
+        if changed {
+            self.last_traffic_at = Some(now);
+            self.idle_alarmed = false;
+        }
+        let idle_since = *self.last_traffic_at.get_or_insert(now);
+        status.idle_secs = (now - idle_since).num_seconds();
+
+        // 链路断开时已经报过警，不再重复报无流量
+        if status.link_up
+            && zero_throughput_secs > 0
+            && status.idle_secs >= zero_throughput_secs as i64
+            && !self.idle_alarmed
+        {
+            self.idle_alarmed = true;
+            events.push(Event::NoTraffic);
+        }
+
+        status.counters = Some(counters.clone());
+        self.last = Some((now, counters));
+        events
+    }
+}
+
+/// 各接口最新状态，供 `/system/network` 查询
+#[derive(Debug, Clone)]
+pub struct NetworkMonitor {
+    statuses: Arc<RwLock<Vec<InterfaceStatus>>>,
+}
+
+impl NetworkMonitor {
+    pub fn new(config: &NetworkMonitorConfig) -> Self {
+        let statuses = if config.enabled {
+            config.interfaces.iter().map(InterfaceStatus::pending).collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            statuses: Arc::new(RwLock::new(statuses)),
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<InterfaceStatus> {
+        self.statuses.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, index: usize, status: InterfaceStatus) {
+        let mut statuses = self.statuses.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(slot) = statuses.get_mut(index) {
+            *slot = status;
+        }
+    }
+}
+
+/// 后台任务：定期采样网络接口
+pub async fn run_monitor(monitor: NetworkMonitor, config: NetworkMonitorConfig, db: DbManager) {
+    let controllers: Vec<EthernetController> = config
+        .interfaces
+        .iter()
+        .map(|i| EthernetController::new(&i.name, config.sysfs_base.as_deref()))
+        .collect();
+    let mut trackers: Vec<Tracker> = config.interfaces.iter().map(|_| Tracker::default()).collect();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.sample_interval_secs.max(1)));
+
+    loop {
+        ticker.tick().await;
+        let mut statuses = monitor.statuses();
+
+        for (index, interface) in config.interfaces.iter().enumerate() {
+            let Some(status) = statuses.get_mut(index) else {
+                continue;
+            };
+            let tracker = &mut trackers[index];
+            let was_down = tracker.link_alarmed;
+            let sample = controllers[index].read().await.map_err(|e| e.to_string());
+            let events = tracker.update(status, Utc::now(), sample, interface.zero_throughput_secs);
+            if was_down && status.link_up {
+                info!("Network interface {} link restored", interface.name);
+            }
+
+            for event in events {
+                let (rule_name, value, severity) = match event {
+                    Event::LinkDown => {
+                        (format!("网络中断: {}", interface.name), 0.0, SEVERITY_MAJOR)
+                    }
+                    Event::NoTraffic => (
+                        format!("网络无流量: {} 已 {} 秒", interface.name, status.idle_secs),
+                        status.idle_secs as f64,
+                        SEVERITY_MINOR,
+                    ),
+                };
+                let (conn, device_id) = (db.get_connection(), interface.device_id);
+                if let Err(e) = alarm::raise(conn, device_id, rule_name, value, severity).await {
+                    error!("Failed to raise network alarm for {}: {:?}", interface.name, e);
+                }
+            }
+            monitor.set(index, status.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn counters(operstate: &str, rx: u64, tx: u64) -> InterfaceCounters {
+        InterfaceCounters {
+            operstate: operstate.to_string(),
+            rx_bytes: rx,
+            tx_bytes: tx,
+            ..Default::default()
+        }
+    }
+
+    fn status() -> InterfaceStatus {
+        InterfaceStatus::pending(&NetworkInterfaceConfig {
+            name: "eth0".to_string(),
+            device_id: None,
+            zero_throughput_secs: 60,
+        })
+    }
+
+    #[test]
+    fn test_link_down_once_per_episode() {
+        let mut tracker = Tracker::default();
+        let mut status = status();
+        let t0 = DateTime::<Utc>::UNIX_EPOCH;
+
+        assert!(tracker.update(&mut status, t0, Ok(counters("up", 0, 0)), 60).is_empty());
+        let t1 = t0 + ChronoDuration::seconds(10);
+        let events = tracker.update(&mut status, t1, Ok(counters("down", 0, 0)), 60);
+        assert_eq!(events, vec![Event::LinkDown]);
+        let t2 = t1 + ChronoDuration::seconds(10);
+        assert!(tracker.update(&mut status, t2, Err("gone".to_string()), 60).is_empty());
+        let t3 = t2 + ChronoDuration::seconds(10);
+        assert!(tracker.update(&mut status, t3, Ok(counters("up", 10, 10)), 60).is_empty());
+        let t4 = t3 + ChronoDuration::seconds(10);
+        let events = tracker.update(&mut status, t4, Ok(counters("down", 10, 10)), 60);
+        assert_eq!(events, vec![Event::LinkDown]);
+    }
+
+    #[test]
+    fn test_no_traffic_and_rate() {
+        let mut tracker = Tracker::default();
+        let mut status = status();
+        let t0 = DateTime::<Utc>::UNIX_EPOCH;
+
+        tracker.update(&mut status, t0, Ok(counters("up", 1000, 500)), 60);
+        let t1 = t0 + ChronoDuration::seconds(10);
+        assert!(tracker.update(&mut status, t1, Ok(counters("up", 2000, 500)), 60).is_empty());
+        assert_eq!(status.rx_bytes_per_sec, 100.0);
+        assert_eq!(status.tx_bytes_per_sec, 0.0);
+
+        let t2 = t1 + ChronoDuration::seconds(59);
+        assert!(tracker.update(&mut status, t2, Ok(counters("up", 2000, 500)), 60).is_empty());
+        let t3 = t1 + ChronoDuration::seconds(60);
+        let events = tracker.update(&mut status, t3, Ok(counters("up", 2000, 500)), 60);
+        assert_eq!(events, vec![Event::NoTraffic]);
+        assert_eq!(status.idle_secs, 60);
+        let t4 = t3 + ChronoDuration::seconds(60);
+        assert!(tracker.update(&mut status, t4, Ok(counters("up", 2000, 500)), 60).is_empty());
+
+        // 恢复后重新计时
+        let t5 = t4 + ChronoDuration::seconds(10);
+        tracker.update(&mut status, t5, Ok(counters("up", 2100, 500)), 60);
+        assert_eq!(status.idle_secs, 0);
+    }
+}
@@ -0,0 +1,405 @@
+//! 排污许可限值
+//!
+//! 每条许可限值约束一台排口设备的一个参数，按滚动窗口（1 小时、24 小时、7 天、30 天）
+//! 计算平均值，窗口每小时滑动一次。平均值由小时汇总按样本数加权得到，见
+//! [`crate::services::rollup`]。超标窗口记录在 `permit_exceedances` 中，连续超标的窗口
+//! 合并为一个事件；超标记录是合规依据，不进入运行报警，也不能被确认或屏蔽。
+
+use crate::config::permit::PermitConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::discharge_permit::{
+    Column as PermitColumn, Entity as PermitEntity, Model as DischargePermit, LIMIT_MAX,
+    LIMIT_MIN, PERIOD_DAILY, PERIOD_HOURLY, PERIOD_MONTHLY, PERIOD_WEEKLY,
+};
+use crate::models::hourly_summary::{Column as HourlySummaryColumn, Entity as HourlySummaryEntity};
+use crate::models::permit_exceedance::{
+    ActiveModel as ExceedanceActiveModel, Column as ExceedanceColumn, Entity as ExceedanceEntity,
+    Model as PermitExceedance,
+};
+use crate::services::rollup::hour_start;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Months, NaiveDate, NaiveTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::Serialize;
+use std::time::Duration as StdDuration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// 平均周期对应的窗口小时数
+pub fn window_hours(period: &str) -> Option<i64> {
+    match period {
+        PERIOD_HOURLY => Some(1),
+        PERIOD_DAILY => Some(24),
+        PERIOD_WEEKLY => Some(24 * 7),
+        PERIOD_MONTHLY => Some(24 * 30),
+        _ => None,
+    }
+}
+
+/// 校验许可限值
+pub fn validate(permit: &DischargePermit) -> Result<(), AppError> {
+    if permit.name.trim().is_empty() || permit.metric_type.trim().is_empty() {
+        return Err(AppError::InvalidInput("名称和受限参数不能为空".into()));
+    }
+    if !permit.limit_value.is_finite() {
+        return Err(AppError::InvalidInput("限值无效".into()));
+    }
+    if ![LIMIT_MAX, LIMIT_MIN].contains(&permit.limit_type.as_str()) {
+        return Err(AppError::InvalidInput("限值类型只能是 max 或 min".into()));
+    }
+    if window_hours(&permit.averaging_period).is_none() {
+        let message = format!("未知的平均周期: {}", permit.averaging_period);
+        return Err(AppError::InvalidInput(message.into()));
+    }
+    Ok(())
+}
+
+/// 平均值是否超出限值
+pub fn exceeds(limit_type: &str, limit: f64, average: f64) -> bool {
+    match limit_type {
+        LIMIT_MIN => average < limit,
+        _ => average > limit,
+    }
+}
+
+/// 更严重的平均值
+fn worse(limit_type: &str, a: f64, b: f64) -> f64 {
+    match limit_type {
+        LIMIT_MIN => a.min(b),
+        _ => a.max(b),
+    }
+}
+
+/// 小时汇总中的一点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HourPoint {
+    pub hour: DateTime<Utc>,
+    pub avg: f64,
+    pub count: i64,
+}
+
+/// 一个滚动窗口 `[end - 窗口, end)` 的平均值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub end: DateTime<Utc>,
+    pub average: f64,
+    pub samples: i64,
+}
+
+/// 计算结束于 `first_end..=last_end` 各整小时的滚动平均，没有数据的窗口跳过；
+/// `points` 须按小时升序
+pub fn rolling(
+    points: &[HourPoint],
+    hours: i64,
+    first_end: DateTime<Utc>,
+    last_end: DateTime<Utc>,
+) -> Vec<Window> {
+    let mut windows = Vec::new();
+    let (mut lo, mut hi) = (0, 0);
+    let (mut sum, mut count) = (0.0, 0i64);
+    let mut end = first_end;
+    while end <= last_end {
+        let start = end - Duration::hours(hours);
+        while hi < points.len() && points[hi].hour < end {
+            sum += points[hi].avg * points[hi].count as f64;
+            count += points[hi].count;
+            hi += 1;
+        }
+        while lo < hi && points[lo].hour < start {
+            sum -= points[lo].avg * points[lo].count as f64;
+            count -= points[lo].count;
+            lo += 1;
+        }
+        if count > 0 {
+            windows.push(Window { end, average: sum / count as f64, samples: count });
+        }
+        end += Duration::hours(1);
+    }
+    windows
+}
+
+/// 计算许可限值在 `first_end..=last_end` 各窗口的滚动平均
+async fn windows(
+    conn: &DatabaseConnection,
+    permit: &DischargePermit,
+    first_end: DateTime<Utc>,
+    last_end: DateTime<Utc>,
+) -> Result<Vec<Window>, AppError> {
+    let Some(hours) = window_hours(&permit.averaging_period) else {
+        return Ok(Vec::new());
+    };
+    let points: Vec<HourPoint> = HourlySummaryEntity::find()
+        .filter(HourlySummaryColumn::DeviceId.eq(permit.device_id))
+        .filter(HourlySummaryColumn::MetricType.eq(permit.metric_type.as_str()))
+        .filter(HourlySummaryColumn::Hour.gte(first_end - Duration::hours(hours)))
+        .filter(HourlySummaryColumn::Hour.lt(last_end))
+        .order_by_asc(HourlySummaryColumn::Hour)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|row| HourPoint { hour: row.hour, avg: row.avg, count: row.count })
+        .collect();
+    Ok(rolling(&points, hours, first_end, last_end))
+}
+
+/// 记录一个超标窗口，紧接上一事件的窗口合并到该事件；返回是否为新记录的窗口
+async fn record(
+    conn: &DatabaseConnection,
+    permit: &DischargePermit,
+    window: &Window,
+) -> Result<bool, AppError> {
+    let previous = window.end - Duration::hours(1);
+    let existing = ExceedanceEntity::find()
+        .filter(ExceedanceColumn::PermitId.eq(permit.id))
+        .filter(ExceedanceColumn::FirstWindowEnd.lte(window.end))
+        .filter(ExceedanceColumn::LastWindowEnd.gte(previous))
+        .order_by_desc(ExceedanceColumn::LastWindowEnd)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let now = Utc::now();
+    match existing {
+        Some(event) if event.last_window_end >= window.end => Ok(false),
+        Some(event) => {
+            let peak = worse(&event.limit_type, event.peak_average, window.average);
+            let mut active_model = event.clone().into_active_model();
+            active_model.last_window_end = Set(window.end);
+            active_model.windows = Set(event.windows + 1);
+            active_model.peak_average = Set(peak);
+            active_model.updated_at = Set(now);
+            active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+            Ok(true)
+        }
+        None => {
+            warn!(
+                "Permit {} ({}) exceeded: {} average {} {} limit {}",
+                permit.id,
+                permit.name,
+                permit.averaging_period,
+                window.average,
+                permit.limit_type,
+                permit.limit_value
+            );
+            ExceedanceActiveModel {
+                permit_id: Set(permit.id),
+                device_id: Set(permit.device_id),
+                metric_type: Set(permit.metric_type.clone()),
+                averaging_period: Set(permit.averaging_period.clone()),
+                limit_value: Set(permit.limit_value),
+                limit_type: Set(permit.limit_type.clone()),
+                first_window_end: Set(window.end),
+                last_window_end: Set(window.end),
+                windows: Set(1),
+                peak_average: Set(window.average),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+            Ok(true)
+        }
+    }
+}
+
+/// 评估全部启用的许可限值，返回新记录的超标窗口数
+pub async fn evaluate(
+    conn: &DatabaseConnection,
+    config: &PermitConfig,
+    now: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let permits = PermitEntity::find()
+        .filter(PermitColumn::Enabled.eq(true))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let last_end = hour_start(now) - Duration::hours(config.settle_hours as i64);
+    let first_end = last_end - Duration::hours(config.lookback_hours.max(1) as i64 - 1);
+    let mut recorded = 0;
+    for permit in &permits {
+        for window in windows(conn, permit, first_end, last_end).await? {
+            if exceeds(&permit.limit_type, permit.limit_value, window.average)
+                && record(conn, permit, &window).await?
+            {
+                recorded += 1;
+            }
+        }
+    }
+    Ok(recorded)
+}
+
+/// 后台任务：定期评估许可限值
+pub async fn run_scheduler(config: PermitConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        match evaluate(db.get_connection(), &config, Utc::now()).await {
+            Ok(0) => {}
+            Ok(count) => info!("Recorded {} permit exceedance windows", count),
+            Err(e) => error!("Permit evaluation failed: {:?}", e),
+        }
+    }
+}
+
+/// 超标记录，可按许可限值和时间过滤
+pub async fn exceedances(
+    conn: &DatabaseConnection,
+    permit_id: Option<i32>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<PermitExceedance>, AppError> {
+    let mut select = ExceedanceEntity::find();
+    if let Some(permit_id) = permit_id {
+        select = select.filter(ExceedanceColumn::PermitId.eq(permit_id));
+    }
+    if let Some(start) = start {
+        select = select.filter(ExceedanceColumn::LastWindowEnd.gte(start));
+    }
+    if let Some(end) = end {
+        select = select.filter(ExceedanceColumn::FirstWindowEnd.lt(end));
+    }
+    select
+        .order_by_desc(ExceedanceColumn::FirstWindowEnd)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 单条许可限值的月度合规情况
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PermitCompliance {
+    pub permit: DischargePermit,
+    /// 结束于本月内的有数据的窗口数
+    pub windows_evaluated: usize,
+    pub windows_exceeded: usize,
+    /// 最严重的滚动平均值
+    pub worst_average: Option<f64>,
+    /// 本月全部样本的平均值
+    pub monthly_average: Option<f64>,
+    pub compliant: bool,
+    /// 与本月有交集的超标事件
+    pub exceedances: Vec<PermitExceedance>,
+}
+
+/// 月度合规汇总
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComplianceSummary {
+    /// YYYY-MM
+    pub month: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub compliant: bool,
+    pub permits: Vec<PermitCompliance>,
+}
+
+/// 解析 `YYYY-MM`，返回当月第一天
+pub fn parse_month(month: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput("月份格式应为 YYYY-MM".into()))
+}
+
+/// 计算某月的合规汇总，窗口重新按小时汇总计算，超标事件取自记录
+pub async fn monthly_summary(
+    conn: &DatabaseConnection,
+    month: NaiveDate,
+) -> Result<ComplianceSummary, AppError> {
+    let start = month.and_time(NaiveTime::MIN).and_utc();
+    let end = (month + Months::new(1)).and_time(NaiveTime::MIN).and_utc();
+    let last_end = end.min(hour_start(Utc::now()));
+
+    let permits = PermitEntity::find()
+        .order_by_asc(PermitColumn::Id)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut results = Vec::with_capacity(permits.len());
+    for permit in permits {
+        let windows = windows(conn, &permit, start + Duration::hours(1), last_end).await?;
+        let exceeded = windows
+            .iter()
+            .filter(|w| exceeds(&permit.limit_type, permit.limit_value, w.average))
+            .count();
+        let worst_average = windows
+            .iter()
+            .map(|w| w.average)
+            .reduce(|a, b| worse(&permit.limit_type, a, b));
+        let monthly_average = monthly_average(conn, &permit, start, end).await?;
+        let exceedances = exceedances(conn, Some(permit.id), Some(start), Some(end)).await?;
+        results.push(PermitCompliance {
+            windows_evaluated: windows.len(),
+            windows_exceeded: exceeded,
+            worst_average,
+            monthly_average,
+            compliant: exceeded == 0 && exceedances.is_empty(),
+            exceedances,
+            permit,
+        });
+    }
+
+    Ok(ComplianceSummary {
+        month: month.format("%Y-%m").to_string(),
+        start,
+        end,
+        compliant: results.iter().all(|r| r.compliant || !r.permit.enabled),
+        permits: results,
+    })
+}
+
+/// 整月的样本加权平均
+async fn monthly_average(
+    conn: &DatabaseConnection,
+    permit: &DischargePermit,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Option<f64>, AppError> {
+    let rows = HourlySummaryEntity::find()
+        .filter(HourlySummaryColumn::DeviceId.eq(permit.device_id))
+        .filter(HourlySummaryColumn::MetricType.eq(permit.metric_type.as_str()))
+        .filter(HourlySummaryColumn::Hour.gte(start))
+        .filter(HourlySummaryColumn::Hour.lt(end))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let count: i64 = rows.iter().map(|row| row.count).sum();
+    let sum: f64 = rows.iter().map(|row| row.avg * row.count as f64).sum();
+    Ok((count > 0).then(|| sum / count as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rolling() {
+        let t0 = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let hour = |h: i64| t0 + Duration::hours(h);
+        let points = vec![
+            HourPoint { hour: hour(0), avg: 10.0, count: 1 },
+            HourPoint { hour: hour(1), avg: 20.0, count: 3 },
+            HourPoint { hour: hour(3), avg: 40.0, count: 1 },
+        ];
+        let windows = rolling(&points, 2, hour(1), hour(6));
+        let averages: Vec<(i64, f64)> = windows
+            .iter()
+            .map(|w| ((w.end - t0).num_hours(), w.average))
+            .collect();
+        // 结束于 5 点的窗口 [3, 5) 只有 3 点的数据，结束于 6 点的窗口没有数据
+        assert_eq!(averages, vec![(1, 10.0), (2, 17.5), (3, 20.0), (4, 40.0), (5, 40.0)]);
+        assert_eq!(windows[1].samples, 4);
+
+        assert!(exceeds(LIMIT_MAX, 30.0, 40.0));
+        assert!(!exceeds(LIMIT_MAX, 30.0, 30.0));
+        assert!(exceeds(LIMIT_MIN, 6.0, 5.9));
+        assert_eq!(worse(LIMIT_MIN, 5.9, 6.2), 5.9);
+        assert_eq!(window_hours(PERIOD_DAILY), Some(24));
+    }
+}
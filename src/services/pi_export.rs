@@ -0,0 +1,224 @@
+//! 测量值转发到 PI 历史库
+//!
+//! 后台任务按 id 顺序读取新入库的测量值，按配置的点映射通过 PI Web API 的
+//! `streamsets/recorded` 批量写入。已发送位置保存在本地 redb 文件中，发送失败时不前移，
+//! 数据留在测量值表里等待重试，重试间隔逐次加倍。首次启动从当前最新一条开始，不补发历史数据。
+
+use crate::config::pi::{PiExportConfig, PiTagMapping};
+use crate::database::redb::DbManager as RedbManager;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::measurement::{
+    Column as MeasurementColumn, Entity as MeasurementEntity, Model as Measurement,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+const STATE_TABLE: &str = "pi_export";
+const CURSOR_KEY: &str = "last_measurement_id";
+
+#[derive(Debug, Serialize, PartialEq)]
+struct TimedValue {
+    #[serde(rename = "Timestamp")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "Value")]
+    value: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct StreamValues {
+    #[serde(rename = "WebId")]
+    web_id: String,
+    #[serde(rename = "Items")]
+    items: Vec<TimedValue>,
+}
+
+/// 按点分组，没有映射的测量值跳过
+fn build_payload(measurements: &[Measurement], tags: &[PiTagMapping]) -> Vec<StreamValues> {
+    let web_ids: HashMap<(i32, &str), &str> = tags
+        .iter()
+        .map(|t| ((t.device_id, t.metric_type.as_str()), t.web_id.as_str()))
+        .collect();
+
+    let mut streams: Vec<StreamValues> = Vec::new();
+    for m in measurements {
+        let Some(device_id) = m.device_id else {
+            continue;
+        };
+        let Some(web_id) = web_ids.get(&(device_id, m.metric_type.as_str())) else {
+            continue;
+        };
+        let item = TimedValue { timestamp: m.timestamp, value: m.value };
+        match streams.iter_mut().find(|s| s.web_id == *web_id) {
+            Some(stream) => stream.items.push(item),
+            None => streams.push(StreamValues { web_id: web_id.to_string(), items: vec![item] }),
+        }
+    }
+    streams
+}
+
+fn build_client(config: &PiExportConfig) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+        .danger_accept_invalid_certs(config.accept_invalid_certs)
+        .build()
+}
+
+async fn send(
+    client: &reqwest::Client,
+    config: &PiExportConfig,
+    payload: &[StreamValues],
+) -> Result<(), String> {
+    let url = format!("{}/streamsets/recorded", config.base_url.trim_end_matches('/'));
+    let mut request = client.post(url).json(payload);
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, config.password.as_ref());
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("HTTP {}: {}", status, body.chars().take(500).collect::<String>()));
+    }
+    // 207 表示部分点写入失败，重发会导致其余点重复写入，只记录日志
+    if status == reqwest::StatusCode::MULTI_STATUS {
+        let body = response.text().await.unwrap_or_default();
+        warn!("PI export partially failed: {}", body.chars().take(500).collect::<String>());
+    }
+    Ok(())
+}
+
+async fn next_batch(
+    conn: &DatabaseConnection,
+    after: i32,
+    limit: u64,
+) -> Result<Vec<Measurement>, sea_orm::DbErr> {
+    MeasurementEntity::find()
+        .filter(MeasurementColumn::Id.gt(after))
+        .order_by_asc(MeasurementColumn::Id)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+async fn latest_id(conn: &DatabaseConnection) -> Result<i32, sea_orm::DbErr> {
+    let latest = MeasurementEntity::find()
+        .order_by_desc(MeasurementColumn::Id)
+        .one(conn)
+        .await?;
+    Ok(latest.map_or(0, |m| m.id))
+}
+
+/// 后台任务：持续把新测量值转发到 PI
+pub async fn run_exporter(config: PiExportConfig, db: DbManager) {
+    let state = match RedbManager::new(&config.state_path) {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Failed to open PI export state {}: {:?}", config.state_path, e);
+            return;
+        }
+    };
+    let client = match build_client(&config) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to create PI Web API client: {}", e);
+            return;
+        }
+    };
+    let conn = db.get_connection();
+
+    let mut cursor = match state.get::<i32>(STATE_TABLE, CURSOR_KEY) {
+        Ok(Some(cursor)) => cursor,
+        _ => loop {
+            match latest_id(conn).await {
+                Ok(id) => break id,
+                Err(e) => {
+                    error!("Failed to read latest measurement id: {:?}", e);
+                    tokio::time::sleep(Duration::from_secs(config.interval_secs.max(1))).await;
+                }
+            }
+        },
+    };
+    info!("PI export started after measurement {}, {} tags", cursor, config.tags.len());
+
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    let max_backoff = Duration::from_secs(config.max_backoff_secs).max(interval);
+    let mut backoff = interval;
+
+    loop {
+        let batch = match next_batch(conn, cursor, config.batch_size.max(1)).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                error!("Failed to read measurements for PI export: {:?}", e);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+        let Some(last) = batch.last().map(|m| m.id) else {
+            tokio::time::sleep(interval).await;
+            continue;
+        };
+
+        let payload = build_payload(&batch, &config.tags);
+        if !payload.is_empty() {
+            if let Err(e) = send(&client, &config, &payload).await {
+                warn!("PI export failed, retrying in {:?}: {}", backoff, e);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                continue;
+            }
+        }
+        backoff = interval;
+        cursor = last;
+        if let Err(e) = state.put(STATE_TABLE, CURSOR_KEY, &cursor) {
+            error!("Failed to save PI export cursor: {:?}", e);
+        }
+
+        if (batch.len() as u64) < config.batch_size.max(1) {
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(id: i32, device_id: Option<i32>, metric_type: &str, value: f64) -> Measurement {
+        Measurement {
+            id,
+            metric_type: metric_type.to_string(),
+            timestamp: Utc::now(),
+            value,
+            device_id,
+            unit: String::new(),
+            suppressed_count: 0,
+            quality: "good".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_build_payload_groups_by_tag() {
+        let tags = vec![
+            PiTagMapping { device_id: 1, metric_type: "ph".to_string(), web_id: "P1".to_string() },
+            PiTagMapping { device_id: 2, metric_type: "ph".to_string(), web_id: "P2".to_string() },
+        ];
+        let measurements = vec![
+            measurement(1, Some(1), "ph", 7.0),
+            measurement(2, Some(2), "ph", 7.2),
+            measurement(3, Some(1), "tds", 300.0),
+            measurement(4, None, "ph", 6.9),
+            measurement(5, Some(1), "ph", 7.1),
+        ];
+        let payload = build_payload(&measurements, &tags);
+        assert_eq!(payload.len(), 2);
+        assert_eq!(payload[0].web_id, "P1");
+        assert_eq!(payload[0].items.iter().map(|i| i.value).collect::<Vec<_>>(), vec![7.0, 7.1]);
+        assert_eq!(payload[1].items.len(), 1);
+    }
+}
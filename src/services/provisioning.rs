@@ -0,0 +1,269 @@
+//! 设备自注册
+//!
+//! 1. 新设备向 `provision/request` 发布 `{"serial_number": "..."}`，后台创建待审批设备；
+//! 2. 管理员调用 `POST /devices/{id}/approve` 审批；
+//! 3. 后台生成设备专属的 MQTT 账号与主题前缀，密码明文只在审批接口的响应中返回一次，由现场人员
+//!    写入设备；`provision/response/{serial_number}` 上只发布不含密码的审批结果。
+//!
+//! 凭据只保存摘要，broker 通过 `POST /mqtt/auth` 认证钩子按摘要校验设备账号。序列号会拼进主题，
+//! 不允许包含 MQTT 通配符和层级分隔符。
+
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{
+    self, ActiveModel as DeviceActiveModel, Column as DeviceColumn, Entity as DeviceEntity,
+    Model as Device,
+};
+use crate::models::device_credential::{
+    ActiveModel as CredentialActiveModel, Column as CredentialColumn, Entity as CredentialEntity,
+};
+use crate::mqtt::rumqtt::MqttManager;
+use crate::utils::crypto;
+use crate::utils::error::AppError;
+use chrono::Utc;
+use rumqttc::QoS;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+pub const REQUEST_TOPIC: &str = "provision/request";
+
+pub fn response_topic(serial_number: &str) -> String {
+    format!("provision/response/{}", serial_number)
+}
+
+/// 序列号能否安全地拼进主题
+fn valid_serial_number(serial_number: &str) -> bool {
+    !serial_number.is_empty()
+        && !serial_number.chars().any(|c| matches!(c, '+' | '#' | '/') || c.is_control())
+}
+
+/// 设备上报的注册请求
+#[derive(Debug, Deserialize)]
+pub struct ProvisionRequest {
+    pub serial_number: String,
+    pub name: Option<String>,
+    pub device_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+/// 审批后下发的设备凭据
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IssuedCredentials {
+    pub device_id: i32,
+    pub mqtt_username: String,
+    pub mqtt_password: String,
+    pub topic_prefix: String,
+}
+
+/// 审批后发布到注册响应主题的结果，不含密码
+#[derive(Debug, Serialize)]
+struct ApprovalNotice<'a> {
+    device_id: i32,
+    mqtt_username: &'a str,
+    topic_prefix: &'a str,
+}
+
+/// 按序列号查找设备
+pub async fn find_by_serial(
+    conn: &DatabaseConnection,
+    serial_number: &str,
+) -> Result<Option<Device>, AppError> {
+    DeviceEntity::find()
+        .filter(DeviceColumn::SerialNumber.eq(serial_number))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 登记待审批设备；序列号已存在时返回已有设备
+pub async fn register_pending(
+    conn: &DatabaseConnection,
+    request: ProvisionRequest,
+) -> Result<Device, AppError> {
+    let serial_number = request.serial_number.trim().to_string();
+    if serial_number.is_empty() {
+        return Err(AppError::InvalidInput("序列号不能为空".into()));
+    }
+    if !valid_serial_number(&serial_number) {
+        return Err(AppError::InvalidInput("序列号不能包含 +、#、/ 或控制字符".into()));
+    }
+
+    if let Some(existing) = find_by_serial(conn, &serial_number).await? {
+        return Ok(existing);
+    }
+
+    let now = Utc::now();
+    let new_device = DeviceActiveModel {
+        name: Set(request.name.unwrap_or_else(|| serial_number.clone())),
+        location: Set(String::new()),
+        status: Set(0),
+        device_type: Set(request.device_type.unwrap_or_default()),
+        manufacturer: Set(request.manufacturer.unwrap_or_default()),
+        model: Set(request.model.unwrap_or_default()),
+        installation_date: Set(now),
+        last_maintenance: Set(now),
+        operational_hours: Set(0.0),
+        temperature: Set(0.0),
+        pressure: Set(0.0),
+        flow_rate: Set(0.0),
+        power_consumption: Set(0.0),
+        serial_number: Set(Some(serial_number)),
+        provision_status: Set(device::PROVISION_PENDING.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    DeviceEntity::insert(new_device)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 处理 `provision/request` 消息
+pub async fn handle_request(db: DbManager, payload: Vec<u8>) {
+    let request = match serde_json::from_slice::<ProvisionRequest>(&payload) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid provisioning request: {}", e);
+            return;
+        }
+    };
+
+    match register_pending(db.get_connection(), request).await {
+        Ok(device) if device.provision_status == device::PROVISION_PENDING => {
+            info!(
+                "Device {:?} registered as pending (id={})",
+                device.serial_number, device.id
+            );
+        }
+        Ok(device) => {
+            info!(
+                "Provisioning request from already approved device {:?} (id={})",
+                device.serial_number, device.id
+            );
+        }
+        Err(e) => error!("Failed to register pending device: {:?}", e),
+    }
+}
+
+/// 审批设备并签发 MQTT 凭据，旧凭据同时吊销
+pub async fn approve(
+    conn: &DatabaseConnection,
+    mqtt: Option<&MqttManager>,
+    device_id: i32,
+) -> Result<(Device, IssuedCredentials), AppError> {
+    let existing_device = DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)?;
+
+    if existing_device.provision_status != device::PROVISION_PENDING {
+        return Err(AppError::InvalidInput("设备不处于待审批状态".into()));
+    }
+
+    let credentials = IssuedCredentials {
+        device_id,
+        mqtt_username: format!("device-{}", device_id),
+        mqtt_password: crypto::random_token(16),
+        topic_prefix: format!("devices/{}", device_id),
+    };
+    let now = Utc::now();
+
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+
+    CredentialEntity::update_many()
+        .col_expr(CredentialColumn::Revoked, Expr::value(true))
+        .col_expr(CredentialColumn::UpdatedAt, Expr::value(now))
+        .filter(CredentialColumn::DeviceId.eq(device_id))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let new_credential = CredentialActiveModel {
+        device_id: Set(device_id),
+        mqtt_username: Set(credentials.mqtt_username.clone()),
+        password_hash: Set(crypto::sha256_hex(credentials.mqtt_password.as_bytes())),
+        topic_prefix: Set(credentials.topic_prefix.clone()),
+        revoked: Set(false),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+    CredentialEntity::insert(new_credential)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut device_active_model = existing_device.into_active_model();
+    device_active_model.provision_status = Set(device::PROVISION_ACTIVE.to_string());
+    device_active_model.updated_at = Set(now);
+    let device = DeviceEntity::update(device_active_model)
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+
+    if let (Some(mqtt), Some(serial_number)) = (mqtt, device.serial_number.as_deref()) {
+        if valid_serial_number(serial_number) {
+            let notice = ApprovalNotice {
+                device_id,
+                mqtt_username: &credentials.mqtt_username,
+                topic_prefix: &credentials.topic_prefix,
+            };
+            let payload = serde_json::to_vec(&notice).map_err(|_| AppError::InternalError)?;
+            mqtt.enqueue_publish(&response_topic(serial_number), payload, QoS::AtLeastOnce)
+                .await;
+        } else {
+            warn!("Not publishing approval for device {} with unsafe serial number", device_id);
+        }
+    }
+
+    Ok((device, credentials))
+}
+
+/// 校验设备 MQTT 账号，供 broker 认证钩子调用
+///
+/// 用户名不是设备账号时返回 `None`，交由 broker 的其他认证方式处理；已吊销的凭据不再通过。
+pub async fn verify_credentials(
+    conn: &DatabaseConnection,
+    username: &str,
+    password: &str,
+) -> Result<Option<bool>, AppError> {
+    let credentials = CredentialEntity::find()
+        .filter(CredentialColumn::MqttUsername.eq(username))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if credentials.is_empty() {
+        return Ok(None);
+    }
+
+    let password_hash = crypto::sha256_hex(password.as_bytes());
+    Ok(Some(credentials.iter().any(|credential| {
+        !credential.revoked && credential.password_hash == password_hash
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_serial_number() {
+        assert!(valid_serial_number("SN-2026-0001"));
+        assert!(!valid_serial_number(""));
+        assert!(!valid_serial_number("SN/0001"));
+        assert!(!valid_serial_number("SN+"));
+        assert!(!valid_serial_number("#"));
+        assert!(!valid_serial_number("SN\n"));
+    }
+}
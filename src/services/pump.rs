@@ -0,0 +1,227 @@
+//! 水泵效率评估
+//!
+//! 按实测流量、出口压力（折算扬程）和输入功率计算运行效率，与厂家效率曲线在同一流量下的值比较，
+//! 低于曲线值超过设定百分比时写入"水泵性能下降"报警，恢复后才会再次报警。
+
+use crate::config::pump::PumpMonitorConfig;
+use crate::config::quality::QualityConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::SEVERITY_MINOR;
+use crate::models::calibration_curve::KIND_PIECEWISE;
+use crate::models::pump_curve::{Column as PumpCurveColumn, Entity as PumpCurveEntity, Model as PumpCurve};
+use crate::services::alarm;
+use crate::services::cache::HotCache;
+use crate::services::calibration::Curve;
+use crate::services::latest::{self, DeviceLatest};
+use crate::services::measurement::{self, NewMeasurement};
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::error;
+use utoipa::ToSchema;
+
+const GRAVITY: f64 = 9.80665;
+
+/// 一次效率评估的结果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PumpEfficiency {
+    pub device_id: i32,
+    /// 流量 (m³/h)
+    pub flow: f64,
+    /// 扬程 (m)
+    pub head: f64,
+    /// 输入功率 (kW)
+    pub power: f64,
+    /// 实测效率 (%)
+    pub efficiency: f64,
+    /// 曲线效率 (%)
+    pub expected: f64,
+    /// 低于曲线的百分比
+    pub deviation_pct: f64,
+    pub degraded: bool,
+}
+
+pub fn efficiency_curve(curve: &PumpCurve) -> Result<Curve, AppError> {
+    Curve::parse(KIND_PIECEWISE, &curve.efficiency_curve)
+}
+
+/// 校验曲线定义
+pub fn validate(curve: &PumpCurve) -> Result<(), AppError> {
+    efficiency_curve(curve)?;
+    if let Some(head_curve) = &curve.head_curve {
+        Curve::parse(KIND_PIECEWISE, head_curve)?;
+    }
+    if !(curve.degradation_pct > 0.0 && curve.degradation_pct < 100.0) {
+        return Err(AppError::InvalidInput("报警阈值须在 0 到 100 之间".into()));
+    }
+    Ok(())
+}
+
+/// 由流量 (m³/h)、压力 (MPa)、功率 (kW) 计算效率 (%)
+pub fn evaluate(
+    curve: &PumpCurve,
+    flow: f64,
+    pressure: f64,
+    power: f64,
+    density: f64,
+) -> Result<Option<PumpEfficiency>, AppError> {
+    if flow <= 0.0 || power <= 0.0 {
+        return Ok(None);
+    }
+
+    let head = pressure * 1_000_000.0 / (density * GRAVITY);
+    let hydraulic_kw = density * GRAVITY * (flow / 3600.0) * head / 1000.0;
+    let efficiency = hydraulic_kw / power * 100.0;
+    let expected = efficiency_curve(curve)?.apply(flow);
+    if expected <= 0.0 {
+        return Ok(None);
+    }
+    let deviation_pct = (expected - efficiency) / expected * 100.0;
+
+    Ok(Some(PumpEfficiency {
+        device_id: curve.device_id,
+        flow,
+        head,
+        power,
+        efficiency,
+        expected,
+        deviation_pct,
+        degraded: deviation_pct > curve.degradation_pct,
+    }))
+}
+
+pub async fn find_curve(conn: &DatabaseConnection, device_id: i32) -> Result<Option<PumpCurve>, AppError> {
+    PumpCurveEntity::find()
+        .filter(PumpCurveColumn::DeviceId.eq(device_id))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 取足够新的最新值
+fn recent(latest: &DeviceLatest, metric: &str, max_age: ChronoDuration) -> Option<f64> {
+    latest
+        .values
+        .get(metric)
+        .filter(|v| Utc::now() - v.timestamp <= max_age)
+        .map(|v| v.value)
+}
+
+/// 按设备当前的最新值评估效率，数据不全或过旧时为空
+pub async fn current(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    config: &PumpMonitorConfig,
+    curve: &PumpCurve,
+) -> Result<Option<PumpEfficiency>, AppError> {
+    let latest = latest::for_device(conn, cache, curve.device_id).await?;
+    let max_age = ChronoDuration::seconds(config.max_sample_age_secs);
+
+    let (Some(flow), Some(pressure), Some(power)) = (
+        recent(&latest, metric_registry::FLOW, max_age),
+        recent(&latest, metric_registry::PRESSURE, max_age),
+        recent(&latest, metric_registry::POWER, max_age),
+    ) else {
+        return Ok(None);
+    };
+
+    evaluate(curve, flow, pressure, power, config.fluid_density)
+}
+
+/// 后台任务：定期评估所有配置了曲线的水泵
+pub async fn run_monitor(
+    config: PumpMonitorConfig,
+    quality: QualityConfig,
+    db: DbManager,
+    cache: HotCache,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+    // 已报警、尚未恢复的水泵
+    let mut degraded: HashSet<i32> = HashSet::new();
+
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+
+        let curves = match PumpCurveEntity::find().all(conn).await {
+            Ok(curves) => curves,
+            Err(e) => {
+                error!("Failed to load pump curves: {}", e);
+                continue;
+            }
+        };
+
+        for curve in curves {
+            let result = match current(conn, &cache, &config, &curve).await {
+                Ok(Some(result)) => result,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to evaluate pump {}: {:?}", curve.device_id, e);
+                    continue;
+                }
+            };
+
+            let efficiency = NewMeasurement {
+                metric_type: metric_registry::PUMP_EFFICIENCY.to_string(),
+                timestamp: Utc::now(),
+                value: result.efficiency.clamp(0.0, 100.0),
+                device_id: Some(curve.device_id),
+                unit: None,
+            };
+            if let Err(e) = measurement::create(conn, &cache, &quality, efficiency).await {
+                error!("Failed to store pump efficiency for {}: {:?}", curve.device_id, e);
+            }
+
+            if !result.degraded {
+                degraded.remove(&curve.device_id);
+                continue;
+            }
+            if degraded.insert(curve.device_id) {
+                let rule_name = format!(
+                    "水泵性能下降: 设备 {} 效率 {:.1}% 低于曲线 {:.1}%",
+                    curve.device_id, result.efficiency, result.expected
+                );
+                let (device_id, value) = (Some(curve.device_id), result.deviation_pct);
+                if let Err(e) = alarm::raise(conn, device_id, rule_name, value, SEVERITY_MINOR).await {
+                    error!("Failed to raise pump degradation alarm: {:?}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> PumpCurve {
+        PumpCurve {
+            id: 1,
+            device_id: 1,
+            efficiency_curve: "[[0, 0], [100, 70], [200, 80]]".to_string(),
+            head_curve: None,
+            degradation_pct: 10.0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // 100 m³/h、0.2 MPa（约 20.4 m）时水力功率约 5.56 kW
+        let healthy = evaluate(&curve(), 100.0, 0.2, 8.0, 1000.0).unwrap().unwrap();
+        assert!((healthy.efficiency - 69.44).abs() < 0.01);
+        assert_eq!(healthy.expected, 70.0);
+        assert!(!healthy.degraded);
+
+        let degraded = evaluate(&curve(), 100.0, 0.2, 10.0, 1000.0).unwrap().unwrap();
+        assert!((degraded.efficiency - 55.56).abs() < 0.01);
+        assert!(degraded.degraded);
+
+        assert!(evaluate(&curve(), 0.0, 0.2, 10.0, 1000.0).unwrap().is_none());
+    }
+}
@@ -0,0 +1,400 @@
+//! 主备泵组轮换
+//!
+//! 泵组按 `duty_count` 决定同时运行的台数，`duty` 记录当前分配，第一台为主泵。每个周期：
+//! 先剔除故障的泵（运行状态为 fault、有未搁置的 critical / major 未处理报警，或运行分配后超过
+//! `feedback_timeout_secs` 仍没有运行反馈），空出的位置由运行小时数最少的备用泵顶替并报警；
+//! 再比较运行小时数，运行中的泵比最少运行的备用泵多出 `rotation_hours` 时换下运行最久的一台。
+//! 组内的泵配置了 GPIO 输出（`gpio.outputs[].device_id`）时下发启停，否则分配只供外部 PLC 参考。
+//! 没有运行反馈的故障一直保持，直到设备报告运行或由操作员复位。
+
+use crate::app_state::AppState;
+use crate::config::pump_group::PumpGroupConfig;
+use crate::models::alarm_log::{
+    Column as AlarmLogColumn, Entity as AlarmLogEntity, SEVERITY_CRITICAL, SEVERITY_MAJOR,
+};
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::device_state_event::{CATEGORY_OPERATION, STATE_FAULT, STATE_RUNNING};
+use crate::models::pump_group::{
+    Column as PumpGroupColumn, Entity as PumpGroupEntity, Model as PumpGroup,
+};
+use crate::services::{alarm, device_state};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, Set,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// 解析 `members` / `duty` 中的设备 ID 列表
+pub fn parse_ids(value: &str) -> Result<Vec<i32>, AppError> {
+    serde_json::from_str(value).map_err(|_| AppError::InvalidInput("设备列表须为设备 ID 的 JSON 数组".into()))
+}
+
+pub fn validate(group: &PumpGroup) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::InvalidInput(format!("泵组参数无效: {}", msg).into()));
+
+    if group.name.trim().is_empty() {
+        return invalid("名称不能为空");
+    }
+    let members = parse_ids(&group.members)?;
+    if members.is_empty() {
+        return invalid("至少需要一台泵");
+    }
+    if members.iter().collect::<HashSet<_>>().len() != members.len() {
+        return invalid("泵不能重复");
+    }
+    if !(1..=members.len() as i32).contains(&group.duty_count) {
+        return invalid("运行台数须在 1 到组内泵数之间");
+    }
+    if !(group.rotation_hours.is_finite() && group.rotation_hours >= 0.0) {
+        return invalid("轮换小时数不能为负");
+    }
+    if group.feedback_timeout_secs <= 0 {
+        return invalid("反馈超时须大于 0");
+    }
+    Ok(())
+}
+
+/// 计算新的运行分配，返回分配和是否发生了轮换
+///
+/// `available` 为可用的泵及其运行小时数，按组内优先顺序。仍可用的泵保持原位置，
+/// 空出的位置按运行小时数从少到多补充；运行小时数相同时按优先顺序。
+pub fn assign(
+    duty: &[i32],
+    available: &[(i32, f64)],
+    duty_count: usize,
+    rotation_hours: f64,
+) -> (Vec<i32>, bool) {
+    let hours: HashMap<i32, f64> = available.iter().copied().collect();
+    let mut next: Vec<i32> =
+        duty.iter().copied().filter(|id| hours.contains_key(id)).take(duty_count).collect();
+    let mut standby: Vec<(i32, f64)> =
+        available.iter().copied().filter(|(id, _)| !next.contains(id)).collect();
+    standby.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let fill = duty_count.saturating_sub(next.len()).min(standby.len());
+    next.extend(standby.drain(..fill).map(|(id, _)| id));
+
+    let mut rotated = false;
+    if rotation_hours > 0.0 {
+        let worn = next
+            .iter()
+            .enumerate()
+            .map(|(position, id)| (position, hours[id]))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let (Some((position, worn_hours)), Some(&(fresh, fresh_hours))) = (worn, standby.first()) {
+            if worn_hours - fresh_hours >= rotation_hours {
+                next[position] = fresh;
+                rotated = true;
+            }
+        }
+    }
+    (next, rotated)
+}
+
+/// 不可用的泵及原因
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct MemberFault {
+    pub device_id: i32,
+    pub reason: String,
+}
+
+/// 泵组当前的运行分配
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct DutyStatus {
+    pub group_id: i32,
+    pub name: String,
+    /// 主泵
+    pub lead: Option<i32>,
+    /// 辅泵，按启动顺序
+    pub lag: Vec<i32>,
+    /// 可用但未运行的备用泵
+    pub standby: Vec<i32>,
+    pub faulted: Vec<MemberFault>,
+    /// 可用的泵不足运行台数
+    pub short: bool,
+    pub last_rotated_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct GroupState {
+    /// 进入运行分配的时间，用于判断运行反馈超时
+    assigned: HashMap<i32, DateTime<Utc>>,
+    /// 没有运行反馈的泵
+    no_feedback: HashSet<i32>,
+    /// 最后一次成功下发的启停
+    commanded: HashMap<i32, bool>,
+    /// 已报警的故障泵，恢复后清除
+    alarmed: HashSet<i32>,
+    short_alarmed: bool,
+    status: DutyStatus,
+}
+
+/// 各泵组的运行状态
+#[derive(Debug, Clone, Default)]
+pub struct PumpGroupRuntime {
+    groups: Arc<Mutex<HashMap<i32, GroupState>>>,
+}
+
+impl PumpGroupRuntime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> Vec<DutyStatus> {
+        let groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statuses: Vec<DutyStatus> = groups.values().map(|g| g.status.clone()).collect();
+        statuses.sort_by_key(|s| s.group_id);
+        statuses
+    }
+
+    pub fn get(&self, group_id: i32) -> Option<DutyStatus> {
+        let groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        groups.get(&group_id).map(|g| g.status.clone())
+    }
+
+    /// 清除没有运行反馈的故障，下一周期重新参与分配
+    pub fn reset(&self, group_id: i32) -> Vec<i32> {
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(group) = groups.get_mut(&group_id) else {
+            return Vec::new();
+        };
+        group.assigned.clear();
+        group.no_feedback.drain().collect()
+    }
+
+    fn with<R>(&self, group_id: i32, f: impl FnOnce(&mut GroupState) -> R) -> R {
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        f(groups.entry(group_id).or_default())
+    }
+
+    fn retain(&self, ids: &[i32]) {
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        groups.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// 设备是否有未处理、未搁置的严重报警
+async fn has_severe_alarm(conn: &DatabaseConnection, device_id: i32) -> Result<bool, AppError> {
+    let count = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::DeviceId.eq(device_id))
+        .filter(AlarmLogColumn::IsProcessed.eq(false))
+        .filter(AlarmLogColumn::Shelved.eq(false))
+        .filter(AlarmLogColumn::Severity.is_in([SEVERITY_CRITICAL, SEVERITY_MAJOR]))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(count > 0)
+}
+
+/// 下发组内一台泵的启停，没有配置 GPIO 输出时返回 false
+async fn command(state: &AppState, device_id: i32, run: bool) -> Result<bool, AppError> {
+    let Some(output) = state.gpio_outputs.outputs().iter().find(|o| o.device_id == Some(device_id))
+    else {
+        return Ok(false);
+    };
+    let operator = Some("pump_group".to_string());
+    state.gpio_outputs.set(state.db.get_connection(), &output.name, run, operator).await?;
+    Ok(true)
+}
+
+async fn tick_group(
+    state: &AppState,
+    runtime: &PumpGroupRuntime,
+    config: &PumpGroupConfig,
+    group: &PumpGroup,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let conn = state.db.get_connection();
+    let members = parse_ids(&group.members)?;
+    let duty = parse_ids(&group.duty).unwrap_or_default();
+    let timeout = Duration::seconds(group.feedback_timeout_secs as i64);
+    let (assigned, no_feedback) =
+        runtime.with(group.id, |g| (g.assigned.clone(), g.no_feedback.clone()));
+
+    let mut available = Vec::new();
+    let mut faulted = Vec::new();
+    let mut recovered = Vec::new();
+    for &device_id in &members {
+        let Some(device) = DeviceEntity::find_by_id(device_id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+        else {
+            faulted.push(MemberFault { device_id, reason: "设备不存在".to_string() });
+            continue;
+        };
+        let operation = device_state::current(conn, device_id, CATEGORY_OPERATION).await?;
+        let running = operation.as_deref() == Some(STATE_RUNNING);
+        if running {
+            recovered.push(device_id);
+        }
+        let timed_out = duty.contains(&device_id)
+            && assigned.get(&device_id).is_some_and(|at| now - *at > timeout);
+        let reason = if operation.as_deref() == Some(STATE_FAULT) {
+            Some("运行状态为故障")
+        } else if has_severe_alarm(conn, device_id).await? {
+            Some("有未处理的严重报警")
+        } else if !running && (timed_out || no_feedback.contains(&device_id)) {
+            Some("没有运行反馈")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => faulted.push(MemberFault { device_id, reason: reason.to_string() }),
+            None => available.push((device_id, device.operational_hours)),
+        }
+    }
+
+    let duty_count = group.duty_count.max(0) as usize;
+    let (next, rotated) = assign(&duty, &available, duty_count, group.rotation_hours);
+    let short = next.len() < duty_count;
+
+    // 每次故障只报警一次，运行中的泵被顶替时注明接替的泵
+    let new_faults: Vec<&MemberFault> = runtime.with(group.id, |g| {
+        g.alarmed.retain(|id| faulted.iter().any(|f| f.device_id == *id));
+        faulted.iter().filter(|f| !g.alarmed.contains(&f.device_id)).collect()
+    });
+    for fault in new_faults {
+        let replacement = next.iter().find(|id| !duty.contains(id));
+        let rule_name = match replacement {
+            Some(replacement) => format!(
+                "泵组 {}：泵 {} {}，切换到泵 {}",
+                group.name, fault.device_id, fault.reason, replacement
+            ),
+            None => format!("泵组 {}：泵 {} {}", group.name, fault.device_id, fault.reason),
+        };
+        alarm::raise(conn, Some(fault.device_id), rule_name, fault.device_id as f64, &config.severity)
+            .await?;
+        runtime.with(group.id, |g| g.alarmed.insert(fault.device_id));
+    }
+    let short_alarmed = runtime.with(group.id, |g| std::mem::replace(&mut g.short_alarmed, short));
+    if short && !short_alarmed {
+        let rule_name = format!("泵组 {} 可用泵不足 {} 台", group.name, duty_count);
+        alarm::raise(conn, None, rule_name, next.len() as f64, &config.severity).await?;
+    }
+
+    for &device_id in &members {
+        let run = next.contains(&device_id);
+        let commanded = runtime.with(group.id, |g| g.commanded.get(&device_id).copied());
+        if commanded == Some(run) {
+            continue;
+        }
+        match command(state, device_id, run).await {
+            Ok(true) => {
+                info!("Pump group {}: pump {} {}", group.name, device_id, if run { "started" } else { "stopped" });
+                runtime.with(group.id, |g| g.commanded.insert(device_id, run));
+            }
+            Ok(false) => {}
+            Err(e) => error!("Pump group {}: failed to command pump {}: {:?}", group.name, device_id, e),
+        }
+    }
+
+    if next != duty {
+        if rotated {
+            info!("Pump group {} rotated duty from {:?} to {:?}", group.name, duty, next);
+        } else {
+            warn!("Pump group {} reassigned duty from {:?} to {:?}", group.name, duty, next);
+        }
+        let mut active_model = group.clone().into_active_model();
+        active_model.duty = Set(serde_json::to_string(&next).unwrap_or_default());
+        if rotated {
+            active_model.last_rotated_at = Set(Some(now));
+        }
+        active_model.updated_at = Set(now);
+        active_model.update(conn).await.map_err(|_| AppError::InternalError)?;
+    }
+
+    let newly_timed_out: Vec<i32> = faulted
+        .iter()
+        .filter(|f| duty.contains(&f.device_id) && assigned.get(&f.device_id).is_some_and(|at| now - *at > timeout))
+        .map(|f| f.device_id)
+        .collect();
+    runtime.with(group.id, |g| {
+        g.assigned.retain(|id, _| next.contains(id));
+        for id in &next {
+            g.assigned.entry(*id).or_insert(now);
+        }
+        g.no_feedback.extend(newly_timed_out);
+        for id in &recovered {
+            g.no_feedback.remove(id);
+        }
+        g.status = DutyStatus {
+            group_id: group.id,
+            name: group.name.clone(),
+            lead: next.first().copied(),
+            lag: next.iter().skip(1).copied().collect(),
+            standby: available.iter().map(|(id, _)| *id).filter(|id| !next.contains(id)).collect(),
+            faulted,
+            short,
+            last_rotated_at: if rotated { Some(now) } else { group.last_rotated_at },
+            updated_at: Some(now),
+        };
+    });
+    Ok(())
+}
+
+async fn tick(
+    state: &AppState,
+    runtime: &PumpGroupRuntime,
+    config: &PumpGroupConfig,
+) -> Result<(), AppError> {
+    let groups = PumpGroupEntity::find()
+        .filter(PumpGroupColumn::Enabled.eq(true))
+        .all(state.db.get_connection())
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    runtime.retain(&groups.iter().map(|g| g.id).collect::<Vec<_>>());
+
+    let now = Utc::now();
+    for group in &groups {
+        if let Err(e) = tick_group(state, runtime, config, group, now).await {
+            error!("Pump group {} cycle failed: {:?}", group.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 后台任务：故障切换、按运行小时轮换并下发启停
+pub async fn run(state: Arc<AppState>) {
+    let config = state.settings.pump_group.clone();
+    let runtime = state.pump_groups.clone();
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(1)));
+    loop {
+        ticker.tick().await;
+        if let Err(e) = tick(&state, &runtime, &config).await {
+            error!("Pump group cycle failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_failover() {
+        // 1 号主泵故障，由运行最少的 3 号顶替，2 号辅泵保持原位
+        let available = [(2, 500.0), (3, 100.0), (4, 300.0)];
+        assert_eq!(assign(&[1, 2], &available, 2, 0.0), (vec![2, 3], false));
+        // 没有可用的备用泵时少于运行台数
+        assert_eq!(assign(&[1], &[], 1, 0.0), (vec![], false));
+    }
+
+    #[test]
+    fn test_assign_rotation() {
+        let available = [(1, 1000.0), (2, 960.0), (3, 950.0)];
+        // 差 50 小时，未到 100 小时的轮换间隔
+        assert_eq!(assign(&[1], &available, 1, 100.0), (vec![1], false));
+        assert_eq!(assign(&[1], &available, 1, 40.0), (vec![3], true));
+        // 换下运行最久的一台
+        assert_eq!(assign(&[2, 1], &available, 2, 40.0), (vec![2, 3], true));
+    }
+}
@@ -0,0 +1,217 @@
+//! PWM 调速
+//!
+//! 加药泵等设备按配置映射到 PWM 通道，占空比和频率须在通道的安全范围内；
+//! 占空比按设定速率逐步调整到目标值，避免泵速突变，新的设定会中断正在进行的调整。
+
+use crate::config::pwm::{PwmChannelConfig, PwmConfig};
+use crate::utils::error::AppError;
+use crate::utils::pwm::PwmController;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 调整占空比的步进间隔
+const RAMP_TICK: Duration = Duration::from_millis(100);
+
+/// 通道当前状态
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PwmStatus {
+    pub device_id: i32,
+    /// 当前占空比 (%)
+    pub duty_cycle: f64,
+    /// 目标占空比 (%)
+    pub target_duty_cycle: f64,
+    pub frequency_hz: f64,
+    /// 是否正在逐步调整
+    pub ramping: bool,
+}
+
+struct Channel {
+    config: PwmChannelConfig,
+    controller: tokio::sync::Mutex<Option<PwmController>>,
+    status: Mutex<PwmStatus>,
+    ramp: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// 从 `current` 向 `target` 移动不超过 `max_step`
+fn step_towards(current: f64, target: f64, max_step: f64) -> f64 {
+    if (target - current).abs() <= max_step {
+        target
+    } else {
+        current + max_step.copysign(target - current)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PwmManager {
+    channels: Arc<HashMap<i32, Arc<Channel>>>,
+}
+
+impl std::fmt::Debug for PwmManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PwmManager")
+            .field("devices", &self.channels.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Channel {
+    fn status(&self) -> PwmStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    async fn controller(&self) -> Result<PwmController, AppError> {
+        let mut controller = self.controller.lock().await;
+        if let Some(controller) = controller.as_ref() {
+            return Ok(controller.clone());
+        }
+        let opened = PwmController::open(self.config.chip, self.config.channel).await.map_err(|e| {
+            error!("Failed to open PWM {}/{}: {}", self.config.chip, self.config.channel, e);
+            AppError::ServiceUnavailable("PWM 通道不可用".into())
+        })?;
+        *controller = Some(opened.clone());
+        Ok(opened)
+    }
+
+    async fn write(&self, frequency_hz: f64, duty: f64) -> Result<(), AppError> {
+        self.controller()
+            .await?
+            .set(frequency_hz, duty)
+            .await
+            .map_err(|e| {
+                error!("Failed to write PWM for device {}: {}", self.config.device_id, e);
+                AppError::ServiceUnavailable("PWM 输出失败".into())
+            })
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PwmStatus)) {
+        f(&mut self.status.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+impl PwmManager {
+    pub fn new(config: &PwmConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let channels = config
+            .channels
+            .iter()
+            .map(|channel| {
+                let status = PwmStatus {
+                    device_id: channel.device_id,
+                    duty_cycle: 0.0,
+                    target_duty_cycle: 0.0,
+                    frequency_hz: channel.default_frequency_hz,
+                    ramping: false,
+                };
+                let channel = Channel {
+                    config: channel.clone(),
+                    controller: tokio::sync::Mutex::new(None),
+                    status: Mutex::new(status),
+                    ramp: Mutex::new(None),
+                };
+                (channel.config.device_id, Arc::new(channel))
+            })
+            .collect();
+
+        Self {
+            channels: Arc::new(channels),
+        }
+    }
+
+    fn channel(&self, device_id: i32) -> Result<&Arc<Channel>, AppError> {
+        self.channels.get(&device_id).ok_or(AppError::NotFound)
+    }
+
+    pub fn status(&self, device_id: i32) -> Result<PwmStatus, AppError> {
+        Ok(self.channel(device_id)?.status())
+    }
+
+    /// 设定占空比和频率，`ramp` 为 false 时直接跳变
+    pub async fn set(
+        &self,
+        device_id: i32,
+        duty_cycle: f64,
+        frequency_hz: Option<f64>,
+        ramp: bool,
+    ) -> Result<PwmStatus, AppError> {
+        let channel = self.channel(device_id)?.clone();
+        let config = &channel.config;
+
+        if !(duty_cycle >= config.min_duty && duty_cycle <= config.max_duty) {
+            return Err(AppError::InvalidInput(
+                format!("占空比须在 {} 到 {} 之间", config.min_duty, config.max_duty).into(),
+            ));
+        }
+        let frequency_hz = frequency_hz.unwrap_or(channel.status().frequency_hz);
+        if !(frequency_hz >= config.min_frequency_hz && frequency_hz <= config.max_frequency_hz) {
+            return Err(AppError::InvalidInput(
+                format!("频率须在 {} 到 {} Hz 之间", config.min_frequency_hz, config.max_frequency_hz).into(),
+            ));
+        }
+
+        if let Some(previous) = channel.ramp.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            previous.abort();
+        }
+
+        let current = channel.status().duty_cycle;
+        let ramp_rate = config.ramp_rate_pct_per_sec;
+        if !ramp || ramp_rate <= 0.0 || current == duty_cycle {
+            channel.write(frequency_hz, duty_cycle).await?;
+            channel.update(|s| {
+                s.duty_cycle = duty_cycle;
+                s.target_duty_cycle = duty_cycle;
+                s.frequency_hz = frequency_hz;
+                s.ramping = false;
+            });
+            info!("PWM for device {} set to {:.1}% @ {} Hz", device_id, duty_cycle, frequency_hz);
+            return Ok(channel.status());
+        }
+
+        // 频率立即生效，占空比从当前值开始逐步调整
+        channel.write(frequency_hz, current).await?;
+        channel.update(|s| {
+            s.target_duty_cycle = duty_cycle;
+            s.frequency_hz = frequency_hz;
+            s.ramping = true;
+        });
+
+        let ramping = channel.clone();
+        let handle = tokio::spawn(async move {
+            let max_step = ramp_rate * RAMP_TICK.as_secs_f64();
+            let mut ticker = tokio::time::interval(RAMP_TICK);
+            let mut duty = current;
+            while duty != duty_cycle {
+                ticker.tick().await;
+                duty = step_towards(duty, duty_cycle, max_step);
+                if let Err(e) = ramping.write(frequency_hz, duty).await {
+                    error!("PWM ramp for device {} stopped: {:?}", device_id, e);
+                    break;
+                }
+                ramping.update(|s| s.duty_cycle = duty);
+            }
+            ramping.update(|s| s.ramping = false);
+            info!("PWM for device {} at {:.1}% @ {} Hz", device_id, duty, frequency_hz);
+        });
+        *channel.ramp.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+
+        Ok(channel.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_towards() {
+        assert_eq!(step_towards(0.0, 50.0, 1.0), 1.0);
+        assert_eq!(step_towards(50.0, 20.0, 5.0), 45.0);
+        assert_eq!(step_towards(49.5, 50.0, 1.0), 50.0);
+    }
+}
@@ -0,0 +1,164 @@
+//! 测量值数据质量
+//!
+//! 入库前按 `quality.rules` 中的规则给每条数据打上质量标记：超出规则量程为 `out_of_range`，
+//! 数值连续 `frozen_minutes` 分钟不变为 `frozen`（传感器卡死），与上一条数据相比变化率超限为
+//! `suspect`（突跳），都不满足时为 `good`；`bad` 只由人工修改数据时设定。指标登记的有效范围
+//! 是物理上的硬限制，超出时仍直接拒绝入库。聚合、小时汇总和每日汇总默认排除 `bad` 和
+//! `out_of_range` 的数据，见 [`EXCLUDED_QUALITIES`]。
+//!
+//! 规则由调用方从 `AppState` 的配置传入，上报路径上随只读模式的入库流程一起持有。
+
+use crate::config::quality::{QualityConfig, QualityRule};
+use crate::models::measurement::{
+    Column as MeasurementColumn, Entity as MeasurementEntity, EXCLUDED_QUALITIES, QUALITIES,
+    QUALITY_FROZEN, QUALITY_GOOD, QUALITY_OUT_OF_RANGE, QUALITY_SUSPECT,
+};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::SimpleExpr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+
+/// 校验人工设定的质量标记
+pub fn parse(quality: &str) -> Result<&'static str, AppError> {
+    QUALITIES.iter().copied().find(|q| *q == quality).ok_or_else(|| {
+        AppError::InvalidInput(
+            format!("无效的数据质量: {}，可选 {}", quality, QUALITIES.join(" / ")).into(),
+        )
+    })
+}
+
+/// 聚合时排除坏数据的条件
+pub fn usable() -> SimpleExpr {
+    MeasurementColumn::Quality.is_not_in(EXCLUDED_QUALITIES)
+}
+
+fn out_of_range(rule: &QualityRule, value: f64) -> bool {
+    rule.min.is_some_and(|min| value < min) || rule.max.is_some_and(|max| value > max)
+}
+
+/// 相对上一条数据的变化率是否超限
+fn is_spike(
+    rule: &QualityRule,
+    previous: (DateTime<Utc>, f64),
+    timestamp: DateTime<Utc>,
+    value: f64,
+) -> bool {
+    let Some(max_rate) = rule.max_rate_per_min else {
+        return false;
+    };
+    let minutes = (timestamp - previous.0).num_milliseconds() as f64 / 60_000.0;
+    minutes > 0.0 && (value - previous.1).abs() / minutes > max_rate
+}
+
+fn series_filter(metric_type: &str, device_id: Option<i32>) -> sea_orm::Select<MeasurementEntity> {
+    let device = match device_id {
+        Some(device_id) => MeasurementColumn::DeviceId.eq(device_id),
+        None => MeasurementColumn::DeviceId.is_null(),
+    };
+    MeasurementEntity::find()
+        .filter(MeasurementColumn::MetricType.eq(metric_type))
+        .filter(device)
+}
+
+/// 此前 `minutes` 分钟内数值都与 `value` 相同，且更早的一条数据也相同
+async fn is_frozen(
+    conn: &DatabaseConnection,
+    metric_type: &str,
+    device_id: Option<i32>,
+    timestamp: DateTime<Utc>,
+    value: f64,
+    minutes: u64,
+) -> Result<bool, AppError> {
+    let since = timestamp - Duration::minutes(minutes as i64);
+    let changed = series_filter(metric_type, device_id)
+        .filter(MeasurementColumn::Timestamp.gte(since))
+        .filter(MeasurementColumn::Timestamp.lt(timestamp))
+        .filter(MeasurementColumn::Value.ne(value))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if changed > 0 {
+        return Ok(false);
+    }
+    let anchor = series_filter(metric_type, device_id)
+        .filter(MeasurementColumn::Timestamp.lte(since))
+        .order_by_desc(MeasurementColumn::Timestamp)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(anchor.is_some_and(|m| m.value == value))
+}
+
+/// 评估一条待入库数据的质量，`value` 为换算到默认单位后的值
+pub async fn assess(
+    conn: &DatabaseConnection,
+    config: &QualityConfig,
+    metric_type: &str,
+    device_id: Option<i32>,
+    timestamp: DateTime<Utc>,
+    value: f64,
+) -> Result<&'static str, AppError> {
+    let Some(rule) = config.rule_for(device_id, metric_type) else {
+        return Ok(QUALITY_GOOD);
+    };
+    if out_of_range(rule, value) {
+        return Ok(QUALITY_OUT_OF_RANGE);
+    }
+    if let Some(minutes) = rule.frozen_minutes.filter(|m| *m > 0) {
+        if is_frozen(conn, metric_type, device_id, timestamp, value, minutes).await? {
+            return Ok(QUALITY_FROZEN);
+        }
+    }
+    if rule.max_rate_per_min.is_some() {
+        let previous = series_filter(metric_type, device_id)
+            .filter(MeasurementColumn::Timestamp.lt(timestamp))
+            .order_by_desc(MeasurementColumn::Timestamp)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        if previous.is_some_and(|p| is_spike(rule, (p.timestamp, p.value), timestamp, value)) {
+            return Ok(QUALITY_SUSPECT);
+        }
+    }
+    Ok(QUALITY_GOOD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rules() {
+        let rule = QualityRule {
+            metric_type: "ph".to_string(),
+            min: Some(2.0),
+            max: Some(12.0),
+            max_rate_per_min: Some(1.0),
+            ..Default::default()
+        };
+        assert!(out_of_range(&rule, 12.5));
+        assert!(out_of_range(&rule, 1.0));
+        assert!(!out_of_range(&rule, 7.0));
+        assert!(!out_of_range(&QualityRule::default(), 1.0e9));
+
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        // 2 分钟变化 1.5，每分钟 0.75
+        assert!(!is_spike(&rule, (t0, 7.0), t0 + Duration::minutes(2), 8.5));
+        // 30 秒变化 1，每分钟 2
+        assert!(is_spike(&rule, (t0, 7.0), t0 + Duration::seconds(30), 8.0));
+        // 同一时刻的重复数据不判定
+        assert!(!is_spike(&rule, (t0, 7.0), t0, 9.0));
+
+        let config = QualityConfig {
+            enabled: true,
+            rules: vec![rule.clone(), QualityRule { device_id: Some(3), ..rule }],
+        };
+        assert_eq!(config.rule_for(Some(3), "ph").and_then(|r| r.device_id), Some(3));
+        assert_eq!(config.rule_for(Some(4), "ph").and_then(|r| r.device_id), None);
+        assert!(config.rule_for(Some(3), "tds").is_none());
+
+        assert_eq!(parse("frozen").unwrap(), QUALITY_FROZEN);
+        assert!(parse("unknown").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! 查询保护
+//!
+//! 原始数据查询按时间范围和假定采样间隔估算要扫描的行数，超过上限时返回 422，
+//! 并给出合适的 `/measurements/aggregate` 时间桶，避免一次画图请求拖垮网关。
+//! 未指定开始时间的查询只按页取最新数据，不做估算。
+
+use crate::config::query_guard::QueryGuardConfig;
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+
+/// 建议时间桶的候选值（秒）
+const BUCKET_STEPS: [i64; 8] = [60, 300, 900, 1800, 3600, 21600, 43200, 86400];
+
+/// 原始查询的范围
+#[derive(Debug, Clone, Copy)]
+pub struct RawQuery {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// 是否只查单台设备
+    pub single_device: bool,
+    /// 是否只查单个指标
+    pub single_metric: bool,
+    pub offset: u64,
+}
+
+/// 估算查询要扫描的行数
+fn estimate_rows(config: &QueryGuardConfig, range_secs: i64, device_count: u64, query: &RawQuery) -> u64 {
+    let per_series = range_secs.max(0) as u64 / config.sample_interval_secs.max(1);
+    let devices = if query.single_device { 1 } else { device_count.max(1) };
+    let metrics = if query.single_metric { 1 } else { metric_registry::METRICS.len() as u64 };
+    per_series.saturating_mul(devices).saturating_mul(metrics)
+}
+
+/// 使时间桶数不超过上限的最小候选桶长
+pub fn suggest_bucket(range_secs: i64, max_buckets: u64) -> i64 {
+    let needed = range_secs.max(1) / max_buckets.max(1) as i64;
+    BUCKET_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step >= needed)
+        .unwrap_or_else(|| (needed / 86400 + 1) * 86400)
+}
+
+fn too_large(message: String, range_secs: i64, config: &QueryGuardConfig) -> AppError {
+    let bucket = suggest_bucket(range_secs, config.max_buckets);
+    AppError::Unprocessable(
+        format!(
+            "{}，请缩小时间范围或改用 /measurements/aggregate?bucket_secs={} 查询聚合数据",
+            message, bucket
+        )
+        .into(),
+    )
+}
+
+/// 检查原始数据查询
+pub fn check_raw(config: &QueryGuardConfig, query: &RawQuery, device_count: u64) -> Result<(), AppError> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let end = query.end.unwrap_or_else(Utc::now);
+    let range_secs = query.start.map(|start| (end - start).num_seconds());
+
+    if query.offset > config.max_offset {
+        return Err(too_large(
+            format!("分页偏移 {} 超过上限 {}", query.offset, config.max_offset),
+            range_secs.unwrap_or(86400),
+            config,
+        ));
+    }
+
+    let Some(range_secs) = range_secs else {
+        return Ok(());
+    };
+    let estimated = estimate_rows(config, range_secs, device_count, query);
+    if estimated > config.max_raw_rows {
+        return Err(too_large(
+            format!("预计扫描约 {} 行原始数据，超过上限 {}", estimated, config.max_raw_rows),
+            range_secs,
+            config,
+        ));
+    }
+    Ok(())
+}
+
+/// 检查聚合查询的时间桶数
+pub fn check_buckets(
+    config: &QueryGuardConfig,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket_secs: i64,
+) -> Result<(), AppError> {
+    if bucket_secs <= 0 {
+        return Err(AppError::InvalidInput("bucket_secs 须为正数".into()));
+    }
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间须早于结束时间".into()));
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let range_secs = (end - start).num_seconds();
+    let buckets = (range_secs / bucket_secs) as u64;
+    if buckets > config.max_buckets {
+        let suggested = suggest_bucket(range_secs, config.max_buckets);
+        return Err(AppError::Unprocessable(
+            format!(
+                "时间桶数 {} 超过上限 {}，请使用 bucket_secs={} 或更大的值",
+                buckets, config.max_buckets, suggested
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn query(days: i64) -> RawQuery {
+        let end = Utc::now();
+        RawQuery {
+            start: Some(end - Duration::days(days)),
+            end: Some(end),
+            single_device: true,
+            single_metric: true,
+            offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_suggest_bucket() {
+        assert_eq!(suggest_bucket(3600, 2000), 60);
+        assert_eq!(suggest_bucket(30 * 86400, 2000), 1800);
+        assert_eq!(suggest_bucket(3 * 365 * 86400, 200), 2 * 86400);
+    }
+
+    #[test]
+    fn test_check_raw() {
+        let config = QueryGuardConfig::default();
+        // 10s 间隔下单序列 7 天约 6 万行，30 天约 26 万行
+        assert!(check_raw(&config, &query(7), 50).is_ok());
+        assert!(matches!(check_raw(&config, &query(30), 50), Err(AppError::Unprocessable(_))));
+
+        let all_devices = RawQuery { single_device: false, ..query(1) };
+        assert!(check_raw(&config, &all_devices, 50).is_err());
+
+        let unbounded = RawQuery { start: None, ..query(1) };
+        assert!(check_raw(&config, &unbounded, 50).is_ok());
+        assert!(check_raw(&config, &RawQuery { offset: 20_000, ..unbounded }, 50).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! 只读模式
+//!
+//! 维护期间由管理员手动开启，或磁盘空间不足时自动开启。只读期间所有修改类接口返回 503，
+//! 数据上报接口改为写入 redb 暂存并返回 202；退出只读后由后台任务按顺序补写入库。
+
+use crate::config::quality::QualityConfig;
+use crate::config::read_only::ReadOnlyConfig;
+use crate::database::redb::DbManager as RedbManager;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::measurement::Model as Measurement;
+use crate::services::cache::HotCache;
+use crate::services::compression::{Compressor, Decision};
+use crate::services::measurement::{self, NewMeasurement};
+use crate::services::metric_registry;
+use crate::utils::disk;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+const BUFFER_TABLE: &str = "ingest_buffer";
+/// 补写时每批读取的条数
+const REPLAY_BATCH: usize = 100;
+const MB: u64 = 1024 * 1024;
+
+/// 数据上报接口及其指标类型，`None` 表示指标类型由请求体给出
+pub const INGEST_PATHS: [(&str, Option<&str>); 7] = [
+    ("/measurements", None),
+    ("/ph-values", Some(metric_registry::PH)),
+    ("/tds-values", Some(metric_registry::TDS)),
+    ("/turbidity-values", Some(metric_registry::TURBIDITY)),
+    ("/flow-values", Some(metric_registry::FLOW)),
+    ("/level-values", Some(metric_registry::LEVEL)),
+    ("/pressure-values", Some(metric_registry::PRESSURE)),
+];
+
+/// 单条上报的处理结果
+#[derive(Debug)]
+pub enum Ingested {
+    /// 当前样本已入库
+    Stored(Measurement),
+    /// 只读期间写入暂存
+    Buffered,
+    /// 被压缩规则丢弃，或由旋转门暂留尚未入库
+    Suppressed,
+}
+
+/// 只读期间暂存的上报数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferedMeasurement {
+    pub metric_type: String,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub device_id: Option<i32>,
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub suppressed_count: i32,
+}
+
+/// 只读模式状态
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadOnlyStatus {
+    pub active: bool,
+    /// 管理员手动开启
+    pub manual: bool,
+    /// 因磁盘空间不足自动开启
+    pub low_disk: bool,
+    pub reason: Option<String>,
+    pub changed_by: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// 待补写的上报条数
+    pub buffered: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    manual: bool,
+    low_disk: bool,
+    reason: Option<String>,
+    changed_by: Option<String>,
+    since: Option<DateTime<Utc>>,
+}
+
+struct Buffer {
+    db: RedbManager,
+    /// 最后写入的序号，持有锁直到写入完成以保证顺序
+    last_id: tokio::sync::Mutex<u64>,
+}
+
+struct Inner {
+    config: ReadOnlyConfig,
+    active: AtomicBool,
+    state: Mutex<State>,
+    buffer: Option<Buffer>,
+    changed: Notify,
+    compressor: Compressor,
+    /// 入库时的数据质量检查规则
+    quality: QualityConfig,
+}
+
+#[derive(Clone)]
+pub struct ReadOnlyMode {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for ReadOnlyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadOnlyMode")
+            .field("active", &self.is_active())
+            .finish_non_exhaustive()
+    }
+}
+
+fn buffer_key(id: u64) -> String {
+    format!("{:020}", id)
+}
+
+impl ReadOnlyMode {
+    pub fn new(config: ReadOnlyConfig, compressor: Compressor, quality: QualityConfig) -> Self {
+        let buffer = match RedbManager::new(&config.buffer_path) {
+            Ok(db) => {
+                let last_id = db
+                    .last_key(BUFFER_TABLE)
+                    .ok()
+                    .flatten()
+                    .and_then(|key| key.parse::<u64>().ok())
+                    .unwrap_or(0);
+                Some(Buffer {
+                    db,
+                    last_id: tokio::sync::Mutex::new(last_id),
+                })
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to open ingest buffer {}: {}; ingestion will be rejected while read-only",
+                    config.buffer_path, e
+                );
+                None
+            }
+        };
+
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                active: AtomicBool::new(false),
+                state: Mutex::new(State::default()),
+                buffer,
+                changed: Notify::new(),
+                compressor,
+                quality,
+            }),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.inner.active.load(Ordering::Acquire)
+    }
+
+    fn update<F: FnOnce(&mut State)>(&self, f: F) {
+        let mut state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        let was_active = state.manual || state.low_disk;
+        f(&mut state);
+        let active = state.manual || state.low_disk;
+
+        if active != was_active {
+            state.since = active.then(Utc::now);
+            if active {
+                warn!("Read-only mode engaged: {}", state.reason.as_deref().unwrap_or("-"));
+            } else {
+                info!("Read-only mode released");
+            }
+        }
+        self.inner.active.store(active, Ordering::Release);
+        drop(state);
+        self.inner.changed.notify_one();
+    }
+
+    /// 管理员开启/关闭只读模式
+    pub fn set_manual(&self, enabled: bool, reason: Option<String>, changed_by: Option<String>) {
+        self.update(|state| {
+            state.manual = enabled;
+            state.changed_by = changed_by;
+            if enabled {
+                state.reason = Some(reason.unwrap_or_else(|| "维护中".to_string()));
+            } else if state.low_disk {
+                state.reason = Some("磁盘空间不足".to_string());
+            } else {
+                state.reason = None;
+            }
+        });
+    }
+
+    fn set_low_disk(&self, low_disk: bool) {
+        let current = self.inner.state.lock().unwrap_or_else(|e| e.into_inner()).low_disk;
+        if current == low_disk {
+            return;
+        }
+        self.update(|state| {
+            state.low_disk = low_disk;
+            if low_disk && !state.manual {
+                state.reason = Some("磁盘空间不足".to_string());
+                state.changed_by = None;
+            } else if !low_disk && !state.manual {
+                state.reason = None;
+            }
+        });
+    }
+
+    /// 修改类请求被拒绝时的提示
+    pub fn message(&self) -> String {
+        let state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        format!(
+            "系统处于只读模式（{}），暂不接受修改",
+            state.reason.as_deref().unwrap_or("维护中")
+        )
+    }
+
+    pub fn buffered_count(&self) -> u64 {
+        self.inner
+            .buffer
+            .as_ref()
+            .and_then(|buffer| buffer.db.len(BUFFER_TABLE).ok())
+            .unwrap_or(0)
+    }
+
+    pub fn status(&self) -> ReadOnlyStatus {
+        let buffered = self.buffered_count();
+        let state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        ReadOnlyStatus {
+            active: state.manual || state.low_disk,
+            manual: state.manual,
+            low_disk: state.low_disk,
+            reason: state.reason.clone(),
+            changed_by: state.changed_by.clone(),
+            since: state.since,
+            buffered,
+        }
+    }
+
+    /// 暂存一条上报数据
+    pub async fn buffer(&self, measurement: BufferedMeasurement) -> Result<(), AppError> {
+        let Some(buffer) = &self.inner.buffer else {
+            return Err(AppError::ServiceUnavailable(self.message().into()));
+        };
+        if buffer.db.len(BUFFER_TABLE)? >= self.inner.config.max_buffered {
+            return Err(AppError::ServiceUnavailable("只读期间的上报暂存已满".into()));
+        }
+
+        let json = serde_json::to_string(&measurement).map_err(|_| AppError::InternalError)?;
+        let mut last_id = buffer.last_id.lock().await;
+        let id = *last_id + 1;
+        buffer.db.put(BUFFER_TABLE, &buffer_key(id), &json)?;
+        *last_id = id;
+        Ok(())
+    }
+
+    /// 写入一条上报数据：先按规则压缩，只读期间暂存，否则直接入库，返回是否被暂存
+    pub async fn ingest(
+        &self,
+        conn: &DatabaseConnection,
+        cache: &HotCache,
+        new: NewMeasurement,
+    ) -> Result<bool, AppError> {
+        let ingested = self.ingest_one(conn, cache, new).await?;
+        Ok(matches!(ingested, Ingested::Buffered))
+    }
+
+    /// 同 [`Self::ingest`]，当前样本入库时返回入库记录，供单条上报接口响应
+    pub async fn ingest_one(
+        &self,
+        conn: &DatabaseConnection,
+        cache: &HotCache,
+        new: NewMeasurement,
+    ) -> Result<Ingested, AppError> {
+        match self.inner.compressor.check(&new) {
+            Decision::Suppress => Ok(Ingested::Suppressed),
+            Decision::Store { suppressed } => self.store(conn, cache, new, suppressed).await,
+            // 旋转门暂存的样本入库失败与当前样本无关，只记日志
+            Decision::StoreHeld { held, suppressed } => {
+                match self.store(conn, cache, held, suppressed).await {
+                    Err(AppError::InvalidInput(msg)) => {
+                        warn!("Dropping held measurement: {}", msg);
+                        Ok(Ingested::Suppressed)
+                    }
+                    Ok(Ingested::Stored(_)) => Ok(Ingested::Suppressed),
+                    result => result,
+                }
+            }
+        }
+    }
+
+    async fn store(
+        &self,
+        conn: &DatabaseConnection,
+        cache: &HotCache,
+        new: NewMeasurement,
+        suppressed_count: i32,
+    ) -> Result<Ingested, AppError> {
+        if !self.is_active() {
+            let quality = &self.inner.quality;
+            let measurement =
+                measurement::create_compressed(conn, cache, quality, new, suppressed_count).await?;
+            return Ok(Ingested::Stored(measurement));
+        }
+
+        self.buffer(BufferedMeasurement {
+            metric_type: new.metric_type,
+            timestamp: new.timestamp,
+            value: new.value,
+            device_id: new.device_id,
+            unit: new.unit,
+            suppressed_count,
+        })
+        .await?;
+        Ok(Ingested::Buffered)
+    }
+
+    fn check_disk(&self) {
+        let config = &self.inner.config;
+        if !config.auto_on_low_disk {
+            return;
+        }
+        let Some(free) = disk::free_bytes(Path::new(&config.disk_path)) else {
+            return;
+        };
+
+        let threshold = config.min_free_disk_mb * MB;
+        let low_disk = self.inner.state.lock().unwrap_or_else(|e| e.into_inner()).low_disk;
+        if !low_disk && free < threshold {
+            self.set_low_disk(true);
+        } else if low_disk && free > threshold + threshold / 10 {
+            self.set_low_disk(false);
+        }
+    }
+
+    /// 暂存文件是否可用，服务运行时文件被占用，其他进程打不开
+    pub fn buffer_available(&self) -> bool {
+        self.inner.buffer.is_some()
+    }
+
+    /// 把暂存的上报数据按顺序写入数据库，再次进入只读时中止，返回写入的条数
+    pub async fn replay(&self, db: &DbManager, cache: &HotCache) -> Result<u64, AppError> {
+        let Some(buffer) = &self.inner.buffer else {
+            return Ok(0);
+        };
+
+        let mut replayed = 0u64;
+        while !self.is_active() {
+            let batch = buffer.db.range_after::<String>(BUFFER_TABLE, None, REPLAY_BATCH)?;
+            if batch.is_empty() {
+                break;
+            }
+
+            for (key, json) in batch {
+                if self.is_active() {
+                    break;
+                }
+                match serde_json::from_str::<BufferedMeasurement>(&json) {
+                    Ok(m) => {
+                        let new = NewMeasurement {
+                            metric_type: m.metric_type,
+                            timestamp: m.timestamp,
+                            value: m.value,
+                            device_id: m.device_id,
+                            unit: m.unit,
+                        };
+                        let conn = db.get_connection();
+                        let (quality, suppressed) = (&self.inner.quality, m.suppressed_count);
+                        match measurement::create_compressed(conn, cache, quality, new, suppressed)
+                            .await
+                        {
+                            Ok(_) => replayed += 1,
+                            Err(AppError::InvalidInput(msg)) => {
+                                warn!("Dropping buffered measurement {}: {}", key, msg)
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    Err(e) => warn!("Dropping unreadable buffered measurement {}: {}", key, e),
+                }
+                buffer.db.delete(BUFFER_TABLE, &key)?;
+            }
+        }
+
+        if replayed > 0 {
+            info!("Replayed {} measurements buffered during read-only mode", replayed);
+        }
+        Ok(replayed)
+    }
+}
+
+/// 后台任务：定期检查磁盘空间，退出只读后补写暂存的上报数据
+pub async fn run_worker(mode: ReadOnlyMode, db: DbManager, cache: HotCache) {
+    let interval = Duration::from_secs(mode.inner.config.check_interval_secs.max(1));
+
+    loop {
+        mode.check_disk();
+        if !mode.is_active() {
+            if let Err(e) = mode.replay(&db, &cache).await {
+                error!("Failed to replay buffered measurements: {:?}", e);
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = mode.inner.changed.notified() => {}
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! 执行器状态核对
+//!
+//! 服务重启（尤其是断电恢复）后读取 GPIO 输出和可写 Modbus 寄存器的实际状态，
+//! 与数据库中最后一次成功下发的值比较，不一致时记录日志并按配置报警。
+//! 核对只读不写，不会重新下发任何输出，由操作人员确认后再处理。
+
+use crate::config::modbus::{ModbusConfig, RegisterKind};
+use crate::config::reconcile::ReconcileConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::modbus_write::{
+    Column as ModbusWriteColumn, Entity as ModbusWriteEntity, STATUS_WRITTEN,
+};
+use crate::services::alarm;
+use crate::services::gpio_output::{self, GpioOutputs};
+use crate::utils::error::AppError;
+use crate::utils::modbus::ModbusClient;
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// 与最后一次下发一致
+pub const STATUS_MATCH: &str = "match";
+/// 与最后一次下发不一致
+pub const STATUS_MISMATCH: &str = "mismatch";
+/// 从未下发过，只记录实际状态
+pub const STATUS_NO_COMMAND: &str = "no_command";
+/// 无法读取实际状态
+pub const STATUS_UNREADABLE: &str = "unreadable";
+
+/// 单个执行器的核对结果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ActuatorCheck {
+    /// gpio / holding / coil
+    pub kind: String,
+    pub device_id: Option<i32>,
+    /// GPIO 输出名称或寄存器地址
+    pub target: String,
+    pub label: String,
+    /// 最后一次下发的值，GPIO 和线圈为 0/1
+    pub commanded: Option<f64>,
+    pub commanded_at: Option<DateTime<Utc>>,
+    pub actual: Option<f64>,
+    /// match / mismatch / no_command / unreadable
+    pub status: String,
+    pub message: Option<String>,
+}
+
+/// 核对结果汇总
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Reconciliation {
+    pub checked_at: DateTime<Utc>,
+    pub mismatches: usize,
+    pub unreadable: usize,
+    pub actuators: Vec<ActuatorCheck>,
+}
+
+/// 比较下发值与实际值；保持寄存器允许半个比例系数的误差
+fn compare(
+    commanded: Option<f64>,
+    actual: Result<f64, String>,
+    tolerance: f64,
+) -> (&'static str, Option<String>) {
+    match (commanded, actual) {
+        (_, Err(e)) => (STATUS_UNREADABLE, Some(e)),
+        (None, Ok(_)) => (STATUS_NO_COMMAND, None),
+        (Some(commanded), Ok(actual)) if (commanded - actual).abs() <= tolerance => {
+            (STATUS_MATCH, None)
+        }
+        (Some(commanded), Ok(actual)) => {
+            (STATUS_MISMATCH, Some(format!("最后下发 {}，实际 {}", commanded, actual)))
+        }
+    }
+}
+
+async fn check_gpio(
+    conn: &DatabaseConnection,
+    gpio: &GpioOutputs,
+) -> Result<Vec<ActuatorCheck>, AppError> {
+    let mut checks = Vec::new();
+    for output in gpio.outputs() {
+        let last = gpio_output::last_written(conn, &output.name).await?;
+        let actual = gpio
+            .read_back(output)
+            .await
+            .map(|high| high as u8 as f64)
+            .map_err(|e| e.to_string());
+        let commanded = last.as_ref().map(|w| w.value as u8 as f64);
+        let (status, message) = compare(commanded, actual.clone(), 0.0);
+        checks.push(ActuatorCheck {
+            kind: "gpio".to_string(),
+            device_id: output.device_id,
+            target: output.name.clone(),
+            label: output.name.clone(),
+            commanded,
+            commanded_at: last.map(|w| w.created_at),
+            actual: actual.ok(),
+            status: status.to_string(),
+            message,
+        });
+    }
+    Ok(checks)
+}
+
+async fn check_modbus(
+    conn: &DatabaseConnection,
+    config: &ModbusConfig,
+) -> Result<Vec<ActuatorCheck>, AppError> {
+    let mut checks = Vec::new();
+    if !config.enabled {
+        return Ok(checks);
+    }
+    let timeout = Duration::from_millis(config.timeout_ms);
+    for device in &config.devices {
+        if device.writable.is_empty() {
+            continue;
+        }
+        let mut client = ModbusClient::connect(&device.target, device.unit_id, timeout)
+            .await
+            .map_err(|e| e.to_string());
+
+        for register in &device.writable {
+            let last = ModbusWriteEntity::find()
+                .filter(ModbusWriteColumn::DeviceId.eq(device.device_id))
+                .filter(ModbusWriteColumn::Kind.eq(register.kind.as_str()))
+                .filter(ModbusWriteColumn::Address.eq(register.address as i32))
+                .filter(ModbusWriteColumn::Status.eq(STATUS_WRITTEN))
+                .order_by_desc(ModbusWriteColumn::Id)
+                .one(conn)
+                .await
+                .map_err(|_| AppError::InternalError)?;
+
+            let actual = match client.as_mut() {
+                Err(e) => Err(e.clone()),
+                Ok(client) => match register.kind {
+                    RegisterKind::Holding => client
+                        .read_holding_registers(register.address, 1)
+                        .await
+                        .map(|raw| raw.first().copied().unwrap_or_default() as f64)
+                        .map(|raw| raw * register.scale),
+                    RegisterKind::Coil => client
+                        .read_coils(register.address, 1)
+                        .await
+                        .map(|coils| coils.first().copied().unwrap_or_default() as u8 as f64),
+                }
+                .map_err(|e| e.to_string()),
+            };
+            let commanded = last.as_ref().and_then(|w| w.written_value);
+            let tolerance = match register.kind {
+                RegisterKind::Holding => register.scale.abs() / 2.0,
+                RegisterKind::Coil => 0.0,
+            };
+            let (status, message) = compare(commanded, actual.clone(), tolerance);
+            checks.push(ActuatorCheck {
+                kind: register.kind.as_str().to_string(),
+                device_id: Some(device.device_id),
+                target: register.address.to_string(),
+                label: register.label.clone(),
+                commanded,
+                commanded_at: last.map(|w| w.created_at),
+                actual: actual.ok(),
+                status: status.to_string(),
+                message,
+            });
+        }
+    }
+    Ok(checks)
+}
+
+/// 读取全部执行器的实际状态并与最后一次下发比较
+pub async fn check(
+    conn: &DatabaseConnection,
+    gpio: &GpioOutputs,
+    modbus: &ModbusConfig,
+) -> Result<Reconciliation, AppError> {
+    let mut actuators = check_gpio(conn, gpio).await?;
+    actuators.extend(check_modbus(conn, modbus).await?);
+    Ok(Reconciliation {
+        checked_at: Utc::now(),
+        mismatches: actuators.iter().filter(|a| a.status == STATUS_MISMATCH).count(),
+        unreadable: actuators.iter().filter(|a| a.status == STATUS_UNREADABLE).count(),
+        actuators,
+    })
+}
+
+/// 启动时核对一次，不一致时记录日志并按配置报警
+pub async fn run_startup(
+    config: ReconcileConfig,
+    modbus: ModbusConfig,
+    gpio: GpioOutputs,
+    db: DbManager,
+) {
+    let conn = db.get_connection();
+    let result = match check(conn, &gpio, &modbus).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Actuator reconciliation failed: {:?}", e);
+            return;
+        }
+    };
+    info!(
+        "Actuator reconciliation: {} checked, {} mismatched, {} unreadable",
+        result.actuators.len(),
+        result.mismatches,
+        result.unreadable
+    );
+
+    for actuator in &result.actuators {
+        match actuator.status.as_str() {
+            STATUS_MISMATCH => {
+                warn!(
+                    "Actuator {} {} ({}) mismatch after restart: commanded {:?}, actual {:?}",
+                    actuator.kind,
+                    actuator.target,
+                    actuator.label,
+                    actuator.commanded,
+                    actuator.actual
+                );
+                if !config.alarm_on_mismatch {
+                    continue;
+                }
+                let rule_name = format!("执行器状态不一致: {} {}", actuator.kind, actuator.label);
+                let value = actuator.actual.unwrap_or_default();
+                let (device_id, severity) = (actuator.device_id, &config.severity);
+                if let Err(e) = alarm::raise(conn, device_id, rule_name, value, severity).await {
+                    error!("Failed to raise reconciliation alarm: {:?}", e);
+                }
+            }
+            STATUS_UNREADABLE => warn!(
+                "Actuator {} {} ({}) state unreadable after restart: {}",
+                actuator.kind,
+                actuator.target,
+                actuator.label,
+                actuator.message.as_deref().unwrap_or_default()
+            ),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare(Some(1.0), Ok(1.0), 0.0).0, STATUS_MATCH);
+        assert_eq!(compare(Some(1.0), Ok(0.0), 0.0).0, STATUS_MISMATCH);
+        assert_eq!(compare(Some(12.3), Ok(12.34), 0.05).0, STATUS_MATCH);
+        assert_eq!(compare(None, Ok(1.0), 0.0).0, STATUS_NO_COMMAND);
+        assert_eq!(compare(Some(1.0), Err("timeout".to_string()), 0.0).0, STATUS_UNREADABLE);
+    }
+}
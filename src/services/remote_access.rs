@@ -0,0 +1,328 @@
+//! 厂商远程访问代理
+//!
+//! 为设备的 Modbus/HTTP 等 TCP 接口开启限时代理端口，所有流量按方向、时间戳记录到文件，
+//! 到期或被手动关闭后自动断开全部连接，代替临时开放端口转发。代理端口只接受开启会话时登记的
+//! 厂商来源 IP，其他地址的连接直接断开并记入流量记录。
+
+use crate::config::remote_access::RemoteAccessConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::middleware::network_policy::Cidr;
+use crate::models::remote_session::{
+    ActiveModel as RemoteSessionActiveModel, Entity as RemoteSessionEntity,
+    Model as RemoteSession,
+};
+use crate::utils::error::AppError;
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::{EntityTrait, IntoActiveModel, Set};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{tcp::OwnedReadHalf, tcp::OwnedWriteHalf, TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+/// 开启会话的参数
+#[derive(Debug, Clone)]
+pub struct OpenSession {
+    pub vendor: String,
+    pub reason: String,
+    pub target: String,
+    pub client_ip: String,
+    pub duration_minutes: u32,
+}
+
+/// 流量记录器，串口控制台也用它记录收发内容
+pub(crate) struct Recorder {
+    file: Mutex<tokio::fs::File>,
+    pub(crate) bytes_to_target: AtomicU64,
+    pub(crate) bytes_from_target: AtomicU64,
+}
+
+impl Recorder {
+    pub(crate) fn new(file: tokio::fs::File) -> Self {
+        Self {
+            file: Mutex::new(file),
+            bytes_to_target: AtomicU64::new(0),
+            bytes_from_target: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) async fn record(&self, connection: u64, to_target: bool, data: &[u8]) {
+        let (direction, counter) = if to_target {
+            ("C>S", &self.bytes_to_target)
+        } else {
+            ("S>C", &self.bytes_from_target)
+        };
+        counter.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+        let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+        let line = format!(
+            "{} #{} {} {} {}\n",
+            Utc::now().to_rfc3339(),
+            connection,
+            direction,
+            data.len(),
+            hex
+        );
+        let mut file = self.file.lock().await;
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            error!("Failed to write session recording: {}", e);
+        }
+    }
+
+    pub(crate) async fn note(&self, connection: u64, message: &str) {
+        let line = format!("{} #{} -- {}\n", Utc::now().to_rfc3339(), connection, message);
+        let mut file = self.file.lock().await;
+        let _ = file.write_all(line.as_bytes()).await;
+    }
+}
+
+/// 远程访问会话管理
+#[derive(Debug, Clone)]
+pub struct RemoteAccessManager {
+    config: Arc<RemoteAccessConfig>,
+    allowed_targets: Arc<Vec<Cidr>>,
+    active: Arc<Mutex<HashMap<i32, watch::Sender<bool>>>>,
+}
+
+impl RemoteAccessManager {
+    pub fn new(config: RemoteAccessConfig) -> Self {
+        let allowed_targets = config
+            .allowed_targets
+            .iter()
+            .filter_map(|cidr| {
+                let parsed = Cidr::parse(cidr);
+                if parsed.is_none() {
+                    warn!("Ignoring invalid remote access target network: {}", cidr);
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            config: Arc::new(config),
+            allowed_targets: Arc::new(allowed_targets),
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 开启代理会话
+    pub async fn open(&self, db: &DbManager, request: OpenSession) -> Result<RemoteSession, AppError> {
+        if !self.config.enabled {
+            return Err(AppError::InvalidInput("远程访问代理未启用".into()));
+        }
+
+        let target: SocketAddr = request
+            .target
+            .parse()
+            .map_err(|_| AppError::InvalidInput("目标地址格式应为 ip:port".into()))?;
+        if !self.allowed_targets.iter().any(|net| net.contains(target.ip())) {
+            return Err(AppError::Forbidden);
+        }
+        let client_ip: IpAddr = request
+            .client_ip
+            .trim()
+            .parse()
+            .map_err(|_| AppError::InvalidInput("来源地址应为 IP 地址".into()))?;
+        if request.duration_minutes == 0 || request.duration_minutes > self.config.max_duration_minutes {
+            return Err(AppError::InvalidInput(
+                format!("访问时长须在 1 到 {} 分钟之间", self.config.max_duration_minutes).into(),
+            ));
+        }
+        if request.vendor.trim().is_empty() || request.reason.trim().is_empty() {
+            return Err(AppError::InvalidInput("必须填写访问人员与原因".into()));
+        }
+
+        let listener = TcpListener::bind((self.config.bind_host.as_str(), 0)).await?;
+        let listen_port = listener.local_addr()?.port();
+
+        tokio::fs::create_dir_all(&self.config.recording_dir).await?;
+        let now = Utc::now();
+        let expires_at = now + ChronoDuration::minutes(request.duration_minutes as i64);
+
+        let new_session = RemoteSessionActiveModel {
+            vendor: Set(request.vendor),
+            reason: Set(request.reason),
+            target: Set(target.to_string()),
+            client_ip: Set(Some(client_ip.to_string())),
+            listen_port: Set(listen_port as i32),
+            recording_path: Set(String::new()),
+            bytes_to_target: Set(0),
+            bytes_from_target: Set(0),
+            expires_at: Set(expires_at),
+            closed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        let session = RemoteSessionEntity::insert(new_session)
+            .exec_with_returning(db.get_connection())
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        let recording_path: PathBuf = [
+            self.config.recording_dir.as_str(),
+            &format!("session-{}.log", session.id),
+        ]
+        .iter()
+        .collect();
+        let file = tokio::fs::File::create(&recording_path).await?;
+
+        let mut session_active_model = session.into_active_model();
+        session_active_model.recording_path = Set(recording_path.to_string_lossy().into_owned());
+        let session = RemoteSessionEntity::update(session_active_model)
+            .exec(db.get_connection())
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        let recorder = Arc::new(Recorder::new(file));
+        recorder
+            .note(
+                0,
+                &format!(
+                    "session opened by {} from {} for {}: {}",
+                    session.vendor, client_ip, target, session.reason
+                ),
+            )
+            .await;
+
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        self.active.lock().await.insert(session.id, cancel_tx);
+
+        let duration = (expires_at - now).to_std().unwrap_or_default();
+        tokio::spawn(run_session(
+            self.clone(),
+            db.clone(),
+            session.id,
+            listener,
+            client_ip,
+            target,
+            recorder,
+            duration,
+            cancel_rx,
+        ));
+
+        info!(
+            "Remote access session {} opened on port {} -> {}",
+            session.id, listen_port, target
+        );
+        Ok(session)
+    }
+
+    /// 提前关闭会话
+    pub async fn close(&self, id: i32) -> bool {
+        match self.active.lock().await.remove(&id) {
+            Some(cancel) => {
+                let _ = cancel.send(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_session(
+    manager: RemoteAccessManager,
+    db: DbManager,
+    id: i32,
+    listener: TcpListener,
+    client_ip: IpAddr,
+    target: SocketAddr,
+    recorder: Arc<Recorder>,
+    duration: std::time::Duration,
+    mut cancel_rx: watch::Receiver<bool>,
+) {
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    let mut connections = JoinSet::new();
+    let mut connection_no = 0u64;
+
+    let reason = loop {
+        tokio::select! {
+            _ = &mut deadline => break "expired",
+            _ = cancel_rx.changed() => break "closed by operator",
+            accepted = listener.accept() => match accepted {
+                Ok((client, peer)) if peer.ip() != client_ip => {
+                    drop(client);
+                    recorder.note(0, &format!("rejected connection from {}", peer)).await;
+                    warn!("Remote access session {} rejected connection from {}", id, peer);
+                }
+                Ok((client, peer)) => {
+                    connection_no += 1;
+                    recorder.note(connection_no, &format!("client {} connected", peer)).await;
+                    connections.spawn(proxy_connection(client, target, recorder.clone(), connection_no));
+                }
+                Err(e) => warn!("Remote access session {} accept error: {}", id, e),
+            },
+        }
+    };
+
+    connections.abort_all();
+    drop(listener);
+    manager.active.lock().await.remove(&id);
+    recorder.note(0, &format!("session ended: {}", reason)).await;
+
+    let conn = db.get_connection();
+    match RemoteSessionEntity::find_by_id(id).one(conn).await {
+        Ok(Some(session)) => {
+            let now = Utc::now();
+            let mut active_model = session.into_active_model();
+            active_model.closed_at = Set(Some(now));
+            active_model.bytes_to_target = Set(recorder.bytes_to_target.load(Ordering::Relaxed) as i64);
+            active_model.bytes_from_target = Set(recorder.bytes_from_target.load(Ordering::Relaxed) as i64);
+            active_model.updated_at = Set(now);
+            if let Err(e) = RemoteSessionEntity::update(active_model).exec(conn).await {
+                error!("Failed to close remote access session {}: {}", id, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to load remote access session {}: {}", id, e),
+    }
+
+    info!("Remote access session {} ended: {}", id, reason);
+}
+
+async fn proxy_connection(client: TcpStream, target: SocketAddr, recorder: Arc<Recorder>, connection: u64) {
+    let upstream = match TcpStream::connect(target).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            recorder.note(connection, &format!("connect to target failed: {}", e)).await;
+            return;
+        }
+    };
+
+    let (client_read, client_write) = client.into_split();
+    let (upstream_read, upstream_write) = upstream.into_split();
+
+    tokio::select! {
+        _ = pipe(client_read, upstream_write, recorder.clone(), connection, true) => {}
+        _ = pipe(upstream_read, client_write, recorder.clone(), connection, false) => {}
+    }
+
+    recorder.note(connection, "connection closed").await;
+}
+
+async fn pipe(
+    mut from: OwnedReadHalf,
+    mut to: OwnedWriteHalf,
+    recorder: Arc<Recorder>,
+    connection: u64,
+    to_target: bool,
+) {
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = match from.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        recorder.record(connection, to_target, &buf[..n]).await;
+        if to.write_all(&buf[..n]).await.is_err() {
+            break;
+        }
+    }
+}
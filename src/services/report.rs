@@ -0,0 +1,519 @@
+//! 合规报表
+//!
+//! 按日、按周汇总排放量（逐日累计流量）、pH 超标次数、报警统计和设备运行时长，
+//! 渲染为 PDF/XLSX（见 [`crate::services::report_render`]），保存到本地目录或 S3，
+//! 并在 `reports` 表中登记。后台任务每天在 `generate_hour` 之后生成前一天的日报，
+//! 每周一生成上一周的周报；同一周期重新生成时覆盖旧文件。
+//!
+//! 日界按 `timezone.default` 时区的本地零点划分，夏令时切换日的日报覆盖 23 或 25 小时。
+//! 排放量取自 `flow_totals` 的逐日累计流量（见 [`crate::services::flow_total`]），日界与报表一致；
+//! 平均和最大流量按整点的小时汇总统计，时区偏移不是整小时的地区会有半小时以内的误差。
+//! pH 统计与小时汇总一样排除质量为 `bad` / `out_of_range` 的数据。
+
+use crate::config::report::ReportConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::{Column as AlarmLogColumn, Entity as AlarmLogEntity, SEVERITIES};
+use crate::models::device::Entity as DeviceEntity;
+use crate::models::flow_total::{
+    Column as FlowTotalColumn, Entity as FlowTotalEntity, PERIOD_DAY as FLOW_PERIOD_DAY,
+};
+use crate::models::hourly_summary::{Column as HourlySummaryColumn, Entity as HourlySummaryEntity};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::models::report::{
+    ActiveModel as ReportActiveModel, Column as ReportColumn, Entity as ReportEntity,
+    Model as Report, FORMAT_PDF, FORMAT_XLSX, KIND_DAILY, KIND_WEEKLY,
+};
+use crate::models::device_state_event::{CATEGORY_OPERATION, STATE_RUNNING};
+use crate::services::{device_state, quality, report_render};
+use crate::utils::error::AppError;
+use crate::utils::s3::S3Client;
+use crate::utils::timezone;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use chrono_tz::Tz;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+pub const STORAGE_LOCAL: &str = "local";
+pub const STORAGE_S3: &str = "s3";
+
+/// 设备的排放量
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DischargeRow {
+    pub device_id: i32,
+    pub device_name: String,
+    /// 排放量（m³），周期内逐日累计流量之和
+    pub volume_m3: f64,
+    /// 小时平均流量的均值，没有瞬时流量数据时为 0
+    pub avg_flow: f64,
+    pub max_flow: f64,
+    /// 有瞬时流量数据的小时数
+    pub hours: i64,
+}
+
+/// 设备的 pH 统计
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PhRow {
+    pub device_id: i32,
+    pub device_name: String,
+    pub samples: i64,
+    /// 超出允许范围的测量值条数
+    pub excursions: i64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// 各严重程度的报警数
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AlarmRow {
+    pub severity: String,
+    pub count: i64,
+    /// 尚未处理的条数
+    pub unprocessed: i64,
+}
+
+/// 设备运行时长
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RuntimeRow {
+    pub device_id: i32,
+    pub device_name: String,
+    pub run_hours: f64,
+    pub alarm_count: i64,
+}
+
+/// 报表内容
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReportData {
+    pub kind: String,
+    pub period_start: NaiveDate,
+    /// 周期末日之后的一天
+    pub period_end: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub ph_min: f64,
+    pub ph_max: f64,
+    pub total_volume_m3: f64,
+    pub discharge: Vec<DischargeRow>,
+    pub ph: Vec<PhRow>,
+    pub alarms: Vec<AlarmRow>,
+    pub runtime: Vec<RuntimeRow>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PhStats {
+    device_id: i32,
+    samples: i64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PhExcursions {
+    device_id: i32,
+    excursions: i64,
+}
+
+/// 报表周期，`end` 为末日之后的一天
+pub fn period(kind: &str, date: NaiveDate) -> Result<(NaiveDate, NaiveDate), AppError> {
+    match kind {
+        KIND_DAILY => Ok((date, date + Duration::days(1))),
+        KIND_WEEKLY => {
+            let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            Ok((start, start + Duration::days(7)))
+        }
+        other => Err(AppError::InvalidInput(format!("未知的报表类型: {}", other).into())),
+    }
+}
+
+/// 汇总周期内的数据，周期按 `tz` 的本地日期划分
+pub async fn collect(
+    conn: &DatabaseConnection,
+    config: &ReportConfig,
+    tz: Tz,
+    kind: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<ReportData, AppError> {
+    let (from, to) = (timezone::midnight(tz, start), timezone::midnight(tz, end));
+    let devices = DeviceEntity::find().all(conn).await.map_err(|_| AppError::InternalError)?;
+    let names: HashMap<i32, String> =
+        devices.iter().map(|device| (device.id, device.name.clone())).collect();
+    let name = |id: i32| names.get(&id).cloned().unwrap_or_else(|| format!("设备 {}", id));
+
+    // 排放量
+    let days = FlowTotalEntity::find()
+        .filter(FlowTotalColumn::Period.eq(FLOW_PERIOD_DAY))
+        .filter(FlowTotalColumn::PeriodStart.gte(start))
+        .filter(FlowTotalColumn::PeriodStart.lt(end))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let hours = HourlySummaryEntity::find()
+        .filter(HourlySummaryColumn::MetricType.eq(config.flow_metric.as_str()))
+        .filter(HourlySummaryColumn::Hour.gte(from))
+        .filter(HourlySummaryColumn::Hour.lt(to))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let empty = |device_id: i32| DischargeRow {
+        device_id,
+        device_name: name(device_id),
+        volume_m3: 0.0,
+        avg_flow: 0.0,
+        max_flow: 0.0,
+        hours: 0,
+    };
+    let mut discharge: BTreeMap<i32, DischargeRow> = BTreeMap::new();
+    for day in days {
+        let row = discharge.entry(day.device_id).or_insert_with(|| empty(day.device_id));
+        row.volume_m3 += day.volume;
+    }
+    for hour in hours {
+        let row = discharge.entry(hour.device_id).or_insert_with(|| empty(hour.device_id));
+        row.avg_flow += hour.avg;
+        row.max_flow = row.max_flow.max(hour.max);
+        row.hours += 1;
+    }
+    let discharge: Vec<DischargeRow> = discharge
+        .into_values()
+        .map(|mut row| {
+            if row.hours > 0 {
+                row.avg_flow /= row.hours as f64;
+            }
+            row
+        })
+        .collect();
+
+    // pH 超标
+    let ph_stats = MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::DeviceId)
+        .column_as(MeasurementColumn::Id.count(), "samples")
+        .column_as(MeasurementColumn::Value.min(), "min")
+        .column_as(MeasurementColumn::Value.max(), "max")
+        .filter(MeasurementColumn::MetricType.eq(config.ph_metric.as_str()))
+        .filter(MeasurementColumn::DeviceId.is_not_null())
+        .filter(MeasurementColumn::Timestamp.gte(from))
+        .filter(MeasurementColumn::Timestamp.lt(to))
+        .filter(quality::usable())
+        .group_by(MeasurementColumn::DeviceId)
+        .into_model::<PhStats>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let excursions: HashMap<i32, i64> = MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::DeviceId)
+        .column_as(MeasurementColumn::Id.count(), "excursions")
+        .filter(MeasurementColumn::MetricType.eq(config.ph_metric.as_str()))
+        .filter(MeasurementColumn::DeviceId.is_not_null())
+        .filter(MeasurementColumn::Timestamp.gte(from))
+        .filter(MeasurementColumn::Timestamp.lt(to))
+        .filter(quality::usable())
+        .filter(
+            Condition::any()
+                .add(MeasurementColumn::Value.lt(config.ph_min))
+                .add(MeasurementColumn::Value.gt(config.ph_max)),
+        )
+        .group_by(MeasurementColumn::DeviceId)
+        .into_model::<PhExcursions>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|row| (row.device_id, row.excursions))
+        .collect();
+    let mut ph: Vec<PhRow> = ph_stats
+        .into_iter()
+        .map(|row| PhRow {
+            device_id: row.device_id,
+            device_name: name(row.device_id),
+            samples: row.samples,
+            excursions: excursions.get(&row.device_id).copied().unwrap_or(0),
+            min: row.min,
+            max: row.max,
+        })
+        .collect();
+    ph.sort_by_key(|row| row.device_id);
+
+    // 报警
+    let alarm_logs = AlarmLogEntity::find()
+        .filter(AlarmLogColumn::TriggerTime.gte(from))
+        .filter(AlarmLogColumn::TriggerTime.lt(to))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let alarms = SEVERITIES
+        .iter()
+        .map(|severity| {
+            let logs = alarm_logs.iter().filter(|log| log.severity == *severity);
+            AlarmRow {
+                severity: severity.to_string(),
+                count: logs.clone().count() as i64,
+                unprocessed: logs.filter(|log| !log.is_processed).count() as i64,
+            }
+        })
+        .collect();
+
+    // 运行时长，每日汇总按 UTC 日划分，这里直接按本地周期统计
+    let mut device_alarms: HashMap<i32, i64> = HashMap::new();
+    for device_id in alarm_logs.iter().filter_map(|log| log.device_id) {
+        *device_alarms.entry(device_id).or_default() += 1;
+    }
+    let mut runtime = Vec::new();
+    for device in &devices {
+        let run_seconds: i64 =
+            device_state::durations(conn, device.id, CATEGORY_OPERATION, from, to, false)
+                .await?
+                .iter()
+                .filter(|d| d.state == STATE_RUNNING)
+                .map(|d| d.seconds)
+                .sum();
+        let alarm_count = device_alarms.get(&device.id).copied().unwrap_or(0);
+        if run_seconds > 0 || alarm_count > 0 {
+            runtime.push(RuntimeRow {
+                device_id: device.id,
+                device_name: device.name.clone(),
+                run_hours: run_seconds as f64 / 3600.0,
+                alarm_count,
+            });
+        }
+    }
+
+    Ok(ReportData {
+        kind: kind.to_string(),
+        period_start: start,
+        period_end: end,
+        generated_at: Utc::now(),
+        ph_min: config.ph_min,
+        ph_max: config.ph_max,
+        total_volume_m3: discharge.iter().map(|row| row.volume_m3).sum(),
+        discharge,
+        ph,
+        alarms,
+        runtime,
+    })
+}
+
+/// 报表文件名，例如 `daily-2026-10-15.pdf`
+pub fn file_name(kind: &str, start: NaiveDate, format: &str) -> String {
+    format!("{}-{}.{}", kind, start.format("%Y-%m-%d"), format)
+}
+
+pub fn content_type(format: &str) -> &'static str {
+    match format {
+        FORMAT_PDF => "application/pdf",
+        FORMAT_XLSX => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn store(config: &ReportConfig, name: &str, body: Vec<u8>) -> io::Result<(String, String)> {
+    let storage = &config.storage;
+    match storage.backend.as_str() {
+        STORAGE_S3 => {
+            let client = S3Client::new(&storage.s3)?;
+            let key = client.key(name);
+            client.put_object(&key, body).await?;
+            Ok((STORAGE_S3.to_string(), key))
+        }
+        _ => {
+            let dir = PathBuf::from(&storage.directory);
+            tokio::fs::create_dir_all(&dir).await?;
+            let path = dir.join(name);
+            tokio::fs::write(&path, body).await?;
+            Ok((STORAGE_LOCAL.to_string(), path.to_string_lossy().into_owned()))
+        }
+    }
+}
+
+/// 读取报表文件
+pub async fn load(config: &ReportConfig, report: &Report) -> Result<Vec<u8>, AppError> {
+    let result = match report.storage.as_str() {
+        STORAGE_S3 => match S3Client::new(&config.storage.s3) {
+            Ok(client) => client.get_object(&report.location).await,
+            Err(e) => Err(e),
+        },
+        _ => tokio::fs::read(&report.location).await,
+    };
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound,
+        _ => {
+            warn!("Failed to read report {}: {}", report.location, e);
+            AppError::ServiceUnavailable("报表文件读取失败".into())
+        }
+    })
+}
+
+/// 生成 `date` 所在周期的报表，返回各格式的登记记录
+pub async fn generate(
+    conn: &DatabaseConnection,
+    config: &ReportConfig,
+    tz: Tz,
+    kind: &str,
+    date: NaiveDate,
+) -> Result<Vec<Report>, AppError> {
+    let (start, end) = period(kind, date)?;
+    let data = collect(conn, config, tz, kind, start, end).await?;
+
+    let mut reports = Vec::new();
+    for format in &config.formats {
+        let body = match format.as_str() {
+            FORMAT_PDF => report_render::pdf(&data, config.pdf_font.as_deref()),
+            FORMAT_XLSX => report_render::xlsx(&data),
+            other => {
+                warn!("Unknown report format {}, skipped", other);
+                continue;
+            }
+        }
+        .map_err(|e| {
+            error!("Failed to render {} report: {}", format, e);
+            AppError::InternalError
+        })?;
+
+        let size = body.len() as i64;
+        let (storage, location) = store(config, &file_name(kind, start, format), body)
+            .await
+            .map_err(|e| {
+                error!("Failed to store {} report: {}", format, e);
+                AppError::ServiceUnavailable("报表文件保存失败".into())
+            })?;
+
+        // 同一周期同一格式只保留最新的一份
+        ReportEntity::delete_many()
+            .filter(ReportColumn::Kind.eq(kind))
+            .filter(ReportColumn::PeriodStart.eq(start))
+            .filter(ReportColumn::Format.eq(format.as_str()))
+            .exec(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+        let report = ReportEntity::insert(ReportActiveModel {
+            kind: Set(kind.to_string()),
+            period_start: Set(start),
+            period_end: Set(end),
+            format: Set(format.clone()),
+            storage: Set(storage),
+            location: Set(location),
+            size_bytes: Set(size),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        })
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+        reports.push(report);
+    }
+
+    info!("Generated {} report for {} .. {}", kind, start, end);
+    Ok(reports)
+}
+
+/// 报表列表，最新的在前
+pub async fn list(
+    conn: &DatabaseConnection,
+    kind: Option<&str>,
+    page: u64,
+    per_page: u64,
+) -> Result<Vec<Report>, AppError> {
+    let mut select = ReportEntity::find();
+    if let Some(kind) = kind {
+        select = select.filter(ReportColumn::Kind.eq(kind));
+    }
+    select
+        .order_by_desc(ReportColumn::PeriodStart)
+        .order_by_asc(ReportColumn::Kind)
+        .order_by_asc(ReportColumn::Format)
+        .offset((page.max(1) - 1) * per_page)
+        .limit(per_page)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+async fn exists(conn: &DatabaseConnection, kind: &str, start: NaiveDate) -> Result<bool, AppError> {
+    let count = ReportEntity::find()
+        .filter(ReportColumn::Kind.eq(kind))
+        .filter(ReportColumn::PeriodStart.eq(start))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(count > 0)
+}
+
+/// 当前应当已经生成的日报、周报周期，按 `tz` 的本地时间判断
+fn due(config: &ReportConfig, tz: Tz, now: DateTime<Utc>) -> Vec<(&'static str, NaiveDate)> {
+    let mut due = Vec::new();
+    let now = now.with_timezone(&tz);
+    if now.hour() < config.generate_hour {
+        return due;
+    }
+    let yesterday = now.date_naive() - Duration::days(1);
+    if config.daily {
+        due.push((KIND_DAILY, yesterday));
+    }
+    if config.weekly {
+        // 上一个完整的周（周一至周日）
+        let this_monday = period(KIND_WEEKLY, now.date_naive()).map(|(start, _)| start);
+        if let Ok(this_monday) = this_monday {
+            due.push((KIND_WEEKLY, this_monday - Duration::days(7)));
+        }
+    }
+    due
+}
+
+/// 后台任务：按计划生成报表
+pub async fn run_scheduler(config: ReportConfig, tz: Tz, db: DbManager) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(600));
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        for (kind, date) in due(&config, tz, Utc::now()) {
+            match exists(conn, kind, date).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to check {} report for {}: {:?}", kind, date, e);
+                    continue;
+                }
+            }
+            if let Err(e) = generate(conn, &config, tz, kind, date).await {
+                error!("Failed to generate {} report for {}: {:?}", kind, date, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_period_and_due() {
+        let thursday = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        assert_eq!(period(KIND_DAILY, thursday).unwrap(), (thursday, thursday + Duration::days(1)));
+        assert_eq!(period(KIND_WEEKLY, thursday).unwrap(), (monday, monday + Duration::days(7)));
+        assert!(period("monthly", thursday).is_err());
+
+        let config = ReportConfig::default();
+        let early = Utc.with_ymd_and_hms(2026, 10, 16, 0, 30, 0).unwrap();
+        assert!(due(&config, Tz::UTC, early).is_empty());
+        let later = Utc.with_ymd_and_hms(2026, 10, 16, 2, 0, 0).unwrap();
+        let expected = vec![(KIND_DAILY, thursday), (KIND_WEEKLY, monday - Duration::days(7))];
+        assert_eq!(due(&config, Tz::UTC, later), expected);
+        // 上海 10-16 01:30 已过生成时刻，纽约还是 10-15 晚上
+        let shanghai = timezone::parse("Asia/Shanghai").unwrap();
+        let new_york = timezone::parse("America/New_York").unwrap();
+        let evening = Utc.with_ymd_and_hms(2026, 10, 15, 17, 30, 0).unwrap();
+        assert_eq!(due(&config, shanghai, evening), expected);
+        assert_eq!(due(&config, new_york, evening)[0], (KIND_DAILY, thursday - Duration::days(1)));
+        assert_eq!(file_name(KIND_DAILY, thursday, FORMAT_PDF), "daily-2026-10-15.pdf");
+    }
+}
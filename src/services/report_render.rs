@@ -0,0 +1,266 @@
+//! 报表渲染
+//!
+//! 先把 [`ReportData`] 整理成若干表格，再分别输出为 PDF（printpdf）和 XLSX（rust_xlsxwriter）。
+//! PDF 内置字体不含中文，需要中文时通过 `report.pdf_font` 指定 TTF 字体文件。
+
+use crate::models::report::KIND_WEEKLY;
+use crate::services::report::ReportData;
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference};
+use rust_xlsxwriter::{Format, Workbook};
+use std::io;
+use tracing::warn;
+
+/// 单元格
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+impl Cell {
+    fn display(&self) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Number(value) => format!("{:.2}", value),
+        }
+    }
+}
+
+/// 报表中的一张表
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: &'static str,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+fn text(value: impl Into<String>) -> Cell {
+    Cell::Text(value.into())
+}
+
+fn number(value: impl Into<f64>) -> Cell {
+    Cell::Number(value.into())
+}
+
+pub fn title(data: &ReportData) -> String {
+    let kind = match data.kind.as_str() {
+        KIND_WEEKLY => "Weekly",
+        _ => "Daily",
+    };
+    let last = data.period_end.pred_opt().unwrap_or(data.period_end);
+    format!("{} compliance report {} - {}", kind, data.period_start, last)
+}
+
+/// 把报表内容整理成表格
+pub fn sections(data: &ReportData) -> Vec<Section> {
+    let summary = Section {
+        title: "Summary",
+        headers: vec!["Item", "Value"],
+        rows: vec![
+            vec![text("Total discharge (m3)"), number(data.total_volume_m3)],
+            vec![text("pH limits"), text(format!("{} - {}", data.ph_min, data.ph_max))],
+            vec![
+                text("pH excursions"),
+                number(data.ph.iter().map(|row| row.excursions).sum::<i64>() as f64),
+            ],
+            vec![
+                text("Alarms"),
+                number(data.alarms.iter().map(|row| row.count).sum::<i64>() as f64),
+            ],
+            vec![text("Generated at"), text(data.generated_at.to_rfc3339())],
+        ],
+    };
+
+    let discharge = Section {
+        title: "Discharge",
+        headers: vec!["Device", "Volume (m3)", "Avg flow", "Max flow", "Hours"],
+        rows: data
+            .discharge
+            .iter()
+            .map(|row| {
+                vec![
+                    text(&row.device_name),
+                    number(row.volume_m3),
+                    number(row.avg_flow),
+                    number(row.max_flow),
+                    number(row.hours as f64),
+                ]
+            })
+            .collect(),
+    };
+    let ph = Section {
+        title: "pH excursions",
+        headers: vec!["Device", "Samples", "Excursions", "Min", "Max"],
+        rows: data
+            .ph
+            .iter()
+            .map(|row| {
+                vec![
+                    text(&row.device_name),
+                    number(row.samples as f64),
+                    number(row.excursions as f64),
+                    number(row.min),
+                    number(row.max),
+                ]
+            })
+            .collect(),
+    };
+    let alarms = Section {
+        title: "Alarms",
+        headers: vec!["Severity", "Count", "Unprocessed"],
+        rows: data
+            .alarms
+            .iter()
+            .map(|row| {
+                vec![
+                    text(&row.severity),
+                    number(row.count as f64),
+                    number(row.unprocessed as f64),
+                ]
+            })
+            .collect(),
+    };
+    let runtime = Section {
+        title: "Equipment runtime",
+        headers: vec!["Device", "Run hours", "Alarms"],
+        rows: data
+            .runtime
+            .iter()
+            .map(|row| {
+                vec![
+                    text(&row.device_name),
+                    number(row.run_hours),
+                    number(row.alarm_count as f64),
+                ]
+            })
+            .collect(),
+    };
+    vec![summary, discharge, ph, alarms, runtime]
+}
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const LINE_HEIGHT: f32 = 6.0;
+
+fn font(doc: &PdfDocumentReference, path: Option<&str>) -> io::Result<IndirectFontRef> {
+    if let Some(path) = path {
+        match std::fs::File::open(path).map_err(|e| e.to_string()).and_then(|file| {
+            doc.add_external_font(io::BufReader::new(file)).map_err(|e| e.to_string())
+        }) {
+            Ok(font) => return Ok(font),
+            Err(e) => warn!("Failed to load PDF font {}: {}, using Helvetica", path, e),
+        }
+    }
+    doc.add_builtin_font(BuiltinFont::Helvetica).map_err(io::Error::other)
+}
+
+/// 渲染 PDF，A4 纵向，表格按列等宽排布，写满一页后换页
+pub fn pdf(data: &ReportData, font_path: Option<&str>) -> io::Result<Vec<u8>> {
+    let title = title(data);
+    let (doc, page, layer) =
+        PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let font = font(&doc, font_path)?;
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    layer.use_text(&title, 16.0, Mm(MARGIN), Mm(y), &font);
+    y -= LINE_HEIGHT * 2.0;
+
+    for section in sections(data) {
+        let width = (PAGE_WIDTH - MARGIN * 2.0) / section.headers.len() as f32;
+        let headers: Vec<String> = section.headers.iter().map(|h| h.to_string()).collect();
+        let lines = std::iter::once(headers)
+            .chain(section.rows.iter().map(|row| row.iter().map(Cell::display).collect()));
+
+        // 标题和表头不单独留在页尾
+        if y < MARGIN + LINE_HEIGHT * 3.0 {
+            let (page, index) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            layer = doc.get_page(page).get_layer(index);
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        layer.use_text(section.title, 12.0, Mm(MARGIN), Mm(y), &font);
+        y -= LINE_HEIGHT * 1.5;
+
+        for cells in lines {
+            if y < MARGIN {
+                let (page, index) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+                layer = doc.get_page(page).get_layer(index);
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            for (i, cell) in cells.iter().enumerate() {
+                let x = MARGIN + width * i as f32;
+                layer.use_text(cell.as_str(), 9.0, Mm(x), Mm(y), &font);
+            }
+            y -= LINE_HEIGHT;
+        }
+        y -= LINE_HEIGHT;
+    }
+
+    doc.save_to_bytes().map_err(io::Error::other)
+}
+
+/// 渲染 XLSX，每张表一个工作表
+pub fn xlsx(data: &ReportData) -> io::Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    for section in sections(data) {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(section.title).map_err(io::Error::other)?;
+        for (col, header) in section.headers.iter().enumerate() {
+            sheet
+                .write_string_with_format(0, col as u16, *header, &bold)
+                .map_err(io::Error::other)?;
+        }
+        for (row, cells) in section.rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let (row, col) = (row as u32 + 1, col as u16);
+                match cell {
+                    Cell::Text(value) => sheet.write_string(row, col, value),
+                    Cell::Number(value) => sheet.write_number(row, col, *value),
+                }
+                .map_err(io::Error::other)?;
+            }
+        }
+        sheet.autofit();
+    }
+    workbook.save_to_buffer().map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::report::{AlarmRow, DischargeRow};
+    use chrono::{NaiveDate, Utc};
+
+    #[test]
+    fn test_sections() {
+        let start = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let data = ReportData {
+            kind: KIND_WEEKLY.to_string(),
+            period_start: start,
+            period_end: start + chrono::Duration::days(7),
+            generated_at: Utc::now(),
+            ph_min: 6.0,
+            ph_max: 9.0,
+            total_volume_m3: 120.0,
+            discharge: vec![DischargeRow {
+                device_id: 1,
+                device_name: "outlet".to_string(),
+                volume_m3: 120.0,
+                avg_flow: 5.0,
+                max_flow: 8.0,
+                hours: 24,
+            }],
+            ph: Vec::new(),
+            alarms: vec![AlarmRow { severity: "major".to_string(), count: 3, unprocessed: 1 }],
+            runtime: Vec::new(),
+        };
+        assert_eq!(title(&data), "Weekly compliance report 2026-10-12 - 2026-10-18");
+        let sections = sections(&data);
+        assert_eq!(sections.len(), 5);
+        assert_eq!(sections[0].rows[3], vec![text("Alarms"), number(3.0)]);
+        assert_eq!(sections[1].rows[0][1], Cell::Number(120.0));
+        assert!(sections.iter().all(|s| s.rows.iter().all(|r| r.len() == s.headers.len())));
+    }
+}
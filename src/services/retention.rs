@@ -0,0 +1,85 @@
+//! 测量值保留期清理与月分区维护
+//!
+//! 分区模式下先删除过期的整月分区，再删除跨越保留边界的那个月中过期的行；未分区时直接按时间删除。
+//! 保留边界取 `timezone.default` 时区的本地零点，保留期内的每一天都是完整的本地日。
+//! 有数据被删除时清空热点缓存中的最新值，避免长期无上报的设备仍返回已删除的值。
+
+use crate::config::retention::RetentionConfig;
+use crate::database::partition;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::services::cache::HotCache;
+use crate::utils::error::AppError;
+use crate::utils::timezone;
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+
+/// 删除保留期之前的测量值，返回删除的分区数和行数
+async fn prune(
+    conn: &DatabaseConnection,
+    config: &RetentionConfig,
+    tz: Tz,
+    partitioned: bool,
+) -> Result<(usize, u64), AppError> {
+    let today = timezone::local_date(tz, Utc::now());
+    let cutoff = timezone::midnight(tz, today - Duration::days(config.measurement_days as i64));
+    let mut dropped = 0;
+    if partitioned {
+        dropped = partition::drop_expired(conn, cutoff)
+            .await
+            .map_err(|e| {
+                error!("Failed to drop expired partitions: {:?}", e);
+                AppError::InternalError
+            })?
+            .len();
+    }
+    let result = MeasurementEntity::delete_many()
+        .filter(MeasurementColumn::Timestamp.lt(cutoff))
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok((dropped, result.rows_affected))
+}
+
+/// 后台任务：定期创建后续月分区并清理过期数据
+///
+/// `partitioned` 为启动时 [`partition::ensure_partitioned`] 的结果。
+pub async fn run_job(
+    config: RetentionConfig,
+    tz: Tz,
+    db: DbManager,
+    cache: HotCache,
+    partitioned: bool,
+) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.check_interval_secs.max(60)));
+
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+
+        if partitioned {
+            let today = Utc::now().date_naive();
+            let count = config.partitions_ahead.max(1);
+            if let Err(e) = partition::ensure_months(conn, today, count).await {
+                error!("Failed to create measurement partitions: {:?}", e);
+            }
+        }
+
+        if config.enabled {
+            match prune(conn, &config, tz, partitioned).await {
+                Ok((0, 0)) => {}
+                // 整月分区删除时已逐个记录日志
+                Ok((_, rows)) => {
+                    if rows > 0 {
+                        info!("Pruned {} expired measurements", rows);
+                    }
+                    cache.clear_latest().await;
+                }
+                Err(e) => error!("Failed to prune measurements: {:?}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! 小时汇总
+//!
+//! 后台任务在每个整小时结束后把各设备各指标的最小/最大/平均值写入 `hourly_summaries`，
+//! 从已汇总的最后一小时之后继续，首次启动回看 `backfill_hours`。补录数据由每日汇总的重算
+//! 一并处理：重新汇总某一天时同时重算当天的小时汇总。日汇总见 [`crate::services::daily_summary`]。
+
+use crate::config::summary::HourlyRollupConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::hourly_summary::{
+    ActiveModel as HourlySummaryActiveModel, Column as HourlySummaryColumn,
+    Entity as HourlySummaryEntity,
+};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::services::measurement::bucket_expr;
+use crate::services::quality;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+
+const HOUR_SECS: i64 = 3600;
+
+#[derive(Debug, FromQueryResult)]
+struct HourAggregate {
+    device_id: i32,
+    metric_type: String,
+    bucket: i64,
+    min: f64,
+    max: f64,
+    avg: f64,
+    count: i64,
+}
+
+/// 所在小时的起点
+pub fn hour_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::hours(1)).unwrap_or(time)
+}
+
+/// 重新计算 `[start, end)` 内各整小时的汇总，返回写入的行数
+pub async fn materialize_hours(
+    conn: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    let (start, end) = (hour_start(start), hour_start(end));
+    if start >= end {
+        return Ok(0);
+    }
+
+    let bucket = bucket_expr(conn.get_database_backend(), HOUR_SECS);
+    let rows = MeasurementEntity::find()
+        .select_only()
+        .column(MeasurementColumn::DeviceId)
+        .column(MeasurementColumn::MetricType)
+        .column_as(Expr::cust(bucket.clone()), "bucket")
+        .column_as(MeasurementColumn::Value.min(), "min")
+        .column_as(MeasurementColumn::Value.max(), "max")
+        .column_as(Func::avg(Expr::col(MeasurementColumn::Value)), "avg")
+        .column_as(MeasurementColumn::Id.count(), "count")
+        .filter(MeasurementColumn::DeviceId.is_not_null())
+        .filter(MeasurementColumn::Timestamp.gte(start))
+        .filter(MeasurementColumn::Timestamp.lt(end))
+        .filter(quality::usable())
+        .group_by(MeasurementColumn::DeviceId)
+        .group_by(MeasurementColumn::MetricType)
+        .group_by(Expr::cust(bucket))
+        .into_model::<HourAggregate>()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let now = Utc::now();
+    let count = rows.len();
+    let txn = conn.begin().await.map_err(|_| AppError::InternalError)?;
+    HourlySummaryEntity::delete_many()
+        .filter(HourlySummaryColumn::Hour.gte(start))
+        .filter(HourlySummaryColumn::Hour.lt(end))
+        .exec(&txn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if !rows.is_empty() {
+        let rows = rows.into_iter().map(|row| HourlySummaryActiveModel {
+            device_id: Set(row.device_id),
+            metric_type: Set(row.metric_type),
+            hour: Set(DateTime::from_timestamp(row.bucket, 0).unwrap_or_default()),
+            min: Set(row.min),
+            max: Set(row.max),
+            avg: Set(row.avg),
+            count: Set(row.count),
+            created_at: Set(now),
+            ..Default::default()
+        });
+        HourlySummaryEntity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|_| AppError::InternalError)?;
+    }
+    txn.commit().await.map_err(|_| AppError::InternalError)?;
+    Ok(count)
+}
+
+/// 下一个待汇总的小时
+async fn next_hour(
+    conn: &DatabaseConnection,
+    backfill_hours: u32,
+) -> Result<DateTime<Utc>, sea_orm::DbErr> {
+    let earliest = hour_start(Utc::now()) - Duration::hours(backfill_hours as i64);
+    let latest = HourlySummaryEntity::find()
+        .order_by_desc(HourlySummaryColumn::Hour)
+        .one(conn)
+        .await?;
+    Ok(latest.map_or(earliest, |row| (row.hour + Duration::hours(1)).max(earliest)))
+}
+
+/// 后台任务：整小时结束后写入小时汇总
+pub async fn run_scheduler(config: HourlyRollupConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.interval_secs.max(60)));
+    let mut from: Option<DateTime<Utc>> = None;
+
+    loop {
+        ticker.tick().await;
+        let conn = db.get_connection();
+        let start = match from {
+            Some(start) => start,
+            None => match next_hour(conn, config.backfill_hours).await {
+                Ok(start) => start,
+                Err(e) => {
+                    error!("Failed to read hourly rollup progress: {:?}", e);
+                    continue;
+                }
+            },
+        };
+        let end = hour_start(Utc::now());
+        if start >= end {
+            from = Some(start);
+            continue;
+        }
+
+        match materialize_hours(conn, start, end).await {
+            Ok(rows) => {
+                info!("Materialized hourly rollups {} .. {} ({} rows)", start, end, rows);
+                from = Some(end);
+            }
+            Err(e) => error!("Failed to materialize hourly rollups from {}: {:?}", start, e),
+        }
+    }
+}
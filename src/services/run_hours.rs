@@ -0,0 +1,212 @@
+//! 设备运行小时累计
+//!
+//! `devices.operational_hours` 由运行状态事件自动累计：设备离开 running 状态时计入本次运行时长，
+//! 持续运行的设备由后台任务定期结算到当前时刻。`run_hours_accrued_at` 记录已结算到的时刻，
+//! 两条路径都只计入该时刻之后的部分，不会重复累计。通过设备接口手动修改运行小时数时，
+//! 视为在修改时刻重新设定基准。每天的运行时长和启动次数由状态事件回放得出，供泵、风机轮换参考。
+
+use crate::config::run_hours::RunHoursConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity, Model as Device};
+use crate::models::device_state_event::{
+    Column as DeviceStateEventColumn, Entity as DeviceStateEventEntity, CATEGORY_OPERATION,
+    STATE_RUNNING,
+};
+use crate::services::device_state;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration as StdDuration;
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+/// 把 `running_since` 到 `until` 的运行时长计入运行小时数，返回计入的小时数
+///
+/// 已结算过的部分跳过；并发结算时只有一方生效。
+pub async fn accrue<C: ConnectionTrait>(
+    conn: &C,
+    device_id: i32,
+    running_since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<f64, AppError> {
+    let Some(device) = DeviceEntity::find_by_id(device_id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+    else {
+        return Ok(0.0);
+    };
+    let from = device.run_hours_accrued_at.map_or(running_since, |at| at.max(running_since));
+    if until <= from {
+        return Ok(0.0);
+    }
+
+    let hours = (until - from).num_milliseconds() as f64 / 3_600_000.0;
+    let unchanged = match device.run_hours_accrued_at {
+        Some(at) => DeviceColumn::RunHoursAccruedAt.eq(at),
+        None => DeviceColumn::RunHoursAccruedAt.is_null(),
+    };
+    let result = DeviceEntity::update_many()
+        .col_expr(DeviceColumn::OperationalHours, Expr::col(DeviceColumn::OperationalHours).add(hours))
+        .col_expr(DeviceColumn::RunHoursAccruedAt, Expr::value(Some(until)))
+        .filter(DeviceColumn::Id.eq(device_id))
+        .filter(unchanged)
+        .exec(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    Ok(if result.rows_affected > 0 { hours } else { 0.0 })
+}
+
+/// 把正在运行的设备的运行小时数结算到 `now`，返回结算的设备数
+pub async fn checkpoint(conn: &DatabaseConnection, now: DateTime<Utc>) -> Result<usize, AppError> {
+    // devices.status 与运行状态事件同步，1 为运行
+    let running = DeviceEntity::find()
+        .filter(DeviceColumn::Status.eq(1))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut count = 0;
+    for device in running {
+        let Some(since) = device_state::running_since(conn, device.id).await? else {
+            continue;
+        };
+        if accrue(conn, device.id, since, now).await? > 0.0 {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 某天的运行情况（UTC 自然日）
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct RunDay {
+    pub date: NaiveDate,
+    pub run_hours: f64,
+    /// 当天进入运行状态的次数
+    pub starts: u32,
+}
+
+/// 设备运行小时与每天的运行历史
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeviceRunHours {
+    pub device_id: i32,
+    pub name: String,
+    pub device_type: String,
+    /// 累计运行小时数，含当前这次运行尚未结算的部分
+    pub operational_hours: f64,
+    /// 当前处于运行状态时为进入运行的时间
+    pub running_since: Option<DateTime<Utc>>,
+    /// 查询天数内的运行小时合计
+    pub period_hours: f64,
+    pub days: Vec<RunDay>,
+}
+
+/// 按天汇总运行秒数和启动次数，没有运行的日期补零
+fn run_days(
+    first: NaiveDate,
+    last: NaiveDate,
+    run_seconds: &[(DateTime<Utc>, i64)],
+    starts: &[DateTime<Utc>],
+) -> Vec<RunDay> {
+    let mut days: BTreeMap<NaiveDate, RunDay> = first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .map(|date| (date, RunDay { date, run_hours: 0.0, starts: 0 }))
+        .collect();
+    for (period_start, seconds) in run_seconds {
+        if let Some(day) = days.get_mut(&period_start.date_naive()) {
+            day.run_hours += *seconds as f64 / 3600.0;
+        }
+    }
+    for start in starts {
+        if let Some(day) = days.get_mut(&start.date_naive()) {
+            day.starts += 1;
+        }
+    }
+    days.into_values().collect()
+}
+
+/// 设备最近 `days` 天（含今天）的运行历史
+pub async fn history(
+    conn: &DatabaseConnection,
+    device: &Device,
+    days: u32,
+    now: DateTime<Utc>,
+) -> Result<DeviceRunHours, AppError> {
+    let last = now.date_naive();
+    let first = last - Duration::days(days.max(1) as i64 - 1);
+    let start = first.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+
+    let run_seconds: Vec<(DateTime<Utc>, i64)> =
+        device_state::durations(conn, device.id, CATEGORY_OPERATION, start, now, true)
+            .await?
+            .into_iter()
+            .filter(|d| d.state == STATE_RUNNING)
+            .map(|d| (d.period_start, d.seconds))
+            .collect();
+    let starts: Vec<DateTime<Utc>> = DeviceStateEventEntity::find()
+        .filter(DeviceStateEventColumn::DeviceId.eq(device.id))
+        .filter(DeviceStateEventColumn::State.eq(STATE_RUNNING))
+        .filter(DeviceStateEventColumn::Timestamp.gte(start))
+        .filter(DeviceStateEventColumn::Timestamp.lt(now))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .map(|event| event.timestamp)
+        .collect();
+
+    let running_since = device_state::running_since(conn, device.id).await?;
+    let pending = match running_since {
+        Some(since) => {
+            let from = device.run_hours_accrued_at.map_or(since, |at| at.max(since));
+            (now - from).num_milliseconds().max(0) as f64 / 3_600_000.0
+        }
+        None => 0.0,
+    };
+    let days = run_days(first, last, &run_seconds, &starts);
+
+    Ok(DeviceRunHours {
+        device_id: device.id,
+        name: device.name.clone(),
+        device_type: device.device_type.clone(),
+        operational_hours: device.operational_hours + pending,
+        running_since,
+        period_hours: days.iter().map(|d| d.run_hours).sum(),
+        days,
+    })
+}
+
+/// 后台任务：定期结算持续运行设备的运行小时数
+pub async fn run_scheduler(config: RunHoursConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(StdDuration::from_secs(config.checkpoint_secs.max(60)));
+    loop {
+        ticker.tick().await;
+        match checkpoint(db.get_connection(), Utc::now()).await {
+            Ok(0) => {}
+            Ok(count) => info!("Accrued run hours for {} running devices", count),
+            Err(e) => error!("Run hours checkpoint failed: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone};
+
+    #[test]
+    fn test_run_days() {
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+        let run_seconds = vec![(at(1, 0), 2 * 3600), (at(3, 0), 5400)];
+        let starts = vec![at(1, 22), at(3, 6), at(3, 12)];
+        let days = run_days(at(1, 0).date_naive(), at(3, 0).date_naive(), &run_seconds, &starts);
+
+        let summary: Vec<(u32, f64, u32)> = days.iter().map(|d| (d.date.day(), d.run_hours, d.starts)).collect();
+        assert_eq!(summary, vec![(1, 2.0, 1), (2, 0.0, 0), (3, 1.5, 2)]);
+    }
+}
@@ -0,0 +1,364 @@
+//! 通信中断安全状态
+//!
+//! 每个执行器在 `safe_state.actuators` 中配置所依赖的传感器和超时时间。任一传感器超过
+//! 超时时间没有新的测量值时，视为遥测中断，按策略下发安全状态（open / close / stop），
+//! 或保持当前输出（hold），同时记录到 `safe_state_events` 并报警。
+//!
+//! 安全状态只在中断开始时下发一次，下发失败时每个检查周期重试；遥测恢复后只记录恢复事件，
+//! 不会自动恢复之前的输出。Modbus 写入以自动化来源越过联锁，因为联锁依赖的正是已中断的遥测，
+//! 但仍受寄存器白名单和安全范围限制。
+
+use crate::app_state::AppState;
+use crate::config::safe_state::{ActuatorTarget, SafePolicy, SafeStateActuator};
+use crate::models::safe_state_event::{
+    ActiveModel as SafeStateEventActiveModel, Column as SafeStateEventColumn,
+    Entity as SafeStateEventEntity, Model as SafeStateEvent, EVENT_TELEMETRY_LOST,
+    EVENT_TELEMETRY_RESTORED, STATUS_APPLIED, STATUS_FAILED, STATUS_HELD,
+};
+use crate::services::alarm;
+use crate::services::latest::{self, LatestValue};
+use crate::services::modbus_write::{self, WriteRequest, WriteSource};
+use crate::utils::error::AppError;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+const OPERATOR: &str = "safe-state";
+
+/// 执行器当前的安全状态
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ActuatorSafeState {
+    pub name: String,
+    pub policy: String,
+    /// 遥测是否中断
+    pub telemetry_lost: bool,
+    pub lost_since: Option<DateTime<Utc>>,
+    /// 遥测中断后安全状态是否已下发，hold 策略视为已下发
+    pub applied: bool,
+    pub stale_sensors: Vec<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SafeStateMonitor {
+    states: Arc<Mutex<BTreeMap<String, ActuatorSafeState>>>,
+}
+
+/// 传感器标识，例如 `3:flow`
+fn sensor_label(device_id: i32, metric_type: &str) -> String {
+    format!("{}:{}", device_id, metric_type)
+}
+
+/// 超时未上报的传感器
+pub fn stale_sensors(
+    actuator: &SafeStateActuator,
+    latest: &HashMap<i32, BTreeMap<String, LatestValue>>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let timeout = chrono::Duration::seconds(actuator.timeout_secs as i64);
+    actuator
+        .sensors
+        .iter()
+        .filter(|sensor| {
+            latest
+                .get(&sensor.device_id)
+                .and_then(|values| values.get(&sensor.metric_type))
+                .is_none_or(|value| now - value.timestamp > timeout)
+        })
+        .map(|sensor| sensor_label(sensor.device_id, &sensor.metric_type))
+        .collect()
+}
+
+/// 策略对应的下发值，hold 不下发
+pub fn command_value(actuator: &SafeStateActuator) -> Option<f64> {
+    let open = match actuator.target {
+        ActuatorTarget::Pwm { .. } => 100.0,
+        _ => 1.0,
+    };
+    match actuator.policy {
+        SafePolicy::Hold => None,
+        SafePolicy::Open => Some(actuator.value.unwrap_or(open)),
+        SafePolicy::Close | SafePolicy::Stop => Some(actuator.value.unwrap_or(0.0)),
+    }
+}
+
+/// 下发安全状态
+async fn apply(state: &AppState, actuator: &SafeStateActuator, value: f64) -> Result<(), AppError> {
+    let conn = state.db.get_connection();
+    match &actuator.target {
+        ActuatorTarget::Gpio { output } => {
+            let operator = Some(OPERATOR.to_string());
+            state.gpio_outputs.set(conn, output, value != 0.0, operator).await?;
+        }
+        ActuatorTarget::Modbus { device_id, register, address } => {
+            let request = WriteRequest {
+                device_id: *device_id,
+                kind: *register,
+                address: *address,
+                value,
+                source: WriteSource::Automation,
+                operator: Some(OPERATOR.to_string()),
+                override_interlocks: true,
+                reason: Some(format!("遥测中断，执行器 {} 进入安全状态", actuator.name)),
+            };
+            modbus_write::write(conn, &state.cache, &state.settings.modbus, request).await?;
+        }
+        ActuatorTarget::Pwm { device_id } => {
+            state.pwm.set(*device_id, value, None, false).await?;
+        }
+    }
+    Ok(())
+}
+
+fn error_message(e: &AppError) -> String {
+    match e {
+        AppError::InvalidInput(msg)
+        | AppError::ServiceUnavailable(msg)
+        | AppError::Unprocessable(msg) => msg.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+async fn log_event(
+    conn: &DatabaseConnection,
+    actuator: &SafeStateActuator,
+    event: &str,
+    status: Option<&str>,
+    value: Option<f64>,
+    stale: &[String],
+    message: Option<String>,
+) {
+    let result = SafeStateEventActiveModel {
+        actuator: Set(actuator.name.clone()),
+        event: Set(event.to_string()),
+        policy: Set(actuator.policy.as_str().to_string()),
+        status: Set(status.map(String::from)),
+        value: Set(value),
+        stale_sensors: Set(stale.join(", ")),
+        message: Set(message),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await;
+    if let Err(e) = result {
+        error!("Failed to record safe-state event for {}: {}", actuator.name, e);
+    }
+}
+
+impl SafeStateMonitor {
+    pub fn new(actuators: &[SafeStateActuator]) -> Self {
+        let states = actuators
+            .iter()
+            .map(|actuator| {
+                let state = ActuatorSafeState {
+                    name: actuator.name.clone(),
+                    policy: actuator.policy.as_str().to_string(),
+                    ..Default::default()
+                };
+                (actuator.name.clone(), state)
+            })
+            .collect();
+        Self { states: Arc::new(Mutex::new(states)) }
+    }
+
+    pub fn status(&self) -> Vec<ActuatorSafeState> {
+        self.states.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect()
+    }
+
+    fn get(&self, name: &str) -> ActuatorSafeState {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        states.get(name).cloned().unwrap_or_default()
+    }
+
+    fn put(&self, state: ActuatorSafeState) {
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        states.insert(state.name.clone(), state);
+    }
+
+    /// 检查一个执行器的遥测，中断时下发安全状态
+    async fn check(
+        &self,
+        state: &AppState,
+        actuator: &SafeStateActuator,
+        latest: &HashMap<i32, BTreeMap<String, LatestValue>>,
+        now: DateTime<Utc>,
+    ) {
+        let conn = state.db.get_connection();
+        let config = &state.settings.safe_state;
+        let stale = stale_sensors(actuator, latest, now);
+        let mut current = self.get(&actuator.name);
+        current.stale_sensors = stale.clone();
+
+        if stale.is_empty() {
+            if current.telemetry_lost {
+                info!("Telemetry for actuator {} restored", actuator.name);
+                log_event(conn, actuator, EVENT_TELEMETRY_RESTORED, None, None, &stale, None)
+                    .await;
+                current.telemetry_lost = false;
+                current.lost_since = None;
+                current.applied = false;
+                current.last_error = None;
+            }
+            self.put(current);
+            return;
+        }
+
+        let first = !current.telemetry_lost;
+        if first {
+            warn!(
+                "Telemetry for actuator {} lost ({}), policy {}",
+                actuator.name,
+                stale.join(", "),
+                actuator.policy.as_str()
+            );
+            current.telemetry_lost = true;
+            current.lost_since = Some(now);
+            let rule_name = format!("遥测中断安全状态: {}", actuator.name);
+            let device_id = actuator.sensors.first().map(|sensor| sensor.device_id);
+            let value = stale.len() as f64;
+            if let Err(e) = alarm::raise(conn, device_id, rule_name, value, &config.severity).await
+            {
+                error!("Failed to raise safe-state alarm: {:?}", e);
+            }
+        }
+        if current.applied {
+            self.put(current);
+            return;
+        }
+
+        let value = command_value(actuator);
+        let (status, message) = match value {
+            None => (STATUS_HELD, None),
+            Some(value) => match apply(state, actuator, value).await {
+                Ok(()) => {
+                    info!("Actuator {} set to safe state {}", actuator.name, value);
+                    (STATUS_APPLIED, None)
+                }
+                Err(e) => {
+                    let message = error_message(&e);
+                    warn!("Failed to set actuator {} to safe state: {}", actuator.name, message);
+                    (STATUS_FAILED, Some(message))
+                }
+            },
+        };
+        current.applied = status != STATUS_FAILED;
+        current.last_error = message.clone();
+        // 失败后每个周期重试，只记录第一次失败和最终结果
+        if first || status != STATUS_FAILED {
+            log_event(conn, actuator, EVENT_TELEMETRY_LOST, Some(status), value, &stale, message)
+                .await;
+        }
+        self.put(current);
+    }
+}
+
+/// 读取执行器依赖的全部传感器的最新值
+async fn latest_values(
+    state: &AppState,
+    actuators: &[SafeStateActuator],
+) -> HashMap<i32, BTreeMap<String, LatestValue>> {
+    let conn = state.db.get_connection();
+    let mut values = HashMap::new();
+    for sensor in actuators.iter().flat_map(|actuator| &actuator.sensors) {
+        if values.contains_key(&sensor.device_id) {
+            continue;
+        }
+        match latest::for_device(conn, &state.cache, sensor.device_id).await {
+            Ok(latest) => {
+                values.insert(sensor.device_id, latest.values);
+            }
+            // 设备不存在时视为没有遥测
+            Err(AppError::NotFound) => {
+                values.insert(sensor.device_id, BTreeMap::new());
+            }
+            Err(e) => warn!("Failed to read latest values of device {}: {:?}", sensor.device_id, e),
+        }
+    }
+    values
+}
+
+/// 后台任务：定期检查遥测，中断时下发安全状态
+pub async fn run(state: Arc<AppState>) {
+    let config = state.settings.safe_state.clone();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.check_interval_secs.max(1)));
+    loop {
+        ticker.tick().await;
+        let latest = latest_values(&state, &config.actuators).await;
+        let now = Utc::now();
+        for actuator in &config.actuators {
+            // 读取失败的设备不参与判断，避免数据库故障被误判为遥测中断
+            let readable = actuator.sensors.iter().all(|s| latest.contains_key(&s.device_id));
+            if readable {
+                state.safe_state.check(&state, actuator, &latest, now).await;
+            }
+        }
+    }
+}
+
+/// 安全状态事件，最新的在前
+pub async fn events(
+    conn: &DatabaseConnection,
+    actuator: Option<&str>,
+    limit: u64,
+) -> Result<Vec<SafeStateEvent>, AppError> {
+    let mut select = SafeStateEventEntity::find();
+    if let Some(actuator) = actuator {
+        select = select.filter(SafeStateEventColumn::Actuator.eq(actuator));
+    }
+    select
+        .order_by_desc(SafeStateEventColumn::Id)
+        .limit(limit)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::safe_state::SensorRef;
+
+    #[test]
+    fn test_stale_sensors_and_value() {
+        let now = Utc::now();
+        let actuator = SafeStateActuator {
+            name: "inlet_valve".to_string(),
+            target: ActuatorTarget::Pwm { device_id: 9 },
+            sensors: vec![
+                SensorRef { device_id: 1, metric_type: "flow".to_string() },
+                SensorRef { device_id: 2, metric_type: "level".to_string() },
+            ],
+            timeout_secs: 60,
+            policy: SafePolicy::Open,
+            value: None,
+        };
+        let value = |age: i64| LatestValue {
+            value: 1.0,
+            unit: String::new(),
+            timestamp: now - chrono::Duration::seconds(age),
+        };
+        let mut latest = HashMap::new();
+        latest.insert(1, BTreeMap::from([("flow".to_string(), value(30))]));
+        latest.insert(2, BTreeMap::from([("level".to_string(), value(90))]));
+        assert_eq!(stale_sensors(&actuator, &latest, now), vec!["2:level".to_string()]);
+        latest.insert(2, BTreeMap::new());
+        assert_eq!(stale_sensors(&actuator, &latest, now), vec!["2:level".to_string()]);
+        latest.insert(2, BTreeMap::from([("level".to_string(), value(10))]));
+        assert!(stale_sensors(&actuator, &latest, now).is_empty());
+
+        assert_eq!(command_value(&actuator), Some(100.0));
+        let hold = SafeStateActuator { policy: SafePolicy::Hold, ..actuator.clone() };
+        assert_eq!(command_value(&hold), None);
+        let stop = SafeStateActuator { policy: SafePolicy::Stop, value: Some(5.0), ..actuator };
+        assert_eq!(command_value(&stop), Some(5.0));
+    }
+}
@@ -0,0 +1,232 @@
+//! 串口远程控制台
+//!
+//! 通过 WebSocket 把客户端与配置中的串口双向桥接，供技术支持人员远程操作仪表的维护控制台。
+//! 每个串口同时只允许一个会话；会话的操作人、原因和全部收发内容都会记录，用于审计。
+
+use crate::config::serial_console::{SerialConsoleConfig, SerialPortConfig};
+use crate::database::sea_orm_db::DbManager;
+use crate::models::serial_session::{
+    ActiveModel as SerialSessionActiveModel, Entity as SerialSessionEntity,
+    Model as SerialSession,
+};
+use crate::services::remote_access::Recorder;
+use crate::utils::error::AppError;
+use crate::utils::uart;
+use axum::extract::ws::{Message, WebSocket};
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use sea_orm::{EntityTrait, IntoActiveModel, Set};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_serial::SerialStream;
+use tracing::{error, info};
+
+/// 串口控制台管理
+#[derive(Debug, Clone)]
+pub struct SerialConsoleManager {
+    config: Arc<SerialConsoleConfig>,
+    /// 正在使用的串口
+    busy: Arc<Mutex<HashSet<String>>>,
+}
+
+/// 已打开、等待 WebSocket 连接的会话
+pub struct ConsoleSession {
+    session: SerialSession,
+    uart: SerialStream,
+    recorder: Arc<Recorder>,
+    idle_timeout: Duration,
+    db: DbManager,
+    _lease: PortLease,
+}
+
+/// 串口占用，会话结束时释放
+struct PortLease {
+    busy: Arc<Mutex<HashSet<String>>>,
+    port: String,
+}
+
+impl Drop for PortLease {
+    fn drop(&mut self) {
+        self.busy.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.port);
+    }
+}
+
+impl SerialConsoleManager {
+    pub fn new(config: SerialConsoleConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            busy: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// 允许访问的串口名称
+    pub fn port_names(&self) -> Vec<String> {
+        self.config.ports.iter().map(|p| p.name.clone()).collect()
+    }
+
+    fn port(&self, name: &str) -> Result<&SerialPortConfig, AppError> {
+        self.config
+            .ports
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or(AppError::NotFound)
+    }
+
+    /// 占用并打开串口，写入会话记录
+    pub async fn open(
+        &self,
+        db: &DbManager,
+        port: &str,
+        operator: String,
+        reason: String,
+        remote_addr: SocketAddr,
+    ) -> Result<ConsoleSession, AppError> {
+        if !self.config.enabled {
+            return Err(AppError::InvalidInput("串口控制台未启用".into()));
+        }
+        if reason.trim().is_empty() {
+            return Err(AppError::InvalidInput("必须填写访问原因".into()));
+        }
+        let port_config = self.port(port)?;
+
+        if !self.busy.lock().unwrap_or_else(|e| e.into_inner()).insert(port.to_string()) {
+            return Err(AppError::InvalidInput(format!("串口 {} 正在被其他会话使用", port).into()));
+        }
+        let lease = PortLease {
+            busy: self.busy.clone(),
+            port: port.to_string(),
+        };
+
+        let uart = uart::open(port_config).map_err(|e| {
+            error!("Failed to open serial port {} ({}): {}", port, port_config.path, e);
+            AppError::ServiceUnavailable(format!("无法打开串口 {}", port).into())
+        })?;
+
+        tokio::fs::create_dir_all(&self.config.recording_dir).await?;
+        let now = Utc::now();
+        let new_session = SerialSessionActiveModel {
+            port: Set(port.to_string()),
+            operator: Set(operator),
+            reason: Set(reason),
+            remote_addr: Set(remote_addr.to_string()),
+            recording_path: Set(String::new()),
+            bytes_to_device: Set(0),
+            bytes_from_device: Set(0),
+            closed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        let session = SerialSessionEntity::insert(new_session)
+            .exec_with_returning(db.get_connection())
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        let recording_path: PathBuf = [
+            self.config.recording_dir.as_str(),
+            &format!("serial-{}.log", session.id),
+        ]
+        .iter()
+        .collect();
+        let file = tokio::fs::File::create(&recording_path).await?;
+
+        let mut session_active_model = session.into_active_model();
+        session_active_model.recording_path = Set(recording_path.to_string_lossy().into_owned());
+        let session = SerialSessionEntity::update(session_active_model)
+            .exec(db.get_connection())
+            .await
+            .map_err(|_| AppError::InternalError)?;
+
+        let recorder = Arc::new(Recorder::new(file));
+        recorder
+            .note(
+                0,
+                &format!(
+                    "serial console {} opened by {} from {}: {}",
+                    session.port, session.operator, session.remote_addr, session.reason
+                ),
+            )
+            .await;
+
+        info!(
+            "Serial console session {} opened on {} by {}",
+            session.id, session.port, session.operator
+        );
+        Ok(ConsoleSession {
+            session,
+            uart,
+            recorder,
+            idle_timeout: Duration::from_secs(self.config.idle_timeout_secs.max(1)),
+            db: db.clone(),
+            _lease: lease,
+        })
+    }
+}
+
+impl ConsoleSession {
+    /// 在 WebSocket 与串口之间转发数据，直到任一方断开或空闲超时
+    pub async fn run(self, socket: WebSocket) {
+        let ConsoleSession {
+            session,
+            uart,
+            recorder,
+            idle_timeout,
+            db,
+            _lease,
+        } = self;
+
+        let (mut ws_tx, mut ws_rx) = socket.split();
+        let (mut uart_rx, mut uart_tx) = tokio::io::split(uart);
+        let mut buf = vec![0u8; 4096];
+
+        let reason = loop {
+            tokio::select! {
+                _ = tokio::time::sleep(idle_timeout) => break "idle timeout",
+                message = ws_rx.next() => {
+                    let data = match message {
+                        Some(Ok(Message::Binary(data))) => data.to_vec(),
+                        Some(Ok(Message::Text(text))) => text.as_bytes().to_vec(),
+                        Some(Ok(Message::Close(_))) | None => break "closed by client",
+                        Some(Ok(_)) => continue,
+                        Some(Err(_)) => break "websocket error",
+                    };
+                    recorder.record(0, true, &data).await;
+                    if uart_tx.write_all(&data).await.is_err() {
+                        break "serial write error";
+                    }
+                }
+                read = uart_rx.read(&mut buf) => {
+                    let n = match read {
+                        Ok(0) | Err(_) => break "serial port closed",
+                        Ok(n) => n,
+                    };
+                    recorder.record(0, false, &buf[..n]).await;
+                    if ws_tx.send(Message::Binary(buf[..n].to_vec().into())).await.is_err() {
+                        break "closed by client";
+                    }
+                }
+            }
+        };
+
+        let _ = ws_tx.send(Message::Close(None)).await;
+        recorder.note(0, &format!("session ended: {}", reason)).await;
+
+        let now = Utc::now();
+        let id = session.id;
+        let mut active_model = session.into_active_model();
+        active_model.closed_at = Set(Some(now));
+        active_model.bytes_to_device = Set(recorder.bytes_to_target.load(Ordering::Relaxed) as i64);
+        active_model.bytes_from_device = Set(recorder.bytes_from_target.load(Ordering::Relaxed) as i64);
+        active_model.updated_at = Set(now);
+        if let Err(e) = SerialSessionEntity::update(active_model).exec(db.get_connection()).await {
+            error!("Failed to close serial console session {}: {}", id, e);
+        }
+
+        info!("Serial console session {} ended: {}", id, reason);
+    }
+}
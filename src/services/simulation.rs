@@ -0,0 +1,337 @@
+//! 传感器模拟注入
+//!
+//! 端到端测试按脚本向指定设备注入一段确定的测量序列：斜坡（线性变化）、阶跃（保持某值，相邻两段
+//! 取值不同即为阶跃）和传感器故障（卡死、掉线、超量程）。样本时间戳从起始时刻按固定间隔排列，
+//! 同一脚本每次生成的序列完全相同。
+//!
+//! 样本与现场采集一样经 [`ReadOnlyMode::ingest`] 写入。每个被接受的样本再按指标类型匹配报警规则
+//! （条件为 `>`、`>=`、`<`、`<=`），并列出该时刻处于触发时间段内的自动化规则，测试据此断言；
+//! 要求写入报警时，触发的规则经 [`alarm::raise`] 记录，搁置、转发等后续处理与其他报警相同。
+//! 与故障注入一样只在调试构建中提供接口。
+
+use crate::config::timezone::TimezoneConfig;
+use crate::models::alarm_log::SEVERITY_MAJOR;
+use crate::models::alarm_rule::{Entity as AlarmRuleEntity, Model as AlarmRule};
+use crate::models::automation_rule::{Entity as AutomationRuleEntity, Model as AutomationRule};
+use crate::models::device::Model as Device;
+use crate::services::alarm;
+use crate::services::automation;
+use crate::services::cache::HotCache;
+use crate::services::measurement::NewMeasurement;
+use crate::services::metric_registry::{self, MetricInfo};
+use crate::services::read_only::ReadOnlyMode;
+use crate::services::site as site_service;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 是否提供模拟注入接口
+pub const AVAILABLE: bool = cfg!(debug_assertions);
+
+/// 单次注入的样本数上限
+pub const MAX_SAMPLES: u32 = 1000;
+
+/// 传感器故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorFault {
+    /// 卡死，重复上一个值
+    Stuck,
+    /// 掉线，不上报，时间照常推进
+    Dropout,
+    /// 超出指标有效范围
+    OutOfRange,
+}
+
+/// 脚本中的一段
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScenarioStep {
+    /// 从 `from` 线性变化到 `to`，含两端
+    Ramp { from: f64, to: f64, samples: u32 },
+    /// 保持 `value`
+    Step { value: f64, samples: u32 },
+    Fault { fault: SensorFault, samples: u32 },
+}
+
+impl ScenarioStep {
+    fn samples(&self) -> u32 {
+        match self {
+            ScenarioStep::Ramp { samples, .. }
+            | ScenarioStep::Step { samples, .. }
+            | ScenarioStep::Fault { samples, .. } => *samples,
+        }
+    }
+}
+
+/// 按脚本排好的样本，掉线时 `value` 为空
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedSample {
+    pub timestamp: DateTime<Utc>,
+    pub value: Option<f64>,
+}
+
+/// 样本的写入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleOutcome {
+    /// 已写入（开启压缩时可能被合并）
+    Accepted,
+    /// 只读期间已暂存
+    Buffered,
+    /// 校验未通过
+    Rejected,
+    /// 掉线，未上报
+    Dropout,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InjectedSample {
+    pub timestamp: DateTime<Utc>,
+    pub value: Option<f64>,
+    pub outcome: SampleOutcome,
+    /// 被拒绝的原因
+    pub error: Option<String>,
+    /// 触发的报警规则名称
+    pub alarms: Vec<String>,
+    /// 该时刻处于触发时间段内的自动化规则 ID
+    pub automation_rules: Vec<i32>,
+}
+
+/// 注入结果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InjectionReport {
+    pub device_id: i32,
+    pub metric_type: String,
+    pub accepted: usize,
+    pub rejected: usize,
+    /// 写入的报警条数，未要求写入报警时为 0
+    pub alarms_raised: usize,
+    pub samples: Vec<InjectedSample>,
+}
+
+/// 按脚本生成样本序列
+pub fn plan(
+    metric: &MetricInfo,
+    steps: &[ScenarioStep],
+    start: DateTime<Utc>,
+    interval_secs: u32,
+) -> Result<Vec<PlannedSample>, AppError> {
+    if interval_secs == 0 {
+        return Err(AppError::InvalidInput("interval_secs 须大于 0".into()));
+    }
+    let total: u32 = steps.iter().map(ScenarioStep::samples).sum();
+    if total == 0 || total > MAX_SAMPLES {
+        return Err(AppError::InvalidInput(
+            format!("样本总数须在 1 到 {} 之间", MAX_SAMPLES).into(),
+        ));
+    }
+
+    let out_of_range = metric.max + (metric.max - metric.min).max(1.0);
+    let mut values = Vec::with_capacity(total as usize);
+    let mut last: Option<f64> = None;
+    for step in steps {
+        match *step {
+            ScenarioStep::Ramp { from, to, samples } => {
+                let span = samples.saturating_sub(1).max(1) as f64;
+                values.extend((0..samples).map(|i| Some(from + (to - from) * i as f64 / span)));
+            }
+            ScenarioStep::Step { value, samples } => {
+                values.extend((0..samples).map(|_| Some(value)));
+            }
+            ScenarioStep::Fault { fault, samples } => {
+                let value = match fault {
+                    SensorFault::Stuck => Some(last.ok_or_else(|| {
+                        AppError::InvalidInput("卡死故障之前须有一段取值".into())
+                    })?),
+                    SensorFault::Dropout => None,
+                    SensorFault::OutOfRange => Some(out_of_range),
+                };
+                values.extend((0..samples).map(|_| value));
+            }
+        }
+        if let Some(value) = values.iter().rev().flatten().next() {
+            last = Some(*value);
+        }
+    }
+
+    let interval = Duration::seconds(interval_secs as i64);
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| PlannedSample { timestamp: start + interval * i as i32, value })
+        .collect())
+}
+
+/// 报警规则对该值是否触发，无法识别的条件不触发
+pub fn triggers(rule: &AlarmRule, metric_type: &str, value: f64) -> bool {
+    if rule.parameter != metric_type {
+        return false;
+    }
+    match rule.condition.trim() {
+        ">" => value > rule.value,
+        ">=" => value >= rule.value,
+        "<" => value < rule.value,
+        "<=" => value <= rule.value,
+        _ => false,
+    }
+}
+
+/// 向设备注入样本并记录各样本触发的报警规则和生效的自动化规则
+///
+/// 只匹配未归属组织或与设备同组织的规则。
+#[allow(clippy::too_many_arguments)]
+pub async fn inject(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    read_only: &ReadOnlyMode,
+    timezone: &TimezoneConfig,
+    device: &Device,
+    metric_type: &str,
+    samples: Vec<PlannedSample>,
+    raise_alarms: bool,
+) -> Result<InjectionReport, AppError> {
+    let visible = |organization_id: Option<i32>| {
+        organization_id.is_none() || organization_id == device.organization_id
+    };
+    let alarm_rules: Vec<AlarmRule> = AlarmRuleEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .into_iter()
+        .filter(|rule| visible(rule.organization_id) && rule.parameter == metric_type)
+        .collect();
+    let mut automation_rules: Vec<(AutomationRule, Tz)> = Vec::new();
+    for rule in AutomationRuleEntity::find()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+    {
+        if visible(rule.organization_id) {
+            let tz = site_service::site_timezone(conn, timezone, rule.site_id).await?;
+            automation_rules.push((rule, tz));
+        }
+    }
+
+    let mut report = InjectionReport {
+        device_id: device.id,
+        metric_type: metric_type.to_string(),
+        accepted: 0,
+        rejected: 0,
+        alarms_raised: 0,
+        samples: Vec::with_capacity(samples.len()),
+    };
+    for sample in samples {
+        let mut injected = InjectedSample {
+            timestamp: sample.timestamp,
+            value: sample.value,
+            outcome: SampleOutcome::Dropout,
+            error: None,
+            alarms: Vec::new(),
+            automation_rules: Vec::new(),
+        };
+        let Some(value) = sample.value else {
+            report.samples.push(injected);
+            continue;
+        };
+
+        let measurement = NewMeasurement {
+            metric_type: metric_type.to_string(),
+            timestamp: sample.timestamp,
+            value,
+            device_id: Some(device.id),
+            unit: None,
+        };
+        match read_only.ingest(conn, cache, measurement).await {
+            Ok(buffered) => {
+                injected.outcome =
+                    if buffered { SampleOutcome::Buffered } else { SampleOutcome::Accepted };
+                report.accepted += 1;
+            }
+            Err(AppError::InvalidInput(msg)) => {
+                injected.outcome = SampleOutcome::Rejected;
+                injected.error = Some(msg.into_owned());
+                report.rejected += 1;
+                report.samples.push(injected);
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        for rule in alarm_rules.iter().filter(|rule| triggers(rule, metric_type, value)) {
+            if raise_alarms {
+                alarm::raise(conn, Some(device.id), rule.name.clone(), value, SEVERITY_MAJOR).await?;
+                report.alarms_raised += 1;
+            }
+            injected.alarms.push(rule.name.clone());
+        }
+        injected.automation_rules = automation_rules
+            .iter()
+            .filter(|(rule, tz)| automation::is_active(rule, *tz, sample.timestamp))
+            .map(|(rule, _)| rule.id)
+            .collect();
+        report.samples.push(injected);
+    }
+
+    Ok(report)
+}
+
+/// 指标类型须已登记
+pub fn lookup_metric(metric_type: &str) -> Result<&'static MetricInfo, AppError> {
+    metric_registry::lookup(metric_type)
+        .ok_or_else(|| AppError::InvalidInput(format!("未知的指标类型: {}", metric_type).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_plan_scenario() {
+        let ph = lookup_metric(metric_registry::PH).unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let steps = vec![
+            ScenarioStep::Ramp { from: 7.0, to: 9.0, samples: 5 },
+            ScenarioStep::Fault { fault: SensorFault::Stuck, samples: 2 },
+            ScenarioStep::Fault { fault: SensorFault::Dropout, samples: 1 },
+            ScenarioStep::Step { value: 6.0, samples: 1 },
+            ScenarioStep::Fault { fault: SensorFault::OutOfRange, samples: 1 },
+        ];
+        let samples = plan(ph, &steps, start, 60).unwrap();
+
+        let values: Vec<Option<f64>> = samples.iter().map(|s| s.value).collect();
+        let expected = [7.0, 7.5, 8.0, 8.5, 9.0, 9.0, 9.0];
+        assert_eq!(values[..7], expected.map(Some));
+        assert_eq!(values[7..9], [None, Some(6.0)]);
+        assert!(!ph.in_range(values[9].unwrap()));
+        assert_eq!(samples[9].timestamp, start + Duration::minutes(9));
+        assert_eq!(plan(ph, &steps, start, 60).unwrap(), samples);
+
+        let stuck_first = [ScenarioStep::Fault { fault: SensorFault::Stuck, samples: 1 }];
+        assert!(plan(ph, &stuck_first, start, 60).is_err());
+        assert!(plan(ph, &[], start, 60).is_err());
+        assert!(plan(ph, &steps, start, 0).is_err());
+    }
+
+    #[test]
+    fn test_triggers() {
+        let now = Utc::now();
+        let rule = AlarmRule {
+            id: 1,
+            name: "pH 偏高".to_string(),
+            condition: ">=".to_string(),
+            parameter: metric_registry::PH.to_string(),
+            value: 9.0,
+            organization_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert!(triggers(&rule, metric_registry::PH, 9.0));
+        assert!(!triggers(&rule, metric_registry::PH, 8.9));
+        assert!(!triggers(&rule, metric_registry::FLOW, 9.5));
+    }
+}
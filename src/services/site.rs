@@ -0,0 +1,219 @@
+//! 厂站/区域层级
+//!
+//! 厂站下可划分多级区域（`parent_id` 指向上级区域），设备挂在厂站或区域下。
+//! 设备挂在区域下时同时记录区域所属的厂站，因此按厂站查询只需过滤 `site_id`。
+//! 厂站可设置自己的时区，班次、自动化规则时段等本地时刻按厂站时区解释。
+
+use crate::config::timezone::TimezoneConfig;
+use crate::models::area::{Column as AreaColumn, Entity as AreaEntity, Model as Area};
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity, Model as Device};
+use crate::models::site::{Entity as SiteEntity, Model as Site};
+use crate::utils::error::AppError;
+use crate::utils::timezone;
+use chrono_tz::Tz;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
+use std::collections::{HashMap, HashSet};
+
+pub async fn get_site(conn: &DatabaseConnection, id: i32) -> Result<Site, AppError> {
+    SiteEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+pub async fn get_area(conn: &DatabaseConnection, id: i32) -> Result<Area, AppError> {
+    AreaEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?
+        .ok_or_else(|| AppError::NotFound)
+}
+
+/// 厂站的时区，未指定厂站或厂站未设置时区时用默认时区
+pub async fn site_timezone(
+    conn: &DatabaseConnection,
+    config: &TimezoneConfig,
+    site_id: Option<i32>,
+) -> Result<Tz, AppError> {
+    let Some(site_id) = site_id else {
+        return Ok(timezone::default_zone(config));
+    };
+    let site = get_site(conn, site_id).await?;
+    match site.timezone.as_deref().map(timezone::parse) {
+        Some(Ok(tz)) => Ok(tz),
+        _ => Ok(timezone::default_zone(config)),
+    }
+}
+
+async fn site_areas(conn: &DatabaseConnection, site_id: i32) -> Result<Vec<Area>, AppError> {
+    AreaEntity::find()
+        .filter(AreaColumn::SiteId.eq(site_id))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 区域及其全部下级区域的ID
+pub fn subtree(areas: &[Area], root: i32) -> Vec<i32> {
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for area in areas {
+        if let Some(parent_id) = area.parent_id {
+            children.entry(parent_id).or_default().push(area.id);
+        }
+    }
+
+    let mut ids = vec![root];
+    let mut visited = HashSet::from([root]);
+    let mut index = 0;
+    while index < ids.len() {
+        if let Some(next) = children.get(&ids[index]) {
+            for &id in next {
+                if visited.insert(id) {
+                    ids.push(id);
+                }
+            }
+        }
+        index += 1;
+    }
+    ids
+}
+
+/// 校验设备的厂站/区域，只指定区域时补全厂站
+pub async fn resolve_placement(
+    conn: &DatabaseConnection,
+    site_id: Option<i32>,
+    area_id: Option<i32>,
+) -> Result<(Option<i32>, Option<i32>), AppError> {
+    if let Some(area_id) = area_id {
+        let area = AreaEntity::find_by_id(area_id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or_else(|| AppError::InvalidInput(format!("区域不存在: {}", area_id).into()))?;
+        if site_id.is_some_and(|site_id| site_id != area.site_id) {
+            return Err(AppError::InvalidInput("区域不属于指定的厂站".into()));
+        }
+        return Ok((Some(area.site_id), Some(area_id)));
+    }
+
+    if let Some(site_id) = site_id {
+        SiteEntity::find_by_id(site_id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or_else(|| AppError::InvalidInput(format!("厂站不存在: {}", site_id).into()))?;
+    }
+
+    Ok((site_id, None))
+}
+
+/// 校验上级区域：须属于同一厂站，且不能把区域挂到自己的下级下面
+pub async fn check_parent(
+    conn: &DatabaseConnection,
+    site_id: i32,
+    area_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<(), AppError> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    let areas = site_areas(conn, site_id).await?;
+    if !areas.iter().any(|a| a.id == parent_id) {
+        return Err(AppError::InvalidInput("上级区域不存在或不属于该厂站".into()));
+    }
+    if let Some(area_id) = area_id {
+        if subtree(&areas, area_id).contains(&parent_id) {
+            return Err(AppError::InvalidInput("上级区域不能是自身或下级区域".into()));
+        }
+    }
+
+    Ok(())
+}
+
+/// 厂站下的全部设备
+pub async fn devices_in_site(conn: &DatabaseConnection, site_id: i32) -> Result<Vec<Device>, AppError> {
+    DeviceEntity::find()
+        .filter(DeviceColumn::SiteId.eq(site_id))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 区域及其下级区域中的全部设备
+pub async fn devices_in_area(conn: &DatabaseConnection, area: &Area) -> Result<Vec<Device>, AppError> {
+    let areas = site_areas(conn, area.site_id).await?;
+    let ids = subtree(&areas, area.id);
+
+    DeviceEntity::find()
+        .filter(DeviceColumn::AreaId.is_in(ids))
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 删除前检查：厂站下不能还有区域或设备
+pub async fn ensure_site_empty(conn: &DatabaseConnection, site_id: i32) -> Result<(), AppError> {
+    let areas = AreaEntity::find()
+        .filter(AreaColumn::SiteId.eq(site_id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let devices = DeviceEntity::find()
+        .filter(DeviceColumn::SiteId.eq(site_id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    if areas > 0 || devices > 0 {
+        return Err(AppError::InvalidInput("厂站下仍有区域或设备，不能删除".into()));
+    }
+    Ok(())
+}
+
+/// 删除前检查：区域下不能还有下级区域或设备
+pub async fn ensure_area_empty(conn: &DatabaseConnection, area_id: i32) -> Result<(), AppError> {
+    let children = AreaEntity::find()
+        .filter(AreaColumn::ParentId.eq(area_id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    let devices = DeviceEntity::find()
+        .filter(DeviceColumn::AreaId.eq(area_id))
+        .count(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    if children > 0 || devices > 0 {
+        return Err(AppError::InvalidInput("区域下仍有下级区域或设备，不能删除".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn area(id: i32, parent_id: Option<i32>) -> Area {
+        Area {
+            id,
+            site_id: 1,
+            parent_id,
+            name: format!("area-{}", id),
+            description: String::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_subtree() {
+        let areas = vec![area(1, None), area(2, Some(1)), area(3, Some(2)), area(4, None)];
+
+        assert_eq!(subtree(&areas, 1), vec![1, 2, 3]);
+        assert_eq!(subtree(&areas, 3), vec![3]);
+        assert_eq!(subtree(&areas, 4), vec![4]);
+    }
+}
@@ -0,0 +1,243 @@
+//! 主机资源监测
+//!
+//! 采集网关的 CPU 负载、内存、数据库所在磁盘和 SoC 温度，超过阈值时写入报警，恢复后才会再次报警。
+//! 磁盘写满会导致数据丢失，这里的报警应早于只读模式的自动切换。
+
+use crate::config::settings::Settings;
+use crate::config::system::SystemMonitorConfig;
+use crate::database::sea_orm_db::DbManager;
+use crate::models::alarm_log::SEVERITY_WARNING;
+use crate::services::alarm;
+use crate::utils::disk;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::error;
+use utoipa::ToSchema;
+
+/// 一次采样的主机资源状况，无法读取的项为空
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct SystemStats {
+    pub cpu_count: usize,
+    pub load_1m: Option<f64>,
+    pub load_5m: Option<f64>,
+    pub load_15m: Option<f64>,
+    pub memory_total_bytes: Option<u64>,
+    pub memory_available_bytes: Option<u64>,
+    pub memory_used_pct: Option<f64>,
+    /// 统计磁盘用量的路径，SQLite 时为数据库文件所在目录
+    pub disk_path: String,
+    pub disk_total_bytes: Option<u64>,
+    pub disk_free_bytes: Option<u64>,
+    pub disk_used_pct: Option<f64>,
+    /// SQLite 数据库文件（含 WAL）大小，其他数据库为空
+    pub database_bytes: Option<u64>,
+    pub temperature_c: Option<f64>,
+    pub collected_at: Option<DateTime<Utc>>,
+}
+
+/// 从连接 URI 中取出 SQLite 数据库文件路径
+fn sqlite_file(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("sqlite://").or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next()?;
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// `/proc/loadavg` 的前三项
+fn parse_loadavg(content: &str) -> Option<(f64, f64, f64)> {
+    let mut fields = content.split_whitespace().map(|f| f.parse::<f64>().ok());
+    Some((fields.next()??, fields.next()??, fields.next()??))
+}
+
+/// `/proc/meminfo` 中的 MemTotal 和 MemAvailable（字节）
+fn parse_meminfo(content: &str) -> (Option<u64>, Option<u64>) {
+    let field = |name: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    };
+    (field("MemTotal:"), field("MemAvailable:"))
+}
+
+fn used_pct(total: u64, free: u64) -> Option<f64> {
+    (total > 0).then(|| total.saturating_sub(free) as f64 * 100.0 / total as f64)
+}
+
+/// 采集来源，按配置确定一次
+#[derive(Debug, Clone)]
+pub struct SystemProbe {
+    database_file: Option<PathBuf>,
+    disk_path: PathBuf,
+    thermal_path: PathBuf,
+}
+
+impl SystemProbe {
+    pub fn new(settings: &Settings) -> Self {
+        let database_file = sqlite_file(&settings.database.url);
+        let disk_path = match database_file.as_ref().and_then(|f| f.parent()) {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            Some(_) => PathBuf::from("."),
+            None => PathBuf::from(&settings.read_only.disk_path),
+        };
+        Self {
+            database_file,
+            disk_path,
+            thermal_path: PathBuf::from(&settings.system_monitor.thermal_path),
+        }
+    }
+
+    pub async fn collect(&self) -> SystemStats {
+        let probe = self.clone();
+        tokio::task::spawn_blocking(move || probe.collect_blocking())
+            .await
+            .unwrap_or_default()
+    }
+
+    fn collect_blocking(&self) -> SystemStats {
+        let mut stats = SystemStats {
+            cpu_count: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            disk_path: self.disk_path.to_string_lossy().into_owned(),
+            collected_at: Some(Utc::now()),
+            ..Default::default()
+        };
+
+        if let Some((l1, l5, l15)) = std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|c| parse_loadavg(&c))
+        {
+            stats.load_1m = Some(l1);
+            stats.load_5m = Some(l5);
+            stats.load_15m = Some(l15);
+        }
+
+        if let Ok(content) = std::fs::read_to_string("/proc/meminfo") {
+            let (total, available) = parse_meminfo(&content);
+            stats.memory_total_bytes = total;
+            stats.memory_available_bytes = available;
+            stats.memory_used_pct = total.zip(available).and_then(|(t, a)| used_pct(t, a));
+        }
+
+        if let Some(usage) = disk::usage(&self.disk_path) {
+            stats.disk_total_bytes = Some(usage.total_bytes);
+            stats.disk_free_bytes = Some(usage.free_bytes);
+            stats.disk_used_pct = used_pct(usage.total_bytes, usage.free_bytes);
+        }
+
+        if let Some(file) = &self.database_file {
+            let size = |path: &Path| std::fs::metadata(path).map(|m| m.len()).ok();
+            let mut wal = file.clone().into_os_string();
+            wal.push("-wal");
+            stats.database_bytes = size(file).map(|main| main + size(Path::new(&wal)).unwrap_or(0));
+        }
+
+        stats.temperature_c = std::fs::read_to_string(&self.thermal_path)
+            .ok()
+            .and_then(|c| c.trim().parse::<f64>().ok())
+            .map(|milli| milli / 1000.0);
+
+        stats
+    }
+}
+
+/// 超限项
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Breach {
+    Load,
+    Memory,
+    Disk,
+    Temperature,
+}
+
+impl Breach {
+    fn label(self) -> &'static str {
+        match self {
+            Breach::Load => "CPU负载过高",
+            Breach::Memory => "内存使用率过高",
+            Breach::Disk => "磁盘使用率过高",
+            Breach::Temperature => "SoC温度过高",
+        }
+    }
+}
+
+/// 找出超过阈值的项及其当前值
+fn breaches(config: &SystemMonitorConfig, stats: &SystemStats) -> Vec<(Breach, f64)> {
+    let load_per_cpu = stats.load_1m.map(|l| l / stats.cpu_count.max(1) as f64);
+    [
+        (Breach::Load, load_per_cpu, config.max_load_per_cpu),
+        (Breach::Memory, stats.memory_used_pct, config.max_memory_pct),
+        (Breach::Disk, stats.disk_used_pct, config.max_disk_pct),
+        (Breach::Temperature, stats.temperature_c, config.max_temperature_c),
+    ]
+    .into_iter()
+    .filter_map(|(breach, value, limit)| value.filter(|v| *v > limit).map(|v| (breach, v)))
+    .collect()
+}
+
+/// 后台任务：定期采集并检查阈值
+pub async fn run_monitor(probe: SystemProbe, config: SystemMonitorConfig, db: DbManager) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+    // 已报警、尚未恢复的项
+    let mut active: HashSet<Breach> = HashSet::new();
+
+    loop {
+        ticker.tick().await;
+        let stats = probe.collect().await;
+        let current = breaches(&config, &stats);
+        active.retain(|b| current.iter().any(|(c, _)| c == b));
+
+        for (breach, value) in current {
+            if !active.insert(breach) {
+                continue;
+            }
+            let rule_name = format!("{}: {:.1}", breach.label(), value);
+            let conn = db.get_connection();
+            if let Err(e) = alarm::raise(conn, None, rule_name, value, SEVERITY_WARNING).await {
+                error!("Failed to raise system alarm: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_file() {
+        assert_eq!(sqlite_file("sqlite://guolu.db?mode=rwc"), Some(PathBuf::from("guolu.db")));
+        assert_eq!(sqlite_file("sqlite:/data/guolu.db"), Some(PathBuf::from("/data/guolu.db")));
+        assert_eq!(sqlite_file("sqlite::memory:"), None);
+        assert_eq!(sqlite_file("postgres://u:p@host/db"), None);
+    }
+
+    #[test]
+    fn test_parse_proc() {
+        assert_eq!(parse_loadavg("0.52 0.41 0.30 1/123 4567\n"), Some((0.52, 0.41, 0.30)));
+        assert_eq!(parse_loadavg(""), None);
+
+        let meminfo = "MemTotal:        2000000 kB\n\
+                       MemFree:          100000 kB\n\
+                       MemAvailable:     500000 kB\n";
+        assert_eq!(parse_meminfo(meminfo), (Some(2_048_000_000), Some(512_000_000)));
+    }
+
+    #[test]
+    fn test_breaches() {
+        let config = SystemMonitorConfig::default();
+        let stats = SystemStats {
+            cpu_count: 4,
+            load_1m: Some(4.0),
+            disk_used_pct: Some(95.0),
+            temperature_c: Some(60.0),
+            ..Default::default()
+        };
+        assert_eq!(breaches(&config, &stats), vec![(Breach::Disk, 95.0)]);
+    }
+}
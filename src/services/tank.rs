@@ -0,0 +1,223 @@
+//! 液位计容积换算
+//!
+//! 超声波液位计上报探头到液面的距离，按罐体几何换算为液位、容积和充满度，
+//! 作为 `level`、`volume`、`percent_full` 三个指标与原始距离一并写入。
+
+use crate::models::calibration_curve::KIND_PIECEWISE;
+use crate::models::measurement::Model as Measurement;
+use crate::models::tank_geometry::{
+    Column as TankColumn, Entity as TankEntity, Model as TankGeometry, SHAPE_CONE, SHAPE_CYLINDER,
+    SHAPE_STRAPPING,
+};
+use crate::services::calibration::Curve;
+use crate::services::measurement::NewMeasurement;
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// 立式圆柱罐
+    Cylinder { radius: f64 },
+    /// 锥底圆柱罐，锥体部分高 `cone_height`
+    Cone { radius: f64, cone_height: f64 },
+    /// 按容积表插值
+    Strapping(Curve),
+}
+
+/// 换算用的罐体参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tank {
+    shape: Shape,
+    sensor_offset: f64,
+    height: f64,
+}
+
+/// 一次换算的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TankReading {
+    pub level: f64,
+    pub volume: f64,
+    pub percent_full: f64,
+}
+
+impl Tank {
+    pub fn from_geometry(geometry: &TankGeometry) -> Result<Self, AppError> {
+        let invalid = |msg: &str| AppError::InvalidInput(format!("罐体参数无效: {}", msg).into());
+        let positive = |v: Option<f64>| v.filter(|v| v.is_finite() && *v > 0.0);
+
+        if !(geometry.height.is_finite() && geometry.height > 0.0) {
+            return Err(invalid("最高液位必须大于 0"));
+        }
+        if !(geometry.sensor_offset.is_finite() && geometry.sensor_offset >= geometry.height) {
+            return Err(invalid("探头到罐底的距离不能小于最高液位"));
+        }
+
+        let shape = match geometry.shape.as_str() {
+            SHAPE_CYLINDER => Shape::Cylinder {
+                radius: positive(geometry.diameter).ok_or_else(|| invalid("缺少直径"))? / 2.0,
+            },
+            SHAPE_CONE => {
+                let radius = positive(geometry.diameter).ok_or_else(|| invalid("缺少直径"))? / 2.0;
+                let cone_height = positive(geometry.cone_height).ok_or_else(|| invalid("缺少锥底高度"))?;
+                if cone_height > geometry.height {
+                    return Err(invalid("锥底高度不能超过最高液位"));
+                }
+                Shape::Cone { radius, cone_height }
+            }
+            SHAPE_STRAPPING => {
+                let table = geometry.strapping_table.as_deref().ok_or_else(|| invalid("缺少容积表"))?;
+                Shape::Strapping(Curve::parse(KIND_PIECEWISE, table)?)
+            }
+            other => return Err(invalid(&format!("未知的罐体形状 {}", other))),
+        };
+
+        Ok(Self {
+            shape,
+            sensor_offset: geometry.sensor_offset,
+            height: geometry.height,
+        })
+    }
+
+    /// 指定液位下的容积
+    fn volume(&self, level: f64) -> f64 {
+        match &self.shape {
+            Shape::Cylinder { radius } => PI * radius * radius * level,
+            Shape::Cone { radius, cone_height } => {
+                let area = PI * radius * radius;
+                if level <= *cone_height {
+                    area * level.powi(3) / (3.0 * cone_height * cone_height)
+                } else {
+                    area * cone_height / 3.0 + area * (level - cone_height)
+                }
+            }
+            Shape::Strapping(table) => table.apply(level).max(0.0),
+        }
+    }
+
+    /// 由探头距离换算液位、容积和充满度
+    pub fn reading(&self, distance: f64) -> TankReading {
+        let level = (self.sensor_offset - distance).clamp(0.0, self.height);
+        let volume = self.volume(level);
+        let capacity = self.volume(self.height);
+        let percent_full = if capacity > 0.0 {
+            (volume / capacity * 100.0).clamp(0.0, 100.0)
+        } else {
+            0.0
+        };
+
+        TankReading {
+            level,
+            volume,
+            percent_full,
+        }
+    }
+}
+
+pub async fn find_geometry(
+    conn: &DatabaseConnection,
+    device_id: i32,
+) -> Result<Option<TankGeometry>, AppError> {
+    TankEntity::find()
+        .filter(TankColumn::DeviceId.eq(device_id))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 距离测量值对应的推算指标，没有配置罐体时为空
+pub async fn derive(
+    conn: &DatabaseConnection,
+    measurement: &Measurement,
+) -> Result<Vec<NewMeasurement>, AppError> {
+    if measurement.metric_type != metric_registry::DISTANCE {
+        return Ok(Vec::new());
+    }
+    let Some(device_id) = measurement.device_id else {
+        return Ok(Vec::new());
+    };
+    let Some(geometry) = find_geometry(conn, device_id).await? else {
+        return Ok(Vec::new());
+    };
+
+    let reading = Tank::from_geometry(&geometry)?.reading(measurement.value);
+    Ok([
+        (metric_registry::LEVEL, reading.level),
+        (metric_registry::VOLUME, reading.volume),
+        (metric_registry::PERCENT_FULL, reading.percent_full),
+    ]
+    .into_iter()
+    .map(|(metric_type, value)| NewMeasurement {
+        metric_type: metric_type.to_string(),
+        timestamp: measurement.timestamp,
+        value,
+        device_id: Some(device_id),
+        unit: None,
+    })
+    .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn geometry(shape: &str) -> TankGeometry {
+        TankGeometry {
+            id: 1,
+            device_id: 1,
+            shape: shape.to_string(),
+            sensor_offset: 5.0,
+            height: 4.0,
+            diameter: Some(2.0),
+            cone_height: Some(1.0),
+            strapping_table: Some("[[0, 0], [2, 10], [4, 30]]".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_cylinder() {
+        let tank = Tank::from_geometry(&geometry(SHAPE_CYLINDER)).unwrap();
+
+        let reading = tank.reading(3.0);
+        assert_eq!(reading.level, 2.0);
+        assert!((reading.volume - 2.0 * PI).abs() < 1e-9);
+        assert!((reading.percent_full - 50.0).abs() < 1e-9);
+
+        // 距离超出范围时液位截断
+        assert_eq!(tank.reading(6.0).level, 0.0);
+        assert_eq!(tank.reading(0.5).percent_full, 100.0);
+    }
+
+    #[test]
+    fn test_cone() {
+        let tank = Tank::from_geometry(&geometry(SHAPE_CONE)).unwrap();
+
+        assert!((tank.reading(4.0).volume - PI / 3.0).abs() < 1e-9);
+        assert!((tank.reading(3.0).volume - (PI / 3.0 + PI)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strapping() {
+        let tank = Tank::from_geometry(&geometry(SHAPE_STRAPPING)).unwrap();
+
+        let reading = tank.reading(2.0);
+        assert_eq!(reading.level, 3.0);
+        assert_eq!(reading.volume, 20.0);
+        assert!((reading.percent_full - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_geometry() {
+        let mut g = geometry(SHAPE_CYLINDER);
+        g.diameter = None;
+        assert!(Tank::from_geometry(&g).is_err());
+
+        let mut g = geometry(SHAPE_CYLINDER);
+        g.sensor_offset = 3.0;
+        assert!(Tank::from_geometry(&g).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! 时序数据存储
+//!
+//! 设备、报警等元数据始终保存在关系数据库中，测量值的聚合查询按配置交给时序后端：
+//! - `sql`：直接在测量值表上按时间桶分组
+//! - `timescaledb`：测量值表转为 hypertable，用 `time_bucket` 聚合
+//! - `influxdb`：测量值由后台任务按 id 顺序写入 InfluxDB（见 [`crate::services::influxdb`]），
+//!   聚合在 InfluxDB 中完成，关系库中的原始数据可按保留期尽早清理
+
+use crate::config::timeseries::TimeSeriesConfig;
+use crate::services::influxdb::InfluxStore;
+use crate::services::measurement::{self, AggregatePoint, MeasurementFilter};
+use crate::utils::error::AppError;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tracing::warn;
+
+pub const BACKEND_SQL: &str = "sql";
+pub const BACKEND_TIMESCALEDB: &str = "timescaledb";
+pub const BACKEND_INFLUXDB: &str = "influxdb";
+
+#[tonic::async_trait]
+pub trait TimeSeriesStore: Send + Sync + std::fmt::Debug {
+    fn backend(&self) -> &'static str;
+
+    /// 按时间桶聚合，返回按时间升序的点
+    async fn aggregate(
+        &self,
+        conn: &DatabaseConnection,
+        filter: &MeasurementFilter,
+        bucket_secs: i64,
+    ) -> Result<Vec<AggregatePoint>, AppError>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SqlStore;
+
+#[tonic::async_trait]
+impl TimeSeriesStore for SqlStore {
+    fn backend(&self) -> &'static str {
+        BACKEND_SQL
+    }
+
+    async fn aggregate(
+        &self,
+        conn: &DatabaseConnection,
+        filter: &MeasurementFilter,
+        bucket_secs: i64,
+    ) -> Result<Vec<AggregatePoint>, AppError> {
+        measurement::aggregate(conn, filter, bucket_secs).await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimescaleStore;
+
+/// `time_bucket` 按 Unix 纪元对齐，与其他后端的时间桶一致
+fn time_bucket_expr(bucket_secs: i64) -> String {
+    format!(
+        "CAST(EXTRACT(EPOCH FROM time_bucket(INTERVAL '{} seconds', \"timestamp\", \
+         TIMESTAMPTZ '1970-01-01 00:00:00+00')) AS BIGINT)",
+        bucket_secs
+    )
+}
+
+#[tonic::async_trait]
+impl TimeSeriesStore for TimescaleStore {
+    fn backend(&self) -> &'static str {
+        BACKEND_TIMESCALEDB
+    }
+
+    async fn aggregate(
+        &self,
+        conn: &DatabaseConnection,
+        filter: &MeasurementFilter,
+        bucket_secs: i64,
+    ) -> Result<Vec<AggregatePoint>, AppError> {
+        measurement::aggregate_by(conn, filter, time_bucket_expr(bucket_secs.max(1))).await
+    }
+}
+
+/// 按配置选择后端；`hypertable` 为启动时转换 hypertable 的结果，失败时退回 sql
+pub fn select(config: &TimeSeriesConfig, hypertable: bool) -> Arc<dyn TimeSeriesStore> {
+    match config.backend.as_str() {
+        BACKEND_TIMESCALEDB if hypertable => Arc::new(TimescaleStore),
+        BACKEND_TIMESCALEDB => {
+            warn!("TimescaleDB hypertable unavailable, aggregating on the SQL table");
+            Arc::new(SqlStore)
+        }
+        BACKEND_INFLUXDB => match InfluxStore::new(&config.influxdb) {
+            Ok(store) => Arc::new(store),
+            Err(e) => {
+                warn!("Failed to create InfluxDB client, aggregating on the SQL table: {}", e);
+                Arc::new(SqlStore)
+            }
+        },
+        BACKEND_SQL => Arc::new(SqlStore),
+        other => {
+            warn!("Unknown time-series backend {}, using sql", other);
+            Arc::new(SqlStore)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let mut config = TimeSeriesConfig::default();
+        assert_eq!(select(&config, false).backend(), BACKEND_SQL);
+        config.backend = BACKEND_TIMESCALEDB.to_string();
+        assert_eq!(select(&config, false).backend(), BACKEND_SQL);
+        assert_eq!(select(&config, true).backend(), BACKEND_TIMESCALEDB);
+        config.backend = BACKEND_INFLUXDB.to_string();
+        assert_eq!(select(&config, false).backend(), BACKEND_INFLUXDB);
+    }
+}
@@ -0,0 +1,296 @@
+//! 长期趋势
+//!
+//! 按时间范围自动选择分辨率：短范围返回原始数据，中等范围按小时聚合，
+//! 更长的范围按天聚合。指定设备时聚合数据优先读取小时汇总和每日汇总表，尚未汇总的时段
+//! （通常是最近一小时或当天）再从原始数据补算。原始数据点数超过上限时改用小时聚合。
+
+use crate::config::trend::TrendConfig;
+use crate::models::daily_summary::{Column as DailySummaryColumn, Entity as DailySummaryEntity};
+use crate::models::hourly_summary::{Column as HourlySummaryColumn, Entity as HourlySummaryEntity};
+use crate::models::measurement::{Column as MeasurementColumn, Entity as MeasurementEntity};
+use crate::services::measurement::{AggregatePoint, MeasurementFilter};
+use crate::services::timeseries::TimeSeriesStore;
+use crate::utils::error::AppError;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const HOUR_SECS: i64 = 3600;
+const DAY_SECS: i64 = 86400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl Resolution {
+    /// 聚合时间桶长度，原始数据为空
+    pub fn bucket_secs(self) -> Option<i64> {
+        match self {
+            Resolution::Raw => None,
+            Resolution::Hourly => Some(HOUR_SECS),
+            Resolution::Daily => Some(DAY_SECS),
+        }
+    }
+}
+
+/// 趋势上的一个点；原始数据的最小、最大、平均值相同，条数为 1
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct TrendPoint {
+    /// 原始数据为采样时间，聚合数据为时间桶起点
+    pub timestamp: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Trend {
+    pub metric: String,
+    pub device_id: Option<i32>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 实际使用的分辨率
+    pub resolution: Resolution,
+    pub bucket_secs: Option<i64>,
+    pub points: Vec<TrendPoint>,
+}
+
+/// 按时间范围选择分辨率
+pub fn choose(start: DateTime<Utc>, end: DateTime<Utc>, config: &TrendConfig) -> Resolution {
+    let range = end - start;
+    if range <= Duration::hours(config.raw_max_hours as i64) {
+        Resolution::Raw
+    } else if range <= Duration::days(config.hourly_max_days as i64) {
+        Resolution::Hourly
+    } else {
+        Resolution::Daily
+    }
+}
+
+impl From<AggregatePoint> for TrendPoint {
+    fn from(point: AggregatePoint) -> Self {
+        Self {
+            timestamp: DateTime::from_timestamp(point.bucket, 0).unwrap_or_default(),
+            min: point.min,
+            max: point.max,
+            avg: point.avg,
+            count: point.count,
+        }
+    }
+}
+
+async fn aggregated(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    filter: &MeasurementFilter,
+    bucket_secs: i64,
+) -> Result<Vec<TrendPoint>, AppError> {
+    let points = store.aggregate(conn, filter, bucket_secs).await?;
+    Ok(points.into_iter().map(TrendPoint::from).collect())
+}
+
+/// 范围内的原始数据，超过上限时返回空
+async fn raw(
+    conn: &DatabaseConnection,
+    filter: &MeasurementFilter,
+    max_points: u64,
+) -> Result<Option<Vec<TrendPoint>>, AppError> {
+    let mut query = MeasurementEntity::find();
+    if let Some(metric_type) = &filter.metric_type {
+        query = query.filter(MeasurementColumn::MetricType.eq(metric_type.as_str()));
+    }
+    if let Some(device_id) = filter.device_id {
+        query = query.filter(MeasurementColumn::DeviceId.eq(device_id));
+    }
+    if let Some(start) = filter.start {
+        query = query.filter(MeasurementColumn::Timestamp.gte(start));
+    }
+    if let Some(end) = filter.end {
+        query = query.filter(MeasurementColumn::Timestamp.lte(end));
+    }
+
+    let rows = query
+        .order_by_asc(MeasurementColumn::Timestamp)
+        .limit(max_points + 1)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+    if rows.len() as u64 > max_points {
+        return Ok(None);
+    }
+    Ok(Some(
+        rows.into_iter()
+            .map(|m| TrendPoint {
+                timestamp: m.timestamp,
+                min: m.value,
+                max: m.value,
+                avg: m.value,
+                count: 1,
+            })
+            .collect(),
+    ))
+}
+
+/// 汇总表中 `[start, end)` 内的整段统计
+async fn rollups(
+    conn: &DatabaseConnection,
+    resolution: Resolution,
+    device_id: i32,
+    metric_type: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<TrendPoint>, AppError> {
+    let points = match resolution {
+        Resolution::Hourly => HourlySummaryEntity::find()
+            .filter(HourlySummaryColumn::DeviceId.eq(device_id))
+            .filter(HourlySummaryColumn::MetricType.eq(metric_type))
+            .filter(HourlySummaryColumn::Hour.gte(start))
+            .filter(HourlySummaryColumn::Hour.lt(end))
+            .order_by_asc(HourlySummaryColumn::Hour)
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .into_iter()
+            .map(|row| TrendPoint {
+                timestamp: row.hour,
+                min: row.min,
+                max: row.max,
+                avg: row.avg,
+                count: row.count,
+            })
+            .collect(),
+        Resolution::Daily => DailySummaryEntity::find()
+            .filter(DailySummaryColumn::DeviceId.eq(device_id))
+            .filter(DailySummaryColumn::MetricType.eq(metric_type))
+            .filter(DailySummaryColumn::Day.gte(start.date_naive()))
+            .filter(DailySummaryColumn::Day.lt(end.date_naive()))
+            .order_by_asc(DailySummaryColumn::Day)
+            .all(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .into_iter()
+            .map(|row| TrendPoint {
+                timestamp: row.day.and_time(chrono::NaiveTime::MIN).and_utc(),
+                min: row.min,
+                max: row.max,
+                avg: row.avg,
+                count: row.count,
+            })
+            .collect(),
+        Resolution::Raw => Vec::new(),
+    };
+    Ok(points)
+}
+
+/// 聚合数据：整段时间读汇总表，首尾不完整的时段和尚未汇总的时段从原始数据计算
+async fn rolled_up(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    filter: &MeasurementFilter,
+    resolution: Resolution,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<TrendPoint>, AppError> {
+    let bucket_secs = resolution.bucket_secs().unwrap_or(HOUR_SECS);
+    // 汇总表只有关联设备的单个指标
+    let (Some(device_id), Some(metric_type)) = (filter.device_id, &filter.metric_type) else {
+        return aggregated(conn, store, filter, bucket_secs).await;
+    };
+
+    let bucket = Duration::seconds(bucket_secs);
+    let floor = |time: DateTime<Utc>| time.duration_trunc(bucket).unwrap_or(time);
+    let first_full = if floor(start) < start { floor(start) + bucket } else { start };
+    let full_end = floor(end);
+    let summaries = if first_full < full_end {
+        rollups(conn, resolution, device_id, metric_type, first_full, full_end).await?
+    } else {
+        Vec::new()
+    };
+
+    let tail_start = match summaries.last() {
+        Some(last) => last.timestamp + bucket,
+        None => start,
+    };
+    let mut points = Vec::new();
+    if !summaries.is_empty() && start < first_full {
+        let head = MeasurementFilter {
+            end: Some(first_full - Duration::milliseconds(1)),
+            ..filter.clone()
+        };
+        points.extend(aggregated(conn, store, &head, bucket_secs).await?);
+    }
+    points.extend(summaries);
+    if tail_start <= end {
+        let tail = MeasurementFilter { start: Some(tail_start.max(start)), ..filter.clone() };
+        points.extend(aggregated(conn, store, &tail, bucket_secs).await?);
+    }
+    Ok(points)
+}
+
+/// 查询趋势，返回实际使用的分辨率
+pub async fn trend(
+    conn: &DatabaseConnection,
+    store: &dyn TimeSeriesStore,
+    metric: String,
+    device_id: Option<i32>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &TrendConfig,
+) -> Result<Trend, AppError> {
+    if start >= end {
+        return Err(AppError::InvalidInput("开始时间必须早于结束时间".into()));
+    }
+
+    let filter = MeasurementFilter {
+        metric_type: Some(metric.clone()),
+        device_id,
+        start: Some(start),
+        end: Some(end),
+        ..Default::default()
+    };
+    let mut resolution = choose(start, end, config);
+    let points = match resolution {
+        Resolution::Raw => match raw(conn, &filter, config.max_raw_points).await? {
+            Some(points) => points,
+            None => {
+                resolution = Resolution::Hourly;
+                rolled_up(conn, store, &filter, resolution, start, end).await?
+            }
+        },
+        Resolution::Hourly | Resolution::Daily => {
+            rolled_up(conn, store, &filter, resolution, start, end).await?
+        }
+    };
+
+    Ok(Trend {
+        metric,
+        device_id,
+        start,
+        end,
+        resolution,
+        bucket_secs: resolution.bucket_secs(),
+        points,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_choose() {
+        let config = TrendConfig::default();
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(choose(start, start + Duration::hours(24), &config), Resolution::Raw);
+        assert_eq!(choose(start, start + Duration::hours(25), &config), Resolution::Hourly);
+        assert_eq!(choose(start, start + Duration::days(60), &config), Resolution::Hourly);
+        assert_eq!(choose(start, start + Duration::days(3 * 365), &config), Resolution::Daily);
+    }
+}
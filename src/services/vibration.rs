@@ -0,0 +1,339 @@
+//! 振动状态监测
+//!
+//! 振动传感器上报的频带值或原始波形按宽行整条存入 `vibration_records`，不拆成逐点测量值；
+//! 只把总振动有效值和轴承温度写入测量表，便于和工艺参数放在一起看。
+//! 频带或总值超过阈值时写入"振动超限"报警，同一频带恢复正常后才会再次报警。
+
+use crate::config::quality::QualityConfig;
+use crate::models::alarm_log::SEVERITY_MAJOR;
+use crate::models::vibration_limit::{
+    Column as VibrationLimitColumn, Entity as VibrationLimitEntity, Model as VibrationLimit,
+};
+use crate::models::vibration_record::{
+    ActiveModel as VibrationRecordActiveModel, Column as VibrationRecordColumn,
+    Entity as VibrationRecordEntity, Model as VibrationRecord, KIND_BANDS, KIND_BURST,
+};
+use crate::services::alarm;
+use crate::services::cache::HotCache;
+use crate::services::measurement::{self, NewMeasurement};
+use crate::services::metric_registry;
+use crate::utils::error::AppError;
+use chrono::{DateTime, TimeZone, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// 单条记录最多的频带/采样点数
+pub const MAX_VALUES: usize = 65536;
+/// 趋势查询最多扫描的记录数
+const TREND_SCAN_LIMIT: u64 = 20000;
+/// 总值在报警中的标签
+const OVERALL_LABEL: &str = "总值";
+
+/// 频带阈值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BandLimit {
+    /// 频带序号，从 0 开始
+    pub band: usize,
+    /// 显示名称，例如 1X、轴承外圈
+    pub label: String,
+    pub limit: f64,
+}
+
+/// 一次上报
+#[derive(Debug, Clone)]
+pub struct NewVibration {
+    pub device_id: i32,
+    pub timestamp: DateTime<Utc>,
+    pub kind: String,
+    pub sample_rate: Option<f64>,
+    pub band_width: Option<f64>,
+    pub values: Vec<f64>,
+    pub temperature: Option<f64>,
+}
+
+/// 趋势中的一个时间桶
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct TrendPoint {
+    pub timestamp: DateTime<Utc>,
+    pub avg: f64,
+    pub max: f64,
+    pub count: usize,
+}
+
+/// 计算总值和峰值：频带值按能量叠加，波形按采样点求有效值
+fn summarize(kind: &str, values: &[f64]) -> Result<(f64, f64), AppError> {
+    if values.is_empty() || values.len() > MAX_VALUES {
+        return Err(AppError::InvalidInput(format!("数据点数须在 1 到 {} 之间", MAX_VALUES).into()));
+    }
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(AppError::InvalidInput("数据中包含无效数值".into()));
+    }
+
+    let sum_sq: f64 = values.iter().map(|v| v * v).sum();
+    let peak = values.iter().fold(0.0_f64, |acc, v| acc.max(v.abs()));
+    let rms = match kind {
+        KIND_BANDS => sum_sq.sqrt(),
+        KIND_BURST => (sum_sq / values.len() as f64).sqrt(),
+        other => return Err(AppError::InvalidInput(format!("未知的数据类型: {}", other).into())),
+    };
+    Ok((rms, peak))
+}
+
+pub fn parse_limits(limit: &VibrationLimit) -> Result<Vec<BandLimit>, AppError> {
+    serde_json::from_str(&limit.band_limits)
+        .map_err(|e| AppError::InvalidInput(format!("频带阈值格式错误: {}", e).into()))
+}
+
+/// 校验阈值定义
+pub fn validate_limit(limit: &VibrationLimit) -> Result<(), AppError> {
+    let bands = parse_limits(limit)?;
+    let valid = |v: f64| v.is_finite() && v > 0.0;
+    if bands.iter().any(|b| !valid(b.limit)) || limit.rms_limit.is_some_and(|v| !valid(v)) {
+        return Err(AppError::InvalidInput("阈值须为正数".into()));
+    }
+    Ok(())
+}
+
+/// 超过阈值的频带标签及数值
+fn exceedances(limit: &VibrationLimit, record: &VibrationRecord) -> Vec<(String, f64)> {
+    let mut exceeded = Vec::new();
+    if record.kind == KIND_BANDS {
+        if let (Ok(bands), Ok(values)) = (parse_limits(limit), serde_json::from_str::<Vec<f64>>(&record.values)) {
+            for band in bands {
+                if let Some(&value) = values.get(band.band) {
+                    if value > band.limit {
+                        exceeded.push((band.label, value));
+                    }
+                }
+            }
+        }
+    }
+    if limit.rms_limit.is_some_and(|l| record.overall_rms > l) {
+        exceeded.push((OVERALL_LABEL.to_string(), record.overall_rms));
+    }
+    exceeded
+}
+
+pub async fn find_limit(conn: &DatabaseConnection, device_id: i32) -> Result<Option<VibrationLimit>, AppError> {
+    VibrationLimitEntity::find()
+        .filter(VibrationLimitColumn::DeviceId.eq(device_id))
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+async fn latest_record(conn: &DatabaseConnection, device_id: i32) -> Result<Option<VibrationRecord>, AppError> {
+    VibrationRecordEntity::find()
+        .filter(VibrationRecordColumn::DeviceId.eq(device_id))
+        .order_by_desc(VibrationRecordColumn::Timestamp)
+        .one(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 保存一条上报并检查阈值
+pub async fn ingest(
+    conn: &DatabaseConnection,
+    cache: &HotCache,
+    quality: &QualityConfig,
+    new: NewVibration,
+) -> Result<VibrationRecord, AppError> {
+    let (overall_rms, peak) = summarize(&new.kind, &new.values)?;
+    let previous = latest_record(conn, new.device_id).await?;
+
+    let active_model = VibrationRecordActiveModel {
+        device_id: Set(new.device_id),
+        timestamp: Set(new.timestamp),
+        kind: Set(new.kind),
+        sample_rate: Set(new.sample_rate),
+        band_width: Set(new.band_width),
+        values: Set(serde_json::to_string(&new.values).map_err(|_| AppError::InternalError)?),
+        overall_rms: Set(overall_rms),
+        peak: Set(peak),
+        temperature: Set(new.temperature),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    let record = VibrationRecordEntity::insert(active_model)
+        .exec_with_returning(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let mut summary = vec![(metric_registry::VIBRATION_RMS, overall_rms)];
+    if let Some(temperature) = record.temperature {
+        summary.push((metric_registry::TEMPERATURE, temperature));
+    }
+    for (metric_type, value) in summary {
+        measurement::create(
+            conn,
+            cache,
+            quality,
+            NewMeasurement {
+                metric_type: metric_type.to_string(),
+                timestamp: record.timestamp,
+                value,
+                device_id: Some(record.device_id),
+                unit: None,
+            },
+        )
+        .await?;
+    }
+
+    if let Some(limit) = find_limit(conn, record.device_id).await? {
+        let before: Vec<String> = previous
+            .map(|p| exceedances(&limit, &p).into_iter().map(|(label, _)| label).collect())
+            .unwrap_or_default();
+        for (label, value) in exceedances(&limit, &record) {
+            if !before.contains(&label) {
+                let rule_name = format!("振动超限: 设备{} {}", record.device_id, label);
+                alarm::raise(conn, Some(record.device_id), rule_name, value, SEVERITY_MAJOR).await?;
+            }
+        }
+    }
+
+    Ok(record)
+}
+
+/// 按时间倒序列出记录
+pub async fn list(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    limit: u64,
+) -> Result<Vec<VibrationRecord>, AppError> {
+    let mut query = VibrationRecordEntity::find().filter(VibrationRecordColumn::DeviceId.eq(device_id));
+    if let Some(start) = start {
+        query = query.filter(VibrationRecordColumn::Timestamp.gte(start));
+    }
+    if let Some(end) = end {
+        query = query.filter(VibrationRecordColumn::Timestamp.lte(end));
+    }
+
+    query
+        .order_by_desc(VibrationRecordColumn::Timestamp)
+        .limit(limit)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+/// 按时间桶聚合，桶起点对齐到 `bucket_secs` 的整数倍
+fn bucket_points(points: &[(DateTime<Utc>, f64)], bucket_secs: i64) -> Vec<TrendPoint> {
+    let mut buckets: BTreeMap<i64, (f64, f64, usize)> = BTreeMap::new();
+    for (timestamp, value) in points {
+        let key = timestamp.timestamp().div_euclid(bucket_secs) * bucket_secs;
+        let entry = buckets.entry(key).or_insert((0.0, f64::MIN, 0));
+        entry.0 += value;
+        entry.1 = entry.1.max(*value);
+        entry.2 += 1;
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(key, (sum, max, count))| {
+            Some(TrendPoint {
+                timestamp: Utc.timestamp_opt(key, 0).single()?,
+                avg: sum / count as f64,
+                max,
+                count,
+            })
+        })
+        .collect()
+}
+
+/// 频带（或不指定时的总值）趋势
+pub async fn trend(
+    conn: &DatabaseConnection,
+    device_id: i32,
+    band: Option<usize>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket_secs: i64,
+) -> Result<Vec<TrendPoint>, AppError> {
+    let records = VibrationRecordEntity::find()
+        .filter(VibrationRecordColumn::DeviceId.eq(device_id))
+        .filter(VibrationRecordColumn::Timestamp.gte(start))
+        .filter(VibrationRecordColumn::Timestamp.lte(end))
+        .order_by_asc(VibrationRecordColumn::Timestamp)
+        .limit(TREND_SCAN_LIMIT)
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)?;
+
+    let points: Vec<(DateTime<Utc>, f64)> = records
+        .iter()
+        .filter_map(|record| match band {
+            None => Some((record.timestamp, record.overall_rms)),
+            Some(band) if record.kind == KIND_BANDS => serde_json::from_str::<Vec<f64>>(&record.values)
+                .ok()
+                .and_then(|values| values.get(band).copied())
+                .map(|value| (record.timestamp, value)),
+            Some(_) => None,
+        })
+        .collect();
+
+    Ok(bucket_points(&points, bucket_secs.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: &str, values: &[f64], overall_rms: f64) -> VibrationRecord {
+        VibrationRecord {
+            id: 1,
+            device_id: 1,
+            timestamp: Utc::now(),
+            kind: kind.to_string(),
+            sample_rate: None,
+            band_width: None,
+            values: serde_json::to_string(values).unwrap(),
+            overall_rms,
+            peak: 0.0,
+            temperature: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize(KIND_BANDS, &[3.0, 4.0]).unwrap(), (5.0, 4.0));
+        assert_eq!(summarize(KIND_BURST, &[1.0, -1.0, 1.0, -1.0]).unwrap(), (1.0, 1.0));
+        assert!(summarize(KIND_BANDS, &[]).is_err());
+        assert!(summarize("fft", &[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_exceedances() {
+        let limit = VibrationLimit {
+            id: 1,
+            device_id: 1,
+            band_limits: r#"[{"band": 0, "label": "1X", "limit": 2.0}, {"band": 5, "label": "BPFO", "limit": 1.0}]"#.to_string(),
+            rms_limit: Some(4.0),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let exceeded = exceedances(&limit, &record(KIND_BANDS, &[3.0, 1.0], 3.2));
+        assert_eq!(exceeded, vec![("1X".to_string(), 3.0)]);
+
+        // 波形只检查总值
+        let exceeded = exceedances(&limit, &record(KIND_BURST, &[3.0, 1.0], 4.5));
+        assert_eq!(exceeded, vec![(OVERALL_LABEL.to_string(), 4.5)]);
+    }
+
+    #[test]
+    fn test_bucket_points() {
+        let t = |secs| Utc.timestamp_opt(secs, 0).unwrap();
+        let points = vec![(t(3600), 1.0), (t(3700), 3.0), (t(7300), 5.0)];
+
+        let buckets = bucket_points(&points, 3600);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].timestamp, t(3600));
+        assert_eq!((buckets[0].avg, buckets[0].max, buckets[0].count), (2.0, 3.0, 2));
+        assert_eq!((buckets[1].avg, buckets[1].max, buckets[1].count), (5.0, 5.0, 1));
+    }
+}
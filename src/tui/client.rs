@@ -0,0 +1,99 @@
+//! 状态监视使用的本机 API 客户端
+
+use crate::handlers::system::QueueDepths;
+use crate::models::alarm_log::Model as AlarmLog;
+use crate::models::device::Model as Device;
+use crate::services::latest::DeviceLatest;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 报警列表显示的条数
+const RECENT_ALARMS: usize = 20;
+
+/// 一次轮询得到的全部数据
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub devices: Vec<Device>,
+    /// 以设备ID为键的最新测量值
+    pub latest: HashMap<i32, DeviceLatest>,
+    /// 按触发时间倒序
+    pub alarms: Vec<AlarmLog>,
+    pub queues: Option<QueueDepths>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// 本次轮询中失败的请求
+    pub errors: Vec<String>,
+}
+
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ApiClient {
+    pub fn new(base_url: String) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let response = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if !response.status().is_success() {
+            return Err(format!("{}: HTTP {}", path, response.status()));
+        }
+        response.json().await.map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// 并发请求各接口，单个接口失败不影响其他数据显示
+    pub async fn snapshot(&self) -> Snapshot {
+        let (devices, latest, alarms, queues) = tokio::join!(
+            self.get::<Vec<Device>>("/devices?per_page=100"),
+            self.get::<Vec<DeviceLatest>>("/devices/latest"),
+            self.get::<Vec<AlarmLog>>("/alarm-logs?per_page=100"),
+            self.get::<QueueDepths>("/system/queues"),
+        );
+
+        let mut snapshot = Snapshot {
+            fetched_at: Some(Utc::now()),
+            ..Default::default()
+        };
+        match devices {
+            Ok(devices) => snapshot.devices = devices,
+            Err(e) => snapshot.errors.push(e),
+        }
+        match latest {
+            Ok(latest) => {
+                snapshot.latest = latest.into_iter().map(|d| (d.device_id, d)).collect();
+            }
+            Err(e) => snapshot.errors.push(e),
+        }
+        match alarms {
+            Ok(mut alarms) => {
+                alarms.sort_by(|a, b| b.trigger_time.cmp(&a.trigger_time));
+                alarms.truncate(RECENT_ALARMS);
+                snapshot.alarms = alarms;
+            }
+            Err(e) => snapshot.errors.push(e),
+        }
+        match queues {
+            Ok(queues) => snapshot.queues = Some(queues),
+            Err(e) => snapshot.errors.push(e),
+        }
+        snapshot
+    }
+}
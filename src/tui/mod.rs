@@ -0,0 +1,72 @@
+//! 终端状态监视
+//!
+//! `guolu tui [地址]` 通过本机 API 轮询设备状态、最近报警与队列积压，供现场调试人员
+//! 在只有 SSH 的情况下查看运行情况。按 `q` 退出，`r` 立即刷新。
+
+pub mod client;
+pub mod ui;
+
+use client::{ApiClient, Snapshot};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use futures_util::StreamExt;
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io;
+use std::time::Duration;
+
+pub const SUBCOMMAND: &str = "tui";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 运行状态监视，直到用户退出
+pub async fn run(base_url: String) -> anyhow::Result<()> {
+    let client = ApiClient::new(base_url)?;
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    let result = event_loop(&mut terminal, &client).await;
+
+    // 无论是否出错都要恢复终端
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+async fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    client: &ApiClient,
+) -> anyhow::Result<()> {
+    let mut events = EventStream::new();
+    let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+    let mut snapshot = Snapshot::default();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                snapshot = client.snapshot().await;
+            }
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                    KeyCode::Char('r') => {
+                        snapshot = client.snapshot().await;
+                        ticker.reset();
+                    }
+                    _ => {}
+                },
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+        }
+
+        terminal.draw(|frame| ui::draw(frame, client.base_url(), &snapshot))?;
+    }
+}
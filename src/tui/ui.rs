@@ -0,0 +1,157 @@
+//! 状态监视界面绘制
+
+use super::client::Snapshot;
+use chrono::Local;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use ratatui::Frame;
+
+pub fn draw(frame: &mut Frame, base_url: &str, snapshot: &Snapshot) {
+    let [header, devices, alarms, footer] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(6),
+        Constraint::Length(12),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(header_widget(base_url, snapshot), header);
+    frame.render_widget(device_table(snapshot), devices);
+    frame.render_widget(alarm_table(snapshot), alarms);
+
+    let hint = match snapshot.errors.first() {
+        Some(e) => Line::from(Span::styled(format!("请求失败 {}", e), Style::default().fg(Color::Red))),
+        None => Line::from("q 退出  r 刷新"),
+    };
+    frame.render_widget(Paragraph::new(hint), footer);
+}
+
+fn header_widget<'a>(base_url: &'a str, snapshot: &Snapshot) -> Paragraph<'a> {
+    let fetched_at = snapshot
+        .fetched_at
+        .map(|t| t.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let queues = match &snapshot.queues {
+        Some(q) => {
+            let mqtt = q
+                .mqtt_outbox
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "未启用".to_string());
+            let mode = if q.read_only {
+                Span::styled("只读", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled("正常", Style::default().fg(Color::Green))
+            };
+            Line::from(vec![
+                Span::raw("运行模式 "),
+                mode,
+                Span::raw(format!("   MQTT 待发送 {}   上报暂存 {}", mqtt, q.ingest_buffer)),
+            ])
+        }
+        None => Line::from("队列状态未知"),
+    };
+
+    Paragraph::new(vec![
+        Line::from(format!("{}   刷新于 {}", base_url, fetched_at)),
+        queues,
+    ])
+    .block(Block::default().borders(Borders::ALL).title(" 运行状态 "))
+}
+
+fn device_table(snapshot: &Snapshot) -> Table<'static> {
+    let rows = snapshot.devices.iter().map(|device| {
+        let status = match (device.provision_status.as_str(), device.status) {
+            ("pending", _) => Cell::from("待审批").style(Style::default().fg(Color::Yellow)),
+            (_, 0) => Cell::from("停用").style(Style::default().fg(Color::DarkGray)),
+            _ => Cell::from("运行").style(Style::default().fg(Color::Green)),
+        };
+
+        let (values, updated) = match snapshot.latest.get(&device.id) {
+            Some(latest) => {
+                let values = latest
+                    .values
+                    .iter()
+                    .map(|(metric, v)| format!("{} {:.2}{}", metric, v.value, v.unit))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                let updated = latest
+                    .values
+                    .values()
+                    .map(|v| v.timestamp)
+                    .max()
+                    .map(|t| t.with_timezone(&Local).format("%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                (values, updated)
+            }
+            None => (String::new(), String::new()),
+        };
+
+        Row::new(vec![
+            Cell::from(device.id.to_string()),
+            Cell::from(device.name.clone()),
+            status,
+            Cell::from(updated),
+            Cell::from(values),
+        ])
+    });
+
+    Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(16),
+            Constraint::Length(8),
+            Constraint::Length(15),
+            Constraint::Min(20),
+        ],
+    )
+    .header(
+        Row::new(["ID", "名称", "状态", "最近上报", "最新值"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" 设备 ({}) ", snapshot.devices.len())),
+    )
+}
+
+fn alarm_table(snapshot: &Snapshot) -> Table<'static> {
+    let rows = snapshot.alarms.iter().map(|alarm| {
+        let style = if alarm.is_processed {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+        Row::new(vec![
+            Cell::from(alarm.trigger_time.with_timezone(&Local).format("%m-%d %H:%M:%S").to_string()),
+            Cell::from(alarm.rule_name.clone()),
+            Cell::from(format!("{:.2}", alarm.trigger_value)),
+            Cell::from(if alarm.is_processed { "已处理" } else { "未处理" }),
+        ])
+        .style(style)
+    });
+
+    let unprocessed = snapshot.alarms.iter().filter(|a| !a.is_processed).count();
+    Table::new(
+        rows,
+        [
+            Constraint::Length(15),
+            Constraint::Min(20),
+            Constraint::Length(10),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(["触发时间", "规则", "触发值", "状态"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" 最近报警 (未处理 {}) ", unprocessed)),
+    )
+}
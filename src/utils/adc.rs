@@ -0,0 +1,37 @@
+//! ADC 读取
+//!
+//! 统一 I2C（ADS1115）和 SPI（MCP3208）ADC 的原始码值读取。
+
+use crate::config::adc::AdcSource;
+use crate::drivers::ads1115::Ads1115;
+use crate::drivers::mcp3208::Mcp3208;
+use crate::utils::i2c::I2cController;
+use crate::utils::spi::SpiController;
+use std::io;
+
+pub enum AdcController {
+    Ads1115 { adc: Ads1115, full_scale: f64 },
+    Mcp3208(Mcp3208),
+}
+
+impl AdcController {
+    pub async fn open(source: &AdcSource) -> io::Result<Self> {
+        Ok(match source {
+            AdcSource::Ads1115 { bus, address, full_scale } => AdcController::Ads1115 {
+                adc: Ads1115::new(I2cController::open(bus, *address).await?),
+                full_scale: *full_scale,
+            },
+            AdcSource::Mcp3208 { spi } => {
+                AdcController::Mcp3208(Mcp3208::new(SpiController::open(spi).await?))
+            }
+        })
+    }
+
+    /// 读取通道原始码值
+    pub async fn read_counts(&self, channel: u8) -> io::Result<f64> {
+        match self {
+            AdcController::Ads1115 { adc, full_scale } => Ok(adc.read_raw(channel, *full_scale).await? as f64),
+            AdcController::Mcp3208(adc) => Ok(adc.read_raw(channel).await? as f64),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! CAN 总线（SocketCAN）
+//!
+//! 基于 Linux SocketCAN 的异步收发，以及把帧中的信号解码为数值。其他平台上打开接口直接返回
+//! `Unsupported`，信号解码不受影响。
+
+use serde::Deserialize;
+#[cfg(target_os = "linux")]
+use socketcan::tokio::CanSocket;
+#[cfg(target_os = "linux")]
+use socketcan::{CanFilter, CanFrame, EmbeddedFrame, ExtendedId, Frame, Id, SocketOptions, StandardId};
+use std::io;
+
+/// 标准帧ID的最大值，超过时按扩展帧处理
+#[cfg(target_os = "linux")]
+const MAX_STANDARD_ID: u32 = 0x7FF;
+
+#[cfg(target_os = "linux")]
+fn frame_id(id: u32) -> io::Result<Id> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid CAN id: {:#x}", id));
+    if id <= MAX_STANDARD_ID {
+        StandardId::new(id as u16).map(Id::Standard).ok_or_else(invalid)
+    } else {
+        ExtendedId::new(id).map(Id::Extended).ok_or_else(invalid)
+    }
+}
+
+/// CAN 接口
+#[cfg(target_os = "linux")]
+pub struct CanController {
+    interface: String,
+    socket: CanSocket,
+}
+
+#[cfg(target_os = "linux")]
+impl CanController {
+    /// 打开接口，例如 `can0`
+    pub fn open(interface: &str) -> io::Result<Self> {
+        let socket = CanSocket::open(interface)?;
+        Ok(Self {
+            interface: interface.to_string(),
+            socket,
+        })
+    }
+
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// 只接收匹配 `(id, mask)` 的帧，为空时接收全部
+    pub fn set_filters(&self, filters: &[(u32, u32)]) -> io::Result<()> {
+        let filters: Vec<CanFilter> = filters.iter().map(|&(id, mask)| CanFilter::new(id, mask)).collect();
+        self.socket.set_filters(&filters)
+    }
+
+    pub async fn send(&self, id: u32, data: &[u8]) -> io::Result<()> {
+        let frame = CanFrame::new(frame_id(id)?, data)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "CAN frame data too long"))?;
+        self.socket.write_frame(frame).await
+    }
+
+    /// 接收一帧，返回帧ID与数据
+    pub async fn recv(&self) -> io::Result<(u32, Vec<u8>)> {
+        let frame = self.socket.read_frame().await?;
+        Ok((frame.raw_id(), frame.data().to_vec()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct CanController {
+    interface: String,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl CanController {
+    pub fn open(_interface: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "SocketCAN is only available on Linux"))
+    }
+
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    pub fn set_filters(&self, _filters: &[(u32, u32)]) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub async fn send(&self, _id: u32, _data: &[u8]) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub async fn recv(&self) -> io::Result<(u32, Vec<u8>)> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// 帧中的一个信号
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CanSignal {
+    pub can_id: u32,
+    /// 起始字节
+    pub start_byte: usize,
+    /// 字节数：1 / 2 / 4
+    pub length: usize,
+    #[serde(default)]
+    pub big_endian: bool,
+    #[serde(default)]
+    pub signed: bool,
+    /// 工程值 = 原始值 × scale + offset
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl CanSignal {
+    /// 从帧数据中解出工程值，数据长度不足或字节数不支持时为空
+    pub fn decode(&self, data: &[u8]) -> Option<f64> {
+        if !matches!(self.length, 1 | 2 | 4) {
+            return None;
+        }
+        let bytes = data.get(self.start_byte..self.start_byte.checked_add(self.length)?)?;
+
+        // 按字节序拼成无符号整数，再按位宽做符号扩展
+        let unsigned = if self.big_endian {
+            bytes.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32)
+        } else {
+            bytes.iter().rev().fold(0u32, |acc, b| (acc << 8) | *b as u32)
+        };
+        let raw = if self.signed {
+            let shift = 32 - 8 * self.length as u32;
+            (((unsigned << shift) as i32) >> shift) as f64
+        } else {
+            unsigned as f64
+        };
+        Some(raw * self.scale + self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(start_byte: usize, length: usize, big_endian: bool, signed: bool) -> CanSignal {
+        CanSignal {
+            can_id: 0x181,
+            start_byte,
+            length,
+            big_endian,
+            signed,
+            scale: 0.5,
+            offset: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let data = [0x01, 0x02, 0xFF, 0xFE, 0x00, 0x00, 0x10, 0x00];
+
+        assert_eq!(signal(0, 2, true, false).decode(&data), Some(129.0));
+        assert_eq!(signal(0, 2, false, false).decode(&data), Some(256.5));
+        assert_eq!(signal(2, 2, true, true).decode(&data), Some(-1.0));
+        assert_eq!(signal(2, 1, false, true).decode(&data), Some(-0.5));
+        assert_eq!(signal(4, 4, false, false).decode(&data), Some(524288.0));
+        assert_eq!(signal(7, 2, false, false).decode(&data), None);
+        assert_eq!(signal(0, 3, false, false).decode(&data), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_frame_id() {
+        assert!(matches!(frame_id(0x181), Ok(Id::Standard(_))));
+        assert!(matches!(frame_id(0x18FF50E5), Ok(Id::Extended(_))));
+        assert!(frame_id(0x2000_0000).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+/// 文件系统容量（字节）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    /// 普通用户可用的空间，不含 root 保留部分
+    pub free_bytes: u64,
+}
+
+/// 路径所在文件系统的容量，无法获取时为 `None`
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+pub fn usage(path: &Path) -> Option<DiskUsage> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: c_path 以 NUL 结尾，stat 在调用期间有效
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(DiskUsage {
+        total_bytes: stat.f_blocks as u64 * stat.f_frsize as u64,
+        free_bytes: stat.f_bavail as u64 * stat.f_frsize as u64,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn usage(_path: &Path) -> Option<DiskUsage> {
+    None
+}
+
+/// 路径所在文件系统的可用空间（字节），无法获取时为 `None`
+pub fn free_bytes(path: &Path) -> Option<u64> {
+    usage(path).map(|u| u.free_bytes)
+}
@@ -1,3 +1,4 @@
+use crate::middleware::logging::current_request_id;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
@@ -11,6 +12,11 @@ pub enum AppError {
     NotFound,
     InvalidInput(Cow<'static, str>),
     InvalidCredentials,
+    Forbidden,
+    TooManyRequests,
+    ServiceUnavailable(Cow<'static, str>),
+    /// 请求合法但代价过高等无法处理的情况
+    Unprocessable(Cow<'static, str>),
     InternalError,
 }
 
@@ -20,12 +26,22 @@ impl IntoResponse for AppError {
             AppError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_string()),
             AppError::InvalidInput(msg) => (StatusCode::BAD_REQUEST, msg.into_owned()),
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            AppError::TooManyRequests => (StatusCode::TOO_MANY_REQUESTS, "Too many requests".to_string()),
+            AppError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.into_owned()),
+            AppError::Unprocessable(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg.into_owned()),
             AppError::InternalError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string()),
         };
 
-        let body = Json(json!({
-            "error": error_message,
-        }));
+        let body = match current_request_id() {
+            Some(request_id) => Json(json!({
+                "error": error_message,
+                "request_id": request_id,
+            })),
+            None => Json(json!({
+                "error": error_message,
+            })),
+        };
 
         (status, body).into_response()
     }
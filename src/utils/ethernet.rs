@@ -0,0 +1,112 @@
+//! 以太网接口状态
+//!
+//! 读取 `/sys/class/net/<iface>` 下的链路状态和收发计数，文件读取放到阻塞线程池执行。
+
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+const SYSFS_ROOT: &str = "/sys/class/net";
+
+/// 一次采样得到的接口计数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct InterfaceCounters {
+    /// up / down / unknown 等，取自 operstate
+    pub operstate: String,
+    /// 是否检测到物理链路，接口未启用时内核不提供
+    pub carrier: Option<bool>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+impl InterfaceCounters {
+    /// operstate 为 unknown 时（部分驱动不报告）以 carrier 为准
+    pub fn link_up(&self) -> bool {
+        match self.operstate.as_str() {
+            "up" => true,
+            "unknown" => self.carrier.unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+/// 单个网络接口
+#[derive(Debug, Clone)]
+pub struct EthernetController {
+    name: String,
+    dir: PathBuf,
+}
+
+fn read_trimmed(path: &Path) -> io::Result<String> {
+    Ok(std::fs::read_to_string(path)?.trim().to_string())
+}
+
+fn read_counter(dir: &Path, name: &str) -> io::Result<u64> {
+    let value = read_trimmed(&dir.join("statistics").join(name))?;
+    value.parse().map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid counter {}", name))
+    })
+}
+
+impl EthernetController {
+    /// `sysfs_base` 为空时使用 `/sys/class/net`
+    pub fn new(name: &str, sysfs_base: Option<&str>) -> Self {
+        let base = sysfs_base.unwrap_or(SYSFS_ROOT);
+        Self {
+            name: name.to_string(),
+            dir: Path::new(base).join(name),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn read(&self) -> io::Result<InterfaceCounters> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || {
+            if !dir.exists() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "interface not found"));
+            }
+            // 接口 down 时读取 carrier 返回 EINVAL
+            let carrier = read_trimmed(&dir.join("carrier")).ok().map(|c| c == "1");
+            Ok(InterfaceCounters {
+                operstate: read_trimmed(&dir.join("operstate"))?,
+                carrier,
+                rx_bytes: read_counter(&dir, "rx_bytes")?,
+                tx_bytes: read_counter(&dir, "tx_bytes")?,
+                rx_errors: read_counter(&dir, "rx_errors")?,
+                tx_errors: read_counter(&dir, "tx_errors")?,
+                rx_dropped: read_counter(&dir, "rx_dropped")?,
+                tx_dropped: read_counter(&dir, "tx_dropped")?,
+            })
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_up() {
+        let mut counters = InterfaceCounters {
+            operstate: "up".to_string(),
+            ..Default::default()
+        };
+        assert!(counters.link_up());
+        counters.operstate = "unknown".to_string();
+        assert!(!counters.link_up());
+        counters.carrier = Some(true);
+        assert!(counters.link_up());
+        counters.operstate = "down".to_string();
+        assert!(!counters.link_up());
+    }
+}
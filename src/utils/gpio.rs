@@ -0,0 +1,281 @@
+//! GPIO 访问
+//!
+//! 优先使用 gpiochip 字符设备（gpio-cdev），边沿事件由内核中断上报；
+//! 旧内核没有字符设备时退回 sysfs 接口，边沿事件改为定时轮询电平。其他平台上打开引脚直接返回
+//! `Unsupported`。
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+#[cfg(target_os = "linux")]
+use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, EventType, Line, LineHandle, LineRequestFlags};
+use serde::Deserialize;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+const CONSUMER: &str = "guolu";
+#[cfg(target_os = "linux")]
+const SYSFS_ROOT: &str = "/sys/class/gpio";
+/// sysfs 模式下轮询电平的间隔
+const SYSFS_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 关注的边沿
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    #[default]
+    Rising,
+    Falling,
+    Both,
+}
+
+impl Edge {
+    fn matches(self, rising: bool) -> bool {
+        match self {
+            Edge::Rising => rising,
+            Edge::Falling => !rising,
+            Edge::Both => true,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn request_flags(self) -> EventRequestFlags {
+        match self {
+            Edge::Rising => EventRequestFlags::RISING_EDGE,
+            Edge::Falling => EventRequestFlags::FALLING_EDGE,
+            Edge::Both => EventRequestFlags::BOTH_EDGES,
+        }
+    }
+}
+
+/// 一次电平跳变
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpioEvent {
+    /// true 为上升沿（变为高电平）
+    pub rising: bool,
+}
+
+#[cfg(target_os = "linux")]
+fn to_io(err: gpio_cdev::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+// 其他平台上无法打开引脚，不会构造任何后端
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+enum Backend {
+    #[cfg(target_os = "linux")]
+    Cdev {
+        line: Line,
+        /// 输出需要一直持有句柄，释放后内核可能复位电平
+        output: Mutex<Option<LineHandle>>,
+    },
+    Sysfs {
+        dir: PathBuf,
+    },
+}
+
+/// 单个 GPIO 引脚
+#[derive(Clone)]
+pub struct GpioController {
+    backend: Arc<Backend>,
+    line: u32,
+}
+
+impl std::fmt::Debug for GpioController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let backend = match *self.backend {
+            #[cfg(target_os = "linux")]
+            Backend::Cdev { .. } => "cdev",
+            Backend::Sysfs { .. } => "sysfs",
+        };
+        f.debug_struct("GpioController")
+            .field("line", &self.line)
+            .field("backend", &backend)
+            .finish()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn sysfs_export(number: u32) -> io::Result<PathBuf> {
+    let dir = PathBuf::from(format!("{}/gpio{}", SYSFS_ROOT, number));
+    if !dir.exists() {
+        std::fs::write(format!("{}/export", SYSFS_ROOT), number.to_string())?;
+    }
+    Ok(dir)
+}
+
+impl GpioController {
+    /// 打开 `chip` 上的第 `line` 根线；字符设备不可用时使用 sysfs 编号 `sysfs_base + line`
+    #[cfg(target_os = "linux")]
+    pub async fn open(chip: &str, line: u32, sysfs_base: u32) -> io::Result<Self> {
+        let chip = chip.to_string();
+        let backend = tokio::task::spawn_blocking(move || {
+            io::Result::Ok(match Chip::new(&chip).and_then(|mut chip| chip.get_line(line)) {
+                Ok(handle) => Backend::Cdev {
+                    line: handle,
+                    output: Mutex::new(None),
+                },
+                Err(e) => {
+                    tracing::warn!("gpiochip {} unavailable ({}), falling back to sysfs", chip, e);
+                    Backend::Sysfs {
+                        dir: sysfs_export(sysfs_base + line)?,
+                    }
+                }
+            })
+        })
+        .await
+        .map_err(io::Error::other)??;
+
+        Ok(Self {
+            backend: Arc::new(backend),
+            line,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn open(_chip: &str, _line: u32, _sysfs_base: u32) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "GPIO is only available on Linux"))
+    }
+
+    async fn blocking<T, F>(&self, f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Backend) -> io::Result<T> + Send + 'static,
+    {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || f(&backend))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// 读取电平
+    pub async fn read(&self) -> io::Result<bool> {
+        self.blocking(|backend| match backend {
+            #[cfg(target_os = "linux")]
+            Backend::Cdev { line, output } => {
+                if let Some(handle) = output.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                    return Ok(handle.get_value().map_err(to_io)? != 0);
+                }
+                let handle = line.request(LineRequestFlags::INPUT, 0, CONSUMER).map_err(to_io)?;
+                Ok(handle.get_value().map_err(to_io)? != 0)
+            }
+            Backend::Sysfs { dir } => {
+                let value = std::fs::read_to_string(dir.join("value"))?;
+                Ok(value.trim() == "1")
+            }
+        })
+        .await
+    }
+
+    /// 读取输出引脚当前的电平，不改变引脚方向
+    ///
+    /// 字符设备只能通过本进程持有的输出句柄读取，尚未持有时返回 `Unsupported`；
+    /// 重新申请句柄会改变方向或电平。sysfs 直接读取 value 文件。
+    pub async fn read_output(&self) -> io::Result<bool> {
+        self.blocking(|backend| match backend {
+            #[cfg(target_os = "linux")]
+            Backend::Cdev { output, .. } => {
+                match output.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                    Some(handle) => Ok(handle.get_value().map_err(to_io)? != 0),
+                    None => Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "output level unreadable without holding the line",
+                    )),
+                }
+            }
+            Backend::Sysfs { dir } => {
+                let value = std::fs::read_to_string(dir.join("value"))?;
+                Ok(value.trim() == "1")
+            }
+        })
+        .await
+    }
+
+    /// 设置输出电平，首次调用时把引脚切换为输出
+    pub async fn write(&self, high: bool) -> io::Result<()> {
+        let value = high as u8;
+        self.blocking(move |backend| match backend {
+            #[cfg(target_os = "linux")]
+            Backend::Cdev { line, output } => {
+                let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+                match output.as_ref() {
+                    Some(handle) => handle.set_value(value).map_err(to_io),
+                    None => {
+                        *output = Some(line.request(LineRequestFlags::OUTPUT, value, CONSUMER).map_err(to_io)?);
+                        Ok(())
+                    }
+                }
+            }
+            Backend::Sysfs { dir } => {
+                if std::fs::read_to_string(dir.join("direction"))?.trim() != "out" {
+                    std::fs::write(dir.join("direction"), "out")?;
+                }
+                std::fs::write(dir.join("value"), value.to_string())
+            }
+        })
+        .await
+    }
+
+    /// 边沿事件流
+    pub async fn events(&self, edge: Edge) -> io::Result<BoxStream<'static, io::Result<GpioEvent>>> {
+        match &*self.backend {
+            #[cfg(target_os = "linux")]
+            Backend::Cdev { line, .. } => {
+                let handle = line
+                    .events(LineRequestFlags::INPUT, edge.request_flags(), CONSUMER)
+                    .map_err(to_io)?;
+                let events = AsyncLineEventHandle::new(handle).map_err(to_io)?;
+                Ok(events
+                    .map(|event| {
+                        let event = event.map_err(to_io)?;
+                        Ok(GpioEvent {
+                            rising: event.event_type() == EventType::RisingEdge,
+                        })
+                    })
+                    .boxed())
+            }
+            Backend::Sysfs { dir } => {
+                let direction = dir.join("direction");
+                tokio::task::spawn_blocking(move || std::fs::write(direction, "in"))
+                    .await
+                    .map_err(io::Error::other)??;
+                let controller = self.clone();
+                let stream = stream::unfold(None, move |last: Option<bool>| {
+                    let controller = controller.clone();
+                    async move {
+                        let mut last = last;
+                        loop {
+                            let level = match controller.read().await {
+                                Ok(level) => level,
+                                Err(e) => return Some((Err(e), last)),
+                            };
+                            let changed = last.is_some_and(|previous| previous != level);
+                            last = Some(level);
+                            if changed && edge.matches(level) {
+                                return Some((Ok(GpioEvent { rising: level }), last));
+                            }
+                            tokio::time::sleep(SYSFS_POLL_INTERVAL).await;
+                        }
+                    }
+                });
+                Ok(stream.boxed())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_matches() {
+        assert!(Edge::Rising.matches(true));
+        assert!(!Edge::Rising.matches(false));
+        assert!(Edge::Falling.matches(false));
+        assert!(Edge::Both.matches(true) && Edge::Both.matches(false));
+    }
+}
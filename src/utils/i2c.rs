@@ -0,0 +1,134 @@
+//! I2C 总线访问
+//!
+//! 基于 `/dev/i2c-*` 的寄存器读写与块传输。底层是阻塞 ioctl，这里统一放到阻塞线程池执行。
+//! 其他平台上打开设备直接返回 `Unsupported`。
+
+#[cfg(target_os = "linux")]
+use i2cdev::core::I2CDevice;
+#[cfg(target_os = "linux")]
+use i2cdev::linux::{LinuxI2CDevice, LinuxI2CError};
+use std::io;
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(target_os = "linux")]
+fn to_io(err: LinuxI2CError) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+/// 总线上的一个从设备
+#[derive(Clone)]
+pub struct I2cController {
+    #[cfg(target_os = "linux")]
+    device: Arc<Mutex<LinuxI2CDevice>>,
+    address: u16,
+}
+
+impl std::fmt::Debug for I2cController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("I2cController")
+            .field("address", &format_args!("{:#04x}", self.address))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl I2cController {
+    /// 打开总线上指定地址的设备，例如 `("/dev/i2c-1", 0x48)`
+    pub async fn open(bus: &str, address: u16) -> io::Result<Self> {
+        let bus = bus.to_string();
+        let device = tokio::task::spawn_blocking(move || LinuxI2CDevice::new(bus, address).map_err(to_io))
+            .await
+            .map_err(io::Error::other)??;
+        Ok(Self {
+            device: Arc::new(Mutex::new(device)),
+            address,
+        })
+    }
+
+    async fn with_device<T, F>(&self, f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut LinuxI2CDevice) -> Result<T, LinuxI2CError> + Send + 'static,
+    {
+        let device = self.device.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut device = device.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut device).map_err(to_io)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// 读 8 位寄存器
+    pub async fn read_register(&self, register: u8) -> io::Result<u8> {
+        self.with_device(move |d| d.smbus_read_byte_data(register)).await
+    }
+
+    /// 写 8 位寄存器
+    pub async fn write_register(&self, register: u8, value: u8) -> io::Result<()> {
+        self.with_device(move |d| d.smbus_write_byte_data(register, value)).await
+    }
+
+    /// 原始写入
+    pub async fn write(&self, data: Vec<u8>) -> io::Result<()> {
+        self.with_device(move |d| d.write(&data)).await
+    }
+
+    /// 先写后读，用于命令式设备
+    pub async fn write_read(&self, data: Vec<u8>, len: usize) -> io::Result<Vec<u8>> {
+        self.with_device(move |d| {
+            d.write(&data)?;
+            let mut buf = vec![0u8; len];
+            d.read(&mut buf)?;
+            Ok(buf)
+        })
+        .await
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl I2cController {
+    pub async fn open(_bus: &str, _address: u16) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "I2C is only available on Linux"))
+    }
+
+    pub async fn read_register(&self, _register: u8) -> io::Result<u8> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub async fn write_register(&self, _register: u8, _value: u8) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub async fn write(&self, _data: Vec<u8>) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub async fn write_read(&self, _data: Vec<u8>, _len: usize) -> io::Result<Vec<u8>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+impl I2cController {
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// 读 16 位寄存器（高字节在前）
+    pub async fn read_register_u16(&self, register: u8) -> io::Result<u16> {
+        let bytes = self.write_read(vec![register], 2).await?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// 写 16 位寄存器（高字节在前）
+    pub async fn write_register_u16(&self, register: u8, value: u16) -> io::Result<()> {
+        let [high, low] = value.to_be_bytes();
+        self.write(vec![register, high, low]).await
+    }
+
+    /// 从寄存器开始连续读取 `len` 字节
+    pub async fn read_block(&self, register: u8, len: usize) -> io::Result<Vec<u8>> {
+        self.write_read(vec![register], len).await
+    }
+}
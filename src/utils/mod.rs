@@ -1,2 +1,20 @@
+pub mod adc;
+pub mod can;
+pub mod crypto;
+pub mod disk;
 pub mod error;
+pub mod ethernet;
+pub mod gpio;
+pub mod i2c;
+pub mod modbus;
+pub mod modbus_loopback;
+pub mod operator;
+pub mod pwm;
 pub mod response;
+pub mod s3;
+pub mod serde_ext;
+pub mod snmp_trap;
+pub mod spi;
+pub mod tenant;
+pub mod timezone;
+pub mod uart;
\ No newline at end of file
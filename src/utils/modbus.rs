@@ -0,0 +1,529 @@
+//! Modbus 主站访问（TCP / RTU）与 TCP 从站
+//!
+//! 32/64 位数值跨多个寄存器存放，各厂家的字与字节顺序不同，按 [`ByteOrder`] 还原。
+//! 字节序以 A 表示最高字节：ABCD 为标准大端，CDAB 交换字序，BADC 交换字内字节，DCBA 为小端。
+
+use crate::config::modbus::ModbusTarget;
+use crate::utils::{modbus_loopback, uart};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_modbus::client::{rtu, tcp, Context, Reader, Writer};
+use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
+use tokio_modbus::{ExceptionCode, Request, Response, Slave};
+use tracing::warn;
+
+/// 多寄存器数值的字节序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    Abcd,
+    Cdab,
+    Badc,
+    Dcba,
+}
+
+impl ByteOrder {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "ABCD" => Some(ByteOrder::Abcd),
+            "CDAB" => Some(ByteOrder::Cdab),
+            "BADC" => Some(ByteOrder::Badc),
+            "DCBA" => Some(ByteOrder::Dcba),
+            _ => None,
+        }
+    }
+
+    fn swap_words(self) -> bool {
+        matches!(self, ByteOrder::Cdab | ByteOrder::Dcba)
+    }
+
+    fn swap_bytes(self) -> bool {
+        matches!(self, ByteOrder::Badc | ByteOrder::Dcba)
+    }
+}
+
+/// 寄存器中的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+}
+
+impl DataType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "u16" => Some(DataType::U16),
+            "i16" => Some(DataType::I16),
+            "u32" => Some(DataType::U32),
+            "i32" => Some(DataType::I32),
+            "f32" => Some(DataType::F32),
+            "u64" => Some(DataType::U64),
+            "i64" => Some(DataType::I64),
+            "f64" => Some(DataType::F64),
+            _ => None,
+        }
+    }
+
+    /// 占用的寄存器数
+    pub fn register_count(self) -> u16 {
+        match self {
+            DataType::U16 | DataType::I16 => 1,
+            DataType::U32 | DataType::I32 | DataType::F32 => 2,
+            DataType::U64 | DataType::I64 | DataType::F64 => 4,
+        }
+    }
+}
+
+/// 按字节序把寄存器还原为大端字节，寄存器数必须为 `N / 2`
+fn to_bytes<const N: usize>(registers: &[u16], order: ByteOrder) -> Option<[u8; N]> {
+    if registers.len() * 2 != N {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, chunk) in bytes.chunks_exact_mut(2).enumerate() {
+        let word = if order.swap_words() {
+            registers[registers.len() - 1 - i]
+        } else {
+            registers[i]
+        };
+        let pair = if order.swap_bytes() { word.to_le_bytes() } else { word.to_be_bytes() };
+        chunk.copy_from_slice(&pair);
+    }
+    Some(bytes)
+}
+
+pub fn decode_u32(registers: &[u16], order: ByteOrder) -> Option<u32> {
+    to_bytes(registers, order).map(u32::from_be_bytes)
+}
+
+pub fn decode_i32(registers: &[u16], order: ByteOrder) -> Option<i32> {
+    to_bytes(registers, order).map(i32::from_be_bytes)
+}
+
+pub fn decode_f32(registers: &[u16], order: ByteOrder) -> Option<f32> {
+    to_bytes(registers, order).map(f32::from_be_bytes)
+}
+
+pub fn decode_u64(registers: &[u16], order: ByteOrder) -> Option<u64> {
+    to_bytes(registers, order).map(u64::from_be_bytes)
+}
+
+pub fn decode_i64(registers: &[u16], order: ByteOrder) -> Option<i64> {
+    to_bytes(registers, order).map(i64::from_be_bytes)
+}
+
+pub fn decode_f64(registers: &[u16], order: ByteOrder) -> Option<f64> {
+    to_bytes(registers, order).map(f64::from_be_bytes)
+}
+
+/// 按数据类型解码为 f64，寄存器数不符时返回 None
+pub fn decode(registers: &[u16], data_type: DataType, order: ByteOrder) -> Option<f64> {
+    // 单寄存器只受字内字节顺序影响
+    let single = || {
+        let [word] = registers else { return None };
+        Some(if order.swap_bytes() { word.swap_bytes() } else { *word })
+    };
+    match data_type {
+        DataType::U16 => single().map(f64::from),
+        DataType::I16 => single().map(|w| w as i16 as f64),
+        DataType::U32 => decode_u32(registers, order).map(f64::from),
+        DataType::I32 => decode_i32(registers, order).map(f64::from),
+        DataType::F32 => decode_f32(registers, order).map(f64::from),
+        DataType::U64 => decode_u64(registers, order).map(|v| v as f64),
+        DataType::I64 => decode_i64(registers, order).map(|v| v as f64),
+        DataType::F64 => decode_f64(registers, order),
+    }
+}
+
+/// 把大端字节按字节序拆成寄存器，与 [`decode`] 互逆
+fn from_bytes(bytes: &[u8], order: ByteOrder) -> Vec<u16> {
+    let mut registers: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| {
+            let word = u16::from_be_bytes([c[0], c[1]]);
+            if order.swap_bytes() { word.swap_bytes() } else { word }
+        })
+        .collect();
+    if order.swap_words() {
+        registers.reverse();
+    }
+    registers
+}
+
+/// 按数据类型编码为寄存器，整数类型四舍五入，超出范围或非有限值返回 None
+pub fn encode(value: f64, data_type: DataType, order: ByteOrder) -> Option<Vec<u16>> {
+    if !value.is_finite() {
+        return None;
+    }
+    let integer = |min: f64, max: f64| {
+        let rounded = value.round();
+        (rounded >= min && rounded <= max).then_some(rounded)
+    };
+    let bytes = match data_type {
+        DataType::U16 => (integer(0.0, u16::MAX as f64)? as u16).to_be_bytes().to_vec(),
+        DataType::I16 => (integer(i16::MIN as f64, i16::MAX as f64)? as i16).to_be_bytes().to_vec(),
+        DataType::U32 => (integer(0.0, u32::MAX as f64)? as u32).to_be_bytes().to_vec(),
+        DataType::I32 => (integer(i32::MIN as f64, i32::MAX as f64)? as i32).to_be_bytes().to_vec(),
+        DataType::F32 => (value as f32).to_be_bytes().to_vec(),
+        DataType::U64 => (integer(0.0, u64::MAX as f64)? as u64).to_be_bytes().to_vec(),
+        DataType::I64 => (integer(i64::MIN as f64, i64::MAX as f64)? as i64).to_be_bytes().to_vec(),
+        DataType::F64 => value.to_be_bytes().to_vec(),
+    };
+    Some(from_bytes(&bytes, order))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+async fn with_timeout<T>(
+    timeout: Duration,
+    request: impl Future<Output = tokio_modbus::Result<T>>,
+) -> io::Result<T> {
+    match tokio::time::timeout(timeout, request).await {
+        Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "modbus request timed out")),
+        Ok(Err(e)) => Err(io::Error::other(e)),
+        Ok(Ok(Err(code))) => Err(io::Error::other(format!("modbus exception: {:?}", code))),
+        Ok(Ok(Ok(value))) => Ok(value),
+    }
+}
+
+pub struct ModbusClient {
+    ctx: Context,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for ModbusClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModbusClient")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ModbusClient {
+    pub async fn connect(
+        target: &ModbusTarget,
+        unit_id: u8,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let slave = Slave(unit_id);
+        let ctx = match target {
+            ModbusTarget::Tcp { host, port } => {
+                let addr = tokio::net::lookup_host((host.as_str(), *port))
+                    .await?
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not found"))?;
+                tokio::time::timeout(timeout, tcp::connect_slave(addr, slave))
+                    .await
+                    .map_err(|_| {
+                        io::Error::new(io::ErrorKind::TimedOut, "modbus connect timed out")
+                    })??
+            }
+            ModbusTarget::Rtu { serial } => rtu::attach_slave(uart::open(serial)?, slave),
+            ModbusTarget::Loopback { bus } => rtu::attach_slave(modbus_loopback::open(bus)?, slave),
+        };
+        Ok(Self { ctx, timeout })
+    }
+
+    pub async fn read_holding_registers(
+        &mut self,
+        address: u16,
+        count: u16,
+    ) -> io::Result<Vec<u16>> {
+        with_timeout(self.timeout, self.ctx.read_holding_registers(address, count)).await
+    }
+
+    pub async fn read_input_registers(
+        &mut self,
+        address: u16,
+        count: u16,
+    ) -> io::Result<Vec<u16>> {
+        with_timeout(self.timeout, self.ctx.read_input_registers(address, count)).await
+    }
+
+    pub async fn read_coils(&mut self, address: u16, count: u16) -> io::Result<Vec<bool>> {
+        with_timeout(self.timeout, self.ctx.read_coils(address, count)).await
+    }
+
+    /// 读取保持寄存器并按类型解码
+    pub async fn read_holding_value(
+        &mut self,
+        address: u16,
+        data_type: DataType,
+        order: ByteOrder,
+    ) -> io::Result<f64> {
+        let registers = self.read_holding_registers(address, data_type.register_count()).await?;
+        decode(&registers, data_type, order)
+            .ok_or_else(|| invalid_data("unexpected register count"))
+    }
+
+    /// 读取输入寄存器并按类型解码
+    pub async fn read_input_value(
+        &mut self,
+        address: u16,
+        data_type: DataType,
+        order: ByteOrder,
+    ) -> io::Result<f64> {
+        let registers = self.read_input_registers(address, data_type.register_count()).await?;
+        decode(&registers, data_type, order)
+            .ok_or_else(|| invalid_data("unexpected register count"))
+    }
+
+    pub async fn write_single_register(&mut self, address: u16, value: u16) -> io::Result<()> {
+        with_timeout(self.timeout, self.ctx.write_single_register(address, value)).await
+    }
+
+    pub async fn write_single_coil(&mut self, address: u16, value: bool) -> io::Result<()> {
+        with_timeout(self.timeout, self.ctx.write_single_coil(address, value)).await
+    }
+}
+
+/// 上位机写入的保持寄存器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterWrite {
+    pub address: u16,
+    pub values: Vec<u16>,
+}
+
+#[derive(Debug, Default)]
+struct RegisterImage {
+    input: HashMap<u16, u16>,
+    holding: HashMap<u16, u16>,
+    discrete: HashMap<u16, bool>,
+}
+
+/// Modbus TCP 从站
+///
+/// 只响应已设置过的寄存器地址，其余地址返回非法数据地址异常。保持寄存器只有
+/// `writable` 中的地址允许写入，写入后更新寄存器并通过通道通知上层。
+#[derive(Debug, Clone)]
+pub struct ModbusServer {
+    image: Arc<RwLock<RegisterImage>>,
+    writable: Arc<HashSet<u16>>,
+    writes: mpsc::UnboundedSender<RegisterWrite>,
+}
+
+impl ModbusServer {
+    pub fn new(writable: HashSet<u16>) -> (Self, mpsc::UnboundedReceiver<RegisterWrite>) {
+        let (writes, receiver) = mpsc::unbounded_channel();
+        let server = Self {
+            image: Arc::default(),
+            writable: Arc::new(writable),
+            writes,
+        };
+        (server, receiver)
+    }
+
+    pub fn set_input(&self, address: u16, values: &[u16]) {
+        let mut image = self.image.write().unwrap_or_else(|e| e.into_inner());
+        for (offset, value) in values.iter().enumerate() {
+            image.input.insert(address.wrapping_add(offset as u16), *value);
+        }
+    }
+
+    pub fn set_holding(&self, address: u16, values: &[u16]) {
+        let mut image = self.image.write().unwrap_or_else(|e| e.into_inner());
+        for (offset, value) in values.iter().enumerate() {
+            image.holding.insert(address.wrapping_add(offset as u16), *value);
+        }
+    }
+
+    pub fn set_discrete(&self, address: u16, values: &[bool]) {
+        let mut image = self.image.write().unwrap_or_else(|e| e.into_inner());
+        for (offset, value) in values.iter().enumerate() {
+            image.discrete.insert(address.wrapping_add(offset as u16), *value);
+        }
+    }
+
+    /// 读取保持寄存器，任一地址未设置时返回 None
+    pub fn holding(&self, address: u16, count: u16) -> Option<Vec<u16>> {
+        let image = self.image.read().unwrap_or_else(|e| e.into_inner());
+        read(&image.holding, address, count)
+    }
+
+    pub(crate) fn handle(&self, request: Request<'_>) -> Result<Response, ExceptionCode> {
+        match request {
+            Request::ReadInputRegisters(address, count) => {
+                let image = self.image.read().unwrap_or_else(|e| e.into_inner());
+                read(&image.input, address, count)
+                    .map(Response::ReadInputRegisters)
+                    .ok_or(ExceptionCode::IllegalDataAddress)
+            }
+            Request::ReadDiscreteInputs(address, count) => {
+                let image = self.image.read().unwrap_or_else(|e| e.into_inner());
+                read(&image.discrete, address, count)
+                    .map(Response::ReadDiscreteInputs)
+                    .ok_or(ExceptionCode::IllegalDataAddress)
+            }
+            Request::ReadHoldingRegisters(address, count) => self
+                .holding(address, count)
+                .map(Response::ReadHoldingRegisters)
+                .ok_or(ExceptionCode::IllegalDataAddress),
+            Request::WriteSingleRegister(address, value) => {
+                self.write(address, &[value])?;
+                Ok(Response::WriteSingleRegister(address, value))
+            }
+            Request::WriteMultipleRegisters(address, values) => {
+                self.write(address, &values)?;
+                Ok(Response::WriteMultipleRegisters(address, values.len() as u16))
+            }
+            _ => Err(ExceptionCode::IllegalFunction),
+        }
+    }
+
+    fn write(&self, address: u16, values: &[u16]) -> Result<(), ExceptionCode> {
+        let writable = (0..values.len())
+            .all(|offset| self.writable.contains(&address.wrapping_add(offset as u16)));
+        if values.is_empty() || !writable {
+            return Err(ExceptionCode::IllegalDataAddress);
+        }
+        self.set_holding(address, values);
+        let _ = self.writes.send(RegisterWrite { address, values: values.to_vec() });
+        Ok(())
+    }
+
+    /// 监听 TCP 端口，直到出错
+    pub async fn serve_tcp(self, listen: SocketAddr) -> io::Result<()> {
+        let server = Server::new(TcpListener::bind(listen).await?);
+        let new_service = |_peer: SocketAddr| Ok(Some(self.clone()));
+        let on_connected =
+            |stream, peer| async move { accept_tcp_connection(stream, peer, new_service) };
+        let on_process_error = |e| warn!("Modbus server connection error: {}", e);
+        server.serve(&on_connected, on_process_error).await
+    }
+}
+
+fn read<T: Copy>(registers: &HashMap<u16, T>, address: u16, count: u16) -> Option<Vec<T>> {
+    if count == 0 || address as u32 + count as u32 > u16::MAX as u32 + 1 {
+        return None;
+    }
+    (0..count).map(|offset| registers.get(&(address + offset)).copied()).collect()
+}
+
+impl tokio_modbus::server::Service for ModbusServer {
+    type Request = Request<'static>;
+    type Response = Response;
+    type Exception = ExceptionCode;
+    type Future = std::future::Ready<Result<Response, ExceptionCode>>;
+
+    fn call(&self, request: Self::Request) -> Self::Future {
+        std::future::ready(self.handle(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 123.456f32 = 0x42F6E979
+    const F32_VECTORS: [(ByteOrder, [u16; 2]); 4] = [
+        (ByteOrder::Abcd, [0x42F6, 0xE979]),
+        (ByteOrder::Cdab, [0xE979, 0x42F6]),
+        (ByteOrder::Badc, [0xF642, 0x79E9]),
+        (ByteOrder::Dcba, [0x79E9, 0xF642]),
+    ];
+
+    #[test]
+    fn test_decode_f32_byte_orders() {
+        for (order, registers) in F32_VECTORS {
+            assert_eq!(decode_f32(&registers, order), Some(123.456), "{:?}", order);
+        }
+        assert_eq!(decode_f32(&[0x42F6], ByteOrder::Abcd), None);
+    }
+
+    #[test]
+    fn test_decode_integers() {
+        assert_eq!(decode_u32(&[0x0001, 0x0002], ByteOrder::Abcd), Some(0x0001_0002));
+        assert_eq!(decode_u32(&[0x0001, 0x0002], ByteOrder::Cdab), Some(0x0002_0001));
+        assert_eq!(decode_i32(&[0xFFFF, 0xFFFE], ByteOrder::Abcd), Some(-2));
+        let registers = [0x0001, 0x0002, 0x0003, 0x0004];
+        assert_eq!(decode_u64(&registers, ByteOrder::Cdab), Some(0x0004_0003_0002_0001));
+        assert_eq!(decode_i64(&[0xFFFF; 4], ByteOrder::Dcba), Some(-1));
+    }
+
+    #[test]
+    fn test_decode_by_type() {
+        let f64_registers = 1234.5f64.to_be_bytes();
+        let registers: Vec<u16> = f64_registers
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(decode(&registers, DataType::F64, ByteOrder::Abcd), Some(1234.5));
+        assert_eq!(decode(&[0xFF38], DataType::I16, ByteOrder::Abcd), Some(-200.0));
+        assert_eq!(decode(&[0x38FF], DataType::I16, ByteOrder::Badc), Some(-200.0));
+        assert_eq!(decode(&[1, 2], DataType::U16, ByteOrder::Abcd), None);
+        assert_eq!(DataType::parse("F32").map(DataType::register_count), Some(2));
+        assert_eq!(ByteOrder::parse("cdab"), Some(ByteOrder::Cdab));
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        for (order, registers) in F32_VECTORS {
+            assert_eq!(encode(123.456, DataType::F32, order), Some(registers.to_vec()));
+        }
+        let cases = [
+            (DataType::I16, -12.0),
+            (DataType::U32, 70000.0),
+            (DataType::I32, -70000.0),
+            (DataType::I64, -1.0),
+            (DataType::F64, 0.125),
+        ];
+        for order in [ByteOrder::Abcd, ByteOrder::Cdab, ByteOrder::Badc, ByteOrder::Dcba] {
+            for (data_type, value) in cases {
+                let registers = encode(value, data_type, order).unwrap();
+                assert_eq!(registers.len(), data_type.register_count() as usize);
+                assert_eq!(decode(&registers, data_type, order), Some(value));
+            }
+        }
+        assert_eq!(encode(70000.0, DataType::U16, ByteOrder::Abcd), None);
+        assert_eq!(encode(f64::NAN, DataType::F32, ByteOrder::Abcd), None);
+    }
+
+    #[test]
+    fn test_server_registers() {
+        let (server, mut writes) = ModbusServer::new(HashSet::from([10, 11]));
+        server.set_input(0, &[1, 2, 3]);
+        server.set_holding(10, &[0, 0]);
+        server.set_discrete(100, &[true, false]);
+
+        assert_eq!(
+            server.handle(Request::ReadInputRegisters(1, 2)),
+            Ok(Response::ReadInputRegisters(vec![2, 3]))
+        );
+        assert_eq!(
+            server.handle(Request::ReadInputRegisters(2, 2)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+        assert_eq!(
+            server.handle(Request::ReadDiscreteInputs(100, 2)),
+            Ok(Response::ReadDiscreteInputs(vec![true, false]))
+        );
+        assert_eq!(
+            server.handle(Request::ReadDiscreteInputs(0, 1)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+        assert_eq!(
+            server.handle(Request::WriteMultipleRegisters(10, vec![5, 6].into())),
+            Ok(Response::WriteMultipleRegisters(10, 2))
+        );
+        assert_eq!(writes.try_recv().ok(), Some(RegisterWrite { address: 10, values: vec![5, 6] }));
+        assert_eq!(server.holding(10, 2), Some(vec![5, 6]));
+        assert_eq!(
+            server.handle(Request::WriteSingleRegister(12, 1)),
+            Err(ExceptionCode::IllegalDataAddress)
+        );
+    }
+}
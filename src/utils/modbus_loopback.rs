@@ -0,0 +1,428 @@
+//! 内存中的 Modbus RTU 总线
+//!
+//! 用于在没有串口硬件的环境（CI）中测试 Modbus 主站相关代码。总线上挂若干模拟从站，
+//! 寄存器由 [`ModbusServer`] 保存，线圈由从站自己保存且全部可写。按名称注册后，
+//! 设备配置使用 `"transport": "loopback"` 即可让 [`ModbusClient`](super::modbus::ModbusClient)
+//! 走完整的 RTU 编解码（含 CRC），与真实串口的区别只在传输层。
+//!
+//! 故障注入按请求逐个生效：超时（不应答）、CRC 错误（应答校验码被破坏）和异常应答，
+//! 也可以把从站整体设为离线。与真实从站一致，CRC 错误或地址不匹配的请求帧直接丢弃。
+
+use crate::utils::modbus::ModbusServer;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio_modbus::{ExceptionCode, Request, Response};
+use tracing::debug;
+
+static BUSES: LazyLock<Mutex<HashMap<String, LoopbackBus>>> = LazyLock::new(Mutex::default);
+
+/// 对单个请求注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// 不应答，主站等待超时
+    Timeout,
+    /// 正常处理，但应答帧的 CRC 错误
+    CrcError,
+    /// 返回异常应答
+    Exception(ExceptionCode),
+}
+
+/// 模拟从站
+#[derive(Debug, Clone)]
+pub struct LoopbackSlave {
+    server: ModbusServer,
+    coils: Arc<RwLock<HashMap<u16, bool>>>,
+    faults: Arc<Mutex<VecDeque<Fault>>>,
+    offline: Arc<AtomicBool>,
+    requests: Arc<AtomicU64>,
+}
+
+impl LoopbackSlave {
+    /// `writable` 为允许写入的保持寄存器地址，与 [`ModbusServer::new`] 相同
+    pub fn new(writable: HashSet<u16>) -> Self {
+        // 写入通知不需要，接收端直接丢弃
+        let (server, _) = ModbusServer::new(writable);
+        Self {
+            server,
+            coils: Arc::default(),
+            faults: Arc::default(),
+            offline: Arc::default(),
+            requests: Arc::default(),
+        }
+    }
+
+    /// 寄存器映像，用于设置输入寄存器、保持寄存器和离散输入
+    pub fn registers(&self) -> &ModbusServer {
+        &self.server
+    }
+
+    pub fn set_coils(&self, address: u16, values: &[bool]) {
+        let mut coils = self.coils.write().unwrap_or_else(|e| e.into_inner());
+        for (offset, value) in values.iter().enumerate() {
+            coils.insert(address.wrapping_add(offset as u16), *value);
+        }
+    }
+
+    pub fn coil(&self, address: u16) -> Option<bool> {
+        self.coils.read().unwrap_or_else(|e| e.into_inner()).get(&address).copied()
+    }
+
+    /// 对接下来的请求依次注入故障，每个故障只生效一次
+    pub fn inject(&self, faults: impl IntoIterator<Item = Fault>) {
+        self.faults.lock().unwrap_or_else(|e| e.into_inner()).extend(faults);
+    }
+
+    /// 离线时不应答任何请求
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    /// 收到的有效请求帧数（含注入故障的请求）
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    fn next_fault(&self) -> Option<Fault> {
+        if self.offline.load(Ordering::Relaxed) {
+            return Some(Fault::Timeout);
+        }
+        self.faults.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+    }
+
+    fn handle(&self, request: Request<'_>) -> Result<Response, ExceptionCode> {
+        match request {
+            Request::ReadCoils(address, count) => {
+                let coils = self.coils.read().unwrap_or_else(|e| e.into_inner());
+                if count == 0 || address as u32 + count as u32 > u16::MAX as u32 + 1 {
+                    return Err(ExceptionCode::IllegalDataAddress);
+                }
+                (0..count)
+                    .map(|offset| coils.get(&(address + offset)).copied())
+                    .collect::<Option<Vec<bool>>>()
+                    .map(Response::ReadCoils)
+                    .ok_or(ExceptionCode::IllegalDataAddress)
+            }
+            Request::WriteSingleCoil(address, value) => {
+                self.set_coils(address, &[value]);
+                Ok(Response::WriteSingleCoil(address, value))
+            }
+            Request::WriteMultipleCoils(address, values) => {
+                self.set_coils(address, &values);
+                Ok(Response::WriteMultipleCoils(address, values.len() as u16))
+            }
+            request => self.server.handle(request),
+        }
+    }
+}
+
+/// 一条 RTU 总线，可挂多个从站
+#[derive(Debug, Clone, Default)]
+pub struct LoopbackBus {
+    slaves: Arc<RwLock<HashMap<u8, LoopbackSlave>>>,
+}
+
+impl LoopbackBus {
+    /// 按名称注册总线，同名的旧总线被替换
+    pub fn register(name: &str) -> Self {
+        let bus = Self::default();
+        BUSES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.to_string(), bus.clone());
+        bus
+    }
+
+    pub fn unregister(name: &str) {
+        BUSES.lock().unwrap_or_else(|e| e.into_inner()).remove(name);
+    }
+
+    pub fn attach(&self, unit_id: u8, slave: LoopbackSlave) {
+        self.slaves.write().unwrap_or_else(|e| e.into_inner()).insert(unit_id, slave);
+    }
+
+    pub fn slave(&self, unit_id: u8) -> Option<LoopbackSlave> {
+        self.slaves.read().unwrap_or_else(|e| e.into_inner()).get(&unit_id).cloned()
+    }
+
+    /// 打开总线（相当于打开串口），返回主站一端的流
+    pub fn open(&self) -> DuplexStream {
+        let (master, line) = tokio::io::duplex(1024);
+        tokio::spawn(self.clone().serve(line));
+        master
+    }
+
+    async fn serve(self, mut line: DuplexStream) {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 256];
+        loop {
+            let n = match line.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            buffer.extend_from_slice(&chunk[..n]);
+            while let Some(frame) = take_frame(&mut buffer) {
+                let Some(reply) = self.respond(&frame) else {
+                    continue;
+                };
+                if line.write_all(&reply).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 处理一帧请求，返回应答帧；不应答时返回 None
+    fn respond(&self, frame: &[u8]) -> Option<Vec<u8>> {
+        let (unit_id, pdu) = check_frame(frame)?;
+        // 广播地址 0 执行写入但不应答
+        let slaves: Vec<(u8, LoopbackSlave)> = {
+            let slaves = self.slaves.read().unwrap_or_else(|e| e.into_inner());
+            slaves
+                .iter()
+                .filter(|(id, _)| unit_id == 0 || **id == unit_id)
+                .map(|(id, slave)| (*id, slave.clone()))
+                .collect()
+        };
+        let request = decode_request(pdu);
+        let mut reply = None;
+        for (id, slave) in slaves {
+            slave.requests.fetch_add(1, Ordering::Relaxed);
+            let fault = slave.next_fault();
+            if fault == Some(Fault::Timeout) {
+                debug!("Loopback slave {} dropped request (injected timeout)", id);
+                continue;
+            }
+            let result = match (&request, fault) {
+                (_, Some(Fault::Exception(code))) => Err(code),
+                (Some(request), _) => slave.handle(request.clone()),
+                (None, _) => Err(ExceptionCode::IllegalFunction),
+            };
+            if unit_id == 0 {
+                continue;
+            }
+            let mut frame = vec![unit_id];
+            frame.extend(encode_response(pdu[0], result));
+            let crc = crc16(&frame);
+            frame.extend(crc.to_le_bytes());
+            if fault == Some(Fault::CrcError) {
+                let last = frame.len() - 1;
+                frame[last] ^= 0xFF;
+            }
+            reply = Some(frame);
+        }
+        reply
+    }
+}
+
+/// 按名称打开已注册的总线
+pub fn open(name: &str) -> io::Result<DuplexStream> {
+    let bus = BUSES.lock().unwrap_or_else(|e| e.into_inner()).get(name).cloned();
+    bus.map(|bus| bus.open()).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("loopback bus {} not registered", name))
+    })
+}
+
+/// Modbus RTU CRC-16，帧中低字节在前
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// 请求帧长度（含地址和 CRC），数据不足时返回 None，未知功能码返回 Some(0)
+fn frame_len(buffer: &[u8]) -> Option<usize> {
+    let function = *buffer.get(1)?;
+    match function {
+        1..=6 => Some(8),
+        15 | 16 => buffer.get(6).map(|count| 9 + *count as usize),
+        _ => Some(0),
+    }
+}
+
+/// 从缓冲区取出一帧完整请求，无法识别的数据整体丢弃
+fn take_frame(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    match frame_len(buffer)? {
+        0 => {
+            buffer.clear();
+            None
+        }
+        len if buffer.len() >= len => Some(buffer.drain(..len).collect()),
+        _ => None,
+    }
+}
+
+/// 校验 CRC，返回从站地址和 PDU
+fn check_frame(frame: &[u8]) -> Option<(u8, &[u8])> {
+    let (body, crc) = frame.split_at(frame.len().checked_sub(2)?);
+    if body.len() < 2 || crc16(body).to_le_bytes() != crc {
+        return None;
+    }
+    Some((body[0], &body[1..]))
+}
+
+fn word(pdu: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*pdu.get(at)?, *pdu.get(at + 1)?]))
+}
+
+fn decode_request(pdu: &[u8]) -> Option<Request<'static>> {
+    let address = word(pdu, 1)?;
+    let value = word(pdu, 3)?;
+    let data = pdu.get(6..).unwrap_or_default();
+    let request = match pdu[0] {
+        1 => Request::ReadCoils(address, value),
+        2 => Request::ReadDiscreteInputs(address, value),
+        3 => Request::ReadHoldingRegisters(address, value),
+        4 => Request::ReadInputRegisters(address, value),
+        5 => Request::WriteSingleCoil(address, value == 0xFF00),
+        6 => Request::WriteSingleRegister(address, value),
+        15 => {
+            let coils = (0..value as usize)
+                .map(|i| data.get(i / 8).map(|byte| byte & (1 << (i % 8)) != 0))
+                .collect::<Option<Vec<bool>>>()?;
+            Request::WriteMultipleCoils(address, Cow::Owned(coils))
+        }
+        16 => {
+            let registers = (0..value as usize)
+                .map(|i| word(data, i * 2))
+                .collect::<Option<Vec<u16>>>()?;
+            Request::WriteMultipleRegisters(address, Cow::Owned(registers))
+        }
+        _ => return None,
+    };
+    Some(request)
+}
+
+fn exception_code(code: ExceptionCode) -> u8 {
+    match code {
+        ExceptionCode::IllegalFunction => 0x01,
+        ExceptionCode::IllegalDataAddress => 0x02,
+        ExceptionCode::IllegalDataValue => 0x03,
+        _ => 0x04,
+    }
+}
+
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+    for (i, bit) in bits.iter().enumerate() {
+        if *bit {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn encode_response(function: u8, result: Result<Response, ExceptionCode>) -> Vec<u8> {
+    let data = match result {
+        Ok(Response::ReadCoils(bits)) | Ok(Response::ReadDiscreteInputs(bits)) => {
+            let bytes = pack_bits(&bits);
+            [vec![bytes.len() as u8], bytes].concat()
+        }
+        Ok(Response::ReadHoldingRegisters(words)) | Ok(Response::ReadInputRegisters(words)) => {
+            let mut bytes = vec![(words.len() * 2) as u8];
+            bytes.extend(words.iter().flat_map(|w| w.to_be_bytes()));
+            bytes
+        }
+        Ok(Response::WriteSingleCoil(address, value)) => {
+            let value: u16 = if value { 0xFF00 } else { 0x0000 };
+            [address.to_be_bytes(), value.to_be_bytes()].concat()
+        }
+        Ok(Response::WriteSingleRegister(address, value))
+        | Ok(Response::WriteMultipleCoils(address, value))
+        | Ok(Response::WriteMultipleRegisters(address, value)) => {
+            [address.to_be_bytes(), value.to_be_bytes()].concat()
+        }
+        Ok(_) => return vec![function | 0x80, 0x04],
+        Err(code) => return vec![function | 0x80, exception_code(code)],
+    };
+    [vec![function], data].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::modbus::ModbusTarget;
+    use crate::utils::modbus::{ByteOrder, DataType, ModbusClient};
+    use std::time::Duration;
+
+    #[test]
+    fn test_frames() {
+        // 读 1 号从站保持寄存器 0，1 个
+        let request = [0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A];
+        assert_eq!(crc16(&request[..6]), 0x0A84);
+        let (unit_id, pdu) = check_frame(&request).unwrap();
+        assert_eq!(unit_id, 1);
+        assert_eq!(decode_request(pdu), Some(Request::ReadHoldingRegisters(0, 1)));
+
+        let mut corrupted = request;
+        corrupted[7] ^= 0xFF;
+        assert_eq!(check_frame(&corrupted), None);
+
+        let mut buffer = request[..5].to_vec();
+        assert_eq!(take_frame(&mut buffer), None);
+        buffer.extend_from_slice(&request[5..]);
+        buffer.push(0x01);
+        assert_eq!(take_frame(&mut buffer), Some(request.to_vec()));
+        assert_eq!(buffer, vec![0x01]);
+
+        assert_eq!(
+            encode_response(0x01, Ok(Response::ReadCoils(vec![true, false, true]))),
+            vec![0x01, 0x01, 0b101]
+        );
+        assert_eq!(
+            encode_response(0x03, Err(ExceptionCode::IllegalDataAddress)),
+            vec![0x83, 0x02]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_over_loopback() {
+        let bus = LoopbackBus::register("test_client_over_loopback");
+        let slave = LoopbackSlave::new(HashSet::from([100]));
+        slave.registers().set_input(0, &[0x42F6, 0xE979]);
+        slave.registers().set_holding(100, &[0]);
+        slave.set_coils(0, &[false]);
+        bus.attach(3, slave.clone());
+
+        let target = ModbusTarget::Loopback { bus: "test_client_over_loopback".to_string() };
+        let timeout = Duration::from_millis(200);
+        let mut client = ModbusClient::connect(&target, 3, timeout).await.unwrap();
+
+        let value = client.read_input_value(0, DataType::F32, ByteOrder::Abcd).await.unwrap();
+        assert_eq!(value as f32, 123.456);
+        client.write_single_register(100, 42).await.unwrap();
+        assert_eq!(slave.registers().holding(100, 1), Some(vec![42]));
+        client.write_single_coil(0, true).await.unwrap();
+        assert_eq!(client.read_coils(0, 1).await.unwrap(), vec![true]);
+        assert!(client.write_single_register(101, 1).await.is_err());
+
+        slave.inject([Fault::Timeout]);
+        let err = client.read_holding_registers(100, 1).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        // 超时后重新连接，丢弃残留的应答
+        let mut client = ModbusClient::connect(&target, 3, timeout).await.unwrap();
+        slave.inject([Fault::CrcError]);
+        assert!(client.read_holding_registers(100, 1).await.is_err());
+
+        let mut client = ModbusClient::connect(&target, 3, timeout).await.unwrap();
+        slave.inject([Fault::Exception(ExceptionCode::ServerDeviceBusy)]);
+        assert!(client.read_holding_registers(100, 1).await.is_err());
+        assert_eq!(client.read_holding_registers(100, 1).await.unwrap(), vec![42]);
+
+        // 地址不匹配的从站不应答
+        let mut other = ModbusClient::connect(&target, 4, timeout).await.unwrap();
+        assert!(other.read_holding_registers(100, 1).await.is_err());
+
+        LoopbackBus::unregister("test_client_over_loopback");
+        assert!(ModbusClient::connect(&target, 3, timeout).await.is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! 操作人标识
+//!
+//! 由前端或反向代理通过 `X-Operator` 头传入，用于配置变更记录。
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use std::convert::Infallible;
+
+pub const OPERATOR_HEADER: &str = "x-operator";
+
+/// 当前请求的操作人，未提供时为 `None`
+#[derive(Debug, Clone, Default)]
+pub struct Operator(pub Option<String>);
+
+impl<S> FromRequestParts<S> for Operator
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let operator = parts
+            .headers
+            .get(OPERATOR_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+
+        Ok(Operator(operator))
+    }
+}
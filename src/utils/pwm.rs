@@ -0,0 +1,96 @@
+//! PWM 输出
+//!
+//! 基于 `/sys/class/pwm` 的硬件 PWM，文件写入放到阻塞线程池执行。
+
+use std::io;
+use std::path::PathBuf;
+
+const SYSFS_ROOT: &str = "/sys/class/pwm";
+
+/// 频率和占空比换算为周期和高电平时间 (ns)
+fn to_nanos(frequency_hz: f64, duty_pct: f64) -> (u64, u64) {
+    let period = (1e9 / frequency_hz).round() as u64;
+    let duty = (period as f64 * duty_pct.clamp(0.0, 100.0) / 100.0).round() as u64;
+    (period, duty.min(period))
+}
+
+/// 内核要求任何时刻 duty_cycle 不超过 period：新周期容得下当前高电平时间就先写周期
+fn period_first(current_duty: u64, new_period: u64) -> bool {
+    current_duty <= new_period
+}
+
+/// 单个 PWM 通道
+#[derive(Debug, Clone)]
+pub struct PwmController {
+    dir: PathBuf,
+}
+
+impl PwmController {
+    /// 打开 pwmchip`chip` 的第 `channel` 路，未导出时先导出
+    pub async fn open(chip: u32, channel: u32) -> io::Result<Self> {
+        let chip_dir = PathBuf::from(format!("{}/pwmchip{}", SYSFS_ROOT, chip));
+        let dir = chip_dir.join(format!("pwm{}", channel));
+        let exported = dir.clone();
+        tokio::task::spawn_blocking(move || {
+            if !exported.exists() {
+                std::fs::write(chip_dir.join("export"), channel.to_string())?;
+            }
+            io::Result::Ok(())
+        })
+        .await
+        .map_err(io::Error::other)??;
+        Ok(Self { dir })
+    }
+
+    /// 设置频率 (Hz) 和占空比 (%)，并使能输出
+    pub async fn set(&self, frequency_hz: f64, duty_pct: f64) -> io::Result<()> {
+        if !(frequency_hz.is_finite() && frequency_hz > 0.0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid PWM frequency"));
+        }
+        let (period, duty) = to_nanos(frequency_hz, duty_pct);
+        let dir = self.dir.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let current_duty: u64 = std::fs::read_to_string(dir.join("duty_cycle"))?
+                .trim()
+                .parse()
+                .unwrap_or(0);
+            if period_first(current_duty, period) {
+                std::fs::write(dir.join("period"), period.to_string())?;
+                std::fs::write(dir.join("duty_cycle"), duty.to_string())?;
+            } else {
+                std::fs::write(dir.join("duty_cycle"), duty.to_string())?;
+                std::fs::write(dir.join("period"), period.to_string())?;
+            }
+            std::fs::write(dir.join("enable"), "1")
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// 关闭输出
+    pub async fn disable(&self) -> io::Result<()> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || std::fs::write(dir.join("enable"), "0"))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_nanos() {
+        assert_eq!(to_nanos(1000.0, 25.0), (1_000_000, 250_000));
+        assert_eq!(to_nanos(50.0, 150.0), (20_000_000, 20_000_000));
+    }
+
+    #[test]
+    fn test_period_first() {
+        // 1kHz 50% 降到 5kHz：当前高电平 500us 超过新周期 200us，须先改占空比
+        assert!(!period_first(500_000, 200_000));
+        assert!(period_first(100_000, 200_000));
+    }
+}
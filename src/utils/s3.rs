@@ -0,0 +1,180 @@
+//! S3 兼容对象存储
+//!
+//! 只实现上传和下载单个对象，请求按 AWS Signature V4 签名，路径风格访问
+//! （`{endpoint}/{bucket}/{key}`），兼容 MinIO 等自建对象存储。
+
+use crate::config::report::S3Config;
+use crate::utils::crypto::{hmac_sha256, sha256_hex, to_hex};
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method, StatusCode};
+use std::io;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct S3Client {
+    config: S3Config,
+    http: Client,
+}
+
+/// 按 RFC 3986 编码路径中的一段，保留 `/`
+fn encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            other => out.push_str(&format!("%{:02X}", other)),
+        }
+    }
+    out
+}
+
+/// 规范请求，`headers` 须为小写名称并按名称排序
+fn canonical_request(
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+) -> String {
+    let canonical_headers: String =
+        headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers: Vec<&str> = headers.iter().map(|(name, _)| *name).collect();
+    format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method,
+        encode_path(path),
+        canonical_headers,
+        signed_headers.join(";"),
+        payload_hash
+    )
+}
+
+/// 计算签名
+fn signature(secret_key: &str, region: &str, amz_date: &str, canonical_request: &str) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let mut key = format!("AWS4{}", secret_key).into_bytes();
+    for part in [date, region, "s3", "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+}
+
+impl S3Client {
+    pub fn new(config: &S3Config) -> io::Result<Self> {
+        if config.endpoint.is_empty() || config.bucket.is_empty() {
+            let message = "S3 endpoint and bucket required";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        let http = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .map_err(io::Error::other)?;
+        Ok(Self { config: config.clone(), http })
+    }
+
+    /// 加上配置的前缀后的对象键
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.config.prefix, name)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> io::Result<reqwest::Response> {
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let url = reqwest::Url::parse(&format!("{}/{}/{}", endpoint, self.config.bucket, key))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                let message = "S3 endpoint has no host";
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        };
+
+        let now: DateTime<Utc> = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = sha256_hex(&body);
+        let headers = [
+            ("host", host.as_str()),
+            ("x-amz-content-sha256", payload_hash.as_str()),
+            ("x-amz-date", amz_date.as_str()),
+        ];
+        let path = format!("/{}/{}", self.config.bucket, key);
+        let canonical = canonical_request(method.as_str(), &path, &headers, &payload_hash);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}/{}/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.config.access_key,
+            &amz_date[..8],
+            self.config.region,
+            signature(&self.config.secret_key, &self.config.region, &amz_date, &canonical)
+        );
+
+        let response = self
+            .http
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+        Ok(response)
+    }
+
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> io::Result<()> {
+        let response = self.send(Method::PUT, key, body).await?;
+        if !response.status().is_success() {
+            return Err(io::Error::other(format!("S3 PUT {} returned {}", key, response.status())));
+        }
+        Ok(())
+    }
+
+    pub async fn get_object(&self, key: &str) -> io::Result<Vec<u8>> {
+        let response = self.send(Method::GET, key, Vec::new()).await?;
+        match response.status() {
+            status if status.is_success() => {
+                Ok(response.bytes().await.map_err(io::Error::other)?.to_vec())
+            }
+            StatusCode::NOT_FOUND => Err(io::Error::new(io::ErrorKind::NotFound, key.to_string())),
+            status => Err(io::Error::other(format!("S3 GET {} returned {}", key, status))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        // AWS 文档中 GET Object 的示例
+        let empty = sha256_hex(b"");
+        let headers = [
+            ("host", "examplebucket.s3.amazonaws.com"),
+            ("range", "bytes=0-9"),
+            ("x-amz-content-sha256", empty.as_str()),
+            ("x-amz-date", "20130524T000000Z"),
+        ];
+        let canonical = canonical_request("GET", "/test.txt", &headers, &empty);
+        let secret = "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY";
+        assert_eq!(
+            signature(secret, "us-east-1", "20130524T000000Z", &canonical),
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+        assert_eq!(encode_path("/reports/日报 1.pdf"), "/reports/%E6%97%A5%E6%8A%A5%201.pdf");
+    }
+}
@@ -0,0 +1,15 @@
+//! serde 辅助函数
+
+use serde::{Deserialize, Deserializer};
+
+/// 区分“未提供”和“显式传 null”
+///
+/// 配合 `#[serde(default, deserialize_with = "double_option")]` 使用：
+/// 字段缺省为 `None`，`null` 为 `Some(None)`，有值为 `Some(Some(v))`。
+pub fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,191 @@
+//! SNMPv2c Trap 编码
+//!
+//! 按 RFC 3416 组装 SNMPv2-Trap-PDU 并做 BER 编码，前两个变量固定为
+//! sysUpTime.0 和 snmpTrapOID.0，其余为调用方给出的变量。
+
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+const SYS_UP_TIME: &str = "1.3.6.1.2.1.1.3.0";
+const SNMP_TRAP_OID: &str = "1.3.6.1.6.3.1.1.4.1.0";
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_TIME_TICKS: u8 = 0x43;
+const TAG_TRAP_V2: u8 = 0xA7;
+/// SNMP 版本字段，v2c 为 1
+const VERSION_2C: i64 = 1;
+
+/// 变量值
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrapValue {
+    Integer(i64),
+    OctetString(String),
+    Oid(String),
+    TimeTicks(u32),
+}
+
+/// 解析点分 OID，至少两段且前两段合法
+pub fn parse_oid(oid: &str) -> Option<Vec<u32>> {
+    let arcs = oid
+        .trim()
+        .trim_start_matches('.')
+        .split('.')
+        .map(|arc| arc.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let valid = arcs.len() >= 2 && arcs[0] <= 2 && (arcs[0] == 2 || arcs[1] < 40);
+    valid.then_some(arcs)
+}
+
+fn push_tlv(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    out.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(content);
+}
+
+fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // 去掉不影响符号的前导字节
+    let mut start = 0;
+    while start < 7 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+fn unsigned(value: u32) -> Vec<u8> {
+    integer(value as i64)
+}
+
+fn oid(arcs: &[u32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut push_arc = |arc: u32| {
+        let mut groups = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        out.extend(groups.into_iter().rev());
+    };
+    push_arc(arcs[0] * 40 + arcs[1]);
+    for arc in &arcs[2..] {
+        push_arc(*arc);
+    }
+    out
+}
+
+fn encode_value(out: &mut Vec<u8>, value: &TrapValue) -> Option<()> {
+    match value {
+        TrapValue::Integer(v) => push_tlv(out, TAG_INTEGER, &integer(*v)),
+        TrapValue::OctetString(v) => push_tlv(out, TAG_OCTET_STRING, v.as_bytes()),
+        TrapValue::Oid(v) => push_tlv(out, TAG_OID, &oid(&parse_oid(v)?)),
+        TrapValue::TimeTicks(v) => push_tlv(out, TAG_TIME_TICKS, &unsigned(*v)),
+    }
+    Some(())
+}
+
+/// 编码一条 Trap 报文，OID 无效时返回 None
+///
+/// `uptime` 为百分之一秒，`variables` 为 (OID, 值)。
+pub fn encode(
+    community: &str,
+    request_id: i32,
+    uptime: u32,
+    trap_oid: &str,
+    variables: &[(String, TrapValue)],
+) -> Option<Vec<u8>> {
+    let mut bindings = Vec::new();
+    let fixed = [
+        (SYS_UP_TIME.to_string(), TrapValue::TimeTicks(uptime)),
+        (SNMP_TRAP_OID.to_string(), TrapValue::Oid(trap_oid.to_string())),
+    ];
+    for (name, value) in fixed.iter().chain(variables) {
+        let mut binding = Vec::new();
+        push_tlv(&mut binding, TAG_OID, &oid(&parse_oid(name)?));
+        encode_value(&mut binding, value)?;
+        push_tlv(&mut bindings, TAG_SEQUENCE, &binding);
+    }
+
+    let mut pdu = Vec::new();
+    push_tlv(&mut pdu, TAG_INTEGER, &integer(request_id as i64));
+    push_tlv(&mut pdu, TAG_INTEGER, &integer(0));
+    push_tlv(&mut pdu, TAG_INTEGER, &integer(0));
+    push_tlv(&mut pdu, TAG_SEQUENCE, &bindings);
+
+    let mut message = Vec::new();
+    push_tlv(&mut message, TAG_INTEGER, &integer(VERSION_2C));
+    push_tlv(&mut message, TAG_OCTET_STRING, community.as_bytes());
+    push_tlv(&mut message, TAG_TRAP_V2, &pdu);
+
+    let mut out = Vec::new();
+    push_tlv(&mut out, TAG_SEQUENCE, &message);
+    Some(out)
+}
+
+/// 以 UDP 发送一条已编码的 Trap
+pub async fn send(target: SocketAddr, packet: &[u8]) -> io::Result<()> {
+    let bind: SocketAddr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }.parse().unwrap();
+    let socket = UdpSocket::bind(bind).await?;
+    socket.send_to(packet, target).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ber_primitives() {
+        assert_eq!(integer(0), vec![0x00]);
+        assert_eq!(integer(127), vec![0x7F]);
+        assert_eq!(integer(128), vec![0x00, 0x80]);
+        assert_eq!(integer(-1), vec![0xFF]);
+        assert_eq!(integer(-129), vec![0xFF, 0x7F]);
+        let arcs = parse_oid("1.3.6.1.4.1.8072").unwrap();
+        assert_eq!(oid(&arcs), vec![0x2B, 6, 1, 4, 1, 0xBF, 0x08]);
+        assert_eq!(parse_oid("1.3.x"), None);
+        assert_eq!(parse_oid("3.1"), None);
+
+        let mut out = Vec::new();
+        push_tlv(&mut out, TAG_OCTET_STRING, &[0x41; 200]);
+        assert_eq!(&out[..3], &[0x04, 0x81, 200]);
+    }
+
+    #[test]
+    fn test_encode_trap() {
+        let packet = encode(
+            "public",
+            1,
+            100,
+            "1.3.6.1.4.1.8072.9999.9999.0.1",
+            &[("1.3.6.1.4.1.8072.9999.9999.1.1".to_string(), TrapValue::Integer(42))],
+        )
+        .unwrap();
+        // SEQUENCE { INTEGER 1, OCTET STRING "public", Trap-PDU ... }
+        assert_eq!(packet[0], TAG_SEQUENCE);
+        assert_eq!(packet[1] as usize, packet.len() - 2);
+        assert_eq!(&packet[2..5], &[TAG_INTEGER, 1, 1]);
+        assert_eq!(&packet[5..13], b"\x04\x06public");
+        assert_eq!(packet[13], TAG_TRAP_V2);
+        assert!(packet.ends_with(&[TAG_INTEGER, 1, 42]));
+
+        let invalid = [("1.3.x".to_string(), TrapValue::Integer(1))];
+        assert_eq!(encode("public", 1, 0, "1.3.6.1", &invalid), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! SPI 总线访问
+//!
+//! 基于 spidev 的全双工传输，模式、速率、字长可配置；ioctl 是阻塞调用，统一放到阻塞线程池执行。
+//! 其他平台上打开设备直接返回 `Unsupported`。
+
+use serde::Deserialize;
+#[cfg(target_os = "linux")]
+use spidev::{SpiModeFlags, Spidev, SpidevOptions, SpidevTransfer};
+use std::io;
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex};
+
+/// 总线参数
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpiOptions {
+    /// 设备路径，例如 /dev/spidev0.0
+    pub path: String,
+    /// SPI 模式 0~3（CPOL/CPHA）
+    #[serde(default)]
+    pub mode: u8,
+    #[serde(default = "default_speed_hz")]
+    pub speed_hz: u32,
+    #[serde(default = "default_bits_per_word")]
+    pub bits_per_word: u8,
+}
+
+fn default_speed_hz() -> u32 {
+    1_000_000
+}
+
+fn default_bits_per_word() -> u8 {
+    8
+}
+
+#[cfg(target_os = "linux")]
+fn mode_flags(mode: u8) -> Option<SpiModeFlags> {
+    match mode {
+        0 => Some(SpiModeFlags::SPI_MODE_0),
+        1 => Some(SpiModeFlags::SPI_MODE_1),
+        2 => Some(SpiModeFlags::SPI_MODE_2),
+        3 => Some(SpiModeFlags::SPI_MODE_3),
+        _ => None,
+    }
+}
+
+/// 一个 SPI 从设备（片选）
+#[derive(Clone)]
+pub struct SpiController {
+    #[cfg(target_os = "linux")]
+    device: Arc<Mutex<Spidev>>,
+    path: String,
+}
+
+impl std::fmt::Debug for SpiController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpiController").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SpiController {
+    pub async fn open(options: &SpiOptions) -> io::Result<Self> {
+        let mode = mode_flags(options.mode).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("invalid SPI mode {}", options.mode))
+        })?;
+
+        let settings = SpidevOptions::new()
+            .mode(mode)
+            .max_speed_hz(options.speed_hz)
+            .bits_per_word(options.bits_per_word)
+            .build();
+        let path = options.path.clone();
+        let device = tokio::task::spawn_blocking(move || {
+            let mut device = Spidev::open(path)?;
+            device.configure(&settings)?;
+            io::Result::Ok(device)
+        })
+        .await
+        .map_err(io::Error::other)??;
+
+        Ok(Self {
+            device: Arc::new(Mutex::new(device)),
+            path: options.path.clone(),
+        })
+    }
+
+    /// 全双工传输：发送 `tx` 的同时读回等长数据
+    pub async fn transfer(&self, tx: Vec<u8>) -> io::Result<Vec<u8>> {
+        let device = self.device.clone();
+        tokio::task::spawn_blocking(move || {
+            let device = device.lock().unwrap_or_else(|e| e.into_inner());
+            let mut rx = vec![0u8; tx.len()];
+            let mut transfer = SpidevTransfer::read_write(&tx, &mut rx);
+            device.transfer(&mut transfer)?;
+            Ok(rx)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl SpiController {
+    pub async fn open(_options: &SpiOptions) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "SPI is only available on Linux"))
+    }
+
+    pub async fn transfer(&self, _tx: Vec<u8>) -> io::Result<Vec<u8>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+impl SpiController {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_flags() {
+        assert_eq!(mode_flags(0), Some(SpiModeFlags::SPI_MODE_0));
+        assert_eq!(mode_flags(3), Some(SpiModeFlags::SPI_MODE_3));
+        assert_eq!(mode_flags(4), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! 租户（组织）隔离
+//!
+//! 开启 `tenancy.enabled` 后按请求的 `X-Api-Key` 所属组织确定租户：绑定了组织的 Key 只能看到和修改
+//! 本组织的用户、设备、报警规则、自动化规则和测量值；未绑定组织的 Key 是平台管理 Key，可以访问全部
+//! 数据并管理组织。测量值不单独记录组织，按所属设备的组织隔离，未关联设备的测量值只有平台管理 Key
+//! 可见。未开启时不做隔离。
+//!
+//! 报警记录、校准、维护计划等挂在设备下的数据同样按设备的组织隔离。厂站、区域和生产日历全厂共用，
+//! 租户只能查看；全厂汇总（报表、能耗区域、排放许可等）和网关本机的设备（执行器、串口、加药系统等）
+//! 只允许平台管理 Key。
+//!
+//! 其他组织的数据一律按不存在处理，不暴露其是否存在。
+
+use crate::app_state::AppState;
+use crate::middleware::api_key::{ApiKeyIdentity, API_KEY_HEADER};
+use crate::models::alarm_log::Entity as AlarmLogEntity;
+use crate::models::device::{Column as DeviceColumn, Entity as DeviceEntity};
+use crate::models::measurement::Entity as MeasurementEntity;
+use crate::services::api_key as api_key_service;
+use crate::utils::error::AppError;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use sea_orm::sea_query::{Query, SimpleExpr};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 当前请求的租户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tenant {
+    /// 未开启多租户或平台管理 Key，不做隔离
+    All,
+    Organization(i32),
+}
+
+impl Tenant {
+    pub(crate) fn from_key(organization_id: Option<i32>) -> Self {
+        organization_id.map_or(Tenant::All, Tenant::Organization)
+    }
+
+    pub fn organization_id(&self) -> Option<i32> {
+        match self {
+            Tenant::All => None,
+            Tenant::Organization(id) => Some(*id),
+        }
+    }
+
+    /// 数据是否对当前租户可见
+    pub fn owns(&self, organization_id: Option<i32>) -> bool {
+        match self {
+            Tenant::All => true,
+            Tenant::Organization(id) => organization_id == Some(*id),
+        }
+    }
+
+    /// 不可见的数据按未找到处理
+    pub fn check(&self, organization_id: Option<i32>) -> Result<(), AppError> {
+        if self.owns(organization_id) {
+            Ok(())
+        } else {
+            Err(AppError::NotFound)
+        }
+    }
+
+    /// 组织管理等平台级操作只允许平台管理 Key
+    pub fn require_platform(&self) -> Result<(), AppError> {
+        match self {
+            Tenant::All => Ok(()),
+            Tenant::Organization(_) => Err(AppError::Forbidden),
+        }
+    }
+
+    /// 新建数据的所属组织：租户只能建在本组织，平台管理 Key 可以任意指定
+    pub fn assign(&self, requested: Option<i32>) -> Result<Option<i32>, AppError> {
+        match (self, requested) {
+            (Tenant::All, requested) => Ok(requested),
+            (Tenant::Organization(id), Some(requested)) if requested != *id => {
+                Err(AppError::Forbidden)
+            }
+            (Tenant::Organization(id), _) => Ok(Some(*id)),
+        }
+    }
+
+    /// 按组织字段过滤查询
+    pub fn scope<Q: QueryFilter, C: ColumnTrait>(&self, query: Q, column: C) -> Q {
+        match self {
+            Tenant::All => query,
+            Tenant::Organization(id) => query.filter(column.eq(*id)),
+        }
+    }
+
+    /// 按设备 ID 列过滤查询，租户只能看到本组织设备的数据
+    pub fn scope_devices<Q: QueryFilter, C: ColumnTrait>(
+        &self,
+        query: Q,
+        device_id_column: C,
+    ) -> Q {
+        match self {
+            Tenant::All => query,
+            Tenant::Organization(id) => query.filter(organization_devices(device_id_column, *id)),
+        }
+    }
+
+    /// 当前租户可见的设备 ID，不做隔离时返回 `None`
+    pub async fn device_ids(
+        &self,
+        conn: &DatabaseConnection,
+    ) -> Result<Option<HashSet<i32>>, AppError> {
+        match self {
+            Tenant::All => Ok(None),
+            Tenant::Organization(id) => {
+                Ok(Some(organization_device_ids(conn, *id).await?.into_iter().collect()))
+            }
+        }
+    }
+
+    /// 设备是否属于当前租户；租户访问未关联设备的数据按未找到处理
+    pub async fn check_device(
+        &self,
+        conn: &DatabaseConnection,
+        device_id: Option<i32>,
+    ) -> Result<(), AppError> {
+        if *self == Tenant::All {
+            return Ok(());
+        }
+        let device_id = device_id.ok_or(AppError::NotFound)?;
+        let device = DeviceEntity::find_by_id(device_id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or(AppError::NotFound)?;
+        self.check(device.organization_id)
+    }
+
+    /// 测量值按所属设备判断是否属于当前租户
+    pub async fn check_measurement(
+        &self,
+        conn: &DatabaseConnection,
+        id: i32,
+    ) -> Result<(), AppError> {
+        if *self == Tenant::All {
+            return Ok(());
+        }
+        let measurement = MeasurementEntity::find_by_id(id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or(AppError::NotFound)?;
+        self.check_device(conn, measurement.device_id).await
+    }
+
+    /// 报警记录按关联设备判断是否属于当前租户
+    pub async fn check_alarm_log(
+        &self,
+        conn: &DatabaseConnection,
+        id: i32,
+    ) -> Result<(), AppError> {
+        if *self == Tenant::All {
+            return Ok(());
+        }
+        let alarm_log = AlarmLogEntity::find_by_id(id)
+            .one(conn)
+            .await
+            .map_err(|_| AppError::InternalError)?
+            .ok_or(AppError::NotFound)?;
+        self.check_device(conn, alarm_log.device_id).await
+    }
+}
+
+/// 设备 ID 列属于指定组织的设备
+pub fn organization_devices<C: ColumnTrait>(
+    device_id_column: C,
+    organization_id: i32,
+) -> SimpleExpr {
+    device_id_column.in_subquery(
+        Query::select()
+            .column(DeviceColumn::Id)
+            .from(DeviceEntity)
+            .and_where(DeviceColumn::OrganizationId.eq(organization_id))
+            .to_owned(),
+    )
+}
+
+/// 组织的全部设备 ID
+pub async fn organization_device_ids(
+    conn: &DatabaseConnection,
+    organization_id: i32,
+) -> Result<Vec<i32>, AppError> {
+    DeviceEntity::find()
+        .select_only()
+        .column(DeviceColumn::Id)
+        .filter(DeviceColumn::OrganizationId.eq(organization_id))
+        .into_tuple()
+        .all(conn)
+        .await
+        .map_err(|_| AppError::InternalError)
+}
+
+impl FromRequestParts<Arc<AppState>> for Tenant {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if !state.settings.tenancy.enabled {
+            return Ok(Tenant::All);
+        }
+        // 上报接口已由认证中间件校验过 Key
+        if let Some(identity) = parts.extensions.get::<ApiKeyIdentity>() {
+            return Ok(Tenant::from_key(identity.organization_id));
+        }
+
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(AppError::InvalidCredentials)?;
+        let api_key = api_key_service::identify(state.db.get_connection(), key).await?;
+        Ok(Tenant::from_key(api_key.organization_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolation() {
+        let tenant = Tenant::Organization(2);
+        assert!(tenant.owns(Some(2)));
+        assert!(!tenant.owns(Some(3)));
+        assert!(!tenant.owns(None));
+        assert!(Tenant::All.owns(None));
+
+        assert_eq!(tenant.assign(None).unwrap(), Some(2));
+        assert_eq!(tenant.assign(Some(2)).unwrap(), Some(2));
+        assert!(tenant.assign(Some(3)).is_err());
+        assert_eq!(Tenant::All.assign(Some(3)).unwrap(), Some(3));
+        assert_eq!(Tenant::All.assign(None).unwrap(), None);
+
+        assert!(tenant.require_platform().is_err());
+        assert!(Tenant::All.require_platform().is_ok());
+    }
+}
@@ -0,0 +1,87 @@
+//! 时区换算
+//!
+//! 时间一律以 UTC 存储，本地日期、时刻按厂站时区（IANA 时区名，见 `sites.timezone`）换算，
+//! 厂站未设置时使用 `timezone.default`。夏令时切换日一天为 23 或 25 小时：本地时刻落在拨快
+//! 跳过的时段内时顺延跳过的时长，拨慢重复的时刻取较早的一次。
+
+use crate::config::timezone::TimezoneConfig;
+use crate::utils::error::AppError;
+use chrono::{
+    DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use tracing::warn;
+
+/// 解析 IANA 时区名
+pub fn parse(name: &str) -> Result<Tz, AppError> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| AppError::InvalidInput(format!("未知的时区: {}", name).into()))
+}
+
+/// 配置的默认时区，配置无效时退回 UTC
+pub fn default_zone(config: &TimezoneConfig) -> Tz {
+    parse(&config.default).unwrap_or_else(|_| {
+        warn!("Invalid default timezone {}, falling back to UTC", config.default);
+        Tz::UTC
+    })
+}
+
+/// 本地时刻对应的 UTC 时间
+pub fn to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.with_timezone(&Utc),
+        LocalResult::None => {
+            // 按跳过之前的偏移换算，结果落在跳过之后
+            let before = tz.offset_from_utc_datetime(&(local - Duration::days(1))).fix();
+            (local - Duration::seconds(before.local_minus_utc() as i64)).and_utc()
+        }
+    }
+}
+
+/// 本地日期零点对应的 UTC 时间
+pub fn midnight(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    to_utc(tz, date.and_time(NaiveTime::MIN))
+}
+
+/// UTC 时间所在的本地日期
+pub fn local_date(tz: Tz, at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&tz).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_dst_days() {
+        let tz = parse("America/New_York").unwrap();
+        assert!(parse("Mars/Olympus").is_err());
+
+        // 2026-03-08 拨快一小时，2026-11-01 拨慢一小时
+        let hours =
+            |d: NaiveDate| (midnight(tz, d + Duration::days(1)) - midnight(tz, d)).num_hours();
+        assert_eq!(hours(date(2026, 3, 8)), 23);
+        assert_eq!(hours(date(2026, 11, 1)), 25);
+        assert_eq!(hours(date(2026, 6, 1)), 24);
+        let june = Utc.with_ymd_and_hms(2026, 6, 1, 4, 0, 0).unwrap();
+        assert_eq!(midnight(tz, date(2026, 6, 1)), june);
+
+        // 跳过的 02:30 顺延为 03:30（夏令时）
+        let skipped = date(2026, 3, 8).and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(to_utc(tz, skipped), Utc.with_ymd_and_hms(2026, 3, 8, 7, 30, 0).unwrap());
+        // 重复的 01:30 取较早的一次（夏令时）
+        let repeated = date(2026, 11, 1).and_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(to_utc(tz, repeated), Utc.with_ymd_and_hms(2026, 11, 1, 5, 30, 0).unwrap());
+
+        // 圣保罗 2018-11-04 零点直接跳到 01:00
+        let sao_paulo = parse("America/Sao_Paulo").unwrap();
+        let start = midnight(sao_paulo, date(2018, 11, 4));
+        assert_eq!(start, Utc.with_ymd_and_hms(2018, 11, 4, 3, 0, 0).unwrap());
+        assert_eq!(local_date(sao_paulo, start), date(2018, 11, 4));
+    }
+}
@@ -0,0 +1,223 @@
+//! 串口（UART）访问
+
+use crate::config::serial_console::SerialPortConfig;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_serial::{
+    DataBits, FlowControl, Parity, SerialPort, SerialPortBuilderExt, SerialStream, StopBits,
+};
+
+/// 缓冲区上限，超过仍未切出一帧则丢弃
+const MAX_FRAME_LEN: usize = 4096;
+
+fn data_bits(bits: u8) -> Option<DataBits> {
+    match bits {
+        5 => Some(DataBits::Five),
+        6 => Some(DataBits::Six),
+        7 => Some(DataBits::Seven),
+        8 => Some(DataBits::Eight),
+        _ => None,
+    }
+}
+
+fn parity(parity: &str) -> Option<Parity> {
+    match parity.to_ascii_lowercase().as_str() {
+        "none" | "n" => Some(Parity::None),
+        "odd" | "o" => Some(Parity::Odd),
+        "even" | "e" => Some(Parity::Even),
+        _ => None,
+    }
+}
+
+fn stop_bits(bits: u8) -> Option<StopBits> {
+    match bits {
+        1 => Some(StopBits::One),
+        2 => Some(StopBits::Two),
+        _ => None,
+    }
+}
+
+fn flow_control(flow: &str) -> Option<FlowControl> {
+    match flow.to_ascii_lowercase().as_str() {
+        "none" => Some(FlowControl::None),
+        "software" | "xonxoff" => Some(FlowControl::Software),
+        "hardware" | "rtscts" => Some(FlowControl::Hardware),
+        _ => None,
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// 按配置打开串口
+pub fn open(config: &SerialPortConfig) -> io::Result<SerialStream> {
+    let data_bits = data_bits(config.data_bits)
+        .ok_or_else(|| invalid(format!("unsupported data bits: {}", config.data_bits)))?;
+    let parity = parity(&config.parity)
+        .ok_or_else(|| invalid(format!("unsupported parity: {}", config.parity)))?;
+    let stop_bits = stop_bits(config.stop_bits)
+        .ok_or_else(|| invalid(format!("unsupported stop bits: {}", config.stop_bits)))?;
+    let flow_control = flow_control(&config.flow_control)
+        .ok_or_else(|| invalid(format!("unsupported flow control: {}", config.flow_control)))?;
+
+    let stream = tokio_serial::new(&config.path, config.baud_rate)
+        .data_bits(data_bits)
+        .parity(parity)
+        .stop_bits(stop_bits)
+        .flow_control(flow_control)
+        .open_native_async()?;
+    Ok(stream)
+}
+
+/// 帧切分方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Framing {
+    /// 以分隔符结尾，返回的帧不含分隔符，例如 `\r\n`
+    Delimiter(Vec<u8>),
+    /// 固定长度
+    FixedLength(usize),
+}
+
+impl Framing {
+    /// 从缓冲区切出一帧，数据不足时返回 None
+    fn split(&self, buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+        match self {
+            Framing::Delimiter(delimiter) => {
+                if delimiter.is_empty() {
+                    return None;
+                }
+                let pos = buffer.windows(delimiter.len()).position(|w| w == delimiter.as_slice())?;
+                let frame = buffer[..pos].to_vec();
+                buffer.drain(..pos + delimiter.len());
+                Some(frame)
+            }
+            Framing::FixedLength(length) => {
+                if *length == 0 || buffer.len() < *length {
+                    return None;
+                }
+                Some(buffer.drain(..*length).collect())
+            }
+        }
+    }
+}
+
+/// 按帧读写的串口，供传感器协议解析使用
+pub struct UartController {
+    stream: SerialStream,
+    framing: Framing,
+    read_timeout: Duration,
+    buffer: Vec<u8>,
+}
+
+impl UartController {
+    pub fn open(config: &SerialPortConfig, framing: Framing) -> io::Result<Self> {
+        match &framing {
+            Framing::Delimiter(d) if d.is_empty() => {
+                return Err(invalid("delimiter must not be empty".to_string()));
+            }
+            Framing::FixedLength(0) => {
+                return Err(invalid("frame length must be positive".to_string()));
+            }
+            Framing::FixedLength(n) if *n > MAX_FRAME_LEN => {
+                return Err(invalid(format!("frame length exceeds {}", MAX_FRAME_LEN)));
+            }
+            _ => {}
+        }
+        Ok(Self {
+            stream: open(config)?,
+            framing,
+            read_timeout: Duration::from_millis(config.read_timeout_ms),
+            buffer: Vec::new(),
+        })
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.stream.write_all(data).await?;
+        self.stream.flush().await
+    }
+
+    /// 读取一帧，超时返回 `TimedOut`，串口关闭返回 `UnexpectedEof`
+    pub async fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+        let read_timeout = self.read_timeout;
+        tokio::time::timeout(read_timeout, self.fill_frame())
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "serial read timed out"))?
+    }
+
+    async fn fill_frame(&mut self) -> io::Result<Vec<u8>> {
+        let mut chunk = [0u8; 256];
+        loop {
+            if let Some(frame) = self.framing.split(&mut self.buffer) {
+                return Ok(frame);
+            }
+            if self.buffer.len() > MAX_FRAME_LEN {
+                self.buffer.clear();
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+            }
+            let n = self.stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// 清掉残留数据后发送请求并等待一帧应答
+    pub async fn request(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        self.buffer.clear();
+        self.stream.clear(tokio_serial::ClearBuffer::Input)?;
+        self.write(data).await?;
+        self.read_frame().await
+    }
+
+    /// 转为帧流，串口关闭时结束；超时和超长帧作为错误项交给调用方处理
+    pub fn frames(self) -> BoxStream<'static, io::Result<Vec<u8>>> {
+        stream::unfold(self, |mut uart| async move {
+            match uart.read_frame().await {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+                result => Some((result, uart)),
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(data_bits(8), Some(DataBits::Eight));
+        assert_eq!(data_bits(9), None);
+        assert_eq!(parity("Even"), Some(Parity::Even));
+        assert_eq!(parity("n"), Some(Parity::None));
+        assert_eq!(parity("mark"), None);
+        assert_eq!(stop_bits(2), Some(StopBits::Two));
+        assert_eq!(stop_bits(0), None);
+        assert_eq!(flow_control("RTSCTS"), Some(FlowControl::Hardware));
+        assert_eq!(flow_control("dtr"), None);
+    }
+
+    #[test]
+    fn test_split_delimiter() {
+        let framing = Framing::Delimiter(b"\r\n".to_vec());
+        let mut buffer = b"12.5\r\n13.0\r\n14".to_vec();
+        assert_eq!(framing.split(&mut buffer), Some(b"12.5".to_vec()));
+        assert_eq!(framing.split(&mut buffer), Some(b"13.0".to_vec()));
+        assert_eq!(framing.split(&mut buffer), None);
+        assert_eq!(buffer, b"14".to_vec());
+    }
+
+    #[test]
+    fn test_split_fixed_length() {
+        let framing = Framing::FixedLength(3);
+        let mut buffer = vec![1, 2, 3, 4, 5];
+        assert_eq!(framing.split(&mut buffer), Some(vec![1, 2, 3]));
+        assert_eq!(framing.split(&mut buffer), None);
+        assert_eq!(buffer, vec![4, 5]);
+    }
+}
@@ -0,0 +1,287 @@
+//! 用户、API Key、组织、配置包和系统状态接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{
+    build_test_app, build_test_app_with, build_test_app_with_admin, delete, get, issue_key, post,
+    put, send,
+};
+use guolu::config::settings::Settings;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_user_crud() {
+    let app = build_test_app().await;
+
+    let (status, user) = post(
+        &app,
+        "/users",
+        json!({
+            "name": "值班员",
+            "email": "duty@example.com",
+            "password": "secret",
+            "permission": "operator"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", user);
+    let id = user["id"].as_i64().unwrap();
+
+    let (status, user) =
+        put(&app, &format!("/users/{}", id), json!({ "permission": "admin" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["permission"], "admin");
+    assert_eq!(user["name"], "值班员");
+
+    let (status, users) = get(&app, "/users").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users.as_array().unwrap().len(), 1);
+
+    let (status, _) = delete(&app, &format!("/users/{}", id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &format!("/users/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = put(&app, &format!("/users/{}", id), json!({ "name": "x" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = delete(&app, &format!("/users/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_api_key_lifecycle() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.api_keys.require_for_ingest = true).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let body = json!({
+        "metric_type": "level",
+        "timestamp": "2026-06-01T08:00:00Z",
+        "value": 2.4,
+        "device_id": null,
+        "unit": null
+    });
+
+    let request = json!({ "name": "网关", "scopes": ["ingest"] });
+    let (status, issued) = send(&app, Method::POST, "/api-keys", Some(request), &admin).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", issued);
+    let old_key = issued["key"].as_str().unwrap().to_string();
+    assert!(old_key.starts_with("gk_"));
+    let id = issued["api_key"]["id"].as_i64().unwrap();
+
+    // 轮换后旧 Key 立即失效
+    let uri = format!("/api-keys/{}/rotate", id);
+    let (status, rotated) = send(&app, Method::POST, &uri, Some(json!({})), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", rotated);
+    let new_key = rotated["key"].as_str().unwrap().to_string();
+    assert_ne!(new_key, old_key);
+    let headers = [("x-api-key", old_key.as_str())];
+    let (status, _) =
+        send(&app, Method::POST, "/measurements", Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let headers = [("x-api-key", new_key.as_str())];
+    let (status, _) =
+        send(&app, Method::POST, "/measurements", Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // 吊销后不能再上报
+    let uri = format!("/api-keys/{}", id);
+    let (status, _) = send(&app, Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, Method::POST, "/measurements", Some(body), &headers).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_key_errors() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let create = |scopes: serde_json::Value| json!({ "name": "x", "scopes": scopes });
+
+    let (status, _) =
+        send(&app, Method::POST, "/api-keys", Some(create(json!(["root"]))), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, Method::POST, "/api-keys", Some(create(json!([]))), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, Method::DELETE, "/api-keys/9999", None, &admin).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) =
+        send(&app, Method::POST, "/api-keys/9999/rotate", Some(json!({})), &admin).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_api_key_requires_admin_caller() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    // 不带 Key 不能签发
+    let (status, _) = post(&app, "/api-keys", json!({ "name": "x", "scopes": ["read"] })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let request = json!({ "name": "只读", "scopes": ["read", "ingest"] });
+    let (status, issued) = send(&app, Method::POST, "/api-keys", Some(request), &admin).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", issued);
+    let reader = issued["key"].as_str().unwrap().to_string();
+    let reader = [("x-api-key", reader.as_str())];
+
+    // 没有 admin 权限的 Key 不能签发，也不能借轮换取得 admin Key 的明文
+    let request = json!({ "name": "x", "scopes": ["read"] });
+    let (status, _) = send(&app, Method::POST, "/api-keys", Some(request), &reader).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, Method::POST, "/api-keys/1/rotate", None, &reader).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // admin Key 也不能签发超出自身权限的 Key
+    let request = json!({ "name": "管理", "scopes": ["admin"] });
+    let (status, issued) = send(&app, Method::POST, "/api-keys", Some(request), &admin).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let limited = issued["key"].as_str().unwrap().to_string();
+    let limited = [("x-api-key", limited.as_str())];
+    let request = json!({ "name": "x", "scopes": ["admin", "ingest"] });
+    let (status, _) = send(&app, Method::POST, "/api-keys", Some(request), &limited).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, Method::POST, "/api-keys/1/rotate", None, &limited).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_rate_limit_ignores_unknown_keys() {
+    let (app, admin) = build_test_app_with_admin(|settings| {
+        settings.rate_limit.enabled = true;
+        settings.rate_limit.burst = 3;
+        settings.rate_limit.per_second = 0.001;
+    })
+    .await;
+    // admin Key 首次出现时先扣 IP 的一个令牌
+    let reader = issue_key(&app, &admin, &["read"], None).await;
+
+    // 每次换一个无效 Key 也按同一客户端计数
+    for (i, expected) in [StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]
+        .into_iter()
+        .enumerate()
+    {
+        let key = format!("gk_forged_{}", i);
+        let headers = [("x-api-key", key.as_str())];
+        let (status, _) = send(&app, Method::GET, "/devices", None, &headers).await;
+        assert_eq!(status, expected);
+    }
+
+    // 未见过的 Key 先扣 IP 的令牌，IP 超限后不再查库，有效 Key 也被拒绝
+    let headers = [("x-api-key", reader.as_str())];
+    let (status, _) = send(&app, Method::GET, "/devices", None, &headers).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // 已校验过的有效 Key 单独计数
+    let headers = [("x-api-key", admin.as_str())];
+    let (status, _) = send(&app, Method::GET, "/devices", None, &headers).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_organization() {
+    let app = build_test_app().await;
+
+    let (status, organization) =
+        post(&app, "/organizations", json!({ "name": "城投水务", "code": "CTSW" })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", organization);
+    let id = organization["id"].as_i64().unwrap();
+
+    let (status, organization) = get(&app, &format!("/organizations/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(organization["code"], "CTSW");
+
+    let (status, _) = post(&app, "/organizations", json!({ "name": " ", "code": "X" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/organizations/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_tenancy_requires_api_key() {
+    let app = build_test_app_with(|settings| settings.tenancy.enabled = true).await;
+
+    let (status, _) = get(&app, "/organizations").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let headers = [("x-api-key", "gk_unknown")];
+    let (status, _) = send(&app, Method::GET, "/devices", None, &headers).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_system_status() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    let (status, mode) = get(&app, "/system/read-only").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mode["active"], false);
+
+    // 切换只读、故障注入和清空统计都要求 admin Key
+    let (status, _) = put(&app, "/system/read-only", json!({ "enabled": true })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = put(&app, "/system/fault-injection", json!({ "clear": true })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = delete(&app, "/system/queries").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let body = |enabled: bool| Some(json!({ "enabled": enabled }));
+    let (status, mode) = send(&app, Method::PUT, "/system/read-only", body(true), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", mode);
+    assert_eq!(mode["manual"], true);
+    // 只读期间仍可关闭只读模式
+    let (status, mode) = send(&app, Method::PUT, "/system/read-only", body(false), &admin).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mode["active"], false);
+
+    // 测试为调试构建，故障注入接口可用
+    let (status, faults) = get(&app, "/system/fault-injection").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(faults["mqtt_drop_percent"].is_number());
+    let (status, _) = send(&app, Method::DELETE, "/system/queries", None, &admin).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+fn with_signing_key(settings: &mut Settings) {
+    settings.config_bundle.signing_key = "secret".to_string();
+}
+
+#[tokio::test]
+async fn test_config_bundle_import_requires_review() {
+    let staging = build_test_app_with(with_signing_key).await;
+    let (status, _) = post(
+        &staging,
+        "/alarm-rules",
+        json!({ "name": "出水 pH 偏高", "condition": ">", "parameter": "ph", "value": 9.0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, bundle) = get(&staging, "/config-bundle/export").await;
+    assert_eq!(status, StatusCode::OK, "{}", bundle);
+    assert_eq!(bundle["payload"]["alarm_rules"].as_array().unwrap().len(), 1);
+    assert!(bundle["payload"]["modbus_mappings"].is_array());
+
+    let production = build_test_app_with(|settings| {
+        with_signing_key(settings);
+        settings.change_control.require_review = true;
+    })
+    .await;
+    let headers = [("x-operator", "zhang")];
+    let (status, summary) =
+        send(&production, Method::POST, "/config-bundle/import", Some(bundle), &headers).await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["alarm_rules"]["pending"], 1);
+    assert_eq!(summary["alarm_rules"]["created"], 0);
+
+    // 审批通过前规则不生效
+    let (_, rules) = get(&production, "/alarm-rules").await;
+    assert!(rules.as_array().unwrap().is_empty());
+    let (_, pending) = get(&production, "/config-revisions?status=pending").await;
+    let id = pending[0]["id"].as_i64().unwrap();
+
+    let uri = format!("/config-revisions/{}/approve", id);
+    let headers = [("x-operator", "li")];
+    let (status, _) = send(&production, Method::POST, &uri, None, &headers).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, rules) = get(&production, "/alarm-rules").await;
+    assert_eq!(rules[0]["name"], "出水 pH 偏高");
+}
@@ -0,0 +1,185 @@
+//! 报警规则、报警记录、自动化规则和模拟注入接口
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{build_test_app, create_device, delete, get, post, put};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_alarm_rule_crud() {
+    let app = build_test_app().await;
+
+    let (status, rule) = post(
+        &app,
+        "/alarm-rules",
+        json!({ "name": "出水 pH 偏高", "condition": ">", "parameter": "ph", "value": 9.0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", rule);
+    let id = rule["id"].as_i64().unwrap();
+
+    let (status, rule) = put(&app, &format!("/alarm-rules/{}", id), json!({ "value": 8.5 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rule["value"], 8.5);
+
+    // 按模板创建，未给出的项使用模板默认值
+    let (status, rule) = post(&app, "/alarm-rules/templates/high_level", json!({})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(rule["parameter"], "level");
+    assert_eq!(rule["condition"], ">");
+
+    let (status, rules) = get(&app, "/alarm-rules").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rules.as_array().unwrap().len(), 2);
+
+    let (status, _) = delete(&app, &format!("/alarm-rules/{}", id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &format!("/alarm-rules/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_alarm_rule_errors() {
+    let app = build_test_app().await;
+
+    let (status, _) = get(&app, "/alarm-rules/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = put(&app, "/alarm-rules/9999", json!({ "value": 1.0 })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = post(&app, "/alarm-rules/templates/no_such_template", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = post(&app, "/alarm-rules", json!({ "name": "缺少条件" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_alarm_log() {
+    let app = build_test_app().await;
+
+    let (status, log) = post(
+        &app,
+        "/alarm-logs",
+        json!({ "rule_name": "高液位", "trigger_value": 3.2, "is_processed": false }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", log);
+    assert_eq!(log["severity"], "warning");
+    let id = log["id"].as_i64().unwrap();
+
+    let (status, log) = get(&app, &format!("/alarm-logs/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(log["rule_name"], "高液位");
+
+    let (status, _) = post(
+        &app,
+        "/alarm-logs",
+        json!({
+            "rule_name": "x",
+            "trigger_value": 1.0,
+            "is_processed": false,
+            "severity": "fatal"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/alarm-logs/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_automation_rule() {
+    let app = build_test_app().await;
+
+    let rule = |window: &str| {
+        json!({ "action": "aerate", "level": 1, "trigger_time_range": window, "sync_alarm": false })
+    };
+    let (status, day) = post(&app, "/automation-rules", rule("08:00-18:00")).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", day);
+    let (status, night) = post(&app, "/automation-rules", rule("22:00-06:00")).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // 未关联厂站的规则按默认时区（UTC）判断
+    let (status, active) = get(&app, "/automation-rules/active?at=2026-06-01T23:30:00Z").await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<_> = active.as_array().unwrap().iter().map(|r| r["id"].clone()).collect();
+    assert_eq!(ids, vec![night["id"].clone()]);
+
+    let (status, _) = post(&app, "/automation-rules", rule("8点到18点")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut with_site = rule("");
+    with_site["site_id"] = json!(9999);
+    let (status, _) = post(&app, "/automation-rules", with_site).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/automation-rules/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_simulation_inject() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "出水 pH 计").await;
+    let (status, _) = post(
+        &app,
+        "/alarm-rules",
+        json!({ "name": "出水 pH 偏高", "condition": ">", "parameter": "ph", "value": 8.5 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, report) = post(
+        &app,
+        &format!("/simulation/devices/{}/inject", device_id),
+        json!({
+            "metric_type": "ph",
+            "start": "2026-06-01T00:00:00Z",
+            "interval_secs": 60,
+            "raise_alarms": true,
+            "steps": [
+                { "type": "ramp", "from": 7.0, "to": 9.0, "samples": 5 },
+                { "type": "fault", "fault": "dropout", "samples": 1 },
+                { "type": "fault", "fault": "out_of_range", "samples": 1 }
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(report["accepted"], 5);
+    assert_eq!(report["rejected"], 1);
+    // 8.5 不触发，9.0 触发
+    assert_eq!(report["alarms_raised"], 1);
+    let samples = report["samples"].as_array().unwrap();
+    assert_eq!(samples[4]["alarms"], json!(["出水 pH 偏高"]));
+    assert_eq!(samples[5]["outcome"], "dropout");
+    assert_eq!(samples[6]["outcome"], "rejected");
+    assert_eq!(samples[6]["timestamp"], "2026-06-01T00:06:00Z");
+
+    let (_, logs) = get(&app, "/alarm-logs").await;
+    assert_eq!(logs.as_array().unwrap().len(), 1);
+    let (_, measurements) = get(&app, &format!("/measurements?device_id={}", device_id)).await;
+    assert_eq!(measurements.as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_simulation_errors() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "出水 pH 计").await;
+    let ramp = json!([{ "type": "ramp", "from": 7.0, "to": 9.0, "samples": 3 }]);
+
+    let (status, _) = post(
+        &app,
+        "/simulation/devices/9999/inject",
+        json!({ "metric_type": "ph", "steps": ramp }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uri = format!("/simulation/devices/{}/inject", device_id);
+    let (status, _) = post(&app, &uri, json!({ "metric_type": "salinity", "steps": ramp })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let stuck = json!([{ "type": "fault", "fault": "stuck", "samples": 1 }]);
+    let (status, _) = post(&app, &uri, json!({ "metric_type": "ph", "steps": stuck })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, &uri, json!({ "metric_type": "ph", "steps": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
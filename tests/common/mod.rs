@@ -0,0 +1,255 @@
+//! 集成测试公用的测试应用
+//!
+//! [`build_test_app`] 用内存 SQLite 建库并执行迁移，返回与服务进程相同的 API 路由，测试用
+//! `tower::ServiceExt::oneshot` 直接调用，不监听端口，也不连接 MQTT、RabbitMQ。每次调用都是一个
+//! 独立的空库，测试之间互不影响。
+
+#![allow(dead_code)]
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use guolu::app_state::AppState;
+use guolu::config::database::DatabaseConfig;
+use guolu::config::settings::Settings;
+use guolu::control::ControlRuntime;
+use guolu::database::migration;
+use guolu::database::sea_orm_db::DbManager;
+use guolu::message_queue::bus;
+use guolu::message_queue::rabbitmq::RabbitMQManager;
+use guolu::routes::api::create_api_router;
+use guolu::services::api_key::{self as api_key_service, ALL_SCOPES};
+use guolu::services::api_usage::UsageRecorder;
+use guolu::services::cache::HotCache;
+use guolu::services::compression::Compressor;
+use guolu::services::device_command::CommandTracker;
+use guolu::services::gpio_output::GpioOutputs;
+use guolu::services::network::NetworkMonitor;
+use guolu::services::pump_group::PumpGroupRuntime;
+use guolu::services::pwm::PwmManager;
+use guolu::services::read_only::ReadOnlyMode;
+use guolu::services::remote_access::RemoteAccessManager;
+use guolu::services::safe_state::SafeStateMonitor;
+use guolu::services::serial_console::SerialConsoleManager;
+use guolu::services::timeseries;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use tower::ServiceExt;
+
+static NEXT_APP: AtomicU32 = AtomicU32::new(0);
+
+/// 默认配置的测试应用，关闭限流和上报认证
+pub async fn build_test_app() -> Router {
+    build_test_app_with(|_| {}).await
+}
+
+/// 在测试默认配置上再做调整，例如开启上报认证或多租户
+pub async fn build_test_app_with(configure: impl FnOnce(&mut Settings)) -> Router {
+    let state = build_test_state(configure).await;
+    Router::new().merge(create_api_router(&state)).with_state(state)
+}
+
+/// 同 [`build_test_app_with`]，另外直接在库中签发一个拥有全部权限的平台管理 Key，返回其明文
+pub async fn build_test_app_with_admin(
+    configure: impl FnOnce(&mut Settings),
+) -> (Router, String) {
+    let state = build_test_state(configure).await;
+    let key = issue_admin_key(&state).await;
+    (Router::new().merge(create_api_router(&state)).with_state(state), key)
+}
+
+/// 同 [`build_test_app_with_admin`]，另外返回应用状态，用于直接调用服务准备 API 无法构造的数据
+pub async fn build_test_app_with_state(
+    configure: impl FnOnce(&mut Settings),
+) -> (Router, Arc<AppState>, String) {
+    let state = build_test_state(configure).await;
+    let key = issue_admin_key(&state).await;
+    (Router::new().merge(create_api_router(&state)).with_state(state.clone()), state, key)
+}
+
+async fn issue_admin_key(state: &AppState) -> String {
+    let scopes: Vec<String> = ALL_SCOPES.iter().map(|s| s.to_string()).collect();
+    let conn = state.db.get_connection();
+    let (_, key) = api_key_service::issue(conn, "测试".into(), &scopes, None)
+        .await
+        .expect("签发平台管理 Key 失败");
+    key
+}
+
+async fn build_test_state(configure: impl FnOnce(&mut Settings)) -> Arc<AppState> {
+    let mut settings = Settings::default();
+    // 内存库只在连接存活期间存在，限定为一个常驻连接
+    settings.database = DatabaseConfig {
+        url: "sqlite://:memory:".to_string(),
+        max_connections: 1,
+        min_connections: 1,
+        ..DatabaseConfig::default()
+    };
+    settings.rate_limit.enabled = false;
+    settings.api_keys.require_for_ingest = false;
+    settings.read_only.auto_on_low_disk = false;
+    let n = NEXT_APP.fetch_add(1, Ordering::Relaxed);
+    let buffer_path =
+        std::env::temp_dir().join(format!("guolu-test-{}-{}.redb", std::process::id(), n));
+    settings.read_only.buffer_path = buffer_path.to_string_lossy().into_owned();
+    configure(&mut settings);
+
+    let db = DbManager::from_config(&settings.database).await.expect("连接内存 SQLite 失败");
+    migration::run_migrations(db.get_connection()).await.expect("执行迁移失败");
+
+    let read_only = ReadOnlyMode::new(
+        settings.read_only.clone(),
+        Compressor::new(settings.compression.clone()),
+        settings.quality.clone(),
+    );
+    // 暂存文件打开后即删除，测试结束不留文件
+    let _ = std::fs::remove_file(&buffer_path);
+
+    let rabbitmq = RabbitMQManager::new(&settings.rabbitmq.uri);
+    let (events, _) =
+        bus::select(&settings.event_bus, &rabbitmq, false, &settings.rabbitmq.exchange);
+
+    let settings = Arc::new(settings);
+    Arc::new(AppState {
+        users: Arc::new(RwLock::new(Vec::new())),
+        db,
+        cache: HotCache::disabled(),
+        timeseries: timeseries::select(&settings.timeseries, false),
+        mqtt: None,
+        rabbitmq: None,
+        events,
+        commands: CommandTracker::new(settings.mqtt.command_timeout_secs),
+        remote_access: RemoteAccessManager::new(settings.remote_access.clone()),
+        serial_console: SerialConsoleManager::new(settings.serial_console.clone()),
+        pwm: PwmManager::new(&settings.pwm),
+        gpio_outputs: GpioOutputs::new(&settings.gpio),
+        safe_state: SafeStateMonitor::new(&settings.safe_state.actuators),
+        control: ControlRuntime::new(),
+        pump_groups: PumpGroupRuntime::new(),
+        network: NetworkMonitor::new(&settings.network_monitor),
+        read_only,
+        api_usage: UsageRecorder::new(),
+        settings: settings.clone(),
+    })
+}
+
+/// 发送请求，返回状态码和 JSON 响应体（无响应体时为 `null`）
+pub async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+    headers: &[(&str, &str)],
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = match body {
+        Some(body) => request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+            Value::String(String::from_utf8_lossy(&bytes).into_owned())
+        })
+    };
+    (status, body)
+}
+
+pub async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    send(app, Method::GET, uri, None, &[]).await
+}
+
+pub async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    send(app, Method::POST, uri, Some(body), &[]).await
+}
+
+pub async fn put(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    send(app, Method::PUT, uri, Some(body), &[]).await
+}
+
+pub async fn delete(app: &Router, uri: &str) -> (StatusCode, Value) {
+    send(app, Method::DELETE, uri, None, &[]).await
+}
+
+/// 创建设备的请求体
+pub fn device_body(name: &str) -> Value {
+    json!({
+        "name": name,
+        "location": "一号泵房",
+        "status": 1,
+        "device_type": "pH 计",
+        "manufacturer": "guolu",
+        "model": "PH-100",
+        "installation_date": "2026-01-01T00:00:00Z",
+        "last_maintenance": "2026-06-01T00:00:00Z",
+        "operational_hours": 0.0,
+        "temperature": 20.0,
+        "pressure": 0.0,
+        "flow_rate": 0.0,
+        "power_consumption": 0.0
+    })
+}
+
+/// 创建设备，返回设备 ID
+pub async fn create_device(app: &Router, name: &str) -> i64 {
+    let (status, body) = post(app, "/devices", device_body(name)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["id"].as_i64().unwrap()
+}
+
+/// 创建厂站，返回厂站 ID
+pub async fn create_site(app: &Router, code: &str) -> i64 {
+    let (status, body) = post(app, "/sites", json!({ "name": code, "code": code })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["id"].as_i64().unwrap()
+}
+
+/// 用平台管理 Key 创建组织，返回组织 ID
+pub async fn create_organization(app: &Router, admin: &str, code: &str) -> i64 {
+    let headers = [("x-api-key", admin)];
+    let body = json!({ "name": code, "code": code });
+    let (status, body) = send(app, Method::POST, "/organizations", Some(body), &headers).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["id"].as_i64().unwrap()
+}
+
+/// 用平台管理 Key 创建属于指定组织的设备，返回设备 ID
+pub async fn create_organization_device(
+    app: &Router,
+    admin: &str,
+    name: &str,
+    organization_id: i64,
+) -> i64 {
+    let headers = [("x-api-key", admin)];
+    let mut body = device_body(name);
+    body["organization_id"] = json!(organization_id);
+    let (status, body) = send(app, Method::POST, "/devices", Some(body), &headers).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["id"].as_i64().unwrap()
+}
+
+/// 用 `admin` 签发指定权限的 Key，返回其明文
+pub async fn issue_key(
+    app: &Router,
+    admin: &str,
+    scopes: &[&str],
+    organization_id: Option<i64>,
+) -> String {
+    let headers = [("x-api-key", admin)];
+    let body = json!({ "name": "测试", "scopes": scopes, "organization_id": organization_id });
+    let (status, body) = send(app, Method::POST, "/api-keys", Some(body), &headers).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    body["key"].as_str().unwrap().to_string()
+}
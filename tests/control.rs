@@ -0,0 +1,489 @@
+//! Modbus 写入、PWM、控制回路和加药接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::Utc;
+use common::{
+    build_test_app_with_admin, create_device, create_organization, create_organization_device,
+    get, issue_key, post, put, send,
+};
+use guolu::config::settings::Settings;
+use guolu::utils::modbus_loopback::{LoopbackBus, LoopbackSlave};
+use serde_json::json;
+use std::collections::HashSet;
+
+/// 挂在回环总线上的水泵
+const PUMP: i64 = 1;
+/// 提供联锁液位的集水井液位计
+const WELL: i64 = 2;
+
+/// 水泵的保持寄存器 10 为频率设定（0–50 Hz，0.1 Hz/位），线圈 1 为启停，启泵受集水井液位联锁
+fn with_modbus(settings: &mut Settings, bus: &str) {
+    settings.modbus = serde_json::from_value(json!({
+        "enabled": true,
+        "timeout_ms": 200,
+        "devices": [{
+            "device_id": PUMP,
+            "transport": "loopback",
+            "bus": bus,
+            "unit_id": 1,
+            "writable": [
+                {
+                    "kind": "holding",
+                    "address": 10,
+                    "label": "1#泵频率",
+                    "min": 0.0,
+                    "max": 50.0,
+                    "scale": 0.1
+                },
+                {
+                    "kind": "coil",
+                    "address": 1,
+                    "label": "1#泵启停",
+                    "interlocks": [{
+                        "device_id": WELL,
+                        "metric_type": "level",
+                        "min": 0.5,
+                        "when_value": 1.0,
+                        "description": "集水井液位过低禁止启泵"
+                    }]
+                }
+            ]
+        }]
+    }))
+    .unwrap();
+}
+
+/// 在总线上挂水泵的模拟从站
+fn attach_pump(bus: &str) -> LoopbackSlave {
+    let slave = LoopbackSlave::new(HashSet::from([10]));
+    LoopbackBus::register(bus).attach(1, slave.clone());
+    slave
+}
+
+#[tokio::test]
+async fn test_modbus_write_safety_checks() {
+    let bus = "test-modbus-write";
+    let slave = attach_pump(bus);
+    let (app, admin_key) = build_test_app_with_admin(|settings| with_modbus(settings, bus)).await;
+    let admin = [("x-api-key", admin_key.as_str())];
+    assert_eq!(create_device(&app, "1#提升泵").await, PUMP);
+    assert_eq!(create_device(&app, "集水井液位计").await, WELL);
+    let uri = format!("/devices/{}/modbus/writes", PUMP);
+    let holding = |value: f64| json!({ "address": 10, "value": value });
+    let coil = |value: f64| json!({ "kind": "coil", "address": 1, "value": value });
+
+    // 不带 Key、Key 没有 control 权限都不能写
+    let (status, _) = post(&app, &uri, holding(30.0)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let reader = issue_key(&app, &admin_key, &["read"], None).await;
+    let reader = [("x-api-key", reader.as_str())];
+    let (status, _) = send(&app, Method::POST, &uri, Some(holding(30.0)), &reader).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 超出范围的值截断后写入
+    let (status, record) = send(&app, Method::POST, &uri, Some(holding(75.0)), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", record);
+    assert_eq!(record["status"], "written");
+    assert_eq!(record["written_value"], 50.0);
+    assert_eq!(record["message"], "已截断到 50");
+    assert_eq!(slave.registers().holding(10, 1), Some(vec![500]));
+
+    // 未列入白名单的寄存器拒绝并留下记录，未知的寄存器类型直接拒绝
+    let (status, _) =
+        send(&app, Method::POST, &uri, Some(json!({ "address": 11, "value": 1.0 })), &admin).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let input = json!({ "kind": "input", "address": 10, "value": 1.0 });
+    let (status, _) = send(&app, Method::POST, &uri, Some(input), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 液位没有最新值时不能启泵，停泵不受联锁限制
+    let (status, _) = send(&app, Method::POST, &uri, Some(coil(1.0)), &admin).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = send(&app, Method::POST, &uri, Some(coil(0.0)), &admin).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(slave.coil(1), Some(false));
+
+    // 越过联锁须有 override 权限并填写原因
+    let mut forced = coil(1.0);
+    forced["override_interlocks"] = json!(true);
+    let (status, _) = send(&app, Method::POST, &uri, Some(forced.clone()), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    forced["reason"] = json!("液位计检修，现场确认水位");
+    let operator = issue_key(&app, &admin_key, &["control"], None).await;
+    let operator = [("x-api-key", operator.as_str())];
+    let (status, _) = send(&app, Method::POST, &uri, Some(forced.clone()), &operator).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, record) = send(&app, Method::POST, &uri, Some(forced), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", record);
+    assert_eq!(record["override_interlocks"], true);
+    assert_eq!(slave.coil(1), Some(true));
+
+    // 液位足够后 control 权限即可启泵
+    let level = json!({
+        "metric_type": "level",
+        "timestamp": Utc::now().to_rfc3339(),
+        "value": 1.2,
+        "device_id": WELL,
+        "unit": null
+    });
+    let (status, _) = post(&app, "/measurements", level).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, record) = send(&app, Method::POST, &uri, Some(coil(1.0)), &operator).await;
+    assert_eq!(status, StatusCode::OK, "{}", record);
+
+    // 每次经过安全检查的写入都有记录，操作人取自调用方 Key
+    let (status, writes) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let writes = writes.as_array().unwrap();
+    assert_eq!(writes.len(), 6);
+    assert_eq!(writes.iter().filter(|w| w["status"] == "blocked").count(), 2);
+    assert!(writes[0]["operator"].as_str().unwrap().starts_with("api-key:"));
+
+    LoopbackBus::unregister(bus);
+}
+
+#[tokio::test]
+async fn test_modbus_write_unavailable() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let body = json!({ "address": 10, "value": 30.0 });
+
+    // 未启用 Modbus
+    let (status, _) =
+        send(&app, Method::POST, "/devices/1/modbus/writes", Some(body.clone()), &admin).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // 从站不应答时记录为失败
+    let bus = "test-modbus-offline";
+    attach_pump(bus).set_offline(true);
+    let (app, admin) = build_test_app_with_admin(|settings| with_modbus(settings, bus)).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let uri = format!("/devices/{}/modbus/writes", PUMP);
+    let (status, _) = send(&app, Method::POST, &uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let (_, writes) = get(&app, &uri).await;
+    assert_eq!(writes[0]["status"], "failed");
+
+    LoopbackBus::unregister(bus);
+}
+
+#[tokio::test]
+async fn test_modbus_write_tenancy() {
+    let bus = "test-modbus-tenancy";
+    let slave = attach_pump(bus);
+    let (app, admin) = build_test_app_with_admin(|settings| {
+        with_modbus(settings, bus);
+        settings.tenancy.enabled = true;
+    })
+    .await;
+    let owner = create_organization(&app, &admin, "CTSW").await;
+    let other = create_organization(&app, &admin, "HXSW").await;
+    assert_eq!(create_organization_device(&app, &admin, "1#提升泵", owner).await, PUMP);
+    let owner = issue_key(&app, &admin, &["read", "control"], Some(owner)).await;
+    let other = issue_key(&app, &admin, &["read", "control"], Some(other)).await;
+    let uri = format!("/devices/{}/modbus/writes", PUMP);
+    let body = json!({ "address": 10, "value": 20.0 });
+
+    // 其他组织的设备按不存在处理
+    let headers = [("x-api-key", other.as_str())];
+    let (status, _) = send(&app, Method::POST, &uri, Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::GET, &uri, None, &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(slave.registers().holding(10, 1), None);
+
+    let headers = [("x-api-key", owner.as_str())];
+    let (status, record) = send(&app, Method::POST, &uri, Some(body), &headers).await;
+    assert_eq!(status, StatusCode::OK, "{}", record);
+    assert_eq!(slave.registers().holding(10, 1), Some(vec![200]));
+
+    LoopbackBus::unregister(bus);
+}
+
+#[tokio::test]
+async fn test_pwm_limits() {
+    let (app, admin) = build_test_app_with_admin(|settings| {
+        settings.pwm = serde_json::from_value(json!({
+            "enabled": true,
+            "channels": [{ "device_id": 1, "channel": 0, "min_duty": 10.0, "max_duty": 90.0 }]
+        }))
+        .unwrap();
+    })
+    .await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    let (status, pwm) = get(&app, "/devices/1/pwm").await;
+    assert_eq!(status, StatusCode::OK, "{}", pwm);
+    assert_eq!(pwm["duty_cycle"], 0.0);
+    let (status, _) = get(&app, "/devices/2/pwm").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 超出安全范围的占空比在驱动硬件前拒绝
+    let (status, _) = post(&app, "/devices/1/pwm", json!({ "duty_cycle": 50.0 })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let body = json!({ "duty_cycle": 95.0 });
+    let (status, _) = send(&app, Method::POST, "/devices/1/pwm", Some(body), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body = json!({ "duty_cycle": 50.0 });
+    let (status, _) = send(&app, Method::POST, "/devices/2/pwm", Some(body), &admin).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_gpio_output_requires_control_key() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let uri = "/gpio/outputs/aerator";
+    let body = json!({ "value": true });
+
+    let (status, _) = post(&app, uri, body.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let reader = issue_key(&app, &admin, &["read"], None).await;
+    let headers = [("x-api-key", reader.as_str())];
+    let (status, _) = send(&app, Method::POST, uri, Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let headers = [("x-api-key", admin.as_str())];
+    let (status, _) = send(&app, Method::POST, uri, Some(body), &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_control_loop_manual_output() {
+    let bus = "test-control-loop";
+    let slave = attach_pump(bus);
+    let (app, admin) = build_test_app_with_admin(|settings| with_modbus(settings, bus)).await;
+    let admin = [("x-api-key", admin.as_str())];
+    assert_eq!(create_device(&app, "1#提升泵").await, PUMP);
+    assert_eq!(create_device(&app, "集水井液位计").await, WELL);
+
+    let (status, control_loop) = post(
+        &app,
+        "/control-loops",
+        json!({
+            "name": "集水井液位",
+            "device_id": WELL,
+            "input_metric": "level",
+            "output_kind": "modbus",
+            "output_device_id": PUMP,
+            "output_address": 10,
+            "action": "reverse",
+            "setpoint": 2.0,
+            "kp": 5.0,
+            "output_max": 50.0
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", control_loop);
+    assert_eq!(control_loop["mode"], "manual");
+    let id = control_loop["id"].as_i64().unwrap();
+
+    // 手动输出和整定都要求 control 权限
+    let mode_uri = format!("/control-loops/{}/mode", id);
+    let tuning_uri = format!("/control-loops/{}/tuning", id);
+    let body = json!({ "mode": "manual", "output": 30.0 });
+    let (status, _) = put(&app, &mode_uri, body.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = put(&app, &tuning_uri, json!({ "kp": 8.0 })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(slave.registers().holding(10, 1), None);
+
+    // 手动输出经过 Modbus 写入检查下发，操作人记录为 Key
+    let (status, control_loop) = send(&app, Method::PUT, &mode_uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", control_loop);
+    assert_eq!(control_loop["output"], 30.0);
+    assert_eq!(slave.registers().holding(10, 1), Some(vec![300]));
+    let (_, writes) = get(&app, &format!("/devices/{}/modbus/writes", PUMP)).await;
+    assert!(writes[0]["operator"].as_str().unwrap().starts_with("api-key:"));
+
+    let body = json!({ "kp": 8.0 });
+    let (status, control_loop) =
+        send(&app, Method::PUT, &tuning_uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", control_loop);
+    assert_eq!(control_loop["kp"], 8.0);
+    assert_eq!(control_loop["setpoint"], 2.0);
+
+    let body = json!({ "mode": "auto" });
+    let (status, control_loop) = send(&app, Method::PUT, &mode_uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(control_loop["mode"], "auto");
+
+    LoopbackBus::unregister(bus);
+}
+
+#[tokio::test]
+async fn test_control_loop_errors() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let body = |output_kind: &str| {
+        json!({
+            "name": "出水 pH",
+            "device_id": 1,
+            "input_metric": "ph",
+            "output_kind": output_kind,
+            "output_device_id": 2,
+            "setpoint": 7.0,
+            "kp": 10.0
+        })
+    };
+
+    // Modbus 输出须指定寄存器，输出方式和限幅须有效
+    let (status, _) = post(&app, "/control-loops", body("modbus")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, "/control-loops", body("analog")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut inverted = body("pwm");
+    inverted["output_min"] = json!(80.0);
+    inverted["output_max"] = json!(20.0);
+    let (status, _) = post(&app, "/control-loops", inverted).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, control_loop) = post(&app, "/control-loops", body("pwm")).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", control_loop);
+    let id = control_loop["id"].as_i64().unwrap();
+    let uri = format!("/control-loops/{}/mode", id);
+    for body in [
+        json!({ "mode": "cruise" }),
+        json!({ "mode": "auto", "output": 50.0 }),
+        json!({ "mode": "manual", "output": 150.0 }),
+    ] {
+        let (status, _) = send(&app, Method::PUT, &uri, Some(body), &admin).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let uri = format!("/control-loops/{}/tuning", id);
+    let body = json!({ "kp": -1.0 });
+    let (status, _) = send(&app, Method::PUT, &uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = get(&app, "/control-loops/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body = json!({ "mode": "manual" });
+    let uri = "/control-loops/9999/mode";
+    let (status, _) = send(&app, Method::PUT, uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_dosing() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    let (status, tank) = post(
+        &app,
+        "/chemical-tanks",
+        json!({
+            "name": "1#酸罐",
+            "chemical": "硫酸",
+            "capacity_l": 1000.0,
+            "level_l": 200.0,
+            "low_level_l": 100.0
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", tank);
+    let tank_id = tank["id"].as_i64().unwrap();
+
+    // 补药后存量不超过容积
+    let uri = format!("/chemical-tanks/{}/refill", tank_id);
+    let (status, tank) = post(&app, &uri, json!({ "volume_l": 900.0 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", tank);
+    assert_eq!(tank["level_l"], 1000.0);
+
+    let (status, pump) = post(
+        &app,
+        "/dosing-pumps",
+        json!({ "name": "1#加酸泵", "tank_id": tank_id, "max_rate_lph": 20.0, "control": "manual" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", pump);
+    let uri = format!("/dosing-pumps/{}/speed", pump["id"].as_i64().unwrap());
+    let speed = |speed_percent: f64| Some(json!({ "speed_percent": speed_percent }));
+    let (status, _) = post(&app, &uri, json!({ "speed_percent": 40.0 })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, pump) = send(&app, Method::POST, &uri, speed(40.0), &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", pump);
+    assert_eq!(pump["speed_percent"], 40.0);
+    let (status, _) = send(&app, Method::POST, &uri, speed(150.0), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 自动模式的加药泵不能手动调速
+    let (status, pump) = post(
+        &app,
+        "/dosing-pumps",
+        json!({
+            "name": "2#加酸泵",
+            "tank_id": tank_id,
+            "max_rate_lph": 20.0,
+            "control": "modbus",
+            "control_device_id": 1,
+            "control_address": 20,
+            "mode": "auto",
+            "ph_device_id": 2,
+            "ph_setpoint": 7.0,
+            "direction": "lower"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", pump);
+    let uri = format!("/dosing-pumps/{}/speed", pump["id"].as_i64().unwrap());
+    let (status, _) = send(&app, Method::POST, &uri, speed(40.0), &admin).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 控制回路和自动模式的加药泵不能驱动同一寄存器
+    let control_loop = |output_address: i32| {
+        json!({
+            "name": "出水 pH",
+            "device_id": 2,
+            "input_metric": "ph",
+            "output_kind": "modbus",
+            "output_device_id": 1,
+            "output_address": output_address,
+            "setpoint": 7.0,
+            "kp": 10.0
+        })
+    };
+    let (status, _) = post(&app, "/control-loops", control_loop(20)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, created) = post(&app, "/control-loops", control_loop(21)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let uri = format!("/dosing-pumps/{}", pump["id"].as_i64().unwrap());
+    let body = json!({ "control_address": 21 });
+    let (status, _) = send(&app, Method::PUT, &uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_dosing_errors() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.tenancy.enabled = true).await;
+    let organization = create_organization(&app, &admin, "CTSW").await;
+    let tenant = issue_key(&app, &admin, &["read", "control"], Some(organization)).await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    let tank = json!({
+        "name": "1#酸罐",
+        "chemical": "硫酸",
+        "capacity_l": 0.0,
+        "level_l": 0.0,
+        "low_level_l": 0.0
+    });
+    let (status, _) = send(&app, Method::POST, "/chemical-tanks", Some(tank), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let pump =
+        json!({ "name": "1#加酸泵", "tank_id": 9999, "max_rate_lph": 20.0, "control": "manual" });
+    let (status, _) = send(&app, Method::POST, "/dosing-pumps", Some(pump), &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let refill = json!({ "volume_l": 10.0 });
+    let (status, _) =
+        send(&app, Method::POST, "/chemical-tanks/9999/refill", Some(refill), &admin).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 加药系统是网关本机设备，只允许平台管理 Key
+    let headers = [("x-api-key", tenant.as_str())];
+    let (status, _) = send(&app, Method::GET, "/chemical-tanks", None, &headers).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, Method::GET, "/dosing-pumps", None, &headers).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, Method::GET, "/dosing-pumps", None, &admin).await;
+    assert_eq!(status, StatusCode::OK);
+}
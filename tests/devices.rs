@@ -0,0 +1,184 @@
+//! 设备、厂站和区域接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{
+    build_test_app, build_test_app_with_state, create_device, create_site, delete, device_body,
+    get, post, put, send,
+};
+use guolu::services::provisioning::{self, ProvisionRequest};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_device_crud() {
+    let app = build_test_app().await;
+
+    let id = create_device(&app, "1# pH 计").await;
+    let (status, body) = get(&app, &format!("/devices/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "1# pH 计");
+    assert_eq!(body["provision_status"], "active");
+
+    let (status, body) = put(&app, &format!("/devices/{}", id), json!({ "name": "进水 pH 计" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "进水 pH 计");
+
+    let (status, body) = get(&app, "/devices").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let (status, _) = delete(&app, &format!("/devices/{}", id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &format!("/devices/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_device_provisioning() {
+    let (app, state, admin) = build_test_app_with_state(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let request = |serial_number: &str| ProvisionRequest {
+        serial_number: serial_number.to_string(),
+        name: None,
+        device_type: None,
+        manufacturer: None,
+        model: None,
+    };
+    let conn = state.db.get_connection();
+
+    // 序列号会拼进响应主题，不能带通配符
+    assert!(provisioning::register_pending(conn, request("SN/#")).await.is_err());
+    let device = provisioning::register_pending(conn, request("SN-0001")).await.unwrap();
+    assert_eq!(device.provision_status, "pending");
+
+    // 审批接口返回密码明文，必须由 admin Key 调用
+    let uri = format!("/devices/{}/approve", device.id);
+    let (status, _) = post(&app, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, approved) = send(&app, Method::POST, &uri, None, &admin).await;
+    assert_eq!(status, StatusCode::OK, "{}", approved);
+    let credentials = &approved["credentials"];
+    let (status, _) = send(&app, Method::POST, &uri, None, &admin).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 下发命令同样要求 admin Key，测试环境未启用 MQTT
+    let uri = format!("/devices/{}/commands", device.id);
+    let (status, _) = post(&app, &uri, json!({ "command": "reboot" })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let body = json!({ "command": "reboot" });
+    let (status, _) = send(&app, Method::POST, &uri, Some(body), &admin).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // broker 认证钩子按摘要校验
+    let auth = |username: &serde_json::Value, password: &serde_json::Value| {
+        json!({ "username": username, "password": password })
+    };
+    let username = &credentials["mqtt_username"];
+    let (status, result) =
+        post(&app, "/mqtt/auth", auth(username, &credentials["mqtt_password"])).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["result"], "allow");
+    let (_, result) = post(&app, "/mqtt/auth", auth(username, &json!("wrong"))).await;
+    assert_eq!(result["result"], "deny");
+    let (_, result) = post(&app, "/mqtt/auth", auth(&json!("gateway"), &json!("x"))).await;
+    assert_eq!(result["result"], "ignore");
+}
+
+#[tokio::test]
+async fn test_device_errors() {
+    let app = build_test_app().await;
+
+    let (status, _) = get(&app, "/devices/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = put(&app, "/devices/9999", json!({ "name": "x" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = delete(&app, "/devices/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 缺少必填字段
+    let (status, _) = post(&app, "/devices", json!({ "name": "x" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 厂站不存在
+    let mut body = device_body("x");
+    body["site_id"] = json!(9999);
+    let (status, body) = post(&app, "/devices", body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("9999"));
+}
+
+#[tokio::test]
+async fn test_site_and_area() {
+    let app = build_test_app().await;
+
+    let (status, site) = post(
+        &app,
+        "/sites",
+        json!({ "name": "城东污水厂", "code": "EAST", "timezone": "Asia/Shanghai" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(site["timezone"], "Asia/Shanghai");
+    let site_id = site["id"].as_i64().unwrap();
+
+    let (status, area) = post(
+        &app,
+        "/areas",
+        json!({ "site_id": site_id, "parent_id": null, "name": "生化池" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let area_id = area["id"].as_i64().unwrap();
+
+    // 只填区域时自动取区域所属厂站
+    let mut body = device_body("曝气池 DO 仪");
+    body["area_id"] = json!(area_id);
+    let (status, device) = post(&app, "/devices", body).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(device["site_id"], site_id);
+
+    let (status, devices) = get(&app, &format!("/sites/{}/devices", site_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(devices.as_array().unwrap().len(), 1);
+
+    // 厂站下仍有区域和设备时不能删除
+    let (status, _) = delete(&app, &format!("/sites/{}", site_id)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_site_errors() {
+    let app = build_test_app().await;
+
+    let (status, _) = post(&app, "/sites", json!({ "name": " ", "code": "X" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) =
+        post(&app, "/sites", json!({ "name": "X", "code": "X", "timezone": "Mars/Olympus" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/sites/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) =
+        post(&app, "/areas", json!({ "site_id": 9999, "parent_id": null, "name": "X" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 区域不属于指定的厂站
+    let east = create_site(&app, "EAST").await;
+    let west = create_site(&app, "WEST").await;
+    let (_, area) =
+        post(&app, "/areas", json!({ "site_id": east, "parent_id": null, "name": "格栅间" })).await;
+    let mut body = device_body("x");
+    body["site_id"] = json!(west);
+    body["area_id"] = area["id"].clone();
+    let (status, _) = post(&app, "/devices", body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 编号重复
+    let (status, _) = post(&app, "/sites", json!({ "name": "东厂", "code": "EAST" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = put(&app, &format!("/sites/{}", west), json!({ "code": "EAST" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = put(&app, &format!("/sites/{}", west), json!({ "code": "WEST" })).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,293 @@
+//! 水泵曲线、罐体参数、校准、标定曲线和维护工单接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{
+    build_test_app, build_test_app_with_admin, create_device, create_organization,
+    create_organization_device, delete, get, issue_key, post, put, send,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_pump_curve() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "1#提升泵").await;
+    let uri = format!("/devices/{}/pump-curve", device_id);
+
+    let (status, _) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let curve = json!({
+        "efficiency_curve": [[0.0, 0.0], [100.0, 70.0], [200.0, 60.0]],
+        "head_curve": [[0.0, 30.0], [200.0, 10.0]]
+    });
+    let headers = [("x-operator", "zhang")];
+    let (status, curve) = send(&app, Method::PUT, &uri, Some(curve), &headers).await;
+    assert_eq!(status, StatusCode::OK, "{}", curve);
+    assert_eq!(curve["degradation_pct"], 10.0);
+    let (status, curve) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(curve["device_id"], device_id);
+
+    // 没有流量、压力和功率的最新值时无法评估
+    let (status, _) = get(&app, &format!("/devices/{}/pump-efficiency", device_id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = delete(&app, &uri).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_pump_curve_errors() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "1#提升泵").await;
+    let uri = format!("/devices/{}/pump-curve", device_id);
+    let curve = json!({ "efficiency_curve": [[0.0, 0.0], [100.0, 70.0]] });
+
+    let (status, _) = put(&app, "/devices/9999/pump-curve", curve.clone()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = put(&app, &uri, json!({ "efficiency_curve": [[100.0, 70.0]] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut degradation = curve;
+    degradation["degradation_pct"] = json!(100.0);
+    let (status, _) = put(&app, &uri, degradation).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, &format!("/devices/{}/pump-efficiency", device_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_tank_geometry() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "1#酸罐液位计").await;
+    let uri = format!("/devices/{}/tank-geometry", device_id);
+    let cylinder =
+        json!({ "shape": "cylinder", "sensor_offset": 3.5, "height": 3.0, "diameter": 2.0 });
+
+    let (status, geometry) = put(&app, &uri, cylinder.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", geometry);
+    assert_eq!(geometry["shape"], "cylinder");
+    let (status, geometry) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(geometry["diameter"], 2.0);
+
+    // 探头不能低于最高液位，锥底罐须给出锥底高度
+    let mut invalid = cylinder.clone();
+    invalid["sensor_offset"] = json!(2.0);
+    let (status, _) = put(&app, &uri, invalid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut cone = cylinder.clone();
+    cone["shape"] = json!("cone");
+    let (status, _) = put(&app, &uri, cone).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = put(&app, "/devices/9999/tank-geometry", cylinder).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = delete(&app, &uri).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_calibration() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "出水 pH 计").await;
+    let calibration = |points: serde_json::Value, calibrated_at: &str| {
+        json!({
+            "device_id": device_id,
+            "metric_type": "ph",
+            "points": points,
+            "calibrated_at": calibrated_at,
+            "technician": "王工",
+            "interval_days": 30
+        })
+    };
+
+    // 单点校准只修正零点
+    let points = json!([{ "reference": 7.0, "measured": 6.5 }]);
+    let (status, first) =
+        post(&app, "/calibrations", calibration(points, "2026-01-01T08:00:00Z")).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", first);
+    assert_eq!(first["gain"], 1.0);
+    assert_eq!(first["offset"], 0.5);
+    let first = first["id"].as_i64().unwrap();
+
+    // 两点校准叠加在上一次的修正之上
+    let points = json!([
+        { "reference": 4.0, "measured": 3.0 },
+        { "reference": 10.0, "measured": 9.0 }
+    ]);
+    let (status, second) =
+        post(&app, "/calibrations", calibration(points, "2026-01-02T08:00:00Z")).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", second);
+    assert_eq!(second["gain"], 1.0);
+    assert_eq!(second["offset"], 1.5);
+    let second = second["id"].as_i64().unwrap();
+
+    let (status, due) = get(&app, "/calibrations/due").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(due[0]["id"], second);
+
+    // 只能从最近一次开始撤销
+    let (status, _) = delete(&app, &format!("/calibrations/{}", first)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = delete(&app, &format!("/calibrations/{}", second)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = delete(&app, &format!("/calibrations/{}", first)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_calibration_errors() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "出水 pH 计").await;
+    let calibration = |device_id: i64, metric_type: &str, points: serde_json::Value| {
+        json!({
+            "device_id": device_id,
+            "metric_type": metric_type,
+            "points": points,
+            "technician": "王工"
+        })
+    };
+    let point = json!([{ "reference": 7.0, "measured": 6.5 }]);
+
+    let (status, _) = post(&app, "/calibrations", calibration(9999, "ph", point.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) =
+        post(&app, "/calibrations", calibration(device_id, "salinity", point.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let three = json!([
+        { "reference": 4.0, "measured": 4.1 },
+        { "reference": 7.0, "measured": 7.1 },
+        { "reference": 10.0, "measured": 10.1 }
+    ]);
+    let (status, _) = post(&app, "/calibrations", calibration(device_id, "ph", three)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/calibrations/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = delete(&app, "/calibrations/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_calibration_curve() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "集水井液位计").await;
+    let curve = |kind: &str, points: serde_json::Value| {
+        json!({ "device_id": device_id, "metric_type": "level", "kind": kind, "points": points })
+    };
+
+    // 4–20 mA 对应 0–10 m
+    let (status, created) =
+        post(&app, "/calibration-curves", curve("piecewise", json!([[4.0, 0.0], [20.0, 10.0]])))
+            .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let id = created["id"].as_i64().unwrap();
+    let (status, preview) =
+        get(&app, &format!("/calibration-curves/{}/preview?raw=12", id)).await;
+    assert_eq!(status, StatusCode::OK, "{}", preview);
+    assert_eq!(preview["value"], 5.0);
+
+    // 同一设备、指标只能有一条启用的曲线
+    let (status, _) =
+        post(&app, "/calibration-curves", curve("polynomial", json!([0.0, 1.0]))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, "/calibration-curves", curve("cubic", json!([0.0, 1.0]))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/calibration-curves/9999/preview?raw=12").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_maintenance() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "1#鼓风机").await;
+
+    let plan = |interval_days: i32| {
+        json!({
+            "device_id": device_id,
+            "name": "更换润滑油",
+            "interval_kind": "calendar",
+            "interval_days": interval_days
+        })
+    };
+    let (status, created) = post(&app, "/maintenance-plans", plan(90)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    assert_eq!(created["enabled"], true);
+    let (status, _) = post(&app, "/maintenance-plans", plan(0)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, order) = post(
+        &app,
+        "/work-orders",
+        json!({ "device_id": device_id, "title": "轴承异响检查" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", order);
+    assert_eq!(order["status"], "open");
+    let uri = format!("/work-orders/{}/close", order["id"].as_i64().unwrap());
+
+    // 关闭人取自 X-Operator，已关闭的工单不能再关闭
+    let headers = [("x-operator", "li")];
+    let body = json!({ "notes": "已更换轴承" });
+    let (status, order) = send(&app, Method::POST, &uri, Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::OK, "{}", order);
+    assert_eq!(order["status"], "closed");
+    assert_eq!(order["closed_by"], "li");
+    let (status, _) = post(&app, &uri, body.clone()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = post(&app, "/work-orders/9999/close", body).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) =
+        post(&app, "/work-orders", json!({ "device_id": 9999, "title": "x" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_equipment_tenancy() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.tenancy.enabled = true).await;
+    let owner = create_organization(&app, &admin, "CTSW").await;
+    let other = create_organization(&app, &admin, "HXSW").await;
+    let device_id = create_organization_device(&app, &admin, "1#提升泵", owner).await;
+    let owner = issue_key(&app, &admin, &["read"], Some(owner)).await;
+    let other = issue_key(&app, &admin, &["read"], Some(other)).await;
+    let uri = format!("/devices/{}/pump-curve", device_id);
+    let curve = json!({ "efficiency_curve": [[0.0, 0.0], [100.0, 70.0]] });
+    let calibration = json!({
+        "device_id": device_id,
+        "metric_type": "ph",
+        "points": [{ "reference": 7.0, "measured": 6.5 }],
+        "technician": "王工"
+    });
+
+    // 其他组织的设备按不存在处理
+    let headers = [("x-api-key", other.as_str())];
+    let (status, _) = send(&app, Method::PUT, &uri, Some(curve.clone()), &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) =
+        send(&app, Method::POST, "/calibrations", Some(calibration.clone()), &headers).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let headers = [("x-api-key", owner.as_str())];
+    let (status, _) = send(&app, Method::PUT, &uri, Some(curve), &headers).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, created) =
+        send(&app, Method::POST, "/calibrations", Some(calibration), &headers).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+
+    // 已有的数据同样只对本组织可见
+    let headers = [("x-api-key", other.as_str())];
+    let (status, _) = send(&app, Method::GET, &uri, None, &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let calibration_uri = format!("/calibrations/{}", created["id"].as_i64().unwrap());
+    let (status, _) = send(&app, Method::GET, &calibration_uri, None, &headers).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, calibrations) = send(&app, Method::GET, "/calibrations", None, &headers).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(calibrations.as_array().unwrap().is_empty());
+}
@@ -0,0 +1,222 @@
+//! 测量值上报和查询接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{
+    build_test_app, build_test_app_with, build_test_app_with_admin, create_device,
+    create_organization, create_organization_device, delete, get, issue_key, post, put, send,
+};
+use guolu::config::compression::{CompressionMethod, CompressionRule};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_measurement_crud() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "进水浊度仪").await;
+
+    let (status, measurement) = post(
+        &app,
+        "/measurements",
+        json!({
+            "metric_type": "turbidity",
+            "timestamp": "2026-06-01T08:00:00Z",
+            "value": 12.5,
+            "device_id": device_id,
+            "unit": null
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", measurement);
+    assert_eq!(measurement["unit"], "NTU");
+    let id = measurement["id"].as_i64().unwrap();
+
+    let (status, measurement) = get(&app, &format!("/measurements/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(measurement["value"], 12.5);
+
+    let correction = json!({ "value": 13.0, "reason": "仪表校准后补正" });
+    let (status, measurement) = put(&app, &format!("/measurements/{}", id), correction).await;
+    assert_eq!(status, StatusCode::OK, "{}", measurement);
+    assert_eq!(measurement["value"], 13.0);
+
+    let (status, list) = get(&app, "/measurements?metric=turbidity").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let (status, _) = delete(&app, &format!("/measurements/{}", id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &format!("/measurements/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_measurement_errors() {
+    let app = build_test_app().await;
+    let body = |metric: &str, value: f64| {
+        json!({
+            "metric_type": metric,
+            "timestamp": "2026-06-01T08:00:00Z",
+            "value": value,
+            "device_id": null,
+            "unit": null
+        })
+    };
+
+    let (status, _) = post(&app, "/measurements", body("salinity", 1.0)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, error) = post(&app, "/measurements", body("ph", 15.0)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("超出有效范围"));
+    let (status, _) = get(&app, "/measurements?metric=salinity").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/measurements/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_ph_value() {
+    let app = build_test_app().await;
+
+    let ph = |value: f64| {
+        json!({
+            "timestamp": "2026-06-01T08:00:00Z",
+            "value": value,
+            "device_id": null,
+            "unit": "pH"
+        })
+    };
+    let (status, value) = post(&app, "/ph-values", ph(7.2)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", value);
+    let id = value["id"].as_i64().unwrap();
+    let (status, value) = get(&app, &format!("/ph-values/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value["value"], 7.2);
+
+    let (status, _) = post(&app, "/ph-values", ph(-1.0)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/ph-values/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_ingest_compression() {
+    let app = build_test_app_with(|settings| {
+        settings.compression.enabled = true;
+        settings.compression.rules = vec![CompressionRule {
+            metric_type: "ph".to_string(),
+            device_id: None,
+            method: CompressionMethod::Deadband,
+            tolerance: 0.1,
+            max_interval_secs: 300,
+        }];
+    })
+    .await;
+    let ph = |timestamp: &str, value: f64| {
+        json!({ "timestamp": timestamp, "value": value, "device_id": null, "unit": "pH" })
+    };
+
+    // 单条上报与批量上报一样经过压缩，死区内的样本不单独入库
+    let (status, value) = post(&app, "/ph-values", ph("2026-06-01T08:00:00Z", 7.0)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", value);
+    let (status, body) = post(&app, "/ph-values", ph("2026-06-01T08:01:00Z", 7.05)).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    assert_eq!(body["buffered"], false);
+    let mut body = ph("2026-06-01T08:02:00Z", 7.5);
+    body["metric_type"] = json!("ph");
+    let (status, measurement) = post(&app, "/measurements", body).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", measurement);
+    assert_eq!(measurement["suppressed_count"], 1);
+}
+
+#[tokio::test]
+async fn test_ingest_requires_api_key() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.api_keys.require_for_ingest = true).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let body = json!({
+        "metric_type": "flow",
+        "timestamp": "2026-06-01T08:00:00Z",
+        "value": 120.0,
+        "device_id": null,
+        "unit": null
+    });
+
+    let (status, _) = post(&app, "/measurements", body.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 只有 read 权限的 Key 不能上报
+    let request = json!({ "name": "看板", "scopes": ["read"] });
+    let (_, issued) = send(&app, Method::POST, "/api-keys", Some(request), &admin).await;
+    let key = issued["key"].as_str().unwrap();
+    let headers = [("x-api-key", key)];
+    let (status, _) =
+        send(&app, Method::POST, "/measurements", Some(body.clone()), &headers).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let request = json!({ "name": "网关", "scopes": ["ingest"] });
+    let (_, issued) = send(&app, Method::POST, "/api-keys", Some(request), &admin).await;
+    let key = issued["key"].as_str().unwrap();
+    let headers = [("x-api-key", key)];
+    let (status, _) = send(&app, Method::POST, "/measurements", Some(body), &headers).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_read_only_buffers_ingest() {
+    let (app, admin) = build_test_app_with_admin(|_| {}).await;
+    let admin = [("x-api-key", admin.as_str())];
+
+    let body = json!({ "enabled": true, "reason": "数据库维护" });
+    let (status, mode) = send(&app, Method::PUT, "/system/read-only", Some(body), &admin).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mode["active"], true);
+
+    // 修改类请求被拒绝，上报数据暂存
+    let (status, _) = post(&app, "/sites", json!({ "name": "X", "code": "X" })).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let (status, _) = post(
+        &app,
+        "/measurements",
+        json!({
+            "metric_type": "ph",
+            "timestamp": "2026-06-01T08:00:00Z",
+            "value": 7.0,
+            "device_id": null,
+            "unit": null
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (status, queues) = get(&app, "/system/queues").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(queues["ingest_buffer"], 1);
+}
+
+#[tokio::test]
+async fn test_read_only_buffer_checks_tenant() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.tenancy.enabled = true).await;
+    let owner = create_organization(&app, &admin, "CTSW").await;
+    let other = create_organization(&app, &admin, "HXSW").await;
+    let device_id = create_organization_device(&app, &admin, "出水 pH 计", owner).await;
+    let other = issue_key(&app, &admin, &["ingest"], Some(other)).await;
+    let admin = [("x-api-key", admin.as_str())];
+    let other = [("x-api-key", other.as_str())];
+
+    let body = json!({ "enabled": true });
+    let (status, _) = send(&app, Method::PUT, "/system/read-only", Some(body), &admin).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 其他组织的设备不能借只读暂存写入
+    let body = json!({ "timestamp": "2026-06-01T08:00:00Z", "value": 7.0, "device_id": device_id });
+    let (status, _) = send(&app, Method::POST, "/ph-values", Some(body.clone()), &other).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::POST, "/ph-values", Some(body), &admin).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (status, queues) = get(&app, "/system/queues").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(queues["ingest_buffer"], 1);
+}
@@ -0,0 +1,292 @@
+//! 报表、排放许可、能耗、趋势和累计流量接口
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::NaiveDate;
+use common::{
+    build_test_app, build_test_app_with, build_test_app_with_admin, build_test_app_with_state,
+    create_device, create_organization, create_organization_device, get, issue_key, post, put,
+    send,
+};
+use guolu::services::{flow_total, report};
+use guolu::utils::timezone;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_report() {
+    let dir = std::env::temp_dir().join(format!("guolu-reports-{}", std::process::id()));
+    let directory = dir.to_string_lossy().into_owned();
+    let app = build_test_app_with(|settings| {
+        settings.report.formats = vec!["xlsx".to_string()];
+        settings.report.storage.directory = directory;
+    })
+    .await;
+
+    let (status, reports) =
+        post(&app, "/reports", json!({ "kind": "daily", "date": "2026-06-01" })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", reports);
+    assert_eq!(reports[0]["format"], "xlsx");
+    let download_url = reports[0]["download_url"].as_str().unwrap().to_string();
+
+    let (status, _) = get(&app, &download_url).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, reports) = get(&app, "/reports?kind=daily").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reports.as_array().unwrap().len(), 1);
+
+    let (status, _) =
+        post(&app, "/reports", json!({ "kind": "monthly", "date": "2026-06-01" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/reports/9999/download").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_report_excludes_flagged_values() {
+    let (app, state, _) = build_test_app_with_state(|_| {}).await;
+    let device_id = create_device(&app, "总排口 pH 计").await;
+    let mut ids = Vec::new();
+    for (timestamp, value) in [("2026-06-01T08:00:00Z", 7.2), ("2026-06-01T09:00:00Z", 11.0)] {
+        let (status, measurement) = post(
+            &app,
+            "/measurements",
+            json!({
+                "metric_type": "ph",
+                "timestamp": timestamp,
+                "value": value,
+                "device_id": device_id,
+                "unit": null
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", measurement);
+        ids.push(measurement["id"].as_i64().unwrap());
+    }
+    // 人工标记为坏数据的超标值不计入合规报表
+    let correction = json!({ "quality": "bad", "reason": "探头清洗" });
+    let (status, _) = put(&app, &format!("/measurements/{}", ids[1]), correction).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let conn = state.db.get_connection();
+    let tz = timezone::default_zone(&state.settings.timezone);
+    let start = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+    let end = NaiveDate::from_ymd_opt(2026, 6, 2).unwrap();
+    let data =
+        report::collect(conn, &state.settings.report, tz, "daily", start, end).await.unwrap();
+    assert_eq!(data.ph.len(), 1);
+    assert_eq!(data.ph[0].samples, 1);
+    assert_eq!(data.ph[0].excursions, 0);
+    assert_eq!(data.ph[0].max, 7.2);
+}
+
+#[tokio::test]
+async fn test_permit() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "总排口 COD 仪").await;
+    let permit = |limit_type: &str, averaging_period: &str| {
+        json!({
+            "name": "总排口 COD",
+            "device_id": device_id,
+            "metric_type": "cod",
+            "limit_value": 50.0,
+            "limit_type": limit_type,
+            "averaging_period": averaging_period
+        })
+    };
+
+    let (status, created) = post(&app, "/discharge-permits", permit("max", "daily")).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let (status, _) = get(&app, &format!("/discharge-permits/{}", created["id"])).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, summary) = get(&app, "/compliance/summary?month=2026-06").await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["month"], "2026-06");
+    assert_eq!(summary["compliant"], true);
+    assert_eq!(summary["permits"].as_array().unwrap().len(), 1);
+    let (status, exceedances) = get(&app, "/permit-exceedances").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(exceedances.as_array().unwrap().is_empty());
+
+    let (status, _) = post(&app, "/discharge-permits", permit("avg", "daily")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, "/discharge-permits", permit("max", "yearly")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/compliance/summary?month=2026-13").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/discharge-permits/9999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_energy() {
+    let app = build_test_app().await;
+
+    let (status, devices) = get(&app, "/energy/devices?start=2026-06-01&end=2026-06-30").await;
+    assert_eq!(status, StatusCode::OK, "{}", devices);
+    assert!(devices.as_array().unwrap().is_empty());
+    let (status, kpis) = get(&app, "/energy/kpis?start=2026-06-01&end=2026-06-30").await;
+    assert_eq!(status, StatusCode::OK, "{}", kpis);
+    assert_eq!(kpis["from"], "2026-06-01");
+    assert_eq!(kpis["total_kwh"], 0.0);
+    let (status, _) = get(&app, "/energy/areas?start=2026-06-01&end=2026-06-30").await;
+    assert_eq!(status, StatusCode::OK);
+
+    for uri in ["/energy/devices", "/energy/areas", "/energy/kpis"] {
+        let (status, _) = get(&app, &format!("{}?start=2026-06-30&end=2026-06-01", uri)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_trend() {
+    let app = build_test_app().await;
+    let device_id = create_device(&app, "出水 pH 计").await;
+    for (timestamp, value) in [("2026-06-01T08:00:00Z", 7.2), ("2026-06-01T09:00:00Z", 7.6)] {
+        let (status, _) = post(
+            &app,
+            "/measurements",
+            json!({
+                "metric_type": "ph",
+                "timestamp": timestamp,
+                "value": value,
+                "device_id": device_id,
+                "unit": null
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    // 一天以内返回原始数据
+    let uri = format!(
+        "/trend?metric=ph&device_id={}&start=2026-06-01T00:00:00Z&end=2026-06-02T00:00:00Z",
+        device_id
+    );
+    let (status, trend) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK, "{}", trend);
+    assert_eq!(trend["resolution"], "raw");
+    assert!(trend["bucket_secs"].is_null());
+    let points = trend["points"].as_array().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1]["avg"], 7.6);
+    assert_eq!(points[1]["count"], 1);
+
+    let (status, _) = get(&app, "/trend?metric=salinity&start=2026-06-01T00:00:00Z").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(
+        &app,
+        "/trend?metric=ph&start=2026-06-02T00:00:00Z&end=2026-06-01T00:00:00Z",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_flow_total() {
+    let (app, state, _) = build_test_app_with_state(|_| {}).await;
+    let device_id = create_device(&app, "总排口流量计").await;
+    let readings = [
+        ("2026-06-01T23:00:00Z", 100.0),
+        ("2026-06-02T08:00:00Z", 110.0),
+        ("2026-06-02T20:00:00Z", 125.5),
+    ];
+    for (timestamp, value) in readings {
+        let (status, _) = post(
+            &app,
+            "/measurements",
+            json!({
+                "metric_type": "flow_total",
+                "timestamp": timestamp,
+                "value": value,
+                "device_id": device_id,
+                "unit": null
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let day = NaiveDate::from_ymd_opt(2026, 6, 2).unwrap();
+    let conn = state.db.get_connection();
+    let tz = timezone::default_zone(&state.settings.timezone);
+    flow_total::materialize(conn, &state.settings.flow_total, tz, day).await.unwrap();
+
+    // 日报的排放量取自累计流量
+    let end = day + chrono::Duration::days(1);
+    let data = report::collect(conn, &state.settings.report, tz, "daily", day, end).await.unwrap();
+    assert_eq!(data.discharge.len(), 1);
+    assert_eq!(data.discharge[0].volume_m3, 25.5);
+    assert_eq!(data.total_volume_m3, 25.5);
+
+    // 以前一天最后的读数为起点
+    let uri = format!("/devices/{}/flow-total?start=2026-06-01&end=2026-06-30", device_id);
+    let (status, totals) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK, "{}", totals);
+    assert_eq!(totals["volume"], 25.5);
+    assert_eq!(totals["resets"], 0);
+    assert_eq!(totals["totals"].as_array().unwrap().len(), 1);
+    let (status, totals) = get(&app, &format!("{}&period=month", uri)).await;
+    assert_eq!(status, StatusCode::OK, "{}", totals);
+    assert_eq!(totals["volume"], 25.5);
+
+    let (status, _) = get(&app, &format!("{}&period=week", uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(
+        &app,
+        &format!("/devices/{}/flow-total?start=2026-06-30&end=2026-06-01", device_id),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/devices/9999/flow-total").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_reporting_tenancy() {
+    let (app, admin) =
+        build_test_app_with_admin(|settings| settings.tenancy.enabled = true).await;
+    let owner = create_organization(&app, &admin, "CTSW").await;
+    let other = create_organization(&app, &admin, "HXSW").await;
+    let device_id = create_organization_device(&app, &admin, "总排口流量计", owner).await;
+    let owner = issue_key(&app, &admin, &["read"], Some(owner)).await;
+    let other = issue_key(&app, &admin, &["read"], Some(other)).await;
+    let owner = [("x-api-key", owner.as_str())];
+    let other = [("x-api-key", other.as_str())];
+
+    // 全厂汇总只允许平台管理 Key
+    for uri in [
+        "/reports",
+        "/discharge-permits",
+        "/permit-exceedances",
+        "/compliance/summary",
+        "/energy/areas",
+        "/energy/kpis",
+    ] {
+        let (status, _) = send(&app, Method::GET, uri, None, &other).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+    }
+    let request = json!({ "kind": "daily", "date": "2026-06-01" });
+    let (status, _) = send(&app, Method::POST, "/reports", Some(request), &other).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, devices) = send(&app, Method::GET, "/energy/devices", None, &other).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(devices.as_array().unwrap().is_empty());
+
+    // 其他组织设备的趋势和累计流量按不存在处理
+    let trend = format!("/trend?metric=ph&device_id={}&start=2026-06-01T00:00:00Z", device_id);
+    let flow_total = format!("/devices/{}/flow-total", device_id);
+    for uri in [&trend, &flow_total] {
+        let (status, _) = send(&app, Method::GET, uri, None, &other).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        let (status, _) = send(&app, Method::GET, uri, None, &owner).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+    // 租户不指定设备时只能看到本组织设备的趋势
+    let (status, _) =
+        send(&app, Method::GET, "/trend?metric=ph&start=2026-06-01T00:00:00Z", None, &owner)
+            .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}